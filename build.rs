@@ -0,0 +1,46 @@
+use std::path::Path;
+use std::process::Command;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Passes the git hash and the build time to `build_info`. Builds outside of a git
+/// checkout (e.g. from a source tarball) report the hash as unknown.
+fn main() {
+    let git_hash = Command::new("git")
+        .args(["rev-parse", "--short=10", "HEAD"])
+        .output()
+        .ok()
+        .filter(|output| output.status.success())
+        .and_then(|output| String::from_utf8(output.stdout).ok())
+        .map(|hash| hash.trim().to_string())
+        .unwrap_or_else(|| String::from("unknown"));
+    println!("cargo:rustc-env=SV_GIT_HASH={git_hash}");
+
+    // Reproducible builds pin the time
+    let build_timestamp = std::env::var("SOURCE_DATE_EPOCH").unwrap_or_else(|_| {
+        SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs()
+            .to_string()
+    });
+    println!("cargo:rustc-env=SV_BUILD_TIMESTAMP={build_timestamp}");
+
+    println!("cargo:rerun-if-changed=.git/HEAD");
+    // Commits move the branch HEAD points to, HEAD itself only changes on checkout.
+    // The branch may be packed, and a path that doesn't exist would rerun every build.
+    if let Ok(head) = std::fs::read_to_string(".git/HEAD")
+        && let Some(branch) = head.trim().strip_prefix("ref: ")
+    {
+        for path in [format!(".git/{branch}"), String::from(".git/packed-refs")] {
+            if Path::new(&path).exists() {
+                println!("cargo:rerun-if-changed={path}");
+            }
+        }
+    }
+    println!("cargo:rerun-if-changed=.git/index");
+    println!("cargo:rerun-if-env-changed=SOURCE_DATE_EPOCH");
+
+    // Needs protoc
+    #[cfg(feature = "grpc")]
+    tonic_build::compile_protos("proto/sensor_vision.proto").expect("Failed to compile the protos");
+}
@@ -26,7 +26,7 @@ async fn main() -> Result<()> {
     client_actor.send(LoadSensors).await??;
     client_actor.send(PingTest).await??;
     sleep(Duration::from_secs(1)).await;
-    let dump = client_actor.send(DumpSensors).await??;
+    let dump = client_actor.send(DumpSensors::default()).await??;
     println!("{}", dump);
 
     Ok(())
@@ -0,0 +1,30 @@
+use chrono::{DateTime, Utc};
+
+use std::sync::LazyLock;
+
+pub const NAME: &str = "SensorVision";
+pub const VERSION: &str = env!("CARGO_PKG_VERSION");
+/// Short hash of the commit built, `unknown` outside of a git checkout
+pub const GIT_HASH: &str = env!("SV_GIT_HASH");
+
+const BUILD_TIMESTAMP: &str = env!("SV_BUILD_TIMESTAMP");
+
+pub fn build_date() -> Option<DateTime<Utc>> {
+    DateTime::from_timestamp(BUILD_TIMESTAMP.parse().ok()?, 0)
+}
+
+/// `SensorVision v0.1.0`
+pub fn title() -> String {
+    format!("{NAME} v{VERSION}")
+}
+
+/// `0.1.0 (1a2b3c4d5e, built 2026-10-17)`, for `--version` and the diagnostics
+pub fn long_version() -> &'static str {
+    static LONG_VERSION: LazyLock<String> = LazyLock::new(|| {
+        let built = build_date()
+            .map(|date| format!(", built {}", date.format("%Y-%m-%d")))
+            .unwrap_or_default();
+        format!("{VERSION} ({GIT_HASH}{built})")
+    });
+    &LONG_VERSION
+}
@@ -0,0 +1,488 @@
+use actix::{Actor, Addr, AsyncContext, Context, Handler, MailboxError, Message, WrapFuture};
+
+use actix_web::body::MessageBody;
+use actix_web::dev::{ServerHandle, ServiceRequest, ServiceResponse};
+use actix_web::http::header::{self, HeaderValue};
+use actix_web::http::{KeepAlive, StatusCode};
+use actix_web::middleware::{from_fn, Next};
+use actix_web::web::{self, Bytes, Data, Json, Path, Query};
+use actix_web::{App, HttpResponse, HttpServer, ResponseError};
+
+use eyre::{eyre, Result, WrapErr};
+
+use serde::Deserialize;
+use serde_json::{json, Value};
+
+use tokio::sync::mpsc;
+
+use uuid::Uuid;
+
+use std::convert::Infallible;
+use std::net::{SocketAddr, TcpListener};
+use std::time::Duration;
+
+use crate::client::client::SensorVisionClient;
+use crate::client::client_queries::{
+    CreateMetrics, CreateSensor, DeleteMetric, DeleteSensor, DumpSensors, PushValue, SubmitSensor,
+    UpdateMetric, UpdateSensor,
+};
+use crate::client::mqtt::subscriptions;
+use crate::client::state::queries::GetStateSnapshot;
+use crate::client::state::{GetAlertHistory, SensorStateEvent, SubscribeToStateEvents};
+use crate::config::ApiConfig;
+use crate::model::sensor::{Metric, Sensor, SensorState};
+use crate::model::{MetricId, SensorId, Timestamp};
+
+// Requests carry a sensor or a batch of metrics at most
+const REQUEST_SIZE_LIMIT: usize = 65536;
+
+/// Livedata events waiting for a slow SSE client before it misses some
+const LIVEDATA_STREAM_CAPACITY: usize = 256;
+
+/// Clients have this long to send the request head
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Idle connections are closed after this long
+const KEEP_ALIVE: Duration = Duration::from_secs(30);
+
+/// Clients have this long to acknowledge the connection being closed
+const DISCONNECT_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Serves the client over HTTP for tooling which would rather not speak MQTT:
+///
+/// - `GET /sensors` the inventory as `dump` writes it
+/// - `POST /sensors` `{"name": ...}` creates a sensor
+/// - `PUT /sensors/ID` `{"name": ..., "state": 0|1}` renames a sensor and optionally enables or disables it
+/// - `DELETE /sensors/ID`
+/// - `POST /sensors/ID/metrics` creates the metrics of a JSON array, in the API format
+/// - `PUT /sensors/ID/metrics/ID` `{"name": ..., "valueAnnotation": ...}` changes a metric, both optional
+/// - `DELETE /sensors/ID/metrics/ID`
+/// - `POST /sensors/ID/metrics/ID/value` `{"value": ..., "timestamp": MILLIS}` pushes a value,
+///   parsed according to the metric type, the timestamp is optional
+/// - `GET /livedata` streams the livedata as server-sent events
+/// - `GET /alerts?since=MILLIS` the alerts in force and the ones resolved since, only the former
+///   without `since`
+///
+/// Changes are requested from the agent, which applies them asynchronously, so they're
+/// answered with `202 Accepted` once sent. Requests have to carry the configured token as
+/// `Authorization: Bearer TOKEN`, which is only optional on loopback addresses.
+pub struct ApiActor {
+    client: Addr<SensorVisionClient>,
+    token: Option<String>,
+    listener: Option<TcpListener>,
+    server: Option<ServerHandle>,
+
+    livedata_subscribers: Vec<mpsc::Sender<String>>,
+}
+
+#[derive(Message)]
+#[rtype(result = "()")]
+struct SubscribeToLivedata(mpsc::Sender<String>);
+
+/// Shared by the workers of the server
+struct ApiContext {
+    client: Addr<SensorVisionClient>,
+    api: Addr<ApiActor>,
+    token: Option<String>,
+}
+
+impl ApiActor {
+    /// Binds right away so that a bad address is reported before anything else starts,
+    /// addresses other than loopback ones are refused without a token
+    pub fn bind(client: Addr<SensorVisionClient>, config: &ApiConfig) -> Result<Self> {
+        let address = config.listen.as_deref().ok_or_else(|| eyre!("The API has no address to listen on"))?;
+        let listener = TcpListener::bind(address).wrap_err_with(|| format!("Failed to listen on {address}"))?;
+        let local_address = listener.local_addr()?;
+        if config.token.is_none() && !local_address.ip().is_loopback() {
+            return Err(eyre!(
+                "The API only listens on {address} with a token, set one in the [api] config section"
+            ));
+        }
+        log::info!("Serving the API at http://{local_address}");
+        Ok(Self {
+            client,
+            token: config.token.clone(),
+            listener: Some(listener),
+            server: None,
+            livedata_subscribers: Vec::new(),
+        })
+    }
+
+    /// Address actually bound, tells the port when binding to port 0
+    pub fn local_addr(&self) -> Option<SocketAddr> {
+        self.listener.as_ref()?.local_addr().ok()
+    }
+}
+
+impl Actor for ApiActor {
+    type Context = Context<Self>;
+
+    fn started(&mut self, ctx: &mut Self::Context) {
+        let client = self.client.clone();
+        let weak_this = ctx.address().downgrade().recipient();
+        ctx.spawn(
+            async move {
+                let _ = client.send(SubscribeToStateEvents(weak_this)).await;
+            }
+            .into_actor(self),
+        );
+
+        let Some(listener) = self.listener.take() else {
+            return;
+        };
+        let context = Data::new(ApiContext {
+            client: self.client.clone(),
+            api: ctx.address(),
+            token: self.token.clone(),
+        });
+        let server = HttpServer::new(move || {
+            App::new()
+                .app_data(context.clone())
+                .app_data(
+                    web::JsonConfig::default()
+                        .limit(REQUEST_SIZE_LIMIT)
+                        // curl -d sends a form content type
+                        .content_type_required(false)
+                        .error_handler(|err, _| ApiError::BadRequest(err.to_string()).into()),
+                )
+                .app_data(web::PathConfig::default().error_handler(|err, _| ApiError::BadRequest(err.to_string()).into()))
+                .app_data(web::QueryConfig::default().error_handler(|err, _| ApiError::BadRequest(err.to_string()).into()))
+                .wrap(from_fn(authorize))
+                .route("/sensors", web::get().to(list_sensors))
+                .route("/sensors", web::post().to(create_sensor))
+                .route("/sensors/{sensor_id}", web::put().to(update_sensor))
+                .route("/sensors/{sensor_id}", web::delete().to(delete_sensor))
+                .route("/sensors/{sensor_id}/metrics", web::post().to(create_metrics))
+                .route("/sensors/{sensor_id}/metrics/{metric_id}", web::put().to(update_metric))
+                .route("/sensors/{sensor_id}/metrics/{metric_id}", web::delete().to(delete_metric))
+                .route("/sensors/{sensor_id}/metrics/{metric_id}/value", web::post().to(push_value))
+                .route("/livedata", web::get().to(stream_livedata))
+                .route("/subscriptions", web::get().to(list_subscriptions))
+                .route("/alerts", web::get().to(list_alerts))
+                .default_service(web::to(not_found))
+        })
+        .client_request_timeout(REQUEST_TIMEOUT)
+        .client_disconnect_timeout(DISCONNECT_TIMEOUT)
+        .keep_alive(KeepAlive::Timeout(KEEP_ALIVE))
+        .workers(1)
+        .disable_signals()
+        .listen(listener);
+
+        let server = match server {
+            Ok(server) => server.run(),
+            Err(err) => {
+                log::error!("API failed to listen: {err}");
+                return;
+            }
+        };
+        self.server = Some(server.handle());
+        actix::spawn(async move {
+            if let Err(err) = server.await {
+                log::error!("API server failed: {err}");
+            }
+        });
+    }
+
+    fn stopped(&mut self, _: &mut Self::Context) {
+        if let Some(server) = self.server.take() {
+            actix::spawn(server.stop(true));
+        }
+    }
+}
+
+impl Handler<SubscribeToLivedata> for ApiActor {
+    type Result = ();
+
+    fn handle(&mut self, SubscribeToLivedata(sender): SubscribeToLivedata, _: &mut Self::Context) {
+        self.livedata_subscribers.push(sender);
+    }
+}
+
+impl Handler<SensorStateEvent> for ApiActor {
+    type Result = ();
+
+    fn handle(&mut self, event: SensorStateEvent, _: &mut Self::Context) -> Self::Result {
+        let SensorStateEvent::Livedata {
+            sensor_id,
+            metric_id,
+            value,
+            timestamp,
+            ..
+        } = event
+        else {
+            return;
+        };
+        let event = json!({
+            "sensorId": sensor_id,
+            "metricId": metric_id,
+            "value": value,
+            "timestamp": timestamp,
+        })
+        .to_string();
+        self.livedata_subscribers.retain(|subscriber| match subscriber.try_send(event.clone()) {
+            Ok(()) => true,
+            Err(mpsc::error::TrySendError::Full(_)) => {
+                log::debug!("Livedata stream client falls behind, dropping a value");
+                true
+            }
+            Err(mpsc::error::TrySendError::Closed(_)) => false,
+        });
+    }
+}
+
+/// Answered as `{"error": ...}`
+#[derive(Debug)]
+enum ApiError {
+    BadRequest(String),
+    Unauthorized,
+    NotFound(String),
+    Internal(eyre::Report),
+}
+
+impl std::fmt::Display for ApiError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ApiError::BadRequest(message) | ApiError::NotFound(message) => f.write_str(message),
+            ApiError::Unauthorized => f.write_str("Missing or wrong token"),
+            ApiError::Internal(err) => write!(f, "{err:#}"),
+        }
+    }
+}
+
+impl ResponseError for ApiError {
+    fn status_code(&self) -> StatusCode {
+        match self {
+            ApiError::BadRequest(_) => StatusCode::BAD_REQUEST,
+            ApiError::Unauthorized => StatusCode::UNAUTHORIZED,
+            ApiError::NotFound(_) => StatusCode::NOT_FOUND,
+            ApiError::Internal(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+
+    fn error_response(&self) -> HttpResponse {
+        let mut response = HttpResponse::build(self.status_code());
+        if let ApiError::Unauthorized = self {
+            response.insert_header((header::WWW_AUTHENTICATE, "Bearer"));
+        }
+        response.json(json!({ "error": self.to_string() }))
+    }
+}
+
+impl From<eyre::Report> for ApiError {
+    fn from(err: eyre::Report) -> Self {
+        ApiError::Internal(err)
+    }
+}
+
+impl From<MailboxError> for ApiError {
+    fn from(err: MailboxError) -> Self {
+        ApiError::Internal(err.into())
+    }
+}
+
+type ApiResult = std::result::Result<HttpResponse, ApiError>;
+
+async fn authorize(
+    request: ServiceRequest,
+    next: Next<impl MessageBody + 'static>,
+) -> std::result::Result<ServiceResponse<impl MessageBody>, actix_web::Error> {
+    let context = request.app_data::<Data<ApiContext>>().expect("The API context is always set");
+    if let Some(token) = &context.token {
+        let presented = request
+            .headers()
+            .get(header::AUTHORIZATION)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.strip_prefix("Bearer "));
+        if !presented.is_some_and(|presented| tokens_match(presented, token)) {
+            return Ok(request.error_response(ApiError::Unauthorized).map_into_right_body());
+        }
+    }
+    log::debug!("API request {} {}", request.method(), request.path());
+    Ok(next.call(request).await?.map_into_left_body())
+}
+
+/// Takes as long whichever character differs, not to tell how much of the token was right
+fn tokens_match(presented: &str, token: &str) -> bool {
+    presented.len() == token.len()
+        && presented
+            .bytes()
+            .zip(token.bytes())
+            .fold(0, |difference, (a, b)| difference | (a ^ b))
+            == 0
+}
+
+#[derive(Deserialize)]
+struct SensorBody {
+    name: String,
+    #[serde(default)]
+    state: Option<SensorState>,
+}
+
+#[derive(Deserialize)]
+struct MetricBody {
+    name: Option<String>,
+    #[serde(rename = "valueAnnotation")]
+    value_annotation: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct ValueBody {
+    value: Value,
+    timestamp: Option<Timestamp>,
+}
+
+fn parse_id<Id: From<Uuid>>(id: &str) -> std::result::Result<Id, ApiError> {
+    Uuid::parse_str(id)
+        .map(Id::from)
+        .map_err(|_| ApiError::BadRequest(String::from("Malformed sensor or metric id")))
+}
+
+fn accepted() -> ApiResult {
+    Ok(HttpResponse::Accepted().json(json!({})))
+}
+
+async fn list_sensors(context: Data<ApiContext>) -> ApiResult {
+    let dump = context.client.send(DumpSensors::default()).await??;
+    Ok(HttpResponse::Ok().content_type("application/json").body(dump))
+}
+
+async fn create_sensor(context: Data<ApiContext>, body: Json<SensorBody>) -> ApiResult {
+    let SensorBody { name, state } = body.into_inner();
+    match state {
+        // Only the created sensor can be disabled, so this waits for its id
+        Some(state) => {
+            let sensor = Sensor::builder()
+                .name(name)
+                .state(state)
+                .build()
+                .map_err(|err| ApiError::BadRequest(err.to_string()))?;
+            context.client.send(SubmitSensor(sensor)).await??;
+        }
+        None => context.client.send(CreateSensor { name }).await??,
+    }
+    accepted()
+}
+
+async fn update_sensor(context: Data<ApiContext>, path: Path<String>, body: Json<SensorBody>) -> ApiResult {
+    let sensor_id = parse_id::<SensorId>(&path)?;
+    let SensorBody { name, state } = body.into_inner();
+    context
+        .client
+        .send(UpdateSensor {
+            sensor_id,
+            name,
+            state,
+        })
+        .await??;
+    accepted()
+}
+
+async fn delete_sensor(context: Data<ApiContext>, path: Path<String>) -> ApiResult {
+    let sensor_id = parse_id::<SensorId>(&path)?;
+    context.client.send(DeleteSensor { sensor_id }).await??;
+    accepted()
+}
+
+async fn create_metrics(context: Data<ApiContext>, path: Path<String>, body: Json<Vec<Metric>>) -> ApiResult {
+    let sensor_id = parse_id::<SensorId>(&path)?;
+    let metrics = body.into_inner();
+    context.client.send(CreateMetrics { sensor_id, metrics }).await??;
+    accepted()
+}
+
+async fn update_metric(
+    context: Data<ApiContext>,
+    path: Path<(String, String)>,
+    body: Json<MetricBody>,
+) -> ApiResult {
+    let sensor_id = parse_id::<SensorId>(&path.0)?;
+    let metric_id = parse_id::<MetricId>(&path.1)?;
+    let MetricBody {
+        name,
+        value_annotation,
+    } = body.into_inner();
+    context
+        .client
+        .send(UpdateMetric {
+            sensor_id,
+            metric_id,
+            name,
+            value_annotation,
+        })
+        .await??;
+    accepted()
+}
+
+async fn delete_metric(context: Data<ApiContext>, path: Path<(String, String)>) -> ApiResult {
+    let sensor_id = parse_id::<SensorId>(&path.0)?;
+    let metric_id = parse_id::<MetricId>(&path.1)?;
+    context
+        .client
+        .send(DeleteMetric {
+            sensor_id,
+            metric_id,
+        })
+        .await??;
+    accepted()
+}
+
+async fn push_value(context: Data<ApiContext>, path: Path<(String, String)>, body: Json<ValueBody>) -> ApiResult {
+    let sensor_id = parse_id::<SensorId>(&path.0)?;
+    let metric_id = parse_id::<MetricId>(&path.1)?;
+    let ValueBody { value, timestamp } = body.into_inner();
+    let sensors = context.client.send(GetStateSnapshot).await??;
+    let metric = sensors
+        .get(&sensor_id)
+        .and_then(|sensor| sensor.metrics.get(&metric_id))
+        .ok_or_else(|| ApiError::NotFound(String::from("No such metric")))?;
+    // Typed in values and JSON scalars are parsed alike
+    let value = match &value {
+        Value::String(text) => metric.parse_value(text),
+        value => metric.parse_value(&value.to_string()),
+    }
+    .map_err(|err| ApiError::BadRequest(err.to_string()))?;
+    context
+        .client
+        .send(PushValue {
+            sensor_id,
+            metric_id,
+            value,
+            timestamp,
+        })
+        .await??;
+    accepted()
+}
+
+async fn stream_livedata(context: Data<ApiContext>) -> ApiResult {
+    let (sender, receiver) = mpsc::channel(LIVEDATA_STREAM_CAPACITY);
+    context.api.send(SubscribeToLivedata(sender)).await?;
+    // Ends once the client hangs up, the subscription is dropped with the receiver
+    let events = futures::stream::unfold(receiver, |mut receiver| async move {
+        let event = receiver.recv().await?;
+        Some((Ok::<_, Infallible>(Bytes::from(format!("data: {event}\n\n"))), receiver))
+    });
+    Ok(HttpResponse::Ok()
+        .content_type("text/event-stream")
+        .insert_header((header::CACHE_CONTROL, HeaderValue::from_static("no-cache")))
+        .streaming(events))
+}
+
+#[derive(Deserialize)]
+struct AlertsQuery {
+    since: Option<Timestamp>,
+}
+
+async fn list_alerts(context: Data<ApiContext>, query: Query<AlertsQuery>) -> ApiResult {
+    let since = query.since.unwrap_or_else(Timestamp::now);
+    let alerts = context.client.send(GetAlertHistory { since }).await??;
+    Ok(HttpResponse::Ok().json(alerts))
+}
+
+async fn not_found() -> ApiResult {
+    Err(ApiError::NotFound(String::from("Not found")))
+}
+
+async fn list_subscriptions() -> ApiResult {
+    let subscriptions = serde_json::to_string(&subscriptions::list(None)).map_err(eyre::Report::from)?;
+    Ok(HttpResponse::Ok().content_type("application/json").body(subscriptions))
+}
@@ -1,41 +1,119 @@
 use actix::{
-    Actor, Addr, AsyncContext, Context, Handler, WrapFuture,
+    Actor, ActorFutureExt, Addr, AsyncContext, Context, Handler, Message, WrapFuture,
 };
 
-use eyre::Result;
+use eyre::{eyre, Result};
 
 use futures::FutureExt;
 
 use serde::{Deserialize, Serialize};
 
+use std::time::{Duration, Instant, SystemTime};
+
+use crate::client::client_queries::LoadSensors;
+use crate::client::describe_scheduler::DescribeScheduler;
+use crate::client::reconciler::Reconciler;
 use crate::client::mqtt::{
-    MqttActor, MqttListenerService, MqttMessage, MqttRequest, OneWayMessage, SubscribeToListener,
+    MqttActor, MqttCredentials, MqttEvent, MqttListenerService, MqttMessage, MqttRequest,
+    OneWayMessage, Reconnect, SubscribeToConnectionEvents, SubscribeToListener,
 };
 use crate::client::state::queries::{
-    GetMetricIdByName, GetMetricIds, GetSensorIdByName, GetStateSnapshot,
+    GetLastLivedata, GetMetricIdByName, GetMetricIds, GetSensorIdByName, GetSensorIdsByName, GetStateSnapshot,
+    GetStateVersion, GetUnwatchedLivedata, FindMetrics, GetVersionedStateSnapshot, ResolveMetric, ResolveSensor,
 };
 use crate::client::state::{
-    queries, MqttScheme, SensorStateEvent, SensorsStateActor, SubscribeToStateEvents,
+    queries, GetAlertHistory, GetAlertRules, GetSilencing, MqttScheme, SensorStateEvent, SetAlertRules,
+    SetMaintenanceWindows, SilenceAlerts, StateRouterActor, SubscribeToStateEvents,
 };
-use crate::model::{ConnectorId};
+use crate::config::{BrokerConfig, PushConfig, StateConfig};
+use crate::correlation::{self, CorrelationId};
+use crate::model::{ConnectorId, MetricId, SensorId};
+use crate::telemetry;
+
+/// Processes the wrapped query as a part of the operation `0`
+pub struct Correlated<M>(pub CorrelationId, pub M);
+
+impl<M: Message> Message for Correlated<M> {
+    type Result = M::Result;
+}
 
 #[derive(Clone)]
 pub struct SensorVisionClient {
     pub(crate) connector_id: ConnectorId,
 
     pub(crate) mqtt_actor: Addr<MqttActor>,
-    pub(crate) state_actor: Addr<SensorsStateActor>,
+    pub(crate) state_actor: Addr<StateRouterActor>,
 
-    #[allow(dead_code)]
     mqtt_listener_service: Addr<MqttListenerService>,
+
+    // SensorList reloads triggered by sensor updates are coalesced
+    last_reload_at: Option<Instant>,
+    pub(crate) reload_scheduled: bool,
+
+    describe_scheduler: DescribeScheduler,
+    describe_batch_scheduled: bool,
+    pub(crate) reconciler: Reconciler,
+
+    // Metrics a push by name doesn't find are created with these settings
+    pub(crate) push: PushConfig,
+}
+
+/// Bursts of sensor updates (e.g. bulk renames) cause at most one reload per interval
+const RELOAD_MIN_INTERVAL: Duration = Duration::from_millis(500);
+
+const INVENTORY_TIMEOUT: Duration = Duration::from_secs(10);
+const INVENTORY_SETTLE_POLLS: u32 = 3;
+
+/// Loads the sensors and waits until the state stops changing, i.e. all metrics are described
+pub async fn load_inventory(client: &Addr<SensorVisionClient>) -> Result<()> {
+    client.send(LoadSensors).await??;
+
+    let started_at = Instant::now();
+    let mut last_version = 0;
+    let mut unchanged_polls = 0;
+    while unchanged_polls < INVENTORY_SETTLE_POLLS {
+        if started_at.elapsed() > INVENTORY_TIMEOUT {
+            return Err(eyre!("Timed out loading sensors"));
+        }
+        tokio::time::sleep(Duration::from_millis(200)).await;
+
+        let version = client.send(GetStateVersion).await??;
+        if version != 0 && version == last_version {
+            unchanged_polls += 1;
+        } else {
+            unchanged_polls = 0;
+        }
+        last_version = version;
+    }
+    Ok(())
 }
 
 impl SensorVisionClient {
     pub async fn new(connector_id: ConnectorId) -> Result<Self> {
+        Self::with_credentials(
+            connector_id,
+            &BrokerConfig::default(),
+            &MqttCredentials::default(),
+            &StateConfig::default(),
+        )
+        .await
+    }
+
+    pub async fn with_credentials(
+        connector_id: ConnectorId,
+        broker: &BrokerConfig,
+        credentials: &MqttCredentials,
+        state: &StateConfig,
+    ) -> Result<Self> {
         let events_topic = format!("/v1.0/{}/#", connector_id);
-        let mqtt_actor = MqttActor::connect_and_start().await?;
-        let mqtt_listener_service = MqttListenerService::connect_and_start(events_topic).await?;
-        let state_actor = SensorsStateActor::new().start();
+        let mqtt_actor = MqttActor::connect_and_start(broker, credentials).await?;
+        let mqtt_listener_service =
+            MqttListenerService::connect_and_start(events_topic, broker, credentials).await?;
+        let state_router = StateRouterActor::new(state.shards, state.mailbox_capacity, state.history_file.as_deref())?;
+        let state_actor = StateRouterActor::create(|ctx| {
+            ctx.set_mailbox_capacity(state.mailbox_capacity);
+            state_router
+        });
 
         mqtt_listener_service
             .send(SubscribeToListener(state_actor.downgrade().recipient()))
@@ -46,24 +124,124 @@ impl SensorVisionClient {
             mqtt_actor,
             state_actor,
             mqtt_listener_service,
+            last_reload_at: None,
+            reload_scheduled: false,
+            describe_scheduler: DescribeScheduler::new(
+                broker.describe_batch_size,
+                broker.describe_interval(),
+            ),
+            describe_batch_scheduled: false,
+            reconciler: Reconciler::default(),
+            push: PushConfig::default(),
         })
     }
 
+    /// Creates the sensors and metrics pushes by name lack, if `push` says so
+    pub fn with_push(mut self, push: PushConfig) -> Self {
+        self.push = push;
+        self
+    }
+
+    /// Reloads the sensors right away unless a reload has been sent recently,
+    /// otherwise schedules one for when the interval passes
+    fn request_reload(&mut self, ctx: &mut Context<Self>) {
+        if self.reload_scheduled {
+            log::debug!("Sensors reload is already scheduled");
+            return;
+        }
+
+        let since_last_reload = self
+            .last_reload_at
+            .map_or(RELOAD_MIN_INTERVAL, |last_reload_at| last_reload_at.elapsed());
+        if since_last_reload >= RELOAD_MIN_INTERVAL {
+            self.reload();
+            return;
+        }
+
+        self.reload_scheduled = true;
+        ctx.run_later(RELOAD_MIN_INTERVAL - since_last_reload, |act, _| {
+            act.reload_scheduled = false;
+            act.reload();
+        });
+    }
+
+    fn reload(&mut self) {
+        self.last_reload_at = Some(Instant::now());
+        self.raw_message(MqttScheme::SensorList, None);
+    }
+
     pub(crate) fn raw_message_inner(
         mqtt_actor: &Addr<MqttActor>,
         connector_id: &ConnectorId,
         scheme: MqttScheme,
         payload: Option<String>,
     ) {
-        let (topic, _, _) = scheme.get_topics();
+        let (topic, response_topic, error_topic) = scheme.get_topics();
         let full_topic = format!("/v1.0/{}/{}", connector_id, topic);
 
         let message = payload.unwrap_or(String::from("{}"));
 
-        mqtt_actor.do_send(OneWayMessage(MqttMessage {
-            topic: full_topic,
-            message,
-        }));
+        let full_response_topic = format!("/v1.0/{}/{}", connector_id, response_topic);
+        let full_error_topic = format!("/v1.0/{}/{}", connector_id, error_topic);
+
+        let correlation_id = correlation::current();
+        if let Some(correlation_id) = correlation_id {
+            correlation::expect_response(correlation_id, &[&full_response_topic, &full_error_topic]);
+        }
+        telemetry::request_sent(&full_topic, &[&full_response_topic, &full_error_topic]);
+
+        mqtt_actor.do_send(OneWayMessage {
+            message: MqttMessage {
+                topic: full_topic,
+                message,
+                correlation_id,
+            },
+            response_topic: full_response_topic,
+            error_topic: full_error_topic,
+            sensor_id: scheme.sensor_id(),
+        });
+    }
+
+    /// The protocol describes one metric per request, so a request is queued for each of them
+    /// and sent by the describe scheduler
+    pub(crate) fn describe_metrics(
+        &mut self,
+        ctx: &mut Context<Self>,
+        sensor_id: SensorId,
+        metric_ids: impl IntoIterator<Item = MetricId>,
+    ) {
+        let metric_ids: Vec<MetricId> = metric_ids.into_iter().collect();
+        let queued = self.describe_scheduler.enqueue(
+            sensor_id,
+            metric_ids.iter().copied(),
+            correlation::current(),
+            Instant::now(),
+        );
+        if queued < metric_ids.len() {
+            log::debug!(
+                "{} describe(s) of sensor {sensor_id} are pending already",
+                metric_ids.len() - queued
+            );
+        }
+        if !self.describe_batch_scheduled {
+            self.send_describes(ctx);
+        }
+    }
+
+    fn send_describes(&mut self, ctx: &mut Context<Self>) {
+        for (sensor_id, metric_id, correlation_id) in self.describe_scheduler.next_batch(Instant::now()) {
+            correlation::sync_scope(correlation_id, || {
+                self.raw_message(MqttScheme::MetricDescribe(sensor_id, metric_id), None)
+            });
+        }
+        if self.describe_scheduler.is_idle() {
+            return;
+        }
+        self.describe_batch_scheduled = true;
+        ctx.run_later(self.describe_scheduler.interval(), |act, ctx| {
+            act.describe_batch_scheduled = false;
+            act.send_describes(ctx);
+        });
     }
 
     pub(crate) fn raw_message(&self, scheme: MqttScheme, payload: Option<String>) {
@@ -93,17 +271,22 @@ impl SensorVisionClient {
         let full_error_topic = format!("/v1.0/{}/{}", connector_id, error_topic);
 
         let message = message.unwrap_or(String::from("{}"));
-
-        Ok(mqtt_actor
+        let sent_at = SystemTime::now();
+        let response = mqtt_actor
             .send(MqttRequest {
                 message: MqttMessage {
-                    topic: full_topic,
+                    topic: full_topic.clone(),
                     message,
+                    correlation_id: correlation::current(),
                 },
                 response_topic: full_response_topic,
                 error_topic: full_error_topic,
+                sensor_id: scheme.sensor_id(),
             })
-            .await??)
+            .await?;
+        telemetry::request_completed(&full_topic, sent_at, response.is_err());
+        // The reply reaches the state as well, which completes the exchange
+        response
     }
 
     #[allow(dead_code)]
@@ -143,10 +326,15 @@ impl Actor for SensorVisionClient {
 
     fn started(&mut self, ctx: &mut Self::Context) {
         let state_actor = self.state_actor.clone();
-        let weak_this = ctx.address().downgrade().recipient();
+        let mqtt_listener_service = self.mqtt_listener_service.clone();
+        let weak_this = ctx.address().downgrade();
         ctx.spawn(
             async move {
-                let _ = state_actor.send(SubscribeToStateEvents(weak_this)).await;
+                let _ = state_actor.send(SubscribeToStateEvents(weak_this.clone().recipient())).await;
+                // Replies to the describes
+                let _ = mqtt_listener_service
+                    .send(SubscribeToListener(weak_this.recipient()))
+                    .await;
             }
             .into_actor(self),
         );
@@ -159,54 +347,71 @@ impl Handler<SensorStateEvent> for SensorVisionClient {
     fn handle(&mut self, event: SensorStateEvent, ctx: &mut Self::Context) -> Self::Result {
         use SensorStateEvent::*;
         match &event {
-            NewLinkedSensorLoaded(linked_sensor) | ExistingLinkedSensorLoaded(linked_sensor) => {
-                for linked_metric in &linked_sensor.metrics {
-                    self.raw_message(
-                        MqttScheme::MetricDescribe(
-                            linked_sensor.sensor_id,
-                            linked_metric.metric_id,
-                        ),
-                        None,
-                    );
-                }
+            NewLinkedSensorLoaded(linked_sensor) => {
+                self.describe_metrics(
+                    ctx,
+                    linked_sensor.sensor_id,
+                    linked_sensor.metrics.ids().copied(),
+                );
+            }
+
+            // The list itself carries the names, the states and the deleted metrics,
+            // only the metrics of the changed sensors may need describing
+            ExistingLinkedSensorLoaded(linked_sensor) if self.reconciler.take_dirty(linked_sensor.sensor_id) => {
+                self.describe_metrics(ctx, linked_sensor.sensor_id, linked_sensor.metrics.ids().copied());
+            }
+
+            // Unless a describe got no reply, e.g. lost while offline
+            ExistingLinkedSensorLoaded(linked_sensor) => {
+                let sensor_id = linked_sensor.sensor_id;
+                let listed: Vec<MetricId> = linked_sensor.metrics.ids().copied().collect();
+                let state_actor = self.state_actor.clone();
+                ctx.spawn(
+                    async move { state_actor.send(queries::GetMetricIds(sensor_id)).await? }
+                        .into_actor(self)
+                        .map(move |query_result, act, ctx| match query_result {
+                            Ok(Some(described)) => {
+                                let undescribed: Vec<MetricId> =
+                                    listed.into_iter().filter(|metric_id| !described.contains(metric_id)).collect();
+                                if !undescribed.is_empty() {
+                                    act.describe_metrics(ctx, sensor_id, undescribed);
+                                }
+                            }
+                            Ok(None) => {}
+                            Err(err) => log::error!("Query failed: {}", err),
+                        }),
+                );
             }
 
             NewMetricCreated {
                 sensor_id,
                 metric_id,
-            } => self.raw_message(MqttScheme::MetricDescribe(*sensor_id, *metric_id), None),
+            } => self.describe_metrics(ctx, *sensor_id, [*metric_id]),
 
-            SensorUpdated { .. } => {
+            SensorDeleted { sensor_id } => {
+                self.describe_scheduler.forget_sensor(*sensor_id);
+                self.reconciler.forget(*sensor_id);
+            }
+
+            SensorUpdated { sensor_id } => {
                 // There is no other way to get sensor/metric update details
                 // rather than reloading all the sensors again :(
-                self.raw_message(MqttScheme::SensorList, None)
+                self.reconciler.mark_dirty(*sensor_id);
+                self.request_reload(ctx)
             }
 
             SensorMetricsUpdated { sensor_id } => {
                 let query = queries::GetMetricIds(*sensor_id);
                 let state_actor = self.state_actor.clone();
-                let mqtt_actor = self.mqtt_actor.clone();
-                let connector_id = self.connector_id.clone();
                 let sensor_id = *sensor_id;
                 ctx.spawn(
-                    async move {
-                        let query_result = state_actor.send(query).await;
-                        if let Err(err) = query_result {
-                            log::error!("Query failed: {}", err);
-                        } else {
-                            if let Some(metric_ids) = query_result.unwrap() {
-                                for metric_id in metric_ids {
-                                    Self::raw_message_inner(
-                                        &mqtt_actor,
-                                        &connector_id,
-                                        MqttScheme::MetricDescribe(sensor_id.clone(), metric_id),
-                                        None,
-                                    );
-                                }
-                            }
-                        }
-                    }
-                    .into_actor(self),
+                    async move { state_actor.send(query).await? }
+                        .into_actor(self)
+                        .map(move |query_result, act, ctx| match query_result {
+                            Ok(Some(metric_ids)) => act.describe_metrics(ctx, sensor_id, metric_ids),
+                            Ok(None) => {}
+                            Err(err) => log::error!("Query failed: {}", err),
+                        }),
                 );
             }
 
@@ -215,6 +420,41 @@ impl Handler<SensorStateEvent> for SensorVisionClient {
     }
 }
 
+/// Replies to the describes, after which the same metrics may be described again
+impl Handler<MqttEvent> for SensorVisionClient {
+    type Result = ();
+
+    fn handle(&mut self, MqttEvent(msg): MqttEvent, _: &mut Self::Context) -> Self::Result {
+        if !msg.topic.ends_with("/inventory/inbox") && !msg.topic.ends_with("/inventory/error/inbox") {
+            return;
+        }
+        let (ids, _) = MqttScheme::extract_ids_and_pattern(&msg.topic);
+        // The connector id comes first
+        if let [_, sensor_id, metric_id, ..] = ids.as_slice() {
+            self.describe_scheduler
+                .described(SensorId::from(*sensor_id), MetricId::from(*metric_id));
+        }
+    }
+}
+
+impl Handler<SubscribeToConnectionEvents> for SensorVisionClient {
+    type Result = ();
+
+    fn handle(&mut self, msg: SubscribeToConnectionEvents, _: &mut Self::Context) -> Self::Result {
+        self.mqtt_listener_service.do_send(msg);
+    }
+}
+
+impl Handler<Reconnect> for SensorVisionClient {
+    type Result = ();
+
+    fn handle(&mut self, msg: Reconnect, _: &mut Self::Context) -> Self::Result {
+        self.mqtt_actor.do_send(msg.clone());
+        self.mqtt_listener_service.do_send(msg);
+    }
+}
+
+/// Passes the queries on to the state, a state which is gone fails the query
 macro_rules! delegate_state_queries {
     ($actor:ty, { $( $msg:ty ),* $(,)? }) => {
         $(
@@ -223,19 +463,38 @@ macro_rules! delegate_state_queries {
 
                 fn handle(&mut self, msg: $msg, _: &mut Self::Context) -> Self::Result {
                     let state_actor = self.state_actor.clone();
-                    async move {
-                        state_actor.send(msg).await.expect("Delegating failed")
-                    }.boxed_local()
+                    async move { state_actor.send(msg).await? }.boxed_local()
                 }
             }
         )*
     };
 }
 
+impl Handler<SubscribeToStateEvents> for SensorVisionClient {
+    type Result = ();
+
+    fn handle(&mut self, msg: SubscribeToStateEvents, _: &mut Self::Context) -> Self::Result {
+        self.state_actor.do_send(msg);
+    }
+}
+
 delegate_state_queries!(SensorVisionClient, {
-    SubscribeToStateEvents,
     GetStateSnapshot,
+    GetVersionedStateSnapshot,
+    GetStateVersion,
+    GetLastLivedata,
+    GetUnwatchedLivedata,
     GetMetricIds,
     GetSensorIdByName,
+    GetSensorIdsByName,
     GetMetricIdByName,
+    ResolveSensor,
+    ResolveMetric,
+    FindMetrics,
+    SetAlertRules,
+    GetAlertRules,
+    GetAlertHistory,
+    SetMaintenanceWindows,
+    SilenceAlerts,
+    GetSilencing,
 });
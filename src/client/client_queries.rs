@@ -1,18 +1,25 @@
-use actix::{Handler, Message, ResponseFuture};
+use actix::{ActorFutureExt, Addr, AsyncContext, Handler, Message, MessageResult, ResponseFuture, WrapFuture};
 
 use eyre::{eyre, Context, Result};
 
 use futures::FutureExt;
 
-use std::time::SystemTime;
+use std::path::PathBuf;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
 
-use crate::client::client::SensorVisionClient;
-use crate::client::state::queries::GetStateSnapshot;
-use crate::client::state::MqttScheme;
+use crate::client::client::{Correlated, SensorVisionClient};
+use crate::client::state::queries::{
+    GetLastLivedata, GetMetricIdByName, GetSensorIdsByName, GetStateSnapshot, GetUnwatchedLivedata,
+    ResolveMetric, ResolveSensor,
+};
+use crate::client::state::{
+    parse_inventory, plan_import, protect_recent_livedata, ImportStep, MqttScheme, StateRouterActor,
+};
 
 use crate::model::protocol::{CreateMetricPayload, CreateSensorRequest, DeleteMetricRequest, MetricValue, MetricsArrayRequest, PingRequest, PingResponse, PushMetricValueRequest, UpdateMetricRequest, UpdateSensorRequest};
-use crate::model::sensor::Metric;
-use crate::model::{MetricId, SensorId};
+use crate::model::sensor::{Metric, NewSensor, SensorState};
+use crate::correlation::{self, CorrelationId};
+use crate::model::{ConnectorId, MetricId, SensorId, Timestamp};
 
 #[derive(Message)]
 #[rtype(result = "Result<()>")]
@@ -29,7 +36,7 @@ pub struct CreateSensor {
 pub struct UpdateSensor {
     pub sensor_id: SensorId,
     pub name: String,
-    pub state: Option<bool>,
+    pub state: Option<SensorState>,
 }
 
 #[derive(Message)]
@@ -38,9 +45,74 @@ pub struct DeleteSensor {
     pub sensor_id: SensorId,
 }
 
+/// Creates a sensor with the same metrics as the source one, predefined and custom alike
 #[derive(Message)]
+#[rtype(result = "Result<()>")]
+pub struct CloneSensor {
+    pub source_sensor_id: SensorId,
+    pub new_name: String,
+}
+
+/// Creates a sensor put together by [`SensorBuilder`](crate::model::sensor::SensorBuilder),
+/// then its metrics once the sensor appears, and disables it if asked to
+#[derive(Message)]
+#[rtype(result = "Result<SensorId>")]
+pub struct SubmitSensor(pub NewSensor);
+
+/// Sends a request which failed once more, as it was
+#[derive(Message)]
+#[rtype(result = "Result<()>")]
+pub struct RetryRequest {
+    pub scheme: MqttScheme,
+    pub payload: String,
+}
+
+/// Sensors are always ordered by id, so that dumps of the same inventory are identical
+#[derive(Message, Default)]
 #[rtype(result = "Result<String>")]
-pub struct DumpSensors;
+pub struct DumpSensors {
+    pub metric_order: MetricOrder,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum MetricOrder {
+    /// As the connector lists them, i.e. the order they were created in
+    #[default]
+    Creation,
+
+    /// By name, metrics sharing a name by id
+    Name,
+}
+
+impl std::str::FromStr for MetricOrder {
+    type Err = eyre::Report;
+
+    fn from_str(order: &str) -> Result<Self> {
+        match order {
+            "creation" => Ok(Self::Creation),
+            "name" => Ok(Self::Name),
+            _ => Err(eyre!("Unknown order '{order}', expected creation or name")),
+        }
+    }
+}
+
+/// Brings the inventory in line with a JSON file describing it, in the format of
+/// [`DumpSensors`] or as a list of sensors, and returns the steps it took
+#[derive(Message)]
+#[rtype(result = "Result<Vec<ImportStep>>")]
+pub struct ImportSensors {
+    pub path: PathBuf,
+    /// Also delete what the file lacks, as well as metrics it redefines
+    pub prune: bool,
+    /// Only plan the steps
+    pub dry_run: bool,
+    /// Sensors and metrics which received livedata within this long aren't deleted
+    pub protect_livedata: Option<Duration>,
+}
+
+/// How long metrics of a sensor created by an import or a clone wait for the sensor to appear,
+/// and pushes for the sensor or metric they created
+const CREATED_SENSOR_TIMEOUT: Duration = Duration::from_secs(10);
 
 #[derive(Message)]
 #[rtype(result = "Result<()>")]
@@ -69,13 +141,99 @@ pub struct DeleteMetric {
     pub metric_id: MetricId,
 }
 
+/// Describes the metrics again without reloading the whole inventory,
+/// e.g. after they were changed outside of this client
+#[derive(Message)]
+#[rtype(result = "Result<()>")]
+pub struct DescribeMetrics {
+    pub sensor_id: SensorId,
+    pub metric_ids: Vec<MetricId>,
+}
+
 #[derive(Message)]
 #[rtype(result = "Result<()>")]
 pub struct PushValue {
     pub sensor_id: SensorId,
     pub metric_id: MetricId,
     pub value: MetricValue,
-    pub timestamp: Option<SystemTime>,
+    pub timestamp: Option<Timestamp>,
+}
+
+/// Name-based variant of [`DeleteSensor`]
+#[derive(Message)]
+#[rtype(result = "Result<()>")]
+pub struct DeleteSensorByName {
+    pub sensor_name: String,
+}
+
+/// Name-based variant of [`DeleteMetric`]
+#[derive(Message)]
+#[rtype(result = "Result<()>")]
+pub struct DeleteMetricByName {
+    pub sensor_name: String,
+    pub metric_name: String,
+}
+
+/// Name-based variant of [`PushValue`], the value is parsed according to the metric type
+#[derive(Message)]
+#[rtype(result = "Result<()>")]
+pub struct PushValueByName {
+    pub sensor_name: String,
+    pub metric_name: String,
+    pub value: String,
+    pub timestamp: Option<Timestamp>,
+}
+
+/// [`ResolveMetric`] which creates the sensor or the metric if missing and the push config
+/// of the client allows it
+#[derive(Message)]
+#[rtype(result = "Result<(SensorId, Metric)>")]
+pub struct ResolveMetricForPush {
+    pub sensor_name: String,
+    pub metric_name: String,
+}
+
+#[derive(Message)]
+#[rtype(result = "ConnectorId")]
+pub struct GetConnectorId;
+
+/// Whether a sensors reload is held back to coalesce it with further updates
+#[derive(Message)]
+#[rtype(result = "bool")]
+pub struct IsReloadPending;
+
+impl SensorVisionClient {
+    /// Sends the id-based query resolved from a name-based one, keeping the operation it belongs to
+    async fn forward_resolved<M>(
+        this: Addr<Self>,
+        correlation_id: Option<CorrelationId>,
+        msg: M,
+    ) -> Result<()>
+    where
+        M: Message<Result = Result<()>> + Send + 'static,
+        Self: Handler<M> + Handler<Correlated<M>>,
+    {
+        match correlation_id {
+            Some(correlation_id) => this.send(Correlated(correlation_id, msg)).await?,
+            None => this.send(msg).await?,
+        }
+    }
+}
+
+impl Handler<GetConnectorId> for SensorVisionClient {
+    type Result = MessageResult<GetConnectorId>;
+
+    fn handle(&mut self, _: GetConnectorId, _: &mut Self::Context) -> Self::Result {
+        MessageResult(self.connector_id)
+    }
+}
+
+impl Handler<IsReloadPending> for SensorVisionClient {
+    type Result = bool;
+
+    fn handle(&mut self, _: IsReloadPending, _: &mut Self::Context) -> Self::Result {
+        self.reload_scheduled
+    }
 }
 
 impl Handler<PingTest> for SensorVisionClient {
@@ -87,7 +245,7 @@ impl Handler<PingTest> for SensorVisionClient {
         };
 
         let mqtt_actor = self.mqtt_actor.clone();
-        let connector_id = self.connector_id.clone();
+        let connector_id = self.connector_id;
 
         async move {
             let pong: PingResponse =
@@ -111,6 +269,7 @@ impl Handler<CreateSensor> for SensorVisionClient {
         CreateSensor { name }: CreateSensor,
         _: &mut Self::Context,
     ) -> Self::Result {
+        log::info!("Creating sensor {name}");
         let request = CreateSensorRequest {
             name: String::from(name),
         };
@@ -131,9 +290,10 @@ impl Handler<UpdateSensor> for SensorVisionClient {
         }: UpdateSensor,
         _: &mut Self::Context,
     ) -> Self::Result {
+        log::info!("Updating sensor {sensor_id}: name {name}, state {state:?}");
         let request = UpdateSensorRequest {
             name: String::from(name),
-            state: state.map(|x| x as u8),
+            state: state.map(u8::from),
         };
         self.message(MqttScheme::SensorUpdate(sensor_id), &request)
     }
@@ -147,29 +307,387 @@ impl Handler<DeleteSensor> for SensorVisionClient {
         DeleteSensor { sensor_id }: DeleteSensor,
         _: &mut Self::Context,
     ) -> Self::Result {
+        log::info!("Deleting sensor {sensor_id}");
         Ok(self.raw_message(MqttScheme::SensorDelete(sensor_id), None))
     }
 }
 
+impl Handler<CloneSensor> for SensorVisionClient {
+    type Result = ResponseFuture<Result<()>>;
+
+    fn handle(
+        &mut self,
+        CloneSensor {
+            source_sensor_id,
+            new_name,
+        }: CloneSensor,
+        ctx: &mut Self::Context,
+    ) -> Self::Result {
+        let state_actor = self.state_actor.clone();
+        let this = ctx.address();
+        let correlation_id = correlation::current();
+
+        async move {
+            let sensors = state_actor.send(GetStateSnapshot).await??;
+            let source = sensors
+                .get(&source_sensor_id)
+                .ok_or_else(|| eyre!("Sensor {source_sensor_id} not found"))?;
+            // Otherwise the metrics could end up on the namesake
+            if sensors.values().any(|sensor| sensor.name == new_name) {
+                return Err(eyre!("Sensor {new_name} already exists"));
+            }
+            let metrics: Vec<Metric> = source.metrics.iter().map(Metric::detached).collect();
+            log::info!(
+                "Cloning sensor {} as {new_name} with {} metric(s)",
+                source.name,
+                metrics.len()
+            );
+
+            let create_sensor = CreateSensor {
+                name: new_name.clone(),
+            };
+            Self::forward_resolved(this.clone(), correlation_id, create_sensor).await?;
+            if metrics.is_empty() {
+                return Ok(());
+            }
+            let sensor_id = Self::created_sensor_id(&state_actor, &new_name).await?;
+            Self::forward_resolved(this, correlation_id, CreateMetrics { sensor_id, metrics }).await
+        }
+        .boxed_local()
+    }
+}
+
+impl Handler<SubmitSensor> for SensorVisionClient {
+    type Result = ResponseFuture<Result<SensorId>>;
+
+    fn handle(
+        &mut self,
+        SubmitSensor(NewSensor {
+            name,
+            state,
+            metrics,
+        }): SubmitSensor,
+        ctx: &mut Self::Context,
+    ) -> Self::Result {
+        let state_actor = self.state_actor.clone();
+        let this = ctx.address();
+        let correlation_id = correlation::current();
+
+        async move {
+            let sensors = state_actor.send(GetStateSnapshot).await??;
+            // Otherwise the metrics could end up on the namesake
+            if sensors.values().any(|sensor| sensor.name == name) {
+                return Err(eyre!("Sensor {name} already exists"));
+            }
+            log::info!("Submitting sensor {name} with {} metric(s)", metrics.len());
+
+            let create_sensor = CreateSensor { name: name.clone() };
+            Self::forward_resolved(this.clone(), correlation_id, create_sensor).await?;
+            let sensor_id = Self::created_sensor_id(&state_actor, &name).await?;
+            if !metrics.is_empty() {
+                let create_metrics = CreateMetrics { sensor_id, metrics };
+                Self::forward_resolved(this.clone(), correlation_id, create_metrics).await?;
+            }
+            if state == SensorState::Disabled {
+                let update_sensor = UpdateSensor {
+                    sensor_id,
+                    name,
+                    state: Some(state),
+                };
+                Self::forward_resolved(this, correlation_id, update_sensor).await?;
+            }
+            Ok(sensor_id)
+        }
+        .boxed_local()
+    }
+}
+
+impl Handler<RetryRequest> for SensorVisionClient {
+    type Result = Result<()>;
+
+    fn handle(
+        &mut self,
+        RetryRequest { scheme, payload }: RetryRequest,
+        _: &mut Self::Context,
+    ) -> Self::Result {
+        if !scheme.is_retriable() {
+            return Err(eyre!("{scheme:?} requests aren't retried"));
+        }
+        log::info!("Retrying {scheme:?}");
+        self.raw_message(scheme, Some(payload));
+        Ok(())
+    }
+}
+
 impl Handler<DumpSensors> for SensorVisionClient {
     type Result = ResponseFuture<Result<String>>;
 
-    fn handle(&mut self, _: DumpSensors, _: &mut Self::Context) -> Self::Result {
+    fn handle(
+        &mut self,
+        DumpSensors { metric_order }: DumpSensors,
+        _: &mut Self::Context,
+    ) -> Self::Result {
         let state_actor = self.state_actor.clone();
 
         async move {
-            let sensors = state_actor.send(GetStateSnapshot).await?;
+            let mut sensors = state_actor.send(GetStateSnapshot).await??;
+            if metric_order == MetricOrder::Name {
+                for sensor in sensors.values_mut() {
+                    sensor
+                        .metrics
+                        .sort_by(|a, b| (a.name(), a.metric_id()).cmp(&(b.name(), b.metric_id())));
+                }
+            }
             serde_json::to_string_pretty(&sensors).wrap_err("Failed to dump sensors")
         }
         .boxed_local()
     }
 }
 
+impl Handler<ImportSensors> for SensorVisionClient {
+    type Result = ResponseFuture<Result<Vec<ImportStep>>>;
+
+    fn handle(
+        &mut self,
+        ImportSensors {
+            path,
+            prune,
+            dry_run,
+            protect_livedata,
+        }: ImportSensors,
+        ctx: &mut Self::Context,
+    ) -> Self::Result {
+        let state_actor = self.state_actor.clone();
+        let this = ctx.address();
+        let correlation_id = correlation::current();
+
+        async move {
+            let json = std::fs::read_to_string(&path)
+                .wrap_err_with(|| format!("Failed to read {}", path.display()))?;
+            let desired = parse_inventory(&json)
+                .wrap_err_with(|| format!("Failed to parse {}", path.display()))?;
+            let current = state_actor.send(GetStateSnapshot).await??;
+            let mut steps = plan_import(&current, &desired, prune);
+            if let Some(window) = protect_livedata {
+                let last_livedata = state_actor.send(GetLastLivedata).await??;
+                let now = Timestamp::now();
+                let since = SystemTime::from(now).checked_sub(window).unwrap_or(UNIX_EPOCH).into();
+                let unwatched = state_actor.send(GetUnwatchedLivedata { since, now }).await??;
+                steps = protect_recent_livedata(steps, &last_livedata, since, unwatched);
+            }
+            if dry_run {
+                return Ok(steps);
+            }
+
+            log::info!("Importing {}: {} step(s)", path.display(), steps.len());
+            for step in &steps {
+                Self::apply_import_step(&this, &state_actor, correlation_id, step.clone())
+                    .await
+                    .wrap_err_with(|| format!("Failed to apply {step}"))?;
+            }
+            Ok(steps)
+        }
+        .boxed_local()
+    }
+}
+
+impl SensorVisionClient {
+    /// Waits for the sensor just created under the name to be loaded
+    async fn created_sensor_id(state_actor: &Addr<StateRouterActor>, name: &str) -> Result<SensorId> {
+        tokio::time::timeout(CREATED_SENSOR_TIMEOUT, async {
+            loop {
+                let resolve = ResolveSensor {
+                    sensor_name: name.to_owned(),
+                };
+                if let Ok(Ok(sensor_id)) = state_actor.send(resolve).await {
+                    break sensor_id;
+                }
+                tokio::time::sleep(Duration::from_millis(100)).await;
+            }
+        })
+        .await
+        .map_err(|_| eyre!("Sensor {name} didn't appear in time"))
+    }
+
+    /// Resolves the metric, creating `missing_metric` (and its sensor) if the metric doesn't exist
+    async fn resolve_for_push(
+        this: &Addr<Self>,
+        state_actor: &Addr<StateRouterActor>,
+        correlation_id: Option<CorrelationId>,
+        sensor_name: &str,
+        metric_name: &str,
+        missing_metric: Option<Metric>,
+    ) -> Result<(SensorId, Metric)> {
+        let resolve = ResolveMetric {
+            sensor_name: sensor_name.to_owned(),
+            metric_name: metric_name.to_owned(),
+        };
+        let error = match state_actor.send(resolve).await? {
+            Ok(resolved) => return Ok(resolved),
+            Err(error) => error,
+        };
+        let Some(metric) = missing_metric else {
+            return Err(error);
+        };
+
+        // Only a missing name is created, an ambiguous one still fails the push
+        let step = match state_actor.send(GetSensorIdsByName(sensor_name.to_owned())).await??[..] {
+            [] => ImportStep::CreateSensor {
+                name: sensor_name.to_owned(),
+                state: SensorState::default(),
+                metrics: vec![metric],
+            },
+            [sensor_id] => {
+                let get_metric_id = GetMetricIdByName(sensor_id, metric_name.to_owned());
+                if state_actor.send(get_metric_id).await??.is_some() {
+                    return Err(error);
+                }
+                ImportStep::CreateMetrics {
+                    sensor_id,
+                    sensor: sensor_name.to_owned(),
+                    metrics: vec![metric],
+                }
+            }
+            _ => return Err(error),
+        };
+        log::info!("Pushing to missing {sensor_name}/{metric_name}, creating it: {step}");
+        Self::apply_import_step(this, state_actor, correlation_id, step).await?;
+        Self::created_metric(state_actor, sensor_name, metric_name).await
+    }
+
+    /// Waits for the metric just created under the names to be loaded
+    async fn created_metric(
+        state_actor: &Addr<StateRouterActor>,
+        sensor_name: &str,
+        metric_name: &str,
+    ) -> Result<(SensorId, Metric)> {
+        tokio::time::timeout(CREATED_SENSOR_TIMEOUT, async {
+            loop {
+                let resolve = ResolveMetric {
+                    sensor_name: sensor_name.to_owned(),
+                    metric_name: metric_name.to_owned(),
+                };
+                if let Ok(Ok(resolved)) = state_actor.send(resolve).await {
+                    break resolved;
+                }
+                tokio::time::sleep(Duration::from_millis(100)).await;
+            }
+        })
+        .await
+        .map_err(|_| eyre!("Metric {sensor_name}/{metric_name} didn't appear in time"))
+    }
+
+    async fn apply_import_step(
+        this: &Addr<Self>,
+        state_actor: &Addr<StateRouterActor>,
+        correlation_id: Option<CorrelationId>,
+        step: ImportStep,
+    ) -> Result<()> {
+        let this = this.clone();
+        match step {
+            ImportStep::CreateSensor {
+                name,
+                state,
+                metrics,
+            } => {
+                let create_sensor = CreateSensor { name: name.clone() };
+                Self::forward_resolved(this.clone(), correlation_id, create_sensor).await?;
+                if metrics.is_empty() && state == SensorState::default() {
+                    return Ok(());
+                }
+                // The metrics need the id the connector assigns to the sensor
+                let sensor_id = Self::created_sensor_id(state_actor, &name).await?;
+                if !metrics.is_empty() {
+                    let create_metrics = CreateMetrics { sensor_id, metrics };
+                    Self::forward_resolved(this.clone(), correlation_id, create_metrics)
+                        .await?;
+                }
+                if state != SensorState::default() {
+                    let update_sensor = UpdateSensor {
+                        sensor_id,
+                        name,
+                        state: Some(state),
+                    };
+                    Self::forward_resolved(this, correlation_id, update_sensor).await?;
+                }
+                Ok(())
+            }
+            ImportStep::UpdateSensor {
+                sensor_id,
+                name,
+                state,
+                ..
+            } => {
+                let update_sensor = UpdateSensor {
+                    sensor_id,
+                    name,
+                    state: Some(state),
+                };
+                Self::forward_resolved(this, correlation_id, update_sensor).await
+            }
+            ImportStep::DeleteSensor { sensor_id, .. } => {
+                Self::forward_resolved(this, correlation_id, DeleteSensor { sensor_id }).await
+            }
+            ImportStep::CreateMetrics {
+                sensor_id, metrics, ..
+            } => {
+                let create_metrics = CreateMetrics { sensor_id, metrics };
+                Self::forward_resolved(this, correlation_id, create_metrics).await
+            }
+            ImportStep::UpdateMetric {
+                sensor_id,
+                metric_id,
+                from,
+                name,
+                value_annotation,
+                ..
+            } => {
+                let update_metric = UpdateMetric {
+                    sensor_id,
+                    metric_id,
+                    name: (from != name).then_some(name),
+                    value_annotation,
+                };
+                Self::forward_resolved(this, correlation_id, update_metric).await
+            }
+            ImportStep::DeleteMetric {
+                sensor_id,
+                metric_id,
+                ..
+            } => {
+                let delete_metric = DeleteMetric {
+                    sensor_id,
+                    metric_id,
+                };
+                Self::forward_resolved(this, correlation_id, delete_metric).await
+            }
+            ImportStep::Protected { .. } | ImportStep::Unwatched { .. } => Ok(()),
+        }
+    }
+}
+
 impl Handler<LoadSensors> for SensorVisionClient {
     type Result = Result<()>;
 
-    fn handle(&mut self, _: LoadSensors, _: &mut Self::Context) -> Self::Result {
-        Ok(self.raw_message(MqttScheme::SensorList, None))
+    fn handle(&mut self, _: LoadSensors, ctx: &mut Self::Context) -> Self::Result {
+        log::info!("Loading sensors");
+        // Unlike the reloads after updates, every sensor already known is described anew
+        let state_actor = self.state_actor.clone();
+        let correlation_id = correlation::current();
+        ctx.spawn(
+            async move { state_actor.send(GetStateSnapshot).await? }
+                .into_actor(self)
+                .map(move |sensors, act, _| {
+                    match sensors {
+                        Ok(sensors) => act.reconciler.mark_all_dirty(sensors.into_keys()),
+                        Err(err) => log::error!("Failed to list the sensors known: {err}"),
+                    }
+                    correlation::sync_scope(correlation_id, || {
+                        act.raw_message(MqttScheme::SensorList, None)
+                    });
+                }),
+        );
+        Ok(())
     }
 }
 
@@ -181,6 +699,7 @@ impl Handler<CreateMetrics> for SensorVisionClient {
         CreateMetrics { sensor_id, metrics }: CreateMetrics,
         _: &mut Self::Context,
     ) -> Self::Result {
+        log::info!("Creating {} metric(s) of sensor {sensor_id}", metrics.len());
         let request = MetricsArrayRequest::many(
             metrics
                 .iter()
@@ -209,8 +728,9 @@ impl Handler<UpdateMetric> for SensorVisionClient {
         }: UpdateMetric,
         _: &mut Self::Context,
     ) -> Self::Result {
+        log::info!("Updating metric {sensor_id}/{metric_id}");
         let request = MetricsArrayRequest::one(UpdateMetricRequest {
-            metric_id: metric_id.clone(),
+            metric_id,
             name,
             value_annotation,
         });
@@ -229,13 +749,31 @@ impl Handler<DeleteMetric> for SensorVisionClient {
         }: DeleteMetric,
         _: &mut Self::Context,
     ) -> Self::Result {
+        log::info!("Deleting metric {sensor_id}/{metric_id}");
         let request = MetricsArrayRequest::one(DeleteMetricRequest {
-            metric_id: metric_id.clone(),
+            metric_id,
         });
         self.message(MqttScheme::MetricDelete(sensor_id), &request)
     }
 }
 
+impl Handler<DescribeMetrics> for SensorVisionClient {
+    type Result = Result<()>;
+
+    fn handle(
+        &mut self,
+        DescribeMetrics {
+            sensor_id,
+            metric_ids,
+        }: DescribeMetrics,
+        ctx: &mut Self::Context,
+    ) -> Self::Result {
+        log::info!("Describing {} metric(s) of sensor {sensor_id}", metric_ids.len());
+        self.describe_metrics(ctx, sensor_id, metric_ids);
+        Ok(())
+    }
+}
+
 impl Handler<PushValue> for SensorVisionClient {
     type Result = Result<()>;
 
@@ -249,11 +787,7 @@ impl Handler<PushValue> for SensorVisionClient {
         }: PushValue,
         _: &mut Self::Context,
     ) -> Self::Result {
-        let timestamp = timestamp.map(|ts| {
-            ts.duration_since(SystemTime::UNIX_EPOCH)
-                .unwrap()
-                .as_millis()
-        });
+        log::debug!("Pushing {value:?} to metric {sensor_id}/{metric_id}");
         let request = MetricsArrayRequest::one(PushMetricValueRequest {
             metric_id,
             value,
@@ -263,3 +797,171 @@ impl Handler<PushValue> for SensorVisionClient {
         self.message(MqttScheme::PushValues(sensor_id), &request)
     }
 }
+
+impl Handler<DeleteSensorByName> for SensorVisionClient {
+    type Result = ResponseFuture<Result<()>>;
+
+    fn handle(
+        &mut self,
+        DeleteSensorByName { sensor_name }: DeleteSensorByName,
+        ctx: &mut Self::Context,
+    ) -> Self::Result {
+        let state_actor = self.state_actor.clone();
+        let this = ctx.address();
+        let correlation_id = correlation::current();
+
+        async move {
+            let sensor_id = state_actor.send(ResolveSensor { sensor_name }).await??;
+            Self::forward_resolved(this, correlation_id, DeleteSensor { sensor_id }).await
+        }
+        .boxed_local()
+    }
+}
+
+impl Handler<DeleteMetricByName> for SensorVisionClient {
+    type Result = ResponseFuture<Result<()>>;
+
+    fn handle(
+        &mut self,
+        DeleteMetricByName {
+            sensor_name,
+            metric_name,
+        }: DeleteMetricByName,
+        ctx: &mut Self::Context,
+    ) -> Self::Result {
+        let state_actor = self.state_actor.clone();
+        let this = ctx.address();
+        let correlation_id = correlation::current();
+
+        async move {
+            let (sensor_id, metric) = state_actor
+                .send(ResolveMetric {
+                    sensor_name,
+                    metric_name,
+                })
+                .await??;
+            let delete_metric = DeleteMetric {
+                sensor_id,
+                metric_id: *metric.metric_id(),
+            };
+            Self::forward_resolved(this, correlation_id, delete_metric).await
+        }
+        .boxed_local()
+    }
+}
+
+impl Handler<PushValueByName> for SensorVisionClient {
+    type Result = ResponseFuture<Result<()>>;
+
+    fn handle(
+        &mut self,
+        PushValueByName {
+            sensor_name,
+            metric_name,
+            value,
+            timestamp,
+        }: PushValueByName,
+        ctx: &mut Self::Context,
+    ) -> Self::Result {
+        let state_actor = self.state_actor.clone();
+        let missing_metric = self.push.missing_metric(&metric_name);
+        let this = ctx.address();
+        let correlation_id = correlation::current();
+
+        async move {
+            let (sensor_id, metric) = Self::resolve_for_push(
+                &this,
+                &state_actor,
+                correlation_id,
+                &sensor_name,
+                &metric_name,
+                missing_metric,
+            )
+            .await?;
+            let value = metric
+                .parse_value(&value)
+                .wrap_err_with(|| format!("Failed to parse \"{value}\""))?;
+            let push_value = PushValue {
+                sensor_id,
+                metric_id: *metric.metric_id(),
+                value,
+                timestamp,
+            };
+            Self::forward_resolved(this, correlation_id, push_value).await
+        }
+        .boxed_local()
+    }
+}
+
+impl Handler<ResolveMetricForPush> for SensorVisionClient {
+    type Result = ResponseFuture<Result<(SensorId, Metric)>>;
+
+    fn handle(
+        &mut self,
+        ResolveMetricForPush {
+            sensor_name,
+            metric_name,
+        }: ResolveMetricForPush,
+        ctx: &mut Self::Context,
+    ) -> Self::Result {
+        let state_actor = self.state_actor.clone();
+        let missing_metric = self.push.missing_metric(&metric_name);
+        let this = ctx.address();
+        let correlation_id = correlation::current();
+
+        async move {
+            Self::resolve_for_push(
+                &this,
+                &state_actor,
+                correlation_id,
+                &sensor_name,
+                &metric_name,
+                missing_metric,
+            )
+            .await
+        }
+        .boxed_local()
+    }
+}
+
+macro_rules! correlated_queries {
+    ($actor:ty, { $( $msg:ty ),* $(,)? }) => {
+        $(
+            impl actix::Handler<Correlated<$msg>> for $actor {
+                type Result = <$actor as actix::Handler<$msg>>::Result;
+
+                fn handle(
+                    &mut self,
+                    Correlated(correlation_id, msg): Correlated<$msg>,
+                    ctx: &mut Self::Context,
+                ) -> Self::Result {
+                    crate::correlation::sync_scope(Some(correlation_id), || {
+                        <$actor as actix::Handler<$msg>>::handle(self, msg, ctx)
+                    })
+                }
+            }
+        )*
+    };
+}
+
+correlated_queries!(SensorVisionClient, {
+    PingTest,
+    CreateSensor,
+    UpdateSensor,
+    DeleteSensor,
+    CloneSensor,
+    SubmitSensor,
+    RetryRequest,
+    DumpSensors,
+    ImportSensors,
+    LoadSensors,
+    CreateMetrics,
+    UpdateMetric,
+    DeleteMetric,
+    DescribeMetrics,
+    PushValue,
+    DeleteSensorByName,
+    DeleteMetricByName,
+    PushValueByName,
+    ResolveMetricForPush,
+});
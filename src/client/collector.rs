@@ -0,0 +1,395 @@
+use actix::Addr;
+
+use eyre::{eyre, Result};
+
+use serde_json::Value;
+
+use chrono::Local;
+
+use std::collections::HashMap;
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Mutex, OnceLock};
+use std::time::{Duration, Instant};
+
+use crate::client::client::SensorVisionClient;
+use crate::client::client_queries::{PushValue, ResolveMetricForPush};
+use crate::config::{ConversionConfig, ThrottleConfig};
+use crate::model::protocol::MetricValue;
+use crate::model::sensor::{split_target, Metric};
+use crate::model::SensorId;
+use crate::utils::lock_recovering;
+
+static SUPPRESSED_BY_DEADBAND: AtomicU64 = AtomicU64::new(0);
+static SUPPRESSED_BY_MIN_INTERVAL: AtomicU64 = AtomicU64::new(0);
+
+/// Readings the collectors have dropped since the start, by the filter that dropped them
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct SuppressedSamples {
+    pub deadband: u64,
+    pub min_interval: u64,
+}
+
+pub fn suppressed_samples() -> SuppressedSamples {
+    SuppressedSamples {
+        deadband: SUPPRESSED_BY_DEADBAND.load(Ordering::Relaxed),
+        min_interval: SUPPRESSED_BY_MIN_INTERVAL.load(Ordering::Relaxed),
+    }
+}
+
+/// Metric the readings of a collector are pushed to
+#[derive(Debug, Clone)]
+pub struct CollectorTarget {
+    pub sensor_id: SensorId,
+    pub metric: Metric,
+    /// `SENSOR/METRIC` as configured, with variables if any
+    target: String,
+    /// The target with its variables expanded when it was resolved
+    name: String,
+    /// The expanded name matched no metric, readings are dropped until it does
+    stale: bool,
+    checked_at: Instant,
+    conversion: ConversionConfig,
+    /// Scale and offset taking the readings to the unit of the metric
+    scale: Option<(f64, f64)>,
+    throttle: ThrottleConfig,
+    last_pushed: Option<(MetricValue, Instant)>,
+}
+
+impl CollectorTarget {
+    /// Resolves `SENSOR/METRIC` against the loaded inventory, with its variables expanded
+    pub async fn resolve(
+        client: &Addr<SensorVisionClient>,
+        target: &str,
+        throttle: ThrottleConfig,
+        conversion: ConversionConfig,
+    ) -> Result<Self> {
+        if throttle.deadband.is_some_and(|deadband| deadband.is_nan() || deadband < 0.0) {
+            return Err(eyre!("Deadband of {target} must not be negative"));
+        }
+        if throttle.min_interval_secs < 0.0 || !throttle.min_interval_secs.is_finite() {
+            return Err(eyre!("Minimum interval of {target} must not be negative"));
+        }
+        let name = expand_variables(target)?;
+        let (sensor_id, metric, scale) = Self::lookup(client, &name, conversion).await?;
+        Ok(Self {
+            sensor_id,
+            metric,
+            target: target.to_owned(),
+            name,
+            stale: false,
+            checked_at: Instant::now(),
+            conversion,
+            scale,
+            throttle,
+            last_pushed: None,
+        })
+    }
+
+    async fn lookup(
+        client: &Addr<SensorVisionClient>,
+        name: &str,
+        conversion: ConversionConfig,
+    ) -> Result<(SensorId, Metric, Option<(f64, f64)>)> {
+        let (sensor_name, metric_name) =
+            split_target(name).ok_or_else(|| eyre!("Target '{name}' must look like SENSOR/METRIC"))?;
+        let (sensor_id, metric) = client
+            .send(ResolveMetricForPush {
+                sensor_name,
+                metric_name,
+            })
+            .await??;
+        let scale = match (conversion.unit, &metric) {
+            (None, _) => None,
+            (Some(unit), Metric::Predefined { value_unit, .. }) => {
+                let (converted_unit, scale, offset) = unit.conversion();
+                if converted_unit != *value_unit {
+                    return Err(eyre!("Readings in {unit:?} don't convert to {value_unit:?} of {name}"));
+                }
+                Some((scale, offset))
+            }
+            (Some(unit), Metric::Custom { .. }) => {
+                return Err(eyre!("Readings in {unit:?} can't be converted for {name}, which has no unit"));
+            }
+        };
+        Ok((sensor_id, metric, scale))
+    }
+
+    /// Resolves the target again once its variables expand differently. Returns whether
+    /// there is a metric to push to.
+    async fn follow_variables(&mut self, client: &Addr<SensorVisionClient>) -> bool {
+        if !has_variables(&self.target) {
+            return true;
+        }
+        let name = match expand_variables(&self.target) {
+            Ok(name) => name,
+            Err(err) => {
+                log::warn!("Failed to expand {}: {err}", self.target);
+                return !self.stale;
+            }
+        };
+        let retry_stale = self.stale && self.checked_at.elapsed() >= VARIABLES_TTL;
+        if name == self.name && !retry_stale {
+            return !self.stale;
+        }
+
+        self.checked_at = Instant::now();
+        match Self::lookup(client, &name, self.conversion).await {
+            Ok((sensor_id, metric, scale)) => {
+                log::info!("{} now goes to {name}", self.target);
+                self.sensor_id = sensor_id;
+                self.metric = metric;
+                self.scale = scale;
+                self.stale = false;
+                self.last_pushed = None;
+            }
+            Err(err) => {
+                // Retried quietly until the metric shows up
+                if name != self.name {
+                    log::warn!("{} expands to {name} now, which doesn't resolve: {err}", self.target);
+                }
+                self.stale = true;
+            }
+        }
+        self.name = name;
+        !self.stale
+    }
+
+    /// Pushed as the current value once converted, unless the throttling of the target drops it
+    pub async fn push(&mut self, client: &Addr<SensorVisionClient>, value: MetricValue) {
+        if !self.follow_variables(client).await {
+            return;
+        }
+        let value = self.convert(value);
+        if let Some(counter) = self.suppressed_by(&value) {
+            counter.fetch_add(1, Ordering::Relaxed);
+            log::trace!("Not pushing {value} to {}", self.metric.name());
+            return;
+        }
+        client.do_send(PushValue {
+            sensor_id: self.sensor_id,
+            metric_id: *self.metric.metric_id(),
+            value: value.clone(),
+            timestamp: None,
+        });
+        self.last_pushed = Some((value, Instant::now()));
+    }
+
+    /// Numeric readings taken to the unit of the metric and rounded, others are left as they are
+    fn convert(&self, value: MetricValue) -> MetricValue {
+        if self.scale.is_none() && self.conversion.precision.is_none() {
+            return value;
+        }
+        let raw = match value {
+            MetricValue::Integer(raw) => raw as f64,
+            MetricValue::Double(raw) => raw,
+            _ => return value,
+        };
+        let mut converted = match self.scale {
+            Some((scale, offset)) => raw * scale + offset,
+            None => raw,
+        };
+        if let Some(precision) = self.conversion.precision {
+            let factor = 10_f64.powi(precision.into());
+            converted = (converted * factor).round() / factor;
+        }
+        self.metric.numeric_value(converted)
+    }
+
+    /// Counter of the filter dropping the reading, if any
+    fn suppressed_by(&self, value: &MetricValue) -> Option<&'static AtomicU64> {
+        let (last_value, pushed_at) = self.last_pushed.as_ref()?;
+        if pushed_at.elapsed() < self.throttle.min_interval() {
+            return Some(&SUPPRESSED_BY_MIN_INTERVAL);
+        }
+        let deadband = self.throttle.deadband?;
+        let unchanged = match (last_value, value) {
+            (
+                MetricValue::Integer(_) | MetricValue::Double(_),
+                MetricValue::Integer(_) | MetricValue::Double(_),
+            ) => last_value
+                .as_f64()
+                .zip(value.as_f64())
+                .is_some_and(|(last, new)| (new - last).abs() <= deadband),
+            _ => last_value == value,
+        };
+        unchanged.then_some(&SUPPRESSED_BY_DEADBAND)
+    }
+}
+
+/// Expanded values are kept this long, and unresolved targets retried as often
+const VARIABLES_TTL: Duration = Duration::from_secs(10);
+
+/// Placeholders target names may contain, so that one config fits a whole fleet
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum NameVariable {
+    /// Name of the machine
+    Hostname,
+    /// Network interface of the default route
+    Interface,
+    /// Local date as `YYYY-MM-DD`
+    Date,
+}
+
+impl FromStr for NameVariable {
+    type Err = eyre::Report;
+
+    fn from_str(name: &str) -> Result<Self> {
+        match name {
+            "hostname" => Ok(Self::Hostname),
+            "interface" => Ok(Self::Interface),
+            "date" => Ok(Self::Date),
+            _ => Err(eyre!("Unknown variable {{{name}}}, expected hostname, interface or date")),
+        }
+    }
+}
+
+impl NameVariable {
+    /// The current value, looked up again once the last one is older than [`VARIABLES_TTL`]
+    pub fn value(&self) -> Result<String> {
+        static VALUES: OnceLock<Mutex<HashMap<NameVariable, (String, Instant)>>> = OnceLock::new();
+        let mut values = lock_recovering(VALUES.get_or_init(Mutex::default));
+        if let Some((value, looked_up_at)) = values.get(self)
+            && looked_up_at.elapsed() < VARIABLES_TTL
+        {
+            return Ok(value.clone());
+        }
+        let value = match self {
+            Self::Hostname => hostname()?,
+            Self::Interface => default_interface()?,
+            Self::Date => Local::now().format("%Y-%m-%d").to_string(),
+        };
+        values.insert(*self, (value.clone(), Instant::now()));
+        Ok(value)
+    }
+}
+
+fn hostname() -> Result<String> {
+    let from_file = |path| {
+        std::fs::read_to_string(path)
+            .ok()
+            .map(|hostname| hostname.trim().to_owned())
+            .filter(|hostname| !hostname.is_empty())
+    };
+    std::env::var("COMPUTERNAME")
+        .ok()
+        .or_else(|| from_file("/proc/sys/kernel/hostname"))
+        .or_else(|| from_file("/etc/hostname"))
+        .ok_or_else(|| eyre!("Couldn't tell the hostname"))
+}
+
+/// Taken from the routing table of Linux
+fn default_interface() -> Result<String> {
+    let routes = std::fs::read_to_string("/proc/net/route")
+        .map_err(|err| eyre!("Couldn't read the routing table: {err}"))?;
+    routes
+        .lines()
+        .skip(1)
+        .map(|route| route.split_whitespace().collect::<Vec<_>>())
+        .find(|fields| fields.get(1) == Some(&"00000000"))
+        .and_then(|fields| fields.first().map(|interface| interface.to_string()))
+        .ok_or_else(|| eyre!("There is no default route to tell the interface from"))
+}
+
+fn has_variables(name: &str) -> bool {
+    name.contains('{')
+}
+
+/// `name` with its `{hostname}`, `{interface}` and `{date}` placeholders replaced
+pub fn expand_variables(name: &str) -> Result<String> {
+    let mut expanded = String::new();
+    let mut rest = name;
+    while let Some(start) = rest.find('{') {
+        let end = rest[start..]
+            .find('}')
+            .map(|end| start + end)
+            .ok_or_else(|| eyre!("Unclosed placeholder in '{name}'"))?;
+        let variable: NameVariable = rest[start + 1..end].trim().parse()?;
+        expanded.push_str(&rest[..start]);
+        expanded.push_str(&variable.value()?);
+        rest = &rest[end + 1..];
+    }
+    expanded.push_str(rest);
+    Ok(expanded)
+}
+
+/// The JSONPath subset values are picked with: `$` followed by `.key`, `['key']` and `[index]`,
+/// negative indices counting from the end
+#[derive(Debug, Clone, PartialEq)]
+pub struct JsonPath {
+    path: String,
+    segments: Vec<Segment>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Segment {
+    Key(String),
+    Index(i64),
+}
+
+impl Display for JsonPath {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.path)
+    }
+}
+
+impl FromStr for JsonPath {
+    type Err = eyre::Report;
+
+    fn from_str(path: &str) -> Result<Self> {
+        let invalid = || eyre!("Invalid JSONPath '{path}', expected e.g. $.items[0]['name']");
+        let mut rest = path.trim().strip_prefix('$').ok_or_else(invalid)?;
+        let mut segments = Vec::new();
+        while !rest.is_empty() {
+            if let Some(after) = rest.strip_prefix('.') {
+                let end = after.find(['.', '[']).unwrap_or(after.len());
+                if end == 0 {
+                    return Err(invalid());
+                }
+                segments.push(Segment::Key(after[..end].to_owned()));
+                rest = &after[end..];
+            } else if let Some(after) = rest.strip_prefix('[') {
+                let end = match after.chars().next() {
+                    Some(quote @ ('\'' | '"')) => {
+                        let closing = after[1..].find(quote).ok_or_else(invalid)? + 1;
+                        segments.push(Segment::Key(after[1..closing].to_owned()));
+                        closing + 1
+                    }
+                    _ => {
+                        let closing = after.find(']').ok_or_else(invalid)?;
+                        let index = after[..closing].trim().parse().map_err(|_| invalid())?;
+                        segments.push(Segment::Index(index));
+                        closing
+                    }
+                };
+                rest = after[end..].strip_prefix(']').ok_or_else(invalid)?;
+            } else {
+                return Err(invalid());
+            }
+        }
+        Ok(Self {
+            path: path.trim().to_owned(),
+            segments,
+        })
+    }
+}
+
+impl JsonPath {
+    pub fn select<'a>(&self, value: &'a Value) -> Option<&'a Value> {
+        self.segments
+            .iter()
+            .try_fold(value, |value, segment| match segment {
+                Segment::Key(key) => value.get(key),
+                Segment::Index(index) => {
+                    let array = value.as_array()?;
+                    let index = if *index < 0 {
+                        array.len().checked_sub(index.unsigned_abs() as usize)?
+                    } else {
+                        *index as usize
+                    };
+                    array.get(index)
+                }
+            })
+    }
+}
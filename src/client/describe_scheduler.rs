@@ -0,0 +1,89 @@
+use std::collections::{HashMap, VecDeque};
+use std::time::{Duration, Instant};
+
+use crate::correlation::CorrelationId;
+use crate::model::{MetricId, SensorId};
+
+/// Describes answered later than this are asked for again if requested
+const IN_FLIGHT_TTL: Duration = Duration::from_secs(10);
+
+/// Paces the `MetricDescribe` requests, which the protocol takes one per metric.
+/// A sensor list makes for one per metric of the connector at once, and every
+/// `SensorMetricsUpdated` for all the metrics of the sensor, so the requests are queued,
+/// the ones already queued or waiting for their replies dropped, and sent out in batches.
+#[derive(Clone, Debug)]
+pub struct DescribeScheduler {
+    batch_size: usize,
+    interval: Duration,
+    queue: VecDeque<(SensorId, MetricId, Option<CorrelationId>)>,
+    /// Sent at, describes being answered are expected within [`IN_FLIGHT_TTL`]
+    in_flight: HashMap<(SensorId, MetricId), Instant>,
+}
+
+impl DescribeScheduler {
+    /// Sends at most `batch_size` describes every `interval`, `0` sends them all at once
+    pub fn new(batch_size: usize, interval: Duration) -> Self {
+        Self {
+            batch_size: if batch_size == 0 { usize::MAX } else { batch_size },
+            interval,
+            queue: VecDeque::new(),
+            in_flight: HashMap::new(),
+        }
+    }
+
+    pub fn interval(&self) -> Duration {
+        self.interval
+    }
+
+    /// Queues describes on behalf of the operation `correlation_id`.
+    /// Returns how many were queued, the rest are queued or in flight already.
+    pub fn enqueue(
+        &mut self,
+        sensor_id: SensorId,
+        metric_ids: impl IntoIterator<Item = MetricId>,
+        correlation_id: Option<CorrelationId>,
+        now: Instant,
+    ) -> usize {
+        self.in_flight
+            .retain(|_, sent_at| now.duration_since(*sent_at) < IN_FLIGHT_TTL);
+        let mut queued = 0;
+        for metric_id in metric_ids {
+            let key = (sensor_id, metric_id);
+            let is_queued = self
+                .queue
+                .iter()
+                .any(|(sens_id, met_id, _)| (*sens_id, *met_id) == key);
+            if is_queued || self.in_flight.contains_key(&key) {
+                continue;
+            }
+            self.queue.push_back((sensor_id, metric_id, correlation_id));
+            queued += 1;
+        }
+        queued
+    }
+
+    /// Takes the describes to send now, in the order they were queued
+    pub fn next_batch(&mut self, now: Instant) -> Vec<(SensorId, MetricId, Option<CorrelationId>)> {
+        let batch_size = self.batch_size.min(self.queue.len());
+        let batch: Vec<_> = self.queue.drain(..batch_size).collect();
+        for (sensor_id, metric_id, _) in &batch {
+            self.in_flight.insert((*sensor_id, *metric_id), now);
+        }
+        batch
+    }
+
+    /// A reply came, the metric may be described anew
+    pub fn described(&mut self, sensor_id: SensorId, metric_id: MetricId) {
+        self.in_flight.remove(&(sensor_id, metric_id));
+    }
+
+    /// Describes of a deleted sensor are pointless
+    pub fn forget_sensor(&mut self, sensor_id: SensorId) {
+        self.queue.retain(|(sens_id, _, _)| *sens_id != sensor_id);
+        self.in_flight.retain(|(sens_id, _), _| *sens_id != sensor_id);
+    }
+
+    pub fn is_idle(&self) -> bool {
+        self.queue.is_empty()
+    }
+}
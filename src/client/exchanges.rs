@@ -0,0 +1,152 @@
+use serde_json::{json, Value};
+
+use std::collections::{HashMap, VecDeque};
+use std::sync::{Mutex, OnceLock};
+use std::time::{Duration, Instant};
+
+use crate::logging;
+use crate::model::{SensorId, Timestamp};
+use crate::utils::lock_recovering;
+
+/// Exchanges kept per sensor, older ones are dropped
+const EXCHANGES_PER_SENSOR: usize = 8;
+
+/// A request published to the broker and the reply to it, if any arrived yet
+#[derive(Debug, Clone)]
+pub struct Exchange {
+    pub request_topic: String,
+    pub request: String,
+    pub sent_at: Timestamp,
+
+    pub reply_topic: Option<String>,
+    pub reply: Option<String>,
+    pub is_error: bool,
+    pub latency: Option<Duration>,
+}
+
+impl Exchange {
+    /// Payloads are embedded as JSON where they parse, so that they read well and copy as is,
+    /// redacted and cut like the logged ones
+    pub fn to_json(&self) -> Value {
+        let payload = |payload: &str| {
+            let mut value = serde_json::from_str(payload)
+                .unwrap_or_else(|_| Value::String(payload.to_owned()));
+            logging::sanitize_payload(&mut value);
+            value
+        };
+        json!({
+            "sent_at": self.sent_at.to_datetime().to_rfc3339(),
+            "request_topic": self.request_topic,
+            "request": payload(&self.request),
+            "reply_topic": self.reply_topic,
+            "reply": self.reply.as_deref().map(payload),
+            "error": self.is_error,
+            "latency_ms": self.latency.map(|latency| latency.as_millis() as u64),
+        })
+    }
+}
+
+/// Request awaiting a reply on either of its topics
+struct AwaitingReply {
+    request_id: u64,
+    sensor_id: Option<SensorId>,
+    reply_topics: [String; 2],
+    sent_at: Instant,
+}
+
+#[derive(Default)]
+struct Exchanges {
+    // Requests not tied to a sensor, e.g. listing the sensors, are kept under `None`
+    by_sensor: HashMap<Option<SensorId>, VecDeque<(u64, Exchange)>>,
+
+    // Replies carry no request id, so a reply answers the oldest request awaiting its topic,
+    // the same way the MQTT actor answers its requests
+    awaiting_reply: VecDeque<AwaitingReply>,
+}
+
+fn exchanges() -> &'static Mutex<Exchanges> {
+    static EXCHANGES: OnceLock<Mutex<Exchanges>> = OnceLock::new();
+    EXCHANGES.get_or_init(Mutex::default)
+}
+
+/// Records request `request_id` of the MQTT actor, whose reply is expected on either of the `reply_topics`
+pub fn request_sent(
+    request_id: u64,
+    sensor_id: Option<SensorId>,
+    topic: &str,
+    payload: &str,
+    reply_topics: [&str; 2],
+) {
+    let mut exchanges = lock_recovering(exchanges());
+    exchanges.awaiting_reply.push_back(AwaitingReply {
+        request_id,
+        sensor_id,
+        reply_topics: reply_topics.map(str::to_owned),
+        sent_at: Instant::now(),
+    });
+
+    let sensor_exchanges = exchanges.by_sensor.entry(sensor_id).or_default();
+    if sensor_exchanges.len() == EXCHANGES_PER_SENSOR {
+        sensor_exchanges.pop_front();
+    }
+    sensor_exchanges.push_back((
+        request_id,
+        Exchange {
+            request_topic: topic.to_owned(),
+            request: payload.to_owned(),
+            sent_at: Timestamp::now(),
+            reply_topic: None,
+            reply: None,
+            is_error: false,
+            latency: None,
+        },
+    ));
+}
+
+/// Completes the oldest exchange awaiting a reply on `topic`, if any
+pub fn reply_received(topic: &str, payload: &str, is_error: bool) -> Option<Exchange> {
+    let mut exchanges = lock_recovering(exchanges());
+    let index = exchanges
+        .awaiting_reply
+        .iter()
+        .position(|awaiting| awaiting.reply_topics.iter().any(|reply_topic| reply_topic == topic))?;
+    let awaiting = exchanges.awaiting_reply.remove(index)?;
+
+    let exchange = exchanges
+        .by_sensor
+        .get_mut(&awaiting.sensor_id)
+        .and_then(|sensor_exchanges| {
+            sensor_exchanges
+                .iter_mut()
+                .find(|(request_id, _)| *request_id == awaiting.request_id)
+        })
+        .map(|(_, exchange)| exchange)?;
+    exchange.reply_topic = Some(topic.to_owned());
+    exchange.reply = Some(payload.to_owned());
+    exchange.is_error = is_error;
+    exchange.latency = Some(awaiting.sent_at.elapsed());
+    Some(exchange.clone())
+}
+
+/// Stops awaiting the reply to request `request_id` once the MQTT actor gave up on it,
+/// so that a reply arriving later can't be taken for the one of a newer request
+pub fn request_expired(request_id: u64) {
+    lock_recovering(exchanges())
+        .awaiting_reply
+        .retain(|awaiting| awaiting.request_id != request_id);
+}
+
+/// The last exchanges concerning the sensor, most recent first
+pub fn of_sensor(sensor_id: Option<SensorId>) -> Vec<Exchange> {
+    lock_recovering(exchanges())
+        .by_sensor
+        .get(&sensor_id)
+        .map(|sensor_exchanges| {
+            sensor_exchanges
+                .iter()
+                .rev()
+                .map(|(_, exchange)| exchange.clone())
+                .collect()
+        })
+        .unwrap_or_default()
+}
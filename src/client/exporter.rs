@@ -0,0 +1,235 @@
+use actix::{Actor, Addr, AsyncContext, Context, Handler, Message, ResponseFuture, WrapFuture};
+
+use eyre::{Result, WrapErr};
+
+use futures::FutureExt;
+
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::time::Duration;
+
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::time::timeout;
+
+use crate::build_info;
+use crate::client::client::SensorVisionClient;
+use crate::client::collector;
+use crate::client::state::queries::GetStateSnapshot;
+use crate::client::state::{SensorStateEvent, SubscribeToStateEvents};
+use crate::model::{MetricId, SensorId, Timestamp};
+
+const METRICS_PATH: &str = "/metrics";
+const GAUGE_NAME: &str = "sensor_vision_metric_value";
+const SUPPRESSED_NAME: &str = "sensor_vision_suppressed_samples_total";
+const BUILD_INFO_NAME: &str = "sensor_vision_build_info";
+
+// Scrape requests are tiny, anything bigger isn't one
+const REQUEST_SIZE_LIMIT: usize = 8192;
+
+/// Reading the request and writing the response, each. Connections stalling longer are dropped,
+/// so that they don't pile up.
+const STREAM_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Serves the latest livedata value of every metric at `/metrics` in the Prometheus text format,
+/// one `sensor_vision_metric_value` gauge per sensor/metric pair. String values aren't exported.
+/// The readings the collectors' throttling dropped are counted by `sensor_vision_suppressed_samples_total`,
+/// the version running is labeled on `sensor_vision_build_info`.
+pub struct ExporterActor {
+    client: Addr<SensorVisionClient>,
+    listener: Option<TcpListener>,
+
+    latest: BTreeMap<(SensorId, MetricId), (f64, Timestamp)>,
+}
+
+#[derive(Message)]
+#[rtype(result = "String")]
+struct Scrape;
+
+impl ExporterActor {
+    /// Binds right away so that a bad address is reported before anything else starts
+    pub async fn bind(client: Addr<SensorVisionClient>, address: &str) -> Result<Self> {
+        let listener = TcpListener::bind(address)
+            .await
+            .wrap_err_with(|| format!("Failed to listen on {address}"))?;
+        log::info!("Exporting livedata at http://{address}{METRICS_PATH}");
+        Ok(Self {
+            client,
+            listener: Some(listener),
+            latest: BTreeMap::new(),
+        })
+    }
+}
+
+impl Actor for ExporterActor {
+    type Context = Context<Self>;
+
+    fn started(&mut self, ctx: &mut Self::Context) {
+        let client = self.client.clone();
+        let weak_this = ctx.address().downgrade().recipient();
+        ctx.spawn(
+            async move {
+                let _ = client.send(SubscribeToStateEvents(weak_this)).await;
+            }
+            .into_actor(self),
+        );
+
+        let Some(listener) = self.listener.take() else {
+            return;
+        };
+        let this = ctx.address();
+        actix::spawn(async move {
+            loop {
+                match listener.accept().await {
+                    Ok((stream, _)) => {
+                        actix::spawn(serve(stream, this.clone()));
+                    }
+                    Err(err) => log::warn!("Exporter failed to accept a connection: {err}"),
+                }
+            }
+        });
+    }
+}
+
+async fn serve(mut stream: TcpStream, exporter: Addr<ExporterActor>) {
+    let mut request = Vec::new();
+    let mut buffer = [0u8; 1024];
+    let read = async {
+        while !request.windows(4).any(|window| window == b"\r\n\r\n") {
+            match stream.read(&mut buffer).await {
+                Ok(0) | Err(_) => return false,
+                Ok(read) => request.extend_from_slice(&buffer[..read]),
+            }
+            if request.len() > REQUEST_SIZE_LIMIT {
+                return false;
+            }
+        }
+        true
+    };
+    match timeout(STREAM_TIMEOUT, read).await {
+        Ok(true) => {}
+        Ok(false) => return,
+        Err(_) => {
+            log::debug!("Exporter timed out reading a request");
+            return;
+        }
+    }
+
+    let request = String::from_utf8_lossy(&request);
+    let mut request_line = request.lines().next().unwrap_or_default().split_whitespace();
+    let response = match (request_line.next(), request_line.next()) {
+        (Some("GET"), Some(METRICS_PATH)) => match exporter.send(Scrape).await {
+            Ok(body) => format!(
+                "HTTP/1.1 200 OK\r\nContent-Type: text/plain; version=0.0.4\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{body}",
+                body.len()
+            ),
+            Err(_) => "HTTP/1.1 503 Service Unavailable\r\nContent-Length: 0\r\nConnection: close\r\n\r\n"
+                .to_owned(),
+        },
+        _ => "HTTP/1.1 404 Not Found\r\nContent-Length: 0\r\nConnection: close\r\n\r\n".to_owned(),
+    };
+
+    match timeout(STREAM_TIMEOUT, stream.write_all(response.as_bytes())).await {
+        Ok(Ok(())) => {}
+        Ok(Err(err)) => log::debug!("Exporter failed to respond: {err}"),
+        Err(_) => log::debug!("Exporter timed out responding"),
+    }
+}
+
+fn format_value(value: f64) -> String {
+    match value {
+        f64::INFINITY => "+Inf".to_owned(),
+        f64::NEG_INFINITY => "-Inf".to_owned(),
+        value => value.to_string(),
+    }
+}
+
+/// Label values may contain anything the user typed in
+fn escape_label(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+impl Handler<SensorStateEvent> for ExporterActor {
+    type Result = ();
+
+    fn handle(&mut self, event: SensorStateEvent, _: &mut Self::Context) -> Self::Result {
+        use SensorStateEvent::*;
+        match event {
+            Livedata {
+                sensor_id,
+                metric_id,
+                value,
+                timestamp,
+                ..
+            } => {
+                if let Some(value) = value.as_f64() {
+                    self.latest.insert((sensor_id, metric_id), (value, timestamp));
+                }
+            }
+            SensorDeleted { sensor_id } => {
+                self.latest.retain(|(sens_id, _), _| *sens_id != sensor_id);
+            }
+            MetricDeleted {
+                sensor_id,
+                metric_id,
+            } => {
+                self.latest.remove(&(sensor_id, metric_id));
+            }
+            _ => {}
+        }
+    }
+}
+
+impl Handler<Scrape> for ExporterActor {
+    type Result = ResponseFuture<String>;
+
+    fn handle(&mut self, _: Scrape, _: &mut Self::Context) -> Self::Result {
+        let latest = self.latest.clone();
+        let client = self.client.clone();
+
+        async move {
+            // Names are looked up on every scrape as sensors and metrics can be renamed
+            let sensors = client.send(GetStateSnapshot).await.ok().and_then(Result::ok).unwrap_or_default();
+
+            let mut body = String::new();
+            let _ = writeln!(body, "# HELP {GAUGE_NAME} Latest livedata value of the metric");
+            let _ = writeln!(body, "# TYPE {GAUGE_NAME} gauge");
+            for ((sensor_id, metric_id), (value, timestamp)) in latest {
+                let sensor = sensors.get(&sensor_id);
+                let sensor_name = sensor.map(|sensor| sensor.name.as_str()).unwrap_or_default();
+                let metric_name = sensor
+                    .and_then(|sensor| sensor.metrics.get(&metric_id))
+                    .map(|metric| metric.name().as_str())
+                    .unwrap_or_default();
+                let _ = writeln!(
+                    body,
+                    "{GAUGE_NAME}{{sensor_id=\"{sensor_id}\",metric_id=\"{metric_id}\",sensor=\"{}\",metric=\"{}\"}} {} {}",
+                    escape_label(sensor_name),
+                    escape_label(metric_name),
+                    format_value(value),
+                    timestamp.as_millis(),
+                );
+            }
+
+            let suppressed = collector::suppressed_samples();
+            let _ = writeln!(body, "# HELP {SUPPRESSED_NAME} Collector readings dropped by the throttling");
+            let _ = writeln!(body, "# TYPE {SUPPRESSED_NAME} counter");
+            let _ = writeln!(body, "{SUPPRESSED_NAME}{{reason=\"deadband\"}} {}", suppressed.deadband);
+            let _ = writeln!(body, "{SUPPRESSED_NAME}{{reason=\"min_interval\"}} {}", suppressed.min_interval);
+
+            let _ = writeln!(body, "# HELP {BUILD_INFO_NAME} Version of the exporting SensorVision, always 1");
+            let _ = writeln!(body, "# TYPE {BUILD_INFO_NAME} gauge");
+            let _ = writeln!(
+                body,
+                "{BUILD_INFO_NAME}{{version=\"{}\",git_hash=\"{}\"}} 1",
+                build_info::VERSION,
+                build_info::GIT_HASH,
+            );
+            body
+        }
+        .boxed_local()
+    }
+}
@@ -0,0 +1,369 @@
+use actix::{Actor, Addr, Context, Handler, MailboxError};
+
+use eyre::{Result, WrapErr};
+
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+use tokio::net::TcpListener;
+use tokio::sync::{broadcast, mpsc};
+use tokio_stream::wrappers::{ReceiverStream, TcpListenerStream};
+
+use tonic::transport::Server;
+use tonic::{Request, Response, Status};
+
+use uuid::Uuid;
+
+use crate::client::client::SensorVisionClient;
+use crate::client::client_queries::{
+    CreateMetrics, CreateSensor, DeleteMetric, DeleteSensor, PushValue, UpdateMetric, UpdateSensor,
+};
+use crate::client::state::queries::GetStateSnapshot;
+use crate::client::state::{SensorStateEvent, SubscribeToStateEvents};
+use crate::model::protocol::MetricValue;
+use crate::model::sensor::{Metric, Sensor, SensorState};
+use crate::model::{SensorId, Timestamp};
+
+pub mod proto {
+    tonic::include_proto!("sensor_vision.v1");
+}
+
+use proto::sensor_management_server::{SensorManagement, SensorManagementServer};
+use proto::{metric, value};
+
+/// Livedata events waiting for the slowest stream before it misses some
+const LIVEDATA_CAPACITY: usize = 1024;
+
+/// Serves the `SensorManagement` service of `proto/sensor_vision.proto` for machine to machine
+/// integrations. Binds right away so that a bad address is reported before anything else starts.
+pub async fn serve(client: Addr<SensorVisionClient>, address: &str) -> Result<()> {
+    let listener = TcpListener::bind(address)
+        .await
+        .wrap_err_with(|| format!("Failed to listen on {address}"))?;
+
+    let (livedata, _) = broadcast::channel(LIVEDATA_CAPACITY);
+    let forwarder = LivedataForwarder {
+        livedata: livedata.clone(),
+    }
+    .start();
+    client
+        .send(SubscribeToStateEvents(forwarder.downgrade().recipient()))
+        .await?;
+
+    let service = GrpcService {
+        client,
+        livedata,
+        _forwarder: forwarder,
+    };
+    log::info!("Serving gRPC at {address}");
+    actix::spawn(async move {
+        let served = Server::builder()
+            .add_service(SensorManagementServer::new(service))
+            .serve_with_incoming(TcpListenerStream::new(listener))
+            .await;
+        if let Err(err) = served {
+            log::error!("gRPC server failed: {err}");
+        }
+    });
+    Ok(())
+}
+
+/// Hands the livedata over from the state to the streams, which run outside of actix
+struct LivedataForwarder {
+    livedata: broadcast::Sender<proto::LivedataEvent>,
+}
+
+impl Actor for LivedataForwarder {
+    type Context = Context<Self>;
+}
+
+impl Handler<SensorStateEvent> for LivedataForwarder {
+    type Result = ();
+
+    fn handle(&mut self, event: SensorStateEvent, _: &mut Self::Context) -> Self::Result {
+        let SensorStateEvent::Livedata {
+            sensor_id,
+            metric_id,
+            value,
+            timestamp,
+            ..
+        } = event
+        else {
+            return;
+        };
+        // Nobody streaming is no error
+        let _ = self.livedata.send(proto::LivedataEvent {
+            sensor_id: sensor_id.to_string(),
+            metric_id: metric_id.to_string(),
+            value: Some(value_to_proto(value)),
+            timestamp: timestamp.as_millis(),
+        });
+    }
+}
+
+struct GrpcService {
+    client: Addr<SensorVisionClient>,
+    livedata: broadcast::Sender<proto::LivedataEvent>,
+    // State events are sent to weak recipients only
+    _forwarder: Addr<LivedataForwarder>,
+}
+
+#[tonic::async_trait]
+impl SensorManagement for GrpcService {
+    async fn list_sensors(
+        &self,
+        _: Request<proto::ListSensorsRequest>,
+    ) -> Result<Response<proto::ListSensorsResponse>, Status> {
+        let sensors = self
+            .client
+            .send(GetStateSnapshot)
+            .await
+            .map_err(unavailable)?
+            .map_err(|err| Status::internal(format!("{err:#}")))?;
+        Ok(Response::new(proto::ListSensorsResponse {
+            sensors: sensors.values().map(sensor_to_proto).collect(),
+        }))
+    }
+
+    async fn create_sensor(
+        &self,
+        request: Request<proto::CreateSensorRequest>,
+    ) -> Result<Response<proto::Accepted>, Status> {
+        let proto::CreateSensorRequest { name } = request.into_inner();
+        accepted(self.client.send(CreateSensor { name }).await)
+    }
+
+    async fn update_sensor(
+        &self,
+        request: Request<proto::UpdateSensorRequest>,
+    ) -> Result<Response<proto::Accepted>, Status> {
+        let proto::UpdateSensorRequest {
+            sensor_id,
+            name,
+            enabled,
+        } = request.into_inner();
+        let update_sensor = UpdateSensor {
+            sensor_id: parse_id(&sensor_id)?,
+            name,
+            state: enabled.map(|enabled| {
+                if enabled {
+                    SensorState::Enabled
+                } else {
+                    SensorState::Disabled
+                }
+            }),
+        };
+        accepted(self.client.send(update_sensor).await)
+    }
+
+    async fn delete_sensor(
+        &self,
+        request: Request<proto::DeleteSensorRequest>,
+    ) -> Result<Response<proto::Accepted>, Status> {
+        let sensor_id = parse_id(&request.into_inner().sensor_id)?;
+        accepted(self.client.send(DeleteSensor { sensor_id }).await)
+    }
+
+    async fn create_metrics(
+        &self,
+        request: Request<proto::CreateMetricsRequest>,
+    ) -> Result<Response<proto::Accepted>, Status> {
+        let proto::CreateMetricsRequest { sensor_id, metrics } = request.into_inner();
+        let create_metrics = CreateMetrics {
+            sensor_id: parse_id(&sensor_id)?,
+            metrics: metrics
+                .into_iter()
+                .map(metric_from_proto)
+                .collect::<Result<_, _>>()?,
+        };
+        accepted(self.client.send(create_metrics).await)
+    }
+
+    async fn update_metric(
+        &self,
+        request: Request<proto::UpdateMetricRequest>,
+    ) -> Result<Response<proto::Accepted>, Status> {
+        let proto::UpdateMetricRequest {
+            sensor_id,
+            metric_id,
+            name,
+            value_annotation,
+        } = request.into_inner();
+        let update_metric = UpdateMetric {
+            sensor_id: parse_id(&sensor_id)?,
+            metric_id: parse_id(&metric_id)?,
+            name,
+            value_annotation,
+        };
+        accepted(self.client.send(update_metric).await)
+    }
+
+    async fn delete_metric(
+        &self,
+        request: Request<proto::DeleteMetricRequest>,
+    ) -> Result<Response<proto::Accepted>, Status> {
+        let proto::DeleteMetricRequest {
+            sensor_id,
+            metric_id,
+        } = request.into_inner();
+        let delete_metric = DeleteMetric {
+            sensor_id: parse_id(&sensor_id)?,
+            metric_id: parse_id(&metric_id)?,
+        };
+        accepted(self.client.send(delete_metric).await)
+    }
+
+    async fn push_value(
+        &self,
+        request: Request<proto::PushValueRequest>,
+    ) -> Result<Response<proto::Accepted>, Status> {
+        let proto::PushValueRequest {
+            sensor_id,
+            metric_id,
+            value,
+            timestamp,
+        } = request.into_inner();
+        let push_value = PushValue {
+            sensor_id: parse_id(&sensor_id)?,
+            metric_id: parse_id(&metric_id)?,
+            value: value
+                .and_then(|value| value.value)
+                .map(value_from_proto)
+                .ok_or_else(|| Status::invalid_argument("value is required"))?,
+            timestamp: timestamp.map(Timestamp::from_millis),
+        };
+        accepted(self.client.send(push_value).await)
+    }
+
+    type LivedataStream = ReceiverStream<Result<proto::LivedataEvent, Status>>;
+
+    async fn livedata(
+        &self,
+        request: Request<proto::LivedataRequest>,
+    ) -> Result<Response<Self::LivedataStream>, Status> {
+        let sensor_id = request
+            .into_inner()
+            .sensor_id
+            .map(|sensor_id| parse_id::<SensorId>(&sensor_id))
+            .transpose()?
+            .map(|sensor_id| sensor_id.to_string());
+
+        let mut livedata = self.livedata.subscribe();
+        let (sender, receiver) = mpsc::channel(LIVEDATA_CAPACITY);
+        tokio::spawn(async move {
+            loop {
+                let event = match livedata.recv().await {
+                    Ok(event) => event,
+                    Err(broadcast::error::RecvError::Lagged(missed)) => {
+                        log::debug!("Livedata stream falls behind, {missed} value(s) dropped");
+                        continue;
+                    }
+                    Err(broadcast::error::RecvError::Closed) => return,
+                };
+                if sensor_id.as_ref().is_some_and(|sensor_id| *sensor_id != event.sensor_id) {
+                    continue;
+                }
+                // The client has gone
+                if sender.send(Ok(event)).await.is_err() {
+                    return;
+                }
+            }
+        });
+        Ok(Response::new(ReceiverStream::new(receiver)))
+    }
+}
+
+fn accepted(sent: Result<Result<()>, MailboxError>) -> Result<Response<proto::Accepted>, Status> {
+    match sent {
+        Ok(Ok(())) => Ok(Response::new(proto::Accepted {})),
+        Ok(Err(err)) => Err(Status::internal(format!("{err:#}"))),
+        Err(err) => Err(unavailable(err)),
+    }
+}
+
+fn unavailable(err: MailboxError) -> Status {
+    Status::unavailable(err.to_string())
+}
+
+fn parse_id<Id: From<Uuid>>(id: &str) -> Result<Id, Status> {
+    Uuid::parse_str(id)
+        .map(Id::from)
+        .map_err(|_| Status::invalid_argument(format!("Malformed id '{id}'")))
+}
+
+/// Units and types go by the names the API gives them
+fn api_name<T: Serialize>(value: &T) -> String {
+    serde_json::to_value(value)
+        .ok()
+        .and_then(|name| name.as_str().map(str::to_owned))
+        .unwrap_or_default()
+}
+
+fn from_api_name<T: DeserializeOwned>(name: &str) -> Result<T, Status> {
+    serde_json::from_value(serde_json::Value::String(name.to_owned()))
+        .map_err(|_| Status::invalid_argument(format!("Unknown unit or type '{name}'")))
+}
+
+fn sensor_to_proto(sensor: &Sensor<Metric>) -> proto::Sensor {
+    proto::Sensor {
+        sensor_id: sensor.sensor_id.to_string(),
+        name: sensor.name.clone(),
+        enabled: sensor.state == SensorState::Enabled,
+        metrics: sensor.metrics.iter().map(metric_to_proto).collect(),
+    }
+}
+
+fn metric_to_proto(metric: &Metric) -> proto::Metric {
+    let kind = match metric {
+        Metric::Predefined { value_unit, .. } => metric::Kind::ValueUnit(api_name(value_unit)),
+        Metric::Custom {
+            value_type,
+            value_annotation,
+            ..
+        } => metric::Kind::Custom(proto::CustomKind {
+            value_type: api_name(value_type),
+            value_annotation: value_annotation.clone(),
+        }),
+    };
+    proto::Metric {
+        metric_id: metric.metric_id().to_string(),
+        name: metric.name().clone(),
+        kind: Some(kind),
+    }
+}
+
+fn metric_from_proto(metric: proto::Metric) -> Result<Metric, Status> {
+    match metric.kind {
+        Some(metric::Kind::ValueUnit(value_unit)) => {
+            Ok(Metric::predefined(metric.name, from_api_name(&value_unit)?))
+        }
+        Some(metric::Kind::Custom(custom)) => Ok(Metric::custom(
+            metric.name,
+            from_api_name(&custom.value_type)?,
+            custom.value_annotation,
+        )),
+        None => Err(Status::invalid_argument(format!(
+            "Metric {} has neither a unit nor a type",
+            metric.name
+        ))),
+    }
+}
+
+fn value_to_proto(value: MetricValue) -> proto::Value {
+    let value = match value {
+        MetricValue::Integer(value) => value::Value::Integer(value),
+        MetricValue::Double(value) => value::Value::Double(value),
+        MetricValue::String(value) => value::Value::String(value),
+        MetricValue::Boolean(value) => value::Value::Boolean(value),
+    };
+    proto::Value { value: Some(value) }
+}
+
+fn value_from_proto(value: value::Value) -> MetricValue {
+    match value {
+        value::Value::Integer(value) => MetricValue::Integer(value),
+        value::Value::Double(value) => MetricValue::Double(value),
+        value::Value::String(value) => MetricValue::String(value),
+        value::Value::Boolean(value) => MetricValue::Boolean(value),
+    }
+}
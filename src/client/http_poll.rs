@@ -0,0 +1,132 @@
+use actix::Addr;
+
+use eyre::{eyre, Result, WrapErr};
+
+use serde_json::Value;
+
+use std::collections::BTreeMap;
+use std::time::Duration;
+
+use url::Url;
+
+use crate::client::client::SensorVisionClient;
+use crate::client::collector::{CollectorTarget, JsonPath};
+use crate::config::{ConversionConfig, HttpPollConfig, ThrottleConfig};
+use crate::http;
+use crate::model::protocol::MetricValue;
+use crate::model::sensor::{Metric, ValueType};
+
+/// Value pushed for a picked JSON scalar, numbers are taken as they are unless the metric is
+/// a string one, strings are parsed like typed in values
+fn metric_value(metric: &Metric, value: &Value) -> Result<MetricValue> {
+    let takes_strings = matches!(
+        metric,
+        Metric::Custom {
+            value_type: ValueType::String,
+            ..
+        }
+    );
+    match value {
+        Value::Number(number) if !takes_strings => number
+            .as_f64()
+            .map(|number| metric.numeric_value(number))
+            .ok_or_else(|| eyre!("Number {number} is out of range")),
+        Value::String(text) => metric.parse_value(text),
+        Value::Number(_) | Value::Bool(_) => metric.parse_value(&value.to_string()),
+        _ => Err(eyre!("Expected a number, a string or a boolean, got {value}")),
+    }
+}
+
+struct PolledValue {
+    path: JsonPath,
+    target: CollectorTarget,
+}
+
+/// Resolves the targets of every endpoint against the loaded inventory and keeps polling
+/// the endpoints in the background
+pub async fn start_collectors(
+    client: &Addr<SensorVisionClient>,
+    endpoints: &[HttpPollConfig],
+    throttles: &BTreeMap<String, ThrottleConfig>,
+    conversions: &BTreeMap<String, ConversionConfig>,
+) -> Result<()> {
+    for endpoint in endpoints {
+        let url = http::parse_url(&endpoint.url).wrap_err("Endpoint")?;
+        endpoint.validate()?;
+        let mut values = Vec::with_capacity(endpoint.map.len());
+        for (path, target) in &endpoint.map {
+            values.push(PolledValue {
+                path: path.parse()?,
+                target: CollectorTarget::resolve(
+                    client,
+                    target,
+                    ThrottleConfig::of_target(throttles, target, endpoint.throttle),
+                    ConversionConfig::of_target(conversions, target, endpoint.conversion),
+                )
+                .await
+                .wrap_err_with(|| format!("Endpoint {}", endpoint.url))?,
+            });
+        }
+        log::info!(
+            "Polling {} value(s) from {} every {:?}",
+            values.len(),
+            endpoint.url,
+            endpoint.interval()
+        );
+        actix::spawn(poll(client.clone(), url, endpoint.clone(), values));
+    }
+    Ok(())
+}
+
+async fn poll(
+    client: Addr<SensorVisionClient>,
+    url: Url,
+    endpoint: HttpPollConfig,
+    mut values: Vec<PolledValue>,
+) {
+    let mut failures = 0;
+    loop {
+        match fetch(&url, &endpoint).await {
+            Ok(json) => {
+                failures = 0;
+                for PolledValue { path, target } in &mut values {
+                    let value = path
+                        .select(&json)
+                        .ok_or_else(|| eyre!("Nothing at {path}"))
+                        .and_then(|value| metric_value(&target.metric, value));
+                    match value {
+                        Ok(value) => target.push(&client, value).await,
+                        Err(err) => log::warn!("{} {path}: {err}", endpoint.url),
+                    }
+                }
+            }
+            Err(err) => {
+                failures += 1;
+                log::warn!(
+                    "Failed to poll {}: {err}, retrying in {:?}",
+                    endpoint.url,
+                    retry_delay(&endpoint, failures)
+                );
+            }
+        }
+        tokio::time::sleep(retry_delay(&endpoint, failures)).await;
+    }
+}
+
+/// The interval, doubled on every failure in a row up to the max backoff
+fn retry_delay(endpoint: &HttpPollConfig, failures: u32) -> Duration {
+    endpoint
+        .interval()
+        .saturating_mul(2_u32.saturating_pow(failures))
+        .min(endpoint.max_backoff())
+}
+
+async fn fetch(url: &Url, endpoint: &HttpPollConfig) -> Result<Value> {
+    let headers: Vec<(&str, &str)> = endpoint
+        .headers
+        .iter()
+        .map(|(name, value)| (name.as_str(), value.as_str()))
+        .collect();
+    let body = http::get(url, &headers).await?;
+    serde_json::from_slice(&body).wrap_err("Response isn't JSON")
+}
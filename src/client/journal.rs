@@ -0,0 +1,109 @@
+//! Append-only journal of the MQTT messages the listeners receive, one JSON object per line.
+//!
+//! Recording is off unless [`open`] is called. A journal is fed back with [`replay`] into a
+//! state of its own, without a broker, which reproduces whatever the state made of the
+//! messages back then.
+
+use actix::Addr;
+
+use eyre::{Result, WrapErr};
+
+use serde::{Deserialize, Serialize};
+
+use std::fs::{self, File, OpenOptions};
+use std::io::Write;
+use std::path::Path;
+use std::sync::{Mutex, OnceLock};
+
+use crate::client::mqtt::{MqttEvent, MqttMessage};
+use crate::client::state::SensorsStateActor;
+use crate::model::Timestamp;
+use crate::utils::lock_recovering;
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct JournalEntry {
+    pub received_at: Timestamp,
+    pub topic: String,
+    /// As received, JSON or not
+    pub payload: String,
+}
+
+impl From<JournalEntry> for MqttMessage {
+    fn from(entry: JournalEntry) -> Self {
+        Self {
+            topic: entry.topic,
+            message: entry.payload,
+            correlation_id: None,
+        }
+    }
+}
+
+fn journal() -> &'static OnceLock<Mutex<File>> {
+    static JOURNAL: OnceLock<Mutex<File>> = OnceLock::new();
+    &JOURNAL
+}
+
+/// Starts recording to `path`, appending to what it holds already
+pub fn open(path: &Path) -> Result<()> {
+    let file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .wrap_err_with(|| format!("Failed to open the journal {}", path.display()))?;
+    // A second call keeps recording to the first file
+    let _ = journal().set(Mutex::new(file));
+    Ok(())
+}
+
+/// Appends the message if recording, a failed write is logged and the message dropped
+pub fn record(message: &MqttMessage) {
+    let Some(file) = journal().get() else {
+        return;
+    };
+    let entry = JournalEntry {
+        received_at: Timestamp::now(),
+        topic: message.topic.clone(),
+        payload: message.message.clone(),
+    };
+    let line = match serde_json::to_string(&entry) {
+        Ok(line) => line + "\n",
+        Err(err) => {
+            log::warn!("Failed to journal a message on {}: {err}", message.topic);
+            return;
+        }
+    };
+    // A single write per line, so that lines of concurrent listeners don't interleave
+    if let Err(err) = lock_recovering(file).write_all(line.as_bytes()) {
+        log::warn!("Failed to journal a message on {}: {err}", message.topic);
+    }
+}
+
+/// Entries of the journal in the order they were recorded, blank lines are skipped
+pub fn read(path: &Path) -> Result<Vec<JournalEntry>> {
+    let contents = fs::read_to_string(path)
+        .wrap_err_with(|| format!("Failed to read the journal {}", path.display()))?;
+    contents
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty())
+        .map(|(index, line)| {
+            serde_json::from_str(line)
+                .wrap_err_with(|| format!("{}:{}: not a journal entry", path.display(), index + 1))
+        })
+        .collect()
+}
+
+/// Processes the entries one after another, as fast as the state takes them.
+/// Livedata counts as received when it's replayed rather than when it was recorded.
+pub async fn replay(
+    state: &Addr<SensorsStateActor>,
+    entries: impl IntoIterator<Item = JournalEntry>,
+) -> Result<usize> {
+    let mut replayed = 0;
+    for entry in entries {
+        log::debug!("Replaying {} received at {}", entry.topic, entry.received_at.to_datetime());
+        state.send(MqttEvent(entry.into())).await?;
+        replayed += 1;
+    }
+    Ok(replayed)
+}
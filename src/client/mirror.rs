@@ -0,0 +1,240 @@
+use actix::{Actor, ActorFutureExt, Addr, AsyncContext, Context, Handler, WrapFuture};
+
+use std::collections::{HashSet, VecDeque};
+use std::time::Duration;
+
+use crate::client::client::SensorVisionClient;
+use crate::client::client_queries::{CreateMetrics, CreateSensor, PushValue};
+use crate::client::state::queries::GetStateSnapshot;
+use crate::client::state::{SensorStateEvent, SubscribeToStateEvents};
+use crate::model::protocol::MetricValue;
+use crate::model::sensor::Metric;
+use crate::model::Timestamp;
+use crate::telemetry;
+
+const PENDING_VALUES_LIMIT: usize = 1000;
+const FLUSH_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Republishes livedata of the `source` connector to the equivalently named
+/// sensors/metrics of the `target` connector, creating missing ones on the way.
+pub struct MirrorActor {
+    source: Addr<SensorVisionClient>,
+    target: Addr<SensorVisionClient>,
+
+    // Values waiting for their sensor/metric to show up on the target connector
+    pending: VecDeque<MirroredValue>,
+    flushing: bool,
+
+    // Sensor names and (sensor name, metric name) pairs already requested to be created
+    requested_sensors: HashSet<String>,
+    requested_metrics: HashSet<(String, String)>,
+}
+
+#[derive(Debug, Clone)]
+struct MirroredValue {
+    sensor_name: String,
+    metric: Metric,
+    value: MetricValue,
+    timestamp: Timestamp,
+}
+
+#[derive(Default)]
+struct FlushOutcome {
+    unresolved: Vec<MirroredValue>,
+    missing_sensors: Vec<String>,
+    missing_metrics: Vec<(String, Metric)>,
+}
+
+impl MirrorActor {
+    pub fn new(source: Addr<SensorVisionClient>, target: Addr<SensorVisionClient>) -> Self {
+        Self {
+            source,
+            target,
+            pending: VecDeque::new(),
+            flushing: false,
+            requested_sensors: HashSet::new(),
+            requested_metrics: HashSet::new(),
+        }
+    }
+
+    fn enqueue(&mut self, value: MirroredValue) {
+        if self.pending.len() == PENDING_VALUES_LIMIT {
+            log::warn!("Mirror queue is full, dropping the oldest value");
+            self.pending.pop_front();
+        }
+        self.pending.push_back(value);
+        telemetry::queue_depth("mirror", self.pending.len());
+    }
+
+    fn flush(&mut self, ctx: &mut Context<Self>) {
+        if self.flushing || self.pending.is_empty() {
+            return;
+        }
+        self.flushing = true;
+
+        let values: Vec<MirroredValue> = self.pending.drain(..).collect();
+        let target = self.target.clone();
+
+        ctx.spawn(
+            async move {
+                let mut outcome = FlushOutcome::default();
+
+                let snapshot = async { target.send(GetStateSnapshot).await? };
+                let sensors = match snapshot.await {
+                    Ok(sensors) => sensors,
+                    Err(err) => {
+                        log::error!("Failed to load target state: {err}");
+                        outcome.unresolved = values;
+                        return outcome;
+                    }
+                };
+
+                for value in values {
+                    let Some(sensor) = sensors
+                        .values()
+                        .find(|sensor| sensor.name == value.sensor_name)
+                    else {
+                        outcome.missing_sensors.push(value.sensor_name.clone());
+                        outcome.unresolved.push(value);
+                        continue;
+                    };
+
+                    let Some(metric) = sensor
+                        .metrics
+                        .iter()
+                        .find(|metric| metric.name() == value.metric.name())
+                    else {
+                        outcome
+                            .missing_metrics
+                            .push((value.sensor_name.clone(), value.metric.clone()));
+                        outcome.unresolved.push(value);
+                        continue;
+                    };
+
+                    let push_result = target
+                        .send(PushValue {
+                            sensor_id: sensor.sensor_id,
+                            metric_id: *metric.metric_id(),
+                            value: value.value,
+                            timestamp: Some(value.timestamp),
+                        })
+                        .await;
+                    if let Err(err) = push_result {
+                        log::error!("Failed to mirror value of {}: {err}", value.metric.name());
+                    }
+                }
+
+                outcome
+            }
+            .into_actor(self)
+            .map(|outcome, act, _| {
+                act.flushing = false;
+                act.request_missing(outcome.missing_sensors, outcome.missing_metrics);
+                for value in outcome.unresolved.into_iter().rev() {
+                    act.pending.push_front(value);
+                }
+                act.pending.truncate(PENDING_VALUES_LIMIT);
+                telemetry::queue_depth("mirror", act.pending.len());
+            }),
+        );
+    }
+
+    fn request_missing(
+        &mut self,
+        missing_sensors: Vec<String>,
+        missing_metrics: Vec<(String, Metric)>,
+    ) {
+        for sensor_name in missing_sensors {
+            if self.requested_sensors.insert(sensor_name.clone()) {
+                log::info!("Mirror: creating sensor {sensor_name}");
+                self.target.do_send(CreateSensor { name: sensor_name });
+            }
+        }
+
+        for (sensor_name, metric) in missing_metrics {
+            let key = (sensor_name.clone(), metric.name().clone());
+            if !self.requested_metrics.insert(key) {
+                continue;
+            }
+
+            log::info!("Mirror: creating metric {}/{}", sensor_name, metric.name());
+            let target = self.target.clone();
+            actix::spawn(async move {
+                let Ok(Ok(sensors)) = target.send(GetStateSnapshot).await else {
+                    return;
+                };
+                let Some(sensor) = sensors.values().find(|sensor| sensor.name == sensor_name)
+                else {
+                    return;
+                };
+                if let Err(err) = target
+                    .send(CreateMetrics {
+                        sensor_id: sensor.sensor_id,
+                        metrics: vec![metric.detached()],
+                    })
+                    .await
+                {
+                    log::error!("Failed to send CreateMetrics: {err}");
+                }
+            });
+        }
+    }
+}
+
+impl Actor for MirrorActor {
+    type Context = Context<Self>;
+
+    fn started(&mut self, ctx: &mut Self::Context) {
+        let source = self.source.clone();
+        let weak_this = ctx.address().downgrade().recipient();
+        ctx.spawn(
+            async move {
+                let _ = source.send(SubscribeToStateEvents(weak_this)).await;
+            }
+            .into_actor(self),
+        );
+
+        ctx.run_interval(FLUSH_INTERVAL, |act, ctx| act.flush(ctx));
+    }
+}
+
+impl Handler<SensorStateEvent> for MirrorActor {
+    type Result = ();
+
+    fn handle(&mut self, event: SensorStateEvent, ctx: &mut Self::Context) -> Self::Result {
+        let SensorStateEvent::Livedata {
+            sensor_id,
+            metric_id,
+            value,
+            timestamp,
+            ..
+        } = event
+        else {
+            return;
+        };
+
+        let source = self.source.clone();
+        ctx.spawn(
+            async move {
+                let sensors = source.send(GetStateSnapshot).await.ok()?.ok()?;
+                let sensor = sensors.get(&sensor_id)?;
+                let metric = sensor.metrics.get(&metric_id)?;
+                Some(MirroredValue {
+                    sensor_name: sensor.name.clone(),
+                    metric: metric.clone(),
+                    value,
+                    timestamp,
+                })
+            }
+            .into_actor(self)
+            .map(move |mirrored_value, act, ctx| {
+                if let Some(mirrored_value) = mirrored_value {
+                    act.enqueue(mirrored_value);
+                    act.flush(ctx);
+                } else {
+                    log::warn!("Mirror: unknown source metric {sensor_id}/{metric_id}");
+                }
+            }),
+        );
+    }
+}
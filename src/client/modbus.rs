@@ -0,0 +1,392 @@
+use actix::Addr;
+
+use eyre::{eyre, Result, WrapErr};
+
+use std::collections::BTreeMap;
+use std::path::Path;
+use std::time::Duration;
+
+use tokio::fs::{File, OpenOptions};
+use tokio::io::{AsyncBufReadExt, AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt, BufReader};
+use tokio::net::TcpStream;
+use tokio::process::Command;
+
+use crate::client::client::SensorVisionClient;
+use crate::client::collector::CollectorTarget;
+use crate::config::{
+    ConversionConfig, ModbusRegisterConfig, ModbusSourceConfig, RegisterFormat, RegisterKind,
+    SerialLineConfig, ThrottleConfig,
+};
+
+pub const MODBUS_TCP_PORT: u16 = 502;
+
+/// A slave not answering within this time is considered gone, the connection is reopened
+const RESPONSE_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// Pause before a serial line that failed or closed is opened again
+const REOPEN_DELAY: Duration = Duration::from_secs(5);
+
+const READ_COILS: u8 = 0x01;
+const READ_DISCRETE_INPUTS: u8 = 0x02;
+const READ_HOLDING_REGISTERS: u8 = 0x03;
+const READ_INPUT_REGISTERS: u8 = 0x04;
+
+/// Resolves the targets of every source against the loaded inventory and keeps polling
+/// them in the background, failing sources are retried on every interval
+pub async fn start_collectors(
+    client: &Addr<SensorVisionClient>,
+    modbus: &[ModbusSourceConfig],
+    serial: &[SerialLineConfig],
+    throttles: &BTreeMap<String, ThrottleConfig>,
+    conversions: &BTreeMap<String, ConversionConfig>,
+) -> Result<()> {
+    for source in modbus {
+        if source.address.is_empty() {
+            return Err(eyre!("Modbus source lacks an address"));
+        }
+        if source.interval_secs <= 0.0 || !source.interval_secs.is_finite() {
+            return Err(eyre!("Interval of Modbus source {} must be positive", source.address));
+        }
+        let mut registers = Vec::with_capacity(source.registers.len());
+        for register in &source.registers {
+            let throttle = ThrottleConfig::of_target(throttles, &register.target, source.throttle);
+            let conversion =
+                ConversionConfig::of_target(conversions, &register.target, source.conversion);
+            let target = CollectorTarget::resolve(client, &register.target, throttle, conversion)
+                .await
+                .wrap_err_with(|| format!("Modbus source {}", source.address))?;
+            registers.push((register.clone(), target));
+        }
+        log::info!(
+            "Polling {} register(s) of {} every {:?}",
+            registers.len(),
+            source.address,
+            source.interval()
+        );
+        actix::spawn(poll_modbus(client.clone(), source.clone(), registers));
+    }
+
+    for line in serial {
+        let mut targets = BTreeMap::new();
+        for (name, target) in &line.map {
+            let throttle = ThrottleConfig::of_target(throttles, target, line.throttle);
+            let conversion = ConversionConfig::of_target(conversions, target, line.conversion);
+            let target = CollectorTarget::resolve(client, target, throttle, conversion)
+                .await
+                .wrap_err_with(|| format!("Serial line {}", line.device.display()))?;
+            targets.insert(name.clone(), target);
+        }
+        log::info!(
+            "Reading {} value(s) from {}",
+            targets.len(),
+            line.device.display()
+        );
+        actix::spawn(read_serial_line(client.clone(), line.clone(), targets));
+    }
+    Ok(())
+}
+
+async fn poll_modbus(
+    client: Addr<SensorVisionClient>,
+    source: ModbusSourceConfig,
+    mut registers: Vec<(ModbusRegisterConfig, CollectorTarget)>,
+) {
+    let mut connection: Option<ModbusConnection> = None;
+    let mut ticker = tokio::time::interval(source.interval());
+    ticker.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+    loop {
+        ticker.tick().await;
+        let open = match connection.as_mut() {
+            Some(open) => open,
+            None => match ModbusConnection::open(&source).await {
+                Ok(opened) => connection.insert(opened),
+                Err(err) => {
+                    log::warn!("Failed to connect to Modbus {}: {err}", source.address);
+                    continue;
+                }
+            },
+        };
+
+        for (register, target) in &mut registers {
+            let read = tokio::time::timeout(RESPONSE_TIMEOUT, open.read_register(register)).await;
+            let raw = match read {
+                Ok(Ok(Ok(raw))) => raw,
+                Ok(Ok(Err(exception))) => {
+                    log::warn!(
+                        "Modbus {} refused to read {} at {}: {exception}",
+                        source.address,
+                        register.target,
+                        register.address
+                    );
+                    continue;
+                }
+                Ok(Err(err)) => {
+                    log::warn!("Lost Modbus {}: {err}", source.address);
+                    connection = None;
+                    break;
+                }
+                Err(_) => {
+                    log::warn!("Modbus {} didn't answer in time", source.address);
+                    connection = None;
+                    break;
+                }
+            };
+            let value = target.metric.numeric_value(raw * register.scale + register.offset);
+            target.push(&client, value).await;
+        }
+    }
+}
+
+async fn read_serial_line(
+    client: Addr<SensorVisionClient>,
+    line: SerialLineConfig,
+    mut targets: BTreeMap<String, CollectorTarget>,
+) {
+    loop {
+        match open_serial(&line.device, line.baud_rate).await {
+            Ok(device) => {
+                let mut lines = BufReader::new(device).lines();
+                loop {
+                    match lines.next_line().await {
+                        Ok(Some(text)) => push_reading(&client, &mut targets, &text).await,
+                        Ok(None) => {
+                            log::warn!("Serial line {} closed", line.device.display());
+                            break;
+                        }
+                        Err(err) => {
+                            log::warn!("Failed to read {}: {err}", line.device.display());
+                            break;
+                        }
+                    }
+                }
+            }
+            Err(err) => log::warn!("Failed to open {}: {err}", line.device.display()),
+        }
+        tokio::time::sleep(REOPEN_DELAY).await;
+    }
+}
+
+async fn push_reading(
+    client: &Addr<SensorVisionClient>,
+    targets: &mut BTreeMap<String, CollectorTarget>,
+    text: &str,
+) {
+    let Some((name, value)) = text.split_once(['=', ':', ',']) else {
+        log::debug!("Skipping serial line '{text}'");
+        return;
+    };
+    let Some(target) = targets.get_mut(name.trim()) else {
+        return;
+    };
+    match target.metric.parse_value(value.trim()) {
+        Ok(value) => target.push(client, value).await,
+        Err(err) => log::warn!("Invalid reading of {}: {err}", name.trim()),
+    }
+}
+
+/// Puts the device into raw mode at the given baud rate. Devices `stty` can't configure,
+/// such as pipes, are read as they are.
+async fn open_serial(device: &Path, baud_rate: u32) -> Result<File> {
+    let configured = Command::new("stty")
+        .arg("-F")
+        .arg(device)
+        .args([&baud_rate.to_string(), "raw", "-echo"])
+        .status()
+        .await;
+    if !configured.is_ok_and(|status| status.success()) {
+        log::debug!("Couldn't configure {} with stty", device.display());
+    }
+    OpenOptions::new()
+        .read(true)
+        .write(true)
+        .open(device)
+        .await
+        .wrap_err_with(|| format!("Failed to open {}", device.display()))
+}
+
+trait Stream: AsyncRead + AsyncWrite + Unpin + Send {}
+
+impl<T: AsyncRead + AsyncWrite + Unpin + Send> Stream for T {}
+
+enum Framing {
+    /// MBAP header, the replies are matched by the transaction id
+    Tcp { transaction_id: u16 },
+    /// Unit id up front and a CRC at the end
+    Rtu,
+}
+
+struct ModbusConnection {
+    stream: Box<dyn Stream>,
+    framing: Framing,
+    unit_id: u8,
+}
+
+impl ModbusConnection {
+    async fn open(source: &ModbusSourceConfig) -> Result<Self> {
+        let (stream, framing): (Box<dyn Stream>, _) = match source.address.strip_prefix("tcp://") {
+            Some(host) => {
+                let address = if host.contains(':') {
+                    host.to_owned()
+                } else {
+                    format!("{host}:{MODBUS_TCP_PORT}")
+                };
+                let stream = tokio::time::timeout(RESPONSE_TIMEOUT, TcpStream::connect(&address))
+                    .await
+                    .map_err(|_| eyre!("Timed out connecting to {address}"))??;
+                (Box::new(stream), Framing::Tcp { transaction_id: 0 })
+            }
+            None => {
+                let device = open_serial(Path::new(&source.address), source.baud_rate).await?;
+                (Box::new(device), Framing::Rtu)
+            }
+        };
+        Ok(Self {
+            stream,
+            framing,
+            unit_id: source.unit_id,
+        })
+    }
+
+    /// The decoded raw reading, or the exception the slave replied with
+    async fn read_register(
+        &mut self,
+        register: &ModbusRegisterConfig,
+    ) -> Result<Result<f64, String>> {
+        let (function, quantity) = match register.kind {
+            RegisterKind::Holding => (READ_HOLDING_REGISTERS, register.format.registers()),
+            RegisterKind::Input => (READ_INPUT_REGISTERS, register.format.registers()),
+            RegisterKind::Coil => (READ_COILS, 1),
+            RegisterKind::Discrete => (READ_DISCRETE_INPUTS, 1),
+        };
+        let data = match self.request(function, register.address, quantity).await? {
+            Ok(data) => data,
+            Err(exception) => return Ok(Err(exception)),
+        };
+        if matches!(register.kind, RegisterKind::Coil | RegisterKind::Discrete) {
+            let bit = data.first().ok_or_else(|| eyre!("Empty reply"))? & 0x01;
+            return Ok(Ok(bit as f64));
+        }
+        decode(register.format, register.swap_words, &data).map(Ok)
+    }
+
+    /// Data bytes of the reply to a read request
+    async fn request(
+        &mut self,
+        function: u8,
+        address: u16,
+        quantity: u16,
+    ) -> Result<Result<Vec<u8>, String>> {
+        let mut pdu = vec![function];
+        pdu.extend(address.to_be_bytes());
+        pdu.extend(quantity.to_be_bytes());
+
+        let reply = match &mut self.framing {
+            Framing::Tcp { transaction_id } => {
+                *transaction_id = transaction_id.wrapping_add(1);
+                let transaction_id = *transaction_id;
+
+                let mut frame = Vec::with_capacity(7 + pdu.len());
+                frame.extend(transaction_id.to_be_bytes());
+                frame.extend(0u16.to_be_bytes());
+                frame.extend((pdu.len() as u16 + 1).to_be_bytes());
+                frame.push(self.unit_id);
+                frame.extend(&pdu);
+                self.stream.write_all(&frame).await?;
+
+                let mut header = [0; 7];
+                self.stream.read_exact(&mut header).await?;
+                if u16::from_be_bytes([header[0], header[1]]) != transaction_id {
+                    return Err(eyre!("Reply to another transaction"));
+                }
+                let length = u16::from_be_bytes([header[4], header[5]]) as usize;
+                let mut reply = vec![0; length.saturating_sub(1)];
+                self.stream.read_exact(&mut reply).await?;
+                reply
+            }
+            Framing::Rtu => {
+                let mut frame = vec![self.unit_id];
+                frame.extend(&pdu);
+                frame.extend(crc16(&frame).to_le_bytes());
+                self.stream.write_all(&frame).await?;
+                self.stream.flush().await?;
+
+                let mut reply = vec![0; 3];
+                self.stream.read_exact(&mut reply).await?;
+                // An exception carries its code where the byte count would be
+                let remaining = if reply[1] & 0x80 != 0 {
+                    2
+                } else {
+                    reply[2] as usize + 2
+                };
+                reply.resize(3 + remaining, 0);
+                self.stream.read_exact(&mut reply[3..]).await?;
+
+                let (body, crc) = reply.split_at(reply.len() - 2);
+                if crc16(body).to_le_bytes() != crc {
+                    return Err(eyre!("Reply with a bad CRC"));
+                }
+                // A good CRC doesn't make the reply of another slave on the bus ours
+                if reply[0] != self.unit_id {
+                    return Err(eyre!("Reply from unit {} rather than {}", reply[0], self.unit_id));
+                }
+                reply[1..reply.len() - 2].to_vec()
+            }
+        };
+
+        match reply.as_slice() {
+            [code, exception, ..] if *code == function | 0x80 => Ok(Err(exception_name(*exception))),
+            [code, count, data @ ..] if *code == function && data.len() >= *count as usize => {
+                Ok(Ok(data[..*count as usize].to_vec()))
+            }
+            _ => Err(eyre!("Malformed reply")),
+        }
+    }
+}
+
+fn decode(format: RegisterFormat, swap_words: bool, data: &[u8]) -> Result<f64> {
+    let mut words: Vec<u16> = data
+        .chunks_exact(2)
+        .map(|word| u16::from_be_bytes([word[0], word[1]]))
+        .collect();
+    if words.len() < format.registers() as usize {
+        return Err(eyre!("Expected {} register(s) in the reply", format.registers()));
+    }
+    if swap_words {
+        words.reverse();
+    }
+    let long = || (words[0] as u32) << 16 | words.get(1).copied().unwrap_or_default() as u32;
+    Ok(match format {
+        RegisterFormat::U16 => words[0] as f64,
+        RegisterFormat::I16 => words[0] as i16 as f64,
+        RegisterFormat::U32 => long() as f64,
+        RegisterFormat::I32 => long() as i32 as f64,
+        RegisterFormat::F32 => f32::from_bits(long()) as f64,
+    })
+}
+
+fn exception_name(code: u8) -> String {
+    match code {
+        0x01 => String::from("illegal function"),
+        0x02 => String::from("illegal data address"),
+        0x03 => String::from("illegal data value"),
+        0x04 => String::from("slave device failure"),
+        0x06 => String::from("slave device busy"),
+        code => format!("exception {code:#04x}"),
+    }
+}
+
+/// CRC-16/MODBUS
+fn crc16(bytes: &[u8]) -> u16 {
+    let mut crc = 0xFFFF_u16;
+    for byte in bytes {
+        crc ^= *byte as u16;
+        for _ in 0..8 {
+            crc = if crc & 0x0001 != 0 {
+                (crc >> 1) ^ 0xA001
+            } else {
+                crc >> 1
+            };
+        }
+    }
+    crc
+}
@@ -1,6 +1,6 @@
 use actix::prelude::*;
 
-use eyre::{eyre, OptionExt, Result};
+use eyre::{eyre, Result};
 
 use futures::{FutureExt, StreamExt};
 
@@ -8,17 +8,49 @@ use paho_mqtt as mqtt;
 
 use sha2::{Digest, Sha256};
 
+use std::collections::{HashMap, VecDeque};
+use std::path::Path;
+use std::sync::atomic::{AtomicU64, Ordering};
 use std::time::Duration;
 
+use tokio::sync::oneshot;
+
+use crate::client::exchanges;
+use crate::client::mqtt::subscriptions;
+use crate::config::BrokerConfig;
+use crate::correlation::{self, CorrelationId};
+use crate::logging;
+use crate::model::SensorId;
+
+// The broker is retried with the interval doubling from min to max until it is back
+const RECONNECT_MIN_INTERVAL: Duration = Duration::from_secs(1);
+const RECONNECT_MAX_INTERVAL: Duration = Duration::from_secs(60);
+
 #[derive(Debug, Clone)]
 pub struct MqttMessage {
     pub topic: String,
     pub message: String,
+
+    /// Operation the message was sent on behalf of
+    pub correlation_id: Option<CorrelationId>,
 }
 
+/// Request whose reply isn't awaited, it reaches the state through the listener
 #[derive(Message)]
 #[rtype(result = "Result<()>")]
-pub struct OneWayMessage(pub MqttMessage);
+pub struct OneWayMessage {
+    pub message: MqttMessage,
+    pub response_topic: String,
+    pub error_topic: String,
+
+    /// Sensor the exchange is kept under
+    pub sensor_id: Option<SensorId>,
+}
+
+/// Retries the broker right away rather than after the reconnect backoff, if the connection is lost
+#[derive(Clone, Message)]
+#[rtype(result = "()")]
+pub struct Reconnect;
 
 #[derive(Message)]
 #[rtype(result = "Result<String>")]
@@ -26,30 +58,248 @@ pub struct MqttRequest {
     pub message: MqttMessage,
     pub response_topic: String,
     pub error_topic: String,
+
+    /// Sensor the exchange is kept under
+    pub sensor_id: Option<SensorId>,
+}
+
+/// MQTT topic filter matching, `+` stands for a single level and a trailing `#` for the rest
+pub fn topic_matches(filter: &str, topic: &str) -> bool {
+    let mut topic_levels = topic.split('/');
+    for filter_level in filter.split('/') {
+        if filter_level == "#" {
+            return true;
+        }
+        match topic_levels.next() {
+            Some(topic_level) if filter_level == "+" || filter_level == topic_level => {}
+            _ => return false,
+        }
+    }
+    topic_levels.next().is_none()
+}
+
+/// Certificate set and client id prefix a connector authenticates with.
+///
+/// Each connector needs its own prefix, as the broker drops a session
+/// once another one with the same client id connects.
+#[derive(Debug, Clone)]
+pub struct MqttCredentials {
+    pub trust_store: String,
+    pub client_cert: String,
+    pub private_key: String,
+    pub client_id_prefix: String,
+}
+
+impl Default for MqttCredentials {
+    fn default() -> Self {
+        Self {
+            trust_store: String::from(
+                "/var/lib/teamviewer-iot-agent/certs/TeamViewerAuthority.crt",
+            ),
+            client_cert: String::from("clientCert.crt"),
+            private_key: String::from("privkey.pem"),
+            client_id_prefix: String::from("sv"),
+        }
+    }
+}
+
+impl MqttCredentials {
+    /// Same trust store and file names, but the certificate and the key are looked up in `dir`.
+    pub fn in_dir(&self, dir: &Path, client_id_prefix: &str) -> Self {
+        let relocate = |path: &str| {
+            let file_name = Path::new(path).file_name().unwrap_or(path.as_ref());
+            dir.join(file_name).to_string_lossy().to_string()
+        };
+        Self {
+            trust_store: self.trust_store.clone(),
+            client_cert: relocate(&self.client_cert),
+            private_key: relocate(&self.private_key),
+            client_id_prefix: client_id_prefix.to_owned(),
+        }
+    }
+
+    fn client_id(&self, client_name: &str) -> String {
+        format!("{}_{}", self.client_id_prefix, client_name)
+    }
+}
+
+/// Request waiting for the reply on either of its topics
+struct PendingRequest {
+    id: u64,
+    response_topic: String,
+    error_topic: String,
+    respond_to: oneshot::Sender<Result<String>>,
 }
 
+/// Replies carry no request id, so the requests awaiting the same topics
+/// are answered in the order they were published.
 pub struct MqttActor {
     mqtt_client: mqtt::AsyncClient,
+    client_id: String,
+    request_timeout: Duration,
+
+    replies: Option<mqtt::AsyncReceiver<Option<mqtt::Message>>>,
+    pending: VecDeque<PendingRequest>,
+
+    /// Number of pending requests per subscribed topic
+    subscriptions: HashMap<String, usize>,
 }
 
 impl MqttActor {
-    pub async fn connect_and_start() -> Result<Addr<Self>> {
-        let (mqtt_client, connect_opts) = make_async_mqtt_client("sv_client")?;
-
+    pub async fn connect_and_start(
+        broker: &BrokerConfig,
+        credentials: &MqttCredentials,
+    ) -> Result<Addr<Self>> {
+        let (mut mqtt_client, connect_opts) =
+            make_async_mqtt_client("client", broker, credentials)?;
+
+        let replies = mqtt_client.get_stream(2 << 14);
         mqtt_client.connect(connect_opts).await?;
 
-        Ok(Self { mqtt_client }.start())
+        Ok(Self {
+            client_id: mqtt_client.client_id(),
+            mqtt_client,
+            request_timeout: broker.request_timeout(),
+            replies: Some(replies),
+            pending: VecDeque::new(),
+            subscriptions: HashMap::new(),
+        }
+        .start())
+    }
+
+    /// Topics that weren't subscribed to yet
+    fn subscribe(&mut self, topics: [&String; 2]) -> Vec<String> {
+        let mut new_topics = Vec::new();
+        for topic in topics {
+            let count = self.subscriptions.entry(topic.clone()).or_default();
+            *count += 1;
+            if *count == 1 {
+                subscriptions::subscribed(&self.client_id, topic, mqtt::QOS_1);
+                new_topics.push(topic.clone());
+            }
+        }
+        new_topics
+    }
+
+    fn unsubscribe(&mut self, topics: [&String; 2]) {
+        let mut stale_topics = Vec::new();
+        for topic in topics {
+            if let Some(count) = self.subscriptions.get_mut(topic) {
+                *count -= 1;
+                if *count == 0 {
+                    self.subscriptions.remove(topic);
+                    subscriptions::unsubscribed(&self.client_id, topic);
+                    stale_topics.push(topic.clone());
+                }
+            }
+        }
+        if !stale_topics.is_empty() {
+            // Not awaited, the client performs subscriptions in order anyway
+            let token = self.mqtt_client.unsubscribe_many(&stale_topics);
+            actix::spawn(async move {
+                if let Err(err) = token.await {
+                    log::warn!("Failed to unsubscribe from {stale_topics:?}: {err}");
+                }
+            });
+        }
+    }
+
+    /// Drops the request, unless it's already been answered
+    fn forget(&mut self, request_id: u64) {
+        let Some(index) = self.pending.iter().position(|pending| pending.id == request_id) else {
+            return;
+        };
+        exchanges::request_expired(request_id);
+        if let Some(pending) = self.pending.remove(index) {
+            self.unsubscribe([&pending.response_topic, &pending.error_topic]);
+        }
     }
 }
 
+/// Unique across the clients, so that the exchanges of all of them are kept together
+fn next_request_id() -> u64 {
+    static NEXT_REQUEST_ID: AtomicU64 = AtomicU64::new(0);
+    NEXT_REQUEST_ID.fetch_add(1, Ordering::Relaxed)
+}
+
 impl Actor for MqttActor {
     type Context = Context<Self>;
+
+    fn started(&mut self, ctx: &mut Self::Context) {
+        if let Some(replies) = self.replies.take() {
+            ctx.add_stream(replies.filter_map(|msg_opt| async { msg_opt }));
+        }
+    }
+}
+
+impl StreamHandler<mqtt::Message> for MqttActor {
+    fn handle(&mut self, message: mqtt::Message, _: &mut Self::Context) {
+        let topic = message.topic();
+        subscriptions::message_received(&self.client_id, topic);
+        let Some(index) = self
+            .pending
+            .iter()
+            .position(|pending| pending.response_topic == topic || pending.error_topic == topic)
+        else {
+            log::debug!("Dropping the reply on {topic} nobody waits for");
+            return;
+        };
+        let Some(pending) = self.pending.remove(index) else {
+            return;
+        };
+        self.unsubscribe([&pending.response_topic, &pending.error_topic]);
+
+        let payload = String::from_utf8_lossy(message.payload()).to_string();
+        log::trace!("Reply on {topic}: {}", logging::payload(&payload));
+        let response = if pending.error_topic == topic {
+            Err(eyre!(payload))
+        } else {
+            Ok(payload)
+        };
+        // The requester may have timed out meanwhile
+        let _ = pending.respond_to.send(response);
+    }
+}
+
+pub(super) fn reconnect_if_lost(mqtt_client: &mqtt::AsyncClient) {
+    if mqtt_client.is_connected() {
+        return;
+    }
+    let token = mqtt_client.reconnect();
+    actix::spawn(async move {
+        if let Err(err) = token.await {
+            log::warn!("Failed to reconnect to the broker: {err}");
+        }
+    });
+}
+
+impl Handler<Reconnect> for MqttActor {
+    type Result = ();
+
+    fn handle(&mut self, _: Reconnect, _: &mut Self::Context) -> Self::Result {
+        reconnect_if_lost(&self.mqtt_client);
+    }
 }
 
 impl Handler<OneWayMessage> for MqttActor {
     type Result = ResponseFuture<Result<()>>;
 
-    fn handle(&mut self, OneWayMessage(msg): OneWayMessage, _: &mut Self::Context) -> Self::Result {
+    fn handle(&mut self, request: OneWayMessage, ctx: &mut Self::Context) -> Self::Result {
+        let msg = request.message;
+        correlation::sync_scope(msg.correlation_id, || {
+            log::debug!("Publishing to {}: {}", msg.topic, logging::payload(&msg.message));
+        });
+        let request_id = next_request_id();
+        // Replies are awaited as long as the ones of the requests
+        ctx.run_later(self.request_timeout, move |_, _| exchanges::request_expired(request_id));
+        exchanges::request_sent(
+            request_id,
+            request.sensor_id,
+            &msg.topic,
+            &msg.message,
+            [&request.response_topic, &request.error_topic],
+        );
+
         let message = mqtt::MessageBuilder::new()
             .topic(&msg.topic)
             .payload(msg.message.as_bytes())
@@ -64,69 +314,102 @@ impl Handler<OneWayMessage> for MqttActor {
 }
 
 impl Handler<MqttRequest> for MqttActor {
-    type Result = ResponseFuture<Result<String>>;
+    type Result = ResponseActFuture<Self, Result<String>>;
 
     fn handle(&mut self, msg: MqttRequest, _: &mut Self::Context) -> Self::Result {
-        let mut client = self.mqtt_client.clone();
-        async move {
-            let mut stream = client.get_stream(2 << 14);
-            let (topics, qos) = (
-                [&msg.response_topic, &msg.error_topic],
-                [mqtt::QOS_1, mqtt::QOS_1],
+        correlation::sync_scope(msg.message.correlation_id, || {
+            log::debug!(
+                "Requesting {}: {}",
+                msg.message.topic,
+                logging::payload(&msg.message.message)
             );
-            client.subscribe_many(&topics, &qos).await?;
+        });
+
+        let request_id = next_request_id();
+        exchanges::request_sent(
+            request_id,
+            msg.sensor_id,
+            &msg.message.topic,
+            &msg.message.message,
+            [&msg.response_topic, &msg.error_topic],
+        );
+
+        let new_topics = self.subscribe([&msg.response_topic, &msg.error_topic]);
+        let (tx, rx) = oneshot::channel();
+        self.pending.push_back(PendingRequest {
+            id: request_id,
+            response_topic: msg.response_topic.clone(),
+            error_topic: msg.error_topic.clone(),
+            respond_to: tx,
+        });
+
+        let client = self.mqtt_client.clone();
+        let request_timeout = self.request_timeout;
+        async move {
+            if !new_topics.is_empty() {
+                let qos = vec![mqtt::QOS_1; new_topics.len()];
+                client.subscribe_many(&new_topics, &qos).await?;
+            }
             let message = mqtt::MessageBuilder::new()
                 .topic(&msg.message.topic)
                 .payload(msg.message.message.as_bytes())
                 .qos(mqtt::QOS_1)
                 .finalize();
             client.publish(message).await?;
-            let optopt_message = stream.next().await;
-            client.unsubscribe_many(&topics).await?;
-            let message = optopt_message
-                .ok_or_eyre("no payload")?
-                .ok_or_eyre("no message")?;
-
-            let payload = String::from_utf8_lossy(message.payload()).to_string();
-
-            if msg.error_topic == message.topic() {
-                Err(eyre!(payload))
-            } else {
-                Ok(payload)
+
+            match tokio::time::timeout(request_timeout, rx).await {
+                Ok(response) => response?,
+                Err(_) => Err(eyre!(
+                    "No reply on {} within {}s",
+                    msg.response_topic,
+                    request_timeout.as_secs()
+                )),
             }
-        }.boxed_local()
+        }
+        .into_actor(self)
+        .map(move |response, this, _| {
+            // Failed or timed out requests must not receive a later reply
+            this.forget(request_id);
+            response
+        })
+        .boxed_local()
     }
 }
 
-pub fn make_async_mqtt_client(client_name: &str) -> Result<(mqtt::AsyncClient, mqtt::ConnectOptions)> {
-    let host = String::from("mqtts://localhost:18884");
-
-    let trust_store = String::from("/var/lib/teamviewer-iot-agent/certs/TeamViewerAuthority.crt");
-    let client_cert = String::from("clientCert.crt");
-    let private_key = String::from("privkey.pem");
-
+pub fn make_async_mqtt_client(
+    client_name: &str,
+    broker: &BrokerConfig,
+    credentials: &MqttCredentials,
+) -> Result<(mqtt::AsyncClient, mqtt::ConnectOptions)> {
     let async_client = mqtt::CreateOptionsBuilder::new()
-        .server_uri(&host)
+        .server_uri(broker.uri())
         .max_buffered_messages(200)
-        .client_id(client_name)
+        .client_id(credentials.client_id(client_name))
         .create_client()?;
 
-    let ssl_opts = mqtt::SslOptionsBuilder::new()
-        .trust_store(trust_store)?
-        .key_store(client_cert)?
-        .private_key(private_key)?
-        .finalize();
-
-    let conn_opts = mqtt::ConnectOptionsBuilder::new()
-        .ssl_options(ssl_opts)
+    let mut conn_opts = mqtt::ConnectOptionsBuilder::new();
+    if broker.tls {
+        let ssl_opts = mqtt::SslOptionsBuilder::new()
+            .trust_store(&credentials.trust_store)?
+            .key_store(&credentials.client_cert)?
+            .private_key(&credentials.private_key)?
+            .finalize();
+        conn_opts.ssl_options(ssl_opts);
+    }
+    let conn_opts = conn_opts
         .clean_session(false)
-        .keep_alive_interval(Duration::from_secs(120))
+        .keep_alive_interval(broker.keepalive())
+        .automatic_reconnect(RECONNECT_MIN_INTERVAL, RECONNECT_MAX_INTERVAL)
         .finalize();
 
     Ok((async_client, conn_opts))
 }
 
-pub async fn setup_new_certificate() -> Result<()> {
+/// Requests a certificate for `csr.pem` and stores it as `credentials.client_cert`
+pub async fn setup_new_certificate(
+    broker: &BrokerConfig,
+    credentials: &MqttCredentials,
+) -> Result<()> {
     // According to https://docs-iot.teamviewer.com/mqtt-api/#3-data-model
     let csr_contents = std::fs::read("csr.pem").expect("Failed to read csr.pem");
 
@@ -136,18 +419,14 @@ pub async fn setup_new_certificate() -> Result<()> {
 
     let certback_topic = format!("/certBack/{:x}", csr_digest);
 
-    let trust_store =
-        String::from("/var/lib/teamviewer-iot-agent/certs/TeamViewerAuthority.crt");
-
-    let host = String::from("mqtts://localhost:18883");
     let mut cli = mqtt::CreateOptionsBuilder::new()
-        .server_uri(&host)
+        .server_uri(broker.cert_uri())
         .max_buffered_messages(100)
-        .client_id("sv_cert")
+        .client_id(credentials.client_id("cert"))
         .create_client()?;
 
     let ssl_opts = mqtt::SslOptionsBuilder::new()
-        .trust_store(trust_store)?
+        .trust_store(&credentials.trust_store)?
         .finalize();
 
     let conn_opts = mqtt::ConnectOptionsBuilder::new()
@@ -172,7 +451,7 @@ pub async fn setup_new_certificate() -> Result<()> {
 
     if let Some(message_opt) = strm.next().await {
         if let Some(message) = message_opt {
-            std::fs::write("clientCert.crt", message.payload())?;
+            std::fs::write(&credentials.client_cert, message.payload())?;
         }
     }
 
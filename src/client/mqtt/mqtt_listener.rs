@@ -6,7 +6,15 @@ use futures::StreamExt;
 
 use paho_mqtt as mqtt;
 
-use crate::client::mqtt::{make_async_mqtt_client, MqttMessage};
+use std::time::{Duration, Instant};
+
+use crate::client::journal;
+use crate::client::mqtt::mqtt_client::reconnect_if_lost;
+use crate::client::mqtt::subscriptions;
+use crate::client::mqtt::{make_async_mqtt_client, MqttCredentials, MqttMessage, Reconnect};
+use crate::config::BrokerConfig;
+use crate::logging;
+use crate::telemetry;
 
 #[derive(Clone, Message)]
 #[rtype(result = "()")]
@@ -16,21 +24,54 @@ pub struct MqttEvent(pub MqttMessage);
 #[rtype(result = "()")]
 pub struct SubscribeToListener(pub WeakRecipient<MqttEvent>);
 
+#[derive(Clone, Message)]
+#[rtype(result = "()")]
+pub enum ConnectionEvent {
+    /// The broker is being retried meanwhile
+    Lost,
+    /// Events published while offline are likely missed
+    Restored { offline_for: Duration },
+}
+
+#[derive(Message)]
+#[rtype(result = "()")]
+pub struct SubscribeToConnectionEvents(pub WeakRecipient<ConnectionEvent>);
+
+#[derive(Message)]
+#[rtype(result = "()")]
+struct ConnectionLost;
+
+#[derive(Message)]
+#[rtype(result = "()")]
+struct Reconnected;
+
 pub struct MqttListenerService {
     mqtt_client: mqtt::AsyncClient,
+    client_id: String,
     subscribers: Vec<WeakRecipient<MqttEvent>>,
+
+    connection_subscribers: Vec<WeakRecipient<ConnectionEvent>>,
+    lost_at: Option<Instant>,
 }
 
 impl MqttListenerService {
-    pub async fn connect_and_start(topic: String) -> Result<Addr<Self>> {
-        let (mqtt_client, conn_opts) = make_async_mqtt_client("sv_event")?;
+    pub async fn connect_and_start(
+        topic: String,
+        broker: &BrokerConfig,
+        credentials: &MqttCredentials,
+    ) -> Result<Addr<Self>> {
+        let (mqtt_client, conn_opts) = make_async_mqtt_client("event", broker, credentials)?;
 
         mqtt_client.connect(conn_opts).await?;
         mqtt_client.subscribe(&topic, mqtt::QOS_1).await?;
+        subscriptions::subscribed(&mqtt_client.client_id(), &topic, mqtt::QOS_1);
 
         Ok(Self {
+            client_id: mqtt_client.client_id(),
             mqtt_client,
             subscribers: Vec::default(),
+            connection_subscribers: Vec::default(),
+            lost_at: None,
         }.start())
     }
 }
@@ -45,15 +86,86 @@ impl Actor for MqttListenerService {
             msg_opt.map(|msg| MqttEvent(MqttMessage {
                 topic: msg.topic().to_string(),
                 message: String::from_utf8_lossy(msg.payload()).to_string(),
+                correlation_id: None,
             }))
         });
 
         ctx.add_stream(event_stream);
+
+        // Set after the initial connect, so only reconnects are reported
+        let this = ctx.address();
+        self.mqtt_client
+            .set_connection_lost_callback(move |_| this.do_send(ConnectionLost));
+        let this = ctx.address();
+        self.mqtt_client
+            .set_connected_callback(move |_| this.do_send(Reconnected));
+    }
+}
+
+impl MqttListenerService {
+    fn notify_connection_subscribers(&mut self, event: ConnectionEvent) {
+        self.connection_subscribers
+            .retain(|subscriber| subscriber.upgrade().is_some());
+        for subscriber in &self.connection_subscribers {
+            if let Some(subscriber) = subscriber.upgrade() {
+                subscriber.do_send(event.clone());
+            }
+        }
+    }
+}
+
+impl Handler<ConnectionLost> for MqttListenerService {
+    type Result = ();
+
+    fn handle(&mut self, _: ConnectionLost, _: &mut Self::Context) -> Self::Result {
+        log::warn!("Lost the connection to the broker, reconnecting");
+        if self.lost_at.is_none() {
+            self.lost_at = Some(Instant::now());
+            self.notify_connection_subscribers(ConnectionEvent::Lost);
+        }
+    }
+}
+
+impl Handler<Reconnected> for MqttListenerService {
+    type Result = ();
+
+    fn handle(&mut self, _: Reconnected, _: &mut Self::Context) -> Self::Result {
+        let offline_for = self
+            .lost_at
+            .take()
+            .map_or(Duration::ZERO, |lost_at| lost_at.elapsed());
+        log::info!("Reconnected to the broker after {offline_for:?}");
+        self.notify_connection_subscribers(ConnectionEvent::Restored { offline_for });
+    }
+}
+
+impl Handler<Reconnect> for MqttListenerService {
+    type Result = ();
+
+    fn handle(&mut self, _: Reconnect, _: &mut Self::Context) -> Self::Result {
+        reconnect_if_lost(&self.mqtt_client);
+    }
+}
+
+impl Handler<SubscribeToConnectionEvents> for MqttListenerService {
+    type Result = ();
+
+    fn handle(
+        &mut self,
+        SubscribeToConnectionEvents(subscriber): SubscribeToConnectionEvents,
+        _: &mut Self::Context,
+    ) -> Self::Result {
+        self.connection_subscribers.push(subscriber);
     }
 }
 
 impl StreamHandler<MqttEvent> for MqttListenerService {
     fn handle(&mut self, item: MqttEvent, _: &mut Self::Context) {
+        telemetry::mqtt_message_received();
+        log::trace!("Received on {}: {}", item.0.topic, logging::payload(&item.0.message));
+        journal::record(&item.0);
+        subscriptions::message_received(&self.client_id, &item.0.topic);
+
         // Forward the message to all subscribers
         for subscriber in &self.subscribers {
             if let Some(subscriber) = subscriber.upgrade() {
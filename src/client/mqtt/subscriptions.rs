@@ -0,0 +1,73 @@
+use regex::Regex;
+
+use serde::Serialize;
+
+use std::collections::BTreeMap;
+use std::sync::{Mutex, OnceLock};
+
+use crate::client::mqtt::topic_matches;
+use crate::model::Timestamp;
+use crate::utils::lock_recovering;
+
+/// A topic filter one of the MQTT clients of the process is subscribed to
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Subscription {
+    pub client_id: String,
+    pub filter: String,
+    pub qos: i32,
+    pub subscribed_at: Timestamp,
+
+    /// Messages received on topics matching the filter
+    pub messages: u64,
+    pub last_activity: Option<Timestamp>,
+}
+
+/// Client id and filter -> subscription
+fn registry() -> &'static Mutex<BTreeMap<(String, String), Subscription>> {
+    static REGISTRY: OnceLock<Mutex<BTreeMap<(String, String), Subscription>>> = OnceLock::new();
+    REGISTRY.get_or_init(Mutex::default)
+}
+
+/// Subscribing again keeps the counts, e.g. after a clean session reconnected
+pub fn subscribed(client_id: &str, filter: &str, qos: i32) {
+    lock_recovering(registry())
+        .entry((client_id.to_owned(), filter.to_owned()))
+        .and_modify(|subscription| subscription.qos = qos)
+        .or_insert_with(|| Subscription {
+            client_id: client_id.to_owned(),
+            filter: filter.to_owned(),
+            qos,
+            subscribed_at: Timestamp::now(),
+            messages: 0,
+            last_activity: None,
+        });
+}
+
+pub fn unsubscribed(client_id: &str, filter: &str) {
+    lock_recovering(registry()).remove(&(client_id.to_owned(), filter.to_owned()));
+}
+
+/// Counts the message for every filter of the client it matches
+pub fn message_received(client_id: &str, topic: &str) {
+    let now = Timestamp::now();
+    for subscription in lock_recovering(registry()).values_mut() {
+        if subscription.client_id == client_id && topic_matches(&subscription.filter, topic) {
+            subscription.messages += 1;
+            subscription.last_activity = Some(now);
+        }
+    }
+}
+
+/// Active subscriptions ordered by client and filter, those whose client id or filter
+/// `pattern` matches if given
+pub fn list(pattern: Option<&Regex>) -> Vec<Subscription> {
+    lock_recovering(registry())
+        .values()
+        .filter(|subscription| {
+            pattern.is_none_or(|pattern| {
+                pattern.is_match(&subscription.client_id) || pattern.is_match(&subscription.filter)
+            })
+        })
+        .cloned()
+        .collect()
+}
@@ -0,0 +1,305 @@
+use actix::Addr;
+
+use eyre::{eyre, Result, WrapErr};
+
+use futures::StreamExt;
+
+use paho_mqtt as mqtt;
+
+use serde_json::Value;
+
+use std::collections::{BTreeMap, HashMap};
+use std::str::FromStr;
+use std::time::{Duration, Instant};
+
+use crate::client::client::SensorVisionClient;
+use crate::client::collector::{CollectorTarget, JsonPath, NameVariable};
+use crate::client::mqtt::{subscriptions, topic_matches};
+use crate::config::{ConversionConfig, MqttInConfig, ThrottleConfig};
+
+const KEEPALIVE: Duration = Duration::from_secs(30);
+
+// The broker is retried with the interval doubling from min to max until it is back
+const RECONNECT_MIN_INTERVAL: Duration = Duration::from_secs(1);
+const RECONNECT_MAX_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Targets that failed to resolve are looked up again this long after, in case they got created
+const UNRESOLVED_TTL: Duration = Duration::from_secs(30);
+
+/// Text with `{topic}`, `{topic[N]}`, `{payload}` and `{$.json.path}` placeholders, as well as
+/// the variables of the target names
+#[derive(Debug, Clone)]
+pub struct Template(Vec<Part>);
+
+#[derive(Debug, Clone)]
+enum Part {
+    Text(String),
+    Topic,
+    TopicLevel(usize),
+    Payload,
+    Json(JsonPath),
+    Variable(NameVariable),
+}
+
+impl FromStr for Template {
+    type Err = eyre::Report;
+
+    fn from_str(template: &str) -> Result<Self> {
+        let mut parts = Vec::new();
+        let mut rest = template;
+        while let Some(start) = rest.find('{') {
+            if start > 0 {
+                parts.push(Part::Text(rest[..start].to_owned()));
+            }
+            let end = rest[start..]
+                .find('}')
+                .map(|end| start + end)
+                .ok_or_else(|| eyre!("Unclosed placeholder in '{template}'"))?;
+            let placeholder = rest[start + 1..end].trim();
+            let part = match placeholder {
+                "topic" => Part::Topic,
+                "payload" => Part::Payload,
+                json if json.starts_with('$') => Part::Json(json.parse()?),
+                _ => placeholder
+                    .strip_prefix("topic[")
+                    .and_then(|level| level.strip_suffix(']'))
+                    .and_then(|level| level.trim().parse().ok())
+                    .map(Part::TopicLevel)
+                    .or_else(|| placeholder.parse::<NameVariable>().ok().map(Part::Variable))
+                    .ok_or_else(|| {
+                        eyre!("Unknown placeholder {{{placeholder}}} in '{template}'")
+                    })?,
+            };
+            parts.push(part);
+            rest = &rest[end + 1..];
+        }
+        if !rest.is_empty() {
+            parts.push(Part::Text(rest.to_owned()));
+        }
+        Ok(Self(parts))
+    }
+}
+
+impl Template {
+    /// Renders the same for every message
+    fn is_constant(&self) -> bool {
+        self.0.iter().all(|part| matches!(part, Part::Text(_)))
+    }
+
+    /// Strings picked from the JSON payload are inserted without their quotes
+    pub fn render(&self, topic: &str, payload: &str, json: Option<&Value>) -> Result<String> {
+        let mut rendered = String::new();
+        for part in &self.0 {
+            match part {
+                Part::Text(text) => rendered.push_str(text),
+                Part::Topic => rendered.push_str(topic),
+                Part::TopicLevel(level) => rendered.push_str(
+                    topic
+                        .split('/')
+                        .nth(*level)
+                        .ok_or_else(|| eyre!("Topic {topic} has no level {level}"))?,
+                ),
+                Part::Payload => rendered.push_str(payload),
+                Part::Variable(variable) => rendered.push_str(&variable.value()?),
+                Part::Json(path) => {
+                    let value = json
+                        .and_then(|json| path.select(json))
+                        .ok_or_else(|| eyre!("Nothing at {path} in the payload"))?;
+                    match value {
+                        Value::String(text) => rendered.push_str(text),
+                        value => rendered.push_str(&value.to_string()),
+                    }
+                }
+            }
+        }
+        Ok(rendered)
+    }
+}
+
+struct Mapping {
+    topic: String,
+    target: Template,
+    value: Template,
+}
+
+/// Resolved targets by their rendered names, the ones that failed to resolve with the time they did
+type Targets = HashMap<String, Result<CollectorTarget, Instant>>;
+
+/// Connects to every broker and forwards the messages of the mapped topics in the background.
+/// Fixed targets are resolved against the loaded inventory right away, templated ones once a
+/// message renders them.
+pub async fn start_bridges(
+    client: &Addr<SensorVisionClient>,
+    bridges: &[MqttInConfig],
+    throttles: &BTreeMap<String, ThrottleConfig>,
+    conversions: &BTreeMap<String, ConversionConfig>,
+) -> Result<()> {
+    for bridge in bridges {
+        if !bridge.broker.starts_with("mqtt://") && !bridge.broker.starts_with("mqtts://") {
+            return Err(eyre!("Broker {} isn't a mqtt:// or mqtts:// URI", bridge.broker));
+        }
+        let context = || format!("MQTT bridge {}", bridge.broker);
+        let mut mappings = Vec::with_capacity(bridge.map.len());
+        let mut targets = Targets::new();
+        for mapping in &bridge.map {
+            if mapping.topic.is_empty() {
+                return Err(eyre!("Mapping to {} lacks a topic", mapping.target)).wrap_err_with(context);
+            }
+            let target: Template = mapping.target.parse().wrap_err_with(context)?;
+            if target.is_constant() {
+                let name = target.render("", "", None)?;
+                let throttle = ThrottleConfig::of_target(throttles, &name, bridge.throttle);
+                let conversion = ConversionConfig::of_target(conversions, &name, bridge.conversion);
+                let resolved = CollectorTarget::resolve(client, &name, throttle, conversion)
+                    .await
+                    .wrap_err_with(context)?;
+                targets.insert(name, Ok(resolved));
+            }
+            mappings.push(Mapping {
+                topic: mapping.topic.clone(),
+                target,
+                value: mapping.value.parse().wrap_err_with(context)?,
+            });
+        }
+
+        let (mqtt_client, messages) = connect(bridge)
+            .await
+            .wrap_err_with(|| format!("Failed to connect to {}", bridge.broker))?;
+        log::info!("Bridging {} topic(s) of {}", mappings.len(), bridge.broker);
+        let bridged = Bridged {
+            client: client.clone(),
+            mappings,
+            targets,
+            throttles: throttles.clone(),
+            throttle: bridge.throttle,
+            conversions: conversions.clone(),
+            conversion: bridge.conversion,
+        };
+        actix::spawn(forward(bridged, mqtt_client, messages));
+    }
+    Ok(())
+}
+
+async fn connect(
+    bridge: &MqttInConfig,
+) -> Result<(mqtt::AsyncClient, mqtt::AsyncReceiver<Option<mqtt::Message>>)> {
+    let mut mqtt_client = mqtt::CreateOptionsBuilder::new()
+        .server_uri(&bridge.broker)
+        .client_id(&bridge.client_id)
+        .create_client()?;
+
+    let mut conn_opts = mqtt::ConnectOptionsBuilder::new();
+    if bridge.broker.starts_with("mqtts://") {
+        let mut ssl_opts = mqtt::SslOptionsBuilder::new();
+        if let Some(trust_store) = &bridge.trust_store {
+            ssl_opts.trust_store(trust_store)?;
+        }
+        conn_opts.ssl_options(ssl_opts.finalize());
+    }
+    if let Some(username) = &bridge.username {
+        conn_opts.user_name(username);
+    }
+    if let Some(password) = &bridge.password {
+        conn_opts.password(password);
+    }
+    let conn_opts = conn_opts
+        .clean_session(true)
+        .keep_alive_interval(KEEPALIVE)
+        .automatic_reconnect(RECONNECT_MIN_INTERVAL, RECONNECT_MAX_INTERVAL)
+        .finalize();
+
+    // The session is clean, so the topics are subscribed to on every (re)connect
+    let mut filters: Vec<String> = bridge.map.iter().map(|mapping| mapping.topic.clone()).collect();
+    filters.sort();
+    filters.dedup();
+    let qos = vec![mqtt::QOS_0; filters.len()];
+    for filter in &filters {
+        subscriptions::subscribed(&bridge.client_id, filter, mqtt::QOS_0);
+    }
+    mqtt_client.set_connected_callback(move |mqtt_client| {
+        mqtt_client.subscribe_many(&filters, &qos);
+    });
+
+    let messages = mqtt_client.get_stream(1024);
+    mqtt_client.connect(conn_opts).await?;
+    Ok((mqtt_client, messages))
+}
+
+/// Forwarding state of a connected broker
+struct Bridged {
+    client: Addr<SensorVisionClient>,
+    mappings: Vec<Mapping>,
+    targets: Targets,
+    throttles: BTreeMap<String, ThrottleConfig>,
+    throttle: ThrottleConfig,
+    conversions: BTreeMap<String, ConversionConfig>,
+    conversion: ConversionConfig,
+}
+
+async fn forward(
+    mut bridged: Bridged,
+    // Dropping the client would disconnect it
+    mqtt_client: mqtt::AsyncClient,
+    mut messages: mqtt::AsyncReceiver<Option<mqtt::Message>>,
+) {
+    let client_id = mqtt_client.client_id();
+    while let Some(message) = messages.next().await {
+        // Disconnected, the client reconnects on its own
+        let Some(message) = message else {
+            continue;
+        };
+        let topic = message.topic();
+        subscriptions::message_received(&client_id, topic);
+        let payload = message.payload_str();
+        let json = serde_json::from_str::<Value>(&payload).ok();
+        for index in 0..bridged.mappings.len() {
+            if !topic_matches(&bridged.mappings[index].topic, topic) {
+                continue;
+            }
+            if let Err(err) = bridged.bridge_message(index, topic, &payload, json.as_ref()).await {
+                log::warn!("Failed to bridge {topic}: {err}");
+            }
+        }
+    }
+}
+
+impl Bridged {
+    async fn bridge_message(
+        &mut self,
+        mapping: usize,
+        topic: &str,
+        payload: &str,
+        json: Option<&Value>,
+    ) -> Result<()> {
+        let mapping = &self.mappings[mapping];
+        let name = mapping.target.render(topic, payload, json)?;
+        let value = mapping.value.render(topic, payload, json);
+        let failed_before = match self.targets.get(&name) {
+            None => false,
+            Some(Err(failed_at)) if failed_at.elapsed() >= UNRESOLVED_TTL => true,
+            Some(_) => return self.push(&name, value).await,
+        };
+        let throttle = ThrottleConfig::of_target(&self.throttles, &name, self.throttle);
+        let conversion = ConversionConfig::of_target(&self.conversions, &name, self.conversion);
+        let resolved = CollectorTarget::resolve(&self.client, &name, throttle, conversion).await;
+        match &resolved {
+            // Reported once, the messages for the target are skipped until it's retried
+            Err(err) if !failed_before => log::warn!("Not bridging {topic} to {name}: {err}"),
+            Err(_) => {}
+            Ok(_) if failed_before => log::info!("Bridging {topic} to {name}, which exists now"),
+            Ok(_) => {}
+        }
+        self.targets.insert(name.clone(), resolved.map_err(|_| Instant::now()));
+        self.push(&name, value).await
+    }
+
+    /// Skipped for targets that failed to resolve
+    async fn push(&mut self, name: &str, value: Result<String>) -> Result<()> {
+        let Some(Ok(target)) = self.targets.get_mut(name) else {
+            return Ok(());
+        };
+        let value = target.metric.parse_value(&value?)?;
+        target.push(&self.client, value).await;
+        Ok(())
+    }
+}
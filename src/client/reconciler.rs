@@ -0,0 +1,37 @@
+use std::collections::HashSet;
+
+use crate::model::SensorId;
+
+/// Sensors whose metrics are described again once the sensor list comes.
+/// The protocol can't list a single sensor, so a change of one still takes listing all of them,
+/// but only the changed sensors get their metrics described rather than every metric of the
+/// connector. A burst of changes makes for a single list, see `RELOAD_MIN_INTERVAL`.
+#[derive(Clone, Debug, Default)]
+pub struct Reconciler {
+    dirty: HashSet<SensorId>,
+}
+
+impl Reconciler {
+    pub fn mark_dirty(&mut self, sensor_id: SensorId) {
+        self.dirty.insert(sensor_id);
+    }
+
+    /// Everything may have changed, e.g. while the client was offline
+    pub fn mark_all_dirty(&mut self, sensor_ids: impl IntoIterator<Item = SensorId>) {
+        self.dirty.extend(sensor_ids);
+    }
+
+    /// Whether the listed sensor has its metrics described, it's clean afterwards
+    pub fn take_dirty(&mut self, sensor_id: SensorId) -> bool {
+        self.dirty.remove(&sensor_id)
+    }
+
+    /// Deleted sensors are never listed again
+    pub fn forget(&mut self, sensor_id: SensorId) {
+        self.dirty.remove(&sensor_id);
+    }
+
+    pub fn dirty_count(&self) -> usize {
+        self.dirty.len()
+    }
+}
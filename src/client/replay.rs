@@ -0,0 +1,327 @@
+use actix::Addr;
+
+use chrono::{DateTime, NaiveDateTime};
+
+use eyre::{eyre, Result, WrapErr};
+
+use std::fmt::{Display, Formatter};
+use std::path::Path;
+use std::str::FromStr;
+use std::time::Duration;
+
+use crate::client::client::SensorVisionClient;
+use crate::client::client_queries::PushValue;
+use crate::client::state::queries::ResolveMetric;
+use crate::model::sensor::{split_target, Metric};
+use crate::model::{SensorId, Timestamp};
+
+/// Rows of a CSV without a timestamp column are replayed this far apart at 1x
+pub const DEFAULT_ROW_INTERVAL: Duration = Duration::from_secs(1);
+
+/// How fast the recorded time passes during a replay, parsed from `10x` or `max`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ReplaySpeed {
+    Factor(f64),
+    /// No pauses between the rows
+    Max,
+}
+
+impl Default for ReplaySpeed {
+    fn default() -> Self {
+        Self::Factor(1.0)
+    }
+}
+
+impl Display for ReplaySpeed {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Factor(factor) => write!(f, "{factor}x"),
+            Self::Max => write!(f, "max"),
+        }
+    }
+}
+
+impl FromStr for ReplaySpeed {
+    type Err = eyre::Report;
+
+    fn from_str(spec: &str) -> Result<Self> {
+        let spec = spec.trim();
+        if spec.eq_ignore_ascii_case("max") {
+            return Ok(Self::Max);
+        }
+        spec.strip_suffix(['x', 'X'])
+            .unwrap_or(spec)
+            .parse::<f64>()
+            .ok()
+            .filter(|factor| *factor > 0.0 && factor.is_finite())
+            .map(Self::Factor)
+            .ok_or_else(|| eyre!("Invalid speed '{spec}', expected e.g. 1x, 10x or max"))
+    }
+}
+
+impl ReplaySpeed {
+    /// Pause before the row recorded `recorded` after the previous one
+    fn pause(&self, recorded: Duration) -> Duration {
+        match self {
+            Self::Factor(factor) => recorded.div_f64(*factor),
+            Self::Max => Duration::ZERO,
+        }
+    }
+}
+
+/// CSV of historical values. The first column holds the timestamps if it's named
+/// `timestamp` or `time`, every other column is a metric.
+#[derive(Debug, Clone)]
+pub struct CsvRecording {
+    pub columns: Vec<String>,
+    pub rows: Vec<RecordedRow>,
+}
+
+#[derive(Debug, Clone)]
+pub struct RecordedRow {
+    pub timestamp: Option<Timestamp>,
+    /// One per column, empty cells are skipped
+    pub values: Vec<String>,
+}
+
+impl CsvRecording {
+    pub fn read(path: &Path) -> Result<Self> {
+        let mut rows = read_csv(path)?.into_iter();
+        let mut columns = rows
+            .next()
+            .ok_or_else(|| eyre!("{} is empty", path.display()))?
+            .cells;
+        let timestamped = columns
+            .first()
+            .is_some_and(|column| ["timestamp", "time"].contains(&column.to_lowercase().as_str()));
+        if timestamped {
+            columns.remove(0);
+        }
+
+        let rows = rows
+            .map(|CsvRow { line, mut cells }| {
+                let timestamp = if timestamped {
+                    let cell = cells.remove(0);
+                    let timestamp = parse_timestamp(&cell).ok_or_else(|| {
+                        eyre!("Invalid timestamp '{cell}' at line {line} of {}", path.display())
+                    })?;
+                    Some(timestamp)
+                } else {
+                    None
+                };
+                Ok(RecordedRow {
+                    timestamp,
+                    values: cells,
+                })
+            })
+            .collect::<Result<Vec<_>>>()?;
+        if rows.is_empty() {
+            return Err(eyre!("{} has no values to replay", path.display()));
+        }
+        Ok(Self { columns, rows })
+    }
+}
+
+/// Row of a CSV file along with the line it starts at
+#[derive(Debug, Clone, PartialEq)]
+pub struct CsvRow {
+    pub line: usize,
+    pub cells: Vec<String>,
+}
+
+/// Delimiters a CSV file may use, the first row tells which one
+const CSV_DELIMITERS: [char; 3] = [',', ';', '\t'];
+
+/// Non-empty rows of a CSV file. Cells are split on whichever of `,`, `;` or a tab the first
+/// row uses most, may be quoted with `"`, and every row must have as many as the first one.
+pub fn read_csv(path: &Path) -> Result<Vec<CsvRow>> {
+    let contents = std::fs::read_to_string(path)
+        .wrap_err_with(|| format!("Failed to read {}", path.display()))?;
+    let mut lines = contents
+        .lines()
+        .enumerate()
+        .map(|(index, line)| (index + 1, line))
+        .filter(|(_, line)| !line.trim().is_empty());
+    let Some((first_line, header)) = lines.next() else {
+        return Ok(Vec::new());
+    };
+
+    let delimiter = CSV_DELIMITERS
+        .into_iter()
+        .map(|delimiter| (delimiter, split_csv_line(header, Some(delimiter)).map_or(0, |cells| cells.len())))
+        .filter(|(_, cells)| *cells > 1)
+        .max_by_key(|(_, cells)| *cells)
+        .map(|(delimiter, _)| delimiter);
+    let split = |line_number: usize, line: &str| {
+        split_csv_line(line, delimiter)
+            .map(|cells| CsvRow { line: line_number, cells })
+            .ok_or_else(|| eyre!("Unterminated quote at line {line_number} of {}", path.display()))
+    };
+
+    let header = split(first_line, header)?;
+    let width = header.cells.len();
+    let mut rows = vec![header];
+    for (line_number, line) in lines {
+        let row = split(line_number, line)?;
+        if row.cells.len() != width {
+            return Err(eyre!(
+                "Line {line_number} of {} has {} cells rather than {width}",
+                path.display(),
+                row.cells.len()
+            ));
+        }
+        rows.push(row);
+    }
+    Ok(rows)
+}
+
+/// Trimmed cells of the line, `None` if a quote isn't closed
+fn split_csv_line(line: &str, delimiter: Option<char>) -> Option<Vec<String>> {
+    let mut cells = Vec::new();
+    let mut cell = String::new();
+    let mut quoted = false;
+    let mut chars = line.chars().peekable();
+    while let Some(char) = chars.next() {
+        match char {
+            '"' if quoted && chars.peek() == Some(&'"') => {
+                chars.next();
+                cell.push('"');
+            }
+            '"' if quoted => quoted = false,
+            '"' if cell.trim().is_empty() => {
+                cell.clear();
+                quoted = true;
+            }
+            char if !quoted && Some(char) == delimiter => {
+                cells.push(cell.trim().to_owned());
+                cell.clear();
+            }
+            char => cell.push(char),
+        }
+    }
+    if quoted {
+        return None;
+    }
+    cells.push(cell.trim().to_owned());
+    Some(cells)
+}
+
+/// Epoch seconds or milliseconds, RFC 3339 or `YYYY-MM-DD HH:MM:SS` in UTC
+fn parse_timestamp(cell: &str) -> Option<Timestamp> {
+    if let Ok(number) = cell.parse::<f64>() {
+        // Seconds since the epoch don't reach 10^11 for a few thousand years
+        let millis = if number.abs() < 1e11 {
+            number * 1000.0
+        } else {
+            number
+        };
+        return Some(Timestamp::from_millis(millis as u64));
+    }
+    let datetime = DateTime::parse_from_rfc3339(cell)
+        .map(|datetime| datetime.to_utc())
+        .or_else(|_| {
+            NaiveDateTime::parse_from_str(cell, "%Y-%m-%d %H:%M:%S").map(|naive| naive.and_utc())
+        })
+        .ok()?;
+    Some(Timestamp::from_millis(datetime.timestamp_millis() as u64))
+}
+
+/// Metric a column is pushed to
+pub struct ReplayTarget {
+    pub column: usize,
+    pub sensor_id: SensorId,
+    pub metric: Metric,
+}
+
+/// Resolves the columns to metrics. A column is mapped by `mapping` (`COLUMN=SENSOR/METRIC`)
+/// or, if there is no mapping, is named `SENSOR/METRIC` itself.
+pub async fn resolve_targets(
+    client: &Addr<SensorVisionClient>,
+    recording: &CsvRecording,
+    mapping: &[(String, String)],
+) -> Result<Vec<ReplayTarget>> {
+    let mut targets = Vec::new();
+    for (column, name) in recording.columns.iter().enumerate() {
+        let target = if mapping.is_empty() {
+            name.as_str()
+        } else {
+            match mapping.iter().find(|(mapped, _)| mapped == name) {
+                Some((_, target)) => target.as_str(),
+                None => continue,
+            }
+        };
+        let (sensor_name, metric_name) =
+            split_target(target).ok_or_else(|| eyre!("Column '{name}' must be mapped to SENSOR/METRIC"))?;
+        let (sensor_id, metric) = client
+            .send(ResolveMetric {
+                sensor_name,
+                metric_name,
+            })
+            .await?
+            .wrap_err_with(|| format!("Column '{name}'"))?;
+        targets.push(ReplayTarget {
+            column,
+            sensor_id,
+            metric,
+        });
+    }
+
+    if let Some((column, _)) = mapping
+        .iter()
+        .find(|(column, _)| !recording.columns.contains(column))
+    {
+        return Err(eyre!("No column '{column}' to map"));
+    }
+    if targets.is_empty() {
+        return Err(eyre!("No columns to replay"));
+    }
+    Ok(targets)
+}
+
+/// Pushes the rows one after another, keeping the recorded pauses between them scaled by
+/// `speed`. Values are pushed as current ones, so that they show up as livedata.
+pub async fn replay(
+    client: &Addr<SensorVisionClient>,
+    recording: &CsvRecording,
+    targets: &[ReplayTarget],
+    speed: ReplaySpeed,
+) -> Result<usize> {
+    let mut pushed = 0;
+    let mut previous: Option<&RecordedRow> = None;
+    for (index, row) in recording.rows.iter().enumerate() {
+        if let Some(previous) = previous {
+            let recorded = match (previous.timestamp, row.timestamp) {
+                (Some(from), Some(to)) => {
+                    Duration::from_millis(to.as_millis().saturating_sub(from.as_millis()))
+                }
+                _ => DEFAULT_ROW_INTERVAL,
+            };
+            tokio::time::sleep(speed.pause(recorded)).await;
+        }
+        previous = Some(row);
+
+        for target in targets {
+            let cell = &row.values[target.column];
+            if cell.is_empty() {
+                continue;
+            }
+            let value = target.metric.parse_value(cell).wrap_err_with(|| {
+                format!(
+                    "Failed to parse '{cell}' in row {} for {}",
+                    index + 1,
+                    target.metric.name()
+                )
+            })?;
+            client
+                .send(PushValue {
+                    sensor_id: target.sensor_id,
+                    metric_id: *target.metric.metric_id(),
+                    value,
+                    timestamp: None,
+                })
+                .await??;
+            pushed += 1;
+        }
+    }
+    Ok(pushed)
+}
@@ -0,0 +1,90 @@
+use actix::{Actor, ActorFutureExt, Addr, AsyncContext, Context, WrapFuture};
+
+use eyre::Result;
+
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+
+use crate::client::client::SensorVisionClient;
+use crate::client::state::{GetAlertRules, SetAlertRules};
+use crate::config::read_alert_rules;
+
+const CHECK_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Applies the rules of the alerts file again whenever the file changes, e.g. once reviewed
+/// rules are checked out. The rules in force are kept while the file is invalid.
+pub struct AlertRulesWatcher {
+    client: Addr<SensorVisionClient>,
+    path: PathBuf,
+    modified: Option<SystemTime>,
+    reloading: bool,
+}
+
+impl AlertRulesWatcher {
+    /// The rules read from `path` already are only applied again once it changes
+    pub fn new(client: Addr<SensorVisionClient>, path: PathBuf) -> Self {
+        let modified = modified_at(&path);
+        Self {
+            client,
+            path,
+            modified,
+            reloading: false,
+        }
+    }
+
+    fn check(&mut self, ctx: &mut Context<Self>) {
+        if self.reloading {
+            return;
+        }
+        let modified = modified_at(&self.path);
+        if modified.is_none() || modified == self.modified {
+            return;
+        }
+        self.modified = modified;
+
+        let rules = match read_alert_rules(&self.path) {
+            Ok(rules) => rules,
+            Err(err) => {
+                log::warn!("Keeping the alert rules in force: {err:#}");
+                return;
+            }
+        };
+        self.reloading = true;
+        let client = self.client.clone();
+        ctx.spawn(
+            async move {
+                // Rules saved by the TUI are in force already, setting them again would clear
+                // the raised alerts
+                if client.send(GetAlertRules).await?? == rules {
+                    return Ok(false);
+                }
+                client.send(SetAlertRules(rules)).await??;
+                Ok(true)
+            }
+            .into_actor(self)
+            .map(|result: Result<bool>, act, _| {
+                act.reloading = false;
+                match result {
+                    Ok(true) => log::info!("Reloaded the alert rules of {}", act.path.display()),
+                    Ok(false) => {}
+                    Err(err) => log::warn!(
+                        "Failed to reload the alert rules of {}: {err:#}",
+                        act.path.display()
+                    ),
+                }
+            }),
+        );
+    }
+}
+
+fn modified_at(path: &Path) -> Option<SystemTime> {
+    std::fs::metadata(path).and_then(|metadata| metadata.modified()).ok()
+}
+
+impl Actor for AlertRulesWatcher {
+    type Context = Context<Self>;
+
+    fn started(&mut self, ctx: &mut Self::Context) {
+        ctx.run_interval(CHECK_INTERVAL, Self::check);
+    }
+}
@@ -0,0 +1,335 @@
+use actix::{Actor, Addr, AsyncContext, Context, Handler, Message, SpawnHandle};
+
+use eyre::{eyre, Result};
+
+use std::collections::BTreeMap;
+use std::f64::consts::TAU;
+use std::fmt::{Display, Formatter};
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use crate::client::client::SensorVisionClient;
+use crate::client::client_queries::PushValue;
+use crate::client::replay::read_csv;
+use crate::model::protocol::MetricValue;
+use crate::model::sensor::Metric;
+use crate::model::{MetricId, SensorId};
+
+pub const DEFAULT_SIMULATION_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Source of the simulated values, parsed from
+/// `constant:VALUE`, `sine:AMPLITUDE:PERIOD_SECS[:OFFSET]`, `walk:START:STEP` or `csv:FILE`
+#[derive(Debug, Clone, PartialEq)]
+pub enum Generator {
+    Constant(String),
+    Sine {
+        amplitude: f64,
+        period: Duration,
+        offset: f64,
+    },
+    RandomWalk {
+        start: f64,
+        step: f64,
+    },
+    /// The last column of every row is replayed over and over
+    CsvReplay {
+        path: PathBuf,
+        values: Vec<String>,
+    },
+}
+
+impl Display for Generator {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Constant(value) => write!(f, "constant:{value}"),
+            Self::Sine {
+                amplitude,
+                period,
+                offset,
+            } => write!(f, "sine:{amplitude}:{}:{offset}", period.as_secs_f64()),
+            Self::RandomWalk { start, step } => write!(f, "walk:{start}:{step}"),
+            Self::CsvReplay { path, .. } => write!(f, "csv:{}", path.display()),
+        }
+    }
+}
+
+impl FromStr for Generator {
+    type Err = eyre::Report;
+
+    fn from_str(spec: &str) -> Result<Self> {
+        let number = |part: Option<&str>, name: &str| -> Result<f64> {
+            let part = part.ok_or_else(|| eyre!("Generator '{spec}' lacks the {name}"))?;
+            part.trim()
+                .parse()
+                .map_err(|_| eyre!("Invalid {name} '{part}' in generator '{spec}'"))
+        };
+
+        let (kind, args) = spec.split_once(':').unwrap_or((spec, ""));
+        let mut args_iter = args.split(':');
+        match kind.trim() {
+            "constant" if !args.is_empty() => Ok(Self::Constant(args.to_owned())),
+            "sine" => {
+                let amplitude = number(args_iter.next(), "amplitude")?;
+                let period = number(args_iter.next(), "period")?;
+                if period <= 0.0 || !period.is_finite() {
+                    return Err(eyre!("Period in generator '{spec}' must be positive"));
+                }
+                let offset = args_iter
+                    .next()
+                    .map_or(Ok(0.0), |offset| number(Some(offset), "offset"))?;
+                Ok(Self::Sine {
+                    amplitude,
+                    period: Duration::from_secs_f64(period),
+                    offset,
+                })
+            }
+            "walk" => Ok(Self::RandomWalk {
+                start: number(args_iter.next(), "start")?,
+                step: number(args_iter.next(), "step")?,
+            }),
+            "csv" if !args.is_empty() => Self::csv_replay(Path::new(args)),
+            _ => Err(eyre!(
+                "Unknown generator '{spec}', expected constant:VALUE, sine:AMPLITUDE:PERIOD_SECS[:OFFSET], walk:START:STEP or csv:FILE"
+            )),
+        }
+    }
+}
+
+impl Generator {
+    fn csv_replay(path: &Path) -> Result<Self> {
+        let values: Vec<String> = read_csv(path)?
+            .into_iter()
+            .filter_map(|row| row.cells.into_iter().next_back())
+            .collect();
+        if values.is_empty() {
+            return Err(eyre!("{} has no values to replay", path.display()));
+        }
+        Ok(Self::CsvReplay {
+            path: path.to_owned(),
+            values,
+        })
+    }
+
+    /// `GENERATOR[@SECS]`, the interval defaults to [`DEFAULT_SIMULATION_INTERVAL`]
+    pub fn parse_with_interval(spec: &str) -> Result<(Self, Duration)> {
+        let Some((generator, interval)) = spec.rsplit_once('@') else {
+            return Ok((spec.parse()?, DEFAULT_SIMULATION_INTERVAL));
+        };
+        let interval = interval
+            .trim()
+            .parse::<f64>()
+            .ok()
+            .filter(|secs| *secs > 0.0 && secs.is_finite())
+            .ok_or_else(|| eyre!("Invalid interval '{interval}', expected positive seconds"))?;
+        Ok((generator.parse()?, Duration::from_secs_f64(interval)))
+    }
+}
+
+/// Tiny xorshift generator, the walk doesn't need anything better
+struct Rng(u64);
+
+impl Rng {
+    fn seeded() -> Self {
+        let nanos = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_nanos() as u64;
+        Self(nanos | 1)
+    }
+
+    /// Uniform in [-1, 1)
+    fn next_signed(&mut self) -> f64 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        (self.0 >> 11) as f64 / (1u64 << 52) as f64 - 1.0
+    }
+}
+
+struct Simulation {
+    metric: Metric,
+    generator: Generator,
+    interval: Duration,
+
+    started_at: Instant,
+    tick: usize,
+    walk_value: f64,
+    rng: Rng,
+
+    handle: SpawnHandle,
+}
+
+impl Simulation {
+    fn next_value(&mut self) -> Result<MetricValue> {
+        let raw = match &self.generator {
+            Generator::Constant(value) => return self.metric.parse_value(value),
+            Generator::CsvReplay { values, .. } => {
+                let value = &values[self.tick % values.len()];
+                return self.metric.parse_value(value);
+            }
+            Generator::Sine {
+                amplitude,
+                period,
+                offset,
+            } => {
+                let phase = self.started_at.elapsed().as_secs_f64() / period.as_secs_f64();
+                offset + amplitude * (TAU * phase).sin()
+            }
+            Generator::RandomWalk { start, step } => {
+                if self.tick == 0 {
+                    self.walk_value = *start;
+                } else {
+                    self.walk_value += step * self.rng.next_signed();
+                }
+                self.walk_value
+            }
+        };
+        Ok(self.metric.numeric_value(raw))
+    }
+}
+
+/// Pushes generated values to metrics periodically, at most one simulation per metric
+pub struct SimulatorActor {
+    client: Addr<SensorVisionClient>,
+    simulations: BTreeMap<(SensorId, MetricId), Simulation>,
+}
+
+#[derive(Message)]
+#[rtype(result = "()")]
+pub struct StartSimulation {
+    pub sensor_id: SensorId,
+    pub metric: Metric,
+    pub generator: Generator,
+    pub interval: Duration,
+}
+
+/// Returns whether there was a simulation to stop
+#[derive(Message)]
+#[rtype(result = "bool")]
+pub struct StopSimulation {
+    pub sensor_id: SensorId,
+    pub metric_id: MetricId,
+}
+
+/// Generator and interval of the metric's simulation, if any
+#[derive(Message)]
+#[rtype(result = "Option<(Generator, Duration)>")]
+pub struct GetSimulation {
+    pub sensor_id: SensorId,
+    pub metric_id: MetricId,
+}
+
+impl SimulatorActor {
+    pub fn new(client: Addr<SensorVisionClient>) -> Self {
+        Self {
+            client,
+            simulations: BTreeMap::new(),
+        }
+    }
+
+    fn push_next(&mut self, key: (SensorId, MetricId), ctx: &mut Context<Self>) {
+        let Some(simulation) = self.simulations.get_mut(&key) else {
+            return;
+        };
+        let (sensor_id, metric_id) = key;
+        let value = simulation.next_value();
+        simulation.tick += 1;
+
+        match value {
+            Ok(value) => self.client.do_send(PushValue {
+                sensor_id,
+                metric_id,
+                value,
+                timestamp: None,
+            }),
+            Err(err) => {
+                log::error!("Stopping the simulation of {sensor_id}/{metric_id}: {err}");
+                if let Some(simulation) = self.simulations.remove(&key) {
+                    ctx.cancel_future(simulation.handle);
+                }
+            }
+        }
+    }
+}
+
+impl Actor for SimulatorActor {
+    type Context = Context<Self>;
+}
+
+impl Handler<StartSimulation> for SimulatorActor {
+    type Result = ();
+
+    fn handle(
+        &mut self,
+        StartSimulation {
+            sensor_id,
+            metric,
+            generator,
+            interval,
+        }: StartSimulation,
+        ctx: &mut Self::Context,
+    ) -> Self::Result {
+        let key = (sensor_id, *metric.metric_id());
+        if let Some(simulation) = self.simulations.remove(&key) {
+            ctx.cancel_future(simulation.handle);
+        }
+
+        log::info!(
+            "Simulating {sensor_id}/{} with {generator:?} every {interval:?}",
+            metric.name()
+        );
+        let handle = ctx.run_interval(interval, move |this, ctx| this.push_next(key, ctx));
+        self.simulations.insert(
+            key,
+            Simulation {
+                metric,
+                generator,
+                interval,
+                started_at: Instant::now(),
+                tick: 0,
+                walk_value: 0.0,
+                rng: Rng::seeded(),
+                handle,
+            },
+        );
+        self.push_next(key, ctx);
+    }
+}
+
+impl Handler<StopSimulation> for SimulatorActor {
+    type Result = bool;
+
+    fn handle(
+        &mut self,
+        StopSimulation {
+            sensor_id,
+            metric_id,
+        }: StopSimulation,
+        ctx: &mut Self::Context,
+    ) -> Self::Result {
+        let Some(simulation) = self.simulations.remove(&(sensor_id, metric_id)) else {
+            return false;
+        };
+        log::info!("Stopped simulating {sensor_id}/{metric_id}");
+        ctx.cancel_future(simulation.handle);
+        true
+    }
+}
+
+impl Handler<GetSimulation> for SimulatorActor {
+    type Result = Option<(Generator, Duration)>;
+
+    fn handle(
+        &mut self,
+        GetSimulation {
+            sensor_id,
+            metric_id,
+        }: GetSimulation,
+        _: &mut Self::Context,
+    ) -> Self::Result {
+        self.simulations
+            .get(&(sensor_id, metric_id))
+            .map(|simulation| (simulation.generator.clone(), simulation.interval))
+    }
+}
@@ -0,0 +1,684 @@
+use actix::{Handler, Message};
+
+use eyre::{eyre, Result, WrapErr};
+
+use regex::Regex;
+
+use schemars::JsonSchema;
+
+use serde::{Deserialize, Serialize};
+
+use std::collections::{HashMap, HashSet};
+use std::fmt::{Display, Formatter};
+use std::process::Stdio;
+
+use crate::client::state::{Maintenance, SensorStateEvent, SensorsStateActor, StateStore};
+use crate::model::protocol::MetricValue;
+use crate::model::{MetricId, SensorId, Timestamp};
+use crate::utils::glob_match;
+
+/// Raises an alert once the livedata of the matching metrics leaves `min..max`
+/// or matches the `matches` regular expression
+#[derive(Debug, Clone, Default, PartialEq, Deserialize, JsonSchema, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct AlertRule {
+    /// Sensor name, may contain * and ? wildcards
+    pub sensor: String,
+
+    /// Metric name, may contain * and ? wildcards
+    pub metric: String,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub min: Option<f64>,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max: Option<f64>,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub matches: Option<String>,
+
+    /// How long the condition has to hold before the alert is raised, right away if unset
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub for_secs: Option<u64>,
+
+    /// Critical alerts raise a desktop notification, if enabled, while the terminal is unfocused
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub critical: bool,
+
+    /// Names of the notification sinks the alerts are delivered to regardless of their severity
+    /// thresholds, every routed sink if empty
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub sinks: Vec<String>,
+
+    /// Shell command run on every raised alert with `SV_SENSOR`, `SV_METRIC`, `SV_VALUE`
+    /// and `SV_ALERT` set
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub hook: Option<String>,
+}
+
+impl AlertRule {
+    /// Rule for the metric from a `MIN..MAX` (either end optional) or `~REGEX` condition
+    pub fn with_condition(sensor: String, metric: String, condition: &str) -> Result<Self> {
+        let mut rule = Self {
+            sensor,
+            metric,
+            ..Self::default()
+        };
+
+        let condition = condition.trim();
+        if let Some(pattern) = condition.strip_prefix('~') {
+            Regex::new(pattern).wrap_err_with(|| format!("Invalid pattern '{pattern}'"))?;
+            rule.matches = Some(pattern.to_owned());
+            return Ok(rule);
+        }
+
+        let (min, max) = condition
+            .split_once("..")
+            .ok_or_else(|| eyre!("Expected MIN..MAX or ~REGEX, got '{condition}'"))?;
+        let bound = |bound: &str| -> Result<Option<f64>> {
+            let bound = bound.trim();
+            if bound.is_empty() {
+                return Ok(None);
+            }
+            bound
+                .parse()
+                .map(Some)
+                .map_err(|_| eyre!("Invalid threshold '{bound}'"))
+        };
+        rule.min = bound(min)?;
+        rule.max = bound(max)?;
+        if rule.min.is_none() && rule.max.is_none() {
+            return Err(eyre!("Either threshold is required"));
+        }
+        Ok(rule)
+    }
+
+    /// Rule from a Prometheus-like `SELECTOR OP THRESHOLD` expression, such as
+    /// `Temperature{sensor="Boiler*"} > 90` or `{metric="Status"} =~ "(?i)fault"`. The sensor
+    /// is `*` unless selected. `>` and `<` set `max` and `min`, `>=` and `<=` the closest bounds
+    /// short of the threshold, and `=~` sets `matches`.
+    pub fn with_expression(expr: &str) -> Result<Self> {
+        let (sensor, metric, rest) = parse_selector(expr.trim())
+            .wrap_err_with(|| format!("Invalid selector in '{expr}'"))?;
+        let mut rule = Self {
+            sensor: sensor.unwrap_or_else(|| String::from("*")),
+            metric: metric.ok_or_else(|| eyre!("'{expr}' selects no metric"))?,
+            ..Self::default()
+        };
+
+        let rest = rest.trim_start();
+        let (operator, threshold) = ["=~", ">=", "<=", ">", "<"]
+            .into_iter()
+            .find_map(|operator| Some((operator, rest.strip_prefix(operator)?.trim())))
+            .ok_or_else(|| eyre!("Expected one of =~, >=, <=, > and < in '{expr}'"))?;
+        if operator == "=~" {
+            let (pattern, rest) = parse_quoted(threshold)
+                .ok_or_else(|| eyre!("Expected a quoted pattern in '{expr}'"))?;
+            if !rest.trim().is_empty() {
+                return Err(eyre!("Unexpected '{}' in '{expr}'", rest.trim()));
+            }
+            Regex::new(&pattern).wrap_err_with(|| format!("Invalid pattern '{pattern}'"))?;
+            rule.matches = Some(pattern);
+            return Ok(rule);
+        }
+
+        let threshold: f64 = threshold
+            .parse()
+            .map_err(|_| eyre!("Invalid threshold '{threshold}'"))?;
+        match operator {
+            ">" => rule.max = Some(threshold),
+            ">=" => rule.max = Some(threshold.next_down()),
+            "<" => rule.min = Some(threshold),
+            _ => rule.min = Some(threshold.next_up()),
+        }
+        Ok(rule)
+    }
+
+    /// Inverse of the condition [`AlertRule::with_condition`] takes
+    pub fn condition(&self) -> String {
+        if let Some(pattern) = &self.matches {
+            return format!("~{pattern}");
+        }
+        let bound = |bound: Option<f64>| bound.map(|bound| bound.to_string()).unwrap_or_default();
+        format!("{}..{}", bound(self.min), bound(self.max))
+    }
+
+    pub fn applies_to(&self, sensor_name: &str, metric_name: &str) -> bool {
+        glob_match(&self.sensor, sensor_name) && glob_match(&self.metric, metric_name)
+    }
+
+    /// Catches the rules which would never raise an alert or fail to compile
+    pub fn validate(&self) -> Result<()> {
+        if self.sensor.is_empty() || self.metric.is_empty() {
+            return Err(eyre!("Sensor and metric patterns are required"));
+        }
+        if self.min.is_none() && self.max.is_none() && self.matches.is_none() {
+            return Err(eyre!("One of min, max and matches is required"));
+        }
+        if let (Some(min), Some(max)) = (self.min, self.max)
+            && min > max
+        {
+            return Err(eyre!("min {min} is above max {max}"));
+        }
+        CompiledRule::new(self.clone()).map(|_| ())
+    }
+
+    /// What's wrong with `value` according to the rule, if anything
+    pub fn violation(&self, value: &MetricValue) -> Result<Option<String>> {
+        Ok(CompiledRule::new(self.clone())?.violation(value))
+    }
+
+    fn key(&self) -> (&str, &str) {
+        (&self.sensor, &self.metric)
+    }
+}
+
+impl Display for AlertRule {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}/{} {}", self.sensor, self.metric, self.condition())?;
+        if let Some(for_secs) = self.for_secs {
+            write!(f, " for {for_secs}s")?;
+        }
+        if self.critical {
+            write!(f, " critical")?;
+        }
+        if !self.sinks.is_empty() {
+            write!(f, " to {}", self.sinks.join(","))?;
+        }
+        if let Some(hook) = &self.hook {
+            write!(f, " hook '{hook}'")?;
+        }
+        Ok(())
+    }
+}
+
+/// Sensor and metric patterns of a `METRIC{sensor="...", metric="..."}` selector, where either
+/// part is optional, and what follows the selector
+fn parse_selector(expr: &str) -> Result<(Option<String>, Option<String>, &str)> {
+    let name_end = expr
+        .find(|c: char| c == '{' || c.is_whitespace() || "<>=!".contains(c))
+        .unwrap_or(expr.len());
+    let (name, mut rest) = expr.split_at(name_end);
+    let mut sensor = None;
+    let mut metric = (!name.is_empty()).then(|| name.to_owned());
+
+    let Some(labels) = rest.strip_prefix('{') else {
+        return Ok((sensor, metric, rest));
+    };
+    rest = labels;
+    loop {
+        rest = rest.trim_start();
+        if let Some(after) = rest.strip_prefix('}') {
+            return Ok((sensor, metric, after));
+        }
+        let (label, after) = rest
+            .split_once('=')
+            .ok_or_else(|| eyre!("Expected LABEL=\"PATTERN\" or }}"))?;
+        let (pattern, after) =
+            parse_quoted(after.trim_start()).ok_or_else(|| eyre!("Expected a quoted pattern"))?;
+        let part = match label.trim() {
+            "sensor" => &mut sensor,
+            "metric" => &mut metric,
+            label => return Err(eyre!("Unknown label '{label}', expected sensor or metric")),
+        };
+        if part.replace(pattern).is_some() {
+            return Err(eyre!("The {} is selected twice", label.trim()));
+        }
+        rest = after.trim_start();
+        rest = rest.strip_prefix(',').unwrap_or(rest);
+    }
+}
+
+/// Contents of the leading double-quoted string, where `\"` and `\\` are unescaped,
+/// and what follows it
+fn parse_quoted(text: &str) -> Option<(String, &str)> {
+    let text = text.strip_prefix('"')?;
+    let mut unquoted = String::new();
+    let mut chars = text.char_indices();
+    while let Some((index, c)) = chars.next() {
+        match c {
+            '"' => return Some((unquoted, &text[index + 1..])),
+            '\\' => match chars.next()? {
+                (_, escaped @ ('"' | '\\')) => unquoted.push(escaped),
+                (_, other) => {
+                    unquoted.push('\\');
+                    unquoted.push(other);
+                }
+            },
+            c => unquoted.push(c),
+        }
+    }
+    None
+}
+
+/// Difference between two rule lists, rules are matched by their sensor and metric patterns
+#[derive(Debug, Clone, PartialEq)]
+pub enum AlertRuleChange {
+    Added(AlertRule),
+    Removed(AlertRule),
+    Changed { from: AlertRule, to: AlertRule },
+}
+
+impl Display for AlertRuleChange {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        use AlertRuleChange::*;
+        match self {
+            Added(rule) => write!(f, "+ {rule}"),
+            Removed(rule) => write!(f, "- {rule}"),
+            Changed { from, to } => write!(f, "~ {from} → {to}"),
+        }
+    }
+}
+
+pub fn diff_alert_rules(before: &[AlertRule], after: &[AlertRule]) -> Vec<AlertRuleChange> {
+    use AlertRuleChange::*;
+
+    let find = |rules: &[AlertRule], rule: &AlertRule| {
+        rules.iter().find(|other| other.key() == rule.key()).cloned()
+    };
+    let mut changes = Vec::new();
+    for old_rule in before {
+        match find(after, old_rule) {
+            None => changes.push(Removed(old_rule.clone())),
+            Some(new_rule) if new_rule != *old_rule => changes.push(Changed {
+                from: old_rule.clone(),
+                to: new_rule,
+            }),
+            Some(_) => {}
+        }
+    }
+    for new_rule in after {
+        if find(before, new_rule).is_none() {
+            changes.push(Added(new_rule.clone()));
+        }
+    }
+    changes
+}
+
+/// Rule with its pattern compiled
+struct CompiledRule {
+    rule: AlertRule,
+    pattern: Option<Regex>,
+}
+
+impl CompiledRule {
+    fn new(rule: AlertRule) -> Result<Self> {
+        let pattern = rule
+            .matches
+            .as_deref()
+            .map(Regex::new)
+            .transpose()
+            .wrap_err_with(|| {
+                format!("Invalid alert pattern for {}/{}", rule.sensor, rule.metric)
+            })?;
+        Ok(Self { rule, pattern })
+    }
+
+    /// What's wrong with the value, if anything
+    fn violation(&self, value: &MetricValue) -> Option<String> {
+        if let Some(pattern) = &self.pattern
+            && pattern.is_match(&value.to_string())
+        {
+            return Some(format!("matches '{pattern}'"));
+        }
+
+        let number = value.as_f64()?;
+        if let Some(min) = self.rule.min
+            && number < min
+        {
+            return Some(format!("is below {min}"));
+        }
+        if let Some(max) = self.rule.max
+            && number > max
+        {
+            return Some(format!("is above {max}"));
+        }
+        None
+    }
+}
+
+/// Alert raised by a rule, as kept in the history of the state store
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct AlertRecord {
+    pub sensor_id: SensorId,
+    pub metric_id: MetricId,
+    /// Rule which raised it, as displayed
+    pub rule: String,
+    pub message: String,
+    pub critical: bool,
+    pub fired_at: Timestamp,
+    /// None while the alert is in force
+    pub resolved_at: Option<Timestamp>,
+    /// Raised during maintenance, so it wasn't notified
+    pub silenced: bool,
+}
+
+impl AlertRecord {
+    fn key(&self) -> (Timestamp, SensorId, MetricId, &str) {
+        (self.fired_at, self.sensor_id, self.metric_id, &self.rule)
+    }
+}
+
+/// Merges the alert histories of several stores, oldest first. Stores sharing a history file
+/// report the same alerts, which are kept once.
+pub fn merge_alert_histories(histories: impl IntoIterator<Item = Vec<AlertRecord>>) -> Vec<AlertRecord> {
+    let mut merged: Vec<AlertRecord> = histories.into_iter().flatten().collect();
+    merged.sort_by(|a, b| a.key().cmp(&b.key()));
+    merged.dedup_by(|a, b| a.key() == b.key());
+    merged
+}
+
+/// Rules applying to a metric, along with the names they were matched against
+struct MetricRules {
+    sensor_name: String,
+    metric_name: String,
+    /// Indices into [`Alerts::rules`]
+    rules: Vec<usize>,
+}
+
+/// Rules of a state actor along with the ones currently in alert, by metric and rule index
+#[derive(Default)]
+pub(super) struct Alerts {
+    rules: Vec<CompiledRule>,
+
+    /// Rules of every metric livedata came for, matched against the names in the store once.
+    /// None for metrics the store doesn't know.
+    by_metric: HashMap<(SensorId, MetricId), Option<MetricRules>>,
+
+    raised: HashSet<(SensorId, MetricId, usize)>,
+
+    /// Violated rules waiting for `for_secs` to pass, since the first violating value
+    pending: HashMap<(SensorId, MetricId, usize), Timestamp>,
+
+    /// Rules in alert according to the history when the actor started, by metric. They're
+    /// resumed on the first livedata of the metric if still violated, resolved otherwise.
+    restored: HashMap<(SensorId, MetricId), HashSet<String>>,
+
+    pub(super) maintenance: Maintenance,
+}
+
+impl Alerts {
+    /// Picks up the alerts the history has in force, e.g. raised before a restart
+    pub(super) fn restore(store: &dyn StateStore) -> Self {
+        let mut restored: HashMap<_, HashSet<_>> = HashMap::new();
+        match store.alert_history(Timestamp::now()) {
+            Ok(history) => {
+                for alert in history.into_iter().filter(|alert| alert.resolved_at.is_none()) {
+                    restored
+                        .entry((alert.sensor_id, alert.metric_id))
+                        .or_default()
+                        .insert(alert.rule);
+                }
+            }
+            Err(err) => log::error!("Failed to read the alerts in force: {err}"),
+        }
+        Self {
+            restored,
+            ..Self::default()
+        }
+    }
+
+    /// Has the rules matched again, as sensors and metrics may have been renamed or added
+    pub(super) fn forget_metrics(&mut self) {
+        self.by_metric.clear();
+    }
+}
+
+/// Replaces all the rules, alerts raised so far are cleared
+#[derive(Message)]
+#[rtype(result = "Result<()>")]
+pub struct SetAlertRules(pub Vec<AlertRule>);
+
+#[derive(Message)]
+#[rtype(result = "Result<Vec<AlertRule>>")]
+pub struct GetAlertRules;
+
+/// Alerts still in force and the ones resolved since `since`, oldest first
+#[derive(Message)]
+#[rtype(result = "Result<Vec<AlertRecord>>")]
+pub struct GetAlertHistory {
+    pub since: Timestamp,
+}
+
+impl SensorsStateActor {
+    /// Every rule applying to the metric is checked, each raising an alert of its own.
+    /// Alerts are only raised when the value crosses the threshold, not on every violating value,
+    /// and the metric is cleared once none of its rules is in alert anymore.
+    pub(super) fn check_alerts(
+        &mut self,
+        sensor_id: SensorId,
+        metric_id: MetricId,
+        value: &MetricValue,
+        timestamp: Timestamp,
+    ) -> Result<()> {
+        let key = (sensor_id, metric_id);
+        let mut restored = self.alerts.restored.remove(&key).unwrap_or_default();
+        if self.alerts.rules.is_empty() {
+            self.resolve_restored(sensor_id, metric_id, restored, timestamp);
+            return Ok(());
+        }
+        if !self.alerts.by_metric.contains_key(&key) {
+            let metric_rules = self.match_alert_rules(&sensor_id, &metric_id)?;
+            self.alerts.by_metric.insert(key, metric_rules);
+        }
+        let Some(Some(metric_rules)) = self.alerts.by_metric.get(&key) else {
+            self.resolve_restored(sensor_id, metric_id, restored, timestamp);
+            return Ok(());
+        };
+
+        let mut raised = Vec::new();
+        let mut cleared = Vec::new();
+        for &index in &metric_rules.rules {
+            let compiled = &self.alerts.rules[index];
+            let rule_key = (sensor_id, metric_id, index);
+            match compiled.violation(value) {
+                Some(_) if self.alerts.raised.contains(&rule_key) => {}
+                // Recorded and notified before the restart already
+                Some(_) if restored.remove(&compiled.rule.to_string()) => {
+                    self.alerts.raised.insert(rule_key);
+                }
+                Some(mut violation) => {
+                    if let Some(for_secs) = compiled.rule.for_secs {
+                        let since = *self.alerts.pending.entry(rule_key).or_insert(timestamp);
+                        if timestamp.as_millis().saturating_sub(since.as_millis()) < for_secs * 1000 {
+                            continue;
+                        }
+                        violation = format!("{violation} for {for_secs}s");
+                    }
+                    self.alerts.pending.remove(&rule_key);
+                    self.alerts.raised.insert(rule_key);
+                    raised.push((violation, compiled.rule.clone()));
+                }
+                None if self.alerts.pending.remove(&rule_key).is_some() => {}
+                None if self.alerts.raised.remove(&rule_key) => cleared.push(compiled.rule.to_string()),
+                None => {}
+            }
+        }
+        // Rules which no longer hold or no longer exist
+        cleared.extend(restored);
+        if raised.is_empty() && cleared.is_empty() {
+            return Ok(());
+        }
+
+        let (sensor_name, metric_name) = (metric_rules.sensor_name.clone(), metric_rules.metric_name.clone());
+        let silenced = self
+            .alerts
+            .maintenance
+            .silencing
+            .silences(&sensor_name, &metric_name, Timestamp::now());
+        for rule in &cleared {
+            if let Err(err) = self.store.resolve_alerts(&sensor_id, &metric_id, Some(rule), timestamp) {
+                log::error!("Failed to record the resolved alert: {err}");
+            }
+        }
+        for (violation, rule) in raised {
+            let message = format!("{sensor_name}/{metric_name} = {value} {violation}");
+            if silenced {
+                log::info!("Alert during maintenance: {message}");
+            } else {
+                log::warn!("Alert: {message}");
+            }
+            if let Err(err) = self.store.record_alert(&AlertRecord {
+                sensor_id,
+                metric_id,
+                rule: rule.to_string(),
+                message: message.clone(),
+                critical: rule.critical,
+                fired_at: timestamp,
+                resolved_at: None,
+                silenced,
+            }) {
+                log::error!("Failed to record the alert: {err}");
+            }
+            if let Some(hook) = rule.hook
+                && !silenced
+            {
+                run_hook(hook, &sensor_name, &metric_name, value, &message);
+            }
+            self.emit_event(SensorStateEvent::AlertRaised {
+                sensor_id,
+                metric_id,
+                message,
+                critical: rule.critical,
+                sinks: rule.sinks,
+                value: value.clone(),
+                timestamp,
+                silenced,
+            });
+        }
+        let still_raised = self
+            .alerts
+            .raised
+            .iter()
+            .any(|(sens_id, metr_id, _)| (*sens_id, *metr_id) == key);
+        if !cleared.is_empty() && !still_raised {
+            log::info!("Alert cleared: {sensor_name}/{metric_name} = {value}");
+            self.emit_event(SensorStateEvent::AlertCleared {
+                sensor_id,
+                metric_id,
+            });
+        }
+        Ok(())
+    }
+
+    /// Resolves the restored alerts of a metric no rule applies to anymore
+    fn resolve_restored(&mut self, sensor_id: SensorId, metric_id: MetricId, restored: HashSet<String>, at: Timestamp) {
+        if restored.is_empty() {
+            return;
+        }
+        if let Err(err) = self.store.resolve_alerts(&sensor_id, &metric_id, None, at) {
+            log::error!("Failed to record the resolved alert: {err}");
+        }
+        self.emit_event(SensorStateEvent::AlertCleared {
+            sensor_id,
+            metric_id,
+        });
+    }
+
+    fn match_alert_rules(&self, sensor_id: &SensorId, metric_id: &MetricId) -> Result<Option<MetricRules>> {
+        let Some(sensor) = self.store.sensor(sensor_id)? else {
+            return Ok(None);
+        };
+        let Some(metric) = sensor.metrics.get(metric_id) else {
+            return Ok(None);
+        };
+        let rules = self
+            .alerts
+            .rules
+            .iter()
+            .enumerate()
+            .filter(|(_, compiled)| compiled.rule.applies_to(&sensor.name, metric.name()))
+            .map(|(index, _)| index)
+            .collect();
+        Ok(Some(MetricRules {
+            sensor_name: sensor.name.clone(),
+            metric_name: metric.name().to_owned(),
+            rules,
+        }))
+    }
+}
+
+fn run_hook(
+    hook: String,
+    sensor_name: &str,
+    metric_name: &str,
+    value: &MetricValue,
+    message: &str,
+) {
+    let mut command = tokio::process::Command::new("sh");
+    command
+        .arg("-c")
+        .arg(&hook)
+        .env("SV_SENSOR", sensor_name)
+        .env("SV_METRIC", metric_name)
+        .env("SV_VALUE", value.to_string())
+        .env("SV_ALERT", message)
+        // Anything printed would mess up the UI
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null());
+    actix::spawn(async move {
+        match command.status().await {
+            Ok(status) if !status.success() => {
+                log::warn!("Alert hook '{hook}' exited with {status}")
+            }
+            Ok(_) => {}
+            Err(err) => log::error!("Failed to run alert hook '{hook}': {err}"),
+        }
+    });
+}
+
+impl Handler<SetAlertRules> for SensorsStateActor {
+    type Result = Result<()>;
+
+    fn handle(
+        &mut self,
+        SetAlertRules(rules): SetAlertRules,
+        _: &mut Self::Context,
+    ) -> Self::Result {
+        let rules = rules
+            .into_iter()
+            .map(CompiledRule::new)
+            .collect::<Result<Vec<_>>>()?;
+        self.alerts.rules = rules;
+        self.alerts.by_metric.clear();
+        self.alerts.pending.clear();
+
+        let raised: HashSet<(SensorId, MetricId)> = std::mem::take(&mut self.alerts.raised)
+            .into_iter()
+            .map(|(sensor_id, metric_id, _)| (sensor_id, metric_id))
+            .collect();
+        let now = Timestamp::now();
+        for (sensor_id, metric_id) in raised {
+            if let Err(err) = self.store.resolve_alerts(&sensor_id, &metric_id, None, now) {
+                log::error!("Failed to record the resolved alert: {err}");
+            }
+            self.emit_event(SensorStateEvent::AlertCleared {
+                sensor_id,
+                metric_id,
+            });
+        }
+        Ok(())
+    }
+}
+
+impl Handler<GetAlertRules> for SensorsStateActor {
+    type Result = Result<Vec<AlertRule>>;
+
+    fn handle(&mut self, _: GetAlertRules, _: &mut Self::Context) -> Self::Result {
+        Ok(self
+            .alerts
+            .rules
+            .iter()
+            .map(|compiled| compiled.rule.clone())
+            .collect())
+    }
+}
+
+impl Handler<GetAlertHistory> for SensorsStateActor {
+    type Result = Result<Vec<AlertRecord>>;
+
+    fn handle(&mut self, GetAlertHistory { since }: GetAlertHistory, _: &mut Self::Context) -> Self::Result {
+        self.store.alert_history(since).wrap_err("Failed to read the alert history")
+    }
+}
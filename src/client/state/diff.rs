@@ -0,0 +1,561 @@
+use eyre::{eyre, Result};
+
+use schemars::JsonSchema;
+
+use serde::Deserialize;
+
+use std::collections::{BTreeMap, HashSet};
+use std::fmt::{Display, Formatter};
+use std::time::Duration;
+
+use crate::client::state::{LastLivedata, Sensors};
+use crate::model::sensor::{Metric, Sensor, SensorState};
+use crate::model::{MetricId, SensorId, Timestamp};
+
+/// Difference between two snapshots of the same connector's inventory.
+/// Sensors and metrics are matched by their ids, so renames are told apart from replacements.
+#[derive(Debug, Clone, PartialEq)]
+pub enum InventoryChange {
+    SensorAdded {
+        sensor: String,
+    },
+    SensorRemoved {
+        sensor: String,
+    },
+    SensorRenamed {
+        from: String,
+        to: String,
+    },
+    MetricAdded {
+        sensor: String,
+        metric: String,
+    },
+    MetricRemoved {
+        sensor: String,
+        metric: String,
+    },
+    MetricRenamed {
+        sensor: String,
+        from: String,
+        to: String,
+    },
+    /// Same name, but the unit, type or annotation differs
+    MetricRedefined {
+        sensor: String,
+        metric: String,
+    },
+}
+
+impl Display for InventoryChange {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        use InventoryChange::*;
+        match self {
+            SensorAdded { sensor } => write!(f, "+ {sensor}"),
+            SensorRemoved { sensor } => write!(f, "- {sensor}"),
+            SensorRenamed { from, to } => write!(f, "~ {from} → {to}"),
+            MetricAdded { sensor, metric } => write!(f, "+ {sensor}/{metric}"),
+            MetricRemoved { sensor, metric } => write!(f, "- {sensor}/{metric}"),
+            MetricRenamed { sensor, from, to } => write!(f, "~ {sensor}/{from} → {to}"),
+            MetricRedefined { sensor, metric } => write!(f, "* {sensor}/{metric}"),
+        }
+    }
+}
+
+/// Everything that happened to get from `before` to `after`, sensor by sensor
+pub fn diff_inventories(before: &Sensors, after: &Sensors) -> Vec<InventoryChange> {
+    use InventoryChange::*;
+
+    let mut changes = Vec::new();
+    for (sensor_id, old_sensor) in before {
+        let Some(new_sensor) = after.get(sensor_id) else {
+            changes.push(SensorRemoved {
+                sensor: old_sensor.name.clone(),
+            });
+            continue;
+        };
+
+        if old_sensor.name != new_sensor.name {
+            changes.push(SensorRenamed {
+                from: old_sensor.name.clone(),
+                to: new_sensor.name.clone(),
+            });
+        }
+
+        let sensor = &new_sensor.name;
+        for old_metric in &old_sensor.metrics {
+            let Some(new_metric) = new_sensor.metrics.get(old_metric.metric_id()) else {
+                changes.push(MetricRemoved {
+                    sensor: sensor.clone(),
+                    metric: old_metric.name().clone(),
+                });
+                continue;
+            };
+            if old_metric.name() != new_metric.name() {
+                changes.push(MetricRenamed {
+                    sensor: sensor.clone(),
+                    from: old_metric.name().clone(),
+                    to: new_metric.name().clone(),
+                });
+            } else if old_metric != new_metric {
+                changes.push(MetricRedefined {
+                    sensor: sensor.clone(),
+                    metric: new_metric.name().clone(),
+                });
+            }
+        }
+        for new_metric in &new_sensor.metrics {
+            if !old_sensor.metrics.contains(new_metric.metric_id()) {
+                changes.push(MetricAdded {
+                    sensor: sensor.clone(),
+                    metric: new_metric.name().clone(),
+                });
+            }
+        }
+    }
+
+    for (sensor_id, new_sensor) in after {
+        if before.contains_key(sensor_id) {
+            continue;
+        }
+        changes.push(SensorAdded {
+            sensor: new_sensor.name.clone(),
+        });
+        for metric in &new_sensor.metrics {
+            changes.push(MetricAdded {
+                sensor: new_sensor.name.clone(),
+                metric: metric.name().clone(),
+            });
+        }
+    }
+
+    changes
+}
+
+/// Sensor as an inventory file describes it. Ids are optional: sensors and metrics having
+/// them are matched by id, so that they can be renamed, the rest by name.
+#[derive(Debug, Clone, Deserialize, JsonSchema)]
+pub struct DesiredSensor {
+    pub name: String,
+
+    #[serde(rename = "sensorId", default)]
+    pub sensor_id: SensorId,
+
+    #[serde(default)]
+    pub metrics: Vec<Metric>,
+
+    #[serde(default)]
+    pub state: SensorState,
+}
+
+/// Either what `dump` writes, sensors keyed by their ids, or a plain list of sensors
+pub fn parse_inventory(json: &str) -> Result<Vec<DesiredSensor>> {
+    // Not an untagged enum, the ids don't deserialize from the content it buffers
+    let sensors: Vec<DesiredSensor> = if json.trim_start().starts_with('[') {
+        serde_json::from_str(json)?
+    } else {
+        serde_json::from_str::<BTreeMap<String, DesiredSensor>>(json)?
+            .into_values()
+            .collect()
+    };
+    let mut names = HashSet::new();
+    for sensor in &sensors {
+        if !names.insert(&sensor.name) {
+            return Err(eyre!("Sensor {} is described twice", sensor.name));
+        }
+        let mut metric_names = HashSet::new();
+        if let Some(metric) = sensor.metrics.iter().find(|metric| !metric_names.insert(metric.name())) {
+            return Err(eyre!("Metric {}/{} is described twice", sensor.name, metric.name()));
+        }
+    }
+    Ok(sensors)
+}
+
+/// Request taking the inventory a step closer to the one of a file
+#[derive(Debug, Clone, PartialEq)]
+pub enum ImportStep {
+    CreateSensor {
+        name: String,
+        state: SensorState,
+        metrics: Vec<Metric>,
+    },
+    UpdateSensor {
+        sensor_id: SensorId,
+        from: String,
+        name: String,
+        from_state: SensorState,
+        state: SensorState,
+    },
+    DeleteSensor {
+        sensor_id: SensorId,
+        name: String,
+    },
+    CreateMetrics {
+        sensor_id: SensorId,
+        sensor: String,
+        metrics: Vec<Metric>,
+    },
+    UpdateMetric {
+        sensor_id: SensorId,
+        sensor: String,
+        metric_id: MetricId,
+        from: String,
+        name: String,
+        from_annotation: Option<String>,
+        value_annotation: Option<String>,
+    },
+    DeleteMetric {
+        sensor_id: SensorId,
+        sensor: String,
+        metric_id: MetricId,
+        metric: String,
+    },
+    /// Deletion held back as it would lose the livedata the sensor or metric still receives
+    Protected {
+        deletion: Box<ImportStep>,
+        last_livedata: Timestamp,
+    },
+    /// Deletion held back as no client watched part of the protection window, so the sensor
+    /// or metric may have received livedata then
+    Unwatched {
+        deletion: Box<ImportStep>,
+        unwatched: Duration,
+    },
+}
+
+impl Display for ImportStep {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        use ImportStep::*;
+        let names = |metrics: &[Metric]| {
+            metrics
+                .iter()
+                .map(|metric| metric.name().as_str())
+                .collect::<Vec<_>>()
+                .join(", ")
+        };
+        match self {
+            CreateSensor { name, state, metrics } => {
+                write!(f, "+ {name}")?;
+                if !metrics.is_empty() {
+                    write!(f, " with {}", names(metrics))?;
+                }
+                if *state == SensorState::Disabled {
+                    write!(f, ", {state}")?;
+                }
+                Ok(())
+            }
+            UpdateSensor {
+                from,
+                name,
+                from_state,
+                state,
+                ..
+            } => {
+                if from == name {
+                    write!(f, "~ {name}")?;
+                } else {
+                    write!(f, "~ {from} → {name}")?;
+                }
+                if from_state != state {
+                    write!(f, ": {from_state} → {state}")?;
+                }
+                Ok(())
+            }
+            DeleteSensor { name, .. } => write!(f, "- {name}"),
+            CreateMetrics { sensor, metrics, .. } => write!(f, "+ {sensor}/{}", names(metrics)),
+            UpdateMetric {
+                sensor,
+                from,
+                name,
+                from_annotation,
+                value_annotation,
+                ..
+            } => {
+                if from == name {
+                    write!(f, "~ {sensor}/{name}")?;
+                } else {
+                    write!(f, "~ {sensor}/{from} → {name}")?;
+                }
+                match (from_annotation, value_annotation) {
+                    (Some(from), Some(annotation)) => write!(f, " [{from} → {annotation}]"),
+                    (None, Some(annotation)) => write!(f, " [{annotation}]"),
+                    (_, None) => Ok(()),
+                }
+            }
+            DeleteMetric { sensor, metric, .. } => write!(f, "- {sensor}/{metric}"),
+            Protected {
+                deletion,
+                last_livedata,
+            } => {
+                let last_livedata = last_livedata.to_datetime().format("%Y-%m-%d %H:%M:%S");
+                write_kept(f, deletion)?;
+                write!(f, " livedata at {last_livedata}")
+            }
+            Unwatched { deletion, unwatched } => {
+                write_kept(f, deletion)?;
+                write!(f, " livedata unwatched for {}s", unwatched.as_secs())
+            }
+        }
+    }
+}
+
+/// `! NAME kept,` of a held back deletion
+fn write_kept(f: &mut Formatter<'_>, deletion: &ImportStep) -> std::fmt::Result {
+    match deletion {
+        ImportStep::DeleteSensor { name, .. } => write!(f, "! {name}")?,
+        ImportStep::DeleteMetric { sensor, metric, .. } => write!(f, "! {sensor}/{metric}")?,
+        other => write!(f, "! {other}")?,
+    }
+    write!(f, " kept,")
+}
+
+/// What an import step does to the inventory, the prefix it's displayed with tells it too
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ChangeKind {
+    Created,
+    Updated,
+    Deleted,
+    Protected,
+}
+
+impl ImportStep {
+    pub fn kind(&self) -> ChangeKind {
+        use ImportStep::*;
+        match self {
+            CreateSensor { .. } | CreateMetrics { .. } => ChangeKind::Created,
+            UpdateSensor { .. } | UpdateMetric { .. } => ChangeKind::Updated,
+            DeleteSensor { .. } | DeleteMetric { .. } => ChangeKind::Deleted,
+            Protected { .. } | Unwatched { .. } => ChangeKind::Protected,
+        }
+    }
+}
+
+/// One line totals of the steps, e.g. `2 created, 1 updated, 0 deleted`
+pub fn summarize_import(steps: &[ImportStep]) -> String {
+    let count = |kind| steps.iter().filter(|step| step.kind() == kind).count();
+    let mut summary = format!(
+        "{} created, {} updated, {} deleted",
+        count(ChangeKind::Created),
+        count(ChangeKind::Updated),
+        count(ChangeKind::Deleted)
+    );
+    let protected = count(ChangeKind::Protected);
+    if protected > 0 {
+        summary.push_str(&format!(", {protected} kept for their livedata"));
+    }
+    summary
+}
+
+/// Holds back deleting the sensors and metrics which received livedata since `since`, and, if
+/// the window was `unwatched` in part, all the others too, as their livedata may have been missed.
+/// Metrics the plan recreates with another unit or type are then left as they are too,
+/// rather than created once more next to the kept ones.
+pub fn protect_recent_livedata(
+    steps: Vec<ImportStep>,
+    last_livedata: &LastLivedata,
+    since: Timestamp,
+    unwatched: Duration,
+) -> Vec<ImportStep> {
+    let recent = |sensor_id: &SensorId, metric_id: Option<&MetricId>| {
+        last_livedata
+            .iter()
+            .filter(|((sens_id, met_id), timestamp)| {
+                sens_id == sensor_id
+                    && metric_id.is_none_or(|metric_id| met_id == metric_id)
+                    && **timestamp >= since
+            })
+            .map(|(_, timestamp)| *timestamp)
+            .max()
+    };
+
+    let mut kept_metrics = HashSet::new();
+    let mut protected: Vec<ImportStep> = steps
+        .into_iter()
+        .map(|step| {
+            let last_livedata = match &step {
+                ImportStep::DeleteSensor { sensor_id, .. } => recent(sensor_id, None),
+                ImportStep::DeleteMetric {
+                    sensor_id,
+                    metric_id,
+                    ..
+                } => recent(sensor_id, Some(metric_id)),
+                _ => return step,
+            };
+            if let ImportStep::DeleteMetric { sensor_id, metric, .. } = &step
+                && (last_livedata.is_some() || !unwatched.is_zero())
+            {
+                kept_metrics.insert((*sensor_id, metric.clone()));
+            }
+            match last_livedata {
+                Some(last_livedata) => ImportStep::Protected {
+                    deletion: Box::new(step),
+                    last_livedata,
+                },
+                None if !unwatched.is_zero() => ImportStep::Unwatched {
+                    deletion: Box::new(step),
+                    unwatched,
+                },
+                None => step,
+            }
+        })
+        .collect();
+
+    for step in &mut protected {
+        if let ImportStep::CreateMetrics {
+            sensor_id, metrics, ..
+        } = step
+        {
+            metrics.retain(|metric| !kept_metrics.contains(&(*sensor_id, metric.name().clone())));
+        }
+    }
+    protected.retain(|step| !matches!(step, ImportStep::CreateMetrics { metrics, .. } if metrics.is_empty()));
+    protected
+}
+
+fn annotation(metric: &Metric) -> Option<&String> {
+    match metric {
+        Metric::Custom {
+            value_annotation, ..
+        } => Some(value_annotation),
+        Metric::Predefined { .. } => None,
+    }
+}
+
+/// Whether the metrics hold the same kind of values, which can't be changed in place
+fn same_definition(current: &Metric, desired: &Metric) -> bool {
+    match (current, desired) {
+        (
+            Metric::Predefined { value_unit, .. },
+            Metric::Predefined {
+                value_unit: desired_unit,
+                ..
+            },
+        ) => value_unit == desired_unit,
+        (
+            Metric::Custom { value_type, .. },
+            Metric::Custom {
+                value_type: desired_type,
+                ..
+            },
+        ) => value_type == desired_type,
+        _ => false,
+    }
+}
+
+/// Requests turning `current` into `desired`. Sensors and metrics missing from `desired`
+/// are deleted with `prune` only, and so are metrics whose unit or type changed, as they
+/// have to be created anew.
+pub fn plan_import(current: &Sensors, desired: &[DesiredSensor], prune: bool) -> Vec<ImportStep> {
+    let mut steps = Vec::new();
+    let mut matched = HashSet::new();
+    for desired_sensor in desired {
+        let existing = current.get(&desired_sensor.sensor_id).or_else(|| {
+            current.values().find(|sensor| {
+                sensor.name == desired_sensor.name && !matched.contains(&sensor.sensor_id)
+            })
+        });
+        let Some(existing) = existing else {
+            steps.push(ImportStep::CreateSensor {
+                name: desired_sensor.name.clone(),
+                state: desired_sensor.state,
+                metrics: desired_sensor.metrics.iter().map(Metric::detached).collect(),
+            });
+            continue;
+        };
+        matched.insert(existing.sensor_id);
+
+        if existing.name != desired_sensor.name || existing.state != desired_sensor.state {
+            steps.push(ImportStep::UpdateSensor {
+                sensor_id: existing.sensor_id,
+                from: existing.name.clone(),
+                name: desired_sensor.name.clone(),
+                from_state: existing.state,
+                state: desired_sensor.state,
+            });
+        }
+        plan_metrics(existing, desired_sensor, prune, &mut steps);
+    }
+
+    if prune {
+        for sensor in current.values().filter(|sensor| !matched.contains(&sensor.sensor_id)) {
+            steps.push(ImportStep::DeleteSensor {
+                sensor_id: sensor.sensor_id,
+                name: sensor.name.clone(),
+            });
+        }
+    }
+    steps
+}
+
+fn plan_metrics(
+    existing: &Sensor<Metric>,
+    desired_sensor: &DesiredSensor,
+    prune: bool,
+    steps: &mut Vec<ImportStep>,
+) {
+    let sensor = &desired_sensor.name;
+    let mut matched = HashSet::new();
+    let mut created = Vec::new();
+    for desired_metric in &desired_sensor.metrics {
+        let current_metric = existing.metrics.get(desired_metric.metric_id()).or_else(|| {
+            existing.metrics.iter().find(|metric| {
+                metric.name() == desired_metric.name() && !matched.contains(metric.metric_id())
+            })
+        });
+        let Some(current_metric) = current_metric else {
+            created.push(desired_metric.detached());
+            continue;
+        };
+        let metric_id = *current_metric.metric_id();
+        matched.insert(metric_id);
+
+        if !same_definition(current_metric, desired_metric) {
+            if prune {
+                steps.push(ImportStep::DeleteMetric {
+                    sensor_id: existing.sensor_id,
+                    sensor: sensor.clone(),
+                    metric_id,
+                    metric: current_metric.name().clone(),
+                });
+                created.push(desired_metric.detached());
+            } else {
+                log::warn!(
+                    "Not redefining {sensor}/{}, which takes deleting it first",
+                    current_metric.name()
+                );
+            }
+            continue;
+        }
+        let value_annotation = annotation(desired_metric)
+            .filter(|desired| annotation(current_metric) != Some(desired))
+            .cloned();
+        if current_metric.name() != desired_metric.name() || value_annotation.is_some() {
+            steps.push(ImportStep::UpdateMetric {
+                sensor_id: existing.sensor_id,
+                sensor: sensor.clone(),
+                metric_id,
+                from: current_metric.name().clone(),
+                name: desired_metric.name().clone(),
+                from_annotation: value_annotation.as_ref().and(annotation(current_metric)).cloned(),
+                value_annotation,
+            });
+        }
+    }
+
+    if prune {
+        for metric in existing.metrics.iter().filter(|metric| !matched.contains(metric.metric_id())) {
+            steps.push(ImportStep::DeleteMetric {
+                sensor_id: existing.sensor_id,
+                sensor: sensor.clone(),
+                metric_id: *metric.metric_id(),
+                metric: metric.name().clone(),
+            });
+        }
+    }
+    if !created.is_empty() {
+        steps.push(ImportStep::CreateMetrics {
+            sensor_id: existing.sensor_id,
+            sensor: sensor.clone(),
+            metrics: created,
+        });
+    }
+}
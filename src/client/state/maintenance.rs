@@ -0,0 +1,307 @@
+use actix::{Handler, Message};
+
+use chrono::{Datelike, Local, NaiveDateTime, TimeDelta, Timelike};
+
+use eyre::{eyre, Result, WrapErr};
+
+use schemars::JsonSchema;
+
+use serde::{Deserialize, Serialize};
+
+use std::fmt::{Display, Formatter};
+use std::time::Duration;
+
+use crate::client::state::{SensorStateEvent, SensorsStateActor};
+use crate::model::Timestamp;
+use crate::utils::{duration_secs, glob_match};
+
+/// Windows may last a week at most, so that checking one stays cheap
+const MAX_WINDOW: Duration = Duration::from_secs(7 * 24 * 3600);
+
+/// Recurring span the alerts of the matching metrics are silenced over, starting whenever
+/// the schedule matches. The rules are still checked and the alerts recorded, they're just
+/// not notified.
+#[derive(Debug, Clone, PartialEq, Deserialize, JsonSchema, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct MaintenanceWindow {
+    /// `MINUTE HOUR DAY MONTH WEEKDAY` in local time, as in crontab: `*`, lists, ranges and
+    /// `/STEP`, weekdays 0-7 or SUN-SAT, months 1-12 or JAN-DEC
+    pub schedule: String,
+
+    /// How long the window lasts, such as `90m` or `2h`
+    pub duration: String,
+
+    /// Sensor name, may contain * and ? wildcards
+    #[serde(default = "any_name")]
+    pub sensor: String,
+
+    /// Metric name, may contain * and ? wildcards
+    #[serde(default = "any_name")]
+    pub metric: String,
+}
+
+fn any_name() -> String {
+    String::from("*")
+}
+
+impl MaintenanceWindow {
+    /// Catches malformed schedules and durations before they're put in force
+    pub fn validate(&self) -> Result<()> {
+        self.compile().map(|_| ())
+    }
+
+    /// Whether a window started within `duration` before `at`, in local time
+    pub fn is_active(&self, at: NaiveDateTime) -> Result<bool> {
+        Ok(self.compile()?.is_active(at))
+    }
+
+    pub fn applies_to(&self, sensor_name: &str, metric_name: &str) -> bool {
+        glob_match(&self.sensor, sensor_name) && glob_match(&self.metric, metric_name)
+    }
+
+    fn compile(&self) -> Result<CompiledWindow> {
+        let schedule = Schedule::parse(&self.schedule)
+            .wrap_err_with(|| format!("Invalid maintenance schedule '{}'", self.schedule))?;
+        let duration = Duration::from_secs(duration_secs(&self.duration)?);
+        if duration.is_zero() || duration > MAX_WINDOW {
+            return Err(eyre!("Maintenance windows last up to a week, not {}", self.duration));
+        }
+        Ok(CompiledWindow {
+            schedule,
+            duration: TimeDelta::seconds(duration.as_secs() as i64),
+        })
+    }
+}
+
+impl Display for MaintenanceWindow {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}/{} at '{}' for {}", self.sensor, self.metric, self.schedule, self.duration)
+    }
+}
+
+struct CompiledWindow {
+    schedule: Schedule,
+    duration: TimeDelta,
+}
+
+impl CompiledWindow {
+    /// Looks for a start among the minutes the window would still last from
+    fn is_active(&self, at: NaiveDateTime) -> bool {
+        let Some(minute) = at.with_second(0).and_then(|at| at.with_nanosecond(0)) else {
+            return false;
+        };
+        (0..=self.duration.num_minutes())
+            .map(|back| minute - TimeDelta::minutes(back))
+            .any(|started| at < started + self.duration && self.schedule.matches(started))
+    }
+}
+
+/// Fields of a crontab schedule as bit sets, bit N standing for the value N
+#[derive(Debug, Clone, PartialEq)]
+struct Schedule {
+    minutes: u64,
+    hours: u64,
+    days: u64,
+    months: u64,
+    weekdays: u64,
+    /// As in cron, once both days and weekdays are restricted, matching either of them will do
+    any_day: bool,
+    any_weekday: bool,
+}
+
+const WEEKDAYS: &[&str] = &["SUN", "MON", "TUE", "WED", "THU", "FRI", "SAT"];
+const MONTHS: &[&str] = &["JAN", "FEB", "MAR", "APR", "MAY", "JUN", "JUL", "AUG", "SEP", "OCT", "NOV", "DEC"];
+
+impl Schedule {
+    fn parse(schedule: &str) -> Result<Self> {
+        let fields: Vec<&str> = schedule.split_whitespace().collect();
+        let [minute, hour, day, month, weekday] = fields[..] else {
+            return Err(eyre!("Expected MINUTE HOUR DAY MONTH WEEKDAY"));
+        };
+        let mut weekdays = parse_field(weekday, 0, 7, WEEKDAYS, 0)?;
+        // Both 0 and 7 stand for Sunday
+        if weekdays & (1 << 7) != 0 {
+            weekdays |= 1;
+        }
+        Ok(Self {
+            minutes: parse_field(minute, 0, 59, &[], 0)?,
+            hours: parse_field(hour, 0, 23, &[], 0)?,
+            days: parse_field(day, 1, 31, &[], 0)?,
+            months: parse_field(month, 1, 12, MONTHS, 1)?,
+            weekdays,
+            any_day: day == "*",
+            any_weekday: weekday == "*",
+        })
+    }
+
+    fn matches(&self, at: NaiveDateTime) -> bool {
+        let has = |bits: u64, value: u32| bits & (1 << value) != 0;
+        let day = has(self.days, at.day());
+        let weekday = has(self.weekdays, at.weekday().num_days_from_sunday());
+        let day_matches = match (self.any_day, self.any_weekday) {
+            (true, _) => weekday,
+            (false, true) => day,
+            (false, false) => day || weekday,
+        };
+        has(self.minutes, at.minute()) && has(self.hours, at.hour()) && has(self.months, at.month()) && day_matches
+    }
+}
+
+/// Bits of the values a comma separated list of `*`, `N`, `N-M`, each optionally `/STEP`,
+/// stands for. `names` stand for the values from `first_name` on.
+fn parse_field(field: &str, min: u32, max: u32, names: &[&str], first_name: u32) -> Result<u64> {
+    let value = |text: &str| -> Result<u32> {
+        let value = match names.iter().position(|name| name.eq_ignore_ascii_case(text)) {
+            Some(index) => index as u32 + first_name,
+            None => text.parse().map_err(|_| eyre!("Invalid value '{text}'"))?,
+        };
+        if !(min..=max).contains(&value) {
+            return Err(eyre!("{value} is out of {min}-{max}"));
+        }
+        Ok(value)
+    };
+
+    let mut bits = 0;
+    for part in field.split(',') {
+        let (range, step) = match part.split_once('/') {
+            Some((range, step)) => (
+                range,
+                step.parse::<usize>()
+                    .ok()
+                    .filter(|step| *step > 0)
+                    .ok_or_else(|| eyre!("Invalid step in '{part}'"))?,
+            ),
+            None => (part, 1),
+        };
+        let (start, end) = if range == "*" {
+            (min, max)
+        } else if let Some((start, end)) = range.split_once('-') {
+            (value(start)?, value(end)?)
+        } else if step > 1 {
+            // N/STEP runs up to the maximum
+            (value(range)?, max)
+        } else {
+            (value(range)?, value(range)?)
+        };
+        if start > end {
+            return Err(eyre!("Empty range '{range}'"));
+        }
+        for value in (start..=end).step_by(step) {
+            bits |= 1 << value;
+        }
+    }
+    Ok(bits)
+}
+
+/// Alerts silenced at the moment, by the maintenance windows in progress or until a given time
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Silencing {
+    pub windows: Vec<MaintenanceWindow>,
+    /// Every alert is silenced until then
+    pub until: Option<Timestamp>,
+}
+
+impl Silencing {
+    pub fn is_active(&self) -> bool {
+        !self.windows.is_empty() || self.until.is_some()
+    }
+
+    /// `now` tells whether the silence is over, windows are only checked every so often
+    pub fn silences(&self, sensor_name: &str, metric_name: &str, now: Timestamp) -> bool {
+        self.until.is_some_and(|until| until > now)
+            || self.windows.iter().any(|window| window.applies_to(sensor_name, metric_name))
+    }
+}
+
+/// Windows of a state actor along with the silencing they and the latest silence amount to
+#[derive(Default)]
+pub(super) struct Maintenance {
+    windows: Vec<MaintenanceWindow>,
+    until: Option<Timestamp>,
+    pub(super) silencing: Silencing,
+}
+
+/// Replaces all the maintenance windows
+#[derive(Message)]
+#[rtype(result = "Result<()>")]
+pub struct SetMaintenanceWindows(pub Vec<MaintenanceWindow>);
+
+/// Silences every alert until the given time, or lifts the silence if None
+#[derive(Message)]
+#[rtype(result = "Result<()>")]
+pub struct SilenceAlerts(pub Option<Timestamp>);
+
+#[derive(Message)]
+#[rtype(result = "Result<Silencing>")]
+pub struct GetSilencing;
+
+impl SensorsStateActor {
+    /// Brings the silencing up to date with the clock, letting the subscribers know of changes
+    pub(super) fn check_maintenance(&mut self) {
+        let maintenance = &mut self.alerts.maintenance;
+        let now = Local::now().naive_local();
+        let windows = maintenance
+            .windows
+            .iter()
+            .filter(|window| window.is_active(now).unwrap_or(false))
+            .cloned()
+            .collect();
+        if maintenance.until.is_some_and(|until| until <= Timestamp::now()) {
+            maintenance.until = None;
+        }
+        let silencing = Silencing {
+            windows,
+            until: maintenance.until,
+        };
+        if silencing == maintenance.silencing {
+            return;
+        }
+        for window in &silencing.windows {
+            if !maintenance.silencing.windows.contains(window) {
+                log::info!("Maintenance window {window} started");
+            }
+        }
+        for window in &maintenance.silencing.windows {
+            if !silencing.windows.contains(window) {
+                log::info!("Maintenance window {window} ended");
+            }
+        }
+        maintenance.silencing = silencing.clone();
+        self.emit_event(SensorStateEvent::SilencingChanged(silencing));
+    }
+}
+
+impl Handler<SetMaintenanceWindows> for SensorsStateActor {
+    type Result = Result<()>;
+
+    fn handle(&mut self, SetMaintenanceWindows(windows): SetMaintenanceWindows, _: &mut Self::Context) -> Self::Result {
+        for window in &windows {
+            window.validate()?;
+        }
+        self.alerts.maintenance.windows = windows;
+        self.check_maintenance();
+        Ok(())
+    }
+}
+
+impl Handler<SilenceAlerts> for SensorsStateActor {
+    type Result = Result<()>;
+
+    fn handle(&mut self, SilenceAlerts(until): SilenceAlerts, _: &mut Self::Context) -> Self::Result {
+        match until {
+            Some(until) => log::info!("Alerts silenced until {}", until.to_datetime().with_timezone(&Local)),
+            None => log::info!("Alerts no longer silenced"),
+        }
+        self.alerts.maintenance.until = until;
+        self.check_maintenance();
+        Ok(())
+    }
+}
+
+impl Handler<GetSilencing> for SensorsStateActor {
+    type Result = Result<Silencing>;
+
+    fn handle(&mut self, _: GetSilencing, _: &mut Self::Context) -> Self::Result {
+        Ok(self.alerts.maintenance.silencing.clone())
+    }
+}
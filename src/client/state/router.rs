@@ -0,0 +1,448 @@
+use actix::{Actor, Addr, Context, Handler, ResponseFuture};
+
+use eyre::{eyre, Result};
+
+use futures::future::join_all;
+use futures::FutureExt;
+
+use std::collections::HashSet;
+use std::path::Path;
+use std::time::Duration;
+
+use crate::client::mqtt::{MqttEvent, MqttMessage};
+use crate::client::state::queries::*;
+use crate::client::state::{
+    merge_alert_histories, AlertRecord, AlertRule, GetAlertHistory, GetAlertRules, GetSilencing, LastLivedata,
+    MqttScheme, Sensors, SensorsStateActor, SetAlertRules, SetMaintenanceWindows, SilenceAlerts, Silencing,
+    SubscribeToStateEvents,
+};
+use crate::model::sensor::{LinkedMetric, Metric, Sensor};
+use crate::model::{MetricId, SensorId};
+
+/// Spreads the sensors across several [`SensorsStateActor`]s so that connectors with
+/// thousands of metrics aren't bottlenecked by a single actor. Accepts the same events
+/// and queries as a single state actor does, merging the replies of the shards.
+pub struct StateRouterActor {
+    shards: Vec<Addr<SensorsStateActor>>,
+}
+
+impl StateRouterActor {
+    /// With a single shard every message is passed through as is
+    /// The shards share the livedata history in `history_file` if given
+    pub fn new(shards_count: usize, mailbox_capacity: usize, history_file: Option<&Path>) -> Result<Self> {
+        let shards = (0..shards_count.max(1))
+            .map(|_| {
+                let shard = match history_file {
+                    Some(history_file) => SensorsStateActor::with_history(history_file)?,
+                    None => SensorsStateActor::new()?,
+                };
+                Ok(SensorsStateActor::create(|ctx| {
+                    ctx.set_mailbox_capacity(mailbox_capacity);
+                    shard
+                }))
+            })
+            .collect::<Result<_>>()?;
+        Ok(Self { shards })
+    }
+
+    fn shard_index(&self, sensor_id: &SensorId) -> usize {
+        (sensor_id.as_uuid().as_u128() % self.shards.len() as u128) as usize
+    }
+
+    fn shard_of(&self, sensor_id: &SensorId) -> &Addr<SensorsStateActor> {
+        &self.shards[self.shard_index(sensor_id)]
+    }
+
+    fn route(&self, msg: MqttMessage) {
+        if self.shards.len() == 1 {
+            self.shards[0].do_send(MqttEvent(msg));
+            return;
+        }
+
+        let short_topic = &msg.topic[39..]; // cut /v1.0/6d69c58223fb44a7b76ae61a18faf37c/ off
+        let (mqtt_ids, pattern) = MqttScheme::extract_ids_and_pattern(short_topic);
+
+        let (_, inventory_response, _) = MqttScheme::SensorList.get_templates();
+        let (_, created_response, _) = MqttScheme::SensorCreate.get_templates();
+
+        let sensor_id = if let Some(sensor_id) = mqtt_ids.first() {
+            Some(SensorId::from(*sensor_id))
+        } else if pattern == inventory_response {
+            self.split_inventory(msg);
+            return;
+        } else if pattern == created_response {
+            serde_json::from_str::<Sensor<Metric>>(&msg.message)
+                .ok()
+                .map(|sensor| sensor.sensor_id)
+        } else {
+            None
+        };
+
+        // Events of no particular sensor (pings, errors of sensor/list or sensor/create)
+        // are processed by the first shard
+        let shard = match sensor_id {
+            Some(sensor_id) => self.shard_of(&sensor_id),
+            None => &self.shards[0],
+        };
+        shard.do_send(MqttEvent(msg));
+    }
+
+    /// Every shard gets the part of the inventory it owns
+    fn split_inventory(&self, msg: MqttMessage) {
+        let Ok(linked_sensors) = serde_json::from_str::<Vec<Sensor<LinkedMetric>>>(&msg.message)
+        else {
+            // Let the state actor report it
+            self.shards[0].do_send(MqttEvent(msg));
+            return;
+        };
+
+        let mut parts = vec![Vec::new(); self.shards.len()];
+        for linked_sensor in &linked_sensors {
+            parts[self.shard_index(&linked_sensor.sensor_id)].push(linked_sensor);
+        }
+
+        for (shard, part) in self.shards.iter().zip(parts) {
+            let Ok(message) = serde_json::to_string(&part) else {
+                continue;
+            };
+            shard.do_send(MqttEvent(MqttMessage {
+                message,
+                ..msg.clone()
+            }));
+        }
+    }
+
+    fn shards(&self) -> Vec<Addr<SensorsStateActor>> {
+        self.shards.clone()
+    }
+}
+
+/// Ids of the sensors named `sensor_name` across all the shards, in ascending order
+async fn sensor_ids_by_name(shards: &[Addr<SensorsStateActor>], sensor_name: &str) -> Result<Vec<SensorId>> {
+    let mut sensor_ids = Vec::new();
+    for (_, shard_ids) in shard_sensor_ids_by_name(shards, sensor_name).await? {
+        sensor_ids.extend(shard_ids);
+    }
+    sensor_ids.sort();
+    Ok(sensor_ids)
+}
+
+async fn shard_sensor_ids_by_name(
+    shards: &[Addr<SensorsStateActor>],
+    sensor_name: &str,
+) -> Result<Vec<(Addr<SensorsStateActor>, Vec<SensorId>)>> {
+    let replies = join_all(
+        shards
+            .iter()
+            .map(|shard| shard.send(GetSensorIdsByName(sensor_name.to_owned()))),
+    )
+    .await;
+    let mut found = Vec::new();
+    for (shard, reply) in shards.iter().zip(replies) {
+        found.push((shard.clone(), reply??));
+    }
+    Ok(found)
+}
+
+async fn resolve_sensor(
+    shards: &[Addr<SensorsStateActor>],
+    sensor_name: &str,
+) -> Result<(SensorId, Addr<SensorsStateActor>)> {
+    let mut found = Vec::new();
+    for (shard, sensor_ids) in shard_sensor_ids_by_name(shards, sensor_name).await? {
+        found.extend(sensor_ids.into_iter().map(|sensor_id| (sensor_id, shard.clone())));
+    }
+    match found.len() {
+        0 => Err(eyre!("No sensor named '{sensor_name}'")),
+        1 => Ok(found.remove(0)),
+        count => Err(eyre!(
+            "Sensor name '{sensor_name}' is ambiguous: {count} sensors share it"
+        )),
+    }
+}
+
+impl Handler<MqttEvent> for StateRouterActor {
+    type Result = ();
+
+    fn handle(&mut self, MqttEvent(msg): MqttEvent, _: &mut Self::Context) -> Self::Result {
+        self.route(msg);
+    }
+}
+
+impl Handler<SubscribeToStateEvents> for StateRouterActor {
+    type Result = ();
+
+    fn handle(&mut self, msg: SubscribeToStateEvents, _: &mut Self::Context) -> Self::Result {
+        for shard in &self.shards {
+            shard.do_send(SubscribeToStateEvents(msg.0.clone()));
+        }
+    }
+}
+
+impl Handler<SetAlertRules> for StateRouterActor {
+    type Result = ResponseFuture<Result<()>>;
+
+    fn handle(&mut self, SetAlertRules(rules): SetAlertRules, _: &mut Self::Context) -> Self::Result {
+        let shards = self.shards();
+        async move {
+            let replies =
+                join_all(shards.iter().map(|shard| shard.send(SetAlertRules(rules.clone())))).await;
+            for reply in replies {
+                reply??;
+            }
+            Ok(())
+        }
+        .boxed_local()
+    }
+}
+
+impl Handler<GetAlertRules> for StateRouterActor {
+    type Result = ResponseFuture<Result<Vec<AlertRule>>>;
+
+    fn handle(&mut self, msg: GetAlertRules, _: &mut Self::Context) -> Self::Result {
+        // Every shard has the same rules
+        let shard = self.shards[0].clone();
+        async move { shard.send(msg).await? }.boxed_local()
+    }
+}
+
+impl Handler<SetMaintenanceWindows> for StateRouterActor {
+    type Result = ResponseFuture<Result<()>>;
+
+    fn handle(&mut self, SetMaintenanceWindows(windows): SetMaintenanceWindows, _: &mut Self::Context) -> Self::Result {
+        let shards = self.shards();
+        async move {
+            let replies =
+                join_all(shards.iter().map(|shard| shard.send(SetMaintenanceWindows(windows.clone())))).await;
+            for reply in replies {
+                reply??;
+            }
+            Ok(())
+        }
+        .boxed_local()
+    }
+}
+
+impl Handler<SilenceAlerts> for StateRouterActor {
+    type Result = ResponseFuture<Result<()>>;
+
+    fn handle(&mut self, SilenceAlerts(until): SilenceAlerts, _: &mut Self::Context) -> Self::Result {
+        let shards = self.shards();
+        async move {
+            for reply in join_all(shards.iter().map(|shard| shard.send(SilenceAlerts(until)))).await {
+                reply??;
+            }
+            Ok(())
+        }
+        .boxed_local()
+    }
+}
+
+impl Handler<GetSilencing> for StateRouterActor {
+    type Result = ResponseFuture<Result<Silencing>>;
+
+    fn handle(&mut self, msg: GetSilencing, _: &mut Self::Context) -> Self::Result {
+        // Every shard has the same windows and silence
+        let shard = self.shards[0].clone();
+        async move { shard.send(msg).await? }.boxed_local()
+    }
+}
+
+impl Handler<GetAlertHistory> for StateRouterActor {
+    type Result = ResponseFuture<Result<Vec<AlertRecord>>>;
+
+    fn handle(&mut self, GetAlertHistory { since }: GetAlertHistory, _: &mut Self::Context) -> Self::Result {
+        let shards = self.shards();
+        async move {
+            let mut histories = Vec::new();
+            for history in join_all(shards.iter().map(|shard| shard.send(GetAlertHistory { since }))).await {
+                histories.push(history??);
+            }
+            Ok(merge_alert_histories(histories))
+        }
+        .boxed_local()
+    }
+}
+
+impl Handler<GetStateSnapshot> for StateRouterActor {
+    type Result = ResponseFuture<Result<Sensors>>;
+
+    fn handle(&mut self, _: GetStateSnapshot, _: &mut Self::Context) -> Self::Result {
+        let shards = self.shards();
+        async move {
+            let mut sensors = Sensors::new();
+            for snapshot in join_all(shards.iter().map(|shard| shard.send(GetStateSnapshot))).await
+            {
+                sensors.extend(snapshot??);
+            }
+            Ok(sensors)
+        }
+        .boxed_local()
+    }
+}
+
+impl Handler<GetLastLivedata> for StateRouterActor {
+    type Result = ResponseFuture<Result<LastLivedata>>;
+
+    fn handle(&mut self, _: GetLastLivedata, _: &mut Self::Context) -> Self::Result {
+        let shards = self.shards();
+        async move {
+            let mut last_livedata = LastLivedata::new();
+            for shard_livedata in join_all(shards.iter().map(|shard| shard.send(GetLastLivedata))).await {
+                last_livedata.extend(shard_livedata??);
+            }
+            Ok(last_livedata)
+        }
+        .boxed_local()
+    }
+}
+
+impl Handler<GetUnwatchedLivedata> for StateRouterActor {
+    type Result = ResponseFuture<Result<Duration>>;
+
+    /// The shards watch side by side, the one started last missed the most
+    fn handle(&mut self, GetUnwatchedLivedata { since, now }: GetUnwatchedLivedata, _: &mut Self::Context) -> Self::Result {
+        let shards = self.shards();
+        async move {
+            let mut unwatched = Duration::ZERO;
+            for shard_unwatched in
+                join_all(shards.iter().map(|shard| shard.send(GetUnwatchedLivedata { since, now }))).await
+            {
+                unwatched = unwatched.max(shard_unwatched??);
+            }
+            Ok(unwatched)
+        }
+        .boxed_local()
+    }
+}
+
+impl Handler<GetVersionedStateSnapshot> for StateRouterActor {
+    type Result = ResponseFuture<Result<(u64, Sensors)>>;
+
+    fn handle(&mut self, _: GetVersionedStateSnapshot, _: &mut Self::Context) -> Self::Result {
+        let shards = self.shards();
+        async move {
+            // Every shard only ever bumps its version, so the sum changes whenever any of them does
+            let mut version = 0u64;
+            let mut sensors = Sensors::new();
+            let snapshots =
+                join_all(shards.iter().map(|shard| shard.send(GetVersionedStateSnapshot))).await;
+            for snapshot in snapshots {
+                let (shard_version, shard_sensors) = snapshot??;
+                version = version.wrapping_add(shard_version);
+                sensors.extend(shard_sensors);
+            }
+            Ok((version, sensors))
+        }
+        .boxed_local()
+    }
+}
+
+impl Handler<GetStateVersion> for StateRouterActor {
+    type Result = ResponseFuture<Result<u64>>;
+
+    fn handle(&mut self, _: GetStateVersion, _: &mut Self::Context) -> Self::Result {
+        let shards = self.shards();
+        async move {
+            let mut version = 0u64;
+            for shard_version in join_all(shards.iter().map(|shard| shard.send(GetStateVersion))).await {
+                version = version.wrapping_add(shard_version??);
+            }
+            Ok(version)
+        }
+        .boxed_local()
+    }
+}
+
+impl Handler<GetMetricIds> for StateRouterActor {
+    type Result = ResponseFuture<Result<Option<HashSet<MetricId>>>>;
+
+    fn handle(&mut self, msg: GetMetricIds, _: &mut Self::Context) -> Self::Result {
+        let shard = self.shard_of(&msg.0).clone();
+        async move { shard.send(msg).await? }.boxed_local()
+    }
+}
+
+impl Handler<GetMetricIdByName> for StateRouterActor {
+    type Result = ResponseFuture<Result<Option<MetricId>>>;
+
+    fn handle(&mut self, msg: GetMetricIdByName, _: &mut Self::Context) -> Self::Result {
+        let shard = self.shard_of(&msg.0).clone();
+        async move { shard.send(msg).await? }.boxed_local()
+    }
+}
+
+impl Handler<GetSensorIdByName> for StateRouterActor {
+    type Result = ResponseFuture<Result<Option<SensorId>>>;
+
+    fn handle(&mut self, msg: GetSensorIdByName, _: &mut Self::Context) -> Self::Result {
+        let shards = self.shards();
+        async move { Ok(sensor_ids_by_name(&shards, &msg.0).await?.first().copied()) }.boxed_local()
+    }
+}
+
+impl Handler<GetSensorIdsByName> for StateRouterActor {
+    type Result = ResponseFuture<Result<Vec<SensorId>>>;
+
+    fn handle(&mut self, msg: GetSensorIdsByName, _: &mut Self::Context) -> Self::Result {
+        let shards = self.shards();
+        async move { sensor_ids_by_name(&shards, &msg.0).await }.boxed_local()
+    }
+}
+
+impl Handler<FindMetrics> for StateRouterActor {
+    type Result = ResponseFuture<Result<Vec<MatchedMetric>>>;
+
+    fn handle(&mut self, msg: FindMetrics, _: &mut Self::Context) -> Self::Result {
+        let shards = self.shards();
+        async move {
+            let replies = join_all(shards.iter().map(|shard| {
+                shard.send(FindMetrics {
+                    sensor_pattern: msg.sensor_pattern.clone(),
+                    metric_pattern: msg.metric_pattern.clone(),
+                })
+            }))
+            .await;
+            let mut matched = Vec::new();
+            for reply in replies {
+                matched.extend(reply??);
+            }
+            matched.sort_by(|a: &MatchedMetric, b: &MatchedMetric| {
+                (&a.sensor_name, a.metric.name()).cmp(&(&b.sensor_name, b.metric.name()))
+            });
+            Ok(matched)
+        }
+        .boxed_local()
+    }
+}
+
+impl Handler<ResolveSensor> for StateRouterActor {
+    type Result = ResponseFuture<Result<SensorId>>;
+
+    fn handle(&mut self, msg: ResolveSensor, _: &mut Self::Context) -> Self::Result {
+        let shards = self.shards();
+        async move {
+            let (sensor_id, _) = resolve_sensor(&shards, &msg.sensor_name).await?;
+            Ok(sensor_id)
+        }
+        .boxed_local()
+    }
+}
+
+impl Handler<ResolveMetric> for StateRouterActor {
+    type Result = ResponseFuture<Result<(SensorId, Metric)>>;
+
+    fn handle(&mut self, msg: ResolveMetric, _: &mut Self::Context) -> Self::Result {
+        let shards = self.shards();
+        async move {
+            // The name has to be unique across all the shards, not only within the owning one
+            let (_, shard) = resolve_sensor(&shards, &msg.sensor_name).await?;
+            shard.send(msg).await?
+        }
+        .boxed_local()
+    }
+}
+
+impl Actor for StateRouterActor {
+    type Context = Context<Self>;
+}
@@ -118,6 +118,55 @@ impl MqttScheme {
         }
     }
 
+    /// The sensor the request concerns, if any
+    pub fn sensor_id(&self) -> Option<SensorId> {
+        use MqttScheme::*;
+        match self {
+            SensorUpdate(sensor_id)
+            | SensorDelete(sensor_id)
+            | MetricCreate(sensor_id)
+            | MetricUpdate(sensor_id)
+            | MetricDelete(sensor_id)
+            | PushValues(sensor_id)
+            | MetricDescribe(sensor_id, _) => Some(*sensor_id),
+            SensorList | SensorCreate | Ping => None,
+        }
+    }
+
+    /// Same scheme bound to the ids extracted from one of its topics, the ones missing are
+    /// left as they are
+    pub fn with_ids(&self, ids: &[MqttId]) -> Self {
+        use MqttScheme::*;
+        let sensor_id = |sensor_id: &SensorId| ids.first().copied().map_or(*sensor_id, SensorId::from);
+        match self {
+            SensorUpdate(id) => SensorUpdate(sensor_id(id)),
+            SensorDelete(id) => SensorDelete(sensor_id(id)),
+            MetricCreate(id) => MetricCreate(sensor_id(id)),
+            MetricUpdate(id) => MetricUpdate(sensor_id(id)),
+            MetricDelete(id) => MetricDelete(sensor_id(id)),
+            PushValues(id) => PushValues(sensor_id(id)),
+            MetricDescribe(id, metric_id) => MetricDescribe(
+                sensor_id(id),
+                ids.get(1).copied().map_or(*metric_id, MetricId::from),
+            ),
+            SensorList | SensorCreate | Ping => *self,
+        }
+    }
+
+    /// Requests changing the inventory, which are worth sending again once they failed
+    pub fn is_retriable(&self) -> bool {
+        use MqttScheme::*;
+        matches!(
+            self,
+            SensorCreate
+                | SensorUpdate(_)
+                | SensorDelete(_)
+                | MetricCreate(_)
+                | MetricUpdate(_)
+                | MetricDelete(_)
+        )
+    }
+
     pub fn render_topic(template: &str, args: &[String]) -> String {
         let mut result = template.to_string();
         for arg in args {
@@ -0,0 +1,49 @@
+use crate::client::state::Sensors;
+use crate::model::sensor::Metric;
+use crate::model::SensorId;
+use crate::utils::glob_match;
+
+/// A sensor whose name, or the name of some of its metrics, matched the search
+#[derive(Clone, Debug)]
+pub struct InventoryMatch {
+    pub sensor_id: SensorId,
+    pub sensor_name: String,
+    /// The sensor name matched, not only the metrics
+    pub sensor_matched: bool,
+    /// Only the matching metrics, in the order of the sensor
+    pub metrics: Vec<Metric>,
+}
+
+/// Searches sensor and metric names. Patterns with `*` or `?` are matched as a whole the way
+/// `push` does, others match any name containing them, ignoring case.
+/// Sensors are ordered by name.
+pub fn search_inventory(sensors: &Sensors, pattern: &str) -> Vec<InventoryMatch> {
+    let mut matched: Vec<InventoryMatch> = sensors
+        .values()
+        .filter_map(|sensor| {
+            let metrics: Vec<Metric> = sensor
+                .metrics
+                .iter()
+                .filter(|metric| name_matches(pattern, metric.name()))
+                .cloned()
+                .collect();
+            let sensor_matched = name_matches(pattern, &sensor.name);
+            (sensor_matched || !metrics.is_empty()).then(|| InventoryMatch {
+                sensor_id: sensor.sensor_id,
+                sensor_name: sensor.name.clone(),
+                sensor_matched,
+                metrics,
+            })
+        })
+        .collect();
+    matched.sort_by(|a, b| (&a.sensor_name, a.sensor_id).cmp(&(&b.sensor_name, b.sensor_id)));
+    matched
+}
+
+fn name_matches(pattern: &str, name: &str) -> bool {
+    if pattern.contains(['*', '?']) {
+        glob_match(pattern, name)
+    } else {
+        name.to_lowercase().contains(&pattern.to_lowercase())
+    }
+}
@@ -1,16 +1,20 @@
-use actix::{Actor, Context, Handler, Message, WeakRecipient};
+use actix::{Actor, AsyncContext, Context, Handler, Message, WeakRecipient};
 
-use eyre::{OptionExt, Result, WrapErr};
+use eyre::{Result, WrapErr};
 
 use strum::IntoEnumIterator;
 
-use std::ops::Sub;
-use std::collections::{BTreeMap, HashMap, HashSet};
-
-use crate::client::state::MqttScheme;
-use crate::client::mqtt::MqttEvent;
-use crate::model::sensor::{LinkedMetric, Metric, Sensor};
-use crate::model::{MetricId, MqttId, SensorId};
+use std::collections::HashMap;
+use std::path::Path;
+use std::time::{Duration, Instant};
+
+use crate::client::exchanges;
+use crate::client::state::{Alerts, MqttScheme, Sensors, Silencing, SqliteStateStore, StateStore};
+use crate::client::mqtt::{MqttEvent, MqttMessage};
+use crate::correlation::{self, CorrelationId};
+use crate::model::sensor::{LinkedMetric, Metric, Metrics, Sensor, SensorState};
+use crate::model::{MetricId, MqttId, SensorId, Timestamp};
+use crate::telemetry;
 use crate::model::protocol::{CreateMetricResponsePayload, ErrorResponse, MetricValue, MetricsArrayResponse, PushMetricValueResponse};
 
 #[derive(Debug, Clone, Message)]
@@ -49,6 +53,10 @@ pub enum SensorStateEvent {
         sensor_id: SensorId,
         name: String,
     },
+    SensorStateChanged {
+        sensor_id: SensorId,
+        state: SensorState,
+    },
     MetricNameChanged {
         sensor_id: SensorId,
         metric_id: MetricId,
@@ -64,35 +72,113 @@ pub enum SensorStateEvent {
         sensor_id: SensorId,
         metric_id: MetricId,
         value: MetricValue,
-        timestamp: u64,
+        timestamp: Timestamp,
+        /// When the client received it, the latency of the pipeline is how far it lags behind
+        received_at: Timestamp,
+    },
+
+    /// Livedata of the metric crossed the threshold of an [`AlertRule`](super::AlertRule)
+    AlertRaised {
+        sensor_id: SensorId,
+        metric_id: MetricId,
+        message: String,
+        critical: bool,
+        /// Notification sinks the rule picked, all of them if empty
+        sinks: Vec<String>,
+        value: MetricValue,
+        timestamp: Timestamp,
+        /// Raised during maintenance, only to be shown rather than notified
+        silenced: bool,
     },
+    AlertCleared {
+        sensor_id: SensorId,
+        metric_id: MetricId,
+    },
+    /// Maintenance windows started or ended, or the alerts were silenced or no longer are
+    SilencingChanged(Silencing),
 
     Error {
         message: String,
         code: i32,
+        correlation_id: Option<CorrelationId>,
+        /// Request which failed, bound to the ids of its topic
+        scheme: MqttScheme,
+        timestamp: Timestamp,
+        /// Payload of the failed request, kept for the ones worth retrying
+        request: Option<String>,
     }
 }
 
+/// How long a reply about a sensor not known yet waits for the sensor to appear
+const ORPHAN_TTL: Duration = Duration::from_secs(30);
+
+/// How often the span the livedata has been watched over is extended in the store
+const WATCH_INTERVAL: Duration = Duration::from_secs(10);
+
+/// How often maintenance windows are checked for starting or ending
+const MAINTENANCE_CHECK_INTERVAL: Duration = Duration::from_secs(15);
+
+/// Replies kept per unknown sensor, the oldest ones are dropped first
+const MAX_ORPHANS_PER_SENSOR: usize = 256;
+
+/// Reply which arrived before the sensor it's about, e.g. a describe overtaking the sensor list
+enum Orphan {
+    MetricDescribe(Vec<MqttId>, String),
+    /// Along with when it was received
+    Livedata(Vec<MqttId>, String, Timestamp),
+}
+
 #[derive(Message)]
 #[rtype(result = "()")]
 pub struct SubscribeToStateEvents(pub WeakRecipient<SensorStateEvent>);
 
-// TODO Replace with in-memory SQLite
-pub type Sensors = BTreeMap<SensorId, Sensor<Metric>>;
-
-#[derive(Default)]
 pub struct SensorsStateActor {
-    pub(super) sensors: Sensors,
+    pub(super) store: Box<dyn StateStore>,
+
+    // Bumped on every processed inventory event, lets readers detect concurrent changes
+    pub(super) version: u64,
+
+    // Sensor name -> id, valid until the next inventory change
+    pub(super) resolved_sensors: HashMap<String, SensorId>,
 
     // For speeding up
     topic_schemes: HashMap<String, MqttScheme>,
 
     event_subscribers: Vec<WeakRecipient<SensorStateEvent>>,
+
+    // Replayed once their sensor is loaded or created, in the order they arrived
+    orphans: HashMap<SensorId, Vec<(Instant, Orphan)>>,
+
+    pub(super) alerts: Alerts,
+
+    // Decoding the whole store is costly, so a snapshot is reused until the version changes
+    pub(super) snapshot_cache: Option<(u64, Sensors)>,
 }
 
 impl SensorsStateActor {
-    pub fn new() -> Self {
-        let mut result = Self::default();
+    pub fn new() -> Result<Self> {
+        let store = SqliteStateStore::new().wrap_err("Failed to create the state store")?;
+        Ok(Self::with_store(Box::new(store)))
+    }
+
+    /// Keeps the livedata history in `history_file` rather than in memory
+    pub fn with_history(history_file: &Path) -> Result<Self> {
+        let store = SqliteStateStore::with_history(history_file).wrap_err("Failed to create the state store")?;
+        Ok(Self::with_store(Box::new(store)))
+    }
+
+    pub fn with_store(store: Box<dyn StateStore>) -> Self {
+        let alerts = Alerts::restore(store.as_ref());
+        let mut result = Self {
+            store,
+            version: 0,
+            resolved_sensors: HashMap::new(),
+            topic_schemes: HashMap::new(),
+            event_subscribers: Vec::new(),
+            orphans: HashMap::new(),
+            alerts,
+            snapshot_cache: None,
+        };
 
         for scheme in MqttScheme::iter() {
             result.init_scheme(scheme);
@@ -107,7 +193,7 @@ impl SensorsStateActor {
         self.topic_schemes.insert(error.to_owned(), scheme);
     }
 
-    fn emit_event(&self, event: SensorStateEvent) {
+    pub(super) fn emit_event(&self, event: SensorStateEvent) {
         for subscriber in &self.event_subscribers {
             if let Some(subscriber) = subscriber.upgrade() {
                 subscriber.do_send(event.clone());
@@ -121,87 +207,122 @@ impl SensorsStateActor {
         }
     }
 
+    fn park_orphan(&mut self, sensor_id: SensorId, orphan: Orphan) {
+        let now = Instant::now();
+        self.orphans.retain(|_, orphans| {
+            orphans.retain(|(received, _)| now.duration_since(*received) < ORPHAN_TTL);
+            !orphans.is_empty()
+        });
+
+        log::debug!("Sensor {sensor_id} is not known yet, holding its reply back");
+        let orphans = self.orphans.entry(sensor_id).or_default();
+        if orphans.len() == MAX_ORPHANS_PER_SENSOR {
+            orphans.remove(0);
+        }
+        orphans.push((now, orphan));
+    }
+
+    fn replay_orphans(&mut self) -> Result<()> {
+        let mut sensor_ids = Vec::new();
+        for sensor_id in self.orphans.keys() {
+            if self.store.contains_sensor(sensor_id)? {
+                sensor_ids.push(*sensor_id);
+            }
+        }
+
+        let now = Instant::now();
+        for sensor_id in sensor_ids {
+            let Some(orphans) = self.orphans.remove(&sensor_id) else {
+                continue;
+            };
+            for (received, orphan) in orphans {
+                if now.duration_since(received) >= ORPHAN_TTL {
+                    continue;
+                }
+                let result = match orphan {
+                    Orphan::MetricDescribe(ids, message) => self.event_metric_describe(ids, message),
+                    Orphan::Livedata(ids, message, received_at) => {
+                        self.event_livedata(ids, message, received_at)
+                    }
+                };
+                if let Err(err) = result {
+                    log::error!("Error while replaying a reply for sensor {sensor_id}: {err}");
+                }
+            }
+        }
+        Ok(())
+    }
+
     fn event_sensor_list(&mut self, _: Vec<MqttId>, message: String) -> Result<()> {
         let linked_sensors = serde_json::from_str::<Vec<Sensor<LinkedMetric>>>(&message)
             .wrap_err_with(|| format!("Failed to deserialize: {}", &message))?;
 
         // Merging new sensors
         for linked_sensor in linked_sensors {
-            if self.sensors.contains_key(&linked_sensor.sensor_id) {
-                // Some metrics might've been deleted
-                let deleted_metric_ids = {
-                    // Immutable sensor
-                    let existing_sensor = self.sensors.get(&linked_sensor.sensor_id).unwrap();
-                    // TODO Replace Vec<Metric> with HashMap<Metric> finally!!!
-                    let existing_metric_ids = existing_sensor
-                        .metrics
-                        .iter()
-                        .map(|m| m.metric_id().clone())
-                        .collect::<HashSet<MetricId>>();
-                    let linked_metric_ids = linked_sensor
-                        .metrics
-                        .iter()
-                        .map(|m| m.metric_id.clone())
-                        .collect::<HashSet<MetricId>>();
-                    existing_metric_ids.sub(&linked_metric_ids)
-                };
-
+            if let Some(mut existing_sensor) = self.store.sensor(&linked_sensor.sensor_id)? {
                 let mut events = Vec::new();
 
-                // Mutable sensor -> no `&mut self` available
-                let existing_sensor = self.sensors.get_mut(&linked_sensor.sensor_id).unwrap();
                 // The name might have been changed
                 if existing_sensor.name != linked_sensor.name {
                     existing_sensor.name = linked_sensor.name.clone();
                     events.push(SensorStateEvent::SensorNameChanged {
-                        sensor_id: linked_sensor.sensor_id.clone(),
+                        sensor_id: linked_sensor.sensor_id,
                         name: existing_sensor.name.clone(),
                     });
                 }
 
-                for deleted_metric_id in deleted_metric_ids {
-                    existing_sensor.metrics = existing_sensor
-                        .metrics
-                        .iter()
-                        .filter(|m| deleted_metric_id != *m.metric_id())
-                        .map(|m| m.clone())
-                        .collect();
+                if existing_sensor.state != linked_sensor.state {
+                    existing_sensor.state = linked_sensor.state;
+                    events.push(SensorStateEvent::SensorStateChanged {
+                        sensor_id: linked_sensor.sensor_id,
+                        state: existing_sensor.state,
+                    });
+                }
+
+                // Some metrics might've been deleted
+                let deleted_metric_ids: Vec<MetricId> = existing_sensor
+                    .metrics
+                    .ids()
+                    .filter(|metric_id| !linked_sensor.metrics.contains(metric_id))
+                    .copied()
+                    .collect();
+                for metric_id in deleted_metric_ids {
+                    existing_sensor.metrics.remove(&metric_id);
                     events.push(SensorStateEvent::MetricDeleted {
-                        sensor_id: linked_sensor.sensor_id.clone(),
-                        metric_id: deleted_metric_id,
+                        sensor_id: linked_sensor.sensor_id,
+                        metric_id,
                     });
                 }
 
+                self.store.put_sensor(&existing_sensor)?;
                 self.emit_events(events);
                 self.emit_event(SensorStateEvent::ExistingLinkedSensorLoaded(linked_sensor));
             } else {
                 // Completely new sensor => save it and subscribe to all its events
-                let sensor_id = linked_sensor.sensor_id.clone();
                 let new_sensor = Sensor {
                     name: linked_sensor.name.clone(),
                     connector_id: linked_sensor.connector_id,
                     sensor_id: linked_sensor.sensor_id,
-                    metrics: Vec::new(),
+                    metrics: Metrics::default(),
+                    state: linked_sensor.state,
                 };
 
-                self.sensors.insert(sensor_id, new_sensor);
+                self.store.put_sensor(&new_sensor)?;
 
                 self.emit_event(SensorStateEvent::NewLinkedSensorLoaded(linked_sensor));
             }
         }
 
-        Ok(())
+        self.replay_orphans()
     }
     fn event_sensor_create(&mut self, _: Vec<MqttId>, message: String) -> Result<()> {
         let new_sensor = serde_json::from_str::<Sensor<Metric>>(&message)
             .wrap_err_with(|| format!("Failed to deserialize: {}", &message))?;
 
-        let sensor_id = new_sensor.sensor_id.clone();
-
-        self.sensors.insert(sensor_id, new_sensor.clone());
+        self.store.put_sensor(&new_sensor)?;
 
         self.emit_event(SensorStateEvent::NewSensorCreated(new_sensor));
-        Ok(())
+        self.replay_orphans()
     }
     fn event_sensor_update(&mut self, mut ids: Vec<MqttId>, message: String) -> Result<()> {
         // According to https://docs-iot.teamviewer.com/mqtt-api/#533-update
@@ -211,7 +332,7 @@ impl SensorsStateActor {
         // Thus, re-requesting the entire sensor list as you cannot request concrete sensor
         // details.
 
-        if let Some(sensor_id) = ids.pop() {
+        if let Some(sensor_id) = ids.pop().map(SensorId::from) {
             if message == "Sensor was changed." {
                 self.emit_event(SensorStateEvent::SensorUpdated { sensor_id });
             }
@@ -220,9 +341,10 @@ impl SensorsStateActor {
     }
     fn event_sensor_delete(&mut self, mut ids: Vec<MqttId>, message: String) -> Result<()> {
         // According to https://docs-iot.teamviewer.com/mqtt-api/#534-delete
-        if let Some(sensor_id) = ids.pop() {
+        if let Some(sensor_id) = ids.pop().map(SensorId::from) {
             if message == "Sensor was deleted." {
-                self.sensors.remove(&sensor_id);
+                self.store.remove_sensor(&sensor_id)?;
+                self.orphans.remove(&sensor_id);
                 self.emit_event(SensorStateEvent::SensorDeleted { sensor_id });
             }
         }
@@ -232,25 +354,21 @@ impl SensorsStateActor {
         let (Some(sensor_id), Some(metric_id)) = (ids.get(0), ids.get(1)) else {
             return Ok(());
         };
+        let (sensor_id, metric_id) = (&SensorId::from(*sensor_id), &MetricId::from(*metric_id));
 
         let described_metric = serde_json::from_str::<Metric>(&message)?;
-        let sensor = self
-            .sensors
-            .get_mut(&sensor_id)
-            .ok_or_eyre("Sensor not found")?;
+        let Some(mut sensor) = self.store.sensor(sensor_id)? else {
+            self.park_orphan(*sensor_id, Orphan::MetricDescribe(ids, message));
+            return Ok(());
+        };
 
         let mut events = Vec::new();
-        // If metrics was a HashMap, it'd be much simpler :(
-        let metric_found = sensor
-            .metrics
-            .iter_mut()
-            .find(|m| *m.metric_id() == *metric_id);
-        if let Some(existing_metric) = metric_found {
+        if let Some(existing_metric) = sensor.metrics.get_mut(metric_id) {
             if existing_metric.name() != described_metric.name() {
                 existing_metric.rename(described_metric.name().clone());
                 events.push(SensorStateEvent::MetricNameChanged {
-                    sensor_id: sensor_id.clone(),
-                    metric_id: metric_id.clone(),
+                    sensor_id: *sensor_id,
+                    metric_id: *metric_id,
                     name: existing_metric.name().clone(),
                 });
             }
@@ -273,8 +391,8 @@ impl SensorsStateActor {
                         );
                         *value_annotation = new_annotation;
                         events.push(SensorStateEvent::MetricValueAnnotationChanged {
-                            sensor_id: sensor_id.clone(),
-                            metric_id: metric_id.clone(),
+                            sensor_id: *sensor_id,
+                            metric_id: *metric_id,
                             annotation: value_annotation.clone(),
                         });
                     }
@@ -282,7 +400,7 @@ impl SensorsStateActor {
                 _ => {}
             }
         } else {
-            sensor.metrics.push(described_metric.clone());
+            sensor.metrics.insert(described_metric.clone());
 
             events.push(SensorStateEvent::NewMetricLoaded {
                 sensor_id: *sensor_id,
@@ -290,18 +408,19 @@ impl SensorsStateActor {
             });
         }
 
+        self.store.put_sensor(&sensor)?;
         self.emit_events(events);
         Ok(())
     }
     fn event_metric_create(&mut self, mut ids: Vec<MqttId>, message: String) -> Result<()> {
-        if let Some(sensor_id) = ids.pop() {
+        if let Some(sensor_id) = ids.pop().map(SensorId::from) {
             let metrics_created =
                 serde_json::from_str::<Vec<CreateMetricResponsePayload>>(&message)
                     .wrap_err_with(|| format!("Failed to deserialize: {}", message))?;
             for payload in &metrics_created {
                 self.emit_event(SensorStateEvent::NewMetricCreated {
-                    sensor_id: sensor_id.clone(),
-                    metric_id: payload.metric_id.clone(),
+                    sensor_id,
+                    metric_id: payload.metric_id,
                 });
             }
         }
@@ -309,7 +428,7 @@ impl SensorsStateActor {
     }
     fn event_metric_update(&mut self, mut ids: Vec<MqttId>, message: String) -> Result<()> {
         // According to https://docs-iot.teamviewer.com/mqtt-api/#543-update
-        if let Some(sensor_id) = ids.pop() {
+        if let Some(sensor_id) = ids.pop().map(SensorId::from) {
             if message == "All metrics were successfully modified." {
                 self.emit_event(SensorStateEvent::SensorMetricsUpdated { sensor_id });
             }
@@ -318,7 +437,7 @@ impl SensorsStateActor {
     }
     fn event_metric_delete(&mut self, mut ids: Vec<MqttId>, message: String) -> Result<()> {
         // According to https://docs-iot.teamviewer.com/mqtt-api/#544-delete
-        if let Some(sensor_id) = ids.pop() {
+        if let Some(sensor_id) = ids.pop().map(SensorId::from) {
             if message == "All metrics were successfully deleted." {
                 self.emit_event(SensorStateEvent::SensorUpdated { sensor_id });
             }
@@ -332,18 +451,35 @@ impl SensorsStateActor {
         Ok(())
     }
 
-    fn event_livedata(&mut self, mut ids: Vec<MqttId>, message: String) -> Result<()> {
+    fn event_livedata(&mut self, ids: Vec<MqttId>, message: String, received_at: Timestamp) -> Result<()> {
         // According to https://docs-iot.teamviewer.com/mqtt-api/#52-get-metric-values
-        if let Some(sensor_id) = ids.pop() {
+        if let Some(sensor_id) = ids.last().copied().map(SensorId::from) {
+            if !self.store.contains_sensor(&sensor_id)? {
+                self.park_orphan(sensor_id, Orphan::Livedata(ids, message, received_at));
+                return Ok(());
+            }
             let value_updates =
                 serde_json::from_str::<MetricsArrayResponse<PushMetricValueResponse>>(&message)
                     .wrap_err_with(|| format!("Failed to deserialize: {}", message))?;
+            let timestamp = value_updates.timestamp.unwrap_or_else(Timestamp::now);
             for value_update in value_updates.metrics {
+                if let Err(err) = self.check_alerts(
+                    sensor_id,
+                    value_update.metric_id,
+                    &value_update.value,
+                    timestamp,
+                ) {
+                    log::error!("Failed to check alerts: {err}");
+                }
+                if let Err(err) = self.store.record_livedata(&sensor_id, &value_update.metric_id, timestamp) {
+                    log::error!("Failed to record livedata: {err}");
+                }
                 self.emit_event(SensorStateEvent::Livedata {
-                    sensor_id: sensor_id.clone(),
+                    sensor_id,
                     metric_id: value_update.metric_id,
                     value: value_update.value,
-                    timestamp: value_updates.timestamp.unwrap(),
+                    timestamp,
+                    received_at,
                 });
             }
         }
@@ -355,22 +491,46 @@ impl Handler<MqttEvent> for SensorsStateActor {
     type Result = ();
 
     fn handle(&mut self, MqttEvent(msg): MqttEvent, _: &mut Self::Context) -> Self::Result {
+        let correlation_id = correlation::take_response(&msg.topic);
+        correlation::sync_scope(correlation_id, || self.process_event(msg, correlation_id));
+    }
+}
+
+impl SensorsStateActor {
+    fn process_event(&mut self, msg: MqttMessage, correlation_id: Option<CorrelationId>) {
         let short_topic = msg.topic[39..].to_owned(); // cut /v1.0/6d69c58223fb44a7b76ae61a18faf37c/ off
         let (mqtt_ids, pattern) = MqttScheme::extract_ids_and_pattern(&short_topic);
         // There is no such MqttScheme cause it's an "event"
         if pattern == "sensor/:mqttid:/livedata" {
-            let _ = self.event_livedata(mqtt_ids, msg.message);
+            telemetry::livedata_processed();
+            let _ = self.event_livedata(mqtt_ids, msg.message, Timestamp::now());
             return;
         }
 
         if let Some(scheme) = self.topic_schemes.get(&pattern) {
             use MqttScheme::*;
             let (_, response_pattern, _) = scheme.get_templates();
+            telemetry::reply_received(&msg.topic, response_pattern != pattern);
+            let exchange =
+                exchanges::reply_received(&msg.topic, &msg.message, response_pattern != pattern);
             if response_pattern != pattern {
+                let scheme = scheme.with_ids(&mqtt_ids);
                 if let Ok(error_response) = serde_json::from_str::<ErrorResponse>(&msg.message) {
+                    log::warn!(
+                        "Error #{} in topic '{}': {}",
+                        error_response.code,
+                        msg.topic,
+                        error_response.message,
+                    );
                     self.emit_event(SensorStateEvent::Error {
                         message: error_response.message,
                         code: error_response.code,
+                        correlation_id,
+                        scheme,
+                        timestamp: Timestamp::now(),
+                        request: exchange
+                            .filter(|_| scheme.is_retriable())
+                            .map(|exchange| exchange.request),
                     });
                 } else {
                     log::error!(
@@ -394,6 +554,9 @@ impl Handler<MqttEvent> for SensorsStateActor {
                 MetricDelete(..) => self.event_metric_delete(mqtt_ids, msg.message),
                 Ping => self.event_ping(mqtt_ids, msg.message),
             };
+            self.version = self.version.wrapping_add(1);
+            self.resolved_sensors.clear();
+            self.alerts.forget_metrics();
             if let Err(err) = result {
                 log::error!("Error while processing mqtt event {}", err)
             }
@@ -411,4 +574,13 @@ impl Handler<SubscribeToStateEvents> for SensorsStateActor {
 
 impl Actor for SensorsStateActor {
     type Context = Context<Self>;
+
+    fn started(&mut self, ctx: &mut Self::Context) {
+        ctx.run_interval(WATCH_INTERVAL, |actor, _| {
+            if let Err(err) = actor.store.record_watch(Timestamp::now()) {
+                log::error!("Failed to record the livedata watch: {err}");
+            }
+        });
+        ctx.run_interval(MAINTENANCE_CHECK_INTERVAL, |actor, _| actor.check_maintenance());
+    }
 }
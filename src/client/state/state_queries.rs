@@ -1,84 +1,290 @@
-use actix::{Handler, Message, MessageResult};
+use actix::{Handler, Message};
+
+use eyre::{eyre, Result, WrapErr};
 
 use std::collections::HashSet;
+use std::time::Duration;
 
-use crate::client::state::{Sensors, SensorsStateActor};
-use crate::model::{MetricId, SensorId};
+use crate::client::state::{LastLivedata, Sensors, SensorsStateActor};
+use crate::model::sensor::Metric;
+use crate::model::{MetricId, SensorId, Timestamp};
+use crate::utils::glob_match;
 
 #[derive(Message)]
-#[rtype(result = "Sensors")]
+#[rtype(result = "Result<Sensors>")]
 pub struct GetStateSnapshot;
 
+/// Snapshot along with the state version it was taken at
+#[derive(Message)]
+#[rtype(result = "Result<(u64, Sensors)>")]
+pub struct GetVersionedStateSnapshot;
+
+#[derive(Message)]
+#[rtype(result = "Result<u64>")]
+pub struct GetStateVersion;
+
+/// When the metrics received livedata last, as far as this client has seen
+#[derive(Message)]
+#[rtype(result = "Result<LastLivedata>")]
+pub struct GetLastLivedata;
+
+/// How much of the span from `since` to `now` no client sharing the history watched livedata over,
+/// so that it may have been missed
+#[derive(Message)]
+#[rtype(result = "Result<Duration>")]
+pub struct GetUnwatchedLivedata {
+    pub since: Timestamp,
+    pub now: Timestamp,
+}
+
 #[derive(Message)]
-#[rtype(result = "Option<HashSet<MetricId>>")]
+#[rtype(result = "Result<Option<HashSet<MetricId>>>")]
 pub struct GetMetricIds(pub SensorId);
 
 #[derive(Message)]
-#[rtype(result = "Option<SensorId>")]
+#[rtype(result = "Result<Option<SensorId>>")]
 pub struct GetSensorIdByName(pub String);
 
+/// Ids of all the sensors sharing the name, in ascending order
 #[derive(Message)]
-#[rtype(result = "Option<MetricId>")]
+#[rtype(result = "Result<Vec<SensorId>>")]
+pub struct GetSensorIdsByName(pub String);
+
+#[derive(Message)]
+#[rtype(result = "Result<Option<MetricId>>")]
 pub struct GetMetricIdByName(pub SensorId, pub String);
 
+/// Metrics whose sensor and metric names match the respective glob patterns,
+/// ordered by sensor name and then by metric name
+#[derive(Message)]
+#[rtype(result = "Result<Vec<MatchedMetric>>")]
+pub struct FindMetrics {
+    pub sensor_pattern: String,
+    pub metric_pattern: String,
+}
+
+#[derive(Clone, Debug)]
+pub struct MatchedMetric {
+    pub sensor_id: SensorId,
+    pub sensor_name: String,
+    pub metric: Metric,
+}
+
+/// Unlike [`GetSensorIdByName`], fails if several sensors share the name
+#[derive(Message)]
+#[rtype(result = "Result<SensorId>")]
+pub struct ResolveSensor {
+    pub sensor_name: String,
+}
+
+/// Unlike [`GetMetricIdByName`], fails if the sensor or the metric name is ambiguous
+#[derive(Message)]
+#[rtype(result = "Result<(SensorId, Metric)>")]
+pub struct ResolveMetric {
+    pub sensor_name: String,
+    pub metric_name: String,
+}
+
+impl SensorsStateActor {
+    fn snapshot(&mut self) -> Result<Sensors> {
+        if let Some((version, sensors)) = &self.snapshot_cache
+            && *version == self.version
+        {
+            return Ok(sensors.clone());
+        }
+        let sensors = self.store.snapshot().wrap_err("Failed to take a state snapshot")?;
+        self.snapshot_cache = Some((self.version, sensors.clone()));
+        Ok(sensors)
+    }
+}
+
 impl Handler<GetStateSnapshot> for SensorsStateActor {
-    type Result = MessageResult<GetStateSnapshot>;
+    type Result = Result<Sensors>;
 
     fn handle(&mut self, _: GetStateSnapshot, _: &mut Self::Context) -> Self::Result {
-        MessageResult(self.sensors.clone())
+        self.snapshot()
+    }
+}
+
+impl Handler<GetLastLivedata> for SensorsStateActor {
+    type Result = Result<LastLivedata>;
+
+    fn handle(&mut self, _: GetLastLivedata, _: &mut Self::Context) -> Self::Result {
+        self.store.last_livedata().wrap_err("Failed to read the last livedata")
+    }
+}
+
+impl Handler<GetUnwatchedLivedata> for SensorsStateActor {
+    type Result = Result<Duration>;
+
+    fn handle(&mut self, GetUnwatchedLivedata { since, now }: GetUnwatchedLivedata, _: &mut Self::Context) -> Self::Result {
+        self.store
+            .unwatched_livedata(since, now)
+            .wrap_err("Failed to read the watches")
+    }
+}
+
+impl Handler<GetVersionedStateSnapshot> for SensorsStateActor {
+    type Result = Result<(u64, Sensors)>;
+
+    fn handle(&mut self, _: GetVersionedStateSnapshot, _: &mut Self::Context) -> Self::Result {
+        Ok((self.version, self.snapshot()?))
+    }
+}
+
+impl Handler<GetStateVersion> for SensorsStateActor {
+    type Result = Result<u64>;
+
+    fn handle(&mut self, _: GetStateVersion, _: &mut Self::Context) -> Self::Result {
+        Ok(self.version)
     }
 }
 
 impl Handler<GetMetricIds> for SensorsStateActor {
-    type Result = Option<HashSet<MetricId>>;
+    type Result = Result<Option<HashSet<MetricId>>>;
 
     fn handle(
         &mut self,
         GetMetricIds(sensor_id): GetMetricIds,
         _: &mut Self::Context,
     ) -> Self::Result {
-        self.sensors.get(&sensor_id).map(|sensor| {
-            sensor
-                .metrics
-                .iter()
-                .map(|m| m.metric_id().clone())
-                .collect()
-        })
+        let sensor = self
+            .store
+            .sensor(&sensor_id)
+            .wrap_err_with(|| format!("Failed to read sensor {sensor_id}"))?;
+        Ok(sensor.map(|sensor| sensor.metrics.ids().copied().collect()))
     }
 }
 
 impl Handler<GetSensorIdByName> for SensorsStateActor {
-    type Result = Option<SensorId>;
+    type Result = Result<Option<SensorId>>;
 
     fn handle(
         &mut self,
         GetSensorIdByName(sensor_name): GetSensorIdByName,
         _: &mut Self::Context,
     ) -> Self::Result {
-        self.sensors
-            .iter()
-            .find(|(_, sensor)| sensor.name == sensor_name)
-            .map(|(sensor_id, _)| *sensor_id)
+        Ok(self.store.sensor_ids_by_name(&sensor_name)?.first().copied())
+    }
+}
+
+impl Handler<GetSensorIdsByName> for SensorsStateActor {
+    type Result = Result<Vec<SensorId>>;
+
+    fn handle(
+        &mut self,
+        GetSensorIdsByName(sensor_name): GetSensorIdsByName,
+        _: &mut Self::Context,
+    ) -> Self::Result {
+        self.store
+            .sensor_ids_by_name(&sensor_name)
+            .wrap_err_with(|| format!("Failed to look up sensor '{sensor_name}'"))
     }
 }
 
 impl Handler<GetMetricIdByName> for SensorsStateActor {
-    type Result = Option<MetricId>;
+    type Result = Result<Option<MetricId>>;
 
     fn handle(
         &mut self,
         GetMetricIdByName(sensor_id, name): GetMetricIdByName,
         _: &mut Self::Context,
     ) -> Self::Result {
-        self.sensors
-            .get(&sensor_id)
-            .map(|sensor| {
+        Ok(self
+            .store
+            .metrics_by_name(&sensor_id, &name)?
+            .first()
+            .map(|metric| *metric.metric_id()))
+    }
+}
+
+impl Handler<FindMetrics> for SensorsStateActor {
+    type Result = Result<Vec<MatchedMetric>>;
+
+    fn handle(
+        &mut self,
+        FindMetrics {
+            sensor_pattern,
+            metric_pattern,
+        }: FindMetrics,
+        _: &mut Self::Context,
+    ) -> Self::Result {
+        let mut matched: Vec<MatchedMetric> = self
+            .snapshot()?
+            .values()
+            .filter(|sensor| glob_match(&sensor_pattern, &sensor.name))
+            .flat_map(|sensor| {
                 sensor
                     .metrics
                     .iter()
-                    .find(|metric| metric.name() == &name)
-                    .map(|metric| metric.metric_id().clone())
+                    .filter(|metric| glob_match(&metric_pattern, metric.name()))
+                    .map(|metric| MatchedMetric {
+                        sensor_id: sensor.sensor_id,
+                        sensor_name: sensor.name.clone(),
+                        metric: metric.clone(),
+                    })
             })
-            .flatten()
+            .collect();
+        matched.sort_by(|a, b| {
+            (&a.sensor_name, a.metric.name()).cmp(&(&b.sensor_name, b.metric.name()))
+        });
+        Ok(matched)
+    }
+}
+
+impl SensorsStateActor {
+    fn resolve_sensor(&mut self, sensor_name: &str) -> Result<SensorId> {
+        if let Some(sensor_id) = self.resolved_sensors.get(sensor_name) {
+            return Ok(*sensor_id);
+        }
+
+        let sensor_id = match self.store.sensor_ids_by_name(sensor_name)?[..] {
+            [] => return Err(eyre!("No sensor named '{sensor_name}'")),
+            [sensor_id] => sensor_id,
+            ref matching => {
+                return Err(eyre!(
+                    "Sensor name '{sensor_name}' is ambiguous: {} sensors share it",
+                    matching.len()
+                ));
+            }
+        };
+
+        self.resolved_sensors.insert(sensor_name.to_owned(), sensor_id);
+        Ok(sensor_id)
+    }
+}
+
+impl Handler<ResolveSensor> for SensorsStateActor {
+    type Result = Result<SensorId>;
+
+    fn handle(
+        &mut self,
+        ResolveSensor { sensor_name }: ResolveSensor,
+        _: &mut Self::Context,
+    ) -> Self::Result {
+        self.resolve_sensor(&sensor_name)
+    }
+}
+
+impl Handler<ResolveMetric> for SensorsStateActor {
+    type Result = Result<(SensorId, Metric)>;
+
+    fn handle(
+        &mut self,
+        ResolveMetric {
+            sensor_name,
+            metric_name,
+        }: ResolveMetric,
+        _: &mut Self::Context,
+    ) -> Self::Result {
+        let sensor_id = self.resolve_sensor(&sensor_name)?;
+        let mut matching = self.store.metrics_by_name(&sensor_id, &metric_name)?;
+        match matching.len() {
+            0 => Err(eyre!("Sensor '{sensor_name}' has no metric named '{metric_name}'")),
+            1 => Ok((sensor_id, matching.remove(0))),
+            count => Err(eyre!(
+                "Metric name '{sensor_name}/{metric_name}' is ambiguous: {count} metrics share it"
+            )),
+        }
     }
 }
@@ -0,0 +1,496 @@
+use eyre::{Result, WrapErr};
+
+use rusqlite::{params, Connection, OptionalExtension};
+
+use uuid::Uuid;
+
+use std::collections::BTreeMap;
+use std::path::Path;
+use std::time::Duration;
+
+use crate::client::state::AlertRecord;
+use crate::model::sensor::{Metric, Metrics, Sensor};
+use crate::model::{MetricId, SensorId, Timestamp};
+
+/// Copy of the whole inventory handed out to the readers of the state
+pub type Sensors = BTreeMap<SensorId, Sensor<Metric>>;
+
+/// Time of the latest livedata value of every metric which received one
+pub type LastLivedata = BTreeMap<(SensorId, MetricId), Timestamp>;
+
+/// Storage behind [`SensorsStateActor`](super::SensorsStateActor).
+/// Sensors are always written as a whole, so readers never observe a half-updated sensor.
+pub trait StateStore {
+    fn sensor(&self, sensor_id: &SensorId) -> Result<Option<Sensor<Metric>>>;
+
+    /// Cheaper than [`StateStore::sensor`] as the metrics aren't read
+    fn contains_sensor(&self, sensor_id: &SensorId) -> Result<bool>;
+
+    /// Inserts the sensor or replaces the stored one along with all its metrics
+    fn put_sensor(&mut self, sensor: &Sensor<Metric>) -> Result<()>;
+
+    fn remove_sensor(&mut self, sensor_id: &SensorId) -> Result<()>;
+
+    /// Ids of the sensors named exactly `name`
+    fn sensor_ids_by_name(&self, name: &str) -> Result<Vec<SensorId>>;
+
+    /// Metrics of the sensor named exactly `name`
+    fn metrics_by_name(&self, sensor_id: &SensorId, name: &str) -> Result<Vec<Metric>>;
+
+    /// Consistent copy of the entire inventory
+    fn snapshot(&self) -> Result<Sensors>;
+
+    /// Keeps the latest of the timestamps the metric received livedata at
+    fn record_livedata(&mut self, sensor_id: &SensorId, metric_id: &MetricId, timestamp: Timestamp) -> Result<()>;
+
+    fn last_livedata(&self) -> Result<LastLivedata>;
+
+    /// Extends the span this store has been watching livedata over up to `now`
+    fn record_watch(&mut self, now: Timestamp) -> Result<()>;
+
+    /// Part of the span from `since` to `now` neither this store nor the ones sharing its
+    /// history watched livedata over
+    fn unwatched_livedata(&self, since: Timestamp, now: Timestamp) -> Result<Duration>;
+
+    /// Records a raised alert, once even if the stores sharing its history raise it too
+    fn record_alert(&mut self, alert: &AlertRecord) -> Result<()>;
+
+    /// Resolves the alerts of the metric still in force, only the ones raised by `rule` if given
+    fn resolve_alerts(
+        &mut self,
+        sensor_id: &SensorId,
+        metric_id: &MetricId,
+        rule: Option<&str>,
+        at: Timestamp,
+    ) -> Result<()>;
+
+    /// Alerts still in force and the ones resolved since `since`, oldest first
+    fn alert_history(&self, since: Timestamp) -> Result<Vec<AlertRecord>>;
+}
+
+const SCHEMA: &str = "
+    CREATE TABLE sensors (
+        sensor_id BLOB PRIMARY KEY,
+        connector_id BLOB NOT NULL,
+        name TEXT NOT NULL,
+        state INTEGER NOT NULL
+    );
+    CREATE INDEX sensors_by_name ON sensors (name);
+
+    CREATE TABLE metrics (
+        sensor_id BLOB NOT NULL,
+        position INTEGER NOT NULL,
+        name TEXT NOT NULL,
+        definition TEXT NOT NULL,
+        PRIMARY KEY (sensor_id, position)
+    );
+    CREATE INDEX metrics_by_name ON metrics (sensor_id, name);
+";
+
+/// Created in the history file if there is one, in memory next to the inventory otherwise.
+/// A watch is a span a store was running and receiving livedata over.
+const HISTORY_SCHEMA: &str = "
+    CREATE TABLE IF NOT EXISTS {schema}.livedata (
+        sensor_id BLOB NOT NULL,
+        metric_id BLOB NOT NULL,
+        timestamp INTEGER NOT NULL,
+        PRIMARY KEY (sensor_id, metric_id)
+    );
+
+    CREATE TABLE IF NOT EXISTS {schema}.watches (
+        watch_id INTEGER PRIMARY KEY,
+        started_at INTEGER NOT NULL,
+        until INTEGER NOT NULL
+    );
+
+    CREATE TABLE IF NOT EXISTS {schema}.alerts (
+        sensor_id BLOB NOT NULL,
+        metric_id BLOB NOT NULL,
+        rule TEXT NOT NULL,
+        message TEXT NOT NULL,
+        critical INTEGER NOT NULL,
+        fired_at INTEGER NOT NULL,
+        resolved_at INTEGER,
+        silenced INTEGER NOT NULL,
+        PRIMARY KEY (sensor_id, metric_id, rule, fired_at)
+    );
+";
+
+/// Watches and resolved alerts older than this are dropped from the history file, far beyond
+/// any sensible protection window
+const WATCHES_KEPT: Duration = Duration::from_secs(30 * 24 * 3600);
+
+/// Writers of the history file of other clients are waited for this long
+const HISTORY_BUSY_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// In-memory SQLite database, metric definitions are kept as JSON.
+/// The livedata history may be kept in a file attached to it to outlive the run.
+pub struct SqliteStateStore {
+    connection: Connection,
+    watch_id: i64,
+}
+
+impl SqliteStateStore {
+    pub fn new() -> Result<Self> {
+        let connection = Connection::open_in_memory()?;
+        connection
+            .execute_batch(SCHEMA)
+            .and_then(|_| connection.execute_batch(&HISTORY_SCHEMA.replace("{schema}", "main")))
+            .wrap_err("Failed to create the state schema")?;
+        Self::start_watch(connection)
+    }
+
+    /// Keeps the livedata history in `history_file`, shared with the other clients using it
+    pub fn with_history(history_file: &Path) -> Result<Self> {
+        let connection = Connection::open_in_memory()?;
+        connection
+            .execute_batch(SCHEMA)
+            .wrap_err("Failed to create the state schema")?;
+        connection.busy_timeout(HISTORY_BUSY_TIMEOUT)?;
+        connection
+            .execute("ATTACH DATABASE ?1 AS history", params![history_file.to_string_lossy()])
+            .and_then(|_| connection.execute_batch("PRAGMA history.journal_mode = WAL"))
+            .and_then(|_| connection.execute_batch(&HISTORY_SCHEMA.replace("{schema}", "history")))
+            .wrap_err_with(|| format!("Failed to open {}", history_file.display()))?;
+
+        let expired = Timestamp::now().as_millis().saturating_sub(WATCHES_KEPT.as_millis() as u64);
+        connection.execute("DELETE FROM history.watches WHERE until < ?1", params![expired])?;
+        connection.execute("DELETE FROM history.alerts WHERE resolved_at < ?1", params![expired])?;
+        Self::start_watch(connection)
+    }
+
+    fn start_watch(connection: Connection) -> Result<Self> {
+        let now = Timestamp::now().as_millis();
+        connection.execute("INSERT INTO watches (started_at, until) VALUES (?1, ?1)", params![now])?;
+        let watch_id = connection.last_insert_rowid();
+        Ok(Self { connection, watch_id })
+    }
+
+    fn metrics(&self, sensor_id: &SensorId) -> Result<Metrics<Metric>> {
+        let mut statement = self.connection.prepare_cached(
+            "SELECT definition FROM metrics WHERE sensor_id = ?1 ORDER BY position",
+        )?;
+        let definitions = statement
+            .query_map(params![sensor_id.as_uuid()], |row| row.get::<_, String>(0))?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+        definitions
+            .iter()
+            .map(|definition| Ok(serde_json::from_str(definition)?))
+            .collect()
+    }
+}
+
+impl StateStore for SqliteStateStore {
+    fn sensor(&self, sensor_id: &SensorId) -> Result<Option<Sensor<Metric>>> {
+        let row = self
+            .connection
+            .prepare_cached("SELECT connector_id, name, state FROM sensors WHERE sensor_id = ?1")?
+            .query_row(params![sensor_id.as_uuid()], |row| {
+                Ok((
+                    row.get::<_, Uuid>(0)?,
+                    row.get::<_, String>(1)?,
+                    row.get::<_, u8>(2)?,
+                ))
+            })
+            .optional()?;
+
+        let Some((connector_id, name, state)) = row else {
+            return Ok(None);
+        };
+        Ok(Some(Sensor {
+            name,
+            sensor_id: *sensor_id,
+            metrics: self.metrics(sensor_id)?,
+            state: state.into(),
+            connector_id: connector_id.into(),
+        }))
+    }
+
+    fn contains_sensor(&self, sensor_id: &SensorId) -> Result<bool> {
+        let found = self
+            .connection
+            .prepare_cached("SELECT 1 FROM sensors WHERE sensor_id = ?1")?
+            .exists(params![sensor_id.as_uuid()])?;
+        Ok(found)
+    }
+
+    fn put_sensor(&mut self, sensor: &Sensor<Metric>) -> Result<()> {
+        let transaction = self.connection.transaction()?;
+        transaction.execute(
+            "INSERT OR REPLACE INTO sensors (sensor_id, connector_id, name, state) VALUES (?1, ?2, ?3, ?4)",
+            params![
+                sensor.sensor_id.as_uuid(),
+                sensor.connector_id.as_uuid(),
+                sensor.name,
+                u8::from(sensor.state)
+            ],
+        )?;
+        transaction.execute(
+            "DELETE FROM metrics WHERE sensor_id = ?1",
+            params![sensor.sensor_id.as_uuid()],
+        )?;
+        {
+            let mut insert = transaction.prepare_cached(
+                "INSERT INTO metrics (sensor_id, position, name, definition) VALUES (?1, ?2, ?3, ?4)",
+            )?;
+            for (position, metric) in sensor.metrics.iter().enumerate() {
+                insert.execute(params![
+                    sensor.sensor_id.as_uuid(),
+                    position,
+                    metric.name(),
+                    serde_json::to_string(metric)?,
+                ])?;
+            }
+        }
+        transaction.commit()?;
+        Ok(())
+    }
+
+    fn remove_sensor(&mut self, sensor_id: &SensorId) -> Result<()> {
+        let transaction = self.connection.transaction()?;
+        transaction.execute(
+            "DELETE FROM metrics WHERE sensor_id = ?1",
+            params![sensor_id.as_uuid()],
+        )?;
+        transaction.execute(
+            "DELETE FROM sensors WHERE sensor_id = ?1",
+            params![sensor_id.as_uuid()],
+        )?;
+        transaction.execute(
+            "DELETE FROM livedata WHERE sensor_id = ?1",
+            params![sensor_id.as_uuid()],
+        )?;
+        transaction.commit()?;
+        Ok(())
+    }
+
+    fn sensor_ids_by_name(&self, name: &str) -> Result<Vec<SensorId>> {
+        let mut statement = self
+            .connection
+            .prepare_cached("SELECT sensor_id FROM sensors WHERE name = ?1 ORDER BY sensor_id")?;
+        let sensor_ids = statement
+            .query_map(params![name], |row| row.get::<_, Uuid>(0))?
+            .map(|sensor_id| sensor_id.map(SensorId::from))
+            .collect::<rusqlite::Result<_>>()?;
+        Ok(sensor_ids)
+    }
+
+    fn metrics_by_name(&self, sensor_id: &SensorId, name: &str) -> Result<Vec<Metric>> {
+        let mut statement = self.connection.prepare_cached(
+            "SELECT definition FROM metrics WHERE sensor_id = ?1 AND name = ?2 ORDER BY position",
+        )?;
+        let definitions = statement
+            .query_map(params![sensor_id.as_uuid(), name], |row| {
+                row.get::<_, String>(0)
+            })?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+        definitions
+            .iter()
+            .map(|definition| Ok(serde_json::from_str(definition)?))
+            .collect()
+    }
+
+    fn snapshot(&self) -> Result<Sensors> {
+        // Both tables are read within one transaction
+        let transaction = self.connection.unchecked_transaction()?;
+
+        let mut sensors = Sensors::new();
+        {
+            let mut statement =
+                transaction.prepare_cached("SELECT sensor_id, connector_id, name, state FROM sensors")?;
+            let rows = statement.query_map([], |row| {
+                Ok((
+                    row.get::<_, Uuid>(0)?,
+                    row.get::<_, Uuid>(1)?,
+                    row.get::<_, String>(2)?,
+                    row.get::<_, u8>(3)?,
+                ))
+            })?;
+            for row in rows {
+                let (sensor_id, connector_id, name, state) = row?;
+                let sensor_id = SensorId::from(sensor_id);
+                sensors.insert(
+                    sensor_id,
+                    Sensor {
+                        name,
+                        sensor_id,
+                        metrics: Metrics::default(),
+                        state: state.into(),
+                        connector_id: connector_id.into(),
+                    },
+                );
+            }
+
+            let mut statement = transaction
+                .prepare_cached("SELECT sensor_id, definition FROM metrics ORDER BY sensor_id, position")?;
+            let rows = statement.query_map([], |row| {
+                Ok((row.get::<_, Uuid>(0)?, row.get::<_, String>(1)?))
+            })?;
+            for row in rows {
+                let (sensor_id, definition) = row?;
+                if let Some(sensor) = sensors.get_mut(&SensorId::from(sensor_id)) {
+                    sensor.metrics.insert(serde_json::from_str(&definition)?);
+                }
+            }
+        }
+
+        transaction.finish()?;
+        Ok(sensors)
+    }
+
+    fn record_livedata(&mut self, sensor_id: &SensorId, metric_id: &MetricId, timestamp: Timestamp) -> Result<()> {
+        self.connection
+            .prepare_cached(
+                "INSERT INTO livedata (sensor_id, metric_id, timestamp) VALUES (?1, ?2, ?3)
+                 ON CONFLICT (sensor_id, metric_id) DO UPDATE SET timestamp = max(timestamp, excluded.timestamp)",
+            )?
+            .execute(params![sensor_id.as_uuid(), metric_id.as_uuid(), timestamp.as_millis()])?;
+        Ok(())
+    }
+
+    fn last_livedata(&self) -> Result<LastLivedata> {
+        let mut statement = self
+            .connection
+            .prepare_cached("SELECT sensor_id, metric_id, timestamp FROM livedata")?;
+        let last_livedata = statement
+            .query_map([], |row| {
+                Ok((
+                    (SensorId::from(row.get::<_, Uuid>(0)?), MetricId::from(row.get::<_, Uuid>(1)?)),
+                    Timestamp::from_millis(row.get(2)?),
+                ))
+            })?
+            .collect::<rusqlite::Result<_>>()?;
+        Ok(last_livedata)
+    }
+
+    fn record_watch(&mut self, now: Timestamp) -> Result<()> {
+        self.connection
+            .prepare_cached("UPDATE watches SET until = max(until, ?2) WHERE watch_id = ?1")?
+            .execute(params![self.watch_id, now.as_millis()])?;
+        Ok(())
+    }
+
+    fn unwatched_livedata(&self, since: Timestamp, now: Timestamp) -> Result<Duration> {
+        unwatched_within(&self.connection, since, now)
+    }
+
+    fn record_alert(&mut self, alert: &AlertRecord) -> Result<()> {
+        self.connection
+            .prepare_cached(
+                "INSERT OR IGNORE INTO alerts (sensor_id, metric_id, rule, message, critical, fired_at, resolved_at, silenced)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+            )?
+            .execute(params![
+                alert.sensor_id.as_uuid(),
+                alert.metric_id.as_uuid(),
+                alert.rule,
+                alert.message,
+                alert.critical,
+                alert.fired_at.as_millis(),
+                alert.resolved_at.map(|resolved_at| resolved_at.as_millis()),
+                alert.silenced,
+            ])?;
+        Ok(())
+    }
+
+    fn resolve_alerts(
+        &mut self,
+        sensor_id: &SensorId,
+        metric_id: &MetricId,
+        rule: Option<&str>,
+        at: Timestamp,
+    ) -> Result<()> {
+        self.connection
+            .prepare_cached(
+                "UPDATE alerts SET resolved_at = max(fired_at, ?4)
+                 WHERE sensor_id = ?1 AND metric_id = ?2 AND (?3 IS NULL OR rule = ?3) AND resolved_at IS NULL",
+            )?
+            .execute(params![sensor_id.as_uuid(), metric_id.as_uuid(), rule, at.as_millis()])?;
+        Ok(())
+    }
+
+    fn alert_history(&self, since: Timestamp) -> Result<Vec<AlertRecord>> {
+        alert_history_within(&self.connection, since)
+    }
+}
+
+/// Alerts the clients sharing `history_file` recorded, still in force or resolved since `since`,
+/// none if there is no such file yet
+pub fn alert_history(history_file: &Path, since: Timestamp) -> Result<Vec<AlertRecord>> {
+    if !history_file.exists() {
+        return Ok(Vec::new());
+    }
+
+    let connection = Connection::open(history_file)
+        .wrap_err_with(|| format!("Failed to open {}", history_file.display()))?;
+    connection.busy_timeout(HISTORY_BUSY_TIMEOUT)?;
+    connection
+        .execute_batch(&HISTORY_SCHEMA.replace("{schema}", "main"))
+        .map_err(eyre::Report::from)
+        .and_then(|_| alert_history_within(&connection, since))
+        .wrap_err_with(|| format!("Failed to read the alerts of {}", history_file.display()))
+}
+
+fn alert_history_within(connection: &Connection, since: Timestamp) -> Result<Vec<AlertRecord>> {
+    let alerts = connection
+        .prepare_cached(
+            "SELECT sensor_id, metric_id, rule, message, critical, fired_at, resolved_at, silenced FROM alerts
+             WHERE resolved_at IS NULL OR resolved_at >= ?1 ORDER BY fired_at, sensor_id, metric_id, rule",
+        )?
+        .query_map(params![since.as_millis()], |row| {
+            Ok(AlertRecord {
+                sensor_id: SensorId::from(row.get::<_, Uuid>(0)?),
+                metric_id: MetricId::from(row.get::<_, Uuid>(1)?),
+                rule: row.get(2)?,
+                message: row.get(3)?,
+                critical: row.get(4)?,
+                fired_at: Timestamp::from_millis(row.get(5)?),
+                resolved_at: row.get::<_, Option<u64>>(6)?.map(Timestamp::from_millis),
+                silenced: row.get(7)?,
+            })
+        })?
+        .collect::<rusqlite::Result<_>>()?;
+    Ok(alerts)
+}
+
+/// Part of the span from `since` to `now` none of the clients sharing `history_file` watched
+/// livedata over, all of it if there is no such file yet
+pub fn unwatched_livedata(history_file: &Path, since: Timestamp, now: Timestamp) -> Result<Duration> {
+    let whole = Duration::from_millis(now.as_millis().saturating_sub(since.as_millis()));
+    if !history_file.exists() {
+        return Ok(whole);
+    }
+
+    let connection = Connection::open(history_file)
+        .wrap_err_with(|| format!("Failed to open {}", history_file.display()))?;
+    connection.busy_timeout(HISTORY_BUSY_TIMEOUT)?;
+    unwatched_within(&connection, since, now)
+        .wrap_err_with(|| format!("Failed to read the watches of {}", history_file.display()))
+}
+
+/// Part of the span from `since` to `now` the `watches` table of the connection doesn't cover
+fn unwatched_within(connection: &Connection, since: Timestamp, now: Timestamp) -> Result<Duration> {
+    let whole = Duration::from_millis(now.as_millis().saturating_sub(since.as_millis()));
+    let watches = connection
+        .prepare_cached("SELECT started_at, until FROM watches WHERE until >= ?1 ORDER BY started_at")?
+        .query_map(params![since.as_millis()], |row| {
+            Ok((Timestamp::from_millis(row.get(0)?), Timestamp::from_millis(row.get(1)?)))
+        })?
+        .collect::<rusqlite::Result<Vec<_>>>()?;
+    Ok(whole.saturating_sub(watched_within(&watches, since, now)))
+}
+
+/// Length of the union of the `watches` sorted by their start, clipped to `since..now`
+fn watched_within(watches: &[(Timestamp, Timestamp)], since: Timestamp, now: Timestamp) -> Duration {
+    let mut watched = 0;
+    let mut covered_until = since.as_millis();
+    for (started_at, until) in watches {
+        let start = started_at.as_millis().max(covered_until);
+        let end = until.as_millis().min(now.as_millis());
+        if end > start {
+            watched += end - start;
+            covered_until = end;
+        }
+    }
+    Duration::from_millis(watched)
+}
@@ -0,0 +1,1363 @@
+use eyre::{eyre, Result, WrapErr};
+
+use log::LevelFilter;
+
+use schemars::JsonSchema;
+
+use serde::de::DeserializeOwned;
+use serde::Deserialize;
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::time::Duration;
+
+use crate::client::mqtt::MqttCredentials;
+use crate::client::state::{AlertRule, MaintenanceWindow};
+use crate::i18n::Locale;
+use crate::logging::{Subsystem, LOG_FILE};
+use crate::migrations::{self, ConfigUpgrade, MIGRATIONS};
+use crate::model::sensor::{split_target, Metric, ValueType, ValueUnit};
+use crate::model::SensorId;
+use crate::notifications::{Category, Route, Severity};
+use crate::tui_app::keymap::{self, KeyBinding, KeymapPreset};
+use crate::tui_app::ui_state::RegionSizes;
+use crate::utils::duration_secs;
+
+/// Same as the one of actix
+pub const DEFAULT_MAILBOX_CAPACITY: usize = 16;
+
+/// Looked up in the working directory unless `--config` is given
+pub const DEFAULT_CONFIG_FILE: &str = "sensor-vision.toml";
+
+/// Alert rules kept apart from the config, replace the `[[alerts]]` of the config if present
+pub const DEFAULT_ALERTS_FILE: &str = "sensor-vision-alerts.yaml";
+
+/// Sensors muted in the TUI, per profile, written whenever a sensor is muted or unmuted
+pub const DEFAULT_MUTES_FILE: &str = "sensor-vision-mutes.yaml";
+
+/// Sizes of the TUI regions, per profile, written whenever a region is resized
+pub const DEFAULT_LAYOUT_FILE: &str = "sensor-vision-layout.yaml";
+
+/// Keys of the TUI actions replacing the ones of the keymap preset, written by `keymap export`
+pub const DEFAULT_KEYMAP_FILE: &str = "sensor-vision-keymap.yaml";
+
+/// Time of the latest livedata of every metric, kept across runs for `import --prune`
+pub const DEFAULT_HISTORY_FILE: &str = "sensor-vision-history.sqlite";
+
+/// Mutes and region sizes of the runs without a profile are kept under this name
+pub const NO_PROFILE: &str = "default";
+
+const ENV_PREFIX: &str = "SENSOR_VISION_";
+
+/// Settings are taken from the config file, then overridden by `SENSOR_VISION_*`
+/// environment variables, and finally by the command line.
+#[derive(Debug, Clone, Default, Deserialize, JsonSchema)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    /// Format of the file, older ones are upgraded on load
+    pub version: u32,
+
+    pub broker: BrokerConfig,
+    pub tls: TlsConfig,
+    pub state: StateConfig,
+    pub exporter: ExporterConfig,
+    pub api: ApiConfig,
+    pub import: ImportConfig,
+    pub push: PushConfig,
+
+    /// The gRPC service of the `grpc` feature
+    pub grpc: GrpcConfig,
+
+    /// Registers polled by the collector of the `modbus` feature
+    pub modbus: Vec<ModbusSourceConfig>,
+
+    /// Serial lines read by the collector of the `modbus` feature
+    pub serial: Vec<SerialLineConfig>,
+
+    /// JSON endpoints polled for values
+    pub http: Vec<HttpPollConfig>,
+
+    /// Topics of other brokers bridged into metrics
+    pub mqtt_in: Vec<MqttInConfig>,
+
+    /// Throttling of the collectors per `SENSOR/METRIC` (a `/` in the sensor name escaped as `\/`),
+    /// replacing the one of the collector
+    pub throttle: BTreeMap<String, ThrottleConfig>,
+
+    /// Conversion of the readings per `SENSOR/METRIC` (a `/` in the sensor name escaped as `\/`),
+    /// replacing the one of the collector
+    pub conversion: BTreeMap<String, ConversionConfig>,
+    pub alerts: Vec<AlertRule>,
+
+    /// Spans the alerts of the matching metrics are silenced over
+    pub maintenance: Vec<MaintenanceWindow>,
+
+    /// YAML list of alert rules, [`DEFAULT_ALERTS_FILE`] in the working directory by default
+    pub alerts_file: Option<PathBuf>,
+
+    /// YAML lists of muted sensors by profile, [`DEFAULT_MUTES_FILE`] in the working
+    /// directory by default
+    pub mutes_file: Option<PathBuf>,
+
+    /// YAML region sizes by profile, [`DEFAULT_LAYOUT_FILE`] in the working directory by default
+    pub layout_file: Option<PathBuf>,
+
+    /// YAML keys by action, [`DEFAULT_KEYMAP_FILE`] in the working directory by default
+    pub keymap_file: Option<PathBuf>,
+    pub notifications: NotificationsConfig,
+    pub ui: UiConfig,
+    pub log: LogConfig,
+    pub client_id_prefix: Option<String>,
+
+    /// Profile applied unless `--profile` names another one
+    pub profile: Option<String>,
+
+    /// Broker and certificates per environment, e.g. `[profiles.prod]`
+    pub profiles: BTreeMap<String, ProfileConfig>,
+
+    /// Name of the profile applied
+    #[serde(skip)]
+    pub active_profile: Option<String>,
+
+    /// Set if the file was upgraded on load
+    #[serde(skip)]
+    pub upgrade: Option<ConfigUpgrade>,
+
+    /// File read on load, if any
+    #[serde(skip)]
+    pub file: Option<PathBuf>,
+}
+
+#[derive(Debug, Clone, Deserialize, JsonSchema)]
+#[serde(default, deny_unknown_fields)]
+pub struct BrokerConfig {
+    pub host: String,
+
+    /// Port of the connector API
+    pub port: u16,
+
+    /// Port certificates are requested through
+    pub cert_port: u16,
+
+    pub keepalive_secs: u64,
+
+    /// How long a request waits for its reply before failing
+    pub request_timeout_secs: u64,
+
+    /// `MetricDescribe` requests sent at once when loading the sensors, 0 sends all of them
+    pub describe_batch_size: usize,
+
+    /// Pause between the batches of `MetricDescribe` requests
+    pub describe_interval_ms: u64,
+
+    /// Plain TCP if disabled, which only local test brokers accept
+    pub tls: bool,
+}
+
+impl Default for BrokerConfig {
+    fn default() -> Self {
+        Self {
+            host: String::from("localhost"),
+            port: 18884,
+            cert_port: 18883,
+            keepalive_secs: 120,
+            request_timeout_secs: 30,
+            describe_batch_size: 20,
+            describe_interval_ms: 100,
+            tls: true,
+        }
+    }
+}
+
+impl BrokerConfig {
+    pub fn uri(&self) -> String {
+        format!("{}://{}:{}", self.scheme(), self.host, self.port)
+    }
+
+    pub fn cert_uri(&self) -> String {
+        format!("{}://{}:{}", self.scheme(), self.host, self.cert_port)
+    }
+
+    fn scheme(&self) -> &'static str {
+        if self.tls { "mqtts" } else { "mqtt" }
+    }
+
+    pub fn keepalive(&self) -> Duration {
+        Duration::from_secs(self.keepalive_secs)
+    }
+
+    pub fn request_timeout(&self) -> Duration {
+        Duration::from_secs(self.request_timeout_secs)
+    }
+
+    pub fn describe_interval(&self) -> Duration {
+        Duration::from_millis(self.describe_interval_ms)
+    }
+}
+
+/// Replaces the broker and the certificates of the config, unset values are left as they are
+#[derive(Debug, Clone, Default, Deserialize, JsonSchema)]
+#[serde(default, deny_unknown_fields)]
+pub struct ProfileConfig {
+    /// `mqtts://HOST:PORT`, or `mqtt://HOST:PORT` for plain TCP
+    pub broker: Option<String>,
+    pub cert_port: Option<u16>,
+    pub trust_store: Option<String>,
+    pub client_cert: Option<String>,
+    pub private_key: Option<String>,
+
+    /// Connector the client certificate has to belong to, guards against mixing up
+    /// the certificates of the environments
+    pub connector_id: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, Deserialize, JsonSchema)]
+#[serde(default, deny_unknown_fields)]
+pub struct TlsConfig {
+    pub trust_store: Option<String>,
+    pub client_cert: Option<String>,
+    pub private_key: Option<String>,
+}
+
+#[derive(Debug, Clone, Deserialize, JsonSchema)]
+#[serde(default, deny_unknown_fields)]
+pub struct StateConfig {
+    /// Number of actors the sensors are spread across, only worth raising
+    /// for connectors with thousands of metrics
+    pub shards: usize,
+
+    /// Queries waiting for a state actor before the senders are held back
+    pub mailbox_capacity: usize,
+
+    /// SQLite file keeping when the metrics last received livedata, when they were watched,
+    /// and the alerts raised, across runs. [`DEFAULT_HISTORY_FILE`] in the working directory once
+    /// the config is loaded, only kept in memory by clients built without a config
+    pub history_file: Option<PathBuf>,
+}
+
+impl Default for StateConfig {
+    fn default() -> Self {
+        Self {
+            shards: 1,
+            mailbox_capacity: DEFAULT_MAILBOX_CAPACITY,
+            history_file: None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Deserialize, JsonSchema)]
+#[serde(default, deny_unknown_fields)]
+pub struct ExporterConfig {
+    /// Address the Prometheus `/metrics` endpoint is served on, disabled if unset
+    pub listen: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, Deserialize, JsonSchema)]
+#[serde(default, deny_unknown_fields)]
+pub struct ApiConfig {
+    /// Address the REST API of the `api` feature is served on, disabled if unset
+    pub listen: Option<String>,
+
+    /// Bearer token the requests have to carry, required unless listening on a loopback address
+    pub token: Option<String>,
+}
+
+#[derive(Debug, Clone, Deserialize, JsonSchema)]
+#[serde(default, deny_unknown_fields)]
+pub struct ImportConfig {
+    /// Sensors and metrics which received livedata within this many seconds aren't pruned
+    /// without `--allow-data-loss`, 0 disables the protection
+    pub protect_livedata_secs: u64,
+}
+
+impl Default for ImportConfig {
+    fn default() -> Self {
+        Self {
+            protect_livedata_secs: 3600,
+        }
+    }
+}
+
+/// Pushes by name, of `push` and the collectors, to a metric which doesn't exist
+#[derive(Debug, Clone, Deserialize, JsonSchema)]
+#[serde(default, deny_unknown_fields)]
+pub struct PushConfig {
+    /// Create the missing sensor or metric rather than failing the push
+    pub create_missing: bool,
+
+    /// Unit of the created metrics, custom metrics of `value_type` are created if unset
+    pub value_unit: Option<ValueUnit>,
+    pub value_type: ValueType,
+
+    /// Shown after the values of the created custom metrics
+    pub value_annotation: String,
+}
+
+impl Default for PushConfig {
+    fn default() -> Self {
+        Self {
+            create_missing: false,
+            value_unit: None,
+            value_type: ValueType::Double,
+            value_annotation: "value".into(),
+        }
+    }
+}
+
+impl PushConfig {
+    /// The metric created for a push to `name`, if missing metrics are created
+    pub fn missing_metric(&self, name: &str) -> Option<Metric> {
+        if !self.create_missing {
+            return None;
+        }
+        Some(match &self.value_unit {
+            Some(value_unit) => Metric::predefined(name.to_owned(), value_unit.clone()),
+            None => Metric::custom(
+                name.to_owned(),
+                self.value_type.clone(),
+                self.value_annotation.clone(),
+            ),
+        })
+    }
+}
+
+#[derive(Debug, Clone, Default, Deserialize, JsonSchema)]
+#[serde(default, deny_unknown_fields)]
+pub struct GrpcConfig {
+    /// Address the gRPC service is served on, disabled if unset
+    pub listen: Option<String>,
+}
+
+#[derive(Debug, Clone, Deserialize, JsonSchema)]
+#[serde(default, deny_unknown_fields)]
+pub struct ModbusSourceConfig {
+    /// `tcp://HOST[:PORT]` for Modbus TCP, a serial device such as `/dev/ttyUSB0` for Modbus RTU
+    pub address: String,
+
+    /// Slave the registers are read from
+    pub unit_id: u8,
+
+    pub interval_secs: f64,
+
+    /// Of the serial device, 8N1 is assumed
+    pub baud_rate: u32,
+
+    pub registers: Vec<ModbusRegisterConfig>,
+    pub throttle: ThrottleConfig,
+    pub conversion: ConversionConfig,
+}
+
+impl Default for ModbusSourceConfig {
+    fn default() -> Self {
+        Self {
+            address: String::new(),
+            unit_id: 1,
+            interval_secs: 5.0,
+            baud_rate: 9600,
+            registers: Vec::new(),
+            throttle: ThrottleConfig::default(),
+            conversion: ConversionConfig::default(),
+        }
+    }
+}
+
+impl ModbusSourceConfig {
+    pub fn interval(&self) -> Duration {
+        Duration::from_secs_f64(self.interval_secs)
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Deserialize, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum RegisterKind {
+    #[default]
+    Holding,
+    Input,
+    Coil,
+    Discrete,
+}
+
+/// How the registers are decoded, the 32-bit formats span two registers
+#[derive(Debug, Clone, Copy, Default, PartialEq, Deserialize, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum RegisterFormat {
+    #[default]
+    U16,
+    I16,
+    U32,
+    I32,
+    F32,
+}
+
+impl RegisterFormat {
+    pub fn registers(&self) -> u16 {
+        match self {
+            Self::U16 | Self::I16 => 1,
+            Self::U32 | Self::I32 | Self::F32 => 2,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize, JsonSchema)]
+#[serde(default, deny_unknown_fields)]
+pub struct ModbusRegisterConfig {
+    /// `SENSOR/METRIC` the reading is pushed to
+    pub target: String,
+
+    /// Zero-based, as sent on the wire
+    pub address: u16,
+
+    pub kind: RegisterKind,
+    pub format: RegisterFormat,
+
+    /// The low word of the 32-bit formats comes first
+    pub swap_words: bool,
+
+    /// The pushed value is `raw * scale + offset`
+    pub scale: f64,
+    pub offset: f64,
+}
+
+impl Default for ModbusRegisterConfig {
+    fn default() -> Self {
+        Self {
+            target: String::new(),
+            address: 0,
+            kind: RegisterKind::default(),
+            format: RegisterFormat::default(),
+            swap_words: false,
+            scale: 1.0,
+            offset: 0.0,
+        }
+    }
+}
+
+/// Device printing a reading per line, as `NAME=VALUE`, `NAME: VALUE` or `NAME,VALUE`
+#[derive(Debug, Clone, Deserialize, JsonSchema)]
+#[serde(default, deny_unknown_fields)]
+pub struct SerialLineConfig {
+    pub device: PathBuf,
+    pub baud_rate: u32,
+
+    /// `SENSOR/METRIC` each reading is pushed to by its name, other readings are ignored
+    pub map: BTreeMap<String, String>,
+    pub throttle: ThrottleConfig,
+    pub conversion: ConversionConfig,
+}
+
+impl Default for SerialLineConfig {
+    fn default() -> Self {
+        Self {
+            device: PathBuf::new(),
+            baud_rate: 9600,
+            map: BTreeMap::new(),
+            throttle: ThrottleConfig::default(),
+            conversion: ConversionConfig::default(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize, JsonSchema)]
+#[serde(default, deny_unknown_fields)]
+pub struct HttpPollConfig {
+    /// Plain `http://` URL answering GET requests with JSON
+    pub url: String,
+
+    pub interval_secs: f64,
+
+    /// Sent with every request, e.g. `Authorization`
+    pub headers: BTreeMap<String, String>,
+
+    /// `SENSOR/METRIC` each value is pushed to by the JSONPath it's extracted with
+    pub map: BTreeMap<String, String>,
+
+    /// Failed polls are retried after twice the previous delay, at most this long
+    pub max_backoff_secs: f64,
+    pub throttle: ThrottleConfig,
+    pub conversion: ConversionConfig,
+}
+
+impl Default for HttpPollConfig {
+    fn default() -> Self {
+        Self {
+            url: String::new(),
+            interval_secs: 60.0,
+            headers: BTreeMap::new(),
+            map: BTreeMap::new(),
+            max_backoff_secs: 15.0 * 60.0,
+            throttle: ThrottleConfig::default(),
+            conversion: ConversionConfig::default(),
+        }
+    }
+}
+
+impl HttpPollConfig {
+    /// Catches intervals which aren't a positive number of seconds a `Duration` can hold
+    pub fn validate(&self) -> Result<()> {
+        let interval = Duration::try_from_secs_f64(self.interval_secs)
+            .ok()
+            .filter(|interval| !interval.is_zero());
+        if interval.is_none() {
+            return Err(eyre!("Interval of endpoint {} must be positive, not {}", self.url, self.interval_secs));
+        }
+        if Duration::try_from_secs_f64(self.max_backoff_secs).is_err() {
+            return Err(eyre!(
+                "Max backoff of endpoint {} must not be negative, not {}",
+                self.url,
+                self.max_backoff_secs
+            ));
+        }
+        Ok(())
+    }
+
+    /// Only valid once [`validate`](Self::validate) succeeded
+    pub fn interval(&self) -> Duration {
+        Duration::from_secs_f64(self.interval_secs)
+    }
+
+    /// Never shorter than the interval
+    pub fn max_backoff(&self) -> Duration {
+        Duration::try_from_secs_f64(self.max_backoff_secs)
+            .unwrap_or(Duration::MAX)
+            .max(self.interval())
+    }
+}
+
+#[derive(Debug, Clone, Deserialize, JsonSchema)]
+#[serde(default, deny_unknown_fields)]
+pub struct MqttInConfig {
+    /// `mqtt://HOST[:PORT]`, or `mqtts://HOST[:PORT]` over TLS
+    pub broker: String,
+
+    pub client_id: String,
+    pub username: Option<String>,
+    pub password: Option<String>,
+
+    /// CA certificate of a `mqtts://` broker, the system ones are trusted if unset
+    pub trust_store: Option<String>,
+
+    pub map: Vec<MqttInMappingConfig>,
+    pub throttle: ThrottleConfig,
+    pub conversion: ConversionConfig,
+}
+
+impl Default for MqttInConfig {
+    fn default() -> Self {
+        Self {
+            broker: String::new(),
+            client_id: String::from("sensor-vision-bridge"),
+            username: None,
+            password: None,
+            trust_store: None,
+            map: Vec::new(),
+            throttle: ThrottleConfig::default(),
+            conversion: ConversionConfig::default(),
+        }
+    }
+}
+
+/// `target` and `value` are templates with `{topic}`, `{topic[N]}` (zero-based level of the
+/// topic), `{payload}` and `{$.json.path}` placeholders, the latter picking a field of a JSON payload
+#[derive(Debug, Clone, Deserialize, JsonSchema)]
+#[serde(default, deny_unknown_fields)]
+pub struct MqttInMappingConfig {
+    /// Filter subscribed to, `+` and `#` wildcards allowed
+    pub topic: String,
+
+    /// `SENSOR/METRIC` the value is pushed to
+    pub target: String,
+
+    pub value: String,
+}
+
+impl Default for MqttInMappingConfig {
+    fn default() -> Self {
+        Self {
+            topic: String::new(),
+            target: String::new(),
+            value: String::from("{payload}"),
+        }
+    }
+}
+
+/// Readings of a collector that aren't pushed, both filters are off by default
+#[derive(Debug, Clone, Copy, Default, PartialEq, Deserialize, JsonSchema)]
+#[serde(default, deny_unknown_fields)]
+pub struct ThrottleConfig {
+    /// Readings within this distance of the last pushed one are dropped, `0` drops repeated
+    /// readings only. Non-numeric readings are dropped when equal.
+    pub deadband: Option<f64>,
+
+    /// Readings coming sooner than this after the last pushed one are dropped
+    pub min_interval_secs: f64,
+}
+
+impl ThrottleConfig {
+    /// The throttling configured for the target, the collector's one otherwise
+    pub fn of_target(throttles: &BTreeMap<String, Self>, target: &str, collector: Self) -> Self {
+        configured_for(throttles, target).unwrap_or(collector)
+    }
+
+    pub fn min_interval(&self) -> Duration {
+        Duration::from_secs_f64(self.min_interval_secs.max(0.0))
+    }
+}
+
+/// Applied to the readings of a collector before the throttling, both are off by default
+#[derive(Debug, Clone, Copy, Default, PartialEq, Deserialize, JsonSchema)]
+#[serde(default, deny_unknown_fields)]
+pub struct ConversionConfig {
+    /// Unit the readings come in, converted to the unit of the predefined metric they go to
+    pub unit: Option<SourceUnit>,
+
+    /// Decimal places the readings are rounded to
+    pub precision: Option<u8>,
+}
+
+impl ConversionConfig {
+    /// The conversion configured for the target, the collector's one otherwise
+    pub fn of_target(conversions: &BTreeMap<String, Self>, target: &str, collector: Self) -> Self {
+        configured_for(conversions, target).unwrap_or(collector)
+    }
+}
+
+/// Setting keyed by the target, the keys are compared parsed so that escaping a `/` in the
+/// metric name or not makes no difference
+fn configured_for<T: Copy>(settings: &BTreeMap<String, T>, target: &str) -> Option<T> {
+    settings.get(target).copied().or_else(|| {
+        let target = split_target(target)?;
+        settings
+            .iter()
+            .find(|(key, _)| split_target(key).as_ref() == Some(&target))
+            .map(|(_, setting)| *setting)
+    })
+}
+
+/// Units the readings of the collectors may come in. The ones of the predefined metrics are
+/// there as well, so that readings in them can be checked against the metric.
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum SourceUnit {
+    Celsius,
+    Fahrenheit,
+    Kelvin,
+
+    Meter,
+    Millimeter,
+    Centimeter,
+    Kilometer,
+    Inch,
+    Foot,
+    Mile,
+
+    Kilogram,
+    Gram,
+    Pound,
+    Ounce,
+
+    Pascal,
+    Hectopascal,
+    Kilopascal,
+    Bar,
+    Millibar,
+    Psi,
+
+    MetersPerSecond,
+    KilometersPerHour,
+    MilesPerHour,
+    Knot,
+
+    Second,
+    Millisecond,
+    Minute,
+    Hour,
+
+    Joule,
+    Kilojoule,
+    WattHour,
+    KilowattHour,
+
+    Watt,
+    Milliwatt,
+    Kilowatt,
+
+    Ampere,
+    Milliampere,
+    Volt,
+    Millivolt,
+    Kilovolt,
+
+    Hertz,
+    Kilohertz,
+    Megahertz,
+
+    Radian,
+    Degree,
+
+    Bit,
+    Byte,
+}
+
+impl SourceUnit {
+    /// Unit of the predefined metrics the readings convert to, with the conversion being
+    /// `reading * scale + offset`
+    pub fn conversion(&self) -> (ValueUnit, f64, f64) {
+        use SourceUnit::*;
+        match self {
+            Celsius => (ValueUnit::Celsius, 1.0, 0.0),
+            Fahrenheit => (ValueUnit::Celsius, 5.0 / 9.0, -160.0 / 9.0),
+            Kelvin => (ValueUnit::Celsius, 1.0, -273.15),
+
+            Meter => (ValueUnit::Meter, 1.0, 0.0),
+            Millimeter => (ValueUnit::Meter, 1e-3, 0.0),
+            Centimeter => (ValueUnit::Meter, 1e-2, 0.0),
+            Kilometer => (ValueUnit::Meter, 1e3, 0.0),
+            Inch => (ValueUnit::Meter, 0.0254, 0.0),
+            Foot => (ValueUnit::Meter, 0.3048, 0.0),
+            Mile => (ValueUnit::Meter, 1609.344, 0.0),
+
+            Kilogram => (ValueUnit::Kilogram, 1.0, 0.0),
+            Gram => (ValueUnit::Kilogram, 1e-3, 0.0),
+            Pound => (ValueUnit::Kilogram, 0.45359237, 0.0),
+            Ounce => (ValueUnit::Kilogram, 0.028349523125, 0.0),
+
+            Pascal => (ValueUnit::Pascal, 1.0, 0.0),
+            Hectopascal | Millibar => (ValueUnit::Pascal, 1e2, 0.0),
+            Kilopascal => (ValueUnit::Pascal, 1e3, 0.0),
+            Bar => (ValueUnit::Pascal, 1e5, 0.0),
+            Psi => (ValueUnit::Pascal, 6894.757293168, 0.0),
+
+            MetersPerSecond => (ValueUnit::MetersPerSecond, 1.0, 0.0),
+            KilometersPerHour => (ValueUnit::MetersPerSecond, 1.0 / 3.6, 0.0),
+            MilesPerHour => (ValueUnit::MetersPerSecond, 0.44704, 0.0),
+            Knot => (ValueUnit::MetersPerSecond, 1852.0 / 3600.0, 0.0),
+
+            Second => (ValueUnit::Second, 1.0, 0.0),
+            Millisecond => (ValueUnit::Second, 1e-3, 0.0),
+            Minute => (ValueUnit::Second, 60.0, 0.0),
+            Hour => (ValueUnit::Second, 3600.0, 0.0),
+
+            Joule => (ValueUnit::Joule, 1.0, 0.0),
+            Kilojoule => (ValueUnit::Joule, 1e3, 0.0),
+            WattHour => (ValueUnit::Joule, 3600.0, 0.0),
+            KilowattHour => (ValueUnit::Joule, 3.6e6, 0.0),
+
+            Watt => (ValueUnit::Watt, 1.0, 0.0),
+            Milliwatt => (ValueUnit::Watt, 1e-3, 0.0),
+            Kilowatt => (ValueUnit::Watt, 1e3, 0.0),
+
+            Ampere => (ValueUnit::Ampere, 1.0, 0.0),
+            Milliampere => (ValueUnit::Ampere, 1e-3, 0.0),
+            Volt => (ValueUnit::Volt, 1.0, 0.0),
+            Millivolt => (ValueUnit::Volt, 1e-3, 0.0),
+            Kilovolt => (ValueUnit::Volt, 1e3, 0.0),
+
+            Hertz => (ValueUnit::Hertz, 1.0, 0.0),
+            Kilohertz => (ValueUnit::Hertz, 1e3, 0.0),
+            Megahertz => (ValueUnit::Hertz, 1e6, 0.0),
+
+            Radian => (ValueUnit::Radian, 1.0, 0.0),
+            Degree => (ValueUnit::Radian, std::f64::consts::PI / 180.0, 0.0),
+
+            Bit => (ValueUnit::Bit, 1.0, 0.0),
+            Byte => (ValueUnit::Bit, 8.0, 0.0),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize, JsonSchema)]
+#[serde(default, deny_unknown_fields)]
+pub struct UiConfig {
+    /// `SENSOR[/METRIC]` selected on startup, by id or (fuzzy matched) name
+    pub select: Option<String>,
+
+    /// How often the agent is pinged to tell whether it's responsive, `0` disables pinging
+    pub ping_interval_secs: f64,
+
+    /// Messages waiting for the UI actors before the senders are held back
+    pub mailbox_capacity: usize,
+
+    /// Livedata values waiting to be shown past which they stop triggering renders
+    /// until the UI catches up, `0` never sheds renders
+    pub overload_threshold: usize,
+
+    /// Language of the dialogs, instructions and empty states, `en` or `ru`
+    pub locale: Locale,
+
+    /// Keys of the main view, `default`, `vim` or `emacs`, the keymap file replaces some of them
+    pub keymap: KeymapPreset,
+}
+
+impl Default for UiConfig {
+    fn default() -> Self {
+        Self {
+            select: None,
+            ping_interval_secs: 30.0,
+            mailbox_capacity: DEFAULT_MAILBOX_CAPACITY,
+            overload_threshold: 256,
+            locale: Locale::default(),
+            keymap: KeymapPreset::default(),
+        }
+    }
+}
+
+impl UiConfig {
+    pub fn ping_interval(&self) -> Option<Duration> {
+        (self.ping_interval_secs > 0.0 && self.ping_interval_secs.is_finite())
+            .then(|| Duration::from_secs_f64(self.ping_interval_secs))
+    }
+}
+
+#[derive(Debug, Clone, Deserialize, JsonSchema)]
+#[serde(default, deny_unknown_fields)]
+pub struct LogConfig {
+    pub file: PathBuf,
+
+    /// Level of every subsystem on startup (`off`, `error`, `warn`, `info`, `debug`
+    /// or `trace`), MQTT payloads are traced at `trace`. Defaults per subsystem if unset.
+    pub level: Option<String>,
+
+    /// Levels of single subsystems (`mqtt`, `state`, `client`, `ui` or `other`) over `level`,
+    /// written whenever they're changed in the TUI
+    pub levels: BTreeMap<String, String>,
+
+    /// The file is rotated once it grows past this size
+    pub max_size_kb: u64,
+
+    /// Rotated files kept next to the current one, `FILE.1` being the latest
+    pub max_files: usize,
+
+    /// Every MQTT message received is appended to this file, to be fed back with `--replay`
+    pub journal: Option<String>,
+
+    /// JSON payloads are logged indented over several lines
+    pub pretty_payloads: bool,
+
+    /// Strings in payloads longer than this many characters are cut, `0` keeps them whole
+    pub max_value_len: usize,
+
+    /// Fields of JSON payloads whose string values are replaced before they're logged,
+    /// e.g. `value` for string metric values which may contain personal data
+    pub redact: Vec<String>,
+}
+
+impl Default for LogConfig {
+    fn default() -> Self {
+        Self {
+            file: PathBuf::from(LOG_FILE),
+            level: None,
+            levels: BTreeMap::new(),
+            max_size_kb: 10 * 1024,
+            max_files: 3,
+            journal: None,
+            pretty_payloads: false,
+            max_value_len: 0,
+            redact: Vec::new(),
+        }
+    }
+}
+
+impl LogConfig {
+    pub fn level(&self) -> Result<Option<LevelFilter>> {
+        self.level
+            .as_deref()
+            .map(|level| {
+                level
+                    .parse()
+                    .wrap_err_with(|| format!("Invalid log level '{level}'"))
+            })
+            .transpose()
+    }
+
+    pub fn levels(&self) -> Result<Vec<(Subsystem, LevelFilter)>> {
+        self.levels
+            .iter()
+            .map(|(name, level)| {
+                let subsystem = Subsystem::named(name).ok_or_else(|| {
+                    eyre!("Unknown subsystem '{name}' in log.levels, expected mqtt, state, client, ui or other")
+                })?;
+                let level = level
+                    .parse()
+                    .wrap_err_with(|| format!("Invalid log level '{level}' of {name}"))?;
+                Ok((subsystem, level))
+            })
+            .collect()
+    }
+}
+
+#[derive(Debug, Clone, Deserialize, JsonSchema)]
+#[serde(default, deny_unknown_fields)]
+pub struct NotificationsConfig {
+    /// Least severe notification shown in the log pane
+    pub toast_severity: Severity,
+
+    /// Raise desktop notifications while the terminal is in the background
+    pub desktop: bool,
+
+    /// Run with the summary and the body appended
+    pub command: String,
+
+    pub desktop_severity: Severity,
+
+    /// Ring the terminal bell
+    pub bell: bool,
+
+    pub bell_severity: Severity,
+
+    pub webhooks: Vec<WebhookConfig>,
+}
+
+impl Default for NotificationsConfig {
+    fn default() -> Self {
+        Self {
+            toast_severity: Severity::Warning,
+            desktop: false,
+            command: String::from("notify-send"),
+            desktop_severity: Severity::Critical,
+            bell: false,
+            bell_severity: Severity::Critical,
+            webhooks: Vec::new(),
+        }
+    }
+}
+
+impl NotificationsConfig {
+    pub fn desktop_command(&self) -> Option<String> {
+        self.desktop.then(|| self.command.clone())
+    }
+}
+
+#[derive(Debug, Clone, Deserialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct WebhookConfig {
+    /// Plain `http://` URL the notifications are POSTed to as JSON
+    pub url: String,
+
+    #[serde(default)]
+    pub min_severity: Severity,
+
+    /// Every category if empty
+    #[serde(default)]
+    pub categories: Vec<Category>,
+}
+
+impl WebhookConfig {
+    pub fn route(&self) -> Route {
+        Route {
+            categories: self.categories.clone(),
+            ..Route::from_severity(self.min_severity)
+        }
+    }
+}
+
+impl Config {
+    /// Reads `path`, or [`DEFAULT_CONFIG_FILE`] if it exists, and applies `profile`,
+    /// falling back to the one of `SENSOR_VISION_PROFILE` or the config, then the environment overrides
+    pub fn load(path: Option<&Path>, profile: Option<&str>) -> Result<Self> {
+        let mut config = match path {
+            Some(path) => Self::from_file(path)?,
+            None if Path::new(DEFAULT_CONFIG_FILE).exists() => {
+                Self::from_file(Path::new(DEFAULT_CONFIG_FILE))?
+            }
+            None => Self::default(),
+        };
+        let profile = profile
+            .map(str::to_owned)
+            .or_else(|| std::env::var(format!("{ENV_PREFIX}PROFILE")).ok())
+            .or_else(|| config.profile.clone());
+        if let Some(profile) = profile {
+            config.apply_profile(&profile)?;
+        }
+        config.apply_env()?;
+        config
+            .state
+            .history_file
+            .get_or_insert_with(|| PathBuf::from(DEFAULT_HISTORY_FILE));
+
+        let alerts_file = config.alerts_file();
+        if alerts_file.exists() {
+            config.alerts = read_alert_rules(&alerts_file)?;
+        }
+        for window in &config.maintenance {
+            window.validate()?;
+        }
+        for endpoint in &config.http {
+            endpoint.validate()?;
+        }
+        Ok(config)
+    }
+
+    /// The file read on load, or the default one to create
+    pub fn config_file(&self) -> PathBuf {
+        self.file
+            .clone()
+            .unwrap_or_else(|| PathBuf::from(DEFAULT_CONFIG_FILE))
+    }
+
+    pub fn alerts_file(&self) -> PathBuf {
+        self.alerts_file
+            .clone()
+            .unwrap_or_else(|| PathBuf::from(DEFAULT_ALERTS_FILE))
+    }
+
+    pub fn mutes_file(&self) -> PathBuf {
+        self.mutes_file
+            .clone()
+            .unwrap_or_else(|| PathBuf::from(DEFAULT_MUTES_FILE))
+    }
+
+    pub fn layout_file(&self) -> PathBuf {
+        self.layout_file
+            .clone()
+            .unwrap_or_else(|| PathBuf::from(DEFAULT_LAYOUT_FILE))
+    }
+
+    pub fn keymap_file(&self) -> PathBuf {
+        self.keymap_file
+            .clone()
+            .unwrap_or_else(|| PathBuf::from(DEFAULT_KEYMAP_FILE))
+    }
+
+    /// Bindings of the keymap preset with the keys of the keymap file, if any, replacing theirs
+    pub fn keymap(&self) -> Result<Vec<KeyBinding>> {
+        let keymap_file = self.keymap_file();
+        let keys = if keymap_file.exists() {
+            read_keymap(&keymap_file)?
+        } else {
+            BTreeMap::new()
+        };
+        keymap::keymap(self.ui.keymap, &keys)
+            .wrap_err_with(|| format!("Invalid keymap in {}", keymap_file.display()))
+    }
+
+    fn from_file(path: &Path) -> Result<Self> {
+        let contents = std::fs::read_to_string(path)
+            .wrap_err_with(|| format!("Failed to read {}", path.display()))?;
+        let mut table: toml::Table = toml::from_str(&contents)
+            .wrap_err_with(|| format!("Failed to parse {}", path.display()))?;
+        let upgrade = migrations::upgrade_file(path, &mut table, MIGRATIONS)
+            .wrap_err_with(|| format!("Failed to upgrade {}", path.display()))?;
+        let mut config: Self = table
+            .try_into()
+            .wrap_err_with(|| format!("Failed to parse {}", path.display()))?;
+        config.upgrade = upgrade;
+        config.file = Some(path.to_owned());
+        Ok(config)
+    }
+
+    fn apply_profile(&mut self, name: &str) -> Result<()> {
+        let profile = self.profiles.get(name).cloned().ok_or_else(|| {
+            let known: Vec<&str> = self.profiles.keys().map(String::as_str).collect();
+            eyre!("Unknown profile {name}, the config has {}", known.join(", "))
+        })?;
+        if let Some(broker) = &profile.broker {
+            let (tls, host, port) = parse_broker_uri(broker)
+                .wrap_err_with(|| format!("Invalid broker of profile {name}"))?;
+            self.broker.tls = tls;
+            self.broker.host = host;
+            self.broker.port = port;
+        }
+        if let Some(cert_port) = profile.cert_port {
+            self.broker.cert_port = cert_port;
+        }
+        self.tls.trust_store = profile.trust_store.or(self.tls.trust_store.take());
+        self.tls.client_cert = profile.client_cert.or(self.tls.client_cert.take());
+        self.tls.private_key = profile.private_key.or(self.tls.private_key.take());
+        self.active_profile = Some(name.to_owned());
+        Ok(())
+    }
+
+    /// Connector the active profile expects, if it names one
+    pub fn expected_connector_id(&self) -> Option<&str> {
+        let profile = self.profiles.get(self.active_profile.as_ref()?)?;
+        profile.connector_id.as_deref()
+    }
+
+    fn apply_env(&mut self) -> Result<()> {
+        override_from_env("BROKER_HOST", &mut self.broker.host)?;
+        override_from_env("BROKER_PORT", &mut self.broker.port)?;
+        override_from_env("BROKER_CERT_PORT", &mut self.broker.cert_port)?;
+        override_from_env("BROKER_KEEPALIVE_SECS", &mut self.broker.keepalive_secs)?;
+        override_from_env(
+            "BROKER_REQUEST_TIMEOUT_SECS",
+            &mut self.broker.request_timeout_secs,
+        )?;
+        override_from_env("BROKER_DESCRIBE_BATCH_SIZE", &mut self.broker.describe_batch_size)?;
+        override_from_env("BROKER_DESCRIBE_INTERVAL_MS", &mut self.broker.describe_interval_ms)?;
+        override_from_env("BROKER_TLS", &mut self.broker.tls)?;
+        override_from_env("STATE_SHARDS", &mut self.state.shards)?;
+        override_from_env("STATE_MAILBOX_CAPACITY", &mut self.state.mailbox_capacity)?;
+        override_optional_from_env("EXPORTER_LISTEN", &mut self.exporter.listen);
+        override_optional_from_env("API_LISTEN", &mut self.api.listen);
+        override_optional_from_env("API_TOKEN", &mut self.api.token);
+        override_optional_from_env("GRPC_LISTEN", &mut self.grpc.listen);
+        override_from_env("IMPORT_PROTECT_LIVEDATA_SECS", &mut self.import.protect_livedata_secs)?;
+        override_from_env("PUSH_CREATE_MISSING", &mut self.push.create_missing)?;
+        override_from_env("NOTIFICATIONS_DESKTOP", &mut self.notifications.desktop)?;
+        override_from_env("NOTIFICATIONS_BELL", &mut self.notifications.bell)?;
+        override_optional_from_env("UI_SELECT", &mut self.ui.select);
+        override_from_env("UI_MAILBOX_CAPACITY", &mut self.ui.mailbox_capacity)?;
+        override_from_env("UI_OVERLOAD_THRESHOLD", &mut self.ui.overload_threshold)?;
+        override_from_env("UI_LOCALE", &mut self.ui.locale)?;
+        override_from_env("UI_KEYMAP", &mut self.ui.keymap)?;
+        override_optional_from_env("LOG_LEVEL", &mut self.log.level);
+        override_from_env("LOG_FILE", &mut self.log.file)?;
+        override_optional_from_env("LOG_JOURNAL", &mut self.log.journal);
+        override_from_env("LOG_PRETTY_PAYLOADS", &mut self.log.pretty_payloads)?;
+        override_from_env("LOG_MAX_VALUE_LEN", &mut self.log.max_value_len)?;
+        override_optional_from_env("TRUST_STORE", &mut self.tls.trust_store);
+        override_optional_from_env("CLIENT_CERT", &mut self.tls.client_cert);
+        override_optional_from_env("PRIVATE_KEY", &mut self.tls.private_key);
+        override_optional_from_env("CLIENT_ID_PREFIX", &mut self.client_id_prefix);
+        if let Ok(alerts_file) = std::env::var(format!("{ENV_PREFIX}ALERTS_FILE")) {
+            self.alerts_file = Some(PathBuf::from(alerts_file));
+        }
+        if let Ok(mutes_file) = std::env::var(format!("{ENV_PREFIX}MUTES_FILE")) {
+            self.mutes_file = Some(PathBuf::from(mutes_file));
+        }
+        if let Ok(layout_file) = std::env::var(format!("{ENV_PREFIX}LAYOUT_FILE")) {
+            self.layout_file = Some(PathBuf::from(layout_file));
+        }
+        if let Ok(keymap_file) = std::env::var(format!("{ENV_PREFIX}KEYMAP_FILE")) {
+            self.keymap_file = Some(PathBuf::from(keymap_file));
+        }
+        if let Ok(history_file) = std::env::var(format!("{ENV_PREFIX}STATE_HISTORY_FILE")) {
+            self.state.history_file = Some(PathBuf::from(history_file));
+        }
+        Ok(())
+    }
+
+    /// Credentials of the main connector, unset values fall back to the defaults
+    pub fn credentials(&self) -> MqttCredentials {
+        let defaults = MqttCredentials::default();
+        MqttCredentials {
+            trust_store: self.tls.trust_store.clone().unwrap_or(defaults.trust_store),
+            client_cert: self.tls.client_cert.clone().unwrap_or(defaults.client_cert),
+            private_key: self.tls.private_key.clone().unwrap_or(defaults.private_key),
+            client_id_prefix: self.client_id_prefix.clone().unwrap_or(defaults.client_id_prefix),
+        }
+    }
+}
+
+/// Reads a YAML list of alert rules, failing on the first invalid one
+/// Prometheus-style entry of an alerts file, told from an [`AlertRule`] by its `expr`
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ExpressionRule {
+    /// Only shown in errors
+    #[serde(default)]
+    alert: Option<String>,
+
+    /// See [`AlertRule::with_expression`]
+    expr: String,
+
+    /// Such as `90s`, `5m` or `1h30m`
+    #[serde(default, rename = "for")]
+    for_duration: Option<String>,
+
+    /// `severity: critical` raises critical alerts, other labels are ignored
+    #[serde(default)]
+    labels: BTreeMap<String, String>,
+
+    #[serde(default, rename = "annotations")]
+    _annotations: serde::de::IgnoredAny,
+}
+
+impl ExpressionRule {
+    fn into_rule(self) -> Result<AlertRule> {
+        let mut rule = AlertRule::with_expression(&self.expr)?;
+        rule.for_secs = self.for_duration.as_deref().map(duration_secs).transpose()?;
+        rule.critical = self.labels.get("severity").is_some_and(|severity| severity == "critical");
+        Ok(rule)
+    }
+}
+
+/// Rules of a Prometheus rules file, the groups are only a way to organize them
+#[derive(Deserialize)]
+struct RuleGroups {
+    groups: Vec<RuleGroup>,
+}
+
+#[derive(Deserialize)]
+struct RuleGroup {
+    rules: Vec<serde_yaml::Value>,
+}
+
+/// Reads a list of rules, where each one is either an [`AlertRule`] or a Prometheus-style
+/// `{alert, expr, for, labels}` entry, the latter also grouped as in Prometheus rules files
+pub fn read_alert_rules(path: &Path) -> Result<Vec<AlertRule>> {
+    let contents = std::fs::read_to_string(path)
+        .wrap_err_with(|| format!("Failed to read {}", path.display()))?;
+    let parse_error = || format!("Failed to parse {}", path.display());
+    let document: serde_yaml::Value = serde_yaml::from_str(&contents).wrap_err_with(parse_error)?;
+    let entries: Vec<serde_yaml::Value> = if document.get("groups").is_some() {
+        let groups: RuleGroups = serde_yaml::from_value(document).wrap_err_with(parse_error)?;
+        groups.groups.into_iter().flat_map(|group| group.rules).collect()
+    } else {
+        serde_yaml::from_value(document).wrap_err_with(parse_error)?
+    };
+
+    let mut rules = Vec::with_capacity(entries.len());
+    for (index, entry) in entries.into_iter().enumerate() {
+        let mut name = None;
+        let rule = if entry.get("expr").is_some() {
+            serde_yaml::from_value(entry)
+                .map_err(eyre::Report::from)
+                .and_then(|expression: ExpressionRule| {
+                    name = expression.alert.clone();
+                    expression.into_rule()
+                })
+        } else {
+            serde_yaml::from_value(entry).map_err(eyre::Report::from)
+        };
+        let rule = rule.and_then(|rule: AlertRule| rule.validate().map(|_| rule));
+        let name = name.map(|name| format!(" ({name})")).unwrap_or_default();
+        rules.push(rule.wrap_err_with(|| {
+            format!("Invalid alert rule #{}{name} in {}", index + 1, path.display())
+        })?);
+    }
+    Ok(rules)
+}
+
+pub fn alert_rules_to_yaml(rules: &[AlertRule]) -> Result<String> {
+    serde_yaml::to_string(rules).wrap_err("Failed to serialize the alert rules")
+}
+
+/// Replaces the rules in `path`, read back by [`read_alert_rules`]
+pub fn write_alert_rules(path: &Path, rules: &[AlertRule]) -> Result<()> {
+    std::fs::write(path, alert_rules_to_yaml(rules)?)
+        .wrap_err_with(|| format!("Failed to write {}", path.display()))
+}
+
+/// Replaces `[log.levels]` of the config at `path`, keeping the rest of the file as written.
+/// The file is created if it doesn't exist yet.
+pub fn write_log_levels(path: &Path, levels: &BTreeMap<String, String>) -> Result<()> {
+    let contents = if path.exists() {
+        std::fs::read_to_string(path).wrap_err_with(|| format!("Failed to read {}", path.display()))?
+    } else {
+        String::new()
+    };
+    let mut document: toml_edit::DocumentMut = contents
+        .parse()
+        .wrap_err_with(|| format!("Failed to parse {}", path.display()))?;
+    let log = document
+        .entry("log")
+        .or_insert_with(|| {
+            // Only [log.levels] is written to a config without [log]
+            let mut log = toml_edit::Table::new();
+            log.set_implicit(true);
+            toml_edit::Item::Table(log)
+        })
+        .as_table_mut()
+        .ok_or_else(|| eyre!("log of {} isn't a table", path.display()))?;
+    let mut table = toml_edit::Table::new();
+    for (name, level) in levels {
+        table.insert(name, toml_edit::value(level));
+    }
+    log.insert("levels", toml_edit::Item::Table(table));
+    std::fs::write(path, document.to_string()).wrap_err_with(|| format!("Failed to write {}", path.display()))
+}
+
+/// Sensors muted under `profile`, none if the file doesn't exist yet
+pub fn read_muted_sensors(path: &Path, profile: Option<&str>) -> Result<BTreeSet<SensorId>> {
+    let mut mutes = read_mutes(path)?;
+    Ok(mutes
+        .remove(profile.unwrap_or(NO_PROFILE))
+        .unwrap_or_default())
+}
+
+/// Replaces the sensors muted under `profile`, the other profiles keep theirs
+pub fn write_muted_sensors(
+    path: &Path,
+    profile: Option<&str>,
+    muted: &BTreeSet<SensorId>,
+) -> Result<()> {
+    let mut mutes = read_mutes(path)?;
+    let profile = profile.unwrap_or(NO_PROFILE).to_owned();
+    if muted.is_empty() {
+        mutes.remove(&profile);
+    } else {
+        mutes.insert(profile, muted.clone());
+    }
+    let yaml = serde_yaml::to_string(&mutes).wrap_err("Failed to serialize the muted sensors")?;
+    std::fs::write(path, yaml).wrap_err_with(|| format!("Failed to write {}", path.display()))
+}
+
+/// Keys by action name, see [`keymap::keymap`]
+pub fn read_keymap(path: &Path) -> Result<BTreeMap<String, String>> {
+    let contents = std::fs::read_to_string(path)
+        .wrap_err_with(|| format!("Failed to read {}", path.display()))?;
+    serde_yaml::from_str(&contents).wrap_err_with(|| format!("Failed to parse {}", path.display()))
+}
+
+pub fn keymap_to_yaml(bindings: &[KeyBinding]) -> Result<String> {
+    serde_yaml::to_string(&keymap::keymap_keys(bindings)).wrap_err("Failed to serialize the keymap")
+}
+
+fn read_mutes(path: &Path) -> Result<BTreeMap<String, BTreeSet<SensorId>>> {
+    read_per_profile(path)
+}
+
+/// Region sizes resized under `profile`, the defaults if they never were
+pub fn read_region_sizes(path: &Path, profile: Option<&str>) -> Result<RegionSizes> {
+    let mut layouts: BTreeMap<String, RegionSizes> = read_per_profile(path)?;
+    Ok(layouts
+        .remove(profile.unwrap_or(NO_PROFILE))
+        .map(RegionSizes::clamped)
+        .unwrap_or_default())
+}
+
+/// Replaces the region sizes of `profile`, the other profiles keep theirs
+pub fn write_region_sizes(path: &Path, profile: Option<&str>, sizes: &RegionSizes) -> Result<()> {
+    let mut layouts: BTreeMap<String, RegionSizes> = read_per_profile(path)?;
+    layouts.insert(profile.unwrap_or(NO_PROFILE).to_owned(), *sizes);
+    let yaml = serde_yaml::to_string(&layouts).wrap_err("Failed to serialize the region sizes")?;
+    std::fs::write(path, yaml).wrap_err_with(|| format!("Failed to write {}", path.display()))
+}
+
+/// YAML map by profile, empty if the file doesn't exist yet
+fn read_per_profile<T: DeserializeOwned>(path: &Path) -> Result<BTreeMap<String, T>> {
+    if !path.exists() {
+        return Ok(BTreeMap::default());
+    }
+    let contents = std::fs::read_to_string(path)
+        .wrap_err_with(|| format!("Failed to read {}", path.display()))?;
+    serde_yaml::from_str(&contents).wrap_err_with(|| format!("Failed to parse {}", path.display()))
+}
+
+/// `mqtts://HOST:PORT` into whether TLS is used, the host and the port
+fn parse_broker_uri(uri: &str) -> Result<(bool, String, u16)> {
+    let (scheme, address) = uri
+        .split_once("://")
+        .ok_or_else(|| eyre!("{uri} isn't SCHEME://HOST:PORT"))?;
+    let tls = match scheme {
+        "mqtts" | "ssl" => true,
+        "mqtt" | "tcp" => false,
+        _ => return Err(eyre!("Unknown scheme {scheme}, expected mqtts or mqtt")),
+    };
+    let (host, port) = address
+        .rsplit_once(':')
+        .ok_or_else(|| eyre!("{uri} has no port"))?;
+    let port = port.parse().wrap_err_with(|| format!("Invalid port {port}"))?;
+    Ok((tls, host.to_owned(), port))
+}
+
+fn override_from_env<T: FromStr>(name: &str, value: &mut T) -> Result<()>
+where
+    T::Err: std::error::Error + Send + Sync + 'static,
+{
+    let name = format!("{ENV_PREFIX}{name}");
+    if let Ok(env_value) = std::env::var(&name) {
+        *value = env_value
+            .parse()
+            .wrap_err_with(|| format!("Invalid {name}: {env_value}"))?;
+    }
+    Ok(())
+}
+
+fn override_optional_from_env(name: &str, value: &mut Option<String>) {
+    if let Ok(env_value) = std::env::var(format!("{ENV_PREFIX}{name}")) {
+        *value = Some(env_value);
+    }
+}
@@ -0,0 +1,87 @@
+use std::collections::HashMap;
+use std::fmt::Formatter;
+use std::future::Future;
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::{Mutex, OnceLock};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use crate::utils::lock_recovering;
+
+/// Identifies a single user-initiated operation across the UI, client, MQTT and state layers.
+/// The id of the operation being processed is prepended to every log record.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub struct CorrelationId(u32);
+
+impl CorrelationId {
+    pub fn new() -> Self {
+        static NEXT_ID: OnceLock<AtomicU32> = OnceLock::new();
+        let next_id = NEXT_ID.get_or_init(|| {
+            // Different runs appending to the same log should not share ids
+            let seed = SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .unwrap_or_default()
+                .subsec_nanos();
+            AtomicU32::new(seed)
+        });
+        Self(next_id.fetch_add(1, Ordering::SeqCst))
+    }
+}
+
+impl Default for CorrelationId {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl std::fmt::Display for CorrelationId {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:08x}", self.0)
+    }
+}
+
+tokio::task_local! {
+    static CURRENT: CorrelationId;
+}
+
+/// The id of the operation being processed by the current task, if any
+pub fn current() -> Option<CorrelationId> {
+    CURRENT.try_with(|id| *id).ok()
+}
+
+pub async fn scope<F: Future>(id: CorrelationId, future: F) -> F::Output {
+    CURRENT.scope(id, future).await
+}
+
+pub fn sync_scope<R>(id: Option<CorrelationId>, f: impl FnOnce() -> R) -> R {
+    match id {
+        Some(id) => CURRENT.sync_scope(id, f),
+        None => f(),
+    }
+}
+
+// Responses arrive through the listener connection, so the only thing tying them
+// to the operation is the topic they're expected on.
+const EXPECTED_RESPONSE_TTL: Duration = Duration::from_secs(60);
+
+fn expected_responses() -> &'static Mutex<HashMap<String, (CorrelationId, Instant)>> {
+    static EXPECTED_RESPONSES: OnceLock<Mutex<HashMap<String, (CorrelationId, Instant)>>> =
+        OnceLock::new();
+    EXPECTED_RESPONSES.get_or_init(Mutex::default)
+}
+
+/// Remembers that a reply of operation `id` is expected on any of the `topics`
+pub fn expect_response(id: CorrelationId, topics: &[&str]) {
+    let mut expected = lock_recovering(expected_responses());
+    let now = Instant::now();
+    expected.retain(|_, (_, since)| now.duration_since(*since) < EXPECTED_RESPONSE_TTL);
+    for topic in topics {
+        expected.insert(topic.to_string(), (id, now));
+    }
+}
+
+/// The operation the message received on `topic` is a reply to
+pub fn take_response(topic: &str) -> Option<CorrelationId> {
+    lock_recovering(expected_responses())
+        .remove(topic)
+        .map(|(id, _)| id)
+}
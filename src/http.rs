@@ -0,0 +1,137 @@
+//! Bare HTTP/1.1 client for the plain `http://` endpoints the collectors poll and the webhooks
+//! are posted to. One request per connection, responses are read up to a size limit.
+
+use eyre::{eyre, Result, WrapErr};
+
+use std::time::Duration;
+
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpStream;
+use tokio::time::timeout;
+
+use url::Url;
+
+/// Connecting, sending the request and reading the whole response, each
+const TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Head and body together, a response any bigger is dropped
+pub const RESPONSE_SIZE_LIMIT: usize = 1024 * 1024;
+
+/// `http://` URL with a host, the port defaulting to 80
+pub fn parse_url(url: &str) -> Result<Url> {
+    let parsed = Url::parse(url).wrap_err_with(|| format!("Malformed URL {url}"))?;
+    if parsed.scheme() != "http" {
+        return Err(eyre!("{url} isn't a http:// URL"));
+    }
+    if parsed.host_str().is_none_or(str::is_empty) {
+        return Err(eyre!("{url} has no host"));
+    }
+    Ok(parsed)
+}
+
+pub async fn get(url: &Url, headers: &[(&str, &str)]) -> Result<Vec<u8>> {
+    request("GET", url, headers, None).await
+}
+
+pub async fn post_json(url: &Url, body: &[u8]) -> Result<Vec<u8>> {
+    request("POST", url, &[], Some(("application/json", body))).await
+}
+
+/// Body of a 2xx response, any other status is an error
+async fn request(
+    method: &str,
+    url: &Url,
+    headers: &[(&str, &str)],
+    body: Option<(&str, &[u8])>,
+) -> Result<Vec<u8>> {
+    let host = url.host_str().ok_or_else(|| eyre!("{url} has no host"))?;
+    let port = url.port_or_known_default().unwrap_or(80);
+    let target = match url.query() {
+        Some(query) => format!("{}?{query}", url.path()),
+        None => url.path().to_owned(),
+    };
+
+    let mut head = format!("{method} {target} HTTP/1.1\r\nHost: {host}");
+    if let Some(port) = url.port() {
+        head += &format!(":{port}");
+    }
+    head += "\r\nAccept: application/json\r\n";
+    for (name, value) in headers {
+        head += &format!("{name}: {value}\r\n");
+    }
+    if let Some((content_type, body)) = body {
+        head += &format!("Content-Type: {content_type}\r\nContent-Length: {}\r\n", body.len());
+    }
+    head += "Connection: close\r\n\r\n";
+
+    let mut stream = timeout(TIMEOUT, TcpStream::connect((host.trim_matches(['[', ']']), port)))
+        .await
+        .wrap_err("Timed out connecting")??;
+    let sent = async {
+        stream.write_all(head.as_bytes()).await?;
+        stream.write_all(body.map_or(&[][..], |(_, body)| body)).await
+    };
+    timeout(TIMEOUT, sent).await.wrap_err("Timed out sending the request")??;
+
+    let mut response = Vec::new();
+    let mut limited = stream.take(RESPONSE_SIZE_LIMIT as u64 + 1);
+    timeout(TIMEOUT, limited.read_to_end(&mut response))
+        .await
+        .wrap_err("Timed out reading the response")??;
+    if response.len() > RESPONSE_SIZE_LIMIT {
+        return Err(eyre!("Response is larger than {RESPONSE_SIZE_LIMIT} bytes"));
+    }
+    parse_response(&response)
+}
+
+fn parse_response(response: &[u8]) -> Result<Vec<u8>> {
+    let head_end = response
+        .windows(4)
+        .position(|window| window == b"\r\n\r\n")
+        .ok_or_else(|| eyre!("Malformed HTTP response"))?;
+    let head = String::from_utf8_lossy(&response[..head_end]);
+    let body = &response[head_end + 4..];
+
+    let mut lines = head.lines();
+    let status_line = lines.next().unwrap_or_default();
+    let status: u16 = status_line
+        .split_whitespace()
+        .nth(1)
+        .and_then(|status| status.parse().ok())
+        .ok_or_else(|| eyre!("Malformed HTTP status line '{status_line}'"))?;
+    if !(200..300).contains(&status) {
+        return Err(eyre!("{status_line}"));
+    }
+
+    let chunked = lines.any(|line| {
+        line.split_once(':').is_some_and(|(name, value)| {
+            name.trim().eq_ignore_ascii_case("transfer-encoding")
+                && value.to_ascii_lowercase().contains("chunked")
+        })
+    });
+    if chunked {
+        dechunk(body)
+    } else {
+        Ok(body.to_vec())
+    }
+}
+
+fn dechunk(mut body: &[u8]) -> Result<Vec<u8>> {
+    let malformed = || eyre!("Malformed chunked response");
+    let mut decoded = Vec::new();
+    loop {
+        let line_end = body
+            .windows(2)
+            .position(|window| window == b"\r\n")
+            .ok_or_else(malformed)?;
+        let size_line = String::from_utf8_lossy(&body[..line_end]);
+        let size_hex = size_line.split(';').next().unwrap_or_default().trim();
+        let size = usize::from_str_radix(size_hex, 16).map_err(|_| malformed())?;
+        body = &body[line_end + 2..];
+        if size == 0 {
+            return Ok(decoded);
+        }
+        decoded.extend(body.get(..size).ok_or_else(malformed)?);
+        body = body.get(size + 2..).unwrap_or_default();
+    }
+}
@@ -0,0 +1,560 @@
+//! Catalog of the user-facing strings of the TUI. Every message carries its text in each
+//! locale as a prop named after the locale, texts missing from a locale fall back to English.
+
+use schemars::JsonSchema;
+
+use serde::Deserialize;
+
+use std::fmt::Display;
+use std::sync::OnceLock;
+
+use strum::{EnumIter, EnumProperty, EnumString, IntoStaticStr};
+
+#[derive(
+    Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, JsonSchema, EnumIter, EnumString, IntoStaticStr,
+)]
+#[serde(rename_all = "lowercase")]
+#[strum(serialize_all = "lowercase")]
+pub enum Locale {
+    #[default]
+    En,
+    Ru,
+}
+
+impl Locale {
+    /// Name of the props holding the texts in the locale
+    pub fn code(&self) -> &'static str {
+        self.into()
+    }
+}
+
+static LOCALE: OnceLock<Locale> = OnceLock::new();
+
+/// Selects the locale of the TUI once on startup, later calls are ignored
+pub fn set_locale(locale: Locale) {
+    let _ = LOCALE.set(locale);
+}
+
+pub fn locale() -> Locale {
+    LOCALE.get().copied().unwrap_or_default()
+}
+
+/// Texts may have `{name}` placeholders, see [`Msg::format`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, EnumIter, EnumProperty)]
+pub enum Msg {
+    // Dialog buttons
+    #[strum(props(en = "OK", ru = "ОК"))]
+    OkButton,
+    #[strum(props(en = "CANCEL", ru = "ОТМЕНА"))]
+    CancelButton,
+
+    // Instructions at the bottom of the dialogs, followed by the keys
+    #[strum(props(en = "Accept", ru = "Принять"))]
+    Accept,
+    #[strum(props(en = "Active Only", ru = "Только активные"))]
+    ActiveOnly,
+    #[strum(props(en = "Add/Remove Card", ru = "Добавить/убрать карточку"))]
+    AddRemoveCard,
+    #[strum(props(en = "All", ru = "Все"))]
+    All,
+    #[strum(props(en = "Browse", ru = "Обзор"))]
+    Browse,
+    #[strum(props(en = "Change", ru = "Изменить"))]
+    Change,
+    #[strum(props(en = "Change Field", ru = "Сменить поле"))]
+    ChangeField,
+    #[strum(props(en = "Change Level", ru = "Сменить уровень"))]
+    ChangeLevel,
+    #[strum(props(en = "Change Value", ru = "Сменить значение"))]
+    ChangeValue,
+    #[strum(props(en = "Close", ru = "Закрыть"))]
+    Close,
+    #[strum(props(en = "Complete", ru = "Дополнить"))]
+    Complete,
+    #[strum(props(en = "Copy", ru = "Копировать"))]
+    CopyValue,
+    #[strum(props(en = "Dismiss", ru = "Скрыть"))]
+    Dismiss,
+    #[strum(props(en = "Find", ru = "Найти"))]
+    Find,
+    #[strum(props(en = "Fold", ru = "Свернуть"))]
+    Fold,
+    #[strum(props(en = "Move", ru = "Перейти"))]
+    Move,
+    #[strum(props(en = "Press", ru = "Нажать"))]
+    Press,
+    #[strum(props(en = "Recent", ru = "Недавние"))]
+    Recent,
+    #[strum(props(en = "Remove", ru = "Удалить"))]
+    Remove,
+    #[strum(props(en = "Retry", ru = "Повторить"))]
+    Retry,
+    #[strum(props(en = "Save", ru = "Сохранить"))]
+    Save,
+    #[strum(props(en = "Scroll", ru = "Прокрутка"))]
+    Scroll,
+    #[strum(props(en = "Search", ru = "Поиск"))]
+    Search,
+    #[strum(props(en = "Search:", ru = "Поиск:"))]
+    SearchPrompt,
+    #[strum(props(en = "Select", ru = "Выбор"))]
+    Select,
+    #[strum(props(en = "Select Button", ru = "Выбор кнопки"))]
+    SelectButton,
+    #[strum(props(en = "Select Card", ru = "Выбор карточки"))]
+    SelectCard,
+
+    // Dialogs
+    #[strum(props(en = "Alert Rule", ru = "Правило оповещения"))]
+    AlertRule,
+    #[strum(props(en = "Alert when", ru = "Условие"))]
+    AlertWhen,
+    #[strum(props(en = "For (secs)", ru = "Дольше (с)"))]
+    AlertFor,
+    #[strum(props(en = "Severity", ru = "Важность"))]
+    AlertSeverity,
+    #[strum(props(en = "Notify", ru = "Уведомить"))]
+    AlertNotify,
+    #[strum(props(en = "No sink picked, routed by severity", ru = "Получатели не выбраны, по важности"))]
+    AlertNoSinks,
+    #[strum(props(en = "No livedata to check the rule against", ru = "Нет данных для проверки правила"))]
+    AlertNoLivedata,
+    #[strum(props(en = "Errors ({count})", ru = "Ошибки ({count})"))]
+    Errors,
+    #[strum(props(en = "No errors", ru = "Ошибок нет"))]
+    NoErrors,
+    #[strum(props(en = "Alert History ({count})", ru = "История оповещений ({count})"))]
+    AlertHistory,
+    #[strum(props(en = "No alerts", ru = "Оповещений нет"))]
+    NoAlerts,
+    #[strum(props(en = "active", ru = "активно"))]
+    AlertActive,
+    #[strum(props(en = "Keys", ru = "Клавиши"))]
+    Keys,
+    #[strum(props(en = "Log Filters", ru = "Фильтры журнала"))]
+    LogFilters,
+    #[strum(props(en = "Records are appended to {file}", ru = "Записи добавляются в {file}"))]
+    LogFileNote,
+    #[strum(props(en = "Inventory", ru = "Инвентарь"))]
+    Inventory,
+    #[strum(props(en = "Exchanges of {sensor}", ru = "Обмен сообщениями: {sensor}"))]
+    SensorExchanges,
+    #[strum(props(en = "Connector Exchanges", ru = "Обмен сообщениями коннектора"))]
+    ConnectorExchanges,
+    #[strum(props(en = "Save Inventory", ru = "Сохранение инвентаря"))]
+    SaveInventory,
+    #[strum(props(en = "Write the inventory JSON to a file?", ru = "Записать инвентарь в JSON-файл?"))]
+    SaveInventoryText,
+    #[strum(props(en = "Import Inventory", ru = "Импорт инвентаря"))]
+    ImportInventory,
+    #[strum(props(
+        en = "Create and update sensors and metrics as in a file?",
+        ru = "Создать и обновить датчики и метрики по файлу?"
+    ))]
+    ImportInventoryText,
+    #[strum(props(en = "Overwrite File", ru = "Перезапись файла"))]
+    OverwriteFile,
+    #[strum(props(en = "{path} already exists. Overwrite it?", ru = "{path} уже существует. Перезаписать?"))]
+    OverwriteFileText,
+    #[strum(props(en = "Duplicate Metric Names", ru = "Повторяющиеся имена метрик"))]
+    DuplicateMetricNames,
+    #[strum(props(
+        en = "{names} already taken. Create suffixed, e.g. '{example} (2)'?",
+        ru = "{names} уже заняты. Создать с суффиксом, например '{example} (2)'?"
+    ))]
+    DuplicateMetricNamesText,
+    #[strum(props(en = "Name:", ru = "Имя:"))]
+    NameLabel,
+    #[strum(props(en = "Create Sensor", ru = "Создание датчика"))]
+    CreateSensor,
+    #[strum(props(en = "Create a new Sensor?", ru = "Создать новый датчик?"))]
+    CreateSensorText,
+    #[strum(props(en = "Silence Alerts", ru = "Тишина оповещений"))]
+    SilenceAlerts,
+    #[strum(props(
+        en = "Silence every alert for how long? They're still checked and recorded. 0 lifts the silence",
+        ru = "На сколько отключить оповещения? Они по-прежнему проверяются и записываются. 0 включает их"
+    ))]
+    SilenceAlertsText,
+    #[strum(props(en = "For:", ru = "На:"))]
+    DurationLabel,
+    #[strum(props(en = "Update Sensor", ru = "Изменение датчика"))]
+    UpdateSensor,
+    #[strum(props(en = "Rename Sensor {sensor}?", ru = "Переименовать датчик {sensor}?"))]
+    UpdateSensorText,
+    #[strum(props(en = "Clone Sensor", ru = "Копирование датчика"))]
+    CloneSensor,
+    #[strum(props(
+        en = "Clone Sensor {sensor} with its {count} metric(s)?",
+        ru = "Скопировать датчик {sensor} вместе с метриками ({count})?"
+    ))]
+    CloneSensorText,
+    #[strum(props(en = "{sensor} copy", ru = "{sensor} копия"))]
+    CloneSensorName,
+    #[strum(props(en = "Delete Sensor", ru = "Удаление датчика"))]
+    DeleteSensor,
+    #[strum(props(en = "Delete Sensor #{sensor}?", ru = "Удалить датчик #{sensor}?"))]
+    DeleteSensorText,
+    #[strum(props(en = "Create Metrics", ru = "Создание метрик"))]
+    CreateMetrics,
+    #[strum(props(
+        en = "Which Metrics to create? Every card is created",
+        ru = "Какие метрики создать? Создаётся каждая карточка"
+    ))]
+    CreateMetricsText,
+    #[strum(props(en = "Update Metric", ru = "Изменение метрики"))]
+    UpdateMetric,
+    #[strum(props(en = "Change current metric", ru = "Изменить текущую метрику"))]
+    UpdateMetricText,
+    #[strum(props(en = "Delete Metric", ru = "Удаление метрики"))]
+    DeleteMetric,
+    #[strum(props(en = "Delete Metric # {sensor} / #{metric}?", ru = "Удалить метрику # {sensor} / #{metric}?"))]
+    DeleteMetricText,
+    #[strum(props(en = "Push Value to Metric", ru = "Отправка значения в метрику"))]
+    PushValue,
+    #[strum(props(en = "Push value to Metric {metric}?", ru = "Отправить значение в метрику {metric}?"))]
+    PushValueText,
+    #[strum(props(en = "Value:", ru = "Значение:"))]
+    ValueLabel,
+    #[strum(props(en = "Start Simulation", ru = "Запуск симуляции"))]
+    StartSimulation,
+    #[strum(props(
+        en = "Simulate {metric} with constant:V, sine:A:PERIOD[:OFFSET], walk:START:STEP or csv:FILE, @SECS",
+        ru = "Симулировать {metric}: constant:V, sine:A:PERIOD[:OFFSET], walk:START:STEP или csv:FILE, @SECS"
+    ))]
+    StartSimulationText,
+    #[strum(props(en = "Generator:", ru = "Генератор:"))]
+    GeneratorLabel,
+    #[strum(props(en = "Stop Simulation", ru = "Остановка симуляции"))]
+    StopSimulation,
+    #[strum(props(
+        en = "Stop pushing {generator}@{secs} to Metric {metric}?",
+        ru = "Прекратить отправку {generator}@{secs} в метрику {metric}?"
+    ))]
+    StopSimulationText,
+
+    // Main view
+    #[strum(props(en = "Current connector has no sensors", ru = "У коннектора нет датчиков"))]
+    NoSensors,
+    #[strum(props(en = "Current sensor has no metrics", ru = "У датчика нет метрик"))]
+    NoSensorMetrics,
+    #[strum(props(en = "No metrics", ru = "Нет метрик"))]
+    NoMetrics,
+    #[strum(props(en = "No sensor matches '{query}'", ru = "Нет датчиков, подходящих под '{query}'"))]
+    NoMatch,
+    #[strum(props(en = "NO DATA", ru = "НЕТ ДАННЫХ"))]
+    NoData,
+    #[strum(props(en = "MUTED", ru = "ЗАГЛУШЕН"))]
+    Muted,
+    #[strum(props(en = "No livedata yet", ru = "Данных пока нет"))]
+    NoLivedataYet,
+    #[strum(props(en = "Most active metrics", ru = "Самые активные метрики"))]
+    MostActiveMetrics,
+    #[strum(props(en = "Press any key", ru = "Нажмите любую клавишу"))]
+    PressAnyKey,
+    #[strum(props(en = "Uptime: {uptime}", ru = "Время работы: {uptime}"))]
+    Uptime,
+    #[strum(props(en = "Sensors: {sensors}, metrics: {metrics}", ru = "Датчиков: {sensors}, метрик: {metrics}"))]
+    SensorsCount,
+    #[strum(props(en = "Livedata values: {count}", ru = "Получено значений: {count}"))]
+    LivedataCount,
+    #[strum(props(en = "Errors: {count}", ru = "Ошибок: {count}"))]
+    ErrorsCount,
+    #[strum(props(en = "Livedata", ru = "Данные"))]
+    Livedata,
+    #[strum(props(en = "Livedata vs {comparison}", ru = "Данные и {comparison}"))]
+    LivedataVs,
+    #[strum(props(en = "↓ {count} newer", ru = "↓ новых: {count}"))]
+    NewerLogRecords,
+    #[strum(props(
+        en = "⚠ The agent didn't answer the last {count} pings",
+        ru = "⚠ Агент не ответил на последние пинги: {count}"
+    ))]
+    PingsUnansweredBanner,
+    #[strum(props(en = "+{count} metric(s) not shown", ru = "+{count} метрик(и) не показано"))]
+    HiddenMetrics,
+    #[strum(props(en = "sorted by {column}", ru = "сортировка: {column}"))]
+    SortedBy,
+    #[strum(props(en = "page {page}/{pages}", ru = "страница {page}/{pages}"))]
+    PageOfPages,
+
+    // Badges of the header and the terminal title
+    #[strum(props(en = "reloading…", ru = "обновление…"))]
+    ReloadingBadge,
+    #[strum(props(en = "offline", ru = "нет связи"))]
+    OfflineBadge,
+    #[strum(props(en = "not responding", ru = "не отвечает"))]
+    NotRespondingBadge,
+    #[strum(props(en = "{count} alert(s)", ru = "оповещений: {count}"))]
+    AlertsBadge,
+    #[strum(props(en = "silenced", ru = "без оповещений"))]
+    SilencedBadge,
+    #[strum(props(en = "silenced until {until}", ru = "без оповещений до {until}"))]
+    SilencedUntilBadge,
+    #[strum(props(en = "maintenance ({count})", ru = "обслуживание ({count})"))]
+    MaintenanceBadge,
+    #[strum(props(en = "{count} error(s)", ru = "ошибок: {count}"))]
+    ErrorsBadge,
+    #[strum(props(en = "ping ✗{count}", ru = "пинг ✗{count}"))]
+    PingFailingBadge,
+    #[strum(props(en = "● {millis} ms", ru = "● {millis} мс"))]
+    PingLatencyBadge,
+
+    // Notifications, the summary followed by the body
+    #[strum(props(en = "Agent responding again", ru = "Агент снова отвечает"))]
+    AgentResponding,
+    #[strum(props(en = "Ping answered in {millis} ms", ru = "Ответ на пинг за {millis} мс"))]
+    PingAnswered,
+    #[strum(props(en = "Agent not responding", ru = "Агент не отвечает"))]
+    AgentNotResponding,
+    #[strum(props(en = "{count} pings in a row went unanswered", ru = "Пингов подряд без ответа: {count}"))]
+    PingsUnanswered,
+    #[strum(props(en = "Connection lost", ru = "Соединение потеряно"))]
+    ConnectionLost,
+    #[strum(props(en = "Reconnecting to the broker", ru = "Переподключение к брокеру"))]
+    Reconnecting,
+    #[strum(props(en = "Connection restored", ru = "Соединение восстановлено"))]
+    ConnectionRestored,
+    #[strum(props(en = "Offline for {duration}", ru = "Связи не было {duration}"))]
+    OfflineFor,
+    #[strum(props(en = "Reconnected after {duration}", ru = "Переподключено спустя {duration}"))]
+    ReconnectedAfter,
+    #[strum(props(en = "Critical alert", ru = "Критическое оповещение"))]
+    CriticalAlert,
+    #[strum(props(en = "Alert", ru = "Оповещение"))]
+    AlertRaised,
+
+    // Footer
+    #[strum(props(en = "<Sensor Action>", ru = "<Действие с датчиком>"))]
+    SensorAction,
+    #[strum(props(en = "<Metric Action>", ru = "<Действие с метрикой>"))]
+    MetricAction,
+    #[strum(props(en = "<Key>", ru = "<Клавиша>"))]
+    Key,
+    #[strum(props(en = "<⇧ + Key>", ru = "<⇧ + Клавиша>"))]
+    ShiftKey,
+    #[strum(props(en = "Next", ru = "Следующий"))]
+    Next,
+    #[strum(props(en = "New", ru = "Создать"))]
+    New,
+    #[strum(props(en = "Edit", ru = "Изменить"))]
+    Edit,
+    #[strum(props(en = "Delete", ru = "Удалить"))]
+    Delete,
+    #[strum(props(en = "Refresh", ru = "Обновить"))]
+    Refresh,
+    #[strum(props(en = "Push Value", ru = "Отправить"))]
+    Push,
+    #[strum(props(en = "Simulate", ru = "Симуляция"))]
+    Simulate,
+    #[strum(props(en = "Alert", ru = "Оповещение"))]
+    Alert,
+    #[strum(props(en = "Inventory JSON", ru = "JSON инвентаря"))]
+    InventoryJson,
+    #[strum(props(en = "Exchanges", ru = "Обмен"))]
+    Exchanges,
+    #[strum(props(en = "Save JSON", ru = "Сохранить JSON"))]
+    SaveJson,
+    #[strum(props(en = "Import JSON", ru = "Импорт JSON"))]
+    ImportJson,
+    #[strum(props(en = "Page", ru = "Страница"))]
+    Page,
+    #[strum(props(en = "Log", ru = "Журнал"))]
+    Log,
+    #[strum(props(en = "Resize", ru = "Размер"))]
+    Resize,
+    #[strum(props(en = "Filter", ru = "Фильтр"))]
+    Filter,
+    #[strum(props(en = "Follow", ru = "Следить"))]
+    Follow,
+    #[strum(props(en = "Compare", ru = "Сравнить"))]
+    Compare,
+    #[strum(props(en = "Axis", ru = "Ось"))]
+    Axis,
+    #[strum(props(en = "Freeze", ru = "Пауза"))]
+    Freeze,
+    #[strum(props(en = "Stats", ru = "Статистика"))]
+    Stats,
+    #[strum(props(en = "Table", ru = "Таблица"))]
+    Table,
+    #[strum(props(en = "Overview", ru = "Обзор"))]
+    Overview,
+    #[strum(props(en = "Mute", ru = "Заглушить"))]
+    Mute,
+    #[strum(props(en = "Help", ru = "Справка"))]
+    Help,
+    #[strum(props(en = "Quit", ru = "Выход"))]
+    Quit,
+
+    // Help screen
+    #[strum(props(en = "Sensors", ru = "Датчики"))]
+    SensorsGroup,
+    #[strum(props(en = "Metrics", ru = "Метрики"))]
+    MetricsGroup,
+    #[strum(props(en = "Dialogs", ru = "Диалоги"))]
+    DialogsGroup,
+    #[strum(props(en = "View", ru = "Вид"))]
+    ViewGroup,
+    #[strum(props(en = "General", ru = "Общее"))]
+    GeneralGroup,
+    #[strum(props(en = "Select the next sensor", ru = "Выбрать следующий датчик"))]
+    NextSensorHelp,
+    #[strum(props(en = "Select the previous sensor", ru = "Выбрать предыдущий датчик"))]
+    PrevSensorHelp,
+    #[strum(props(en = "Create a sensor", ru = "Создать датчик"))]
+    CreateSensorHelp,
+    #[strum(props(en = "Rename the selected sensor", ru = "Переименовать выбранный датчик"))]
+    EditSensorHelp,
+    #[strum(props(
+        en = "Create a copy of the selected sensor with all its metrics",
+        ru = "Скопировать выбранный датчик со всеми метриками"
+    ))]
+    CloneSensorHelp,
+    #[strum(props(en = "Enable or disable the selected sensor", ru = "Включить или выключить выбранный датчик"))]
+    ToggleSensorStateHelp,
+    #[strum(props(en = "Delete the selected sensor", ru = "Удалить выбранный датчик"))]
+    DeleteSensorHelp,
+    #[strum(props(
+        en = "Mute or unmute the livedata of the selected sensor, which is only counted while muted",
+        ru = "Заглушить данные выбранного датчика или вернуть их, заглушённые данные только считаются"
+    ))]
+    ToggleMuteHelp,
+    #[strum(props(
+        en = "Describe all metrics of the selected sensor again",
+        ru = "Заново запросить описания всех метрик выбранного датчика"
+    ))]
+    RefreshSensorHelp,
+    #[strum(props(en = "Select the next metric", ru = "Выбрать следующую метрику"))]
+    NextMetricHelp,
+    #[strum(props(en = "Select the previous metric", ru = "Выбрать предыдущую метрику"))]
+    PrevMetricHelp,
+    #[strum(props(en = "Show the previous page of metrics", ru = "Показать предыдущую страницу метрик"))]
+    PrevMetricPageHelp,
+    #[strum(props(en = "Show the next page of metrics", ru = "Показать следующую страницу метрик"))]
+    NextMetricPageHelp,
+    #[strum(props(en = "Create metrics", ru = "Создать метрики"))]
+    CreateMetricHelp,
+    #[strum(props(en = "Edit the selected metric", ru = "Изменить выбранную метрику"))]
+    EditMetricHelp,
+    #[strum(props(en = "Delete the selected metric", ru = "Удалить выбранную метрику"))]
+    DeleteMetricHelp,
+    #[strum(props(en = "Describe the selected metric again", ru = "Заново запросить описание выбранной метрики"))]
+    RefreshMetricHelp,
+    #[strum(props(en = "Push a value to the selected metric", ru = "Отправить значение в выбранную метрику"))]
+    PushValueHelp,
+    #[strum(props(
+        en = "Start or stop simulating the selected metric",
+        ru = "Запустить или остановить симуляцию выбранной метрики"
+    ))]
+    SimulateHelp,
+    #[strum(props(
+        en = "Edit the alert rule of the selected metric",
+        ru = "Изменить правило оповещения выбранной метрики"
+    ))]
+    EditAlertHelp,
+    #[strum(props(en = "Edit the log filters", ru = "Изменить фильтры журнала"))]
+    EditLogFiltersHelp,
+    #[strum(props(en = "Browse the inventory as JSON", ru = "Просмотреть инвентарь в виде JSON"))]
+    ViewInventoryJsonHelp,
+    #[strum(props(
+        en = "Show the last requests and replies of the selected sensor",
+        ru = "Показать последние запросы и ответы выбранного датчика"
+    ))]
+    ViewExchangesHelp,
+    #[strum(props(en = "Show the errors the agent replied with", ru = "Показать ошибки, которыми ответил агент"))]
+    ViewErrorsHelp,
+    #[strum(props(en = "Show the alerts raised lately and the ones in force", ru = "Показать недавние и действующие оповещения"))]
+    ViewAlertHistoryHelp,
+    #[strum(props(en = "Silence the alerts for a while, e.g. during maintenance", ru = "Отключить оповещения на время, например на время обслуживания"))]
+    SilenceAlertsHelp,
+    #[strum(props(en = "Send the last failed request again", ru = "Повторить последний неудавшийся запрос"))]
+    RetryLastRequestHelp,
+    #[strum(props(en = "Save the inventory to a file", ru = "Сохранить инвентарь в файл"))]
+    SaveInventoryHelp,
+    #[strum(props(
+        en = "Bring the inventory in line with a file and show the changes",
+        ru = "Привести инвентарь в соответствие с файлом и показать изменения"
+    ))]
+    ImportInventoryHelp,
+    #[strum(props(en = "Show or hide the log pane", ru = "Показать или скрыть панель журнала"))]
+    ToggleLogPaneHelp,
+    #[strum(props(en = "Scroll the log pane back", ru = "Прокрутить журнал назад"))]
+    ScrollLogBackHelp,
+    #[strum(props(en = "Scroll the log pane forward", ru = "Прокрутить журнал вперёд"))]
+    ScrollLogForwardHelp,
+    #[strum(props(
+        en = "Shrink the log pane, or the columns of the overview",
+        ru = "Уменьшить панель журнала или столбцы обзора"
+    ))]
+    ShrinkRegionHelp,
+    #[strum(props(
+        en = "Grow the log pane, or the columns of the overview",
+        ru = "Увеличить панель журнала или столбцы обзора"
+    ))]
+    GrowRegionHelp,
+    #[strum(props(en = "Filter the sensors and metrics by name", ru = "Отфильтровать датчики и метрики по имени"))]
+    FilterHelp,
+    #[strum(props(en = "Follow the metrics receiving livedata", ru = "Следовать за метриками, получающими данные"))]
+    ToggleFocusFollowHelp,
+    #[strum(props(
+        en = "Overlay the charts with the last hour or yesterday",
+        ru = "Наложить на графики прошлый час или вчерашний день"
+    ))]
+    CycleComparisonHelp,
+    #[strum(props(
+        en = "Span the charts from the oldest value, the last minute or the last 10 minutes",
+        ru = "Показывать на графиках все значения, последнюю минуту или последние 10 минут"
+    ))]
+    CycleTimeAxisHelp,
+    #[strum(props(
+        en = "Freeze the charts for inspection or let them move again",
+        ru = "Остановить графики для изучения или запустить их снова"
+    ))]
+    ToggleFreezeHelp,
+    #[strum(props(
+        en = "Show or hide the statistics under the charts",
+        ru = "Показать или скрыть статистику под графиками"
+    ))]
+    ToggleStatsHelp,
+    #[strum(props(
+        en = "Show the metrics of the selected sensor as cards or as a table",
+        ru = "Показать метрики выбранного датчика карточками или таблицей"
+    ))]
+    ToggleMetricLayoutHelp,
+    #[strum(props(en = "Sort the metric table by the next column", ru = "Сортировать таблицу метрик по следующему столбцу"))]
+    CycleTableSortHelp,
+    #[strum(props(
+        en = "Show sparklines of all sensors at once, or the selected sensor again",
+        ru = "Показать спарклайны всех датчиков сразу или снова выбранный датчик"
+    ))]
+    ToggleOverviewHelp,
+    #[strum(props(en = "Switch between the dark and the light theme", ru = "Переключить тёмную и светлую тему"))]
+    ToggleThemeHelp,
+    #[strum(props(en = "Show this help", ru = "Показать эту справку"))]
+    HelpHelp,
+}
+
+impl Msg {
+    /// Text in the selected locale
+    pub fn text(self) -> &'static str {
+        self.text_in(locale())
+    }
+
+    pub fn text_in(self, locale: Locale) -> &'static str {
+        self.get_str(locale.code())
+            .or_else(|| self.get_str(Locale::En.code()))
+            .unwrap_or_default()
+    }
+
+    /// Text in the selected locale with the `{name}` placeholders replaced by the values
+    pub fn format(self, args: &[(&str, &dyn Display)]) -> String {
+        self.format_in(locale(), args)
+    }
+
+    pub fn format_in(self, locale: Locale, args: &[(&str, &dyn Display)]) -> String {
+        args.iter()
+            .fold(self.text_in(locale).to_owned(), |text, (name, value)| {
+                text.replace(&format!("{{{name}}}"), &value.to_string())
+            })
+    }
+}
@@ -1,4 +1,14 @@
+pub mod build_info;
+pub mod config;
+pub mod correlation;
+pub mod http;
+pub mod i18n;
+pub mod logging;
+pub mod migrations;
 pub mod model;
+pub mod notifications;
+pub mod schema;
+pub mod telemetry;
 
 pub mod client {
     pub mod mqtt {
@@ -6,42 +16,94 @@ pub mod client {
         pub use mqtt_listener::*;
         mod mqtt_client;
         mod mqtt_listener;
+
+        pub mod subscriptions;
     }
 
     pub mod state {
+        pub use alerts::*;
+        pub use diff::*;
+        pub use maintenance::*;
         pub use scheme::*;
+        pub use router::*;
+        pub use search::*;
         pub use sensors_state::*;
+        pub use store::*;
 
+        mod alerts;
+        mod diff;
+        mod maintenance;
+        mod router;
         mod scheme;
+        mod search;
         mod sensors_state;
+        mod store;
 
         #[path = "state_queries.rs"]
         pub mod queries;
     }
 
+    #[cfg(feature = "api")]
+    pub mod api;
     pub mod client;
     pub mod client_queries;
+    pub mod collector;
+    pub mod describe_scheduler;
+    pub mod exchanges;
+    pub mod exporter;
+    #[cfg(feature = "grpc")]
+    pub mod grpc;
+    pub mod http_poll;
+    pub mod journal;
+    pub mod mirror;
+    #[cfg(feature = "modbus")]
+    pub mod modbus;
+    pub mod mqtt_in;
+    pub mod reconciler;
+    pub mod replay;
+    pub mod rules_watcher;
+    pub mod simulator;
 }
 
 pub mod tui_app {
     pub mod dialog {
+        pub use alert::*;
+        pub use alert_history::*;
         pub use confirmation::*;
+        pub use errors::*;
         pub use generic::*;
+        pub use help::*;
         pub use input::*;
+        pub use json_viewer::*;
+        pub use log_filter::*;
+        pub use message::*;
         pub use metric::*;
+        pub use path::*;
+        pub use tasks::*;
 
         pub mod render;
 
+        mod alert;
+        mod alert_history;
         mod confirmation;
+        mod errors;
         mod generic;
+        mod help;
         mod input;
+        mod json_viewer;
+        mod log_filter;
+        mod message;
         mod metric;
+        mod path;
+        mod tasks;
     }
 
     pub mod ui_state {
+        pub use layout::RegionSizes;
         pub use state::*;
         mod state;
 
+        pub mod frame_model;
         pub mod render;
 
         #[path = "state_queries.rs"]
@@ -51,10 +113,26 @@ pub mod tui_app {
     }
 
     pub mod app;
+    pub mod keymap;
+    pub mod renderer;
     pub mod tui;
 
     mod theme;
     mod utils;
 }
 
+/// Fake broker and agent the client can be tested against without a TeamViewer IoT agent
+#[cfg(feature = "testing")]
+pub mod testing {
+    pub use agent::*;
+    pub use broker::*;
+    pub use fixtures::*;
+    pub use harness::*;
+
+    mod agent;
+    mod broker;
+    mod fixtures;
+    mod harness;
+}
+
 mod utils;
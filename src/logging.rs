@@ -0,0 +1,373 @@
+use chrono::{DateTime, Local};
+
+use eyre::{Result, WrapErr};
+
+use log::{Level, LevelFilter, Log, Metadata, Record};
+
+use serde_json::Value;
+
+use std::collections::{BTreeMap, HashSet, VecDeque};
+use std::fmt::{self, Display, Formatter};
+use std::fs::{File, OpenOptions};
+use std::io::Write;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Mutex, OnceLock};
+
+use strum::{EnumIter, EnumProperty, IntoEnumIterator};
+
+use crate::config::LogConfig;
+use crate::correlation;
+use crate::utils::lock_recovering;
+
+/// Written to in the working directory unless the config says otherwise
+pub const LOG_FILE: &str = "sensor-vision.log";
+
+/// Parts of the app the log filters can be adjusted for independently.
+/// The `target` prop is the module path prefix of the records belonging to the subsystem,
+/// the `name` one the key of its level in `[log.levels]`.
+#[derive(Clone, Copy, Debug, PartialEq, EnumIter, EnumProperty)]
+pub enum Subsystem {
+    #[strum(props(target = "sensor_vision::client::mqtt", name = "mqtt"))]
+    Mqtt,
+
+    #[strum(props(target = "sensor_vision::client::state", name = "state"))]
+    State,
+
+    #[strum(props(target = "sensor_vision::client", name = "client"))]
+    Client,
+
+    #[strum(props(target = "sensor_vision::tui_app", name = "ui"))]
+    Ui,
+
+    #[strum(props(target = "", name = "other"))]
+    Other,
+}
+
+const LEVELS: [LevelFilter; 6] = [
+    LevelFilter::Off,
+    LevelFilter::Error,
+    LevelFilter::Warn,
+    LevelFilter::Info,
+    LevelFilter::Debug,
+    LevelFilter::Trace,
+];
+
+static SUBSYSTEM_LEVELS: [AtomicUsize; 5] = [
+    AtomicUsize::new(LevelFilter::Info as usize),
+    AtomicUsize::new(LevelFilter::Info as usize),
+    AtomicUsize::new(LevelFilter::Info as usize),
+    AtomicUsize::new(LevelFilter::Info as usize),
+    AtomicUsize::new(LevelFilter::Warn as usize),
+];
+
+impl Subsystem {
+    /// The most specific subsystem the target belongs to
+    pub fn of(target: &str) -> Self {
+        Self::iter()
+            .find(|subsystem| target.starts_with(subsystem.target()))
+            .unwrap_or(Subsystem::Other)
+    }
+
+    fn target(&self) -> &'static str {
+        self.get_str("target").unwrap()
+    }
+
+    pub fn name(&self) -> &'static str {
+        self.get_str("name").unwrap()
+    }
+
+    pub fn named(name: &str) -> Option<Self> {
+        Self::iter().find(|subsystem| subsystem.name() == name)
+    }
+
+    pub fn level(&self) -> LevelFilter {
+        LEVELS[SUBSYSTEM_LEVELS[*self as usize].load(Ordering::SeqCst)]
+    }
+
+    pub fn set_level(&self, level: LevelFilter) {
+        SUBSYSTEM_LEVELS[*self as usize].store(level as usize, Ordering::SeqCst);
+    }
+
+    pub fn more_verbose(&self) {
+        self.set_level(LEVELS[(self.level() as usize + 1).min(LEVELS.len() - 1)]);
+    }
+
+    pub fn less_verbose(&self) {
+        self.set_level(LEVELS[(self.level() as usize).saturating_sub(1)]);
+    }
+}
+
+/// The current level of every subsystem by its name, as kept in `[log.levels]`
+pub fn subsystem_levels() -> BTreeMap<String, String> {
+    Subsystem::iter()
+        .map(|subsystem| (subsystem.name().to_owned(), subsystem.level().to_string().to_lowercase()))
+        .collect()
+}
+
+/// Records the log pane can scroll back through
+pub const LOG_BUFFER_LIMIT: usize = 1000;
+
+#[derive(Debug, Clone)]
+pub struct LogEntry {
+    pub time: DateTime<Local>,
+    pub level: Level,
+    pub message: String,
+}
+
+fn buffer() -> &'static Mutex<VecDeque<LogEntry>> {
+    static BUFFER: OnceLock<Mutex<VecDeque<LogEntry>>> = OnceLock::new();
+    BUFFER.get_or_init(|| Mutex::new(VecDeque::with_capacity(LOG_BUFFER_LIMIT)))
+}
+
+/// Up to `count` records in the order they were logged, the `skip` latest ones left out
+pub fn recent_entries(count: usize, skip: usize) -> Vec<LogEntry> {
+    let buffer = lock_recovering(buffer());
+    let end = buffer.len().saturating_sub(skip);
+    buffer
+        .range(end.saturating_sub(count)..end)
+        .cloned()
+        .collect()
+}
+
+pub fn entries_count() -> usize {
+    lock_recovering(buffer()).len()
+}
+
+/// Errors among the buffered records
+pub fn errors_count() -> usize {
+    lock_recovering(buffer())
+        .iter()
+        .filter(|entry| entry.level == Level::Error)
+        .count()
+}
+
+/// Replaces the string values of the redacted fields
+const REDACTED: &str = "<redacted>";
+
+/// How payloads are shaped before they're logged or shown in the diagnostics
+#[derive(Debug, Clone, Default)]
+pub struct PayloadFormat {
+    pretty: bool,
+    max_value_len: usize,
+    redact: HashSet<String>,
+}
+
+impl PayloadFormat {
+    pub fn from_config(config: &LogConfig) -> Self {
+        Self {
+            pretty: config.pretty_payloads,
+            max_value_len: config.max_value_len,
+            redact: config.redact.iter().cloned().collect(),
+        }
+    }
+
+    /// Payloads which aren't JSON are only cut
+    pub fn apply(&self, payload: &str) -> String {
+        let Ok(mut value) = serde_json::from_str::<Value>(payload) else {
+            return self.truncate(payload);
+        };
+        if !self.pretty && self.max_value_len == 0 && self.redact.is_empty() {
+            return payload.to_owned();
+        }
+        self.sanitize(&mut value);
+        let formatted = if self.pretty {
+            serde_json::to_string_pretty(&value)
+        } else {
+            serde_json::to_string(&value)
+        };
+        formatted.unwrap_or_else(|_| payload.to_owned())
+    }
+
+    /// Redacts and cuts the strings in place
+    pub fn sanitize(&self, value: &mut Value) {
+        self.sanitize_value(value, false);
+    }
+
+    fn sanitize_value(&self, value: &mut Value, redacted: bool) {
+        match value {
+            Value::String(string) if redacted => *string = REDACTED.to_owned(),
+            Value::String(string) => *string = self.truncate(string),
+            Value::Array(items) => {
+                for item in items {
+                    self.sanitize_value(item, redacted);
+                }
+            }
+            Value::Object(fields) => {
+                for (name, field) in fields {
+                    self.sanitize_value(field, redacted || self.redact.contains(name));
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn truncate(&self, string: &str) -> String {
+        let length = string.chars().count();
+        if self.max_value_len == 0 || length <= self.max_value_len {
+            return string.to_owned();
+        }
+        let kept: String = string.chars().take(self.max_value_len).collect();
+        format!("{kept}…(+{})", length - self.max_value_len)
+    }
+}
+
+fn payload_format() -> &'static OnceLock<PayloadFormat> {
+    static PAYLOAD_FORMAT: OnceLock<PayloadFormat> = OnceLock::new();
+    &PAYLOAD_FORMAT
+}
+
+/// Payload shaped the way the config says once it's logged, left as is before [`init`]
+pub struct Payload<'a>(&'a str);
+
+pub fn payload(payload: &str) -> Payload<'_> {
+    Payload(payload)
+}
+
+impl Display for Payload<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match payload_format().get() {
+            Some(format) => f.write_str(&format.apply(self.0)),
+            None => f.write_str(self.0),
+        }
+    }
+}
+
+/// Redacts and cuts a payload shown in the diagnostics, e.g. the exchanges of a sensor
+pub fn sanitize_payload(value: &mut Value) {
+    if let Some(format) = payload_format().get() {
+        format.sanitize(value);
+    }
+}
+
+/// Log file renamed to `FILE.1` (and the older ones shifted up to `FILE.<max_files>`)
+/// once it grows past the limit
+struct RotatingFile {
+    path: PathBuf,
+    file: File,
+    size: u64,
+    max_size: u64,
+    max_files: usize,
+}
+
+impl RotatingFile {
+    fn open(config: &LogConfig) -> Result<Self> {
+        let file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&config.file)
+            .wrap_err_with(|| format!("Failed to open {}", config.file.display()))?;
+        Ok(Self {
+            path: config.file.clone(),
+            size: file.metadata()?.len(),
+            file,
+            max_size: config.max_size_kb * 1024,
+            max_files: config.max_files,
+        })
+    }
+
+    fn rotated_path(&self, index: usize) -> PathBuf {
+        let mut path = self.path.clone().into_os_string();
+        path.push(format!(".{index}"));
+        path.into()
+    }
+
+    fn rotate(&mut self) -> std::io::Result<()> {
+        self.file.flush()?;
+        if self.max_files == 0 {
+            self.file = File::create(&self.path)?;
+        } else {
+            for index in (1..self.max_files).rev() {
+                let from = self.rotated_path(index);
+                if from.exists() {
+                    std::fs::rename(from, self.rotated_path(index + 1))?;
+                }
+            }
+            std::fs::rename(&self.path, self.rotated_path(1))?;
+            self.file = OpenOptions::new().create(true).append(true).open(&self.path)?;
+        }
+        self.size = 0;
+        Ok(())
+    }
+
+    fn write_line(&mut self, line: &str) -> std::io::Result<()> {
+        if self.size > 0 && self.size + line.len() as u64 > self.max_size {
+            self.rotate()?;
+        }
+        self.file.write_all(line.as_bytes())?;
+        self.size += line.len() as u64;
+        Ok(())
+    }
+}
+
+/// Appends records to the log file and to the buffer behind the log pane, filtering them
+/// by the subsystem levels which can be changed at runtime.
+struct FileLogger {
+    file: Mutex<RotatingFile>,
+}
+
+impl Log for FileLogger {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        metadata.level() <= Subsystem::of(metadata.target()).level()
+    }
+
+    fn log(&self, record: &Record) {
+        if !self.enabled(record.metadata()) {
+            return;
+        }
+
+        let correlation_id = correlation::current()
+            .map(|id| format!(" op:{id}"))
+            .unwrap_or_default();
+
+        let now = Local::now();
+        let line = format!(
+            "{} {:<5} [{}{}] {}\n",
+            now.format("%Y-%m-%d %H:%M:%S%.3f"),
+            record.level(),
+            record.target(),
+            correlation_id,
+            record.args()
+        );
+
+        let _ = lock_recovering(&self.file).write_line(&line);
+
+        // A line per record in the log pane, even for pretty-printed payloads
+        let mut message = format!("{}{correlation_id}", record.args());
+        if message.contains('\n') {
+            message = message.lines().map(str::trim).collect::<Vec<_>>().join(" ");
+        }
+
+        let mut buffer = lock_recovering(buffer());
+        if buffer.len() == LOG_BUFFER_LIMIT {
+            buffer.pop_front();
+        }
+        buffer.push_back(LogEntry {
+            time: now,
+            level: record.level(),
+            message,
+        });
+    }
+
+    fn flush(&self) {
+        let _ = lock_recovering(&self.file).file.flush();
+    }
+}
+
+pub fn init(config: &LogConfig) -> Result<()> {
+    let _ = payload_format().set(PayloadFormat::from_config(config));
+    if let Some(level) = config.level()? {
+        for subsystem in Subsystem::iter() {
+            subsystem.set_level(level);
+        }
+    }
+    for (subsystem, level) in config.levels()? {
+        subsystem.set_level(level);
+    }
+    log::set_boxed_logger(Box::new(FileLogger {
+        file: Mutex::new(RotatingFile::open(config)?),
+    }))?;
+    // The actual filtering happens per subsystem
+    log::set_max_level(LevelFilter::Trace);
+    Ok(())
+}
@@ -1,21 +1,63 @@
 use actix::Actor;
 
-use clap::{arg, command, ArgAction};
+use clap::{arg, command, ArgAction, Command};
 
-use eyre::{OptionExt, Result};
+use crossterm::style::Stylize;
+
+use eyre::{eyre, OptionExt, Result, WrapErr};
 
 use ratatui::{backend::CrosstermBackend, Terminal};
 
+use regex::Regex;
+
+use sensor_vision::build_info;
 use sensor_vision::client::client::*;
+use sensor_vision::client::client_queries::{
+    DumpSensors, GetConnectorId, ImportSensors, LoadSensors, MetricOrder, PingTest, PushValue,
+    PushValueByName,
+};
+use sensor_vision::client::collector;
+#[cfg(feature = "api")]
+use sensor_vision::client::api::ApiActor;
+use sensor_vision::client::exporter::ExporterActor;
+use sensor_vision::client::http_poll;
+use sensor_vision::client::journal;
+use sensor_vision::client::replay::{self, resolve_targets, CsvRecording, ReplaySpeed};
+use sensor_vision::client::rules_watcher::AlertRulesWatcher;
+use sensor_vision::client::simulator::{Generator, SimulatorActor, StartSimulation};
+use sensor_vision::client::state::{
+    alert_history, diff_alert_rules, search_inventory, summarize_import, ChangeKind, ImportStep, MqttScheme,
+    SensorsStateActor, SetAlertRules, SetMaintenanceWindows,
+};
+use sensor_vision::client::state::queries::{FindMetrics, GetStateSnapshot, ResolveMetric};
+use sensor_vision::client::mirror::MirrorActor;
+use sensor_vision::client::mqtt_in;
+#[cfg(feature = "grpc")]
+use sensor_vision::client::grpc;
+#[cfg(feature = "modbus")]
+use sensor_vision::client::modbus;
 use sensor_vision::client::mqtt::setup_new_certificate;
+use sensor_vision::client::mqtt::subscriptions::{self, Subscription};
 
-use sensor_vision::model::ConnectorId;
+use sensor_vision::config::{
+    alert_rules_to_yaml, keymap_to_yaml, read_alert_rules, write_alert_rules, ApiConfig, Config,
+};
+use sensor_vision::i18n;
+use sensor_vision::logging;
+use sensor_vision::migrations::{ConfigUpgrade, CONFIG_VERSION};
+use sensor_vision::schema::SchemaKind;
+use sensor_vision::telemetry;
+use sensor_vision::model::sensor::split_target;
+use sensor_vision::model::{ConnectorId, Timestamp};
 
 use sensor_vision::tui_app::app::{AppClient, RunLoop};
+use sensor_vision::tui_app::keymap;
 use sensor_vision::tui_app::tui::Tui;
 
 use std::fs;
-use std::io;
+use std::io::{self, IsTerminal, Write};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
 
 use tokio::sync::oneshot;
 use x509_certificate::X509Certificate;
@@ -23,28 +65,376 @@ use x509_certificate::X509Certificate;
 #[actix::main]
 async fn main() -> Result<()> {
     let matches = command!()
+        .long_version(build_info::long_version())
         .arg(arg!(-n --new "Quick setup a new connector").action(ArgAction::SetTrue))
+        .arg(
+            arg!(-c --config <FILE> "Config file, sensor-vision.toml in the working directory by default")
+                .value_parser(clap::value_parser!(PathBuf)),
+        )
+        .arg(arg!(--profile <NAME> "Broker and certificates of the [profiles.NAME] config section"))
+        .arg(arg!(--host <HOST> "Broker host, overrides the config"))
+        .arg(arg!(--port <PORT> "Broker port, overrides the config").value_parser(clap::value_parser!(u16)))
+        .arg(arg!(--"trust-store" <FILE> "CA certificate of the broker, overrides the config"))
+        .arg(
+            arg!(--"mirror-to" <DIR> "Mirror livedata to the connector whose certificate is in DIR")
+                .value_parser(clap::value_parser!(PathBuf)),
+        )
+        .arg(arg!(--exporter <ADDR> "Serve livedata for Prometheus at http://ADDR/metrics, overrides the config"))
+        .arg(arg!(--api <ADDR> "Serve the REST API at http://ADDR, needs the api feature, overrides the config"))
+        .arg(arg!(--grpc <ADDR> "Serve the gRPC service at ADDR, needs the grpc feature, overrides the config"))
+        .arg(
+            arg!(--simulate <SPEC> "Push generated values while the UI runs, SENSOR/METRIC=GENERATOR[@SECS] with GENERATOR one of constant:VALUE, sine:AMPLITUDE:PERIOD_SECS[:OFFSET], walk:START:STEP, csv:FILE")
+                .action(ArgAction::Append),
+        )
+        .arg(arg!(--"log-level" <LEVEL> "Level of every subsystem: off, error, warn, info, debug or trace (MQTT payloads included), overrides the config"))
+        .arg(arg!(--journal <FILE> "Append every MQTT message received to FILE, overrides the config"))
+        .arg(
+            arg!(--replay <FILE> "Feed a journal into the state without a broker and print the inventory it results in")
+                .value_parser(clap::value_parser!(PathBuf)),
+        )
+        .arg(arg!(--select <TARGET> "Select SENSOR[/METRIC] on startup, by id or name, names are fuzzy matched, overrides the config"))
+        .arg(
+            arg!(--"idle-timeout" <SECS> "Show the screensaver after SECS without key presses, 0 disables it")
+                .value_parser(clap::value_parser!(u64))
+                .default_value("300"),
+        )
+        .subcommand(
+            Command::new("push")
+                .about("Push a value to metrics addressed by sensor and metric names")
+                .arg(arg!(<TARGET> "SENSOR/METRIC, both parts may contain * and ? wildcards, a / in the sensor name is escaped as \\/"))
+                .arg(arg!(<VALUE> "Value, parsed according to the metric type"))
+                .arg(arg!(-y --yes "Don't ask before pushing to several metrics").action(ArgAction::SetTrue))
+                .arg(arg!(--"dry-run" "Only list the matching metrics").action(ArgAction::SetTrue)),
+        )
+        .subcommand(
+            Command::new("replay")
+                .about("Replay historical values from a CSV file, one row after another")
+                .arg(arg!(<FILE> "CSV with an optional timestamp column first, then a column per metric").value_parser(clap::value_parser!(PathBuf)))
+                .arg(arg!(--map <MAPPING> "COLUMN=SENSOR/METRIC, only the mapped columns are replayed if given, otherwise columns are named SENSOR/METRIC").action(ArgAction::Append))
+                .arg(arg!(--speed <SPEED> "How fast the recorded time passes: 1x, 10x, ... or max").default_value("1x")),
+        )
+        .subcommand(
+            Command::new("collect")
+                .about("Push the readings of the configured HTTP endpoints, MQTT topics, Modbus registers and serial lines until Ctrl+C"),
+        )
+        .subcommand(
+            Command::new("subscriptions")
+                .about("List the MQTT subscriptions of the client and its collectors along with their message counts")
+                .arg(arg!([REGEX] "Only the subscriptions whose client id or topic filter matches"))
+                .arg(
+                    arg!(--listen <SECS> "How long to count messages before listing")
+                        .value_parser(clap::value_parser!(u64))
+                        .default_value("5"),
+                ),
+        )
+        .subcommand(
+            Command::new("dump")
+                .about("Write the inventory as JSON, sensors ordered by id")
+                .arg(arg!([FILE] "Output file, stdout by default").value_parser(clap::value_parser!(PathBuf)))
+                .arg(
+                    arg!(--order <ORDER> "Order of the metrics of each sensor: creation or name")
+                        .value_parser(clap::value_parser!(MetricOrder))
+                        .default_value("creation"),
+                ),
+        )
+        .subcommand(
+            Command::new("find")
+                .about("Find sensors and metrics by name and print their ids and topics")
+                .arg(arg!(<PATTERN> "Part of the name, ignoring case, or a whole name with * and ? wildcards"))
+                .arg(arg!(--"all-profiles" "Search the connectors of all profiles of the config, not just the active one").action(ArgAction::SetTrue)),
+        )
+        .subcommand(
+            Command::new("import")
+                .about("Create, update and delete sensors and metrics to match an inventory file")
+                .arg(arg!(<FILE> "JSON as written by dump, or a list of sensors, ids may be omitted").value_parser(clap::value_parser!(PathBuf)))
+                .arg(arg!(--prune "Also delete what the file lacks and recreate metrics whose unit or type changed").action(ArgAction::SetTrue))
+                .arg(arg!(--"allow-data-loss" "Prune sensors and metrics which received livedata lately too").action(ArgAction::SetTrue))
+                .arg(arg!(-y --yes "Don't ask before applying the changes").action(ArgAction::SetTrue))
+                .arg(arg!(--"dry-run" "Only preview the changes").action(ArgAction::SetTrue))
+                .arg(
+                    arg!(--profiles <PROFILES> "Import to the connectors of these profiles of the config in turn, comma separated")
+                        .value_delimiter(',')
+                        .conflicts_with("all-profiles"),
+                )
+                .arg(arg!(--"all-profiles" "Import to the connectors of all profiles of the config in turn").action(ArgAction::SetTrue)),
+        )
+        .subcommand(
+            Command::new("alerts")
+                .about("Edit the alert rules in bulk through a YAML spec file, or review the alerts raised")
+                .subcommand_required(true)
+                .subcommand(
+                    Command::new("export")
+                        .about("Write the current alert rules as YAML")
+                        .arg(arg!([FILE] "Output file, stdout by default").value_parser(clap::value_parser!(PathBuf))),
+                )
+                .subcommand(
+                    Command::new("import")
+                        .about("Validate the rules in FILE, preview the changes and save them as the alert rules")
+                        .arg(arg!(<FILE> "YAML list of alert rules").value_parser(clap::value_parser!(PathBuf)))
+                        .arg(arg!(-y --yes "Don't ask before saving the changes").action(ArgAction::SetTrue))
+                        .arg(arg!(--"dry-run" "Only preview the changes").action(ArgAction::SetTrue)),
+                )
+                .subcommand(
+                    Command::new("history")
+                        .about("List the alerts in force and the ones resolved lately, as kept in the history file")
+                        .arg(arg!([PATTERN] "Only the alerts whose message contains PATTERN, ignoring case"))
+                        .arg(
+                            arg!(--hours <HOURS> "How far back the resolved alerts go")
+                                .value_parser(clap::value_parser!(u64))
+                                .default_value("24"),
+                        )
+                        .arg(arg!(--active "Only the alerts in force").action(ArgAction::SetTrue)),
+                ),
+        )
+        .subcommand(
+            Command::new("keymap")
+                .about("Customize the keys of the TUI")
+                .subcommand_required(true)
+                .subcommand(
+                    Command::new("export")
+                        .about("Write the keys in use as YAML, to be edited and saved as the keymap file")
+                        .arg(arg!([FILE] "Output file, stdout by default").value_parser(clap::value_parser!(PathBuf))),
+                ),
+        )
+        .subcommand(
+            Command::new("schema")
+                .about("Write the JSON Schema of the files the crate reads")
+                .arg(
+                    arg!(<TYPE> "sensor, metric, inventory, alerts or config")
+                        .value_parser(clap::value_parser!(SchemaKind)),
+                )
+                .arg(arg!([FILE] "Output file, stdout by default").value_parser(clap::value_parser!(PathBuf))),
+        )
         .get_matches();
+
+    let result = run(matches).await;
+    telemetry::shutdown();
+    result
+}
+
+async fn run(matches: clap::ArgMatches) -> Result<()> {
+    // Schemas don't depend on the config, which may be the very file to check
+    if let Some(("schema", schema_matches)) = matches.subcommand() {
+        let kind = *schema_matches.get_one::<SchemaKind>("TYPE").unwrap();
+        return write_schema(kind, schema_matches.get_one::<PathBuf>("FILE"));
+    }
+
+    let mut config = Config::load(
+        matches.get_one::<PathBuf>("config").map(PathBuf::as_path),
+        matches.get_one::<String>("profile").map(String::as_str),
+    )?;
+    if let Some(host) = matches.get_one::<String>("host") {
+        config.broker.host = host.clone();
+    }
+    if let Some(port) = matches.get_one::<u16>("port") {
+        config.broker.port = *port;
+    }
+    if let Some(trust_store) = matches.get_one::<String>("trust-store") {
+        config.tls.trust_store = Some(trust_store.clone());
+    }
+    if let Some(select) = matches.get_one::<String>("select") {
+        config.ui.select = Some(select.clone());
+    }
+    if let Some(exporter) = matches.get_one::<String>("exporter") {
+        config.exporter.listen = Some(exporter.clone());
+    }
+    if let Some(api) = matches.get_one::<String>("api") {
+        config.api.listen = Some(api.clone());
+    }
+    if let Some(grpc) = matches.get_one::<String>("grpc") {
+        config.grpc.listen = Some(grpc.clone());
+    }
+    if let Some(log_level) = matches.get_one::<String>("log-level") {
+        config.log.level = Some(log_level.clone());
+        config.log.levels.clear();
+    }
+    if let Some(journal) = matches.get_one::<String>("journal") {
+        config.log.journal = Some(journal.clone());
+    }
+    logging::init(&config.log)?;
+    telemetry::init()?;
+    log::info!("{} {}", build_info::NAME, build_info::long_version());
+    if let Some(upgrade) = &config.upgrade {
+        report_config_upgrade(upgrade);
+    }
+
+    // Alert rules are edited, and the alerts raised reviewed, offline
+    if let Some(("alerts", alerts_matches)) = matches.subcommand() {
+        return alerts(&config, alerts_matches);
+    }
+    if let Some(("keymap", keymap_matches)) = matches.subcommand() {
+        return export_keymap(&config, keymap_matches);
+    }
+
+    // Replays need no broker
+    if let Some(file) = matches.get_one::<PathBuf>("replay") {
+        return replay_journal(file).await;
+    }
+    if let Some(file) = &config.log.journal {
+        journal::open(Path::new(file))?;
+    }
+
+    if let Some(("find", find_matches)) = matches.subcommand()
+        && find_matches.get_flag("all-profiles")
+    {
+        let config_path = matches.get_one::<PathBuf>("config").map(PathBuf::as_path);
+        return find_in_profiles(&config, config_path, find_matches.get_one::<String>("PATTERN").unwrap()).await;
+    }
+
+    if let Some(("import", import_matches)) = matches.subcommand()
+        && (import_matches.get_flag("all-profiles") || import_matches.contains_id("profiles"))
+    {
+        let profiles: Vec<String> = match import_matches.get_many::<String>("profiles") {
+            Some(profiles) => profiles.cloned().collect(),
+            None => config.profiles.keys().cloned().collect(),
+        };
+        let config_path = matches.get_one::<PathBuf>("config").map(PathBuf::as_path);
+        let file = import_matches.get_one::<PathBuf>("FILE").unwrap();
+        return import_to_profiles(&config, config_path, &profiles, file, import_matches).await;
+    }
+
     if matches.get_flag("new") {
-        setup_new_certificate().await?;
+        setup_new_certificate(&config.broker, &config.credentials()).await?;
     }
 
-    let cert_contents = fs::read("clientCert.crt")?;
+    let client_actor = connect(&config).await?;
 
-    let cert = X509Certificate::from_pem(&cert_contents).expect("Failed to parse clientCert.crt");
+    if !config.alerts.is_empty() {
+        client_actor.send(SetAlertRules(config.alerts.clone())).await??;
+    }
+    if !config.maintenance.is_empty() {
+        client_actor.send(SetMaintenanceWindows(config.maintenance.clone())).await??;
+    }
+    let _rules_watcher = matches
+        .subcommand()
+        .is_none()
+        .then(|| AlertRulesWatcher::new(client_actor.clone(), config.alerts_file()).start());
 
-    let connector_id = cert.subject_common_name().ok_or_eyre("Certificate has no CN")?;
-    let connector_id: ConnectorId = connector_id.into();
+    // One-shot commands have nothing to export
+    let _exporter_actor = match &config.exporter.listen {
+        Some(address) if matches.subcommand().is_none() => {
+            Some(ExporterActor::bind(client_actor.clone(), address).await?.start())
+        }
+        _ => None,
+    };
+    let _api_actor = match &config.api.listen {
+        Some(_) if matches.subcommand().is_none() => Some(serve_api(&client_actor, &config.api)?),
+        _ => None,
+    };
+    if let Some(address) = &config.grpc.listen
+        && matches.subcommand().is_none()
+    {
+        serve_grpc(&client_actor, address).await?;
+    }
+
+    if let Some(target_dir) = matches.get_one::<PathBuf>("mirror-to") {
+        return mirror(client_actor, &config, target_dir).await;
+    }
+
+    if let Some(("push", push_matches)) = matches.subcommand() {
+        let arg = |name: &str| push_matches.get_one::<String>(name).unwrap().clone();
+        let options = PushOptions {
+            assume_yes: push_matches.get_flag("yes"),
+            dry_run: push_matches.get_flag("dry-run"),
+        };
+        return push(client_actor, &arg("TARGET"), arg("VALUE"), options).await;
+    }
 
-    let client_actor = SensorVisionClient::new(connector_id).await?.start();
+    if let Some(("replay", replay_matches)) = matches.subcommand() {
+        let mapping = replay_matches
+            .get_many::<String>("map")
+            .into_iter()
+            .flatten()
+            .map(|mapping| {
+                mapping
+                    .split_once('=')
+                    .map(|(column, target)| (column.to_owned(), target.to_owned()))
+                    .ok_or_eyre("Mapping must look like COLUMN=SENSOR/METRIC")
+            })
+            .collect::<Result<Vec<_>>>()?;
+        let speed = replay_matches.get_one::<String>("speed").unwrap().parse()?;
+        let file = replay_matches.get_one::<PathBuf>("FILE").unwrap();
+        return replay(client_actor, file, &mapping, speed).await;
+    }
+
+    if let Some(("collect", _)) = matches.subcommand() {
+        if !has_collectors(&config) {
+            return Err(eyre!("No [[http]], [[mqtt_in]], [[modbus]] or [[serial]] sources configured"));
+        }
+        start_collectors(&client_actor, &config).await?;
+        println!("Collecting readings, press Ctrl+C to stop");
+        tokio::signal::ctrl_c().await?;
+        let suppressed = collector::suppressed_samples();
+        println!(
+            "Readings dropped by the deadband: {}, by the minimum interval: {}",
+            suppressed.deadband, suppressed.min_interval
+        );
+        return Ok(());
+    }
 
+    if let Some(("subscriptions", subscriptions_matches)) = matches.subcommand() {
+        let pattern = subscriptions_matches
+            .get_one::<String>("REGEX")
+            .map(String::as_str)
+            .map(Regex::new)
+            .transpose()?;
+        let listen = Duration::from_secs(*subscriptions_matches.get_one::<u64>("listen").unwrap());
+        start_collectors(&client_actor, &config).await?;
+        tokio::time::sleep(listen).await;
+        print_subscriptions(&subscriptions::list(pattern.as_ref()));
+        return Ok(());
+    }
+
+    let simulator_actor = SimulatorActor::new(client_actor.clone()).start();
+    if let Some(specs) = matches.get_many::<String>("simulate") {
+        start_simulations(&client_actor, &simulator_actor, specs).await?;
+    }
+
+    if let Some(("dump", dump_matches)) = matches.subcommand() {
+        let metric_order = *dump_matches.get_one::<MetricOrder>("order").unwrap();
+        return dump(client_actor, dump_matches.get_one::<PathBuf>("FILE"), metric_order).await;
+    }
+
+    if let Some(("find", find_matches)) = matches.subcommand() {
+        let pattern = find_matches.get_one::<String>("PATTERN").unwrap();
+        if find(client_actor, pattern).await? == 0 {
+            return Err(eyre!("Nothing matches '{pattern}'"));
+        }
+        return Ok(());
+    }
+
+    if let Some(("import", import_matches)) = matches.subcommand() {
+        let file = import_matches.get_one::<PathBuf>("FILE").unwrap();
+        return import(client_actor, file, ImportOptions::new(&config, import_matches)).await;
+    }
+
+    start_collectors(&client_actor, &config).await?;
+
+    i18n::set_locale(config.ui.locale);
+    keymap::set_keymap(config.keymap()?);
     let backend = CrosstermBackend::new(io::stdout());
     let terminal = Terminal::new(backend)?;
 
     let mut tui = Tui::new(terminal);
     tui.init()?;
 
-    let app_actor = AppClient::new(client_actor).start();
+    let idle_timeout = Some(*matches.get_one::<u64>("idle-timeout").unwrap())
+        .filter(|secs| *secs != 0)
+        .map(Duration::from_secs);
+    let app_actor = AppClient::new(client_actor)
+        .with_idle_timeout(idle_timeout)
+        .with_ping_interval(config.ui.ping_interval())
+        .with_simulator(simulator_actor)
+        .with_notifications(&config.notifications)
+        .with_selection(config.ui.select.clone())
+        .with_profile(config.active_profile.clone())
+        .with_mutes_file(config.mutes_file())
+        .with_alerts_file(config.alerts_file())
+        .with_layout_file(config.layout_file())
+        .with_config_file(config.config_file())
+        .with_mailbox_capacity(config.ui.mailbox_capacity)
+        .with_overload_threshold(config.ui.overload_threshold)
+        .start();
 
     let (finished_sender, rx) = oneshot::channel();
 
@@ -52,3 +442,663 @@ async fn main() -> Result<()> {
 
     Ok(rx.await??)
 }
+
+/// Client of the connector of the active profile, which has to be the one the profile expects
+async fn connect(config: &Config) -> Result<actix::Addr<SensorVisionClient>> {
+    let credentials = config.credentials();
+    let connector_id = read_connector_id(&credentials.client_cert)?;
+    if let Some(expected) = config.expected_connector_id()
+        && connector_id.to_string() != expected.replace('-', "").to_ascii_lowercase()
+    {
+        return Err(eyre!(
+            "{} belongs to connector {connector_id}, profile {} expects {expected}",
+            credentials.client_cert,
+            config.active_profile.as_deref().unwrap_or_default()
+        ));
+    }
+
+    Ok(SensorVisionClient::with_credentials(connector_id, &config.broker, &credentials, &config.state)
+        .await?
+        .with_push(config.push.clone())
+        .start())
+}
+
+fn read_connector_id(cert_path: &str) -> Result<ConnectorId> {
+    let cert_contents = fs::read(cert_path)?;
+
+    let cert = X509Certificate::from_pem(&cert_contents)
+        .unwrap_or_else(|_| panic!("Failed to parse {cert_path}"));
+
+    let connector_id = cert.subject_common_name().ok_or_eyre("Certificate has no CN")?;
+    Ok(connector_id.into())
+}
+
+async fn mirror(
+    source: actix::Addr<SensorVisionClient>,
+    config: &Config,
+    target_dir: &Path,
+) -> Result<()> {
+    let target_credentials = config.credentials().in_dir(target_dir, "sv_mirror");
+    let target_connector_id = read_connector_id(&target_credentials.client_cert)?;
+
+    let target =
+        SensorVisionClient::with_credentials(
+            target_connector_id,
+            &config.broker,
+            &target_credentials,
+            &config.state,
+        )
+        .await?
+        .start();
+
+    // Both inventories are needed to match sensors and metrics by their names
+    target.send(LoadSensors).await??;
+    source.send(LoadSensors).await??;
+
+    let _mirror_actor = MirrorActor::new(source, target).start();
+
+    println!("Mirroring livedata to connector {target_connector_id}, press Ctrl+C to stop");
+    tokio::signal::ctrl_c().await?;
+
+    Ok(())
+}
+
+async fn start_simulations(
+    client: &actix::Addr<SensorVisionClient>,
+    simulator: &actix::Addr<SimulatorActor>,
+    specs: impl Iterator<Item = &String>,
+) -> Result<()> {
+    load_inventory(client).await?;
+
+    for spec in specs {
+        let (target, generator) = spec
+            .split_once('=')
+            .ok_or_eyre("Simulation must look like SENSOR/METRIC=GENERATOR[@SECS]")?;
+        let (sensor_name, metric_name) =
+            split_target(target).ok_or_eyre("Target must look like SENSOR/METRIC")?;
+        let (generator, interval) = Generator::parse_with_interval(generator)?;
+        let (sensor_id, metric) = client
+            .send(ResolveMetric {
+                sensor_name,
+                metric_name,
+            })
+            .await??;
+        simulator
+            .send(StartSimulation {
+                sensor_id,
+                metric,
+                generator,
+                interval,
+            })
+            .await?;
+    }
+    Ok(())
+}
+
+/// Starts the configured collectors, if any, once the inventory is loaded
+async fn start_collectors(client: &actix::Addr<SensorVisionClient>, config: &Config) -> Result<()> {
+    if !has_collectors(config) {
+        return Ok(());
+    }
+    load_inventory(client).await?;
+    http_poll::start_collectors(client, &config.http, &config.throttle, &config.conversion).await?;
+    mqtt_in::start_bridges(client, &config.mqtt_in, &config.throttle, &config.conversion).await?;
+    start_modbus_collectors(client, config).await
+}
+
+fn has_collectors(config: &Config) -> bool {
+    !config.modbus.is_empty()
+        || !config.serial.is_empty()
+        || !config.http.is_empty()
+        || !config.mqtt_in.is_empty()
+}
+
+#[cfg(feature = "modbus")]
+async fn start_modbus_collectors(
+    client: &actix::Addr<SensorVisionClient>,
+    config: &Config,
+) -> Result<()> {
+    modbus::start_collectors(
+        client,
+        &config.modbus,
+        &config.serial,
+        &config.throttle,
+        &config.conversion,
+    )
+    .await
+}
+
+#[cfg(not(feature = "modbus"))]
+async fn start_modbus_collectors(_: &actix::Addr<SensorVisionClient>, config: &Config) -> Result<()> {
+    if config.modbus.is_empty() && config.serial.is_empty() {
+        return Ok(());
+    }
+    Err(eyre!("Modbus and serial sources need sensor-vision built with the modbus feature"))
+}
+
+#[cfg(feature = "api")]
+fn serve_api(client: &actix::Addr<SensorVisionClient>, config: &ApiConfig) -> Result<actix::Addr<ApiActor>> {
+    Ok(ApiActor::bind(client.clone(), config)?.start())
+}
+
+#[cfg(not(feature = "api"))]
+fn serve_api(_: &actix::Addr<SensorVisionClient>, _: &ApiConfig) -> Result<()> {
+    Err(eyre!("The REST API needs sensor-vision built with the api feature"))
+}
+
+#[cfg(feature = "grpc")]
+async fn serve_grpc(client: &actix::Addr<SensorVisionClient>, address: &str) -> Result<()> {
+    grpc::serve(client.clone(), address).await
+}
+
+#[cfg(not(feature = "grpc"))]
+async fn serve_grpc(_: &actix::Addr<SensorVisionClient>, _: &str) -> Result<()> {
+    Err(eyre!("The gRPC service needs sensor-vision built with the grpc feature"))
+}
+
+struct PushOptions {
+    assume_yes: bool,
+    dry_run: bool,
+}
+
+async fn push(
+    client: actix::Addr<SensorVisionClient>,
+    target: &str,
+    value: String,
+    options: PushOptions,
+) -> Result<()> {
+    let (sensor_pattern, metric_pattern) =
+        split_target(target).ok_or_eyre("Target must look like SENSOR/METRIC")?;
+    let (sensor_pattern, metric_pattern) = (sensor_pattern.as_str(), metric_pattern.as_str());
+
+    load_inventory(&client).await?;
+
+    let is_glob = |pattern: &str| pattern.contains(['*', '?']);
+    if !is_glob(sensor_pattern) && !is_glob(metric_pattern) && !options.dry_run {
+        client
+            .send(PushValueByName {
+                sensor_name: sensor_pattern.to_owned(),
+                metric_name: metric_pattern.to_owned(),
+                value,
+                timestamp: None,
+            })
+            .await??;
+    } else {
+        let matched = client
+            .send(FindMetrics {
+                sensor_pattern: sensor_pattern.to_owned(),
+                metric_pattern: metric_pattern.to_owned(),
+            })
+            .await??;
+        if matched.is_empty() {
+            return Err(eyre!("No metrics match '{target}'"));
+        }
+
+        // Parse up front so that a bad value doesn't leave the batch half-pushed
+        let mut pushes = Vec::with_capacity(matched.len());
+        for matched_metric in &matched {
+            println!("  {}/{}", matched_metric.sensor_name, matched_metric.metric.name());
+            pushes.push(PushValue {
+                sensor_id: matched_metric.sensor_id,
+                metric_id: *matched_metric.metric.metric_id(),
+                value: matched_metric.metric.parse_value(&value).wrap_err_with(|| {
+                    format!("Failed to parse \"{value}\" for {}", matched_metric.metric.name())
+                })?,
+                timestamp: None,
+            });
+        }
+
+        if options.dry_run {
+            println!("{} metric(s) match", matched.len());
+            return Ok(());
+        }
+        if pushes.len() > 1
+            && !options.assume_yes
+            && !confirm(&format!("Push {value} to {} metrics?", pushes.len()))?
+        {
+            return Ok(());
+        }
+
+        for push_value in pushes {
+            client.send(push_value).await??;
+        }
+    }
+
+    // Requests share the connection with the pushes, so the reply means the pushes went through too
+    client.send(PingTest).await??;
+    Ok(())
+}
+
+async fn replay(
+    client: actix::Addr<SensorVisionClient>,
+    file: &Path,
+    mapping: &[(String, String)],
+    speed: ReplaySpeed,
+) -> Result<()> {
+    let recording = CsvRecording::read(file)?;
+    load_inventory(&client).await?;
+    let targets = resolve_targets(&client, &recording, mapping).await?;
+
+    println!(
+        "Replaying {} row(s) of {} to {} metric(s) at {speed}",
+        recording.rows.len(),
+        file.display(),
+        targets.len()
+    );
+    let pushed = replay::replay(&client, &recording, &targets, speed).await?;
+
+    client.send(PingTest).await??;
+    println!("{pushed} value(s) pushed");
+    Ok(())
+}
+
+async fn dump(
+    client: actix::Addr<SensorVisionClient>,
+    file: Option<&PathBuf>,
+    metric_order: MetricOrder,
+) -> Result<()> {
+    load_inventory(&client).await?;
+
+    let dump = client.send(DumpSensors { metric_order }).await??;
+    match file {
+        Some(file) => fs::write(file, dump + "\n")
+            .wrap_err_with(|| format!("Failed to write {}", file.display()))?,
+        None => println!("{dump}"),
+    }
+    Ok(())
+}
+
+/// On stderr as well, as the log file may be the last place looked at
+fn report_config_upgrade(upgrade: &ConfigUpgrade) {
+    let summary = format!(
+        "Upgraded the config from version {} to {CONFIG_VERSION}, the original is kept as {}",
+        upgrade.from_version,
+        upgrade.backup.display()
+    );
+    log::info!("{summary}");
+    eprintln!("{summary}");
+    for migration in &upgrade.migrations {
+        log::info!("Config migration applied: {migration}");
+        eprintln!("  - {migration}");
+    }
+}
+
+fn write_schema(kind: SchemaKind, file: Option<&PathBuf>) -> Result<()> {
+    let schema = serde_json::to_string_pretty(&kind.schema())?;
+    match file {
+        Some(file) => fs::write(file, schema + "\n")
+            .wrap_err_with(|| format!("Failed to write {}", file.display()))?,
+        None => println!("{schema}"),
+    }
+    Ok(())
+}
+
+fn print_subscriptions(subscriptions: &[Subscription]) {
+    if subscriptions.is_empty() {
+        println!("No subscriptions");
+        return;
+    }
+    let client_width = subscriptions.iter().map(|sub| sub.client_id.len()).max().unwrap_or(0);
+    let filter_width = subscriptions.iter().map(|sub| sub.filter.len()).max().unwrap_or(0);
+    println!(
+        "{:client_width$}  {:filter_width$}  QOS  {:>8}  LAST ACTIVITY",
+        "CLIENT", "FILTER", "MESSAGES"
+    );
+    for sub in subscriptions {
+        let last_activity = sub.last_activity.map_or_else(
+            || String::from("never"),
+            |at| at.to_datetime().with_timezone(&chrono::Local).format("%H:%M:%S").to_string(),
+        );
+        println!(
+            "{:client_width$}  {:filter_width$}  {:>3}  {:>8}  {last_activity}",
+            sub.client_id, sub.filter, sub.qos, sub.messages
+        );
+    }
+}
+
+async fn replay_journal(file: &Path) -> Result<()> {
+    let entries = journal::read(file)?;
+    let state = SensorsStateActor::new()?.start();
+    let replayed = journal::replay(&state, entries).await?;
+    log::info!("Replayed {replayed} messages of {}", file.display());
+
+    let sensors = state.send(GetStateSnapshot).await??;
+    println!("{}", serde_json::to_string_pretty(&sensors).wrap_err("Failed to dump sensors")?);
+    Ok(())
+}
+
+/// Searches the connector of every profile in turn, the ones failing to connect are reported and skipped
+async fn find_in_profiles(config: &Config, config_path: Option<&Path>, pattern: &str) -> Result<()> {
+    if config.profiles.is_empty() {
+        return Err(eyre!("The config has no profiles"));
+    }
+    let mut matched = 0;
+    for profile in config.profiles.keys() {
+        println!("Profile {profile}");
+        let found = async {
+            let profile_config = Config::load(config_path, Some(profile))?;
+            find(connect(&profile_config).await?, pattern).await
+        };
+        match found.await {
+            Ok(0) => println!("Nothing matches"),
+            Ok(count) => matched += count,
+            Err(err) => eprintln!("Failed to search profile {profile}: {err:#}"),
+        }
+    }
+    if matched == 0 {
+        return Err(eyre!("Nothing matches '{pattern}' in any profile"));
+    }
+    Ok(())
+}
+
+/// Prints the matches in the inventory of the client's connector, returns how many sensors matched
+async fn find(client: actix::Addr<SensorVisionClient>, pattern: &str) -> Result<usize> {
+    load_inventory(&client).await?;
+
+    let connector_id = client.send(GetConnectorId).await?;
+    let sensors = client.send(GetStateSnapshot).await??;
+    let matched = search_inventory(&sensors, pattern);
+    if matched.is_empty() {
+        return Ok(0);
+    }
+
+    let topic = |scheme: MqttScheme| format!("/v1.0/{connector_id}/{}", scheme.get_topics().0);
+    println!("Connector {connector_id}");
+    for sensor in &matched {
+        println!("{} {}", sensor.sensor_name, sensor.sensor_id);
+        if sensor.sensor_matched {
+            println!("  values to {}", topic(MqttScheme::PushValues(sensor.sensor_id)));
+        }
+        for metric in &sensor.metrics {
+            let metric_id = *metric.metric_id();
+            println!(
+                "  {} {metric_id} at {}",
+                metric.name(),
+                topic(MqttScheme::MetricDescribe(sensor.sensor_id, metric_id))
+            );
+        }
+    }
+    let metric_count: usize = matched.iter().map(|sensor| sensor.metrics.len()).sum();
+    println!("{} sensor(s), {metric_count} metric(s) match", matched.len());
+    Ok(matched.len())
+}
+
+/// Flags of `import`, `protect_livedata` is the window of livedata keeping a sensor or metric from pruning
+struct ImportOptions {
+    prune: bool,
+    protect_livedata: Option<Duration>,
+    assume_yes: bool,
+    dry_run: bool,
+}
+
+impl ImportOptions {
+    /// The livedata window depends on the config of the profile imported to
+    fn new(config: &Config, matches: &clap::ArgMatches) -> Self {
+        Self {
+            prune: matches.get_flag("prune"),
+            protect_livedata: (!matches.get_flag("allow-data-loss") && config.import.protect_livedata_secs > 0)
+                .then(|| Duration::from_secs(config.import.protect_livedata_secs)),
+            assume_yes: matches.get_flag("yes"),
+            dry_run: matches.get_flag("dry-run"),
+        }
+    }
+
+    fn request(&self, file: &Path, dry_run: bool) -> ImportSensors {
+        ImportSensors {
+            path: file.to_owned(),
+            prune: self.prune,
+            dry_run,
+            protect_livedata: self.protect_livedata,
+        }
+    }
+}
+
+async fn import(
+    client: actix::Addr<SensorVisionClient>,
+    file: &Path,
+    options: ImportOptions,
+) -> Result<()> {
+    let steps = preview_import(&client, file, &options).await?;
+    if steps.is_empty() {
+        println!("No changes");
+        return Ok(());
+    }
+    print_import_steps(&steps);
+    println!("{}", summarize_import(&steps));
+    if options.dry_run
+        || !options.assume_yes && !confirm(&format!("Apply {} changes?", steps.len()))?
+    {
+        return Ok(());
+    }
+
+    let applied = apply_import(&client, file, &options, &steps).await?;
+    println!("Applied: {}", summarize_import(&applied));
+    Ok(())
+}
+
+/// Changes the import would make to the inventory of the client's connector
+async fn preview_import(
+    client: &actix::Addr<SensorVisionClient>,
+    file: &Path,
+    options: &ImportOptions,
+) -> Result<Vec<ImportStep>> {
+    load_inventory(client).await?;
+    client.send(options.request(file, true)).await?
+}
+
+async fn apply_import(
+    client: &actix::Addr<SensorVisionClient>,
+    file: &Path,
+    options: &ImportOptions,
+    previewed: &[ImportStep],
+) -> Result<Vec<ImportStep>> {
+    let applied = client.send(options.request(file, false)).await??;
+    client.send(PingTest).await??;
+    // The inventory may have changed since the preview
+    if applied != previewed {
+        print_import_steps(&applied);
+    }
+    Ok(applied)
+}
+
+/// How the import went on the connector of a profile
+enum ProfileImport {
+    Previewed {
+        client: actix::Addr<SensorVisionClient>,
+        options: ImportOptions,
+        steps: Vec<ImportStep>,
+    },
+    Applied(Vec<ImportStep>),
+    Failed(eyre::Report),
+}
+
+/// Imports the file to the connector of every profile in turn, e.g. to keep staging and
+/// production in sync. All of them are previewed before any is changed, a profile failing to
+/// connect or to import is reported and skipped, and the outcome of each is listed in the end.
+async fn import_to_profiles(
+    config: &Config,
+    config_path: Option<&Path>,
+    profiles: &[String],
+    file: &Path,
+    matches: &clap::ArgMatches,
+) -> Result<()> {
+    if profiles.is_empty() {
+        return Err(eyre!("The config has no profiles"));
+    }
+    if let Some(unknown) = profiles.iter().find(|profile| !config.profiles.contains_key(*profile)) {
+        return Err(eyre!("No profile named '{unknown}' in the config"));
+    }
+
+    let mut imports = Vec::with_capacity(profiles.len());
+    for profile in profiles {
+        println!("Profile {profile}");
+        let previewed = async {
+            let profile_config = Config::load(config_path, Some(profile))?;
+            let client = connect(&profile_config).await?;
+            let options = ImportOptions::new(&profile_config, matches);
+            let steps = preview_import(&client, file, &options).await?;
+            Ok::<_, eyre::Report>(ProfileImport::Previewed { client, options, steps })
+        };
+        let import = previewed.await.unwrap_or_else(ProfileImport::Failed);
+        match &import {
+            ProfileImport::Previewed { steps, .. } if steps.is_empty() => println!("No changes"),
+            ProfileImport::Previewed { steps, .. } => print_import_steps(steps),
+            ProfileImport::Failed(err) => eprintln!("Failed to preview profile {profile}: {err:#}"),
+            ProfileImport::Applied(_) => {}
+        }
+        imports.push(import);
+    }
+
+    let changes: usize = imports
+        .iter()
+        .map(|import| match import {
+            ProfileImport::Previewed { steps, .. } => steps.len(),
+            _ => 0,
+        })
+        .sum();
+    let apply = changes > 0
+        && !matches.get_flag("dry-run")
+        && (matches.get_flag("yes") || confirm(&format!("Apply {changes} changes to {} profile(s)?", profiles.len()))?);
+    if apply {
+        for (profile, import) in profiles.iter().zip(&mut imports) {
+            let ProfileImport::Previewed { client, options, steps } = import else {
+                continue;
+            };
+            if steps.is_empty() {
+                continue;
+            }
+            println!("Applying to profile {profile}");
+            *import = match apply_import(client, file, options, steps).await {
+                Ok(applied) => ProfileImport::Applied(applied),
+                Err(err) => ProfileImport::Failed(err),
+            };
+        }
+    }
+
+    println!();
+    let mut failed = 0;
+    for (profile, import) in profiles.iter().zip(&imports) {
+        match import {
+            ProfileImport::Previewed { steps, .. } if steps.is_empty() => println!("{profile}: no changes"),
+            ProfileImport::Previewed { steps, .. } => println!("{profile}: not applied, {}", summarize_import(steps)),
+            ProfileImport::Applied(applied) => println!("{profile}: applied, {}", summarize_import(applied)),
+            ProfileImport::Failed(err) => {
+                failed += 1;
+                println!("{profile}: failed, {err:#}");
+            }
+        }
+    }
+    if failed > 0 {
+        return Err(eyre!("The import failed for {failed} of {} profile(s)", profiles.len()));
+    }
+    Ok(())
+}
+
+/// Colored like a diff when printed to a terminal
+fn print_import_steps(steps: &[ImportStep]) {
+    let colored = io::stdout().is_terminal();
+    for step in steps {
+        let line = step.to_string();
+        if !colored {
+            println!("{line}");
+            continue;
+        }
+        match step.kind() {
+            ChangeKind::Created => println!("{}", line.green()),
+            ChangeKind::Updated => println!("{}", line.yellow()),
+            ChangeKind::Deleted => println!("{}", line.red()),
+            ChangeKind::Protected => println!("{}", line.magenta()),
+        }
+    }
+}
+
+fn alerts(config: &Config, matches: &clap::ArgMatches) -> Result<()> {
+    match matches.subcommand() {
+        Some(("export", export_matches)) => {
+            let yaml = alert_rules_to_yaml(&config.alerts)?;
+            match export_matches.get_one::<PathBuf>("FILE") {
+                Some(file) => fs::write(file, yaml)
+                    .wrap_err_with(|| format!("Failed to write {}", file.display()))?,
+                None => print!("{yaml}"),
+            }
+        }
+        Some(("import", import_matches)) => {
+            let file = import_matches.get_one::<PathBuf>("FILE").unwrap();
+            let rules = read_alert_rules(file)?;
+
+            let changes = diff_alert_rules(&config.alerts, &rules);
+            if changes.is_empty() {
+                println!("No changes");
+                return Ok(());
+            }
+            for change in &changes {
+                println!("{change}");
+            }
+
+            let alerts_file = config.alerts_file();
+            if import_matches.get_flag("dry-run")
+                || !import_matches.get_flag("yes")
+                    && !confirm(&format!("Save {} changes to {}?", changes.len(), alerts_file.display()))?
+            {
+                return Ok(());
+            }
+            write_alert_rules(&alerts_file, &rules)?;
+        }
+        Some(("history", history_matches)) => {
+            let Some(history_file) = &config.state.history_file else {
+                return Err(eyre!("Alerts are only kept in a history file"));
+            };
+            let hours = *history_matches.get_one::<u64>("hours").unwrap();
+            let since = SystemTime::now()
+                .checked_sub(Duration::from_secs(hours.saturating_mul(3600)))
+                .unwrap_or(UNIX_EPOCH);
+            let pattern = history_matches
+                .get_one::<String>("PATTERN")
+                .map(|pattern| pattern.to_lowercase())
+                .unwrap_or_default();
+            let alerts: Vec<_> = alert_history(history_file, since.into())?
+                .into_iter()
+                .filter(|alert| !history_matches.get_flag("active") || alert.resolved_at.is_none())
+                .filter(|alert| alert.message.to_lowercase().contains(&pattern))
+                .collect();
+            let format = |at: Timestamp| {
+                at.to_datetime().with_timezone(&chrono::Local).format("%Y-%m-%d %H:%M:%S").to_string()
+            };
+            for alert in &alerts {
+                let resolved_at = alert.resolved_at.map_or_else(|| String::from("active"), format);
+                let critical = if alert.critical { " critical" } else { "" };
+                let silenced = if alert.silenced { " silenced" } else { "" };
+                println!(
+                    "{}  {resolved_at:19}  {}{critical}{silenced}",
+                    format(alert.fired_at),
+                    alert.message
+                );
+            }
+            println!("{} alert(s)", alerts.len());
+        }
+        _ => unreachable!("a subcommand is required"),
+    }
+    Ok(())
+}
+
+fn export_keymap(config: &Config, matches: &clap::ArgMatches) -> Result<()> {
+    let Some(("export", export_matches)) = matches.subcommand() else {
+        unreachable!("a subcommand is required")
+    };
+    let yaml = keymap_to_yaml(&config.keymap()?)?;
+    match export_matches.get_one::<PathBuf>("FILE") {
+        Some(file) => fs::write(file, yaml).wrap_err_with(|| format!("Failed to write {}", file.display())),
+        None => {
+            print!("{yaml}");
+            Ok(())
+        }
+    }
+}
+
+fn confirm(question: &str) -> Result<bool> {
+    print!("{question} [y/N] ");
+    io::stdout().flush()?;
+    let mut answer = String::new();
+    io::stdin().read_line(&mut answer)?;
+    Ok(matches!(answer.trim(), "y" | "Y" | "yes"))
+}
@@ -0,0 +1,98 @@
+//! Upgrades of config files written for earlier versions. A change of the format comes with
+//! a migration from the version before it, so that existing files keep loading.
+
+use eyre::{eyre, Result, WrapErr};
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use toml::{Table, Value};
+
+/// Key of the version of the format, files without it are version 0
+pub const VERSION_KEY: &str = "version";
+
+pub struct Migration {
+    /// Reported once the migration is applied
+    pub description: &'static str,
+    pub apply: fn(&mut Table) -> Result<()>,
+}
+
+/// The migration at index N upgrades version N to N + 1
+pub const MIGRATIONS: &[Migration] = &[Migration {
+    description: "Record the version of the config format",
+    apply: |_| Ok(()),
+}];
+
+pub const CONFIG_VERSION: u32 = MIGRATIONS.len() as u32;
+
+/// Migrations applied to a config file on load, which was rewritten
+#[derive(Debug, Clone, PartialEq)]
+pub struct ConfigUpgrade {
+    pub from_version: u32,
+    pub migrations: Vec<&'static str>,
+    /// The file as it was
+    pub backup: PathBuf,
+}
+
+pub fn version_of(config: &Table) -> Result<u32> {
+    match config.get(VERSION_KEY) {
+        None => Ok(0),
+        Some(Value::Integer(version)) => {
+            u32::try_from(*version).map_err(|_| eyre!("Invalid config version {version}"))
+        }
+        Some(value) => Err(eyre!("Invalid config version {value}")),
+    }
+}
+
+/// Applies the migrations `config` lacks in order and stamps it with the latest version,
+/// returns the descriptions of the migrations applied
+pub fn upgrade(config: &mut Table, migrations: &[Migration]) -> Result<Vec<&'static str>> {
+    let version = version_of(config)? as usize;
+    if version > migrations.len() {
+        return Err(eyre!(
+            "Config version {version} is newer than {}, the latest this build reads",
+            migrations.len()
+        ));
+    }
+    let mut applied = Vec::new();
+    for (from, migration) in migrations.iter().enumerate().skip(version) {
+        (migration.apply)(config).wrap_err_with(|| {
+            format!("Failed to upgrade the config from version {from}: {}", migration.description)
+        })?;
+        applied.push(migration.description);
+    }
+    config.insert(VERSION_KEY.to_owned(), Value::Integer(migrations.len() as i64));
+    Ok(applied)
+}
+
+/// Upgrades the config read from `path`. The file is rewritten, the original kept as
+/// `FILE.vN.bak`, only if a migration changed more than the version, as rewriting drops
+/// the comments.
+pub fn upgrade_file(
+    path: &Path,
+    config: &mut Table,
+    migrations: &[Migration],
+) -> Result<Option<ConfigUpgrade>> {
+    let from_version = version_of(config)?;
+    let mut original = config.clone();
+    let applied = upgrade(config, migrations)?;
+
+    original.remove(VERSION_KEY);
+    let mut upgraded = config.clone();
+    upgraded.remove(VERSION_KEY);
+    if original == upgraded {
+        return Ok(None);
+    }
+
+    let mut backup = path.as_os_str().to_owned();
+    backup.push(format!(".v{from_version}.bak"));
+    let backup = PathBuf::from(backup);
+    fs::copy(path, &backup).wrap_err_with(|| format!("Failed to back up {}", path.display()))?;
+    let contents = toml::to_string_pretty(config).wrap_err("Failed to serialize the upgraded config")?;
+    fs::write(path, contents).wrap_err_with(|| format!("Failed to write {}", path.display()))?;
+    Ok(Some(ConfigUpgrade {
+        from_version,
+        migrations: applied,
+        backup,
+    }))
+}
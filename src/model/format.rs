@@ -0,0 +1,127 @@
+//! Display of metric values: the unit of a predefined metric, or the annotation of a custom one,
+//! sets the decimal places, whether the values are scaled with SI prefixes and the suffix.
+
+use crate::model::protocol::MetricValue;
+use crate::model::sensor::{Metric, ValueType, ValueUnit};
+
+use strum::IntoEnumIterator;
+
+/// Decimal exponent -> SI prefix, from the largest
+const SI_PREFIXES: [(i32, &str); 7] = [
+    (9, "G"),
+    (6, "M"),
+    (3, "k"),
+    (0, ""),
+    (-3, "m"),
+    (-6, "µ"),
+    (-9, "n"),
+];
+
+/// Decimal places of custom doubles, annotations carry no precision
+const CUSTOM_PRECISION: usize = 2;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ValueFormat {
+    /// Shown after the value, may be empty
+    suffix: String,
+    /// Decimal places of the value once scaled
+    precision: usize,
+    prefixed: bool,
+    /// Unscaled values are shown without decimal places
+    integer: bool,
+}
+
+impl ValueFormat {
+    pub fn of(metric: &Metric) -> Self {
+        match metric {
+            Metric::Predefined { value_unit, .. } => Self {
+                suffix: value_unit.symbol().to_owned(),
+                precision: value_unit.precision(),
+                prefixed: value_unit.prefixed(),
+                integer: false,
+            },
+            Metric::Custom {
+                value_type,
+                value_annotation,
+                ..
+            } => {
+                let suffix = value_annotation.trim();
+                Self {
+                    suffix: suffix.to_owned(),
+                    precision: CUSTOM_PRECISION,
+                    // An annotation naming a unit, e.g. `W`, is scaled the way the unit is
+                    prefixed: ValueUnit::iter().any(|unit| unit.prefixed() && unit.symbol() == suffix),
+                    integer: matches!(value_type, ValueType::Integer | ValueType::Boolean),
+                }
+            }
+        }
+    }
+
+    /// Same format for values derived from the metric ones, e.g. averages, which have
+    /// decimal places even if the metric values don't
+    pub fn fractional(&self) -> Self {
+        Self {
+            integer: false,
+            ..self.clone()
+        }
+    }
+
+    /// E.g. `101.33 kPa`, `21.5 °C` or `48.13743°`
+    pub fn format(&self, value: f64) -> String {
+        let (scaled, prefix) = self.scale(value);
+        let precision = if self.integer && prefix.is_empty() { 0 } else { self.precision };
+        self.suffixed(format!("{scaled:.precision$}"), prefix)
+    }
+
+    /// Same as [`format`](Self::format), but unscaled integers are shown exactly, even
+    /// those f64 can't hold. Strings are shown as they are.
+    pub fn format_value(&self, value: &MetricValue) -> String {
+        match value {
+            MetricValue::Integer(integer) if self.exponent(*integer as f64) == 0 => {
+                self.suffixed(integer.to_string(), "")
+            }
+            MetricValue::String(string) => string.clone(),
+            value => self.format(value.as_f64().unwrap_or_default()),
+        }
+    }
+
+    /// The value the way it's typed in: unscaled, without the suffix and rounded to what
+    /// [`format`](Self::format) shows, so that [`Metric::parse_value`] reads it back
+    pub fn editable(&self, value: &MetricValue) -> String {
+        match value {
+            MetricValue::Double(double) if !self.integer => {
+                let exponent = self.exponent(*double);
+                let precision = (self.precision as i32 - exponent).max(0) as usize;
+                format!("{double:.precision$}")
+            }
+            value => value.to_string(),
+        }
+    }
+
+    fn suffixed(&self, number: String, prefix: &str) -> String {
+        match self.suffix.as_str() {
+            "" => format!("{number}{prefix}"),
+            "°" => format!("{number}°"),
+            suffix => format!("{number} {prefix}{suffix}"),
+        }
+    }
+
+    fn scale(&self, value: f64) -> (f64, &'static str) {
+        let exponent = self.exponent(value);
+        let prefix = SI_PREFIXES
+            .iter()
+            .find(|(prefix_exponent, _)| *prefix_exponent == exponent)
+            .map_or("", |(_, prefix)| *prefix);
+        (value / 10f64.powi(exponent), prefix)
+    }
+
+    /// Exponent of the SI prefix the value is scaled with, 0 if it isn't
+    fn exponent(&self, value: f64) -> i32 {
+        let magnitude = value.abs();
+        if !self.prefixed || magnitude == 0.0 || !magnitude.is_finite() {
+            return 0;
+        }
+        let (largest, smallest) = (SI_PREFIXES[0].0, SI_PREFIXES[SI_PREFIXES.len() - 1].0);
+        ((magnitude.log10().floor() as i32).div_euclid(3) * 3).clamp(smallest, largest)
+    }
+}
@@ -1,8 +1,14 @@
+use chrono::{DateTime, Utc};
 use std::fmt::Formatter;
 use std::convert::{From, Into};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
 use uuid::Uuid;
+use schemars::r#gen::SchemaGenerator;
+use schemars::schema::{InstanceType, Metadata, Schema, SchemaObject, StringValidation};
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
+pub mod format;
 pub mod protocol;
 pub mod sensor;
 
@@ -17,6 +23,33 @@ impl MqttId {
     pub fn is_nil(&self) -> bool {
         self.uuid.is_nil()
     }
+
+    pub fn as_uuid(&self) -> &Uuid {
+        &self.uuid
+    }
+}
+
+/// The simple form is written, the hyphenated one is read as well
+impl JsonSchema for MqttId {
+    fn schema_name() -> String {
+        String::from("MqttId")
+    }
+
+    fn json_schema(_: &mut SchemaGenerator) -> Schema {
+        SchemaObject {
+            metadata: Some(Box::new(Metadata {
+                description: Some(String::from("UUID as 32 hexadecimal digits")),
+                ..Default::default()
+            })),
+            instance_type: Some(InstanceType::String.into()),
+            string: Some(Box::new(StringValidation {
+                pattern: Some(String::from("^[0-9a-fA-F]{8}(-?[0-9a-fA-F]{4}){3}-?[0-9a-fA-F]{12}$")),
+                ..Default::default()
+            })),
+            ..Default::default()
+        }
+        .into()
+    }
 }
 
 impl std::fmt::Display for MqttId {
@@ -61,7 +94,116 @@ impl From<Uuid> for MqttId {
     }
 }
 
-// TODO apply strong typedef
-pub type ConnectorId = MqttId;
-pub type SensorId = MqttId;
-pub type MetricId = MqttId;
+/// Milliseconds since the Unix epoch, the way the protocol carries them
+#[derive(Copy, Clone, Debug, Default, Eq, Hash, Ord, PartialEq, PartialOrd, Serialize, Deserialize, JsonSchema)]
+#[serde(transparent)]
+pub struct Timestamp(u64);
+
+impl Timestamp {
+    pub fn now() -> Self {
+        SystemTime::now().into()
+    }
+
+    pub fn from_millis(millis: u64) -> Self {
+        Self(millis)
+    }
+
+    pub fn as_millis(&self) -> u64 {
+        self.0
+    }
+
+    pub fn to_datetime(&self) -> DateTime<Utc> {
+        SystemTime::from(*self).into()
+    }
+}
+
+impl From<SystemTime> for Timestamp {
+    fn from(value: SystemTime) -> Self {
+        // Times before the epoch don't occur in the protocol
+        let since_epoch = value.duration_since(UNIX_EPOCH).unwrap_or_default();
+        Self(since_epoch.as_millis() as u64)
+    }
+}
+
+impl From<Timestamp> for SystemTime {
+    fn from(value: Timestamp) -> Self {
+        UNIX_EPOCH + Duration::from_millis(value.0)
+    }
+}
+
+/// Declares an id of its own kind around [`MqttId`], so that a sensor id can't be passed
+/// where a metric id is expected. Serialized the same way, untyped ids from the topics are
+/// converted with `From`.
+macro_rules! typed_id {
+    ($(#[$meta:meta])* $name:ident) => {
+        $(#[$meta])*
+        #[derive(Copy, Clone, Debug, Default, Eq, Hash, Ord, PartialEq, PartialOrd, Serialize, Deserialize, JsonSchema)]
+        #[serde(transparent)]
+        pub struct $name(MqttId);
+
+        impl $name {
+            pub fn is_nil(&self) -> bool {
+                self.0.is_nil()
+            }
+
+            pub fn as_uuid(&self) -> &Uuid {
+                self.0.as_uuid()
+            }
+        }
+
+        impl std::fmt::Display for $name {
+            fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+                self.0.fmt(f)
+            }
+        }
+
+        impl From<MqttId> for $name {
+            fn from(value: MqttId) -> Self {
+                Self(value)
+            }
+        }
+
+        impl From<$name> for MqttId {
+            fn from(value: $name) -> Self {
+                value.0
+            }
+        }
+
+        impl From<Uuid> for $name {
+            fn from(value: Uuid) -> Self {
+                Self(value.into())
+            }
+        }
+
+        impl From<&str> for $name {
+            fn from(value: &str) -> Self {
+                Self(value.into())
+            }
+        }
+
+        impl From<String> for $name {
+            fn from(value: String) -> Self {
+                Self(value.into())
+            }
+        }
+
+        impl From<$name> for String {
+            fn from(value: $name) -> Self {
+                value.0.into()
+            }
+        }
+
+        impl From<&$name> for String {
+            fn from(value: &$name) -> Self {
+                value.0.into()
+            }
+        }
+    };
+}
+
+typed_id!(
+    /// Common name of the connector certificate
+    ConnectorId
+);
+typed_id!(SensorId);
+typed_id!(MetricId);
@@ -1,7 +1,7 @@
 use serde_with::{serde_as, DisplayFromStr};
 use serde::{Deserialize, Serialize};
 
-use crate::model::MetricId;
+use crate::model::{MetricId, Timestamp};
 use crate::model::sensor::Metric;
 
 // TODO Incorporate into MqttRequest and MqtResponse types
@@ -36,7 +36,7 @@ impl<T> MetricsArrayRequest<T> {
 #[derive(Debug, Deserialize)]
 pub struct MetricsArrayResponse<T> {
     pub metrics: Vec<T>,
-    pub timestamp: Option<u64>,
+    pub timestamp: Option<Timestamp>,
 }
 
 #[serde_as]
@@ -89,6 +89,29 @@ pub enum MetricValue {
     Boolean(bool),
 }
 
+impl MetricValue {
+    /// Position of the value on a chart, strings have none
+    pub fn as_f64(&self) -> Option<f64> {
+        match self {
+            MetricValue::Integer(value) => Some(*value as f64),
+            MetricValue::Double(value) => Some(*value),
+            MetricValue::Boolean(value) => Some(*value as u8 as f64),
+            MetricValue::String(_) => None,
+        }
+    }
+}
+
+impl std::fmt::Display for MetricValue {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            MetricValue::Integer(value) => write!(f, "{value}"),
+            MetricValue::Double(value) => write!(f, "{value}"),
+            MetricValue::String(value) => write!(f, "{value}"),
+            MetricValue::Boolean(value) => write!(f, "{value}"),
+        }
+    }
+}
+
 #[derive(Debug, Serialize)]
 pub struct PushMetricValueRequest {
     #[serde(rename = "metricId")]
@@ -97,7 +120,7 @@ pub struct PushMetricValueRequest {
     pub value: MetricValue,
 
     #[serde(skip_serializing_if = "Option::is_none")]
-    pub timestamp: Option<u128>,
+    pub timestamp: Option<Timestamp>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -1,89 +1,139 @@
-use eyre::Result;
+use eyre::{eyre, Result};
 
-use serde::{Deserialize, Serialize};
+use indexmap::IndexMap;
+
+use schemars::r#gen::SchemaGenerator;
+use schemars::schema::{InstanceType, Metadata, Schema, SchemaObject};
+use schemars::JsonSchema;
+
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
 use serde_valid::Validate;
 
-use strum::EnumIter;
+use std::collections::HashSet;
+
+use strum::{EnumIter, EnumProperty};
 
 use crate::model::protocol::MetricValue;
 use crate::model::*;
 
-#[derive(Clone, Debug, Eq, PartialEq, Deserialize, Serialize, EnumIter)]
+/// `symbol` is shown after the values, `precision` is the number of their decimal places and
+/// `prefixed` units are scaled with SI prefixes
+#[derive(Clone, Debug, Eq, PartialEq, Deserialize, Serialize, EnumIter, EnumProperty, JsonSchema)]
 pub enum ValueUnit {
     #[serde(rename = "SI.ElectricCurrent.AMPERE")]
+    #[strum(props(symbol = "A", precision = "2", prefixed = "true"))]
     Ampere,
 
     #[serde(rename = "SI.DataAmount.BIT")]
+    #[strum(props(symbol = "bit", precision = "1", prefixed = "true"))]
     Bit,
 
     #[serde(rename = "SI.LuminousIntensity.CANDELA")]
+    #[strum(props(symbol = "cd", precision = "2", prefixed = "true"))]
     Candela,
 
     #[serde(rename = "SI.Temperature.CELSIUS")]
+    #[strum(props(symbol = "°C", precision = "1"))]
     Celsius,
 
     #[serde(rename = "NoSI.Dimensionless.DECIBEL")]
+    #[strum(props(symbol = "dB", precision = "1"))]
     Decibel,
 
     #[serde(rename = "SI.ElectricCapacitance.FARAD")]
+    #[strum(props(symbol = "F", precision = "2", prefixed = "true"))]
     Farad,
 
     #[serde(rename = "SI.Frequency.HERTZ")]
+    #[strum(props(symbol = "Hz", precision = "2", prefixed = "true"))]
     Hertz,
 
     #[serde(rename = "SI.Energy.JOULE")]
+    #[strum(props(symbol = "J", precision = "2", prefixed = "true"))]
     Joule,
 
     #[serde(rename = "SI.Mass.KILOGRAM")]
+    #[strum(props(symbol = "kg", precision = "2"))]
     Kilogram,
 
     #[serde(rename = "NoSI.Location.LATITUDE")]
+    #[strum(props(symbol = "°", precision = "5"))]
     Latitude,
 
     #[serde(rename = "NoSI.Location.LONGITUDE")]
+    #[strum(props(symbol = "°", precision = "5"))]
     Longitude,
 
     #[serde(rename = "SI.Length.METER")]
+    #[strum(props(symbol = "m", precision = "2", prefixed = "true"))]
     Meter,
 
     #[serde(rename = "SI.Velocity.METERS_PER_SECOND")]
+    #[strum(props(symbol = "m/s", precision = "2"))]
     MetersPerSecond,
 
     #[serde(rename = "SI.Acceleration.METERS_PER_SQUARE_SECOND")]
+    #[strum(props(symbol = "m/s²", precision = "2"))]
     MetersPerSquareSecond,
 
     #[serde(rename = "SI.AmountOfSubstance.MOLE")]
+    #[strum(props(symbol = "mol", precision = "2", prefixed = "true"))]
     Mole,
 
     #[serde(rename = "SI.Force.NEWTON")]
+    #[strum(props(symbol = "N", precision = "2", prefixed = "true"))]
     Newton,
 
     #[serde(rename = "SI.ElectricResistance.OHM")]
+    #[strum(props(symbol = "Ω", precision = "2", prefixed = "true"))]
     Ohm,
 
     #[serde(rename = "SI.Pressure.PASCAL")]
+    #[strum(props(symbol = "Pa", precision = "2", prefixed = "true"))]
     Pascal,
 
     #[serde(rename = "NoSI.Dimensionless.PERCENT")]
+    #[strum(props(symbol = "%", precision = "1"))]
     Percent,
 
     #[serde(rename = "SI.Angle.RADIAN")]
+    #[strum(props(symbol = "rad", precision = "3"))]
     Radian,
 
     #[serde(rename = "SI.Duration.SECOND")]
+    #[strum(props(symbol = "s", precision = "2", prefixed = "true"))]
     Second,
 
     #[serde(rename = "SI.Area.SQUARE_METRE")]
+    #[strum(props(symbol = "m²", precision = "2"))]
     SquareMetre,
 
     #[serde(rename = "SI.ElectricPotential.VOLT")]
+    #[strum(props(symbol = "V", precision = "2", prefixed = "true"))]
     Volt,
 
     #[serde(rename = "SI.Power.WATT")]
+    #[strum(props(symbol = "W", precision = "2", prefixed = "true"))]
     Watt,
 }
 
-#[derive(Clone, Debug, Eq, PartialEq, Deserialize, Serialize, EnumIter)]
+impl ValueUnit {
+    pub fn symbol(&self) -> &'static str {
+        self.get_str("symbol").unwrap_or_default()
+    }
+
+    pub fn precision(&self) -> usize {
+        self.get_str("precision")
+            .and_then(|precision| precision.parse().ok())
+            .unwrap_or(2)
+    }
+
+    pub fn prefixed(&self) -> bool {
+        self.get_str("prefixed").is_some()
+    }
+}
+
+#[derive(Clone, Debug, Eq, PartialEq, Deserialize, Serialize, EnumIter, JsonSchema)]
 pub enum ValueType {
     #[serde(rename = "bool")]
     Boolean,
@@ -99,42 +149,459 @@ pub enum ValueType {
 }
 
 impl ValueType {
+    /// Numbers may use either a decimal point or a decimal comma and be digit-grouped,
+    /// e.g. `3,14`, `1 234,5`, `1,234.5` or `1,234`
     pub fn to_value(&self, value: &str) -> Result<MetricValue> {
+        let trimmed = value.trim();
         match self {
-            Self::Double => Ok(MetricValue::Double(value.parse()?)),
-            Self::Integer => Ok(MetricValue::Integer(value.parse()?)),
-            Self::Boolean => Ok(MetricValue::Boolean(value.parse()?)),
+            Self::Double => normalize_number(trimmed)
+                .parse()
+                .map(MetricValue::Double)
+                .map_err(|_| eyre!("Expected a number, got '{trimmed}'")),
+            Self::Integer => normalize_number(trimmed)
+                .parse()
+                .map(MetricValue::Integer)
+                .map_err(|_| eyre!("Expected a whole number, got '{trimmed}'")),
+            Self::Boolean => match trimmed.to_lowercase().as_str() {
+                "true" => Ok(MetricValue::Boolean(true)),
+                "false" => Ok(MetricValue::Boolean(false)),
+                _ => Err(eyre!("Expected true or false, got '{trimmed}'")),
+            },
             Self::String => Ok(MetricValue::String(value.to_owned())),
         }
     }
 }
 
-#[derive(Clone, Debug, Eq, PartialEq, Deserialize, Serialize, Validate)]
-pub struct Sensor<T> {
+/// Drops the digit grouping and turns the decimal separator into a point.
+/// A separator occurring once and not followed by the other one is taken for the decimal one,
+/// except for a comma grouping the thousands, like in `1,234` or `-12,500`.
+fn normalize_number(value: &str) -> String {
+    let value: String = value
+        .chars()
+        .filter(|char| !matches!(char, ' ' | '\u{a0}' | '\u{202f}' | '\'' | '_'))
+        .collect();
+
+    let decimal_separator = match (value.rfind('.'), value.rfind(',')) {
+        (Some(point), Some(comma)) => Some(if point > comma { '.' } else { ',' }),
+        (Some(_), None) => (value.matches('.').count() == 1).then_some('.'),
+        (None, Some(comma)) => {
+            (value.matches(',').count() == 1 && !groups_thousands(&value, comma)).then_some(',')
+        }
+        (None, None) => None,
+    };
+
+    value
+        .chars()
+        .filter_map(|char| match char {
+            '.' | ',' if Some(char) == decimal_separator => Some('.'),
+            '.' | ',' => None,
+            char => Some(char),
+        })
+        .collect()
+}
+
+/// The separator at `at` has 1 to 3 digits not starting with a zero before it and 3 digits after
+fn groups_thousands(value: &str, at: usize) -> bool {
+    let (before, after) = (value[..at].trim_start_matches(['-', '+']), &value[at + 1..]);
+    let all_digits = |digits: &str| digits.chars().all(|char| char.is_ascii_digit());
+    (1..=3).contains(&before.len())
+        && !before.starts_with('0')
+        && all_digits(before)
+        && after.len() == 3
+        && all_digits(after)
+}
+
+/// Splits `SENSOR/METRIC` at the first `/` which isn't escaped as `\/`, so that sensors with a `/`
+/// in their names can be addressed too, e.g. `Room 1\/2/Temperature`. `\\` stands for a backslash,
+/// any other one is kept as it is.
+pub fn split_target(target: &str) -> Option<(String, String)> {
+    let mut parts = vec![String::new()];
+    let mut chars = target.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => match chars.next() {
+                Some(escaped @ ('/' | '\\')) => parts.last_mut().unwrap().push(escaped),
+                Some(other) => parts.last_mut().unwrap().extend(['\\', other]),
+                None => parts.last_mut().unwrap().push('\\'),
+            },
+            '/' if parts.len() == 1 => parts.push(String::new()),
+            c => parts.last_mut().unwrap().push(c),
+        }
+    }
+    let [sensor, metric] = <[String; 2]>::try_from(parts).ok()?;
+    Some((sensor, metric))
+}
+
+#[derive(Clone, Debug, Eq, PartialEq, Deserialize, Serialize, Validate, JsonSchema)]
+pub struct Sensor<T: HasMetricId> {
     #[validate(min_length = 2)]
     #[validate(max_length = 64)]
+    #[schemars(length(min = 2, max = 64))]
     pub name: String,
 
     #[serde(rename = "sensorId")]
     pub sensor_id: SensorId,
 
-    // TODO Spike how to get HashMap here instead of Vec
     #[serde(default)]
-    pub metrics: Vec<T>,
+    pub metrics: Metrics<T>,
+
+    #[serde(default)]
+    pub state: SensorState,
 
     #[serde(skip)]
     pub connector_id: ConnectorId,
 }
 
-#[derive(Clone, Debug, Eq, PartialEq, Deserialize, Serialize, Validate)]
+/// Whether the agent accepts values for the sensor, `1` or `0` in the API
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, Deserialize, Serialize)]
+#[serde(from = "u8", into = "u8")]
+pub enum SensorState {
+    #[default]
+    Enabled,
+    Disabled,
+}
+
+impl SensorState {
+    pub fn toggled(self) -> Self {
+        match self {
+            Self::Enabled => Self::Disabled,
+            Self::Disabled => Self::Enabled,
+        }
+    }
+}
+
+impl From<u8> for SensorState {
+    fn from(state: u8) -> Self {
+        match state {
+            0 => Self::Disabled,
+            _ => Self::Enabled,
+        }
+    }
+}
+
+impl From<SensorState> for u8 {
+    fn from(state: SensorState) -> Self {
+        match state {
+            SensorState::Enabled => 1,
+            SensorState::Disabled => 0,
+        }
+    }
+}
+
+impl JsonSchema for SensorState {
+    fn schema_name() -> String {
+        String::from("SensorState")
+    }
+
+    fn json_schema(_: &mut SchemaGenerator) -> Schema {
+        SchemaObject {
+            metadata: Some(Box::new(Metadata {
+                description: Some(String::from("`1` if the sensor is enabled, `0` if disabled")),
+                default: Some(1.into()),
+                ..Default::default()
+            })),
+            instance_type: Some(InstanceType::Integer.into()),
+            enum_values: Some(vec![1.into(), 0.into()]),
+            ..Default::default()
+        }
+        .into()
+    }
+}
+
+impl std::fmt::Display for SensorState {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Enabled => write!(f, "enabled"),
+            Self::Disabled => write!(f, "disabled"),
+        }
+    }
+}
+
+impl Sensor<Metric> {
+    /// Puts a new sensor together, see [`SensorBuilder`]
+    pub fn builder() -> SensorBuilder {
+        SensorBuilder::default()
+    }
+
+    /// Names of the new metrics which a metric of the sensor, or an earlier new one, already has.
+    /// The agent accepts duplicates, which can only be told apart by their ids afterwards.
+    pub fn colliding_names(&self, new_metrics: &[Metric]) -> Vec<String> {
+        let mut taken: HashSet<&str> = self.metrics.iter().map(|metric| metric.name().as_str()).collect();
+        let mut colliding = Vec::new();
+        for metric in new_metrics {
+            if !taken.insert(metric.name().as_str()) && !colliding.contains(metric.name()) {
+                colliding.push(metric.name().clone());
+            }
+        }
+        colliding
+    }
+
+    /// Renames the colliding new metrics to the first free `NAME (2)`, `NAME (3)`...
+    /// and returns the renamings as `(old, new)` pairs
+    pub fn suffix_colliding(&self, new_metrics: &mut [Metric]) -> Vec<(String, String)> {
+        let mut taken: HashSet<String> = self.metrics.iter().map(|metric| metric.name().clone()).collect();
+        let mut renamed = Vec::new();
+        for metric in new_metrics {
+            let name = metric.name().clone();
+            if taken.insert(name.clone()) {
+                continue;
+            }
+            let suffixed = (2..)
+                .map(|n| format!("{name} ({n})"))
+                .find(|suffixed| !taken.contains(suffixed))
+                .expect("a free suffix");
+            taken.insert(suffixed.clone());
+            metric.rename(suffixed.clone());
+            renamed.push((name, suffixed));
+        }
+        renamed
+    }
+}
+
+/// Sensor to be created along with its metrics, which get their ids from the agent
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct NewSensor {
+    pub name: String,
+    pub state: SensorState,
+    pub metrics: Vec<Metric>,
+}
+
+/// Puts a [`NewSensor`] together for library users, e.g.
+/// `Sensor::builder().name("Boiler").predefined("Temperature", ValueUnit::Celsius).build()`.
+/// What the agent would reject, or silently accept but leave ambiguous, is checked at
+/// [`build`](SensorBuilder::build) rather than by the agent.
+#[derive(Clone, Debug, Default)]
+pub struct SensorBuilder {
+    name: String,
+    state: SensorState,
+    metrics: Vec<Metric>,
+}
+
+impl SensorBuilder {
+    pub fn name(mut self, name: impl Into<String>) -> Self {
+        self.name = name.into();
+        self
+    }
+
+    pub fn state(mut self, state: SensorState) -> Self {
+        self.state = state;
+        self
+    }
+
+    pub fn metric(mut self, metric: Metric) -> Self {
+        self.metrics.push(metric);
+        self
+    }
+
+    pub fn metrics(mut self, metrics: impl IntoIterator<Item = Metric>) -> Self {
+        self.metrics.extend(metrics);
+        self
+    }
+
+    pub fn predefined(self, name: impl Into<String>, value_unit: ValueUnit) -> Self {
+        self.metric(Metric::predefined(name.into(), value_unit))
+    }
+
+    pub fn custom(
+        self,
+        name: impl Into<String>,
+        value_type: ValueType,
+        value_annotation: impl Into<String>,
+    ) -> Self {
+        self.metric(Metric::custom(name.into(), value_type, value_annotation.into()))
+    }
+
+    /// Fails on the first invalid name or annotation, on metrics sharing a name,
+    /// and on metrics which already have ids, i.e. belong to another sensor
+    pub fn build(self) -> Result<NewSensor> {
+        let sensor = Sensor::<Metric> {
+            name: self.name,
+            sensor_id: SensorId::default(),
+            metrics: Metrics::default(),
+            state: self.state,
+            connector_id: ConnectorId::default(),
+        };
+        sensor
+            .validate()
+            .map_err(|errors| eyre!("Invalid sensor '{}': {errors}", sensor.name))?;
+
+        let mut names = HashSet::new();
+        for metric in &self.metrics {
+            metric
+                .validate()
+                .map_err(|errors| eyre!("Invalid metric '{}': {errors}", metric.name()))?;
+            if !metric.metric_id().is_nil() {
+                return Err(eyre!("Metric {} already has id {}", metric.name(), metric.metric_id()));
+            }
+            if !names.insert(metric.name()) {
+                return Err(eyre!("Metric {} is added twice", metric.name()));
+            }
+        }
+
+        Ok(NewSensor {
+            name: sensor.name,
+            state: sensor.state,
+            metrics: self.metrics,
+        })
+    }
+}
+
+pub trait HasMetricId {
+    fn metric_id(&self) -> &MetricId;
+}
+
+/// Metrics of a sensor by their ids, in the order the server lists them.
+/// (De)serialized as the array the API uses.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Metrics<T>(IndexMap<MetricId, T>);
+
+impl<T> Default for Metrics<T> {
+    fn default() -> Self {
+        Self(IndexMap::new())
+    }
+}
+
+impl<T: HasMetricId> Metrics<T> {
+    /// Replaces the metric with the same id in place, or appends it
+    pub fn insert(&mut self, metric: T) -> Option<T> {
+        self.0.insert(*metric.metric_id(), metric)
+    }
+}
+
+impl<T> Metrics<T> {
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    pub fn get(&self, metric_id: &MetricId) -> Option<&T> {
+        self.0.get(metric_id)
+    }
+
+    pub fn get_mut(&mut self, metric_id: &MetricId) -> Option<&mut T> {
+        self.0.get_mut(metric_id)
+    }
+
+    pub fn contains(&self, metric_id: &MetricId) -> bool {
+        self.0.contains_key(metric_id)
+    }
+
+    pub fn position(&self, metric_id: &MetricId) -> Option<usize> {
+        self.0.get_index_of(metric_id)
+    }
+
+    pub fn get_index(&self, index: usize) -> Option<&T> {
+        self.0.get_index(index).map(|(_, metric)| metric)
+    }
+
+    /// Keeps the order of the remaining metrics
+    pub fn remove(&mut self, metric_id: &MetricId) -> Option<T> {
+        self.0.shift_remove(metric_id)
+    }
+
+    pub fn retain(&mut self, mut keep: impl FnMut(&T) -> bool) {
+        self.0.retain(|_, metric| keep(metric));
+    }
+
+    pub fn sort_by(&mut self, mut compare: impl FnMut(&T, &T) -> std::cmp::Ordering) {
+        self.0.sort_by(|_, a, _, b| compare(a, b));
+    }
+
+    pub fn ids(&self) -> indexmap::map::Keys<'_, MetricId, T> {
+        self.0.keys()
+    }
+
+    pub fn iter(&self) -> indexmap::map::Values<'_, MetricId, T> {
+        self.0.values()
+    }
+
+    pub fn iter_mut(&mut self) -> indexmap::map::ValuesMut<'_, MetricId, T> {
+        self.0.values_mut()
+    }
+}
+
+impl<T: HasMetricId> FromIterator<T> for Metrics<T> {
+    fn from_iter<I: IntoIterator<Item = T>>(metrics: I) -> Self {
+        let mut collected = Self::default();
+        collected.extend(metrics);
+        collected
+    }
+}
+
+impl<T: HasMetricId> Extend<T> for Metrics<T> {
+    fn extend<I: IntoIterator<Item = T>>(&mut self, metrics: I) {
+        for metric in metrics {
+            self.insert(metric);
+        }
+    }
+}
+
+impl<T> IntoIterator for Metrics<T> {
+    type Item = T;
+    type IntoIter = indexmap::map::IntoValues<MetricId, T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.0.into_values()
+    }
+}
+
+impl<'a, T> IntoIterator for &'a Metrics<T> {
+    type Item = &'a T;
+    type IntoIter = indexmap::map::Values<'a, MetricId, T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl<'a, T> IntoIterator for &'a mut Metrics<T> {
+    type Item = &'a mut T;
+    type IntoIter = indexmap::map::ValuesMut<'a, MetricId, T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter_mut()
+    }
+}
+
+impl<T: Serialize> Serialize for Metrics<T> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+        serializer.collect_seq(self.iter())
+    }
+}
+
+impl<T: JsonSchema> JsonSchema for Metrics<T> {
+    fn is_referenceable() -> bool {
+        Vec::<T>::is_referenceable()
+    }
+
+    fn schema_name() -> String {
+        Vec::<T>::schema_name()
+    }
+
+    fn json_schema(generator: &mut SchemaGenerator) -> Schema {
+        Vec::<T>::json_schema(generator)
+    }
+}
+
+impl<'de, T: Deserialize<'de> + HasMetricId> Deserialize<'de> for Metrics<T> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> std::result::Result<Self, D::Error> {
+        Ok(Vec::<T>::deserialize(deserializer)?.into_iter().collect())
+    }
+}
+
+#[derive(Clone, Debug, Eq, PartialEq, Deserialize, Serialize, Validate, JsonSchema)]
 #[serde(untagged)]
 pub enum Metric {
     Predefined {
         #[validate(min_length = 2)]
         #[validate(max_length = 64)]
+        #[schemars(length(min = 2, max = 64))]
         name: String,
 
-        #[serde(rename = "metricId")]
+        #[serde(rename = "metricId", default)]
         #[serde(skip_serializing_if = "MetricId::is_nil")]
         metric_id: MetricId,
 
@@ -144,13 +611,18 @@ pub enum Metric {
     Custom {
         #[validate(min_length = 2)]
         #[validate(max_length = 64)]
+        #[schemars(length(min = 2, max = 64))]
         name: String,
 
-        #[serde(rename = "metricId")]
+        #[serde(rename = "metricId", default)]
         #[serde(skip_serializing_if = "MetricId::is_nil")]
         metric_id: MetricId,
 
-        // TODO Add validation
+        // Unit-like text the API shows next to the values
+        #[validate(min_length = 1, message = "required")]
+        #[validate(max_length = 32, message = "32 characters at most")]
+        #[schemars(length(min = 1, max = 32))]
+        #[validate(pattern = r"^[\p{L}\p{N} %°/._\-]*$", message = "letters, digits, spaces and %°/._- only")]
         #[serde(rename = "valueAnnotation")]
         value_annotation: String,
 
@@ -190,6 +662,44 @@ impl Metric {
         }
     }
 
+    /// Parses a value typed in by the user according to the metric type.
+    /// Predefined metrics are always numeric.
+    pub fn parse_value(&self, value: &str) -> Result<MetricValue> {
+        match self {
+            Metric::Predefined { .. } => ValueType::Double.to_value(value),
+            Metric::Custom { value_type, .. } => value_type.to_value(value),
+        }
+    }
+
+    /// Value of a numeric reading, rounded or formatted according to the metric type
+    pub fn numeric_value(&self, raw: f64) -> MetricValue {
+        let value_type = match self {
+            Metric::Predefined { .. } => &ValueType::Double,
+            Metric::Custom { value_type, .. } => value_type,
+        };
+        match value_type {
+            ValueType::Double => MetricValue::Double(raw),
+            ValueType::Integer => MetricValue::Integer(raw.round() as i64),
+            ValueType::Boolean => MetricValue::Boolean(raw > 0.0),
+            ValueType::String => MetricValue::String(format!("{raw:.3}")),
+        }
+    }
+
+    /// Same metric definition, not bound to any metric id
+    pub fn detached(&self) -> Self {
+        match self {
+            Metric::Predefined {
+                name, value_unit, ..
+            } => Metric::predefined(name.clone(), value_unit.clone()),
+            Metric::Custom {
+                name,
+                value_type,
+                value_annotation,
+                ..
+            } => Metric::custom(name.clone(), value_type.clone(), value_annotation.clone()),
+        }
+    }
+
     pub fn rename(&mut self, new_name: String) {
         match self {
             Metric::Predefined { name, .. } => {*name = new_name;},
@@ -198,7 +708,7 @@ impl Metric {
     }
 }
 
-#[derive(Clone, Debug, Eq, PartialEq, Default, Deserialize)]
+#[derive(Clone, Debug, Eq, PartialEq, Default, Deserialize, Serialize)]
 pub struct LinkedMetric {
     pub link: String,
 
@@ -206,6 +716,18 @@ pub struct LinkedMetric {
     pub metric_id: MetricId,
 }
 
+impl HasMetricId for Metric {
+    fn metric_id(&self) -> &MetricId {
+        Metric::metric_id(self)
+    }
+}
+
+impl HasMetricId for LinkedMetric {
+    fn metric_id(&self) -> &MetricId {
+        &self.metric_id
+    }
+}
+
 // TODO get rid of Default impl for Metric
 impl Default for Metric {
     fn default() -> Self {
@@ -0,0 +1,237 @@
+use schemars::JsonSchema;
+
+use serde::{Deserialize, Serialize};
+
+use std::io::Write as _;
+use std::process::Stdio;
+use std::sync::Arc;
+
+use crate::http;
+use crate::model::Timestamp;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Deserialize, JsonSchema, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    #[default]
+    Info,
+    Warning,
+    Critical,
+}
+
+/// What a notification is about, lets a sink subscribe to a part of them only
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, JsonSchema, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Category {
+    Alert,
+    Connection,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Notification {
+    pub severity: Severity,
+    pub category: Category,
+    pub summary: String,
+    pub body: String,
+
+    /// Delivered to these sinks only, regardless of their severity thresholds, if not empty
+    #[serde(skip)]
+    pub sinks: Vec<String>,
+}
+
+impl Notification {
+    pub fn new(severity: Severity, category: Category, summary: &str, body: &str) -> Self {
+        Self {
+            severity,
+            category,
+            summary: summary.to_owned(),
+            body: body.to_owned(),
+            sinks: Vec::new(),
+        }
+    }
+
+    pub fn to_sinks(mut self, sinks: Vec<String>) -> Self {
+        self.sinks = sinks;
+        self
+    }
+}
+
+/// Somewhere notifications end up. Delivery must not block, slow sinks spawn a task.
+pub trait NotificationSink {
+    /// How alert rules refer to the sink
+    fn name(&self) -> &'static str;
+
+    fn notify(&self, notification: &Notification);
+}
+
+/// Which notifications a sink receives
+#[derive(Debug, Clone, Default)]
+pub struct Route {
+    pub min_severity: Severity,
+
+    /// Every category if empty
+    pub categories: Vec<Category>,
+
+    /// Skip the notifications raised while the terminal has the focus
+    pub unfocused_only: bool,
+}
+
+impl Route {
+    pub fn from_severity(min_severity: Severity) -> Self {
+        Self {
+            min_severity,
+            ..Self::default()
+        }
+    }
+
+    pub fn unfocused_only(mut self) -> Self {
+        self.unfocused_only = true;
+        self
+    }
+
+    fn accepts(&self, sink: &str, notification: &Notification, focused: bool) -> bool {
+        let routed = if notification.sinks.is_empty() {
+            notification.severity >= self.min_severity
+                && (self.categories.is_empty() || self.categories.contains(&notification.category))
+        } else {
+            notification.sinks.iter().any(|name| name == sink)
+        };
+        routed && !(self.unfocused_only && focused)
+    }
+}
+
+/// Hands every notification over to the sinks whose routes accept it
+#[derive(Clone, Default)]
+pub struct NotificationDispatcher {
+    sinks: Vec<(Route, Arc<dyn NotificationSink>)>,
+}
+
+impl NotificationDispatcher {
+    pub fn add_sink(&mut self, route: Route, sink: impl NotificationSink + 'static) {
+        self.sinks.push((route, Arc::new(sink)));
+    }
+
+    /// Names of the sinks in the order they were added, without repetitions
+    pub fn sink_names(&self) -> Vec<&'static str> {
+        let mut names = Vec::new();
+        for (_, sink) in &self.sinks {
+            if !names.contains(&sink.name()) {
+                names.push(sink.name());
+            }
+        }
+        names
+    }
+
+    pub fn dispatch(&self, notification: Notification, focused: bool) {
+        log::debug!(
+            "{:?} {:?} notification: {}",
+            notification.severity,
+            notification.category,
+            notification.summary
+        );
+        for (route, sink) in &self.sinks {
+            if route.accepts(sink.name(), &notification, focused) {
+                sink.notify(&notification);
+            }
+        }
+    }
+}
+
+/// Runs `command SUMMARY BODY`, e.g. `notify-send`
+pub struct DesktopSink {
+    command: String,
+}
+
+impl DesktopSink {
+    pub fn new(command: String) -> Self {
+        Self { command }
+    }
+}
+
+impl NotificationSink for DesktopSink {
+    fn name(&self) -> &'static str {
+        "desktop"
+    }
+
+    fn notify(&self, notification: &Notification) {
+        let mut command_line = self.command.split_whitespace();
+        let Some(program) = command_line.next() else {
+            return;
+        };
+        let mut command = tokio::process::Command::new(program);
+        command
+            .args(command_line)
+            .arg(&notification.summary)
+            .arg(&notification.body)
+            // Anything printed would mess up the UI
+            .stdin(Stdio::null())
+            .stdout(Stdio::null())
+            .stderr(Stdio::null());
+        actix::spawn(async move {
+            if let Err(err) = command.status().await {
+                log::warn!("Failed to raise a desktop notification: {err}");
+            }
+        });
+    }
+}
+
+/// Rings the terminal bell
+pub struct BellSink;
+
+impl NotificationSink for BellSink {
+    fn name(&self) -> &'static str {
+        "bell"
+    }
+
+    fn notify(&self, _notification: &Notification) {
+        let mut stdout = std::io::stdout();
+        if let Err(err) = stdout.write_all(b"\x07").and_then(|_| stdout.flush()) {
+            log::warn!("Failed to ring the bell: {err}");
+        }
+    }
+}
+
+/// POSTs the notification as JSON to a plain `http://` URL
+pub struct WebhookSink {
+    url: String,
+}
+
+impl WebhookSink {
+    pub fn new(url: String) -> Self {
+        Self { url }
+    }
+}
+
+#[derive(Serialize)]
+struct WebhookPayload<'a> {
+    #[serde(flatten)]
+    notification: &'a Notification,
+    timestamp: Timestamp,
+}
+
+impl NotificationSink for WebhookSink {
+    fn name(&self) -> &'static str {
+        "webhook"
+    }
+
+    fn notify(&self, notification: &Notification) {
+        let url = match http::parse_url(&self.url) {
+            Ok(url) => url,
+            Err(err) => {
+                log::warn!("Webhook: {err}");
+                return;
+            }
+        };
+        let payload = WebhookPayload {
+            notification,
+            timestamp: Timestamp::now(),
+        };
+        let Ok(body) = serde_json::to_vec(&payload) else {
+            return;
+        };
+        actix::spawn(async move {
+            if let Err(err) = http::post_json(&url, &body).await {
+                log::warn!("Failed to call webhook {url}: {err}");
+            }
+        });
+    }
+}
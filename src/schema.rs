@@ -0,0 +1,70 @@
+//! JSON Schemas of the files fed into the crate, generated from the types they're read into,
+//! so that editors and CI can check the files before the crate reads them.
+
+use eyre::{eyre, Result};
+
+use schemars::schema::RootSchema;
+use schemars::{schema_for, JsonSchema};
+
+use std::collections::BTreeMap;
+use std::str::FromStr;
+
+use strum::{EnumIter, EnumProperty, IntoEnumIterator};
+
+use crate::client::state::{AlertRule, DesiredSensor};
+use crate::config::Config;
+use crate::model::sensor::{Metric, Sensor};
+use crate::model::SensorId;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, EnumIter, EnumProperty)]
+pub enum SchemaKind {
+    /// A sensor as the agent lists it and `dump` writes it
+    #[strum(props(name = "sensor"))]
+    Sensor,
+    #[strum(props(name = "metric"))]
+    Metric,
+    /// What `import` reads
+    #[strum(props(name = "inventory"))]
+    Inventory,
+    /// The alert rules file
+    #[strum(props(name = "alerts"))]
+    Alerts,
+    #[strum(props(name = "config"))]
+    Config,
+}
+
+impl SchemaKind {
+    pub fn name(&self) -> &'static str {
+        self.get_str("name").unwrap_or_default()
+    }
+
+    pub fn schema(&self) -> RootSchema {
+        match self {
+            Self::Sensor => schema_for!(Sensor<Metric>),
+            Self::Metric => schema_for!(Metric),
+            Self::Inventory => schema_for!(InventoryFile),
+            Self::Alerts => schema_for!(Vec<AlertRule>),
+            Self::Config => schema_for!(Config),
+        }
+    }
+}
+
+impl FromStr for SchemaKind {
+    type Err = eyre::Report;
+
+    fn from_str(name: &str) -> Result<Self> {
+        Self::iter().find(|kind| kind.name() == name).ok_or_else(|| {
+            let known: Vec<&str> = Self::iter().map(|kind| kind.name()).collect();
+            eyre!("Unknown schema '{name}', expected one of: {}", known.join(", "))
+        })
+    }
+}
+
+/// Either a plain list of sensors or what `dump` writes, sensors keyed by their ids.
+/// Only described, the inventory is parsed by [`parse_inventory`](crate::client::state::parse_inventory).
+#[derive(JsonSchema)]
+#[schemars(untagged)]
+pub enum InventoryFile {
+    List(Vec<DesiredSensor>),
+    ById(BTreeMap<SensorId, DesiredSensor>),
+}
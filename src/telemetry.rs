@@ -0,0 +1,253 @@
+//! Optional OTLP export of request spans and internal metrics.
+//!
+//! Built with the `otel` feature and activated by setting `OTEL_EXPORTER_OTLP_ENDPOINT`
+//! (the rest of the standard `OTEL_*` variables are honoured by the exporters).
+//! Without either of them every function here is a no-op.
+
+use eyre::Result;
+
+use std::time::SystemTime;
+
+#[cfg(feature = "otel")]
+mod otel {
+    use eyre::Result;
+
+    use opentelemetry::metrics::{Counter, Gauge, Histogram};
+    use opentelemetry::trace::{Span, SpanKind, Status, Tracer};
+    use opentelemetry::{global, KeyValue};
+
+    use opentelemetry_otlp::{MetricExporter, SpanExporter};
+
+    use opentelemetry_sdk::metrics::{PeriodicReader, SdkMeterProvider};
+    use opentelemetry_sdk::trace::TracerProvider;
+    use opentelemetry_sdk::{runtime, Resource};
+
+    use std::collections::HashMap;
+    use std::sync::{Mutex, OnceLock};
+    use std::time::{Duration, SystemTime};
+
+    use crate::build_info;
+    use crate::correlation;
+    use crate::utils::lock_recovering;
+
+    const SERVICE_NAME: &str = "sensor-vision";
+
+    // Replies which never arrive must not pile up
+    const PENDING_REPLY_TTL: Duration = Duration::from_secs(60);
+
+    pub(super) struct Telemetry {
+        tracer_provider: TracerProvider,
+        meter_provider: SdkMeterProvider,
+
+        request_duration: Histogram<f64>,
+        requests: Counter<u64>,
+        mqtt_messages: Counter<u64>,
+        livedata: Counter<u64>,
+        queue_depth: Gauge<u64>,
+
+        // Reply topic -> (request topic, sent at)
+        pending_replies: Mutex<HashMap<String, (String, SystemTime)>>,
+    }
+
+    static TELEMETRY: OnceLock<Telemetry> = OnceLock::new();
+
+    pub(super) fn get() -> Option<&'static Telemetry> {
+        TELEMETRY.get()
+    }
+
+    pub(super) fn init() -> Result<()> {
+        if std::env::var_os("OTEL_EXPORTER_OTLP_ENDPOINT").is_none() {
+            return Ok(());
+        }
+
+        let resource = Resource::new([
+            KeyValue::new("service.name", SERVICE_NAME),
+            KeyValue::new("service.version", build_info::long_version()),
+        ]);
+
+        let tracer_provider = TracerProvider::builder()
+            .with_batch_exporter(SpanExporter::builder().with_tonic().build()?, runtime::Tokio)
+            .with_resource(resource.clone())
+            .build();
+        global::set_tracer_provider(tracer_provider.clone());
+
+        let metric_reader =
+            PeriodicReader::builder(MetricExporter::builder().with_tonic().build()?, runtime::Tokio)
+                .build();
+        let meter_provider = SdkMeterProvider::builder()
+            .with_reader(metric_reader)
+            .with_resource(resource)
+            .build();
+        global::set_meter_provider(meter_provider.clone());
+
+        let meter = global::meter(SERVICE_NAME);
+        let telemetry = Telemetry {
+            tracer_provider,
+            meter_provider,
+            request_duration: meter
+                .f64_histogram("sv.request.duration")
+                .with_unit("ms")
+                .with_description("Time between sending a request and receiving its reply")
+                .build(),
+            requests: meter
+                .u64_counter("sv.requests")
+                .with_description("Requests sent to the broker")
+                .build(),
+            mqtt_messages: meter
+                .u64_counter("sv.mqtt.messages")
+                .with_description("Messages received through the listener connection")
+                .build(),
+            livedata: meter
+                .u64_counter("sv.livedata")
+                .with_description("Livedata values processed by the state")
+                .build(),
+            queue_depth: meter
+                .u64_gauge("sv.queue.depth")
+                .with_description("Number of items waiting in an internal queue")
+                .build(),
+            pending_replies: Mutex::default(),
+        };
+
+        let _ = TELEMETRY.set(telemetry);
+        log::info!("OpenTelemetry export enabled");
+        Ok(())
+    }
+
+    impl Telemetry {
+        pub(super) fn shutdown(&self) {
+            if let Err(err) = self.tracer_provider.shutdown() {
+                log::warn!("Failed to flush spans: {err}");
+            }
+            if let Err(err) = self.meter_provider.shutdown() {
+                log::warn!("Failed to flush metrics: {err}");
+            }
+        }
+
+        pub(super) fn request_sent(&self, topic: &str, reply_topics: &[&str]) {
+            self.requests.add(1, &[]);
+
+            let mut pending = lock_recovering(&self.pending_replies);
+            let now = SystemTime::now();
+            pending.retain(|_, (_, sent_at)| {
+                now.duration_since(*sent_at).unwrap_or_default() < PENDING_REPLY_TTL
+            });
+            for reply_topic in reply_topics {
+                pending.insert(reply_topic.to_string(), (topic.to_owned(), now));
+            }
+        }
+
+        pub(super) fn reply_received(&self, reply_topic: &str, is_error: bool) {
+            let Some((topic, sent_at)) = lock_recovering(&self.pending_replies).remove(reply_topic)
+            else {
+                return;
+            };
+            self.request_completed(&topic, sent_at, is_error);
+        }
+
+        pub(super) fn request_completed(&self, topic: &str, sent_at: SystemTime, is_error: bool) {
+            let outcome = if is_error { "error" } else { "ok" };
+            let duration = SystemTime::now().duration_since(sent_at).unwrap_or_default();
+            self.request_duration.record(
+                duration.as_secs_f64() * 1000.0,
+                &[KeyValue::new("outcome", outcome)],
+            );
+
+            let mut attributes = vec![
+                KeyValue::new("messaging.system", "mqtt"),
+                KeyValue::new("messaging.destination.name", topic.to_owned()),
+                KeyValue::new("outcome", outcome),
+            ];
+            if let Some(correlation_id) = correlation::current() {
+                attributes.push(KeyValue::new("sv.correlation_id", correlation_id.to_string()));
+            }
+
+            let tracer = global::tracer(SERVICE_NAME);
+            let mut span = tracer
+                .span_builder("mqtt request")
+                .with_kind(SpanKind::Client)
+                .with_start_time(sent_at)
+                .with_attributes(attributes)
+                .start(&tracer);
+            if is_error {
+                span.set_status(Status::error("error reply"));
+            }
+            span.end();
+        }
+
+        pub(super) fn mqtt_message_received(&self) {
+            self.mqtt_messages.add(1, &[]);
+        }
+
+        pub(super) fn livedata_processed(&self) {
+            self.livedata.add(1, &[]);
+        }
+
+        pub(super) fn queue_depth(&self, queue: &'static str, depth: usize) {
+            self.queue_depth
+                .record(depth as u64, &[KeyValue::new("queue", queue)]);
+        }
+    }
+}
+
+pub fn init() -> Result<()> {
+    #[cfg(feature = "otel")]
+    otel::init()?;
+    Ok(())
+}
+
+/// Flushes whatever hasn't been exported yet
+pub fn shutdown() {
+    #[cfg(feature = "otel")]
+    if let Some(telemetry) = otel::get() {
+        telemetry.shutdown();
+    }
+}
+
+/// A message was published to `topic`, its reply is expected on any of the `reply_topics`
+#[allow(unused_variables)]
+pub fn request_sent(topic: &str, reply_topics: &[&str]) {
+    #[cfg(feature = "otel")]
+    if let Some(telemetry) = otel::get() {
+        telemetry.request_sent(topic, reply_topics);
+    }
+}
+
+/// A message arrived on `reply_topic`; completes the request waiting for it, if any
+#[allow(unused_variables)]
+pub fn reply_received(reply_topic: &str, is_error: bool) {
+    #[cfg(feature = "otel")]
+    if let Some(telemetry) = otel::get() {
+        telemetry.reply_received(reply_topic, is_error);
+    }
+}
+
+/// A request awaited in place has completed
+#[allow(unused_variables)]
+pub fn request_completed(topic: &str, sent_at: SystemTime, is_error: bool) {
+    #[cfg(feature = "otel")]
+    if let Some(telemetry) = otel::get() {
+        telemetry.request_completed(topic, sent_at, is_error);
+    }
+}
+
+pub fn mqtt_message_received() {
+    #[cfg(feature = "otel")]
+    if let Some(telemetry) = otel::get() {
+        telemetry.mqtt_message_received();
+    }
+}
+
+pub fn livedata_processed() {
+    #[cfg(feature = "otel")]
+    if let Some(telemetry) = otel::get() {
+        telemetry.livedata_processed();
+    }
+}
+
+#[allow(unused_variables)]
+pub fn queue_depth(queue: &'static str, depth: usize) {
+    #[cfg(feature = "otel")]
+    if let Some(telemetry) = otel::get() {
+        telemetry.queue_depth(queue, depth);
+    }
+}
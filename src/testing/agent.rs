@@ -0,0 +1,310 @@
+use serde::Deserialize;
+use serde_json::{json, Value};
+
+use std::collections::BTreeMap;
+
+use strum::IntoEnumIterator;
+
+use uuid::Uuid;
+
+use crate::client::state::MqttScheme;
+use crate::model::protocol::MetricValue;
+use crate::model::sensor::{LinkedMetric, Metric, Sensor, SensorState};
+use crate::model::{ConnectorId, MetricId, SensorId, Timestamp};
+
+/// Inventory of a connector answering the requests of the agent API described by [`MqttScheme`],
+/// with the same reply payloads a TeamViewer IoT agent sends
+#[derive(Default)]
+pub struct FakeAgent {
+    connector_id: ConnectorId,
+    sensors: BTreeMap<SensorId, FakeSensor>,
+    next_id: u128,
+}
+
+struct FakeSensor {
+    name: String,
+    metrics: Vec<Metric>,
+    state: SensorState,
+}
+
+#[derive(Deserialize)]
+struct NameRequest {
+    name: String,
+}
+
+#[derive(Deserialize)]
+struct UpdateRequest {
+    name: String,
+    state: Option<SensorState>,
+}
+
+#[derive(Deserialize)]
+struct MetricsRequest {
+    metrics: Vec<Value>,
+}
+
+type Replies = Vec<(String, String)>;
+
+impl FakeAgent {
+    pub fn new(connector_id: ConnectorId) -> Self {
+        Self {
+            connector_id,
+            ..Self::default()
+        }
+    }
+
+    fn new_id<Id: From<Uuid>>(&mut self) -> Id {
+        self.next_id += 1;
+        Uuid::from_u128(self.next_id).into()
+    }
+
+    pub fn add_sensor(&mut self, name: &str, metrics: Vec<Metric>) -> SensorId {
+        let sensor_id = self.new_id();
+        let metrics = metrics
+            .into_iter()
+            .map(|metric| self.bind_metric(metric))
+            .collect();
+        self.sensors.insert(
+            sensor_id,
+            FakeSensor {
+                name: name.to_owned(),
+                metrics,
+                state: SensorState::default(),
+            },
+        );
+        sensor_id
+    }
+
+    /// Adds a metric to the sensor without telling anyone, `None` if there is no such sensor
+    pub fn add_metric(&mut self, sensor_id: SensorId, metric: Metric) -> Option<MetricId> {
+        let metric = self.bind_metric(metric);
+        let metric_id = *metric.metric_id();
+        self.sensors.get_mut(&sensor_id)?.metrics.push(metric);
+        Some(metric_id)
+    }
+
+    pub fn sensors(&self) -> Vec<(SensorId, String, Vec<Metric>)> {
+        self.sensors
+            .iter()
+            .map(|(sensor_id, sensor)| (*sensor_id, sensor.name.clone(), sensor.metrics.clone()))
+            .collect()
+    }
+
+    /// Same metric with a newly assigned id
+    fn bind_metric(&mut self, metric: Metric) -> Metric {
+        let metric_id: MetricId = self.new_id();
+        let mut bound = serde_json::to_value(metric.detached()).unwrap_or_default();
+        bound["metricId"] = json!(metric_id);
+        // Going through a string, the ids don't deserialize from a `Value` inside an untagged enum
+        serde_json::from_str(&bound.to_string()).unwrap_or(metric)
+    }
+
+    fn full_topic(&self, topic: &str) -> String {
+        format!("/v1.0/{}/{}", self.connector_id, topic)
+    }
+
+    /// The livedata event subscribers of the sensor receive
+    pub fn livedata(
+        &self,
+        sensor_id: SensorId,
+        values: &[(MetricId, MetricValue)],
+        timestamp: Timestamp,
+    ) -> (String, String) {
+        let metrics: Vec<Value> = values
+            .iter()
+            .map(|(metric_id, value)| json!({"metricId": metric_id, "value": value}))
+            .collect();
+        (
+            self.full_topic(&format!("sensor/{sensor_id}/livedata")),
+            json!({"metrics": metrics, "timestamp": timestamp}).to_string(),
+        )
+    }
+
+    /// Replies to a message published on `topic`, if it's a request of the agent API
+    pub fn handle(&mut self, topic: &str, payload: &str) -> Replies {
+        let prefix = self.full_topic("");
+        let Some(short_topic) = topic.strip_prefix(&prefix) else {
+            return Vec::new();
+        };
+        let (ids, pattern) = MqttScheme::extract_ids_and_pattern(short_topic);
+        let Some(scheme) = MqttScheme::iter().find(|scheme| scheme.get_templates().0 == pattern)
+        else {
+            return Vec::new();
+        };
+        let (_, response, error) = scheme.get_templates();
+        let render = |template: &str| {
+            let ids: Vec<String> = ids.iter().map(String::from).collect();
+            self.full_topic(&MqttScheme::render_topic(template, &ids))
+        };
+        let (response_topic, error_topic) = (render(response), render(error));
+
+        let sensor_id = ids.first().copied().map(SensorId::from);
+        let mut replies = Vec::new();
+        let result = match scheme {
+            MqttScheme::SensorList => Ok(self.list()),
+            MqttScheme::SensorCreate => self.create_sensor(payload),
+            MqttScheme::SensorUpdate(..) => self.update_sensor(sensor_id, payload),
+            MqttScheme::SensorDelete(..) => self.delete_sensor(sensor_id),
+            MqttScheme::MetricDescribe(..) => self.describe_metric(sensor_id, ids.get(1).copied().map(MetricId::from)),
+            MqttScheme::MetricCreate(..) => self.create_metrics(sensor_id, payload),
+            MqttScheme::MetricUpdate(..) => self.update_metrics(sensor_id, payload),
+            MqttScheme::MetricDelete(..) => self.delete_metrics(sensor_id, payload),
+            MqttScheme::PushValues(..) => self.push_values(sensor_id, payload, &mut replies),
+            MqttScheme::Ping => serde_json::from_str::<Value>(payload)
+                .map(|request| json!({"answer": request["request"]}).to_string())
+                .map_err(|err| err.to_string()),
+        };
+        match result {
+            Ok(reply) => replies.insert(0, (response_topic, reply)),
+            Err(message) => {
+                let reply = json!({"errorMessage": message, "errorcode": 404}).to_string();
+                replies.insert(0, (error_topic, reply));
+            }
+        }
+        replies
+    }
+
+    fn sensor_mut(&mut self, sensor_id: Option<SensorId>) -> Result<&mut FakeSensor, String> {
+        sensor_id
+            .and_then(|sensor_id| self.sensors.get_mut(&sensor_id))
+            .ok_or_else(|| String::from("Sensor not found."))
+    }
+
+    fn list(&self) -> String {
+        let sensors: Vec<Sensor<LinkedMetric>> = self
+            .sensors
+            .iter()
+            .map(|(sensor_id, sensor)| Sensor {
+                name: sensor.name.clone(),
+                sensor_id: *sensor_id,
+                metrics: sensor
+                    .metrics
+                    .iter()
+                    .map(|metric| LinkedMetric {
+                        link: format!("sensor/{sensor_id}/metric/{}", metric.metric_id()),
+                        metric_id: *metric.metric_id(),
+                    })
+                    .collect(),
+                state: sensor.state,
+                connector_id: self.connector_id,
+            })
+            .collect();
+        serde_json::to_string(&sensors).unwrap_or_default()
+    }
+
+    fn create_sensor(&mut self, payload: &str) -> Result<String, String> {
+        let NameRequest { name } = serde_json::from_str(payload).map_err(|err| err.to_string())?;
+        let sensor_id = self.add_sensor(&name, Vec::new());
+        Ok(json!({"name": name, "sensorId": sensor_id, "metrics": []}).to_string())
+    }
+
+    fn update_sensor(&mut self, sensor_id: Option<SensorId>, payload: &str) -> Result<String, String> {
+        let UpdateRequest { name, state } =
+            serde_json::from_str(payload).map_err(|err| err.to_string())?;
+        let sensor = self.sensor_mut(sensor_id)?;
+        sensor.name = name;
+        if let Some(state) = state {
+            sensor.state = state;
+        }
+        Ok(String::from("Sensor was changed."))
+    }
+
+    fn delete_sensor(&mut self, sensor_id: Option<SensorId>) -> Result<String, String> {
+        sensor_id
+            .and_then(|sensor_id| self.sensors.remove(&sensor_id))
+            .map(|_| String::from("Sensor was deleted."))
+            .ok_or_else(|| String::from("Sensor not found."))
+    }
+
+    fn describe_metric(
+        &mut self,
+        sensor_id: Option<SensorId>,
+        metric_id: Option<MetricId>,
+    ) -> Result<String, String> {
+        let sensor = self.sensor_mut(sensor_id)?;
+        sensor
+            .metrics
+            .iter()
+            .find(|metric| Some(*metric.metric_id()) == metric_id)
+            .map(|metric| serde_json::to_string(metric).unwrap_or_default())
+            .ok_or_else(|| String::from("Metric not found."))
+    }
+
+    fn create_metrics(&mut self, sensor_id: Option<SensorId>, payload: &str) -> Result<String, String> {
+        let MetricsRequest { metrics } =
+            serde_json::from_str(payload).map_err(|err| err.to_string())?;
+        self.sensor_mut(sensor_id)?;
+
+        let mut created = Vec::new();
+        let mut bound_metrics = Vec::new();
+        for mut metric in metrics {
+            let matching_id = metric["matchingId"].clone();
+            metric["metricId"] = json!(MetricId::default());
+            let metric: Metric =
+                serde_json::from_str(&metric.to_string()).map_err(|err| err.to_string())?;
+            let metric = self.bind_metric(metric);
+            created.push(json!({"matchingId": matching_id, "metricId": metric.metric_id()}));
+            bound_metrics.push(metric);
+        }
+        self.sensor_mut(sensor_id)?.metrics.extend(bound_metrics);
+        Ok(Value::from(created).to_string())
+    }
+
+    fn update_metrics(&mut self, sensor_id: Option<SensorId>, payload: &str) -> Result<String, String> {
+        let MetricsRequest { metrics } =
+            serde_json::from_str(payload).map_err(|err| err.to_string())?;
+        let sensor = self.sensor_mut(sensor_id)?;
+        for update in metrics {
+            let metric = sensor
+                .metrics
+                .iter_mut()
+                .find(|metric| json!(metric.metric_id()) == update["metricId"])
+                .ok_or_else(|| String::from("Metric not found."))?;
+            if let Some(name) = update["name"].as_str() {
+                metric.rename(name.to_owned());
+            }
+            if let (Metric::Custom { value_annotation, .. }, Some(annotation)) =
+                (metric, update["valueAnnotation"].as_str())
+            {
+                *value_annotation = annotation.to_owned();
+            }
+        }
+        Ok(String::from("All metrics were successfully modified."))
+    }
+
+    fn delete_metrics(&mut self, sensor_id: Option<SensorId>, payload: &str) -> Result<String, String> {
+        let MetricsRequest { metrics } =
+            serde_json::from_str(payload).map_err(|err| err.to_string())?;
+        let sensor = self.sensor_mut(sensor_id)?;
+        sensor.metrics.retain(|metric| {
+            !metrics
+                .iter()
+                .any(|deleted| json!(metric.metric_id()) == deleted["metricId"])
+        });
+        Ok(String::from("All metrics were successfully deleted."))
+    }
+
+    /// Pushed values come back as livedata, as they do to every subscriber of the sensor
+    fn push_values(
+        &mut self,
+        sensor_id: Option<SensorId>,
+        payload: &str,
+        replies: &mut Replies,
+    ) -> Result<String, String> {
+        let MetricsRequest { metrics } =
+            serde_json::from_str(payload).map_err(|err| err.to_string())?;
+        self.sensor_mut(sensor_id)?;
+        let sensor_id = sensor_id.unwrap_or_default();
+
+        let mut values = Vec::new();
+        for pushed in metrics {
+            let metric_id: MetricId =
+                serde_json::from_value(pushed["metricId"].clone()).map_err(|err| err.to_string())?;
+            let value: MetricValue =
+                serde_json::from_value(pushed["value"].clone()).map_err(|err| err.to_string())?;
+            values.push((metric_id, value));
+        }
+        replies.push(self.livedata(sensor_id, &values, Timestamp::now()));
+        Ok(String::from("Values were pushed."))
+    }
+}
@@ -0,0 +1,303 @@
+use eyre::{eyre, Result};
+
+use std::sync::{Arc, Mutex};
+
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::tcp::OwnedReadHalf;
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::mpsc;
+
+use crate::client::mqtt::topic_matches;
+use crate::config::BrokerConfig;
+use crate::model::protocol::MetricValue;
+use crate::model::sensor::Metric;
+use crate::model::{ConnectorId, MetricId, SensorId, Timestamp};
+use crate::testing::FakeAgent;
+use crate::utils::lock_recovering;
+
+const CONNECT: u8 = 1;
+const PUBLISH: u8 = 3;
+const PUBACK: u8 = 4;
+const SUBSCRIBE: u8 = 8;
+const SUBACK: u8 = 9;
+const UNSUBSCRIBE: u8 = 10;
+const UNSUBACK: u8 = 11;
+const PINGREQ: u8 = 12;
+const DISCONNECT: u8 = 14;
+
+/// In-process MQTT 3.1.1 broker on a random local port, speaking plain TCP.
+///
+/// Only what the client relies on is supported: QoS 0 and 1 publishes (delivered as QoS 0),
+/// subscriptions with `+` and `#` wildcards and keepalive pings. Messages published to the
+/// agent API of the connector are answered by a [`FakeAgent`] the way a TeamViewer IoT agent does.
+pub struct FakeBroker {
+    port: u16,
+    state: Arc<Mutex<BrokerState>>,
+}
+
+#[derive(Default)]
+struct BrokerState {
+    sessions: Vec<Session>,
+    next_session_id: usize,
+    agent: FakeAgent,
+    /// Every message published by the clients, in order
+    published: Vec<(String, String)>,
+}
+
+struct Session {
+    id: usize,
+    filters: Vec<String>,
+    outbox: mpsc::UnboundedSender<Vec<u8>>,
+}
+
+impl FakeBroker {
+    pub async fn start(connector_id: ConnectorId) -> Result<Self> {
+        let listener = TcpListener::bind("127.0.0.1:0").await?;
+        let port = listener.local_addr()?.port();
+        let state = Arc::new(Mutex::new(BrokerState {
+            agent: FakeAgent::new(connector_id),
+            ..BrokerState::default()
+        }));
+
+        let accepting_state = state.clone();
+        tokio::spawn(async move {
+            while let Ok((stream, _)) = listener.accept().await {
+                let state = accepting_state.clone();
+                tokio::spawn(async move {
+                    if let Err(err) = serve(stream, state).await {
+                        log::debug!("Fake broker session ended: {err}");
+                    }
+                });
+            }
+        });
+
+        Ok(Self { port, state })
+    }
+
+    pub fn port(&self) -> u16 {
+        self.port
+    }
+
+    /// Points the client to this broker, with the requests failing fast
+    pub fn broker_config(&self) -> BrokerConfig {
+        BrokerConfig {
+            host: String::from("127.0.0.1"),
+            port: self.port,
+            cert_port: self.port,
+            keepalive_secs: 30,
+            request_timeout_secs: 5,
+            // Nothing to spare the fake agent from
+            describe_batch_size: 0,
+            describe_interval_ms: 0,
+            tls: false,
+        }
+    }
+
+    /// Adds a sensor to the inventory of the agent, as if another client created it
+    pub fn add_sensor(&self, name: &str, metrics: Vec<Metric>) -> SensorId {
+        lock_recovering(&self.state).agent.add_sensor(name, metrics)
+    }
+
+    /// Adds a metric to the sensor as if another client created it, its event lost on the way
+    pub fn add_metric(&self, sensor_id: SensorId, metric: Metric) -> Option<MetricId> {
+        lock_recovering(&self.state).agent.add_metric(sensor_id, metric)
+    }
+
+    /// Sensors the agent knows of, with their metrics
+    pub fn sensors(&self) -> Vec<(SensorId, String, Vec<Metric>)> {
+        lock_recovering(&self.state).agent.sensors()
+    }
+
+    /// Publishes livedata of the metric as if another client pushed the value
+    pub fn push_livedata(&self, sensor_id: SensorId, metric_id: MetricId, value: MetricValue) {
+        let mut state = lock_recovering(&self.state);
+        let (topic, payload) =
+            state
+                .agent
+                .livedata(sensor_id, &[(metric_id, value)], Timestamp::now());
+        state.route(&topic, &payload);
+    }
+
+    /// Every message the clients have published so far, as topic and payload
+    pub fn published(&self) -> Vec<(String, String)> {
+        lock_recovering(&self.state).published.clone()
+    }
+}
+
+impl BrokerState {
+    /// Delivers the message to every session subscribed to the topic
+    fn route(&mut self, topic: &str, payload: &str) {
+        let packet = publish_packet(topic, payload);
+        self.sessions.retain(|session| {
+            if !session.filters.iter().any(|filter| topic_matches(filter, topic)) {
+                return true;
+            }
+            session.outbox.send(packet.clone()).is_ok()
+        });
+    }
+
+    fn publish(&mut self, topic: String, payload: String) {
+        self.route(&topic, &payload);
+        let replies = self.agent.handle(&topic, &payload);
+        self.published.push((topic, payload));
+        for (topic, payload) in replies {
+            self.route(&topic, &payload);
+        }
+    }
+
+    fn session(&mut self, id: usize) -> Option<&mut Session> {
+        self.sessions.iter_mut().find(|session| session.id == id)
+    }
+}
+
+async fn serve(stream: TcpStream, state: Arc<Mutex<BrokerState>>) -> Result<()> {
+    let (mut reader, mut writer) = stream.into_split();
+    let (outbox, mut outgoing) = mpsc::unbounded_channel::<Vec<u8>>();
+
+    tokio::spawn(async move {
+        while let Some(packet) = outgoing.recv().await {
+            if writer.write_all(&packet).await.is_err() {
+                break;
+            }
+        }
+    });
+
+    let session_id = {
+        let mut state = lock_recovering(&state);
+        state.next_session_id += 1;
+        let id = state.next_session_id;
+        state.sessions.push(Session {
+            id,
+            filters: Vec::new(),
+            outbox: outbox.clone(),
+        });
+        id
+    };
+
+    let served = async {
+        loop {
+            let (header, body) = read_packet(&mut reader).await?;
+            let mut body = Body(&body);
+            match header >> 4 {
+                CONNECT => outbox.send(vec![0x20, 0x02, 0x00, 0x00])?,
+                PUBLISH => {
+                    let qos = (header >> 1) & 0x03;
+                    let topic = body.string()?;
+                    if qos > 0 {
+                        let packet_id = body.u16()?;
+                        outbox.send(ack_packet(PUBACK, packet_id))?;
+                    }
+                    let payload = String::from_utf8_lossy(body.0).to_string();
+                    lock_recovering(&state).publish(topic, payload);
+                }
+                PUBACK => {}
+                SUBSCRIBE => {
+                    let packet_id = body.u16()?;
+                    let mut filters = Vec::new();
+                    while !body.0.is_empty() {
+                        filters.push(body.string()?);
+                        body.u8()?;
+                    }
+                    let mut suback = vec![SUBACK << 4];
+                    suback.extend(remaining_length(2 + filters.len()));
+                    suback.extend(packet_id.to_be_bytes());
+                    // Every subscription is granted QoS 0
+                    suback.extend(vec![0x00; filters.len()]);
+                    if let Some(session) = lock_recovering(&state).session(session_id) {
+                        session.filters.extend(filters);
+                    }
+                    outbox.send(suback)?;
+                }
+                UNSUBSCRIBE => {
+                    let packet_id = body.u16()?;
+                    let mut filters = Vec::new();
+                    while !body.0.is_empty() {
+                        filters.push(body.string()?);
+                    }
+                    if let Some(session) = lock_recovering(&state).session(session_id) {
+                        session.filters.retain(|filter| !filters.contains(filter));
+                    }
+                    outbox.send(ack_packet(UNSUBACK, packet_id))?;
+                }
+                PINGREQ => outbox.send(vec![0xD0, 0x00])?,
+                DISCONNECT => return Ok(()),
+                packet_type => return Err(eyre!("Unsupported packet type {packet_type}")),
+            }
+        }
+    };
+    let result: Result<()> = served.await;
+
+    lock_recovering(&state)
+        .sessions
+        .retain(|session| session.id != session_id);
+    result
+}
+
+async fn read_packet(reader: &mut OwnedReadHalf) -> Result<(u8, Vec<u8>)> {
+    let header = reader.read_u8().await?;
+    let mut length = 0usize;
+    for shift in (0..28).step_by(7) {
+        let byte = reader.read_u8().await?;
+        length |= ((byte & 0x7F) as usize) << shift;
+        if byte & 0x80 == 0 {
+            break;
+        }
+    }
+    let mut body = vec![0; length];
+    reader.read_exact(&mut body).await?;
+    Ok((header, body))
+}
+
+/// Cursor over the variable header and the payload of a packet
+struct Body<'a>(&'a [u8]);
+
+impl Body<'_> {
+    fn u8(&mut self) -> Result<u8> {
+        let (&byte, rest) = self.0.split_first().ok_or_else(|| eyre!("Truncated packet"))?;
+        self.0 = rest;
+        Ok(byte)
+    }
+
+    fn u16(&mut self) -> Result<u16> {
+        Ok(u16::from_be_bytes([self.u8()?, self.u8()?]))
+    }
+
+    fn string(&mut self) -> Result<String> {
+        let length = self.u16()? as usize;
+        if self.0.len() < length {
+            return Err(eyre!("Truncated packet"));
+        }
+        let (string, rest) = self.0.split_at(length);
+        self.0 = rest;
+        Ok(String::from_utf8_lossy(string).to_string())
+    }
+}
+
+fn remaining_length(mut length: usize) -> Vec<u8> {
+    let mut bytes = Vec::new();
+    loop {
+        let mut byte = (length % 128) as u8;
+        length /= 128;
+        if length > 0 {
+            byte |= 0x80;
+        }
+        bytes.push(byte);
+        if length == 0 {
+            return bytes;
+        }
+    }
+}
+
+fn ack_packet(packet_type: u8, packet_id: u16) -> Vec<u8> {
+    let [high, low] = packet_id.to_be_bytes();
+    vec![packet_type << 4, 0x02, high, low]
+}
+
+fn publish_packet(topic: &str, payload: &str) -> Vec<u8> {
+    let mut packet = vec![PUBLISH << 4];
+    packet.extend(remaining_length(2 + topic.len() + payload.len()));
+    packet.extend((topic.len() as u16).to_be_bytes());
+    packet.extend(topic.as_bytes());
+    packet.extend(payload.as_bytes());
+    packet
+}
@@ -0,0 +1,89 @@
+use actix::{Actor, Addr};
+
+use eyre::Result;
+
+use crate::client::client::SensorVisionClient;
+use crate::client::mqtt::MqttCredentials;
+use crate::config::{PushConfig, StateConfig};
+use crate::model::sensor::{Metric, Sensor, SensorState, ValueType};
+use crate::model::{ConnectorId, MetricId, SensorId};
+use crate::testing::FakeBroker;
+
+use std::path::{Path, PathBuf};
+
+/// Connector of the fake agents the tests start
+pub fn connector_id() -> ConnectorId {
+    "6d69c58223fb44a7b76ae61a18faf37c".into()
+}
+
+/// Started client of a broker faking [`connector_id`], with the default push config
+pub async fn connect(broker: &FakeBroker) -> Result<Addr<SensorVisionClient>> {
+    connect_with_push(broker, PushConfig::default()).await
+}
+
+pub async fn connect_with_push(broker: &FakeBroker, push: PushConfig) -> Result<Addr<SensorVisionClient>> {
+    let client = SensorVisionClient::with_credentials(
+        connector_id(),
+        &broker.broker_config(),
+        &MqttCredentials::default(),
+        &StateConfig::default(),
+    )
+    .await?;
+    Ok(client.with_push(push).start())
+}
+
+/// Double metric with a plain `unit` annotation, the id given as 32 hex digits
+pub fn metric(metric_id: &str, name: &str) -> Metric {
+    Metric::Custom {
+        name: String::from(name),
+        metric_id: MetricId::from(metric_id),
+        value_annotation: String::from("unit"),
+        value_type: ValueType::Double,
+    }
+}
+
+/// Enabled sensor of the default connector, the id given as 32 hex digits
+pub fn sensor(sensor_id: &str, name: &str, metrics: Vec<Metric>) -> Sensor<Metric> {
+    Sensor {
+        name: String::from(name),
+        sensor_id: SensorId::from(sensor_id),
+        metrics: metrics.into_iter().collect(),
+        state: SensorState::Enabled,
+        connector_id: Default::default(),
+    }
+}
+
+/// File in a directory of its own under the temp dir, the directory removed on drop along
+/// with whatever was written next to the file (backups, SQLite journals)
+pub struct TempFile {
+    dir: PathBuf,
+    path: PathBuf,
+}
+
+impl TempFile {
+    /// Not created yet, `name` having to be unique within the test binary
+    pub fn new(name: &str) -> Self {
+        let dir = std::env::temp_dir().join(format!("sensor-vision-{}-{name}", std::process::id()));
+        // Left by an earlier run that got killed
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).expect("a temp dir");
+        let path = dir.join(name);
+        Self { dir, path }
+    }
+
+    pub fn with_contents(name: &str, contents: &str) -> Self {
+        let file = Self::new(name);
+        std::fs::write(&file.path, contents).expect("a temp file");
+        file
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+}
+
+impl Drop for TempFile {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.dir);
+    }
+}
@@ -0,0 +1,179 @@
+use actix::{Actor, Addr, Context, Handler, Message, MessageResult};
+
+use eyre::Result;
+
+use crate::client::mqtt::{MqttEvent, MqttMessage};
+use crate::client::state::queries::GetStateSnapshot;
+use crate::client::state::{
+    AlertRecord, AlertRule, GetAlertHistory, MqttScheme, SensorStateEvent, Sensors, SensorsStateActor, SetAlertRules,
+    SilenceAlerts, SubscribeToStateEvents,
+};
+use crate::model::protocol::MetricValue;
+use crate::model::{ConnectorId, MetricId, SensorId, Timestamp};
+use crate::testing::FakeAgent;
+
+use std::path::{Path, PathBuf};
+
+/// Drives a [`SensorsStateActor`] with hand-picked MQTT messages, no broker involved.
+///
+/// Every injected message is processed before the injecting call returns, and the events it
+/// emitted are recorded in order, so that the same messages always lead to the same events.
+/// Replies are produced by a [`FakeAgent`], but it's up to the test when, and in which order,
+/// they reach the state.
+pub struct SensorStateHarness {
+    agent: FakeAgent,
+    connector_id: ConnectorId,
+    state: Addr<SensorsStateActor>,
+    recorder: Addr<EventRecorder>,
+    history_file: Option<PathBuf>,
+}
+
+impl SensorStateHarness {
+    /// Must be called from within an actix system
+    pub async fn start(connector_id: ConnectorId) -> Result<Self> {
+        Self::start_with(connector_id, SensorsStateActor::new()?, None).await
+    }
+
+    /// Keeps the history in `history_file`, so that it outlives a [`SensorStateHarness::restart`]
+    pub async fn start_with_history(connector_id: ConnectorId, history_file: &Path) -> Result<Self> {
+        let state = SensorsStateActor::with_history(history_file)?;
+        Self::start_with(connector_id, state, Some(history_file.to_owned())).await
+    }
+
+    async fn start_with(
+        connector_id: ConnectorId,
+        state: SensorsStateActor,
+        history_file: Option<PathBuf>,
+    ) -> Result<Self> {
+        let state = state.start();
+        let recorder = EventRecorder::default().start();
+        state
+            .send(SubscribeToStateEvents(recorder.clone().recipient().downgrade()))
+            .await?;
+        Ok(Self {
+            agent: FakeAgent::new(connector_id),
+            connector_id,
+            state,
+            recorder,
+            history_file,
+        })
+    }
+
+    /// Replaces the state with a fresh one, empty but for the history, as a restarted client
+    /// starts. The agent keeps its inventory.
+    pub async fn restart(&mut self) -> Result<()> {
+        let state = match &self.history_file {
+            Some(history_file) => SensorsStateActor::with_history(history_file)?,
+            None => SensorsStateActor::new()?,
+        }
+        .start();
+        state
+            .send(SubscribeToStateEvents(self.recorder.clone().recipient().downgrade()))
+            .await?;
+        self.state = state;
+        Ok(())
+    }
+
+    /// Inventory of the agent, to seed it or to change it behind the back of the state
+    pub fn agent(&mut self) -> &mut FakeAgent {
+        &mut self.agent
+    }
+
+    /// Replies of the agent to the request, in the order it sends them, not injected yet
+    pub fn request(&mut self, scheme: MqttScheme, payload: &str) -> Vec<MqttMessage> {
+        let topic = format!("/v1.0/{}/{}", self.connector_id, scheme.get_topics().0);
+        self.agent
+            .handle(&topic, payload)
+            .into_iter()
+            .map(|(topic, message)| MqttMessage {
+                topic,
+                message,
+                correlation_id: None,
+            })
+            .collect()
+    }
+
+    /// Sends the request and injects the replies as they come
+    pub async fn exchange(&mut self, scheme: MqttScheme, payload: &str) -> Result<()> {
+        for reply in self.request(scheme, payload) {
+            self.inject(reply).await?;
+        }
+        Ok(())
+    }
+
+    /// Processes the message as if the listener received it
+    pub async fn inject(&self, message: MqttMessage) -> Result<()> {
+        self.state.send(MqttEvent(message)).await?;
+        Ok(())
+    }
+
+    /// Livedata of the sensor, as another client pushing the values would cause
+    pub async fn livedata(
+        &self,
+        sensor_id: SensorId,
+        values: &[(MetricId, MetricValue)],
+        timestamp: Timestamp,
+    ) -> Result<()> {
+        let (topic, message) = self.agent.livedata(sensor_id, values, timestamp);
+        self.inject(MqttMessage {
+            topic,
+            message,
+            correlation_id: None,
+        })
+        .await
+    }
+
+    /// Events emitted since the previous call, in order
+    pub async fn take_events(&self) -> Result<Vec<SensorStateEvent>> {
+        // Queued after the events of every processed message
+        Ok(self.recorder.send(TakeEvents).await?)
+    }
+
+    /// Replaces the alert rules the livedata is checked against
+    pub async fn set_alert_rules(&self, rules: Vec<AlertRule>) -> Result<()> {
+        self.state.send(SetAlertRules(rules)).await?
+    }
+
+    /// Silences every alert until `until`, or lifts the silence
+    pub async fn silence_alerts(&self, until: Option<Timestamp>) -> Result<()> {
+        self.state.send(SilenceAlerts(until)).await?
+    }
+
+    /// Alerts in force and the ones resolved since `since`, oldest first
+    pub async fn alert_history(&self, since: Timestamp) -> Result<Vec<AlertRecord>> {
+        self.state.send(GetAlertHistory { since }).await?
+    }
+
+    pub async fn snapshot(&self) -> Result<Sensors> {
+        self.state.send(GetStateSnapshot).await?
+    }
+}
+
+#[derive(Default)]
+struct EventRecorder {
+    events: Vec<SensorStateEvent>,
+}
+
+impl Actor for EventRecorder {
+    type Context = Context<Self>;
+}
+
+impl Handler<SensorStateEvent> for EventRecorder {
+    type Result = ();
+
+    fn handle(&mut self, event: SensorStateEvent, _: &mut Self::Context) -> Self::Result {
+        self.events.push(event);
+    }
+}
+
+#[derive(Message)]
+#[rtype(result = "Vec<SensorStateEvent>")]
+struct TakeEvents;
+
+impl Handler<TakeEvents> for EventRecorder {
+    type Result = MessageResult<TakeEvents>;
+
+    fn handle(&mut self, _: TakeEvents, _: &mut Self::Context) -> Self::Result {
+        MessageResult(std::mem::take(&mut self.events))
+    }
+}
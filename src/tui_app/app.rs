@@ -1,34 +1,86 @@
-use actix::{Actor, Addr, AsyncContext, Context, Handler, Message, StreamHandler, WrapFuture};
-use crossterm::event::{Event as CrosstermEvent, KeyCode, KeyEvent, KeyEventKind};
-use std::sync::atomic::Ordering;
+use actix::{Actor, ActorFutureExt, Addr, AsyncContext, Context, Handler, Message, StreamHandler, WrapFuture};
+use crossterm::event::{Event as CrosstermEvent, KeyEvent, KeyEventKind};
+use std::cell::Cell;
+use std::fmt::Display;
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
 use std::sync::Arc;
+use std::time::{Duration, Instant, SystemTime};
 
-use eyre::Result;
+use eyre::{eyre, Result};
 
 use futures::StreamExt;
 
 use tokio::sync::mpsc;
 use tokio::sync::oneshot;
-use tokio::sync::Mutex;
 
-use crate::client::client::SensorVisionClient;
+use crate::client::client::{load_inventory, Correlated, SensorVisionClient};
+use crate::client::exchanges::{self, Exchange};
 use crate::client::client_queries::{
-    CreateMetrics, CreateSensor, DeleteMetric, DeleteSensor, LoadSensors, PushValue, UpdateMetric,
-    UpdateSensor,
+    CloneSensor, CreateMetrics, CreateSensor, DeleteMetric, DeleteSensor, DescribeMetrics, DumpSensors, GetConnectorId,
+    ImportSensors, IsReloadPending,
+    LoadSensors, MetricOrder, PingTest, PushValue, RetryRequest, UpdateMetric, UpdateSensor,
+};
+use crate::client::simulator::{
+    Generator, GetSimulation, SimulatorActor, StartSimulation, StopSimulation,
+};
+use crate::client::mqtt::{ConnectionEvent, Reconnect, SubscribeToConnectionEvents};
+use crate::client::state::queries::{
+    GetStateSnapshot, GetStateVersion, GetVersionedStateSnapshot,
+};
+use crate::client::state::{
+    diff_inventories, summarize_import, AlertRule, GetAlertHistory, GetAlertRules, GetSilencing, SensorStateEvent,
+    Sensors, SetAlertRules, SilenceAlerts, SubscribeToStateEvents,
+};
+use crate::config::{
+    read_muted_sensors, read_region_sizes, write_alert_rules, write_log_levels, write_muted_sensors,
+    write_region_sizes, NotificationsConfig, DEFAULT_MAILBOX_CAPACITY,
+};
+use crate::correlation::{self, CorrelationId};
+use crate::logging;
+use crate::i18n::Msg;
+use crate::model::format::ValueFormat;
+use crate::model::sensor::{split_target, Metric, ValueType, ValueUnit};
+use crate::model::{MetricId, SensorId, Timestamp};
+use crate::telemetry;
+use crate::notifications::{
+    BellSink, Category, DesktopSink, Notification, NotificationDispatcher, NotificationSink, Route,
+    Severity, WebhookSink,
 };
-use crate::client::state::queries::GetStateSnapshot;
-use crate::client::state::{SensorStateEvent, Sensors, SubscribeToStateEvents};
-use crate::model::sensor::{Metric, ValueType, ValueUnit};
 use crate::tui_app::dialog::{
-    ConfirmationDialogActor, ConfirmationDialogState, DialogButton, DialogResult, InputDialogActor,
-    InputDialogState, MetricDialogActor, MetricDialogState, ModalDialog,
+    AlertDialogActor, AlertDialogState, AlertHistoryDialogActor, AlertHistoryDialogState, ConfirmationDialogActor, ConfirmationDialogState, DialogButton, DialogResult, DialogTasks,
+    ErrorsDialogActor,
+    ErrorsDialogState, HelpDialogActor,
+    HelpDialogState, InputDialogActor,
+    InputDialogState, JsonViewerDialogActor, JsonViewerDialogState, LogFilterDialogActor,
+    LogFilterDialogState, MessageDialogActor, MessageDialogState, MetricDialogActor, MetricDialogState, ModalDialog, PathDialogActor,
+    PathDialogState,
 };
-use crate::tui_app::tui::{SharedTui, Tui};
+use crate::tui_app::keymap::{self, Action};
+use crate::tui_app::renderer::Renderer;
+use crate::tui_app::tui::Tui;
+use crate::tui_app::utils;
 use crate::tui_app::ui_state::queries::*;
 use crate::tui_app::ui_state::render::Render;
-use crate::tui_app::ui_state::UIState;
+use crate::tui_app::ui_state::{
+    ErrorEntry, MetricLayout, Screensaver, UIState, PING_FAILURES_WARNING,
+};
 
 use crate::tui_app::theme::THEME_INDEX;
+use crate::utils::{duration_secs, fuzzy_match};
+
+const CURRENT_STATE_ATTEMPTS: usize = 5;
+
+const DEFAULT_INVENTORY_FILE: &str = "sensors.json";
+
+const DEFAULT_SIMULATION: &str = "sine:10:60@1";
+
+const STARTUP_SELECTION_TIMEOUT: Duration = Duration::from_secs(10);
+const STARTUP_SELECTION_POLL_INTERVAL: Duration = Duration::from_millis(250);
+
+/// How far back the alert history goes, alerts still in force are shown regardless
+const ALERT_HISTORY_SPAN: Duration = Duration::from_secs(7 * 24 * 3600);
 
 #[derive(Message)]
 #[rtype(result = "()")]
@@ -37,24 +89,367 @@ pub struct RunLoop {
     pub tui: Tui,
 }
 
+const SCREENSAVER_PAGE_DURATION: Duration = Duration::from_secs(10);
+
+const CLOCK_CHECK_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Pings unanswered for this long, or for the ping interval if shorter, count as failed
+const PING_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// The monotonic clock stands still while the system sleeps, unlike the wall clock.
+/// Smaller differences are clock adjustments (NTP) rather than sleep.
+const SLEEP_THRESHOLD: Duration = Duration::from_secs(30);
+
+/// Position of the candidate whose id is `query`, or whose name is `query` ignoring case,
+/// or else the shortest name fuzzy-matching `query`
+fn best_match<'a, Id: Display>(
+    query: &str,
+    candidates: impl Iterator<Item = (Id, &'a str)>,
+) -> Option<usize> {
+    let candidates: Vec<(Id, &str)> = candidates.collect();
+    candidates
+        .iter()
+        .position(|(id, _)| id.to_string() == query)
+        .or_else(|| {
+            candidates
+                .iter()
+                .position(|(_, name)| name.eq_ignore_ascii_case(query))
+        })
+        .or_else(|| {
+            candidates
+                .iter()
+                .enumerate()
+                .filter(|(_, (_, name))| fuzzy_match(query, name))
+                .min_by_key(|(_, (_, name))| name.len())
+                .map(|(index, _)| index)
+        })
+}
+
+/// The entry of `shown` following the one at `current_index`, or preceding it if `backwards`
+fn step<Id: Copy>(shown: &[(usize, Id)], current_index: usize, backwards: bool) -> Option<(usize, Id)> {
+    if backwards {
+        shown.iter().rev().find(|(index, _)| *index < current_index).copied()
+    } else {
+        shown.iter().find(|(index, _)| *index > current_index).copied()
+    }
+}
+
+fn alert_history_start() -> Timestamp {
+    (SystemTime::now() - ALERT_HISTORY_SPAN).into()
+}
+
+/// Shows notifications in the log pane
+struct ToastSink;
+
+impl NotificationSink for ToastSink {
+    fn name(&self) -> &'static str {
+        "toast"
+    }
+
+    fn notify(&self, notification: &Notification) {
+        let level = match notification.severity {
+            Severity::Info => log::Level::Info,
+            Severity::Warning => log::Level::Warn,
+            Severity::Critical => log::Level::Error,
+        };
+        log::log!(level, "{}: {}", notification.summary, notification.body);
+    }
+}
+
 #[derive(Clone)]
 pub struct AppClient {
     sv_client_actor: Addr<SensorVisionClient>,
     ui_state_actor: Addr<UIState>,
+    /// Context of the UI state until the app starts it with the mailbox capacity
+    ui_state_context: Rc<Cell<Option<Context<UIState>>>>,
+    simulator_actor: Addr<SimulatorActor>,
 
     rerun_sender: Option<mpsc::Sender<()>>,
     exit_sender: Option<mpsc::Sender<()>>,
+
+    started_at: Instant,
+    last_input_at: Instant,
+    idle_timeout: Option<Duration>,
+    screensaver_tick: Option<usize>,
+
+    ping_interval: Option<Duration>,
+    ping_in_flight: bool,
+
+    clock_checked_at: (Instant, SystemTime),
+
+    /// Whether the terminal has the focus, as far as the terminal reports it
+    focused: bool,
+    notifications: NotificationDispatcher,
+
+    /// `SENSOR[/METRIC]` to select once the inventory is loaded
+    startup_selection: Option<String>,
+
+    /// Config profile the client runs with, shown in the header
+    profile: Option<String>,
+    /// Keeps the muted sensors of each profile, mutes aren't kept without it
+    mutes_file: Option<PathBuf>,
+    /// Keeps the alert rules edited in the dialog, they are lost on exit without it
+    alerts_file: Option<PathBuf>,
+    /// Keeps the region sizes of each profile, resized regions are reset on exit without it
+    layout_file: Option<PathBuf>,
+    /// Keeps the log levels set in the dialog under `[log.levels]`
+    config_file: Option<PathBuf>,
+
+    mailbox_capacity: usize,
+
+    /// Livedata values handed to the UI state and not accepted yet
+    livedata_in_flight: Arc<AtomicUsize>,
+    overload_threshold: usize,
+    overloaded: Arc<AtomicBool>,
+
+    dialog_tasks: DialogTasks,
 }
 
 impl AppClient {
     pub fn new(sv_client_actor: Addr<SensorVisionClient>) -> Self {
-        let ui_state_actor = UIState::default().start();
+        let ui_state_context = Context::new();
+        let simulator_actor = SimulatorActor::new(sv_client_actor.clone()).start();
         Self {
             sv_client_actor,
-            ui_state_actor,
+            ui_state_actor: ui_state_context.address(),
+            ui_state_context: Rc::new(Cell::new(Some(ui_state_context))),
+            simulator_actor,
             rerun_sender: Option::default(),
             exit_sender: Option::default(),
+            started_at: Instant::now(),
+            last_input_at: Instant::now(),
+            idle_timeout: None,
+            screensaver_tick: None,
+            ping_interval: None,
+            ping_in_flight: false,
+            clock_checked_at: (Instant::now(), SystemTime::now()),
+            focused: true,
+            notifications: NotificationDispatcher::default(),
+            startup_selection: None,
+            profile: None,
+            mutes_file: None,
+            alerts_file: None,
+            layout_file: None,
+            config_file: None,
+            mailbox_capacity: DEFAULT_MAILBOX_CAPACITY,
+            livedata_in_flight: Arc::default(),
+            overload_threshold: 0,
+            overloaded: Arc::default(),
+            dialog_tasks: DialogTasks::default(),
+        }
+    }
+
+    /// Capacity of the mailboxes of the app and its UI state
+    pub fn with_mailbox_capacity(mut self, mailbox_capacity: usize) -> Self {
+        self.mailbox_capacity = mailbox_capacity;
+        self
+    }
+
+    /// Livedata stops triggering renders while more than `overload_threshold` values are
+    /// waiting for the UI state, `0` never sheds renders
+    pub fn with_overload_threshold(mut self, overload_threshold: usize) -> Self {
+        self.overload_threshold = overload_threshold;
+        self
+    }
+
+    /// Shows the screensaver after no key has been pressed for `idle_timeout`
+    pub fn with_idle_timeout(mut self, idle_timeout: Option<Duration>) -> Self {
+        self.idle_timeout = idle_timeout;
+        self
+    }
+
+    /// Pings the agent every `ping_interval` to tell whether it's responsive
+    pub fn with_ping_interval(mut self, ping_interval: Option<Duration>) -> Self {
+        self.ping_interval = ping_interval;
+        self
+    }
+
+    /// Shares the simulator with simulations started outside of the UI
+    pub fn with_simulator(mut self, simulator_actor: Addr<SimulatorActor>) -> Self {
+        self.simulator_actor = simulator_actor;
+        self
+    }
+
+    /// Selects the sensor, and optionally its metric, as soon as they are loaded
+    pub fn with_selection(mut self, selection: Option<String>) -> Self {
+        self.startup_selection = selection;
+        self
+    }
+
+    pub fn with_profile(mut self, profile: Option<String>) -> Self {
+        self.profile = profile;
+        self
+    }
+
+    /// Restores the sensors muted under the profile from `mutes_file` and keeps them there
+    pub fn with_mutes_file(mut self, mutes_file: PathBuf) -> Self {
+        self.mutes_file = Some(mutes_file);
+        self
+    }
+
+    /// Writes the alert rules to `alerts_file` whenever they are edited
+    pub fn with_alerts_file(mut self, alerts_file: PathBuf) -> Self {
+        self.alerts_file = Some(alerts_file);
+        self
+    }
+
+    /// Restores the region sizes resized under the profile from `layout_file` and keeps them there
+    pub fn with_layout_file(mut self, layout_file: PathBuf) -> Self {
+        self.layout_file = Some(layout_file);
+        self
+    }
+
+    /// Writes the log levels to `config_file` whenever the log filters dialog is closed
+    pub fn with_config_file(mut self, config_file: PathBuf) -> Self {
+        self.config_file = Some(config_file);
+        self
+    }
+
+    /// Routes the notifications to the log pane and the sinks enabled in `config`
+    pub fn with_notifications(mut self, config: &NotificationsConfig) -> Self {
+        let mut notifications = NotificationDispatcher::default();
+        notifications.add_sink(Route::from_severity(config.toast_severity), ToastSink);
+        if let Some(command) = config.desktop_command() {
+            notifications.add_sink(
+                Route::from_severity(config.desktop_severity).unfocused_only(),
+                DesktopSink::new(command),
+            );
+        }
+        if config.bell {
+            notifications.add_sink(Route::from_severity(config.bell_severity), BellSink);
+        }
+        for webhook in &config.webhooks {
+            notifications.add_sink(webhook.route(), WebhookSink::new(webhook.url.clone()));
+        }
+        self.notifications = notifications;
+        self
+    }
+
+    fn notify(&self, notification: Notification) {
+        self.notifications.dispatch(notification, self.focused);
+    }
+
+    fn update_screensaver(&mut self, idle_timeout: Duration, ctx: &mut Context<Self>) {
+        if self.last_input_at.elapsed() < idle_timeout {
+            return;
+        }
+
+        let tick = self.screensaver_tick.map_or(0, |tick| tick + 1);
+        self.screensaver_tick = Some(tick);
+        let screensaver = Screensaver {
+            tick,
+            uptime: self.started_at.elapsed(),
+        };
+
+        let app = self.clone();
+        ctx.spawn(
+            async move {
+                let _ = app.ui_state_actor.send(SetScreensaver(Some(screensaver))).await;
+                app.rerender().await;
+            }
+            .into_actor(self),
+        );
+    }
+
+    /// Measures the round trip of a ping, a ping still unanswered is not pinged over
+    fn ping(&mut self, ping_interval: Duration, ctx: &mut Context<Self>) {
+        if self.ping_in_flight {
+            return;
         }
+        self.ping_in_flight = true;
+
+        let sv_client_actor = self.sv_client_actor.clone();
+        let timeout = ping_interval.min(PING_TIMEOUT);
+        ctx.spawn(
+            async move {
+                let sent_at = Instant::now();
+                match tokio::time::timeout(timeout, sv_client_actor.send(PingTest)).await {
+                    Ok(Ok(Ok(()))) => Ok(sent_at.elapsed()),
+                    Ok(Ok(Err(err))) => Err(err),
+                    Ok(Err(err)) => Err(err.into()),
+                    Err(_) => Err(eyre!("No answer in {timeout:?}")),
+                }
+            }
+            .into_actor(self)
+            .map(|pong, act, ctx| act.record_ping(pong, ctx)),
+        );
+    }
+
+    fn record_ping(&mut self, pong: Result<Duration>, ctx: &mut Context<Self>) {
+        self.ping_in_flight = false;
+        let latency = match pong {
+            Ok(latency) => Some(latency),
+            Err(err) => {
+                log::warn!("Ping failed: {err}");
+                None
+            }
+        };
+
+        let app = self.clone();
+        ctx.spawn(
+            async move {
+                let Ok(previous) = app.ui_state_actor.send(RecordPing(latency)).await else {
+                    return;
+                };
+                let notification = match latency {
+                    Some(latency) if previous.is_failing() => Some(Notification::new(
+                        Severity::Info,
+                        Category::Connection,
+                        Msg::AgentResponding.text(),
+                        &Msg::PingAnswered.format(&[("millis", &latency.as_millis())]),
+                    )),
+                    None if previous.failures + 1 == PING_FAILURES_WARNING => Some(Notification::new(
+                        Severity::Warning,
+                        Category::Connection,
+                        Msg::AgentNotResponding.text(),
+                        &Msg::PingsUnanswered.format(&[("count", &PING_FAILURES_WARNING)]),
+                    )),
+                    _ => None,
+                };
+                if let Some(notification) = notification {
+                    app.notify(notification);
+                }
+                app.rerender().await;
+            }
+            .into_actor(self),
+        );
+    }
+
+    /// Detects the system having been asleep by comparing the clocks
+    fn check_clock_jump(&mut self, ctx: &mut Context<Self>) {
+        let (checked_at, checked_at_wall) = self.clock_checked_at;
+        self.clock_checked_at = (Instant::now(), SystemTime::now());
+
+        let elapsed = checked_at.elapsed();
+        let elapsed_wall = checked_at_wall.elapsed().unwrap_or_default();
+        let Some(asleep_for) = elapsed_wall.checked_sub(elapsed) else {
+            return;
+        };
+        if asleep_for < SLEEP_THRESHOLD {
+            return;
+        }
+
+        log::info!("Resumed after sleeping for {asleep_for:?}");
+        let resumed_at = SystemTime::now();
+        let gap = MarkSleepGap {
+            from: Timestamp::from(resumed_at - asleep_for),
+            to: Timestamp::from(resumed_at),
+        };
+        let reason = format!("Resumed after {}", utils::format_duration(asleep_for));
+
+        let app = self.clone();
+        ctx.spawn(
+            async move {
+                let _ = app.ui_state_actor.send(gap).await;
+                // The connection is likely dead by now, the refresh fails if it isn't back in time
+                // and is repeated once the connection is restored
+                app.sv_client_actor.do_send(Reconnect);
+                if let Err(err) = app.summarize_inventory_changes(reason).await {
+                    log::warn!("Failed to refresh the sensors after sleep: {err}");
+                }
+            }
+            .into_actor(self),
+        );
     }
 
     async fn run(
@@ -63,11 +458,39 @@ impl AppClient {
         mut rerun_receiver: mpsc::Receiver<()>,
         mut exit_receiver: mpsc::Receiver<()>,
     ) -> Result<()> {
-        self.sv_client_actor.send(LoadSensors).await??;
-        let tui: SharedTui = Arc::new(Mutex::new(tui));
+        let connector_id = self.sv_client_actor.send(GetConnectorId).await?;
+        self.ui_state_actor.send(SetConnector(connector_id)).await?;
+        if let Some(profile) = self.profile.clone() {
+            self.ui_state_actor.send(SetProfile(profile)).await?;
+        }
+        if let Some(mutes_file) = &self.mutes_file {
+            match read_muted_sensors(mutes_file, self.profile.as_deref()) {
+                Ok(muted) => self.ui_state_actor.send(SetMuted(muted.into_iter().collect())).await?,
+                Err(err) => log::warn!("Failed to restore the muted sensors: {err}"),
+            }
+        }
+        if let Some(layout_file) = &self.layout_file {
+            match read_region_sizes(layout_file, self.profile.as_deref()) {
+                Ok(region_sizes) => self.ui_state_actor.send(SetRegionSizes(region_sizes)).await?,
+                Err(err) => log::warn!("Failed to restore the region sizes: {err}"),
+            }
+        }
+        if let Err(err) = self.restore_alerts().await {
+            log::warn!("Failed to restore the alerts in force: {err}");
+        }
+        let silencing = self.sv_client_actor.send(GetSilencing).await??;
+        self.ui_state_actor.send(SetSilencing(silencing)).await?;
+        self.sv_client_actor
+            .send(Correlated(CorrelationId::new(), LoadSensors))
+            .await??;
+        if let Some(selection) = self.startup_selection.clone() {
+            let app = self.clone();
+            actix::spawn(async move { app.apply_startup_selection(&selection).await });
+        }
+        let renderer = Renderer::spawn(tui, self.ui_state_actor.clone());
 
         loop {
-            self.render(tui.clone()).await?;
+            self.render(&renderer).await?;
 
             tokio::select! {
                 Some(_) = exit_receiver.recv() => {
@@ -78,67 +501,197 @@ impl AppClient {
                 }
             }
         }
-        tui.lock().await.exit()?;
+        self.dialog_tasks.abort_all();
+        renderer.stop().await?.exit()?;
         Ok(())
     }
 
-    async fn render(&self, tui: SharedTui) -> Result<()> {
-        let sensors = self.sv_client_actor.send(GetStateSnapshot).await?;
-        self.ui_state_actor.send(Render { tui, sensors }).await?;
-        Ok(())
+    /// Sensors and metrics are described one by one, so the selection is retried
+    /// until it resolves or the inventory is given enough time to load
+    async fn apply_startup_selection(&self, selection: &str) {
+        let (sensor_query, metric_query) = match split_target(selection) {
+            Some((sensor, metric)) => (sensor, Some(metric)),
+            None => (selection.to_owned(), None),
+        };
+        let (sensor_query, metric_query) = (sensor_query.as_str(), metric_query.as_deref());
+
+        let started_at = Instant::now();
+        let mut selected_sensor = None;
+        while started_at.elapsed() < STARTUP_SELECTION_TIMEOUT {
+            let Ok((sensors, _)) = self.current_state().await else {
+                break;
+            };
+            let found_sensor = best_match(
+                sensor_query,
+                sensors.values().map(|sensor| (sensor.sensor_id, sensor.name.as_str())),
+            )
+            .map(|index| (index, sensors.values().nth(index).unwrap()));
+            if let Some((sensor_index, sensor)) = found_sensor {
+                if selected_sensor != Some(sensor.sensor_id) {
+                    selected_sensor = Some(sensor.sensor_id);
+                    let _ = self
+                        .ui_state_actor
+                        .send(SelectSensor(Some((sensor_index, sensor.sensor_id))))
+                        .await;
+                    let _ = self.ui_state_actor.send(SelectMetric(None)).await;
+                    self.rerender().await;
+                }
+
+                let Some(metric_query) = metric_query else {
+                    return;
+                };
+                let found_metric = best_match(
+                    metric_query,
+                    sensor
+                        .metrics
+                        .iter()
+                        .map(|metric| (*metric.metric_id(), metric.name().as_str())),
+                );
+                if let Some(metric_index) = found_metric {
+                    let metric_id = *sensor.metrics.get_index(metric_index).unwrap().metric_id();
+                    let _ = self
+                        .ui_state_actor
+                        .send(SelectMetric(Some((metric_index, metric_id))))
+                        .await;
+                    self.rerender().await;
+                    return;
+                }
+            }
+            tokio::time::sleep(STARTUP_SELECTION_POLL_INTERVAL).await;
+        }
+
+        match selected_sensor {
+            Some(_) => log::warn!("No metric matches the startup selection '{selection}'"),
+            None => log::warn!("No sensor matches the startup selection '{selection}'"),
+        }
+        self.rerender().await;
+    }
+
+    async fn render(&self, renderer: &Renderer) -> Result<()> {
+        let (version, sensors) = self.sv_client_actor.send(GetVersionedStateSnapshot).await??;
+        let reload_pending = self.sv_client_actor.send(IsReloadPending).await?;
+        let frame = self
+            .ui_state_actor
+            .send(Render {
+                sensors,
+                version,
+                reload_pending,
+            })
+            .await?;
+        renderer.draw(frame).await
     }
 
     async fn rerender(&self) {
         if let Some(sender) = &self.rerun_sender {
-            let _ = sender.send(()).await;
+            // A full channel means a render is pending already, which shows this change too
+            let _ = sender.try_send(());
+        }
+    }
+
+    /// Shows the dialog, then takes it down and hands its result to `on_result` once it's
+    /// closed with one. A dialog closed without a result, e.g. replaced by another dialog,
+    /// leaves the screen to whatever replaced it.
+    async fn open_dialog<R, F>(
+        &self,
+        dialog: ModalDialog,
+        result: oneshot::Receiver<DialogResult<R>>,
+        on_result: impl FnOnce(DialogResult<R>) -> F + 'static,
+    ) -> Result<()>
+    where
+        R: 'static,
+        F: Future<Output = ()> + 'static,
+    {
+        let app = self.clone();
+        self.dialog_tasks.spawn(async move {
+            let Ok(dialog_result) = result.await else {
+                return;
+            };
+            let _ = app.ui_state_actor.send(SetModalDialog(None)).await;
+            on_result(dialog_result).await;
+            app.rerender().await;
+        });
+
+        self.ui_state_actor.send(SetModalDialog(Some(dialog))).await?;
+        self.rerender().await;
+        Ok(())
+    }
+
+    /// Counts the livedata value in, warns once the UI falls behind
+    fn livedata_queued(&self) {
+        let in_flight = self.livedata_in_flight.fetch_add(1, Ordering::Relaxed) + 1;
+        telemetry::queue_depth("ui_livedata", in_flight);
+        if self.overload_threshold > 0
+            && in_flight > self.overload_threshold
+            && !self.overloaded.swap(true, Ordering::Relaxed)
+        {
+            log::warn!("UI is overloaded with {in_flight} livedata values, skipping renders");
+        }
+    }
+
+    /// Counts the livedata value out, tells whether its render should be shed
+    fn livedata_accepted(&self) -> bool {
+        let in_flight = self.livedata_in_flight.fetch_sub(1, Ordering::Relaxed) - 1;
+        telemetry::queue_depth("ui_livedata", in_flight);
+        let overloaded = self.overload_threshold > 0 && in_flight > self.overload_threshold;
+        if !overloaded && self.overloaded.swap(false, Ordering::Relaxed) {
+            log::info!("UI caught up with the livedata");
         }
+        overloaded
     }
 
+    /// Consistent pair of snapshots: sensors are guaranteed not to change while
+    /// the UI state is being fetched, and the selection is anchored to the sensors.
     async fn current_state(&self) -> Result<(Sensors, UIState)> {
-        // FIXME potential data race, use in-memory SQLite for storing state
-        let sensors = self.sv_client_actor.send(GetStateSnapshot).await?;
-        let ui_state = self.ui_state_actor.send(GetUIStateSnapshot).await?;
-        Ok((sensors, ui_state))
+        for _ in 0..CURRENT_STATE_ATTEMPTS {
+            let (version, sensors) = self.sv_client_actor.send(GetVersionedStateSnapshot).await??;
+            let mut ui_state = self.ui_state_actor.send(GetUIStateSnapshot).await?;
+            if self.sv_client_actor.send(GetStateVersion).await?? != version {
+                continue;
+            }
+            ui_state.anchor_to(&sensors);
+            return Ok((sensors, ui_state));
+        }
+        Err(eyre!("Sensors state keeps changing, giving up"))
     }
 
+    /// Cycles through the sensors the filter shows
     async fn next_sensor(&self) -> Result<()> {
+        self.step_sensor(false).await
+    }
+
+    async fn step_sensor(&self, backwards: bool) -> Result<()> {
         let (sensors, ui_state) = self.current_state().await?;
 
         let ui_state_actor = self.ui_state_actor.clone();
 
-        if sensors.is_empty() {
+        let shown: Vec<(usize, SensorId)> = sensors
+            .iter()
+            .enumerate()
+            .filter(|(_, (_, sensor))| ui_state.filter.shows_sensor(sensor))
+            .map(|(index, (sensor_id, _))| (index, *sensor_id))
+            .collect();
+        if shown.is_empty() {
             ui_state_actor.send(SelectSensor(None)).await?;
             ui_state_actor.send(SelectMetric(None)).await?;
             return Ok(());
         }
 
-        if let Some((current_index, _)) = ui_state.current_sensor {
-            if current_index < sensors.len() - 1 {
-                let new_index = current_index.wrapping_add(1);
-                ui_state_actor
-                    .send(SelectSensor(Some((
-                        new_index,
-                        sensors.iter().nth(new_index).unwrap().0.clone(),
-                    ))))
-                    .await?;
-                ui_state_actor.send(SelectMetric(None)).await?;
-                return Ok(());
-            }
-        }
-
-        ui_state_actor
-            .send(SelectSensor(Some((
-                0,
-                sensors.iter().nth(0).unwrap().0.clone(),
-            ))))
-            .await?;
-
+        let next = ui_state
+            .current_sensor
+            .and_then(|(current_index, _)| step(&shown, current_index, backwards))
+            .unwrap_or(if backwards { shown[shown.len() - 1] } else { shown[0] });
+        ui_state_actor.send(SelectSensor(Some(next))).await?;
         ui_state_actor.send(SelectMetric(None)).await?;
 
         Ok(())
     }
 
+    /// Cycles through the metrics of the current sensor the filter shows
     async fn next_metric(&self) -> Result<()> {
+        self.step_metric(false).await
+    }
+
+    async fn step_metric(&self, backwards: bool) -> Result<()> {
         let (sensors, ui_state) = self.current_state().await?;
 
         let Some((_, current_sensor_id)) = ui_state.current_sensor else {
@@ -151,27 +704,167 @@ impl AppClient {
 
         let ui_state_actor = self.ui_state_actor.clone();
 
-        let metrics = &sensor.metrics;
-        if metrics.is_empty() {
+        let shown: Vec<(usize, MetricId)> = sensor
+            .metrics
+            .iter()
+            .enumerate()
+            .filter(|(_, metric)| ui_state.filter.shows_metric(sensor, metric))
+            .map(|(index, metric)| (index, *metric.metric_id()))
+            .collect();
+        if shown.is_empty() {
             ui_state_actor.send(SelectMetric(None)).await?;
             return Ok(());
         }
-        let mut new_index = 0;
-        if let Some((current_index, _)) = ui_state.current_metric {
-            if current_index < metrics.len() - 1 {
-                new_index = current_index.wrapping_add(1);
-            }
+
+        let next = ui_state
+            .current_metric
+            .and_then(|(current_index, _)| step(&shown, current_index, backwards))
+            .unwrap_or(if backwards { shown[shown.len() - 1] } else { shown[0] });
+        ui_state_actor.send(SelectMetric(Some(next))).await?;
+
+        Ok(())
+    }
+
+    /// Describes the selected metric, or all metrics of the selected sensor, again
+    async fn refresh(&self, selected_metric_only: bool) -> Result<()> {
+        let (sensors, ui_state) = self.current_state().await?;
+
+        let Some((_, sensor_id)) = ui_state.current_sensor else {
+            return Ok(());
+        };
+        let Some(sensor) = sensors.get(&sensor_id) else {
+            return Ok(());
+        };
+
+        let metric_ids = if selected_metric_only {
+            let Some((_, metric_id)) = ui_state.current_metric else {
+                return Ok(());
+            };
+            vec![metric_id]
+        } else {
+            sensor
+                .metrics
+                .iter()
+                .map(|metric| *metric.metric_id())
+                .collect()
+        };
+        if metric_ids.is_empty() {
+            return Ok(());
+        }
+
+        self.sv_client_actor
+            .send(Correlated(
+                CorrelationId::new(),
+                DescribeMetrics {
+                    sensor_id,
+                    metric_ids,
+                },
+            ))
+            .await?
+    }
+
+    /// Selects the first metric of the page `delta` pages away from the selected one
+    async fn page_metrics(&self, delta: isize) -> Result<()> {
+        let (sensors, ui_state) = self.current_state().await?;
+
+        let Some((_, current_sensor_id)) = ui_state.current_sensor else {
+            return Ok(());
+        };
+
+        let Some(sensor) = sensors.get(&current_sensor_id) else {
+            return Ok(());
+        };
+
+        let shown: Vec<(usize, MetricId)> = sensor
+            .metrics
+            .iter()
+            .enumerate()
+            .filter(|(_, metric)| ui_state.filter.shows_metric(sensor, metric))
+            .map(|(index, metric)| (index, *metric.metric_id()))
+            .collect();
+        if shown.is_empty() {
+            return Ok(());
         }
-        ui_state_actor
-            .send(SelectMetric(Some((
-                new_index,
-                metrics[new_index].metric_id().clone(),
-            ))))
+
+        let page_size = ui_state.metric_page_size.max(1);
+        let position = ui_state
+            .current_metric
+            .and_then(|(current_index, _)| {
+                shown.iter().position(|(index, _)| *index == current_index)
+            })
+            .unwrap_or_default();
+        let last_page = (shown.len() - 1) / page_size;
+        let page = (position / page_size).saturating_add_signed(delta).min(last_page);
+        self.ui_state_actor
+            .send(SelectMetric(Some(shown[page * page_size])))
             .await?;
 
         Ok(())
     }
 
+    /// Moves the selection to the first sensor and metric the filter shows,
+    /// unless the selected ones are still shown
+    async fn select_filtered(&self) -> Result<()> {
+        let (sensors, ui_state) = self.current_state().await?;
+
+        let current_sensor = ui_state
+            .current_sensor
+            .and_then(|(_, sensor_id)| sensors.get(&sensor_id));
+        if !current_sensor.is_some_and(|sensor| ui_state.filter.shows_sensor(sensor)) {
+            return self.next_sensor_and_metric().await;
+        }
+
+        let current_metric = current_sensor.zip(ui_state.current_metric).and_then(
+            |(sensor, (_, metric_id))| sensor.metrics.get(&metric_id).map(|metric| (sensor, metric)),
+        );
+        if !current_metric.is_some_and(|(sensor, metric)| ui_state.filter.shows_metric(sensor, metric))
+        {
+            self.ui_state_actor.send(SelectMetric(None)).await?;
+            self.next_metric().await?;
+        }
+        Ok(())
+    }
+
+    async fn next_sensor_and_metric(&self) -> Result<()> {
+        self.next_sensor().await?;
+        self.next_metric().await
+    }
+
+    async fn prev_sensor_and_metric(&self) -> Result<()> {
+        self.step_sensor(true).await?;
+        self.next_metric().await
+    }
+
+    /// Selects the given metric unless a dialog is open or the filter hides the metric
+    async fn focus_metric(&self, sensor_id: SensorId, metric_id: MetricId) -> Result<()> {
+        let (sensors, ui_state) = self.current_state().await?;
+        if ui_state.modal_dialog.is_some() || ui_state.filter.editing {
+            return Ok(());
+        }
+
+        let Some(sensor_index) = sensors.keys().position(|id| *id == sensor_id) else {
+            return Ok(());
+        };
+        let sensor = &sensors[&sensor_id];
+        let (Some(metric_index), Some(metric)) = (
+            sensor.metrics.position(&metric_id),
+            sensor.metrics.get(&metric_id),
+        ) else {
+            return Ok(());
+        };
+        if !ui_state.filter.shows_metric(sensor, metric) {
+            return Ok(());
+        }
+
+        self.ui_state_actor
+            .send(SelectSensor(Some((sensor_index, sensor_id))))
+            .await?;
+        self.ui_state_actor
+            .send(SelectMetric(Some((metric_index, metric_id))))
+            .await?;
+        Ok(())
+    }
+
     async fn handle_key_event(&mut self, key_event: KeyEvent) -> Result<()> {
         if key_event.kind != KeyEventKind::Press {
             return Ok(());
@@ -181,59 +874,212 @@ impl AppClient {
             return Ok(());
         }
 
-        use KeyCode::*;
+        if self.ui_state_actor.send(HandleFilterKey(key_event)).await? {
+            self.select_filtered().await?;
+            self.rerender().await;
+            return Ok(());
+        }
+
+        let Some(action) = keymap::action_for(&key_event) else {
+            return Ok(());
+        };
 
-        match key_event.code {
-            Char('q') => {
+        match action {
+            Action::Quit => {
                 if let Some(sender) = &self.exit_sender {
                     sender.send(()).await?;
                 }
             }
 
-            Tab => {
-                self.next_sensor().await?;
-                self.next_metric().await?;
+            Action::Help => {
+                self.show_help().await?;
+            }
+
+            Action::NextSensor => {
+                self.next_sensor_and_metric().await?;
+            }
+
+            Action::PrevSensor => {
+                self.prev_sensor_and_metric().await?;
             }
 
-            BackTab => {
+            Action::NextMetric => {
                 self.next_metric().await?;
             }
 
-            Char('d') => {
+            Action::PrevMetric => {
+                self.step_metric(true).await?;
+            }
+
+            Action::PrevMetricPage => {
+                self.page_metrics(-1).await?;
+            }
+
+            Action::NextMetricPage => {
+                self.page_metrics(1).await?;
+            }
+
+            Action::DeleteSensor => {
                 self.delete_sensor().await?;
             }
 
-            Char('D') => {
+            Action::DeleteMetric => {
                 self.delete_metric().await?;
             }
 
-            Char('n') => {
+            Action::RefreshSensor => {
+                self.refresh(false).await?;
+            }
+
+            Action::RefreshMetric => {
+                self.refresh(true).await?;
+            }
+
+            Action::CreateSensor => {
                 self.create_sensor().await?;
             }
 
-            Char('N') => {
+            Action::CreateMetric => {
                 self.create_metric().await?;
             }
 
-            Char('e') => {
+            Action::EditSensor => {
                 self.update_sensor().await?;
             }
 
-            Char('E') => {
+            Action::CloneSensor => {
+                self.clone_sensor().await?;
+            }
+            Action::ToggleSensorState => {
+                self.toggle_sensor_state().await?;
+            }
+
+            Action::ToggleMute => {
+                self.toggle_mute().await?;
+            }
+
+            Action::EditMetric => {
                 self.update_metric().await?;
             }
 
-            Char(' ') => {
+            Action::PushValue => {
                 self.push_value().await?;
             }
 
-            Char('t') => {
-                let theme_idx = THEME_INDEX.load(Ordering::SeqCst);
-                THEME_INDEX.store(if theme_idx != 0 { 0 } else { 1 }, Ordering::SeqCst);
+            Action::Simulate => {
+                self.simulate().await?;
             }
 
-            _ => {
-                return Ok(());
+            Action::EditAlert => {
+                self.edit_alert().await?;
+            }
+
+            Action::EditLogFilters => {
+                self.edit_log_filters().await?;
+            }
+
+            Action::ViewInventoryJson => {
+                self.view_inventory_json().await?;
+            }
+
+            Action::ViewExchanges => {
+                self.view_exchanges().await?;
+            }
+
+            Action::ViewErrors => {
+                self.view_errors().await?;
+            }
+
+            Action::ViewAlertHistory => {
+                self.view_alert_history().await?;
+            }
+
+            Action::SilenceAlerts => {
+                self.silence_alerts().await?;
+            }
+
+            Action::RetryLastRequest => {
+                self.retry_last_request().await?;
+            }
+
+            Action::SaveInventory => {
+                self.save_inventory().await?;
+            }
+
+            Action::ImportInventory => {
+                self.import_inventory().await?;
+            }
+
+            Action::ToggleLogPane => {
+                self.ui_state_actor.send(ToggleLogPane).await?;
+            }
+
+            Action::ScrollLogBack => {
+                self.ui_state_actor.send(ScrollLogPane(1)).await?;
+            }
+
+            Action::ScrollLogForward => {
+                self.ui_state_actor.send(ScrollLogPane(-1)).await?;
+            }
+
+            Action::ShrinkRegion => {
+                self.resize_region(-1).await?;
+            }
+
+            Action::GrowRegion => {
+                self.resize_region(1).await?;
+            }
+
+            Action::Filter => {
+                self.ui_state_actor.send(EditFilter).await?;
+            }
+
+            Action::ToggleFocusFollow => {
+                let enabled = self.ui_state_actor.send(ToggleFocusFollow).await?;
+                log::info!("Focus-follow mode {}", if enabled { "enabled" } else { "disabled" });
+            }
+
+            Action::CycleComparison => {
+                let comparison = self.ui_state_actor.send(CycleComparison).await?;
+                log::info!("Comparing the livedata with: {}", comparison.name());
+            }
+
+            Action::CycleTimeAxis => {
+                let time_axis = self.ui_state_actor.send(CycleTimeAxis).await?;
+                log::info!("Time axes of the charts span: {}", time_axis.name());
+            }
+
+            Action::ToggleStats => {
+                let shown = self.ui_state_actor.send(ToggleStats).await?;
+                log::info!("Livedata statistics {}", if shown { "shown" } else { "hidden" });
+            }
+
+            Action::ToggleMetricLayout => {
+                let layout = match self.ui_state_actor.send(ToggleMetricLayout).await? {
+                    MetricLayout::Cards => "cards",
+                    MetricLayout::Table => "a table",
+                };
+                log::info!("Metrics shown as {layout}");
+            }
+
+            Action::CycleTableSort => {
+                let column = self.ui_state_actor.send(CycleTableSort).await?;
+                log::info!("Metric table sorted by: {}", column.title());
+            }
+
+            Action::ToggleOverview => {
+                let overview = self.ui_state_actor.send(ToggleOverview).await?;
+                log::info!("{}", if overview { "Showing all sensors" } else { "Showing the selected sensor" });
+            }
+
+            Action::ToggleFreeze => {
+                let frozen = self.ui_state_actor.send(ToggleFreeze).await?;
+                log::info!("Charts {}", if frozen { "frozen" } else { "unfrozen" });
+            }
+
+            Action::ToggleTheme => {
+                let theme_idx = THEME_INDEX.load(Ordering::SeqCst);
+                THEME_INDEX.store(if theme_idx != 0 { 0 } else { 1 }, Ordering::SeqCst);
             }
         }
 
@@ -242,42 +1088,413 @@ impl AppClient {
         Ok(())
     }
 
-    async fn create_sensor(&self) -> Result<()> {
+    /// Reloads the sensors after a while offline and shows what has changed meanwhile
+    /// in one go, rather than letting the changes slip by unnoticed
+    async fn summarize_inventory_changes(&self, reason: String) -> Result<()> {
+        let before = self.sv_client_actor.send(GetStateSnapshot).await??;
+        load_inventory(&self.sv_client_actor).await?;
+        let after = self.sv_client_actor.send(GetStateSnapshot).await??;
+
+        let changes = diff_inventories(&before, &after);
+        log::info!("{reason}, {} inventory change(s)", changes.len());
+        if changes.is_empty() {
+            self.rerender().await;
+            return Ok(());
+        }
+
+        let ui_state = self.ui_state_actor.send(GetUIStateSnapshot).await?;
+        if ui_state.modal_dialog.is_some() {
+            // Not to snatch the dialog being worked with
+            log::info!("{reason}: {} inventory change(s)", changes.len());
+            self.rerender().await;
+            return Ok(());
+        }
+
+        let lines = changes.iter().map(ToString::to_string).collect();
+        let (tx, rx) = oneshot::channel();
+        let dialog_actor =
+            MessageDialogActor::new(MessageDialogState::diff(format!(" {reason} "), lines), tx)
+                .start();
+
+        self.open_dialog(ModalDialog::Message(dialog_actor), rx, |_| async {}).await
+    }
+
+    async fn show_help(&self) -> Result<()> {
+        let (tx, rx) = oneshot::channel();
+        let dialog_actor = HelpDialogActor::new(HelpDialogState::default(), tx).start();
+
+        self.open_dialog(ModalDialog::Help(dialog_actor), rx, |_| async {}).await
+    }
+
+    async fn edit_log_filters(&self) -> Result<()> {
+        let (tx, rx) = oneshot::channel();
+        let dialog_actor = LogFilterDialogActor::new(LogFilterDialogState::default(), tx).start();
+
+        let app = self.clone();
+
+        self.open_dialog(ModalDialog::LogFilter(dialog_actor), rx, move |_| async move {
+            if let Some(config_file) = &app.config_file
+                && let Err(err) = write_log_levels(config_file, &logging::subsystem_levels())
+            {
+                log::warn!("Failed to keep the log levels: {err}");
+            }
+        })
+        .await
+    }
+
+    async fn view_inventory_json(&self) -> Result<()> {
+        let dump = self.sv_client_actor.send(DumpSensors::default()).await??;
+        let root = serde_json::from_str(&dump)?;
+        self.view_json(Msg::Inventory.text().to_owned(), root).await
+    }
+
+    /// Shows the last requests concerning the selected sensor along with their replies,
+    /// or the connector-wide ones if no sensor is selected
+    async fn view_exchanges(&self) -> Result<()> {
+        let (sensors, ui_state) = self.current_state().await?;
+        let sensor = ui_state
+            .current_sensor
+            .and_then(|(_, sensor_id)| sensors.get(&sensor_id));
+
+        let title = match sensor {
+            Some(sensor) => Msg::SensorExchanges.format(&[("sensor", &sensor.name)]),
+            None => Msg::ConnectorExchanges.text().to_owned(),
+        };
+        let exchanges = exchanges::of_sensor(sensor.map(|sensor| sensor.sensor_id))
+            .iter()
+            .map(Exchange::to_json)
+            .collect();
+        self.view_json(title, serde_json::Value::Array(exchanges)).await
+    }
+
+    /// Lets the errors the agent replied with be dismissed or their requests sent again
+    async fn view_errors(&self) -> Result<()> {
+        let ui_state = self.ui_state_actor.send(GetUIStateSnapshot).await?;
+        let (tx, rx) = oneshot::channel();
+        let dialog_actor =
+            ErrorsDialogActor::new(ErrorsDialogState::new(ui_state.errors.iter().cloned()), tx)
+                .start();
+
+        let app = self.clone();
+
+        self.open_dialog(ModalDialog::Errors(dialog_actor), rx, move |dialog_result| async move {
+            if let DialogResult::Accept { result } = dialog_result {
+                let _ = app.ui_state_actor.send(DismissErrors(result.dismissed)).await;
+                let retried = result.retry.and_then(|id| {
+                    ui_state.errors.iter().find(|error| error.id == id).cloned()
+                });
+                if let Some(error) = retried
+                    && let Err(err) = app.retry(error).await
+                {
+                    log::error!("Failed to retry the request: {err}");
+                }
+            }
+        })
+        .await
+    }
+
+    /// Alerts the history has in force, e.g. raised overnight by another client sharing it,
+    /// are marked right away rather than on their next livedata
+    async fn restore_alerts(&self) -> Result<()> {
+        let alerts = self
+            .sv_client_actor
+            .send(GetAlertHistory {
+                since: alert_history_start(),
+            })
+            .await??;
+        let active: Vec<_> = alerts.iter().filter(|alert| alert.resolved_at.is_none()).collect();
+        for alert in &active {
+            self.ui_state_actor
+                .send(SetAlert {
+                    sensor_id: alert.sensor_id,
+                    metric_id: alert.metric_id,
+                    raised: true,
+                })
+                .await?;
+        }
+        if !alerts.is_empty() {
+            log::info!(
+                "{} alert(s) in force, {} raised and resolved lately, see the alert history",
+                active.len(),
+                alerts.len() - active.len()
+            );
+        }
+        Ok(())
+    }
+
+    async fn view_alert_history(&self) -> Result<()> {
+        let alerts = self
+            .sv_client_actor
+            .send(GetAlertHistory {
+                since: alert_history_start(),
+            })
+            .await??;
+        let (tx, rx) = oneshot::channel();
+        let dialog_actor = AlertHistoryDialogActor::new(AlertHistoryDialogState::new(alerts), tx).start();
+
+        self.open_dialog(ModalDialog::AlertHistory(dialog_actor), rx, |_| async {}).await
+    }
+
+    /// Asks for how long to silence every alert, e.g. for an unplanned maintenance
+    async fn silence_alerts(&self) -> Result<()> {
         let (tx, rx) = oneshot::channel();
         let dialog_actor = InputDialogActor::new(
             InputDialogState {
-                title: "Create Sensor".to_owned(),
-                text: "Create a new Sensor?".to_owned(),
-                label: "Name:".to_owned(),
-                text_input: None,
+                title: Msg::SilenceAlerts.text().to_owned(),
+                text: Msg::SilenceAlertsText.text().to_owned(),
+                label: Msg::DurationLabel.text().to_owned(),
+                text_input: Some(String::from("2h")),
                 focused_button: Some(DialogButton::Ok),
             },
             tx,
         )
         .start();
 
-        let ui_state_actor = self.ui_state_actor.clone();
-        let sv_client_actor = self.sv_client_actor.clone();
+        let app = self.clone();
 
-        actix::spawn(async move {
-            let dialog_result = rx.await.expect("Receiving failed");
-            let _ = ui_state_actor.send(SetModalDialog(None)).await;
-            if let DialogResult::Accept { result: new_name } = dialog_result {
-                if let Err(err) = sv_client_actor
-                    .send(CreateSensor {
-                        name: new_name.clone(),
+        self.open_dialog(ModalDialog::Input(dialog_actor), rx, move |dialog_result| async move {
+            if let DialogResult::Accept { result: duration } = dialog_result
+                && let Err(err) = app.silence_for(&duration).await
+            {
+                log::error!("Failed to silence the alerts for {duration}: {err}");
+            }
+        })
+        .await
+    }
+
+    /// A zero duration lifts the silence
+    async fn silence_for(&self, duration: &str) -> Result<()> {
+        let secs = match duration.trim() {
+            "0" => 0,
+            duration => duration_secs(duration)?,
+        };
+        let until = (secs > 0).then(|| Timestamp::from(SystemTime::now() + Duration::from_secs(secs)));
+        self.sv_client_actor.send(SilenceAlerts(until)).await?
+    }
+
+    /// Sends the latest failed request changing the inventory once more
+    async fn retry_last_request(&self) -> Result<()> {
+        let ui_state = self.ui_state_actor.send(GetUIStateSnapshot).await?;
+        let Some(error) = ui_state.errors.iter().rev().find(|error| error.is_retriable()) else {
+            log::info!("No failed request to retry");
+            return Ok(());
+        };
+        self.retry(error.clone()).await?;
+        self.rerender().await;
+        Ok(())
+    }
+
+    /// The error is dismissed, should the request fail again a new one is filed
+    async fn retry(&self, error: ErrorEntry) -> Result<()> {
+        let Some(payload) = error.request else {
+            return Ok(());
+        };
+        self.ui_state_actor.send(DismissErrors(vec![error.id])).await?;
+        let correlation_id = CorrelationId::new();
+        self.sv_client_actor
+            .send(Correlated(correlation_id, RetryRequest {
+                scheme: error.scheme,
+                payload,
+            }))
+            .await?
+    }
+
+    async fn view_json(&self, title: String, root: serde_json::Value) -> Result<()> {
+        let (tx, rx) = oneshot::channel();
+        let dialog_actor =
+            JsonViewerDialogActor::new(JsonViewerDialogState::new(title, root), tx).start();
+
+        self.open_dialog(ModalDialog::JsonViewer(dialog_actor), rx, |_| async {}).await
+    }
+
+    async fn save_inventory(&self) -> Result<()> {
+        let (tx, rx) = oneshot::channel();
+        let dialog_actor = PathDialogActor::new(
+            PathDialogState::new(
+                Msg::SaveInventory.text().to_owned(),
+                Msg::SaveInventoryText.text().to_owned(),
+                DEFAULT_INVENTORY_FILE.to_owned(),
+            ),
+            tx,
+        )
+        .start();
+
+        let app = self.clone();
+
+        self.open_dialog(ModalDialog::Path(dialog_actor), rx, move |dialog_result| async move {
+            let DialogResult::Accept { result: path } = dialog_result else {
+                return;
+            };
+
+            if path.exists() && !app.confirm_overwrite(&path).await {
+                return;
+            }
+
+            let written = async {
+                // Sorted, so that files saved at different times can be diffed
+                let dump = app
+                    .sv_client_actor
+                    .send(DumpSensors {
+                        metric_order: MetricOrder::Name,
                     })
-                    .await
-                {
-                    log::error!("Failed to send SensorUpdate for {new_name}: {err}");
+                    .await??;
+                std::fs::write(&path, dump)?;
+                Ok::<_, eyre::Report>(())
+            }
+            .await;
+            match written {
+                Ok(()) => log::info!("Inventory written to {}", path.display()),
+                Err(err) => {
+                    log::error!("Failed to write the inventory to {}: {err}", path.display());
                 }
             }
-        });
+        })
+        .await
+    }
 
-        let message = SetModalDialog(Some(ModalDialog::Input(dialog_actor.clone())));
-        self.ui_state_actor.send(message).await?;
+    /// Applies the file without pruning, so nothing missing from it is deleted,
+    /// then shows what the import changed
+    async fn import_inventory(&self) -> Result<()> {
+        let (tx, rx) = oneshot::channel();
+        let dialog_actor = PathDialogActor::new(
+            PathDialogState::new(
+                Msg::ImportInventory.text().to_owned(),
+                Msg::ImportInventoryText.text().to_owned(),
+                DEFAULT_INVENTORY_FILE.to_owned(),
+            ),
+            tx,
+        )
+        .start();
 
-        Ok(())
+        let app = self.clone();
+        let correlation_id = CorrelationId::new();
+        self.open_dialog(ModalDialog::Path(dialog_actor), rx, move |dialog_result| {
+            correlation::scope(correlation_id, async move {
+                let DialogResult::Accept { result: path } = dialog_result else {
+                    return;
+                };
+
+                let import = ImportSensors {
+                    path: path.clone(),
+                    prune: false,
+                    dry_run: false,
+                    // Nothing is deleted without pruning
+                    protect_livedata: None,
+                };
+                let steps = match app.sv_client_actor.send(Correlated(correlation_id, import)).await {
+                    Ok(Ok(steps)) => steps,
+                    Ok(Err(err)) => {
+                        log::error!("Failed to import {}: {err:#}", path.display());
+                        return;
+                    }
+                    Err(err) => {
+                        log::error!("Failed to send ImportSensors: {err}");
+                        return;
+                    }
+                };
+                log::info!("Imported {}: {}", path.display(), summarize_import(&steps));
+                if steps.is_empty() {
+                    app.rerender().await;
+                    return;
+                }
+
+                let mut lines: Vec<String> = steps.iter().map(ToString::to_string).collect();
+                lines.push(String::new());
+                lines.push(summarize_import(&steps));
+                let (tx, rx) = oneshot::channel();
+                let title = format!(" Imported {} ", path.display());
+                let dialog_actor = MessageDialogActor::new(MessageDialogState::diff(title, lines), tx).start();
+                if let Err(err) = app.open_dialog(ModalDialog::Message(dialog_actor), rx, |_| async {}).await {
+                    log::error!("Failed to show the import of {}: {err}", path.display());
+                }
+            })
+        })
+        .await
+    }
+
+    async fn confirm_overwrite(&self, path: &Path) -> bool {
+        let (tx, rx) = oneshot::channel::<DialogResult<()>>();
+        let dialog_actor = ConfirmationDialogActor::new(
+            ConfirmationDialogState {
+                title: Msg::OverwriteFile.text().to_owned(),
+                text: Msg::OverwriteFileText.format(&[("path", &path.display())]),
+                focused_button: Some(DialogButton::Cancel),
+            },
+            tx,
+        )
+        .start();
+
+        let message = SetModalDialog(Some(ModalDialog::Confirmation(dialog_actor)));
+        if self.ui_state_actor.send(message).await.is_err() {
+            return false;
+        }
+        self.rerender().await;
+
+        let dialog_result = rx.await;
+        let _ = self.ui_state_actor.send(SetModalDialog(None)).await;
+        matches!(dialog_result, Ok(DialogResult::Accept { result: () }))
+    }
+
+    /// Metrics sharing a name are only told apart by their ids, so they're suffixed
+    /// unless the user gives up creating them
+    async fn confirm_suffixing(&self, colliding: &[String]) -> bool {
+        let (tx, rx) = oneshot::channel::<DialogResult<()>>();
+        let dialog_actor = ConfirmationDialogActor::new(
+            ConfirmationDialogState {
+                title: Msg::DuplicateMetricNames.text().to_owned(),
+                text: Msg::DuplicateMetricNamesText.format(&[
+                    ("names", &colliding.join(", ")),
+                    ("example", &colliding[0]),
+                ]),
+                focused_button: Some(DialogButton::Ok),
+            },
+            tx,
+        )
+        .start();
+
+        let message = SetModalDialog(Some(ModalDialog::Confirmation(dialog_actor)));
+        if self.ui_state_actor.send(message).await.is_err() {
+            return false;
+        }
+        self.rerender().await;
+
+        let dialog_result = rx.await;
+        let _ = self.ui_state_actor.send(SetModalDialog(None)).await;
+        matches!(dialog_result, Ok(DialogResult::Accept { result: () }))
+    }
+
+    async fn create_sensor(&self) -> Result<()> {
+        let (tx, rx) = oneshot::channel();
+        let dialog_actor = InputDialogActor::new(
+            InputDialogState {
+                title: Msg::CreateSensor.text().to_owned(),
+                text: Msg::CreateSensorText.text().to_owned(),
+                label: Msg::NameLabel.text().to_owned(),
+                text_input: None,
+                focused_button: Some(DialogButton::Ok),
+            },
+            tx,
+        )
+        .start();
+
+        let app = self.clone();
+        let correlation_id = CorrelationId::new();
+        self.open_dialog(ModalDialog::Input(dialog_actor), rx, move |dialog_result| {
+            correlation::scope(correlation_id, async move {
+                if let DialogResult::Accept { result: new_name } = dialog_result {
+                    if let Err(err) = app
+                        .sv_client_actor
+                        .send(Correlated(correlation_id, CreateSensor {
+                            name: new_name.clone(),
+                        }))
+                        .await
+                    {
+                        log::error!("Failed to send SensorUpdate for {new_name}: {err}");
+                    }
+                }
+            })
+        })
+        .await
     }
 
     async fn update_sensor(&self) -> Result<()> {
@@ -291,9 +1508,9 @@ impl AppClient {
         let (tx, rx) = oneshot::channel();
         let dialog_actor = InputDialogActor::new(
             InputDialogState {
-                title: "Update Sensor".to_owned(),
-                text: format!("Rename Sensor {}?", sensor_name),
-                label: "Name:".to_owned(),
+                title: Msg::UpdateSensor.text().to_owned(),
+                text: Msg::UpdateSensorText.format(&[("sensor", &sensor_name)]),
+                label: Msg::NameLabel.text().to_owned(),
                 text_input: Some(sensor_name),
                 focused_button: Some(DialogButton::Ok),
             },
@@ -301,29 +1518,118 @@ impl AppClient {
         )
         .start();
 
-        let ui_state_actor = self.ui_state_actor.clone();
-        let sv_client_actor = self.sv_client_actor.clone();
+        let app = self.clone();
+        let correlation_id = CorrelationId::new();
+        self.open_dialog(ModalDialog::Input(dialog_actor), rx, move |dialog_result| {
+            correlation::scope(correlation_id, async move {
+                if let DialogResult::Accept { result: new_name } = dialog_result {
+                    if let Err(err) = app
+                        .sv_client_actor
+                        .send(Correlated(correlation_id, UpdateSensor {
+                            sensor_id,
+                            name: new_name.clone(),
+                            state: None,
+                        }))
+                        .await
+                    {
+                        log::error!("Failed to send SensorUpdate for {new_name}: {err}");
+                    }
+                }
+            })
+        })
+        .await
+    }
 
-        actix::spawn(async move {
-            let dialog_result = rx.await.expect("Receiving failed");
-            let _ = ui_state_actor.send(SetModalDialog(None)).await;
-            if let DialogResult::Accept { result: new_name } = dialog_result {
-                if let Err(err) = sv_client_actor
-                    .send(UpdateSensor {
-                        sensor_id,
-                        name: new_name.clone(),
-                        state: None,
-                    })
-                    .await
-                {
-                    log::error!("Failed to send SensorUpdate for {new_name}: {err}");
+    async fn clone_sensor(&self) -> Result<()> {
+        let (sensors, ui_state) = self.current_state().await?;
+        let Some(sensor) = ui_state
+            .current_sensor
+            .and_then(|(_, sensor_id)| sensors.get(&sensor_id))
+        else {
+            return Ok(());
+        };
+        let source_sensor_id = sensor.sensor_id;
+
+        let (tx, rx) = oneshot::channel();
+        let dialog_actor = InputDialogActor::new(
+            InputDialogState {
+                title: Msg::CloneSensor.text().to_owned(),
+                text: Msg::CloneSensorText.format(&[
+                    ("sensor", &sensor.name),
+                    ("count", &sensor.metrics.len()),
+                ]),
+                label: Msg::NameLabel.text().to_owned(),
+                text_input: Some(Msg::CloneSensorName.format(&[("sensor", &sensor.name)])),
+                focused_button: Some(DialogButton::Ok),
+            },
+            tx,
+        )
+        .start();
+
+        let app = self.clone();
+        let correlation_id = CorrelationId::new();
+        self.open_dialog(ModalDialog::Input(dialog_actor), rx, move |dialog_result| {
+            correlation::scope(correlation_id, async move {
+                if let DialogResult::Accept { result: new_name } = dialog_result {
+                    let clone_sensor = CloneSensor {
+                        source_sensor_id,
+                        new_name: new_name.clone(),
+                    };
+                    match app.sv_client_actor.send(Correlated(correlation_id, clone_sensor)).await {
+                        Ok(Ok(())) => {}
+                        Ok(Err(err)) => log::error!("Failed to clone sensor as {new_name}: {err}"),
+                        Err(err) => log::error!("Failed to send CloneSensor for {new_name}: {err}"),
+                    }
                 }
-            }
-        });
+            })
+        })
+        .await
+    }
 
-        let message = SetModalDialog(Some(ModalDialog::Input(dialog_actor.clone())));
-        self.ui_state_actor.send(message).await?;
+    /// The new state shows up once the sensors are reloaded after the update
+    async fn toggle_sensor_state(&self) -> Result<()> {
+        let (sensors, ui_state) = self.current_state().await?;
+        let Some(sensor) = ui_state
+            .current_sensor
+            .and_then(|(_, sensor_id)| sensors.get(&sensor_id))
+        else {
+            return Ok(());
+        };
+
+        let correlation_id = CorrelationId::new();
+        self.sv_client_actor
+            .send(Correlated(correlation_id, UpdateSensor {
+                sensor_id: sensor.sensor_id,
+                name: sensor.name.clone(),
+                state: Some(sensor.state.toggled()),
+            }))
+            .await?
+    }
+
+    async fn resize_region(&self, delta: i16) -> Result<()> {
+        let region_sizes = self.ui_state_actor.send(ResizeRegion(delta)).await?;
+        if let Some(layout_file) = &self.layout_file
+            && let Err(err) = write_region_sizes(layout_file, self.profile.as_deref(), &region_sizes)
+        {
+            log::warn!("Failed to keep the region sizes: {err}");
+        }
+        Ok(())
+    }
 
+    async fn toggle_mute(&self) -> Result<()> {
+        let Some((sensor_id, muted)) = self.ui_state_actor.send(ToggleMute).await? else {
+            return Ok(());
+        };
+        if muted.contains(&sensor_id) {
+            log::info!("Livedata of {sensor_id} muted, it's only counted");
+        } else {
+            log::info!("Livedata of {sensor_id} unmuted");
+        }
+        if let Some(mutes_file) = &self.mutes_file
+            && let Err(err) = write_muted_sensors(mutes_file, self.profile.as_deref(), &muted.into_iter().collect())
+        {
+            log::warn!("Failed to keep the muted sensors: {err}");
+        }
         Ok(())
     }
 
@@ -336,31 +1642,30 @@ impl AppClient {
         let (tx, rx) = oneshot::channel::<DialogResult<()>>();
         let dialog_actor = ConfirmationDialogActor::new(
             ConfirmationDialogState {
-                title: "Delete Sensor".to_owned(),
-                text: format!("Delete Sensor #{}?", sensor_id),
+                title: Msg::DeleteSensor.text().to_owned(),
+                text: Msg::DeleteSensorText.format(&[("sensor", &sensor_id)]),
                 focused_button: Some(DialogButton::Cancel),
             },
             tx,
         )
         .start();
 
-        let ui_state_actor = self.ui_state_actor.clone();
-        let sv_client_actor = self.sv_client_actor.clone();
-
-        actix::spawn(async move {
-            let dialog_result = rx.await.expect("Receiving failed");
-            let _ = ui_state_actor.send(SetModalDialog(None)).await;
-            if matches!(dialog_result, DialogResult::Accept { result: () }) {
-                if let Err(err) = sv_client_actor.send(DeleteSensor { sensor_id }).await {
-                    log::error!("Failed to send SensorDelete for {sensor_id}: {err}");
+        let app = self.clone();
+        let correlation_id = CorrelationId::new();
+        self.open_dialog(ModalDialog::Confirmation(dialog_actor), rx, move |dialog_result| {
+            correlation::scope(correlation_id, async move {
+                if matches!(dialog_result, DialogResult::Accept { result: () }) {
+                    if let Err(err) = app
+                        .sv_client_actor
+                        .send(Correlated(correlation_id, DeleteSensor { sensor_id }))
+                        .await
+                    {
+                        log::error!("Failed to send SensorDelete for {sensor_id}: {err}");
+                    }
                 }
-            }
-        });
-
-        let message = SetModalDialog(Some(ModalDialog::Confirmation(dialog_actor.clone())));
-        self.ui_state_actor.send(message).await?;
-
-        Ok(())
+            })
+        })
+        .await
     }
 
     async fn create_metric(&self) -> Result<()> {
@@ -371,9 +1676,9 @@ impl AppClient {
 
         let (tx, rx) = oneshot::channel();
         let dialog_actor = MetricDialogActor::new(
-            MetricDialogState::new(
-                "Create Metric".to_owned(),
-                "Which Metric to create?".to_owned(),
+            MetricDialogState::batch(
+                Msg::CreateMetrics.text().to_owned(),
+                Msg::CreateMetricsText.text().to_owned(),
                 vec![
                     Metric::predefined(String::default(), ValueUnit::Percent),
                     Metric::custom(String::default(), ValueType::Integer, String::default()),
@@ -383,29 +1688,40 @@ impl AppClient {
         )
         .start();
 
-        let ui_state_actor = self.ui_state_actor.clone();
-        let sv_client_actor = self.sv_client_actor.clone();
+        let app = self.clone();
+        let correlation_id = CorrelationId::new();
+        self.open_dialog(ModalDialog::Metric(dialog_actor), rx, move |dialog_result| {
+            correlation::scope(correlation_id, async move {
+                let DialogResult::Accept { result: mut metrics } = dialog_result else {
+                    return;
+                };
 
-        actix::spawn(async move {
-            let dialog_result = rx.await.expect("Receiving failed");
-            let _ = ui_state_actor.send(SetModalDialog(None)).await;
-            if let DialogResult::Accept { result: new_metric } = dialog_result {
-                if let Err(err) = sv_client_actor
-                    .send(CreateMetrics {
-                        sensor_id,
-                        metrics: vec![new_metric],
-                    })
+                // The sensor may have got metrics while the dialog was open
+                let sensors =
+                    app.sv_client_actor.send(GetStateSnapshot).await.ok().and_then(Result::ok).unwrap_or_default();
+                if let Some(sensor) = sensors.get(&sensor_id) {
+                    let colliding = sensor.colliding_names(&metrics);
+                    if !colliding.is_empty() {
+                        if !app.confirm_suffixing(&colliding).await {
+                            log::warn!("Not creating metrics named like existing ones: {}", colliding.join(", "));
+                            return;
+                        }
+                        for (name, suffixed) in sensor.suffix_colliding(&mut metrics) {
+                            log::info!("Creating {name} as {suffixed}");
+                        }
+                    }
+                }
+
+                if let Err(err) = app
+                    .sv_client_actor
+                    .send(Correlated(correlation_id, CreateMetrics { sensor_id, metrics }))
                     .await
                 {
                     log::error!("Failed to send CreateMetrics: {err}");
                 }
-            }
-        });
-
-        let message = SetModalDialog(Some(ModalDialog::Metric(dialog_actor.clone())));
-        self.ui_state_actor.send(message).await?;
-
-        Ok(())
+            })
+        })
+        .await
     }
 
     async fn update_metric(&self) -> Result<()> {
@@ -420,53 +1736,49 @@ impl AppClient {
             .get(&sensor_id)
             .unwrap()
             .metrics
-            .iter()
-            .find(|metric| metric.metric_id() == &metric_id)
+            .get(&metric_id)
             .unwrap();
 
         let (tx, rx) = oneshot::channel();
         let dialog_actor = MetricDialogActor::new(
             MetricDialogState::new(
-                "Update Metric".to_owned(),
-                "Change current metric".to_owned(),
+                Msg::UpdateMetric.text().to_owned(),
+                Msg::UpdateMetricText.text().to_owned(),
                 vec![current_metric.clone()],
             )?,
             tx,
         )
         .start();
 
-        let ui_state_actor = self.ui_state_actor.clone();
-        let sv_client_actor = self.sv_client_actor.clone();
-
-        actix::spawn(async move {
-            let dialog_result = rx.await.expect("Receiving failed");
-            let _ = ui_state_actor.send(SetModalDialog(None)).await;
-            if let DialogResult::Accept { result: metric } = dialog_result {
-                if let Err(err) = sv_client_actor
-                    .send(UpdateMetric {
-                        sensor_id,
-                        metric_id,
-                        name: Some(metric.name().to_owned()),
-                        value_annotation: {
-                            match metric {
-                                Metric::Custom {
-                                    value_annotation, ..
-                                } => Some(value_annotation),
-                                _ => None,
-                            }
-                        },
-                    })
-                    .await
-                {
-                    log::error!("Failed to send MetricUpdate: {err}");
+        let app = self.clone();
+        let correlation_id = CorrelationId::new();
+        self.open_dialog(ModalDialog::Metric(dialog_actor), rx, move |dialog_result| {
+            correlation::scope(correlation_id, async move {
+                if let DialogResult::Accept { result: mut metrics } = dialog_result {
+                    let metric = metrics.remove(0);
+                    if let Err(err) = app
+                        .sv_client_actor
+                        .send(Correlated(correlation_id, UpdateMetric {
+                            sensor_id,
+                            metric_id,
+                            name: Some(metric.name().to_owned()),
+                            value_annotation: {
+                                match metric {
+                                    Metric::Custom {
+                                        value_annotation, ..
+                                    } => Some(value_annotation),
+                                    _ => None,
+                                }
+                            },
+                        }))
+                        .await
+                    {
+                        log::error!("Failed to send MetricUpdate: {err}");
+                    }
                 }
-            }
-        });
-
-        let message = SetModalDialog(Some(ModalDialog::Metric(dialog_actor.clone())));
-        self.ui_state_actor.send(message).await?;
-
-        Ok(())
+            })
+        })
+        .await
     }
 
     async fn delete_metric(&self) -> Result<()> {
@@ -480,42 +1792,38 @@ impl AppClient {
         let (tx, rx) = oneshot::channel::<DialogResult<()>>();
         let dialog_actor = ConfirmationDialogActor::new(
             ConfirmationDialogState {
-                title: "Delete Metric".to_owned(),
-                text: format!("Delete Metric # {} / #{}?", sensor_id, metric_id),
+                title: Msg::DeleteMetric.text().to_owned(),
+                text: Msg::DeleteMetricText.format(&[("sensor", &sensor_id), ("metric", &metric_id)]),
                 focused_button: Some(DialogButton::Cancel),
             },
             tx,
         )
         .start();
 
-        let ui_state_actor = self.ui_state_actor.clone();
-        let sv_client_actor = self.sv_client_actor.clone();
-
-        actix::spawn(async move {
-            let dialog_result = rx.await.expect("Receiving failed");
-            let _ = ui_state_actor.send(SetModalDialog(None)).await;
-            if matches!(dialog_result, DialogResult::Accept { result: () }) {
-                if let Err(err) = sv_client_actor
-                    .send(DeleteMetric {
-                        sensor_id,
-                        metric_id,
-                    })
-                    .await
-                {
-                    log::error!("Failed to send MetricDelete for {sensor_id}/{metric_id}: {err}");
+        let app = self.clone();
+        let correlation_id = CorrelationId::new();
+        self.open_dialog(ModalDialog::Confirmation(dialog_actor), rx, move |dialog_result| {
+            correlation::scope(correlation_id, async move {
+                if matches!(dialog_result, DialogResult::Accept { result: () }) {
+                    if let Err(err) = app
+                        .sv_client_actor
+                        .send(Correlated(correlation_id, DeleteMetric {
+                            sensor_id,
+                            metric_id,
+                        }))
+                        .await
+                    {
+                        log::error!("Failed to send MetricDelete for {sensor_id}/{metric_id}: {err}");
+                    }
                 }
-            }
-        });
-
-        let message = SetModalDialog(Some(ModalDialog::Confirmation(dialog_actor.clone())));
-        self.ui_state_actor.send(message).await?;
-
-        Ok(())
+            })
+        })
+        .await
     }
 
     async fn push_value(&self) -> Result<()> {
         let (sensors, ui_state) = self.current_state().await?;
-        let (Some((_, sensor_id)), Some((metric_index, metric_id))) =
+        let (Some((_, sensor_id)), Some((_, metric_id))) =
             (ui_state.current_sensor, ui_state.current_metric)
         else {
             return Ok(());
@@ -525,7 +1833,7 @@ impl AppClient {
             .get(&sensor_id)
             .unwrap()
             .metrics
-            .get(metric_index)
+            .get(&metric_id)
             .unwrap()
             .clone();
 
@@ -534,15 +1842,15 @@ impl AppClient {
         let default_value = ui_state
             .livedata
             .get(&(sensor_id, metric_id))
-            .map(|window| window.data.last().map(|(_, val)| val.to_string()))
-            .flatten();
+            .and_then(|window| window.last_value())
+            .map(|value| ValueFormat::of(&metric).editable(value));
 
         let (tx, rx) = oneshot::channel();
         let dialog_actor = InputDialogActor::new(
             InputDialogState {
-                title: "Push Value to Metric".to_owned(),
-                text: format!("Push value to Metric {metric_name}?"),
-                label: "Value:".to_owned(),
+                title: Msg::PushValue.text().to_owned(),
+                text: Msg::PushValueText.format(&[("metric", &metric_name)]),
+                label: Msg::ValueLabel.text().to_owned(),
                 text_input: default_value,
                 focused_button: Some(DialogButton::Ok),
             },
@@ -550,43 +1858,213 @@ impl AppClient {
         )
         .start();
 
-        let ui_state_actor = self.ui_state_actor.clone();
-        let sv_client_actor = self.sv_client_actor.clone();
+        let app = self.clone();
+        let correlation_id = CorrelationId::new();
+        self.open_dialog(ModalDialog::Input(dialog_actor), rx, move |dialog_result| {
+            correlation::scope(correlation_id, async move {
+                if let DialogResult::Accept { result: new_value } = dialog_result {
+                    let metric_value = metric.parse_value(&new_value);
+
+                    if let Err(err) = &metric_value {
+                        log::error!("Failed to parse \"{new_value}\": {err}");
+                        return;
+                    }
 
-        actix::spawn(async move {
-            let dialog_result = rx.await.expect("Receiving failed");
-            let _ = ui_state_actor.send(SetModalDialog(None)).await;
-            if let DialogResult::Accept { result: new_value } = dialog_result {
-                let metric_value = match &metric {
-                    Metric::Predefined { .. } => ValueType::Double.to_value(&new_value),
-                    Metric::Custom { value_type, .. } => value_type.to_value(&new_value),
-                };
+                    let metric_value = metric_value.unwrap();
+
+                    if let Err(err) = app
+                        .sv_client_actor
+                        .send(Correlated(correlation_id, PushValue {
+                            sensor_id,
+                            metric_id,
+                            value: metric_value,
+                            timestamp: None,
+                        }))
+                        .await
+                    {
+                        log::error!("Failed to Push Metric: {err}");
+                    }
+                }
+            })
+        })
+        .await
+    }
 
-                if let Err(err) = &metric_value {
-                    log::error!("Failed to parse \"{new_value}\": {err}");
-                    return;
+    async fn edit_alert(&self) -> Result<()> {
+        let (sensors, ui_state) = self.current_state().await?;
+        let (Some((_, sensor_id)), Some((_, metric_id))) =
+            (ui_state.current_sensor, ui_state.current_metric)
+        else {
+            return Ok(());
+        };
+        let Some(sensor) = sensors.get(&sensor_id) else {
+            return Ok(());
+        };
+        let Some(metric) = sensor.metrics.get(&metric_id) else {
+            return Ok(());
+        };
+        let (sensor_name, metric_name) = (sensor.name.clone(), metric.name().clone());
+
+        let rules = self.sv_client_actor.send(GetAlertRules).await??;
+        // Only the rule made for exactly this metric is edited, wildcard rules stay as they are
+        let is_own_rule = {
+            let (sensor_name, metric_name) = (sensor_name.clone(), metric_name.clone());
+            move |rule: &AlertRule| rule.sensor == sensor_name && rule.metric == metric_name
+        };
+        let own_rule = rules.iter().find(|rule| is_own_rule(rule)).cloned();
+
+        let value = ui_state
+            .livedata
+            .get(&(sensor_id, *metric.metric_id()))
+            .and_then(|window| window.last_value())
+            .cloned();
+
+        let (tx, rx) = oneshot::channel();
+        let dialog_actor = AlertDialogActor::new(
+            AlertDialogState::new(
+                sensor_name.clone(),
+                metric_name.clone(),
+                own_rule.as_ref(),
+                self.notifications.sink_names(),
+                value,
+            ),
+            tx,
+        )
+        .start();
+
+        let app = self.clone();
+
+        self.open_dialog(ModalDialog::Alert(dialog_actor), rx, move |dialog_result| async move {
+            let DialogResult::Accept { result: rule } = dialog_result else {
+                return;
+            };
+
+            let mut rules: Vec<AlertRule> =
+                rules.into_iter().filter(|rule| !is_own_rule(rule)).collect();
+            if let Some(rule) = rule {
+                // Takes precedence over the wildcard rules
+                rules.insert(0, rule);
+            }
+            let updated = async {
+                app.sv_client_actor.send(SetAlertRules(rules.clone())).await??;
+                match &app.alerts_file {
+                    Some(alerts_file) => write_alert_rules(alerts_file, &rules),
+                    None => Ok(()),
                 }
+            }
+            .await;
+            if let Err(err) = updated {
+                log::error!("Failed to update the alert of {sensor_name}/{metric_name}: {err}");
+            }
+        })
+        .await
+    }
 
-                let metric_value = metric_value.unwrap();
+    async fn simulate(&self) -> Result<()> {
+        let (sensors, ui_state) = self.current_state().await?;
+        let (Some((_, sensor_id)), Some((_, metric_id))) =
+            (ui_state.current_sensor, ui_state.current_metric)
+        else {
+            return Ok(());
+        };
+        let Some(metric) = sensors
+            .get(&sensor_id)
+            .and_then(|sensor| sensor.metrics.get(&metric_id))
+            .cloned()
+        else {
+            return Ok(());
+        };
 
-                if let Err(err) = sv_client_actor
-                    .send(PushValue {
-                        sensor_id,
-                        metric_id,
-                        value: metric_value,
-                        timestamp: None,
-                    })
-                    .await
-                {
-                    log::error!("Failed to Push Metric: {err}");
+        let running = self
+            .simulator_actor
+            .send(GetSimulation {
+                sensor_id,
+                metric_id,
+            })
+            .await?;
+        if let Some((generator, interval)) = running {
+            return self
+                .stop_simulation(sensor_id, &metric, generator, interval)
+                .await;
+        }
+
+        let (tx, rx) = oneshot::channel();
+        let dialog_actor = InputDialogActor::new(
+            InputDialogState {
+                title: Msg::StartSimulation.text().to_owned(),
+                text: Msg::StartSimulationText.format(&[("metric", metric.name())]),
+                label: Msg::GeneratorLabel.text().to_owned(),
+                text_input: Some(DEFAULT_SIMULATION.to_owned()),
+                focused_button: Some(DialogButton::Ok),
+            },
+            tx,
+        )
+        .start();
+
+        let app = self.clone();
+
+        self.open_dialog(ModalDialog::Input(dialog_actor), rx, move |dialog_result| async move {
+            let DialogResult::Accept { result: spec } = dialog_result else {
+                return;
+            };
+
+            match Generator::parse_with_interval(&spec) {
+                Ok((generator, interval)) => {
+                    let _ = app
+                        .simulator_actor
+                        .send(StartSimulation {
+                            sensor_id,
+                            metric,
+                            generator,
+                            interval,
+                        })
+                        .await;
+                }
+                Err(err) => {
+                    log::error!("Failed to start the simulation: {err}");
                 }
             }
-        });
+        })
+        .await
+    }
+
+    async fn stop_simulation(
+        &self,
+        sensor_id: SensorId,
+        metric: &Metric,
+        generator: Generator,
+        interval: Duration,
+    ) -> Result<()> {
+        let metric_id = *metric.metric_id();
+        let (tx, rx) = oneshot::channel::<DialogResult<()>>();
+        let dialog_actor = ConfirmationDialogActor::new(
+            ConfirmationDialogState {
+                title: Msg::StopSimulation.text().to_owned(),
+                text: Msg::StopSimulationText.format(&[
+                    ("generator", &generator),
+                    ("secs", &interval.as_secs_f64()),
+                    ("metric", metric.name()),
+                ]),
+                focused_button: Some(DialogButton::Ok),
+            },
+            tx,
+        )
+        .start();
 
-        let message = SetModalDialog(Some(ModalDialog::Input(dialog_actor.clone())));
-        self.ui_state_actor.send(message).await?;
+        let app = self.clone();
 
-        Ok(())
+        self.open_dialog(ModalDialog::Confirmation(dialog_actor), rx, move |dialog_result| async move {
+            if matches!(dialog_result, DialogResult::Accept { result: () }) {
+                let _ = app
+                    .simulator_actor
+                    .send(StopSimulation {
+                        sensor_id,
+                        metric_id,
+                    })
+                    .await;
+            }
+        })
+        .await
     }
 }
 
@@ -594,13 +2072,23 @@ impl Actor for AppClient {
     type Context = Context<Self>;
 
     fn started(&mut self, ctx: &mut Self::Context) {
+        ctx.set_mailbox_capacity(self.mailbox_capacity);
+        if let Some(mut ui_state_context) = self.ui_state_context.take() {
+            ui_state_context.set_mailbox_capacity(self.mailbox_capacity);
+            ui_state_context.run(UIState::default());
+        }
+
         let sv_client_actor = self.sv_client_actor.clone();
         let weak_this = ctx.address().downgrade().recipient();
+        let weak_this_connection = ctx.address().downgrade().recipient();
         ctx.spawn(
             async move {
                 let _ = sv_client_actor
                     .send(SubscribeToStateEvents(weak_this))
                     .await;
+                let _ = sv_client_actor
+                    .send(SubscribeToConnectionEvents(weak_this_connection))
+                    .await;
             }
             .into_actor(self),
         );
@@ -611,6 +2099,62 @@ impl Actor for AppClient {
             .filter_map(|term_event| async { term_event.ok().map(|event| TermEvent(event)) });
 
         ctx.add_stream(event_stream);
+
+        if let Some(idle_timeout) = self.idle_timeout {
+            ctx.run_interval(SCREENSAVER_PAGE_DURATION, move |act, ctx| {
+                act.update_screensaver(idle_timeout, ctx)
+            });
+        }
+
+        ctx.run_interval(CLOCK_CHECK_INTERVAL, |act, ctx| act.check_clock_jump(ctx));
+
+        if let Some(ping_interval) = self.ping_interval {
+            ctx.run_interval(ping_interval, move |act, ctx| act.ping(ping_interval, ctx));
+        }
+    }
+}
+
+impl Handler<ConnectionEvent> for AppClient {
+    type Result = ();
+
+    fn handle(&mut self, event: ConnectionEvent, ctx: &mut Self::Context) -> Self::Result {
+        let offline_for = match event {
+            ConnectionEvent::Lost => {
+                self.notify(Notification::new(
+                    Severity::Critical,
+                    Category::Connection,
+                    Msg::ConnectionLost.text(),
+                    Msg::Reconnecting.text(),
+                ));
+                None
+            }
+            ConnectionEvent::Restored { offline_for } => {
+                self.notify(Notification::new(
+                    Severity::Info,
+                    Category::Connection,
+                    Msg::ConnectionRestored.text(),
+                    &Msg::OfflineFor.format(&[("duration", &utils::format_duration(offline_for))]),
+                ));
+                Some(offline_for)
+            }
+        };
+
+        let app = self.clone();
+        ctx.spawn(
+            async move {
+                let _ = app.ui_state_actor.send(SetOffline(offline_for.is_none())).await;
+                let Some(offline_for) = offline_for else {
+                    app.rerender().await;
+                    return;
+                };
+                let reason =
+                    Msg::ReconnectedAfter.format(&[("duration", &utils::format_duration(offline_for))]);
+                if let Err(err) = app.summarize_inventory_changes(reason).await {
+                    log::error!("Failed to refresh the sensors: {err}");
+                }
+            }
+            .into_actor(self),
+        );
     }
 }
 
@@ -622,6 +2166,20 @@ impl StreamHandler<TermEvent> for AppClient {
     fn handle(&mut self, TermEvent(event): TermEvent, ctx: &mut Self::Context) {
         match event {
             CrosstermEvent::Key(key_event) => {
+                self.last_input_at = Instant::now();
+                if self.screensaver_tick.take().is_some() {
+                    // The key only wakes the UI up
+                    let app = self.clone();
+                    ctx.spawn(
+                        async move {
+                            let _ = app.ui_state_actor.send(SetScreensaver(None)).await;
+                            app.rerender().await;
+                        }
+                        .into_actor(self),
+                    );
+                    return;
+                }
+
                 let mut app = self.clone();
                 ctx.spawn(
                     async move {
@@ -632,6 +2190,9 @@ impl StreamHandler<TermEvent> for AppClient {
                 );
             }
 
+            CrosstermEvent::FocusGained => self.focused = true,
+            CrosstermEvent::FocusLost => self.focused = false,
+
             CrosstermEvent::Resize(..) => {
                 let app = self.clone();
                 ctx.spawn(
@@ -686,19 +2247,34 @@ impl Handler<SensorStateEvent> for AppClient {
                 metric_id,
                 value,
                 timestamp,
+                received_at,
             } => {
                 let ui_state_actor = self.ui_state_actor.clone();
+                self.livedata_queued();
                 ctx.spawn(
                     async move {
-                        let _ = ui_state_actor
+                        let follow = ui_state_actor
                             .send(AcceptLivedata {
                                 sensor_id,
                                 metric_id,
                                 value,
                                 timestamp,
+                                received_at,
                             })
                             .await;
-                        app.rerender().await;
+                        let shed = app.livedata_accepted();
+                        // Muted livedata changes nothing on the screen
+                        if let Ok(None) = follow {
+                            return;
+                        }
+                        if let Ok(Some(true)) = follow
+                            && let Err(err) = app.focus_metric(sensor_id, metric_id).await
+                        {
+                            log::warn!("Failed to follow livedata: {err}");
+                        }
+                        if !shed {
+                            app.rerender().await;
+                        }
                     }
                     .into_actor(self),
                 );
@@ -735,14 +2311,108 @@ impl Handler<SensorStateEvent> for AppClient {
                 );
             }
 
+            AlertRaised {
+                sensor_id,
+                metric_id,
+                message,
+                critical,
+                sinks,
+                silenced,
+                ..
+            } => {
+                // Still marked, only not notified
+                if !silenced {
+                    self.notify(
+                    Notification::new(
+                        if critical {
+                            Severity::Critical
+                        } else {
+                            Severity::Warning
+                        },
+                        Category::Alert,
+                        if critical {
+                            Msg::CriticalAlert.text()
+                        } else {
+                            Msg::AlertRaised.text()
+                        },
+                        &message,
+                    )
+                    .to_sinks(sinks),
+                );
+                }
+                ctx.spawn(
+                    async move {
+                        let _ = app
+                            .ui_state_actor
+                            .send(SetAlert {
+                                sensor_id,
+                                metric_id,
+                                raised: true,
+                            })
+                            .await;
+                        app.rerender().await;
+                    }
+                    .into_actor(self),
+                );
+            }
+
+            SilencingChanged(silencing) => {
+                ctx.spawn(
+                    async move {
+                        let _ = app.ui_state_actor.send(SetSilencing(silencing)).await;
+                        app.rerender().await;
+                    }
+                    .into_actor(self),
+                );
+            }
+
+            AlertCleared {
+                sensor_id,
+                metric_id,
+            } => {
+                ctx.spawn(
+                    async move {
+                        let _ = app
+                            .ui_state_actor
+                            .send(SetAlert {
+                                sensor_id,
+                                metric_id,
+                                raised: false,
+                            })
+                            .await;
+                        app.rerender().await;
+                    }
+                    .into_actor(self),
+                );
+            }
+
             Error {
-                message, 
+                message,
                 code,
+                correlation_id,
+                scheme,
+                timestamp,
+                request,
             } => {
-                let ui_state_actor = self.ui_state_actor.clone();
+                match correlation_id {
+                    Some(correlation_id) => {
+                        log::error!("Error #{code}: {message} (op:{correlation_id})")
+                    }
+                    None => log::error!("Error #{code}: {message}"),
+                }
                 ctx.spawn(
                     async move {
-                        let _ = ui_state_actor.send(AppendError{message, code}).await;
+                        let _ = app
+                            .ui_state_actor
+                            .send(AddError {
+                                scheme,
+                                code,
+                                message,
+                                timestamp,
+                                correlation_id,
+                                request,
+                            })
+                            .await;
                         app.rerender().await;
                     }
                     .into_actor(self),
@@ -0,0 +1,337 @@
+use crossterm::event::{KeyCode, KeyEvent};
+
+use eyre::{eyre, Result};
+
+use ratatui::layout::{Constraint, Direction, Layout};
+use ratatui::prelude::{Line, Span, Stylize};
+use ratatui::widgets::{Block, Clear};
+use ratatui::Frame;
+
+use strum::{EnumIter, EnumProperty};
+
+use crate::client::state::AlertRule;
+use crate::model::protocol::MetricValue;
+use crate::tui_app::dialog::generic::DialogResult;
+use crate::i18n::Msg;
+use crate::tui_app::dialog::render::*;
+use crate::tui_app::dialog::{DialogActor, KeyEventHandler};
+
+use crate::tui_app::theme::*;
+use UIElement::*;
+
+use crate::tui_app::utils::centered_rect_abs;
+use crate::utils::CircularEnum;
+
+/// Responds with the rule to store, `None` removes the rule of the metric
+pub type AlertDialogActor = DialogActor<AlertDialogState, Option<AlertRule>>;
+
+const LABEL_WIDTH: usize = 12;
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, EnumIter, EnumProperty)]
+enum Operator {
+    #[default]
+    #[strum(props(name = "above", hint = "MAX"))]
+    Above,
+    #[strum(props(name = "below", hint = "MIN"))]
+    Below,
+    #[strum(props(name = "outside", hint = "MIN..MAX"))]
+    Outside,
+    #[strum(props(name = "matches", hint = "REGEX"))]
+    Matches,
+}
+
+impl Operator {
+    fn name(&self) -> &'static str {
+        self.get_str("name").unwrap_or_default()
+    }
+
+    fn hint(&self) -> &'static str {
+        self.get_str("hint").unwrap_or_default()
+    }
+}
+
+/// Rows of the form, sinks follow the last one
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Field {
+    Operator,
+    Threshold,
+    Duration,
+    Severity,
+    Sink(usize),
+}
+
+const FIXED_FIELDS: usize = 4;
+
+/// Condition of the alert rule made for exactly the selected metric
+#[derive(Default, Clone)]
+pub struct AlertDialogState {
+    sensor: String,
+    metric: String,
+
+    operator: Operator,
+    threshold: String,
+    duration: String,
+    critical: bool,
+    sinks: Vec<(&'static str, bool)>,
+    /// Kept as is, hooks are only set in the config
+    hook: Option<String>,
+
+    /// Latest livedata the rule is previewed against
+    value: Option<MetricValue>,
+
+    focused_field: usize,
+}
+
+impl AlertDialogState {
+    pub fn new(
+        sensor: String,
+        metric: String,
+        rule: Option<&AlertRule>,
+        sink_names: Vec<&'static str>,
+        value: Option<MetricValue>,
+    ) -> Self {
+        let bound = |bound: Option<f64>| bound.map(|bound| bound.to_string()).unwrap_or_default();
+        let (operator, threshold) = match rule {
+            Some(rule) if rule.matches.is_some() => {
+                (Operator::Matches, rule.matches.clone().unwrap_or_default())
+            }
+            Some(rule) if rule.min.is_some() && rule.max.is_some() => {
+                (Operator::Outside, rule.condition())
+            }
+            Some(rule) if rule.min.is_some() => (Operator::Below, bound(rule.min)),
+            Some(rule) => (Operator::Above, bound(rule.max)),
+            None => (Operator::default(), String::new()),
+        };
+        let sinks = sink_names
+            .into_iter()
+            .map(|name| {
+                let picked = rule.is_some_and(|rule| rule.sinks.iter().any(|sink| sink == name));
+                (name, picked)
+            })
+            .collect();
+
+        Self {
+            operator,
+            threshold,
+            duration: rule
+                .and_then(|rule| rule.for_secs)
+                .map(|secs| secs.to_string())
+                .unwrap_or_default(),
+            critical: rule.is_some_and(|rule| rule.critical),
+            sinks,
+            hook: rule.and_then(|rule| rule.hook.clone()),
+            value,
+            sensor,
+            metric,
+            focused_field: 1,
+        }
+    }
+
+    fn fields_count(&self) -> usize {
+        FIXED_FIELDS + self.sinks.len()
+    }
+
+    fn field(&self, index: usize) -> Field {
+        match index {
+            0 => Field::Operator,
+            1 => Field::Threshold,
+            2 => Field::Duration,
+            3 => Field::Severity,
+            sink => Field::Sink(sink - FIXED_FIELDS),
+        }
+    }
+
+    fn rule(&self) -> Result<AlertRule> {
+        let threshold = self.threshold.trim();
+        let mut rule = match self.operator {
+            Operator::Outside => {
+                AlertRule::with_condition(self.sensor.clone(), self.metric.clone(), threshold)?
+            }
+            operator => {
+                let condition = match operator {
+                    Operator::Above => format!("..{threshold}"),
+                    Operator::Below => format!("{threshold}.."),
+                    _ => format!("~{threshold}"),
+                };
+                if threshold.is_empty() {
+                    return Err(eyre!("{} is required", operator.hint()));
+                }
+                AlertRule::with_condition(self.sensor.clone(), self.metric.clone(), &condition)?
+            }
+        };
+
+        let duration = self.duration.trim();
+        if !duration.is_empty() {
+            rule.for_secs = Some(
+                duration
+                    .parse()
+                    .map_err(|_| eyre!("Invalid duration '{duration}'"))?,
+            );
+        }
+        rule.critical = self.critical;
+        rule.sinks = self
+            .sinks
+            .iter()
+            .filter(|(_, picked)| *picked)
+            .map(|(name, _)| name.to_string())
+            .collect();
+        rule.hook = self.hook.clone();
+        rule.validate()?;
+        Ok(rule)
+    }
+
+    /// Whether the latest value would raise the alert
+    fn preview(&self) -> Line<'static> {
+        let rule = match self.rule() {
+            Ok(rule) => rule,
+            Err(err) => return Line::from(err.to_string()).themed(ErrorLog),
+        };
+        let Some(value) = &self.value else {
+            return Line::from(Msg::AlertNoLivedata.text());
+        };
+        match rule.violation(value) {
+            Ok(Some(violation)) => {
+                let after = rule
+                    .for_secs
+                    .map(|secs| format!(" after {secs}s"))
+                    .unwrap_or_default();
+                Line::from(format!("{value} {violation}, raised{after}")).themed(MetricPropsBlockAlert)
+            }
+            Ok(None) => Line::from(format!("{value} raises no alert")),
+            Err(err) => Line::from(err.to_string()).themed(ErrorLog),
+        }
+    }
+}
+
+impl KeyEventHandler<Option<AlertRule>> for AlertDialogState {
+    fn handle_key_event(&mut self, key_event: KeyEvent) -> Option<DialogResult<Option<AlertRule>>> {
+        let field = self.field(self.focused_field);
+        match key_event.code {
+            KeyCode::Esc => return Some(DialogResult::Cancel),
+            KeyCode::Delete => return Some(DialogResult::Accept { result: None }),
+            // The preview tells what's wrong with an invalid rule
+            KeyCode::Enter => {
+                if let Ok(rule) = self.rule() {
+                    return Some(DialogResult::Accept { result: Some(rule) });
+                }
+            }
+            KeyCode::Down | KeyCode::Tab => {
+                self.focused_field = (self.focused_field + 1) % self.fields_count();
+            }
+            KeyCode::Up | KeyCode::BackTab => {
+                self.focused_field = self
+                    .focused_field
+                    .checked_sub(1)
+                    .unwrap_or(self.fields_count() - 1);
+            }
+            KeyCode::Left | KeyCode::Right => match field {
+                Field::Operator if key_event.code == KeyCode::Left => {
+                    self.operator = self.operator.prev()
+                }
+                Field::Operator => self.operator = self.operator.next(),
+                Field::Severity => self.critical = !self.critical,
+                _ => {}
+            },
+            KeyCode::Char(' ') if matches!(field, Field::Sink(_)) => {
+                if let Field::Sink(sink) = field {
+                    self.sinks[sink].1 = !self.sinks[sink].1;
+                }
+            }
+            KeyCode::Char(char) => match field {
+                Field::Threshold => self.threshold.push(char),
+                Field::Duration if char.is_ascii_digit() => self.duration.push(char),
+                _ => {}
+            },
+            KeyCode::Backspace => match field {
+                Field::Threshold => {
+                    self.threshold.pop();
+                }
+                Field::Duration => {
+                    self.duration.pop();
+                }
+                _ => {}
+            },
+            _ => {}
+        }
+        None
+    }
+}
+
+impl Renderable for AlertDialogState {
+    fn render(&self, frame: &mut Frame) {
+        let rows_count = self.fields_count() + 4;
+
+        let area = frame.area();
+        let area = centered_rect_abs(64, rows_count as u16 + 2, area);
+
+        let instructions = Line::from(vec![
+            instruction(Msg::Select),
+            "↑/↓".themed(DialogInstructionsActionText).bold(),
+            instruction(Msg::Change),
+            "←/→/␣".themed(DialogInstructionsActionText).bold(),
+            instruction(Msg::Save),
+            "↵".themed(DialogInstructionsActionText).bold(),
+            instruction(Msg::Remove),
+            "<Del>".themed(DialogInstructionsActionText).bold(),
+            instruction(Msg::Close),
+            "<Esc> ".themed(DialogInstructionsActionText).bold(),
+        ]);
+
+        let pad = Block::bordered()
+            .title(Line::from(Msg::AlertRule.text()).centered())
+            .title_bottom(instructions.centered())
+            .themed(DialogPad);
+        let content_area = centered_rect_abs(area.width - 4, area.height - 2, area);
+
+        let content_layout = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints(vec![Constraint::Length(1); rows_count])
+            .split(content_area);
+
+        let row = |index: usize, label: &str, value: String| {
+            let value_element = if index == self.focused_field {
+                DialogTextInputFocused
+            } else {
+                DialogTextInput
+            };
+            Line::from(vec![
+                Span::raw(format!("{label:<LABEL_WIDTH$}")),
+                value.themed(value_element),
+            ])
+        };
+
+        frame.render_widget(Clear, area);
+        frame.render_widget(pad, area);
+        frame.render_widget(
+            Line::from(format!("{}/{}", self.sensor, self.metric)).bold().centered(),
+            content_layout[0],
+        );
+
+        let mut rows = vec![
+            row(0, Msg::AlertWhen.text(), format!("◀ {:^9} ▶", self.operator.name())),
+            row(1, self.operator.hint(), format!("{:<24}", self.threshold)),
+            row(2, Msg::AlertFor.text(), format!("{:<24}", self.duration)),
+            row(
+                3,
+                Msg::AlertSeverity.text(),
+                format!("◀ {:^9} ▶", if self.critical { "critical" } else { "warning" }),
+            ),
+        ];
+        for (sink, (name, picked)) in self.sinks.iter().enumerate() {
+            let label = if sink == 0 { Msg::AlertNotify.text() } else { "" };
+            let check = if *picked { "x" } else { " " };
+            rows.push(row(FIXED_FIELDS + sink, label, format!("[{check}] {name}")));
+        }
+        for (index, line) in rows.into_iter().enumerate() {
+            frame.render_widget(line, content_layout[index + 1]);
+        }
+
+        frame.render_widget(self.preview().centered(), content_layout[rows_count - 2]);
+        if self.sinks.iter().all(|(_, picked)| !picked) {
+            frame.render_widget(
+                Line::from(Msg::AlertNoSinks.text()).italic().centered(),
+                content_layout[rows_count - 1],
+            );
+        }
+    }
+}
@@ -0,0 +1,143 @@
+use crossterm::event::{KeyCode, KeyEvent};
+
+use ratatui::prelude::{Line, Stylize};
+use ratatui::widgets::{Block, Clear, Paragraph};
+use ratatui::Frame;
+
+use crate::client::state::AlertRecord;
+use crate::tui_app::dialog::generic::DialogResult;
+use crate::i18n::Msg;
+use crate::tui_app::dialog::render::*;
+use crate::tui_app::dialog::{DialogActor, KeyEventHandler};
+
+use crate::tui_app::theme::*;
+use UIElement::*;
+
+use crate::tui_app::utils::centered_rect_abs;
+
+pub type AlertHistoryDialogActor = DialogActor<AlertHistoryDialogState, ()>;
+
+/// Alerts raised lately, the latest first. Typed text filters them by their message.
+#[derive(Default, Clone)]
+pub struct AlertHistoryDialogState {
+    alerts: Vec<AlertRecord>,
+    filter: String,
+    active_only: bool,
+    focused: usize,
+}
+
+impl AlertHistoryDialogState {
+    pub fn new(alerts: Vec<AlertRecord>) -> Self {
+        Self {
+            alerts: alerts.into_iter().rev().collect(),
+            ..Self::default()
+        }
+    }
+
+    fn visible(&self) -> Vec<&AlertRecord> {
+        let filter = self.filter.to_lowercase();
+        self.alerts
+            .iter()
+            .filter(|alert| !self.active_only || alert.resolved_at.is_none())
+            .filter(|alert| alert.message.to_lowercase().contains(&filter))
+            .collect()
+    }
+}
+
+impl KeyEventHandler<()> for AlertHistoryDialogState {
+    fn handle_key_event(&mut self, key_event: KeyEvent) -> Option<DialogResult<()>> {
+        match key_event.code {
+            KeyCode::Esc | KeyCode::Enter => return Some(DialogResult::Accept { result: () }),
+            KeyCode::Down => {
+                self.focused = (self.focused + 1).min(self.visible().len().saturating_sub(1));
+            }
+            KeyCode::Up => self.focused = self.focused.saturating_sub(1),
+            KeyCode::Tab => {
+                self.active_only = !self.active_only;
+                self.focused = 0;
+            }
+            KeyCode::Backspace => {
+                self.filter.pop();
+                self.focused = 0;
+            }
+            KeyCode::Char(c) => {
+                self.filter.push(c);
+                self.focused = 0;
+            }
+            _ => {}
+        }
+        None
+    }
+}
+
+impl Renderable for AlertHistoryDialogState {
+    fn render(&self, frame: &mut Frame) {
+        let alerts = self.visible();
+        let area = frame.area();
+        let area = centered_rect_abs(
+            area.width.saturating_sub(8).clamp(40, 120),
+            (alerts.len() as u16 + 4).clamp(6, area.height),
+            area,
+        );
+
+        let instructions = Line::from(vec![
+            instruction(Msg::Select),
+            "↑/↓".themed(DialogInstructionsActionText).bold(),
+            instruction(Msg::ActiveOnly),
+            "<Tab>".themed(DialogInstructionsActionText).bold(),
+            instruction(Msg::Close),
+            "<Esc> ".themed(DialogInstructionsActionText).bold(),
+        ]);
+
+        let pad = Block::bordered()
+            .title(
+                Line::from(format!(" {} ", Msg::AlertHistory.format(&[("count", &alerts.len())]))).centered(),
+            )
+            .title_bottom(instructions.centered())
+            .themed(DialogPad);
+
+        let mut lines = Vec::with_capacity(alerts.len() + 2);
+        lines.push(Line::from(format!(" {} {}", Msg::SearchPrompt.text(), self.filter)));
+        lines.push(Line::from(""));
+        if alerts.is_empty() {
+            lines.push(Line::from(format!(" {}", Msg::NoAlerts.text())).themed(DialogTextInput));
+        }
+        let format = |at: &crate::model::Timestamp| at.to_datetime().format("%m-%d %H:%M:%S").to_string();
+        for (i, alert) in alerts.iter().enumerate() {
+            let element = if i == self.focused {
+                DialogTextInputFocused
+            } else if alert.resolved_at.is_none() {
+                ErrorLog
+            } else {
+                DialogTextInput
+            };
+            let resolved_at = alert
+                .resolved_at
+                .as_ref()
+                .map_or_else(|| Msg::AlertActive.text().to_owned(), format);
+            let critical = if alert.critical { "!" } else { " " };
+            let silenced = if alert.silenced {
+                format!(" ({})", Msg::SilencedBadge.text())
+            } else {
+                String::new()
+            };
+            lines.push(
+                Line::from(format!(
+                    "{critical}{} → {resolved_at:14}  {}{silenced}",
+                    format(&alert.fired_at),
+                    alert.message
+                ))
+                .themed(element),
+            );
+        }
+        // Keeps the focused alert in view, below the filter
+        let visible = area.height.saturating_sub(2) as usize;
+        let scroll = (self.focused + 3).saturating_sub(visible);
+        let content = Paragraph::new(lines)
+            .scroll((scroll as u16, 0))
+            .block(pad);
+
+        frame.render_widget(Clear, area);
+        frame.render_widget(content, area);
+    }
+}
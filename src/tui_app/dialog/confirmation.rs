@@ -8,6 +8,7 @@ use ratatui::Frame;
 use strum::IntoEnumIterator;
 
 use crate::tui_app::dialog::generic::{DialogButton, DialogResult};
+use crate::i18n::Msg;
 use crate::tui_app::dialog::render::*;
 use crate::tui_app::dialog::{DialogActor, KeyEventHandler};
 
@@ -60,11 +61,11 @@ impl Renderable for ConfirmationDialogState {
         let area = centered_rect_abs(50, 5, area);
 
         let instructions = Line::from(vec![
-            " Select Button ".themed(DialogInstructionsText),
+            instruction(Msg::SelectButton),
             "↹ ".themed(DialogInstructionsActionText).bold(),
-            " Press ".themed(DialogInstructionsText),
+            instruction(Msg::Press),
             "↵".themed(DialogInstructionsActionText).bold(),
-            " Close ".themed(DialogInstructionsText),
+            instruction(Msg::Close),
             "<Esc> ".themed(DialogInstructionsActionText).bold(),
         ]);
         let pad = Block::bordered()
@@ -0,0 +1,149 @@
+use crossterm::event::{KeyCode, KeyEvent};
+
+use ratatui::prelude::{Line, Stylize};
+use ratatui::widgets::{Block, Clear, Paragraph};
+use ratatui::Frame;
+
+use crate::tui_app::dialog::generic::DialogResult;
+use crate::i18n::Msg;
+use crate::tui_app::dialog::render::*;
+use crate::tui_app::dialog::{DialogActor, KeyEventHandler};
+use crate::tui_app::ui_state::ErrorEntry;
+
+use crate::tui_app::theme::*;
+use UIElement::*;
+
+use crate::tui_app::utils::centered_rect_abs;
+
+pub type ErrorsDialogActor = DialogActor<ErrorsDialogState, ErrorsDialogResult>;
+
+/// What to do with the errors once the dialog is closed
+#[derive(Debug, Clone, Default)]
+pub struct ErrorsDialogResult {
+    pub dismissed: Vec<u64>,
+    /// Error whose request is to be sent again
+    pub retry: Option<u64>,
+}
+
+/// Errors the agent replied with, the latest first
+#[derive(Default, Clone)]
+pub struct ErrorsDialogState {
+    errors: Vec<ErrorEntry>,
+    focused: usize,
+    dismissed: Vec<u64>,
+}
+
+impl ErrorsDialogState {
+    pub fn new(errors: impl DoubleEndedIterator<Item = ErrorEntry>) -> Self {
+        Self {
+            errors: errors.rev().collect(),
+            ..Self::default()
+        }
+    }
+
+    fn close(&mut self, retry: Option<u64>) -> Option<DialogResult<ErrorsDialogResult>> {
+        Some(DialogResult::Accept {
+            result: ErrorsDialogResult {
+                dismissed: std::mem::take(&mut self.dismissed),
+                retry,
+            },
+        })
+    }
+}
+
+impl KeyEventHandler<ErrorsDialogResult> for ErrorsDialogState {
+    fn handle_key_event(&mut self, key_event: KeyEvent) -> Option<DialogResult<ErrorsDialogResult>> {
+        match key_event.code {
+            KeyCode::Esc | KeyCode::Enter => return self.close(None),
+            KeyCode::Down => {
+                self.focused = (self.focused + 1).min(self.errors.len().saturating_sub(1));
+            }
+            KeyCode::Up => self.focused = self.focused.saturating_sub(1),
+            KeyCode::Char('d') | KeyCode::Delete if !self.errors.is_empty() => {
+                let error = self.errors.remove(self.focused);
+                self.dismissed.push(error.id);
+                self.focused = self.focused.min(self.errors.len().saturating_sub(1));
+            }
+            KeyCode::Char('D') => {
+                self.dismissed.extend(self.errors.drain(..).map(|error| error.id));
+                self.focused = 0;
+            }
+            KeyCode::Char('r') => {
+                if let Some(error) = self.errors.get(self.focused)
+                    && error.is_retriable()
+                {
+                    return self.close(Some(error.id));
+                }
+            }
+            _ => {}
+        }
+        None
+    }
+}
+
+impl Renderable for ErrorsDialogState {
+    fn render(&self, frame: &mut Frame) {
+        let area = frame.area();
+        let area = centered_rect_abs(
+            area.width.saturating_sub(8).clamp(40, 100),
+            (self.errors.len() as u16 * 2 + 3).clamp(5, area.height),
+            area,
+        );
+
+        let instructions = Line::from(vec![
+            instruction(Msg::Select),
+            "↑/↓".themed(DialogInstructionsActionText).bold(),
+            instruction(Msg::Retry),
+            "r".themed(DialogInstructionsActionText).bold(),
+            instruction(Msg::Dismiss),
+            "d".themed(DialogInstructionsActionText).bold(),
+            instruction(Msg::All),
+            "D".themed(DialogInstructionsActionText).bold(),
+            instruction(Msg::Close),
+            "<Esc> ".themed(DialogInstructionsActionText).bold(),
+        ]);
+
+        let pad = Block::bordered()
+            .title(
+                Line::from(format!(" {} ", Msg::Errors.format(&[("count", &self.errors.len())]))).centered(),
+            )
+            .title_bottom(instructions.centered())
+            .themed(DialogPad);
+
+        let mut lines = Vec::with_capacity(self.errors.len() * 2);
+        if self.errors.is_empty() {
+            lines.push(Line::from(format!(" {}", Msg::NoErrors.text())).themed(DialogTextInput));
+        }
+        for (i, error) in self.errors.iter().enumerate() {
+            let element = if i == self.focused {
+                DialogTextInputFocused
+            } else {
+                DialogTextInput
+            };
+            let retriable = if error.is_retriable() { " ↻" } else { "" };
+            lines.push(
+                Line::from(format!(
+                    " {} #{} {}{retriable}",
+                    error.timestamp.to_datetime().format("%H:%M:%S"),
+                    error.code,
+                    error.scheme.get_topics().0,
+                ))
+                .themed(element),
+            );
+            let mut detail = format!("   {}", error.message);
+            if let Some(correlation_id) = &error.correlation_id {
+                detail += &format!(" (op:{correlation_id})");
+            }
+            lines.push(Line::from(detail));
+        }
+        // Keeps the focused error in view
+        let visible = area.height.saturating_sub(2) as usize;
+        let scroll = (self.focused * 2 + 2).saturating_sub(visible);
+        let content = Paragraph::new(lines)
+            .scroll((scroll as u16, 0))
+            .block(pad);
+
+        frame.render_widget(Clear, area);
+        frame.render_widget(content, area);
+    }
+}
@@ -9,7 +9,10 @@ use strum::EnumIter;
 
 use tokio::sync::oneshot;
 
-use crate::tui_app::dialog::{ConfirmationDialogActor, InputDialogActor};
+use crate::tui_app::dialog::{
+    AlertDialogActor, AlertHistoryDialogActor, ConfirmationDialogActor, ErrorsDialogActor, HelpDialogActor, InputDialogActor, JsonViewerDialogActor,
+    LogFilterDialogActor, MessageDialogActor, PathDialogActor,
+};
 use crate::tui_app::dialog::metric::MetricDialogActor;
 use crate::tui_app::ui_state::queries::HandleKeyEvent;
 
@@ -30,6 +33,14 @@ pub enum ModalDialog {
     Confirmation(Addr<ConfirmationDialogActor>),
     Input(Addr<InputDialogActor>),
     Metric(Addr<MetricDialogActor>),
+    LogFilter(Addr<LogFilterDialogActor>),
+    JsonViewer(Addr<JsonViewerDialogActor>),
+    Path(Addr<PathDialogActor>),
+    Help(Addr<HelpDialogActor>),
+    Message(Addr<MessageDialogActor>),
+    Alert(Addr<AlertDialogActor>),
+    Errors(Addr<ErrorsDialogActor>),
+    AlertHistory(Addr<AlertHistoryDialogActor>),
 }
 
 /// `S` stands for State
@@ -0,0 +1,98 @@
+use crossterm::event::{KeyCode, KeyEvent};
+
+use ratatui::prelude::{Line, Stylize};
+use ratatui::widgets::{Block, Clear, Paragraph};
+use ratatui::Frame;
+
+use crate::build_info;
+use crate::tui_app::dialog::generic::DialogResult;
+use crate::i18n::Msg;
+use crate::tui_app::dialog::render::*;
+use crate::tui_app::dialog::{DialogActor, KeyEventHandler};
+use crate::tui_app::keymap;
+
+use crate::tui_app::theme::*;
+use UIElement::*;
+
+use crate::tui_app::utils::centered_rect_abs;
+
+pub type HelpDialogActor = DialogActor<HelpDialogState, ()>;
+
+const KEY_COLUMN_WIDTH: usize = 8;
+
+/// Keymap reference of the main view
+#[derive(Default, Clone)]
+pub struct HelpDialogState {
+    scroll: u16,
+}
+
+impl HelpDialogState {
+    fn lines() -> Vec<Line<'static>> {
+        let mut lines = vec![Line::from(format!(
+            "{} {}",
+            build_info::NAME,
+            build_info::long_version()
+        ))
+        .themed(InstructionsText)];
+        for group in keymap::groups() {
+            lines.push(Line::default());
+            lines.push(Line::from(group.title()).bold());
+            for binding in keymap::bindings_of(group) {
+                lines.push(Line::from(vec![
+                    format!("  {:<KEY_COLUMN_WIDTH$}", binding.key.label())
+                        .themed(DialogInstructionsActionText)
+                        .bold(),
+                    binding.description.text().into(),
+                ]));
+            }
+        }
+        lines
+    }
+}
+
+impl KeyEventHandler<()> for HelpDialogState {
+    fn handle_key_event(&mut self, key_event: KeyEvent) -> Option<DialogResult<()>> {
+        match key_event.code {
+            KeyCode::Esc | KeyCode::Enter | KeyCode::Char('?') | KeyCode::Char('q') => {
+                return Some(DialogResult::Cancel);
+            }
+            KeyCode::Down => {
+                let last_line = Self::lines().len().saturating_sub(1) as u16;
+                self.scroll = (self.scroll + 1).min(last_line);
+            }
+            KeyCode::Up => self.scroll = self.scroll.saturating_sub(1),
+            _ => {}
+        }
+        None
+    }
+}
+
+impl Renderable for HelpDialogState {
+    fn render(&self, frame: &mut Frame) {
+        let area = frame.area();
+        let lines = Self::lines();
+        let area = centered_rect_abs(
+            64.min(area.width),
+            (lines.len() as u16 + 2).min(area.height),
+            area,
+        );
+
+        let instructions = Line::from(vec![
+            instruction(Msg::Scroll),
+            "↑/↓".themed(DialogInstructionsActionText).bold(),
+            instruction(Msg::Close),
+            "<Esc> ".themed(DialogInstructionsActionText).bold(),
+        ]);
+
+        let pad = Block::bordered()
+            .title(Line::from(Msg::Keys.text()).centered())
+            .title_bottom(instructions.centered())
+            .themed(DialogPad);
+        let content = Paragraph::new(lines)
+            .scroll((self.scroll, 0))
+            .block(pad);
+
+        frame.render_widget(Clear, area);
+        frame.render_widget(content, area);
+    }
+}
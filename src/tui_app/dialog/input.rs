@@ -9,6 +9,7 @@ use strum::IntoEnumIterator;
 
 use crate::tui_app::dialog::{DialogActor, KeyEventHandler};
 use crate::tui_app::dialog::generic::{DialogButton, DialogResult};
+use crate::i18n::Msg;
 use crate::tui_app::dialog::render::*;
 
 use crate::tui_app::theme::*;
@@ -84,11 +85,11 @@ impl Renderable for InputDialogState {
         let area = centered_rect_abs(50, 6, area);
 
         let instructions = Line::from(vec![
-            " Select Button ".themed(DialogInstructionsText),
+            instruction(Msg::SelectButton),
             "↹ ".themed(DialogInstructionsActionText).bold(),
-            " Press ".themed(DialogInstructionsText),
+            instruction(Msg::Press),
             "↵".themed(DialogInstructionsActionText).bold(),
-            " Close ".themed(DialogInstructionsText),
+            instruction(Msg::Close),
             "<Esc> ".themed(DialogInstructionsActionText).bold(),
         ]);
 
@@ -0,0 +1,408 @@
+use crossterm::event::{KeyCode, KeyEvent};
+
+use ratatui::layout::{Constraint, Direction, Layout};
+use ratatui::prelude::{Line, Span, Stylize};
+use ratatui::widgets::{Block, Clear};
+use ratatui::Frame;
+
+use serde_json::Value;
+
+use std::collections::HashSet;
+use std::sync::Arc;
+
+use crate::tui_app::dialog::generic::DialogResult;
+use crate::i18n::Msg;
+use crate::tui_app::dialog::render::*;
+use crate::tui_app::dialog::{DialogActor, KeyEventHandler};
+
+use crate::tui_app::theme::*;
+use UIElement::*;
+
+use crate::tui_app::utils::{centered_rect_abs, copy_to_clipboard};
+
+pub type JsonViewerDialogActor = DialogActor<JsonViewerDialogState, ()>;
+
+const INDENT: usize = 2;
+const PAGE_LINES: usize = 10;
+
+/// Read-only tree view of a JSON document. Nodes are addressed by their JSON pointers.
+#[derive(Default, Clone)]
+pub struct JsonViewerDialogState {
+    title: String,
+    root: Arc<Value>,
+
+    collapsed: HashSet<String>,
+    cursor: usize,
+
+    search: String,
+    searching: bool,
+}
+
+enum LineKind {
+    Open { brackets: (char, char), len: usize },
+    Close(char),
+    Scalar(Value),
+}
+
+struct JsonLine {
+    depth: usize,
+    pointer: String,
+    key: Option<String>,
+    kind: LineKind,
+    collapsed: bool,
+    trailing_comma: bool,
+}
+
+impl JsonLine {
+    fn matches(&self, needle: &str) -> bool {
+        let key_matches = self
+            .key
+            .as_ref()
+            .is_some_and(|key| key.to_lowercase().contains(needle));
+        let value_matches = match &self.kind {
+            LineKind::Scalar(Value::String(value)) => value.to_lowercase().contains(needle),
+            LineKind::Scalar(value) => value.to_string().contains(needle),
+            _ => false,
+        };
+        key_matches || value_matches
+    }
+}
+
+impl JsonViewerDialogState {
+    pub fn new(title: String, root: Value) -> Self {
+        Self {
+            title,
+            root: Arc::new(root),
+            ..Self::default()
+        }
+    }
+
+    /// Lines of the document as currently folded, or of the whole document
+    fn lines(&self, unfolded: bool) -> Vec<JsonLine> {
+        let mut lines = Vec::new();
+        let collapsed = (!unfolded).then_some(&self.collapsed);
+        flatten(&self.root, None, String::new(), 0, false, collapsed, &mut lines);
+        lines
+    }
+
+    fn toggle(&mut self, collapse: Option<bool>) {
+        let lines = self.lines(false);
+        let Some(line) = lines.get(self.cursor) else {
+            return;
+        };
+        // Folding a closing bracket folds the node it belongs to
+        let pointer = match line.kind {
+            LineKind::Scalar(_) => return,
+            _ => line.pointer.clone(),
+        };
+        let collapse = collapse.unwrap_or(!self.collapsed.contains(&pointer));
+        if collapse {
+            self.collapsed.insert(pointer.clone());
+        } else {
+            self.collapsed.remove(&pointer);
+        }
+        // Keep the cursor on the node's opening line
+        let lines = self.lines(false);
+        if let Some(index) = lines.iter().position(|line| line.pointer == pointer) {
+            self.cursor = index;
+        }
+    }
+
+    /// Copies the node under the cursor as pretty-printed JSON
+    fn copy_current(&self) {
+        let lines = self.lines(false);
+        let Some(node) = lines
+            .get(self.cursor)
+            .and_then(|line| self.root.pointer(&line.pointer))
+        else {
+            return;
+        };
+        let copied = serde_json::to_string_pretty(node)
+            .map_err(std::io::Error::other)
+            .and_then(|json| copy_to_clipboard(&json));
+        if let Err(err) = copied {
+            log::warn!("Failed to copy to the clipboard: {err}");
+        }
+    }
+
+    /// Moves the cursor to the next line matching the search, unfolding its ancestors
+    fn find_next(&mut self, from_current: bool) {
+        if self.search.is_empty() {
+            return;
+        }
+        let needle = self.search.to_lowercase();
+        let current_pointer = self
+            .lines(false)
+            .get(self.cursor)
+            .map(|line| line.pointer.clone());
+
+        let all_lines = self.lines(true);
+        let start = current_pointer
+            .and_then(|pointer| all_lines.iter().position(|line| line.pointer == pointer))
+            .map_or(0, |index| if from_current { index } else { index + 1 });
+
+        let found = (0..all_lines.len())
+            .map(|offset| (start + offset) % all_lines.len())
+            .find(|index| {
+                let line = &all_lines[*index];
+                !matches!(line.kind, LineKind::Close(_)) && line.matches(&needle)
+            });
+        let Some(index) = found else {
+            return;
+        };
+
+        let pointer = all_lines[index].pointer.clone();
+        self.collapsed
+            .retain(|collapsed| !pointer.starts_with(&format!("{collapsed}/")));
+        if let Some(index) = self.lines(false).iter().position(|line| line.pointer == pointer) {
+            self.cursor = index;
+        }
+    }
+}
+
+// (key, pointer, value) of each child of an object or array
+type Children<'a> = Vec<(Option<String>, String, &'a Value)>;
+
+fn flatten(
+    value: &Value,
+    key: Option<String>,
+    pointer: String,
+    depth: usize,
+    trailing_comma: bool,
+    collapsed: Option<&HashSet<String>>,
+    lines: &mut Vec<JsonLine>,
+) {
+    let (brackets, children): ((char, char), Children) = match value {
+        Value::Object(map) => (
+            ('{', '}'),
+            map.iter()
+                .map(|(key, child)| {
+                    let escaped = key.replace('~', "~0").replace('/', "~1");
+                    (Some(key.clone()), format!("{pointer}/{escaped}"), child)
+                })
+                .collect(),
+        ),
+        Value::Array(items) => (
+            ('[', ']'),
+            items
+                .iter()
+                .enumerate()
+                .map(|(index, child)| (None, format!("{pointer}/{index}"), child))
+                .collect(),
+        ),
+        scalar => {
+            lines.push(JsonLine {
+                depth,
+                pointer,
+                key,
+                kind: LineKind::Scalar(scalar.clone()),
+                collapsed: false,
+                trailing_comma,
+            });
+            return;
+        }
+    };
+
+    let is_collapsed = collapsed.is_some_and(|collapsed| collapsed.contains(&pointer));
+    lines.push(JsonLine {
+        depth,
+        pointer: pointer.clone(),
+        key,
+        kind: LineKind::Open {
+            brackets,
+            len: children.len(),
+        },
+        collapsed: is_collapsed,
+        trailing_comma,
+    });
+    if is_collapsed {
+        return;
+    }
+
+    let children_count = children.len();
+    for (index, (child_key, child_pointer, child)) in children.into_iter().enumerate() {
+        let comma = index + 1 < children_count;
+        flatten(child, child_key, child_pointer, depth + 1, comma, collapsed, lines);
+    }
+    lines.push(JsonLine {
+        depth,
+        pointer,
+        key: None,
+        kind: LineKind::Close(brackets.1),
+        collapsed: false,
+        trailing_comma,
+    });
+}
+
+impl KeyEventHandler<()> for JsonViewerDialogState {
+    fn handle_key_event(&mut self, key_event: KeyEvent) -> Option<DialogResult<()>> {
+        if self.searching {
+            match key_event.code {
+                KeyCode::Esc => {
+                    self.searching = false;
+                    self.search.clear();
+                }
+                KeyCode::Enter => {
+                    self.searching = false;
+                    self.find_next(true);
+                }
+                KeyCode::Backspace => {
+                    self.search.pop();
+                }
+                KeyCode::Char(char) => {
+                    self.search.push(char);
+                }
+                _ => {}
+            }
+            return None;
+        }
+
+        let lines_count = self.lines(false).len();
+        match key_event.code {
+            KeyCode::Esc | KeyCode::Char('q') => return Some(DialogResult::Accept { result: () }),
+
+            KeyCode::Down => {
+                self.cursor = (self.cursor + 1).min(lines_count.saturating_sub(1));
+            }
+            KeyCode::Up => {
+                self.cursor = self.cursor.saturating_sub(1);
+            }
+            KeyCode::PageDown => {
+                self.cursor = (self.cursor + PAGE_LINES).min(lines_count.saturating_sub(1));
+            }
+            KeyCode::PageUp => {
+                self.cursor = self.cursor.saturating_sub(PAGE_LINES);
+            }
+            KeyCode::Home => {
+                self.cursor = 0;
+            }
+            KeyCode::End => {
+                self.cursor = lines_count.saturating_sub(1);
+            }
+
+            KeyCode::Enter | KeyCode::Char(' ') => self.toggle(None),
+            KeyCode::Left => self.toggle(Some(true)),
+            KeyCode::Right => self.toggle(Some(false)),
+
+            KeyCode::Char('/') => {
+                self.searching = true;
+                self.search.clear();
+            }
+            KeyCode::Char('n') => self.find_next(false),
+
+            KeyCode::Char('y') => self.copy_current(),
+
+            _ => {}
+        }
+        None
+    }
+}
+
+fn render_line(line: &JsonLine, needle: &str) -> Line<'static> {
+    let mut spans = vec![Span::raw(" ".repeat(line.depth * INDENT))];
+    if let Some(key) = &line.key {
+        spans.push(format!("{key:?}").themed(JsonKey));
+        spans.push(": ".themed(JsonPunctuation));
+    }
+
+    match &line.kind {
+        LineKind::Open { brackets, len } if line.collapsed => {
+            spans.push(format!("{} … {}", brackets.0, brackets.1).themed(JsonPunctuation));
+            let noun = if *len == 1 { "item" } else { "items" };
+            spans.push(format!(" {len} {noun}").themed(JsonLiteral).italic());
+        }
+        LineKind::Open { brackets, .. } => {
+            spans.push(brackets.0.to_string().themed(JsonPunctuation));
+        }
+        LineKind::Close(bracket) => {
+            spans.push(bracket.to_string().themed(JsonPunctuation));
+        }
+        LineKind::Scalar(value) => {
+            let element = match value {
+                Value::String(_) => JsonString,
+                Value::Number(_) => JsonNumber,
+                _ => JsonLiteral,
+            };
+            spans.push(value.to_string().themed(element));
+        }
+    }
+
+    let closes_here = match line.kind {
+        LineKind::Open { .. } => line.collapsed,
+        _ => true,
+    };
+    if line.trailing_comma && closes_here {
+        spans.push(",".themed(JsonPunctuation));
+    }
+
+    let mut line_widget = Line::from(spans);
+    if !needle.is_empty() && !matches!(line.kind, LineKind::Close(_)) && line.matches(needle) {
+        line_widget = line_widget.themed(JsonSearchMatch);
+    }
+    line_widget
+}
+
+impl Renderable for JsonViewerDialogState {
+    fn render(&self, frame: &mut Frame) {
+        let area = frame.area();
+        let area = centered_rect_abs(
+            area.width.saturating_sub(8).max(20),
+            area.height.saturating_sub(4).max(8),
+            area,
+        );
+
+        let instructions = if self.searching {
+            Line::from(vec![
+                instruction(Msg::SearchPrompt),
+                format!("{}_ ", self.search).themed(DialogInstructionsActionText),
+                instruction(Msg::Find),
+                "<Enter> ".themed(DialogInstructionsActionText).bold(),
+            ])
+        } else {
+            Line::from(vec![
+                instruction(Msg::Move),
+                "↑/↓".themed(DialogInstructionsActionText).bold(),
+                instruction(Msg::Fold),
+                "←/→/␣".themed(DialogInstructionsActionText).bold(),
+                instruction(Msg::Search),
+                "/".themed(DialogInstructionsActionText).bold(),
+                instruction(Msg::Next),
+                "n".themed(DialogInstructionsActionText).bold(),
+                instruction(Msg::CopyValue),
+                "y".themed(DialogInstructionsActionText).bold(),
+                instruction(Msg::Close),
+                "<Esc> ".themed(DialogInstructionsActionText).bold(),
+            ])
+        };
+
+        let lines = self.lines(false);
+        let pad = Block::bordered()
+            .title(Line::from(self.title.clone()).centered())
+            .title(Line::from(format!(" {}/{} ", self.cursor + 1, lines.len())).right_aligned())
+            .title_bottom(instructions.centered())
+            .themed(DialogPad);
+        let content_area = centered_rect_abs(area.width - 4, area.height - 2, area);
+
+        frame.render_widget(Clear, area);
+        frame.render_widget(pad, area);
+
+        let height = content_area.height as usize;
+        let offset = (self.cursor + 1).saturating_sub(height);
+        let needle = self.search.to_lowercase();
+
+        let rows = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints(vec![Constraint::Length(1); height])
+            .split(content_area);
+        for (row, (index, line)) in rows
+            .iter()
+            .zip(lines.iter().enumerate().skip(offset))
+        {
+            let mut line = render_line(line, &needle);
+            if index == self.cursor {
+                line = line.themed(DialogTextInputFocused);
+            }
+            frame.render_widget(line, *row);
+        }
+    }
+}
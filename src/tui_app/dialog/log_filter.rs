@@ -0,0 +1,118 @@
+use crossterm::event::{KeyCode, KeyEvent};
+
+use ratatui::layout::{Constraint, Direction, Layout};
+use ratatui::prelude::{Line, Stylize};
+use ratatui::widgets::{Block, Clear};
+use ratatui::Frame;
+
+use strum::IntoEnumIterator;
+
+use crate::logging::Subsystem;
+use crate::tui_app::dialog::generic::DialogResult;
+use crate::i18n::Msg;
+use crate::tui_app::dialog::render::*;
+use crate::tui_app::dialog::{DialogActor, KeyEventHandler};
+
+use crate::tui_app::theme::*;
+use UIElement::*;
+
+use crate::tui_app::utils::centered_rect_abs;
+
+pub type LogFilterDialogActor = DialogActor<LogFilterDialogState, ()>;
+
+/// Levels are applied right away, so the dialog only keeps track of the focused subsystem
+#[derive(Default, Clone)]
+pub struct LogFilterDialogState {
+    focused_subsystem: usize,
+}
+
+impl LogFilterDialogState {
+    fn focused(&self) -> Subsystem {
+        Subsystem::iter().nth(self.focused_subsystem).unwrap()
+    }
+}
+
+impl KeyEventHandler<()> for LogFilterDialogState {
+    fn handle_key_event(&mut self, key_event: KeyEvent) -> Option<DialogResult<()>> {
+        match key_event.code {
+            KeyCode::Esc | KeyCode::Enter => Some(DialogResult::Accept { result: () }),
+
+            KeyCode::Down => {
+                self.focused_subsystem = (self.focused_subsystem + 1) % Subsystem::iter().len();
+                None
+            }
+
+            KeyCode::Up => {
+                self.focused_subsystem = self
+                    .focused_subsystem
+                    .checked_sub(1)
+                    .unwrap_or(Subsystem::iter().len() - 1);
+                None
+            }
+
+            KeyCode::Left => {
+                self.focused().less_verbose();
+                log::info!("Log level of {:?} set to {}", self.focused(), self.focused().level());
+                None
+            }
+
+            KeyCode::Right => {
+                self.focused().more_verbose();
+                log::info!("Log level of {:?} set to {}", self.focused(), self.focused().level());
+                None
+            }
+
+            _ => None,
+        }
+    }
+}
+
+impl Renderable for LogFilterDialogState {
+    fn render(&self, frame: &mut Frame) {
+        let subsystems_count = Subsystem::iter().len();
+
+        let area = frame.area();
+        let area = centered_rect_abs(50, subsystems_count as u16 + 4, area);
+
+        let instructions = Line::from(vec![
+            instruction(Msg::Select),
+            "↑/↓".themed(DialogInstructionsActionText).bold(),
+            instruction(Msg::ChangeLevel),
+            "←/→".themed(DialogInstructionsActionText).bold(),
+            instruction(Msg::Close),
+            "<Esc> ".themed(DialogInstructionsActionText).bold(),
+        ]);
+
+        let pad = Block::bordered()
+            .title(Line::from(Msg::LogFilters.text()).centered())
+            .title_bottom(instructions.centered())
+            .themed(DialogPad);
+        let content_area = centered_rect_abs(area.width - 4, area.height - 2, area);
+
+        let content_layout = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints(
+                // Text line + one line per subsystem
+                vec![Constraint::Length(1); subsystems_count + 1],
+            )
+            .split(content_area);
+
+        frame.render_widget(Clear, area);
+        frame.render_widget(pad, area);
+        frame.render_widget(
+            Line::from(Msg::LogFileNote.format(&[("file", &crate::logging::LOG_FILE)])).centered(),
+            content_layout[0],
+        );
+
+        for (i, subsystem) in Subsystem::iter().enumerate() {
+            let subsystem_name = format!("{:?}", subsystem);
+            let line = Line::from(format!("{:<12}◀ {:^7} ▶", subsystem_name, subsystem.level()))
+                .themed(if i == self.focused_subsystem {
+                    DialogTextInputFocused
+                } else {
+                    DialogTextInput
+                });
+            frame.render_widget(line, content_layout[i + 1]);
+        }
+    }
+}
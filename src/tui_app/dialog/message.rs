@@ -0,0 +1,111 @@
+use crossterm::event::{KeyCode, KeyEvent};
+
+use ratatui::prelude::{Line, Stylize};
+use ratatui::widgets::{Block, Clear, Paragraph};
+use ratatui::Frame;
+
+use crate::tui_app::dialog::generic::DialogResult;
+use crate::i18n::Msg;
+use crate::tui_app::dialog::render::*;
+use crate::tui_app::dialog::{DialogActor, KeyEventHandler};
+
+use crate::tui_app::theme::*;
+use UIElement::*;
+
+use crate::tui_app::utils::centered_rect_abs;
+
+pub type MessageDialogActor = DialogActor<MessageDialogState, ()>;
+
+/// Read-only list of lines, e.g. a summary of what happened in the background
+#[derive(Default, Clone)]
+pub struct MessageDialogState {
+    title: String,
+    lines: Vec<String>,
+    scroll: u16,
+    /// Lines are colored by their `+`, `~`, `*` or `-` prefix
+    diff: bool,
+}
+
+impl MessageDialogState {
+    pub fn new(title: String, lines: Vec<String>) -> Self {
+        Self {
+            title,
+            lines,
+            scroll: 0,
+            diff: false,
+        }
+    }
+
+    /// Changes to the inventory, one per line, colored like a diff
+    pub fn diff(title: String, lines: Vec<String>) -> Self {
+        Self {
+            diff: true,
+            ..Self::new(title, lines)
+        }
+    }
+
+    fn line(&self, line: &str) -> Line<'static> {
+        let text = format!(" {line}");
+        if !self.diff {
+            return Line::from(text);
+        }
+        match line.chars().next() {
+            Some('+') => Line::from(text).themed(DiffCreated),
+            Some('~' | '*') => Line::from(text).themed(DiffUpdated),
+            Some('-') => Line::from(text).themed(DiffDeleted),
+            _ => Line::from(text),
+        }
+    }
+}
+
+impl KeyEventHandler<()> for MessageDialogState {
+    fn handle_key_event(&mut self, key_event: KeyEvent) -> Option<DialogResult<()>> {
+        match key_event.code {
+            KeyCode::Esc | KeyCode::Enter => return Some(DialogResult::Accept { result: () }),
+            KeyCode::Down => {
+                let last_line = self.lines.len().saturating_sub(1) as u16;
+                self.scroll = (self.scroll + 1).min(last_line);
+            }
+            KeyCode::Up => self.scroll = self.scroll.saturating_sub(1),
+            _ => {}
+        }
+        None
+    }
+}
+
+impl Renderable for MessageDialogState {
+    fn render(&self, frame: &mut Frame) {
+        let area = frame.area();
+        let width = self
+            .lines
+            .iter()
+            .map(|line| line.chars().count() as u16 + 4)
+            .max()
+            .unwrap_or_default()
+            .clamp(40, area.width);
+        let area = centered_rect_abs(
+            width,
+            (self.lines.len() as u16 + 2).min(area.height),
+            area,
+        );
+
+        let instructions = Line::from(vec![
+            instruction(Msg::Scroll),
+            "↑/↓".themed(DialogInstructionsActionText).bold(),
+            instruction(Msg::Close),
+            "↵ ".themed(DialogInstructionsActionText).bold(),
+        ]);
+
+        let pad = Block::bordered()
+            .title(Line::from(self.title.as_str()).centered())
+            .title_bottom(instructions.centered())
+            .themed(DialogPad);
+        let lines: Vec<Line> = self.lines.iter().map(|line| self.line(line)).collect();
+        let content = Paragraph::new(lines)
+            .scroll((self.scroll, 0))
+            .block(pad);
+
+        frame.render_widget(Clear, area);
+        frame.render_widget(content, area);
+    }
+}
@@ -3,12 +3,16 @@ use crossterm::event::{KeyCode, KeyEvent};
 use eyre::{eyre, Result};
 
 use ratatui::layout::{Constraint, Direction, Layout, Rect};
-use ratatui::prelude::{Line, Stylize};
+use ratatui::prelude::{Line, Span, Stylize};
 use ratatui::widgets::{Block, BorderType, Clear, Paragraph, Wrap};
 use ratatui::Frame;
 
+use serde_valid::validation::Errors;
+use serde_valid::Validate;
+
 use crate::model::sensor::{Metric, ValueType, ValueUnit};
 use crate::tui_app::dialog::generic::{DialogButton, DialogResult};
+use crate::i18n::Msg;
 use crate::tui_app::dialog::render::*;
 use crate::tui_app::dialog::{DialogActor, KeyEventHandler};
 
@@ -18,7 +22,10 @@ use UIElement::*;
 use crate::tui_app::utils::centered_rect_abs;
 use crate::utils::CircularEnum;
 
-pub type MetricDialogActor = DialogActor<MetricDialogState, Metric>;
+pub type MetricDialogActor = DialogActor<MetricDialogState, Vec<Metric>>;
+
+/// Cards shown side by side, the rest are scrolled to with Tab
+const MAX_VISIBLE_FORMS: usize = 3;
 
 #[derive(Default, Clone)]
 pub struct MetricDialogState {
@@ -27,6 +34,9 @@ pub struct MetricDialogState {
 
     forms: Vec<MetricForm>,
     focused_form: usize,
+
+    // Every card is a metric of the result, cards can be added and removed
+    batch: bool,
 }
 
 #[derive(Default, Clone)]
@@ -51,12 +61,89 @@ impl MetricDialogState {
                     })
                     .collect(),
                 focused_form: 0,
+                batch: false,
             })
         }
     }
+
+    /// Accepts all the cards at once rather than the focused one
+    pub fn batch(title: String, text: String, metrics: Vec<Metric>) -> Result<Self> {
+        Ok(Self {
+            batch: true,
+            ..Self::new(title, text, metrics)?
+        })
+    }
+
+    fn add_form(&mut self) {
+        // Blank metric of the same kind as the focused one
+        let metric = match &self.forms[self.focused_form].metric {
+            Metric::Predefined { value_unit, .. } => {
+                Metric::predefined(String::default(), value_unit.clone())
+            }
+            Metric::Custom { value_type, .. } => {
+                Metric::custom(String::default(), value_type.clone(), String::default())
+            }
+        };
+        self.focused_form += 1;
+        self.forms.insert(
+            self.focused_form,
+            MetricForm {
+                metric,
+                focused_field: 1,
+            },
+        );
+    }
+
+    fn remove_form(&mut self) {
+        if self.forms.len() == 1 {
+            return;
+        }
+        self.forms.remove(self.focused_form);
+        self.focused_form = self.focused_form.min(self.forms.len() - 1);
+    }
 }
 
 impl MetricForm {
+    /// Name and annotation take any character, the unit or type selector doesn't
+    fn is_editing_text(&self) -> bool {
+        self.focused_field != 1
+    }
+
+    fn is_valid(&self) -> bool {
+        self.metric.validate().is_ok()
+    }
+
+    /// First validation error of the field, `field` being its name in the API
+    fn field_error(&self, field: &str) -> Option<String> {
+        let Err(Errors::Object(errors)) = self.metric.validate() else {
+            return None;
+        };
+        match errors.properties.get(field)? {
+            Errors::NewType(errors) => errors.first().map(ToString::to_string),
+            errors => Some(errors.to_string()),
+        }
+    }
+
+    /// Text input followed by its validation error, if any
+    fn field_line<'a>(
+        &self,
+        value: &'a str,
+        placeholder: &'a str,
+        field: &str,
+        focused: bool,
+    ) -> Line<'a> {
+        let input = Span::from(if value.is_empty() { placeholder } else { value });
+        let mut line = Line::from(input.themed(if focused {
+            DialogTextInputFocused
+        } else {
+            DialogTextInput
+        }));
+        if let Some(error) = self.field_error(field) {
+            line.push_span(format!(" ✗ {error}").themed(ErrorLog));
+        }
+        line
+    }
+
     fn handle_key_event(&mut self, key_event: KeyEvent) {
         let mut metric = self.metric.clone();
         match &mut metric {
@@ -223,13 +310,7 @@ impl MetricForm {
         frame.render_widget(Clear, area);
         frame.render_widget(pad, area);
 
-        let name_input = Line::from(if name.is_empty() { "<name>" } else { name }).themed(
-            if focused && self.focused_field == 0 {
-                DialogTextInputFocused
-            } else {
-                DialogTextInput
-            },
-        );
+        let name_input = self.field_line(name, "<name>", "name", focused && self.focused_field == 0);
 
         let value_unit_input =
             Line::from(value_unit.emojified()).themed(if focused && self.focused_field == 1 {
@@ -278,13 +359,7 @@ impl MetricForm {
         frame.render_widget(Clear, area);
         frame.render_widget(pad, area);
 
-        let name_input = Line::from(if name.is_empty() { "<name>" } else { name }).themed(
-            if focused && self.focused_field == 0 {
-                DialogTextInputFocused
-            } else {
-                DialogTextInput
-            },
-        );
+        let name_input = self.field_line(name, "<name>", "name", focused && self.focused_field == 0);
 
         let value_type_input =
             Line::from(value_type.emojified()).themed(if focused && self.focused_field == 1 {
@@ -293,16 +368,12 @@ impl MetricForm {
                 DialogTextInput
             });
 
-        let value_annotation_input = Line::from(if value_annotation.is_empty() {
-            "<annotation>"
-        } else {
-            value_annotation
-        })
-        .themed(if focused && self.focused_field == 2 {
-            DialogTextInputFocused
-        } else {
-            DialogTextInput
-        });
+        let value_annotation_input = self.field_line(
+            value_annotation,
+            "<annotation>",
+            "valueAnnotation",
+            focused && self.focused_field == 2,
+        );
 
         frame.render_widget(name_input, content_layout[0]);
         frame.render_widget(value_type_input, content_layout[1]);
@@ -312,15 +383,41 @@ impl MetricForm {
     }
 }
 
-impl KeyEventHandler<Metric> for MetricDialogState {
-    fn handle_key_event(&mut self, key_event: KeyEvent) -> Option<DialogResult<Metric>> {
+impl KeyEventHandler<Vec<Metric>> for MetricDialogState {
+    fn handle_key_event(&mut self, key_event: KeyEvent) -> Option<DialogResult<Vec<Metric>>> {
+        let selecting = !self.forms[self.focused_form].is_editing_text();
         match key_event.code {
             KeyCode::Esc => Some(DialogResult::Cancel),
 
+            // The first card failing the validation gets the focus instead
+            KeyCode::Enter if self.batch => {
+                match self.forms.iter().position(|form| !form.is_valid()) {
+                    Some(invalid) => {
+                        self.focused_form = invalid;
+                        None
+                    }
+                    None => Some(DialogResult::Accept {
+                        result: self.forms.iter().map(|form| form.metric.clone()).collect(),
+                    }),
+                }
+            }
+
+            KeyCode::Enter if !self.forms[self.focused_form].is_valid() => None,
+
             KeyCode::Enter => Some(DialogResult::Accept {
-                result: self.forms[self.focused_form].metric.clone(),
+                result: vec![self.forms[self.focused_form].metric.clone()],
             }),
 
+            KeyCode::Char('+') if self.batch && selecting => {
+                self.add_form();
+                None
+            }
+
+            KeyCode::Char('-') if self.batch && selecting => {
+                self.remove_form();
+                None
+            }
+
             KeyCode::Tab => {
                 self.focused_form = self.focused_form.wrapping_add(1);
                 if self.focused_form == self.forms.len() {
@@ -340,25 +437,37 @@ impl KeyEventHandler<Metric> for MetricDialogState {
 impl Renderable for MetricDialogState {
     fn render(&self, frame: &mut Frame) {
         let area = frame.area();
-        let area = centered_rect_abs(76, 8, area);
+        let area = centered_rect_abs(if self.batch { 96 } else { 76 }, 8, area);
 
-        let instructions = Line::from(vec![
-            " Select Card ".themed(DialogInstructionsText),
+        let mut instructions = vec![
+            instruction(Msg::SelectCard),
             "↹ ".themed(DialogInstructionsActionText).bold(),
-            " Change Field ".themed(DialogInstructionsText),
+            instruction(Msg::ChangeField),
             "↑/↓".themed(DialogInstructionsActionText).bold(),
-            " Change Value ".themed(DialogInstructionsText),
+            instruction(Msg::ChangeValue),
             "←/→".themed(DialogInstructionsActionText).bold(),
-            " Accept ".themed(DialogInstructionsText),
+        ];
+        if self.batch {
+            instructions.extend([
+                instruction(Msg::AddRemoveCard),
+                "+/-".themed(DialogInstructionsActionText).bold(),
+            ]);
+        }
+        instructions.extend([
+            instruction(Msg::Accept),
             "↵".themed(DialogInstructionsActionText).bold(),
-            " Close ".themed(DialogInstructionsText),
+            instruction(Msg::Close),
             "<Esc> ".themed(DialogInstructionsActionText).bold(),
         ]);
 
-        let pad = Block::bordered()
+        let mut pad = Block::bordered()
             .title(Line::from(self.title.clone()).centered())
-            .title_bottom(instructions.centered())
+            .title_bottom(Line::from(instructions).centered())
             .themed(DialogPad);
+        if self.batch {
+            let position = format!(" {}/{} ", self.focused_form + 1, self.forms.len());
+            pad = pad.title(Line::from(position).right_aligned());
+        }
         let content_area = centered_rect_abs(area.width - 2, area.height - 2, area);
 
         let content_layout = Layout::default()
@@ -371,12 +480,13 @@ impl Renderable for MetricDialogState {
             ])
             .split(content_area);
 
+        // Window of the cards keeping the focused one in sight
+        let visible = self.forms.len().min(MAX_VISIBLE_FORMS);
+        let first_visible = (self.focused_form + 1).saturating_sub(visible);
+
         let option_cards_layout = Layout::default()
             .direction(Direction::Horizontal)
-            .constraints(vec![
-                Constraint::Ratio(1, self.forms.len() as u32);
-                self.forms.len()
-            ])
+            .constraints(vec![Constraint::Ratio(1, visible as u32); visible])
             .split(content_layout[1]);
 
         let text = Paragraph::new(self.text.as_str())
@@ -386,8 +496,9 @@ impl Renderable for MetricDialogState {
         frame.render_widget(Clear, area);
         frame.render_widget(pad, area);
         frame.render_widget(text, content_layout[0]);
-        for (i, form) in self.forms.iter().enumerate() {
-            form.render(frame, option_cards_layout[i], i == self.focused_form);
+        let visible_forms = self.forms.iter().enumerate().skip(first_visible).take(visible);
+        for (area, (i, form)) in option_cards_layout.iter().zip(visible_forms) {
+            form.render(frame, *area, i == self.focused_form);
         }
     }
 }
@@ -0,0 +1,268 @@
+use crossterm::event::{KeyCode, KeyEvent};
+
+use ratatui::layout::{Constraint, Direction, Layout};
+use ratatui::prelude::{Line, Stylize};
+use ratatui::widgets::{Block, Clear, Paragraph, Wrap};
+use ratatui::Frame;
+
+use std::collections::VecDeque;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+use crate::tui_app::dialog::generic::DialogResult;
+use crate::i18n::Msg;
+use crate::tui_app::dialog::render::*;
+use crate::tui_app::dialog::{DialogActor, KeyEventHandler};
+
+use crate::tui_app::theme::*;
+use UIElement::*;
+
+use crate::tui_app::utils::{centered_rect_abs, complete_path, path_candidates};
+use crate::utils::lock_recovering;
+
+pub type PathDialogActor = DialogActor<PathDialogState, PathBuf>;
+
+const RECENT_PATHS_LIMIT: usize = 10;
+
+const PARENT_DIR: &str = "..";
+
+/// Paths accepted by any path dialog during this session, the latest first
+static RECENT_PATHS: Mutex<VecDeque<String>> = Mutex::new(VecDeque::new());
+
+fn remember_path(path: &str) {
+    let mut recent_paths = lock_recovering(&RECENT_PATHS);
+    recent_paths.retain(|recent| recent != path);
+    recent_paths.push_front(path.to_owned());
+    recent_paths.truncate(RECENT_PATHS_LIMIT);
+}
+
+/// File path input. The entries of the directory being typed in are listed below the input
+/// and can be browsed, previously accepted paths can be recalled.
+#[derive(Default, Clone)]
+pub struct PathDialogState {
+    title: String,
+    text: String,
+
+    text_input: String,
+
+    /// (name, is_dir) of the entries matching the input
+    entries: Vec<(String, bool)>,
+    selected_entry: Option<usize>,
+
+    recent_paths: Vec<String>,
+    recent_index: Option<usize>,
+}
+
+impl PathDialogState {
+    pub fn new(title: String, text: String, initial_path: String) -> Self {
+        let recent_paths = lock_recovering(&RECENT_PATHS).iter().cloned().collect();
+        let mut state = Self {
+            title,
+            text,
+            text_input: initial_path,
+            recent_paths,
+            ..Self::default()
+        };
+        state.refresh_entries();
+        state
+    }
+
+    fn set_input(&mut self, text_input: String) {
+        self.text_input = text_input;
+        self.refresh_entries();
+    }
+
+    fn refresh_entries(&mut self) {
+        let (dir, mut entries) = path_candidates(&self.text_input);
+        if dir.len() == self.text_input.len() {
+            entries.insert(0, (PARENT_DIR.to_owned(), true));
+        }
+        self.entries = entries;
+        self.selected_entry = None;
+    }
+
+    fn open_entry(&mut self, (name, is_dir): (String, bool)) {
+        let (dir, _) = path_candidates(&self.text_input);
+        let text_input = if name == PARENT_DIR {
+            parent_dir(dir)
+        } else if is_dir {
+            format!("{dir}{name}/")
+        } else {
+            format!("{dir}{name}")
+        };
+        self.set_input(text_input);
+    }
+
+    fn recall(&mut self, older: bool) {
+        if self.recent_paths.is_empty() {
+            return;
+        }
+        let index = match (self.recent_index, older) {
+            (None, true) => 0,
+            (None, false) => return,
+            (Some(index), true) => (index + 1).min(self.recent_paths.len() - 1),
+            (Some(0), false) => return,
+            (Some(index), false) => index - 1,
+        };
+        self.recent_index = Some(index);
+        self.set_input(self.recent_paths[index].clone());
+    }
+}
+
+/// Parent of `dir`, which is typed either empty or ending with a separator
+fn parent_dir(dir: &str) -> String {
+    let trimmed = dir.trim_end_matches('/');
+    if trimmed.is_empty() {
+        // The root is its own parent
+        return if dir.is_empty() { format!("{PARENT_DIR}/") } else { "/".to_owned() };
+    }
+    let (parent, last) = match trimmed.rsplit_once('/') {
+        Some((parent, last)) => (format!("{parent}/"), last),
+        None => (String::new(), trimmed),
+    };
+    if last == PARENT_DIR || last == "." {
+        format!("{dir}{PARENT_DIR}/")
+    } else {
+        parent
+    }
+}
+
+impl KeyEventHandler<PathBuf> for PathDialogState {
+    fn handle_key_event(&mut self, key_event: KeyEvent) -> Option<DialogResult<PathBuf>> {
+        match key_event.code {
+            KeyCode::Esc => return Some(DialogResult::Cancel),
+
+            KeyCode::Enter => {
+                if let Some(index) = self.selected_entry {
+                    let entry = self.entries[index].clone();
+                    self.open_entry(entry);
+                } else if !self.text_input.is_empty() && !Path::new(&self.text_input).is_dir() {
+                    remember_path(&self.text_input);
+                    return Some(DialogResult::Accept {
+                        result: PathBuf::from(&self.text_input),
+                    });
+                } else if !self.text_input.is_empty() && !self.text_input.ends_with('/') {
+                    let text_input = format!("{}/", self.text_input);
+                    self.set_input(text_input);
+                }
+            }
+
+            KeyCode::Tab => {
+                if let Some(completed) = complete_path(&self.text_input) {
+                    self.set_input(completed);
+                }
+            }
+
+            KeyCode::Down if !self.entries.is_empty() => {
+                self.selected_entry = Some(
+                    self.selected_entry
+                        .map_or(0, |index| (index + 1).min(self.entries.len() - 1)),
+                );
+            }
+            KeyCode::Up => {
+                // Moving up past the first entry gets back to the input
+                self.selected_entry = self.selected_entry.and_then(|index| index.checked_sub(1));
+            }
+
+            KeyCode::PageUp => self.recall(true),
+            KeyCode::PageDown => self.recall(false),
+
+            KeyCode::Char(char) => {
+                let mut text_input = std::mem::take(&mut self.text_input);
+                text_input.push(char);
+                self.set_input(text_input);
+            }
+            KeyCode::Backspace => {
+                let mut text_input = std::mem::take(&mut self.text_input);
+                text_input.pop();
+                self.set_input(text_input);
+            }
+
+            _ => {}
+        }
+        None
+    }
+}
+
+impl Renderable for PathDialogState {
+    fn render(&self, frame: &mut Frame) {
+        let area = frame.area();
+        let area = centered_rect_abs(70, 18, area);
+
+        let instructions = Line::from(vec![
+            instruction(Msg::Complete),
+            "↹".themed(DialogInstructionsActionText).bold(),
+            instruction(Msg::Browse),
+            "↑/↓".themed(DialogInstructionsActionText).bold(),
+            instruction(Msg::Recent),
+            "PgUp/PgDn".themed(DialogInstructionsActionText).bold(),
+            instruction(Msg::Accept),
+            "↵".themed(DialogInstructionsActionText).bold(),
+            instruction(Msg::Close),
+            "<Esc> ".themed(DialogInstructionsActionText).bold(),
+        ]);
+
+        let pad = Block::bordered()
+            .title(Line::from(self.title.clone()).centered())
+            .title_bottom(instructions.centered())
+            .themed(DialogPad);
+        let content_area = centered_rect_abs(area.width - 4, area.height - 2, area);
+
+        let content_layout = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([
+                // 0 Text area
+                Constraint::Length(2),
+                // 1 Text input
+                Constraint::Length(1),
+                // 2 Spacer
+                Constraint::Length(1),
+                // 3 Directory entries
+                Constraint::Fill(1),
+            ])
+            .split(content_area);
+
+        let text = Paragraph::new(self.text.as_str())
+            .centered()
+            .wrap(Wrap { trim: false });
+
+        let text_input_element = || {
+            if self.selected_entry.is_none() {
+                DialogTextInputFocused
+            } else {
+                DialogTextInput
+            }
+        };
+        let text_input_pad = Block::new().themed(text_input_element());
+        let text_input = Line::from(format!("{}_", self.text_input)).themed(text_input_element());
+
+        frame.render_widget(Clear, area);
+        frame.render_widget(pad, area);
+        frame.render_widget(text, content_layout[0]);
+        frame.render_widget(text_input_pad, content_layout[1]);
+        frame.render_widget(text_input, content_layout[1]);
+
+        let height = content_layout[3].height as usize;
+        let offset = self
+            .selected_entry
+            .map_or(0, |index| (index + 1).saturating_sub(height));
+        let rows = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints(vec![Constraint::Length(1); height])
+            .split(content_layout[3]);
+        for (row, (index, (name, is_dir))) in rows
+            .iter()
+            .zip(self.entries.iter().enumerate().skip(offset))
+        {
+            let mut line = if *is_dir {
+                Line::from(format!("{name}/")).bold()
+            } else {
+                Line::from(name.as_str())
+            };
+            if self.selected_entry == Some(index) {
+                line = line.themed(DialogTextInputFocused);
+            }
+            frame.render_widget(line, *row);
+        }
+    }
+}
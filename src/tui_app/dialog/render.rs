@@ -3,7 +3,9 @@ use ratatui::widgets::{
     Block, BorderType, Borders, Paragraph
     ,
 };
+use ratatui::text::Span;
 use ratatui::Frame;
+use crate::i18n::Msg;
 use crate::tui_app::dialog::DialogButton;
 use crate::tui_app::theme::*;
 use UIElement::*;
@@ -15,9 +17,10 @@ pub trait Renderable {
 impl DialogButton {
     pub fn render(&self, frame: &mut Frame, area: Rect, focused: Option<DialogButton>) {
         let text = match self {
-            Self::Ok => "OK",
-            Self::Cancel => "CANCEL",
-        };
+            Self::Ok => Msg::OkButton,
+            Self::Cancel => Msg::CancelButton,
+        }
+        .text();
 
         let mut button_block = Block::default()
             .borders(Borders::LEFT | Borders::RIGHT)
@@ -35,3 +38,8 @@ impl DialogButton {
         frame.render_widget(button, area);
     }
 }
+
+/// What a dialog instruction does, followed by its keys
+pub fn instruction(msg: Msg) -> Span<'static> {
+    format!(" {} ", msg.text()).themed(DialogInstructionsText)
+}
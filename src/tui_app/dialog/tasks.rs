@@ -0,0 +1,55 @@
+use std::collections::HashMap;
+use std::future::Future;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+
+use tokio::task::AbortHandle;
+
+use crate::utils::lock_recovering;
+
+/// Background tasks waiting for the results of the dialogs. Finished tasks drop out
+/// by themselves, the ones still running when the app exits are aborted all at once.
+///
+/// Tasks are expected to end quietly once their dialog is closed without a result,
+/// e.g. replaced by another dialog, rather than to wait forever.
+#[derive(Clone, Default)]
+pub struct DialogTasks {
+    tasks: Arc<Mutex<HashMap<u64, AbortHandle>>>,
+    next_id: Arc<AtomicU64>,
+}
+
+impl DialogTasks {
+    pub fn spawn(&self, task: impl Future<Output = ()> + 'static) {
+        let id = self.next_id.fetch_add(1, Ordering::Relaxed);
+        let tasks = self.clone();
+        let handle = actix::spawn(async move {
+            task.await;
+            lock_recovering(&tasks.tasks).remove(&id);
+        });
+
+        let mut running = lock_recovering(&self.tasks);
+        // A task finished before being registered would never drop out
+        if !handle.is_finished() {
+            running.insert(id, handle.abort_handle());
+        }
+    }
+
+    /// Number of the tasks still running
+    pub fn len(&self) -> usize {
+        lock_recovering(&self.tasks).len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn abort_all(&self) {
+        let aborted: Vec<_> = lock_recovering(&self.tasks).drain().collect();
+        if !aborted.is_empty() {
+            log::debug!("Aborting {} dialog task(s)", aborted.len());
+        }
+        for (_, handle) in aborted {
+            handle.abort();
+        }
+    }
+}
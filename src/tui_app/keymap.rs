@@ -0,0 +1,509 @@
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+
+use eyre::{eyre, Result};
+
+use ratatui::prelude::{Line, Span, Stylize};
+
+use schemars::JsonSchema;
+
+use serde::Deserialize;
+
+use std::collections::BTreeMap;
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+use std::sync::OnceLock;
+
+use strum::{EnumIter, EnumString, IntoEnumIterator, IntoStaticStr};
+
+use crate::i18n::Msg;
+use crate::tui_app::theme::*;
+use UIElement::*;
+
+/// What a key does in the main view, named in snake case in keymap files
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, EnumIter, EnumString, IntoStaticStr)]
+#[strum(serialize_all = "snake_case")]
+pub enum Action {
+    Quit,
+    Help,
+    NextSensor,
+    PrevSensor,
+    NextMetric,
+    PrevMetric,
+    PrevMetricPage,
+    NextMetricPage,
+    CreateSensor,
+    CreateMetric,
+    EditSensor,
+    CloneSensor,
+    ToggleSensorState,
+    ToggleMute,
+    EditMetric,
+    DeleteSensor,
+    DeleteMetric,
+    RefreshSensor,
+    RefreshMetric,
+    PushValue,
+    Simulate,
+    EditAlert,
+    EditLogFilters,
+    ViewInventoryJson,
+    ViewExchanges,
+    ViewErrors,
+    ViewAlertHistory,
+    SilenceAlerts,
+    RetryLastRequest,
+    SaveInventory,
+    ImportInventory,
+    ToggleLogPane,
+    ScrollLogBack,
+    ScrollLogForward,
+    ShrinkRegion,
+    GrowRegion,
+    Filter,
+    ToggleFocusFollow,
+    CycleComparison,
+    CycleTimeAxis,
+    ToggleFreeze,
+    ToggleStats,
+    ToggleMetricLayout,
+    CycleTableSort,
+    ToggleOverview,
+    ToggleTheme,
+}
+
+/// Section of the help screen
+#[derive(Debug, Clone, Copy, PartialEq, Eq, EnumIter)]
+pub enum KeyGroup {
+    Sensors,
+    Metrics,
+    Dialogs,
+    View,
+    General,
+}
+
+impl KeyGroup {
+    pub fn title(&self) -> &'static str {
+        let title = match self {
+            Self::Sensors => Msg::SensorsGroup,
+            Self::Metrics => Msg::MetricsGroup,
+            Self::Dialogs => Msg::DialogsGroup,
+            Self::View => Msg::ViewGroup,
+            Self::General => Msg::GeneralGroup,
+        };
+        title.text()
+    }
+}
+
+/// Key along with the Ctrl and Alt modifiers it's pressed with. Written as in `ctrl+n`, `alt+v`,
+/// `pagedown` or `N` in keymap files.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Key {
+    pub code: KeyCode,
+    pub modifiers: KeyModifiers,
+}
+
+/// Names of the keys which aren't written as the character they type
+const KEY_NAMES: &[(KeyCode, &str)] = {
+    use KeyCode::*;
+    &[
+        (Tab, "tab"),
+        (BackTab, "backtab"),
+        (Char(' '), "space"),
+        (PageUp, "pageup"),
+        (PageDown, "pagedown"),
+        (Up, "up"),
+        (Down, "down"),
+        (Left, "left"),
+        (Right, "right"),
+        (Home, "home"),
+        (End, "end"),
+        (Enter, "enter"),
+        (Esc, "esc"),
+        (Backspace, "backspace"),
+        (Delete, "delete"),
+        (Insert, "insert"),
+    ]
+};
+
+impl Key {
+    pub const fn plain(code: KeyCode) -> Self {
+        Self {
+            code,
+            modifiers: KeyModifiers::NONE,
+        }
+    }
+
+    pub const fn ctrl(c: char) -> Self {
+        Self {
+            code: KeyCode::Char(c),
+            modifiers: KeyModifiers::CONTROL,
+        }
+    }
+
+    pub const fn alt(c: char) -> Self {
+        Self {
+            code: KeyCode::Char(c),
+            modifiers: KeyModifiers::ALT,
+        }
+    }
+
+    /// The key pressed, Shift only being told apart by the character it types
+    pub fn of(key_event: &KeyEvent) -> Self {
+        Self {
+            code: key_event.code,
+            modifiers: key_event.modifiers & (KeyModifiers::CONTROL | KeyModifiers::ALT),
+        }
+    }
+
+    /// How the key is shown in the footer and on the help screen
+    pub fn label(&self) -> String {
+        use KeyCode::*;
+        let key = match self.code {
+            Tab => "↹".into(),
+            BackTab => "⇧ + ↹".into(),
+            Char(' ') => "␣".into(),
+            PageUp => "PgUp".into(),
+            PageDown => "PgDn".into(),
+            Up => "↑".into(),
+            Down => "↓".into(),
+            Left => "←".into(),
+            Right => "→".into(),
+            Char(c) => c.to_string(),
+            _ => self.to_string(),
+        };
+        let mut label = String::new();
+        if self.modifiers.contains(KeyModifiers::CONTROL) {
+            label.push('^');
+        }
+        if self.modifiers.contains(KeyModifiers::ALT) {
+            label.push_str("M-");
+        }
+        label + &key
+    }
+}
+
+impl Display for Key {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        if self.modifiers.contains(KeyModifiers::CONTROL) {
+            write!(f, "ctrl+")?;
+        }
+        if self.modifiers.contains(KeyModifiers::ALT) {
+            write!(f, "alt+")?;
+        }
+        match (KEY_NAMES.iter().find(|(code, _)| *code == self.code), self.code) {
+            (Some((_, name)), _) => write!(f, "{name}"),
+            (None, KeyCode::F(number)) => write!(f, "f{number}"),
+            (None, KeyCode::Char(c)) => write!(f, "{c}"),
+            (None, code) => write!(f, "{code:?}"),
+        }
+    }
+}
+
+impl FromStr for Key {
+    type Err = eyre::Report;
+
+    fn from_str(text: &str) -> Result<Self> {
+        let mut key = text;
+        let mut modifiers = KeyModifiers::NONE;
+        loop {
+            if let Some(rest) = key.strip_prefix("ctrl+").filter(|rest| !rest.is_empty()) {
+                modifiers |= KeyModifiers::CONTROL;
+                key = rest;
+            } else if let Some(rest) = key.strip_prefix("alt+").filter(|rest| !rest.is_empty()) {
+                modifiers |= KeyModifiers::ALT;
+                key = rest;
+            } else {
+                break;
+            }
+        }
+
+        let mut chars = key.chars();
+        let code = match (chars.next(), chars.next()) {
+            (Some(c), None) => KeyCode::Char(c),
+            _ => KEY_NAMES
+                .iter()
+                .find(|(_, name)| name.eq_ignore_ascii_case(key))
+                .map(|(code, _)| *code)
+                .or_else(|| {
+                    let number = key.strip_prefix(['f', 'F'])?.parse().ok()?;
+                    (1..=12).contains(&number).then_some(KeyCode::F(number))
+                })
+                .ok_or_else(|| eyre!("Unknown key '{text}'"))?,
+        };
+        Ok(Self { code, modifiers })
+    }
+}
+
+#[derive(Debug)]
+pub struct KeyBinding {
+    pub key: Key,
+    pub action: Action,
+    pub group: KeyGroup,
+    pub description: Msg,
+}
+
+const fn bind(key: Key, action: Action, group: KeyGroup, description: Msg) -> KeyBinding {
+    KeyBinding {
+        key,
+        action,
+        group,
+        description,
+    }
+}
+
+/// Every action of the main view with its key in the default preset. Both the footer and
+/// the help screen are generated from the bindings of the keymap in use.
+const DEFAULT_BINDINGS: &[KeyBinding] = {
+    use Action::*;
+    use KeyCode::*;
+    const fn key(code: KeyCode) -> Key {
+        Key::plain(code)
+    }
+    &[
+        bind(key(Tab), NextSensor, KeyGroup::Sensors, Msg::NextSensorHelp),
+        bind(key(Up), PrevSensor, KeyGroup::Sensors, Msg::PrevSensorHelp),
+        bind(key(Char('n')), CreateSensor, KeyGroup::Sensors, Msg::CreateSensorHelp),
+        bind(key(Char('e')), EditSensor, KeyGroup::Sensors, Msg::EditSensorHelp),
+        bind(key(Char('c')), CloneSensor, KeyGroup::Sensors, Msg::CloneSensorHelp),
+        bind(key(Char('o')), ToggleSensorState, KeyGroup::Sensors, Msg::ToggleSensorStateHelp),
+        bind(key(Char('m')), ToggleMute, KeyGroup::Sensors, Msg::ToggleMuteHelp),
+        bind(key(Char('d')), DeleteSensor, KeyGroup::Sensors, Msg::DeleteSensorHelp),
+        bind(key(Char('r')), RefreshSensor, KeyGroup::Sensors, Msg::RefreshSensorHelp),
+        bind(key(BackTab), NextMetric, KeyGroup::Metrics, Msg::NextMetricHelp),
+        bind(key(Left), PrevMetric, KeyGroup::Metrics, Msg::PrevMetricHelp),
+        bind(key(PageUp), PrevMetricPage, KeyGroup::Metrics, Msg::PrevMetricPageHelp),
+        bind(key(PageDown), NextMetricPage, KeyGroup::Metrics, Msg::NextMetricPageHelp),
+        bind(key(Char('N')), CreateMetric, KeyGroup::Metrics, Msg::CreateMetricHelp),
+        bind(key(Char('E')), EditMetric, KeyGroup::Metrics, Msg::EditMetricHelp),
+        bind(key(Char('D')), DeleteMetric, KeyGroup::Metrics, Msg::DeleteMetricHelp),
+        bind(key(Char('R')), RefreshMetric, KeyGroup::Metrics, Msg::RefreshMetricHelp),
+        bind(key(Char(' ')), PushValue, KeyGroup::Metrics, Msg::PushValueHelp),
+        bind(key(Char('S')), Simulate, KeyGroup::Metrics, Msg::SimulateHelp),
+        bind(key(Char('a')), EditAlert, KeyGroup::Metrics, Msg::EditAlertHelp),
+        bind(key(Char('L')), EditLogFilters, KeyGroup::Dialogs, Msg::EditLogFiltersHelp),
+        bind(key(Char('j')), ViewInventoryJson, KeyGroup::Dialogs, Msg::ViewInventoryJsonHelp),
+        bind(key(Char('x')), ViewExchanges, KeyGroup::Dialogs, Msg::ViewExchangesHelp),
+        bind(key(Char('!')), ViewErrors, KeyGroup::Dialogs, Msg::ViewErrorsHelp),
+        bind(key(Char('A')), ViewAlertHistory, KeyGroup::Dialogs, Msg::ViewAlertHistoryHelp),
+        bind(key(Char('M')), SilenceAlerts, KeyGroup::Dialogs, Msg::SilenceAlertsHelp),
+        bind(key(Char('.')), RetryLastRequest, KeyGroup::Dialogs, Msg::RetryLastRequestHelp),
+        bind(key(Char('s')), SaveInventory, KeyGroup::Dialogs, Msg::SaveInventoryHelp),
+        bind(key(Char('I')), ImportInventory, KeyGroup::Dialogs, Msg::ImportInventoryHelp),
+        bind(key(Char('l')), ToggleLogPane, KeyGroup::View, Msg::ToggleLogPaneHelp),
+        bind(key(Char('{')), ScrollLogBack, KeyGroup::View, Msg::ScrollLogBackHelp),
+        bind(key(Char('}')), ScrollLogForward, KeyGroup::View, Msg::ScrollLogForwardHelp),
+        bind(key(Char('[')), ShrinkRegion, KeyGroup::View, Msg::ShrinkRegionHelp),
+        bind(key(Char(']')), GrowRegion, KeyGroup::View, Msg::GrowRegionHelp),
+        bind(key(Char('/')), Filter, KeyGroup::View, Msg::FilterHelp),
+        bind(key(Char('f')), ToggleFocusFollow, KeyGroup::View, Msg::ToggleFocusFollowHelp),
+        bind(key(Char('p')), CycleComparison, KeyGroup::View, Msg::CycleComparisonHelp),
+        bind(key(Char('w')), CycleTimeAxis, KeyGroup::View, Msg::CycleTimeAxisHelp),
+        bind(key(Char('z')), ToggleFreeze, KeyGroup::View, Msg::ToggleFreezeHelp),
+        bind(key(Char('i')), ToggleStats, KeyGroup::View, Msg::ToggleStatsHelp),
+        bind(key(Char('v')), ToggleMetricLayout, KeyGroup::View, Msg::ToggleMetricLayoutHelp),
+        bind(key(Char('V')), CycleTableSort, KeyGroup::View, Msg::CycleTableSortHelp),
+        bind(key(Char('g')), ToggleOverview, KeyGroup::View, Msg::ToggleOverviewHelp),
+        bind(key(Char('t')), ToggleTheme, KeyGroup::View, Msg::ToggleThemeHelp),
+        bind(key(Char('?')), Help, KeyGroup::General, Msg::HelpHelp),
+        bind(key(Char('q')), Quit, KeyGroup::General, Msg::Quit),
+    ]
+};
+
+/// hjkl moves between the sensors and the metrics, the keys they take from the default
+/// preset move elsewhere
+const VIM_KEYS: &[(Action, Key)] = {
+    use Action::*;
+    &[
+        (NextSensor, Key::plain(KeyCode::Char('j'))),
+        (PrevSensor, Key::plain(KeyCode::Char('k'))),
+        (NextMetric, Key::plain(KeyCode::Char('l'))),
+        (PrevMetric, Key::plain(KeyCode::Char('h'))),
+        (NextMetricPage, Key::ctrl('f')),
+        (PrevMetricPage, Key::ctrl('b')),
+        (ViewInventoryJson, Key::plain(KeyCode::Char('J'))),
+        (ToggleLogPane, Key::ctrl('l')),
+    ]
+};
+
+const EMACS_KEYS: &[(Action, Key)] = {
+    use Action::*;
+    &[
+        (NextSensor, Key::ctrl('n')),
+        (PrevSensor, Key::ctrl('p')),
+        (NextMetric, Key::ctrl('f')),
+        (PrevMetric, Key::ctrl('b')),
+        (NextMetricPage, Key::ctrl('v')),
+        (PrevMetricPage, Key::alt('v')),
+        (Filter, Key::ctrl('s')),
+    ]
+};
+
+/// Keys shipped along with the default ones, selected with `keymap` in the `[ui]` config section
+#[derive(
+    Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, JsonSchema, EnumIter, EnumString, IntoStaticStr,
+)]
+#[serde(rename_all = "lowercase")]
+#[strum(serialize_all = "lowercase")]
+pub enum KeymapPreset {
+    #[default]
+    Default,
+    Vim,
+    Emacs,
+}
+
+impl KeymapPreset {
+    pub fn bindings(self) -> Vec<KeyBinding> {
+        let keys = match self {
+            Self::Default => &[][..],
+            Self::Vim => VIM_KEYS,
+            Self::Emacs => EMACS_KEYS,
+        };
+        let mut bindings: Vec<KeyBinding> = DEFAULT_BINDINGS
+            .iter()
+            .map(|binding| bind(binding.key, binding.action, binding.group, binding.description))
+            .collect();
+        for (action, key) in keys {
+            if let Some(binding) = bindings.iter_mut().find(|binding| binding.action == *action) {
+                binding.key = *key;
+            }
+        }
+        bindings
+    }
+}
+
+/// Bindings of the preset with the keys of the actions in `keys` replaced, as read from
+/// a keymap file. Fails on unknown actions or keys and on keys bound to two actions.
+pub fn keymap(preset: KeymapPreset, keys: &BTreeMap<String, String>) -> Result<Vec<KeyBinding>> {
+    let mut bindings = preset.bindings();
+    for (action, key) in keys {
+        let action: Action = action.parse().map_err(|_| eyre!("Unknown action '{action}'"))?;
+        let key: Key = key.parse()?;
+        if let Some(binding) = bindings.iter_mut().find(|binding| binding.action == action) {
+            binding.key = key;
+        }
+    }
+    for (index, binding) in bindings.iter().enumerate() {
+        if let Some(other) = bindings[index + 1..].iter().find(|other| other.key == binding.key) {
+            let name = |action: Action| -> &'static str { action.into() };
+            return Err(eyre!(
+                "{} is bound to both {} and {}",
+                binding.key,
+                name(binding.action),
+                name(other.action)
+            ));
+        }
+    }
+    Ok(bindings)
+}
+
+/// Keys of every action by its name, as written to a keymap file
+pub fn keymap_keys(bindings: &[KeyBinding]) -> BTreeMap<String, String> {
+    bindings
+        .iter()
+        .map(|binding| {
+            let action: &'static str = binding.action.into();
+            (action.to_owned(), binding.key.to_string())
+        })
+        .collect()
+}
+
+static KEYMAP: OnceLock<Vec<KeyBinding>> = OnceLock::new();
+
+/// Selects the keymap once on startup, later calls are ignored
+pub fn set_keymap(bindings: Vec<KeyBinding>) {
+    let _ = KEYMAP.set(bindings);
+}
+
+/// The keymap in use, the default preset unless another one was set
+pub fn bindings() -> &'static [KeyBinding] {
+    KEYMAP.get_or_init(|| KeymapPreset::Default.bindings())
+}
+
+/// Footer sections, each entry is a title and the actions whose keys follow it
+const FOOTER: &[&[(Msg, &[Action])]] = {
+    use Action::*;
+    &[
+        &[
+            (Msg::Next, &[NextSensor]),
+            (Msg::New, &[CreateSensor]),
+            (Msg::Edit, &[EditSensor]),
+            (Msg::Delete, &[DeleteSensor]),
+            (Msg::Refresh, &[RefreshSensor]),
+            (Msg::Mute, &[ToggleMute]),
+            (Msg::Push, &[PushValue]),
+            (Msg::Simulate, &[Simulate]),
+            (Msg::Alert, &[EditAlert]),
+        ],
+        &[
+            (Msg::LogFilters, &[EditLogFilters]),
+            (Msg::InventoryJson, &[ViewInventoryJson]),
+            (Msg::Exchanges, &[ViewExchanges]),
+            (Msg::SaveJson, &[SaveInventory]),
+            (Msg::ImportJson, &[ImportInventory]),
+        ],
+        &[
+            (Msg::Page, &[PrevMetricPage, NextMetricPage]),
+            (Msg::Log, &[ToggleLogPane]),
+            (Msg::Scroll, &[ScrollLogBack, ScrollLogForward]),
+            (Msg::Resize, &[ShrinkRegion, GrowRegion]),
+        ],
+        &[
+            (Msg::Filter, &[Filter]),
+            (Msg::Follow, &[ToggleFocusFollow]),
+            (Msg::Compare, &[CycleComparison]),
+            (Msg::Axis, &[CycleTimeAxis]),
+            (Msg::Freeze, &[ToggleFreeze]),
+            (Msg::Stats, &[ToggleStats]),
+            (Msg::Table, &[ToggleMetricLayout]),
+            (Msg::Overview, &[ToggleOverview]),
+        ],
+        &[(Msg::Help, &[Help]), (Msg::Quit, &[Quit])],
+    ]
+};
+
+pub fn action_for(key_event: &KeyEvent) -> Option<Action> {
+    let key = Key::of(key_event);
+    bindings()
+        .iter()
+        .find(|binding| binding.key == key)
+        .map(|binding| binding.action)
+}
+
+fn key_label(action: Action) -> String {
+    bindings()
+        .iter()
+        .find(|binding| binding.action == action)
+        .map(|binding| binding.key.label())
+        .unwrap_or_default()
+}
+
+pub fn bindings_of(group: KeyGroup) -> impl Iterator<Item = &'static KeyBinding> {
+    bindings().iter().filter(move |binding| binding.group == group)
+}
+
+pub fn groups() -> impl Iterator<Item = KeyGroup> {
+    KeyGroup::iter()
+}
+
+/// Instructions at the bottom of the main view
+pub fn footer_instructions() -> Line<'static> {
+    let mut spans: Vec<Span> = vec![
+        format!(" {} ", Msg::SensorAction.text()).themed(InstructionsText),
+        Msg::Key.text().themed(InstructionsActionText).bold(),
+        format!(" {} ", Msg::MetricAction.text()).themed(InstructionsText),
+        format!("{} ", Msg::ShiftKey.text()).themed(InstructionsActionText).bold(),
+    ];
+    for section in FOOTER {
+        spans.push("|".themed(InstructionsText));
+        for (title, actions) in *section {
+            let keys = actions
+                .iter()
+                .map(|action| key_label(*action))
+                .collect::<Vec<_>>()
+                .join("/");
+            spans.push(format!(" {} ", title.text()).themed(InstructionsText));
+            spans.push(format!("{keys} ").themed(InstructionsActionText).bold());
+        }
+    }
+    Line::from(spans)
+}
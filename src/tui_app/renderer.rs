@@ -0,0 +1,55 @@
+use actix::Addr;
+
+use eyre::{eyre, Result};
+
+use tokio::sync::mpsc;
+use tokio::task::JoinHandle;
+
+use crate::tui_app::tui::Tui;
+use crate::tui_app::ui_state::queries::SetMetricPageSize;
+use crate::tui_app::ui_state::render::FrameSnapshot;
+use crate::tui_app::ui_state::UIState;
+
+/// Draws the frames on a thread of its own, which owns the terminal. Neither input
+/// handling nor the UI state ever wait for the terminal to be written to.
+pub struct Renderer {
+    frame_sender: mpsc::Sender<FrameSnapshot>,
+    handle: JoinHandle<Tui>,
+}
+
+impl Renderer {
+    pub fn spawn(mut tui: Tui, ui_state_actor: Addr<UIState>) -> Self {
+        let (frame_sender, mut frame_receiver) = mpsc::channel::<FrameSnapshot>(1);
+        let handle = tokio::task::spawn_blocking(move || {
+            while let Some(mut frame) = frame_receiver.blocking_recv() {
+                // Only the latest of the frames queued while drawing is worth drawing
+                while let Ok(mut newer) = frame_receiver.try_recv() {
+                    newer.supersede(frame);
+                    frame = newer;
+                }
+                // Paging keys move by as many metrics as fit the last frame
+                if let Some(metric_page_size) = frame.draw(&mut tui) {
+                    ui_state_actor.do_send(SetMetricPageSize(metric_page_size));
+                }
+            }
+            tui
+        });
+        Self {
+            frame_sender,
+            handle,
+        }
+    }
+
+    pub async fn draw(&self, frame: FrameSnapshot) -> Result<()> {
+        self.frame_sender
+            .send(frame)
+            .await
+            .map_err(|_| eyre!("The renderer has stopped"))
+    }
+
+    /// Waits for the frames sent so far to be drawn and hands the terminal back
+    pub async fn stop(self) -> Result<Tui> {
+        drop(self.frame_sender);
+        Ok(self.handle.await?)
+    }
+}
@@ -1,7 +1,7 @@
 use ratatui::prelude::Stylize;
-use ratatui::style::{Color, Styled};
+use ratatui::style::{Color, Modifier, Styled};
 
-use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
 
 use strum::EnumProperty;
 use strum_macros;
@@ -10,34 +10,69 @@ use crate::model::sensor::{ValueType, ValueUnit};
 
 pub static THEME_INDEX: AtomicUsize = AtomicUsize::new(0);
 
+/// Set when the terminal can't (or must not) render colors,
+/// the elements are then told apart by their `attrs` only.
+pub static MONOCHROME: AtomicBool = AtomicBool::new(false);
+
+/// Honours https://no-color.org and terminals reporting less than 8 colors
+pub fn detect_color_support() {
+    let no_color = std::env::var_os("NO_COLOR").is_some_and(|value| !value.is_empty());
+    let monochrome = no_color || crossterm::style::available_color_count() < 8;
+    MONOCHROME.store(monochrome, Ordering::SeqCst);
+}
+
 #[derive(strum_macros::EnumProperty)]
 pub enum UIElement {
     /// Color indices according to https://en.wikipedia.org/wiki/ANSI_escape_code#8-bit
     /// `"dark_color, light_color"`
+    ///
+    /// `attrs` are the only styling applied on monochrome terminals:
+    /// `"bold"`, `"dim"`, `"italic"`, `"underlined"`, `"reversed"` separated by `,`
     #[strum(props(bg_colors = "232,255", fg_colors = "14,0"))]
     AppPad,
 
     #[strum(props(fg_colors = "14,0"))]
     InstructionsText,
 
-    #[strum(props(fg_colors = "9,1"))]
+    #[strum(props(fg_colors = "9,1", attrs = "bold"))]
     InstructionsActionText,
 
-    #[strum(props(fg_colors = "9,9"))]
+    #[strum(props(fg_colors = "9,9", attrs = "italic"))]
     NoSensors,
 
-    #[strum(props(bg_colors = "21,39"))]
+    #[strum(props(bg_colors = "21,39", attrs = "reversed"))]
     SelectedSensorTab,
 
-    #[strum(props(fg_colors = "9,9"))]
+    #[strum(props(fg_colors = "9,9", attrs = "italic"))]
     NoMetrics,
 
-    #[strum(props(fg_colors = "189,21"))]
+    #[strum(props(fg_colors = "189,21", attrs = "bold"))]
     SensorName,
 
     #[strum(props(fg_colors = "117,57"))]
     SensorId,
 
+    #[strum(props(fg_colors = "10,28"))]
+    SensorEnabled,
+
+    #[strum(props(fg_colors = "208,166", attrs = "italic"))]
+    SensorDisabled,
+
+    #[strum(props(fg_colors = "244,242", attrs = "italic"))]
+    SensorMuted,
+
+    #[strum(props(fg_colors = "10,28"))]
+    PingHealthy,
+
+    #[strum(props(fg_colors = "11,130"))]
+    PingFailing,
+
+    #[strum(props(fg_colors = "15,15", bg_colors = "124,160", attrs = "bold"))]
+    HealthBanner,
+
+    #[strum(props(fg_colors = "0,15", bg_colors = "214,25", attrs = "bold"))]
+    ProfileBadge,
+
     #[strum(props(fg_colors = "117,57"))]
     MetricId,
 
@@ -56,50 +91,119 @@ pub enum UIElement {
     #[strum(props(fg_colors = "252,233"))]
     MetricPropsBlock,
 
-    #[strum(props(fg_colors = "33,202"))]
+    #[strum(props(fg_colors = "33,202", attrs = "bold"))]
     MetricPropsBlockSelected,
 
-    #[strum(props(fg_colors = "13,5"))]
+    #[strum(props(fg_colors = "196,160", attrs = "bold"))]
+    MetricPropsBlockAlert,
+
+    #[strum(props(fg_colors = "13,5", attrs = "dim"))]
     MetricNoData,
 
+    #[strum(props(fg_colors = "189,21", attrs = "bold,underlined"))]
+    MetricTableHeader,
+
+    #[strum(props(bg_colors = "21,39", attrs = "reversed"))]
+    MetricTableSelected,
+
+    #[strum(props(fg_colors = "10,28", attrs = "bold"))]
+    ActivityTrail,
+
+    #[strum(props(fg_colors = "242,246", attrs = "dim"))]
+    ScreensaverSummary,
+
     #[strum(props(fg_colors = "4,2"))]
     LivedataLine,
 
     #[strum(props(fg_colors = "9,1"))]
     LivedataScatter,
 
+    #[strum(props(fg_colors = "242,246", attrs = "dim"))]
+    LatencyWatermark,
+
     #[strum(props(bg_colors = "234,253"))]
     LivedataChart,
 
+    #[strum(props(fg_colors = "4,2"))]
+    LivedataText,
+
+    #[strum(props(fg_colors = "8,7", attrs = "dim"))]
+    LivedataGap,
+
+    #[strum(props(fg_colors = "6,4", attrs = "dim"))]
+    LivedataGhost,
+
+    #[strum(props(fg_colors = "11,4", attrs = "bold"))]
+    FilterQuery,
+
+    #[strum(props(fg_colors = "13,5"))]
+    HiddenMetrics,
+
     #[strum(props(fg_colors = "7,15", bg_colors = "18,27"))]
     DialogPad,
 
     #[strum(props(fg_colors = "21,33", bg_colors = "18,27"))]
     OptionCard,
 
-    #[strum(props(fg_colors = "129,202", bg_colors = "18,27"))]
+    #[strum(props(fg_colors = "129,202", bg_colors = "18,27", attrs = "bold"))]
     OptionCardSelected,
 
     #[strum(props(fg_colors = "15,0", bg_colors = "244,243"))]
     DialogButton,
 
-    #[strum(props(fg_colors = "15,0", bg_colors = "45,214"))]
+    #[strum(props(fg_colors = "15,0", bg_colors = "45,214", attrs = "reversed"))]
     DialogButtonFocused,
 
     #[strum(props(fg_colors = "15,15"))]
     DialogInstructionsText,
 
-    #[strum(props(fg_colors = "9,220"))]
+    #[strum(props(fg_colors = "9,220", attrs = "bold"))]
     DialogInstructionsActionText,
 
-    #[strum(props(bg_colors = "238,250", fg_colors = "15,0"))]
+    #[strum(props(bg_colors = "238,250", fg_colors = "15,0", attrs = "underlined"))]
     DialogTextInput,
 
-    #[strum(props(bg_colors = "27,44", fg_colors = "15,0"))]
+    #[strum(props(bg_colors = "27,44", fg_colors = "15,0", attrs = "reversed"))]
     DialogTextInputFocused,
 
-    #[strum(props(fg_colors = "9,1"))]
+    #[strum(props(fg_colors = "9,1", attrs = "bold"))]
     ErrorLog,
+
+    #[strum(props(fg_colors = "11,130"))]
+    WarnLog,
+
+    #[strum(props(fg_colors = "7,0"))]
+    InfoLog,
+
+    #[strum(props(fg_colors = "8,245", attrs = "dim"))]
+    DebugLog,
+
+    #[strum(props(fg_colors = "117,25", attrs = "bold"))]
+    JsonKey,
+
+    #[strum(props(fg_colors = "114,28"))]
+    JsonString,
+
+    #[strum(props(fg_colors = "215,130"))]
+    JsonNumber,
+
+    #[strum(props(fg_colors = "176,90", attrs = "italic"))]
+    JsonLiteral,
+
+    #[strum(props(fg_colors = "250,240"))]
+    JsonPunctuation,
+
+    #[strum(props(bg_colors = "58,229", attrs = "underlined"))]
+    JsonSearchMatch,
+
+    #[strum(props(fg_colors = "10,28"))]
+    DiffCreated,
+
+    #[strum(props(fg_colors = "11,130"))]
+    DiffUpdated,
+
+    #[strum(props(fg_colors = "9,1", attrs = "bold"))]
+    DiffDeleted,
 }
 
 impl UIElement {
@@ -116,6 +220,24 @@ impl UIElement {
         (bg_colors, fg_colors)
     }
 
+    fn modifiers(&self) -> Modifier {
+        let Some(attrs) = self.get_str("attrs") else {
+            return Modifier::empty();
+        };
+
+        attrs
+            .split(",")
+            .map(|attr| match attr.trim() {
+                "bold" => Modifier::BOLD,
+                "dim" => Modifier::DIM,
+                "italic" => Modifier::ITALIC,
+                "underlined" => Modifier::UNDERLINED,
+                "reversed" => Modifier::REVERSED,
+                _ => Modifier::empty(),
+            })
+            .fold(Modifier::empty(), |acc, modifier| acc | modifier)
+    }
+
     fn parse_into_colors(colors: &str) -> Option<(Color, Color)> {
         let split: Vec<&str> = colors.split(",").collect();
         if let [dark, light, ..] = split[..] {
@@ -132,6 +254,12 @@ impl UIElement {
 pub trait ColorThemed<'a, T>: Stylize<'a, T> + Sized + Styled<Item = T> {
     fn themed(self, elem: UIElement) -> T {
         let mut style = self.style();
+
+        if MONOCHROME.load(Ordering::SeqCst) {
+            style = style.add_modifier(elem.modifiers());
+            return self.set_style(style);
+        }
+
         let theme_idx = THEME_INDEX.load(Ordering::SeqCst);
 
         let (bg_colors, fg_colors) = elem.color_indices();
@@ -1,7 +1,9 @@
 use eyre::Result;
 
-use crossterm::event::{DisableMouseCapture, EnableMouseCapture};
-use crossterm::terminal::{self, EnterAlternateScreen, LeaveAlternateScreen};
+use crossterm::event::{
+    DisableFocusChange, DisableMouseCapture, EnableFocusChange, EnableMouseCapture,
+};
+use crossterm::terminal::{self, EnterAlternateScreen, LeaveAlternateScreen, SetTitle};
 
 use ratatui::{backend::CrosstermBackend, Terminal};
 
@@ -9,8 +11,9 @@ use std::io;
 use std::io::Stdout;
 use std::panic;
 
+use crate::tui_app::theme::detect_color_support;
+
 pub type CrosstermTerminal = Terminal<CrosstermBackend<Stdout>>;
-pub type SharedTui = std::sync::Arc<tokio::sync::Mutex<Tui>>;
 
 #[derive(Debug)]
 pub struct Tui {
@@ -23,8 +26,15 @@ impl Tui {
     }
 
     pub fn init(&mut self) -> Result<()> {
+        detect_color_support();
+
         terminal::enable_raw_mode()?;
-        crossterm::execute!(io::stdout(), EnterAlternateScreen, EnableMouseCapture)?;
+        crossterm::execute!(
+            io::stdout(),
+            EnterAlternateScreen,
+            EnableMouseCapture,
+            EnableFocusChange
+        )?;
 
         let panic_hook = panic::take_hook();
         panic::set_hook(Box::new(move |panic| {
@@ -39,7 +49,17 @@ impl Tui {
 
     fn reset() -> Result<()> {
         terminal::disable_raw_mode()?;
-        crossterm::execute!(io::stdout(), LeaveAlternateScreen, DisableMouseCapture)?;
+        crossterm::execute!(
+            io::stdout(),
+            LeaveAlternateScreen,
+            DisableMouseCapture,
+            DisableFocusChange
+        )?;
+        Ok(())
+    }
+
+    pub fn set_title(&mut self, title: &str) -> Result<()> {
+        crossterm::execute!(io::stdout(), SetTitle(title))?;
         Ok(())
     }
 
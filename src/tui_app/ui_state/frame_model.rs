@@ -0,0 +1,374 @@
+use serde::Serialize;
+
+use std::cmp::Ordering;
+use std::time::Duration;
+
+use crate::build_info;
+use crate::client::state::Sensors;
+use crate::i18n::Msg;
+use crate::model::sensor::{Metric, Sensor, SensorState};
+use crate::model::{MetricId, SensorId, Timestamp};
+use crate::tui_app::ui_state::{MetricLayout, TableColumn, UIState};
+
+/// What the main view shows, as plain data. Everything depending on the terminal
+/// size (visible tabs, pages of cards, charts) is left to the renderer, so the model
+/// can be checked headlessly or exported to other frontends as JSON.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct FrameModel {
+    /// Warning across the top of the screen
+    pub banner: Option<String>,
+    pub header: Header,
+    /// Shown while the filter is active or being edited
+    pub filter: Option<FilterLine>,
+    pub body: Body,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Header {
+    pub title: String,
+    /// Config profile, so that e.g. prod isn't mistaken for staging
+    pub profile: Option<String>,
+    pub following: bool,
+    /// The charts are frozen for inspection
+    pub frozen: bool,
+    pub reloading: bool,
+    /// Maintenance windows in progress
+    pub maintenance: usize,
+    /// Every alert is silenced until then
+    pub silenced_until: Option<Timestamp>,
+    pub errors: usize,
+    pub ping: Option<PingBadge>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub enum PingBadge {
+    Latency(Duration),
+    /// Pings failed since the last answered one
+    Failing(u32),
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct FilterLine {
+    pub query: String,
+    pub editing: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub enum Body {
+    NoSensors,
+    NoMatch { query: String },
+    Sensors {
+        /// Tabs of the sensors the filter shows
+        tabs: Vec<Tab>,
+        /// Position of the selected tab among `tabs`
+        selected: Option<usize>,
+        sensor: Option<SensorView>,
+    },
+    /// All the sensors the filter shows, a row each
+    Overview { rows: Vec<OverviewRow> },
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Tab {
+    /// Position of the sensor among all the sensors
+    pub index: usize,
+    pub sensor_id: SensorId,
+    pub title: String,
+    pub disabled: bool,
+    /// Livedata of the sensor is only counted
+    pub muted: bool,
+    /// How recently the sensor received livedata, `0` being the latest
+    pub activity: Option<usize>,
+}
+
+/// The selected sensor with the cards of the metrics the filter shows
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct SensorView {
+    pub sensor_id: SensorId,
+    pub name: String,
+    pub state: SensorState,
+    pub cards: Vec<MetricCard>,
+    /// Shown instead of the cards in the table layout
+    pub table: Option<MetricTable>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct MetricCard {
+    /// Position of the metric among all the metrics of the sensor
+    pub index: usize,
+    pub metric_id: MetricId,
+    pub name: String,
+    pub selected: bool,
+    pub alert: bool,
+    pub activity: Option<usize>,
+}
+
+/// A sensor with a sparkline per metric the filter shows
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct OverviewRow {
+    /// Position of the sensor among all the sensors
+    pub index: usize,
+    pub sensor_id: SensorId,
+    pub name: String,
+    pub disabled: bool,
+    pub muted: bool,
+    pub selected: bool,
+    pub sparklines: Vec<MetricSparkline>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct MetricSparkline {
+    pub metric_id: MetricId,
+    pub name: String,
+    /// The numeric values kept, oldest first, scaled from the lowest to the highest of them
+    /// onto `1..=SPARKLINE_MAX` so that a flat line still shows
+    pub points: Vec<u64>,
+    pub last_value: Option<String>,
+    pub alert: bool,
+}
+
+pub const SPARKLINE_MAX: u64 = 100;
+
+/// The metrics of the cards as rows, in the order of the sort column
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct MetricTable {
+    pub sort: TableColumn,
+    pub rows: Vec<MetricRow>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct MetricRow {
+    /// Position of the metric among all the metrics of the sensor
+    pub index: usize,
+    pub metric_id: MetricId,
+    pub name: String,
+    /// Unit of a predefined metric, value type of a custom one
+    pub kind: String,
+    pub annotation: String,
+    pub last_value: Option<String>,
+    pub updated_at: Option<Timestamp>,
+    pub selected: bool,
+    pub alert: bool,
+}
+
+impl FrameModel {
+    pub fn build(sensors: &Sensors, ui_state: &UIState, reload_pending: bool) -> Self {
+        let banner = ui_state.ping.is_failing().then(|| {
+            Msg::PingsUnansweredBanner.format(&[("count", &ui_state.ping.failures)])
+        });
+
+        let ping = if ui_state.ping.failures > 0 {
+            Some(PingBadge::Failing(ui_state.ping.failures))
+        } else {
+            ui_state.ping.latency.map(PingBadge::Latency)
+        };
+        let header = Header {
+            title: build_info::title(),
+            profile: ui_state.profile.clone(),
+            following: ui_state.focus_follow,
+            frozen: ui_state.frozen_at.is_some(),
+            reloading: reload_pending,
+            maintenance: ui_state.silencing.windows.len(),
+            silenced_until: ui_state.silencing.until,
+            errors: ui_state.errors.len(),
+            ping,
+        };
+
+        let filter = &ui_state.filter;
+        let filter_line = (filter.is_active() || filter.editing).then(|| FilterLine {
+            query: filter.query.clone(),
+            editing: filter.editing,
+        });
+
+        Self {
+            banner,
+            header,
+            filter: filter_line,
+            body: Self::body(sensors, ui_state),
+        }
+    }
+
+    fn body(sensors: &Sensors, ui_state: &UIState) -> Body {
+        if sensors.is_empty() {
+            return Body::NoSensors;
+        }
+
+        let filter = &ui_state.filter;
+        let tabs: Vec<Tab> = sensors
+            .values()
+            .enumerate()
+            .filter(|(_, sensor)| filter.shows_sensor(sensor))
+            .map(|(index, sensor)| Tab {
+                index,
+                sensor_id: sensor.sensor_id,
+                title: sensor.name.clone(),
+                disabled: sensor.state == SensorState::Disabled,
+                muted: ui_state.muted.contains(&sensor.sensor_id),
+                activity: ui_state.activity_rank(sensor.sensor_id, None),
+            })
+            .collect();
+        if tabs.is_empty() {
+            return Body::NoMatch {
+                query: filter.query.clone(),
+            };
+        }
+        if ui_state.overview {
+            let rows = tabs
+                .iter()
+                .filter_map(|tab| {
+                    let sensor = sensors.get(&tab.sensor_id)?;
+                    Some(Self::overview_row(sensor, tab, ui_state))
+                })
+                .collect();
+            return Body::Overview { rows };
+        }
+
+        let selected = ui_state
+            .current_sensor
+            .and_then(|(i, _)| tabs.iter().position(|tab| tab.index == i));
+        let sensor = selected
+            .and_then(|selected| sensors.get(&tabs[selected].sensor_id))
+            .map(|sensor| {
+                let cards: Vec<MetricCard> = sensor
+                    .metrics
+                    .iter()
+                    .enumerate()
+                    .filter(|(_, metric)| filter.shows_metric(sensor, metric))
+                    .map(|(index, metric)| {
+                        let metric_id = *metric.metric_id();
+                        MetricCard {
+                            index,
+                            metric_id,
+                            name: metric.name().clone(),
+                            selected: ui_state
+                                .current_metric
+                                .is_some_and(|(_, selected)| selected == metric_id),
+                            alert: ui_state.alerts.contains(&(sensor.sensor_id, metric_id)),
+                            activity: ui_state.activity_rank(sensor.sensor_id, Some(metric_id)),
+                        }
+                    })
+                    .collect();
+                let table = (ui_state.metric_layout == MetricLayout::Table)
+                    .then(|| Self::metric_table(sensor, &cards, ui_state));
+                SensorView {
+                    sensor_id: sensor.sensor_id,
+                    name: sensor.name.clone(),
+                    state: sensor.state,
+                    cards,
+                    table,
+                }
+            });
+
+        Body::Sensors {
+            tabs,
+            selected,
+            sensor,
+        }
+    }
+
+    fn overview_row(sensor: &Sensor<Metric>, tab: &Tab, ui_state: &UIState) -> OverviewRow {
+        let sparklines = sensor
+            .metrics
+            .iter()
+            .filter(|metric| ui_state.filter.shows_metric(sensor, metric))
+            .map(|metric| {
+                let metric_id = *metric.metric_id();
+                let key = (sensor.sensor_id, metric_id);
+                MetricSparkline {
+                    metric_id,
+                    name: metric.name().clone(),
+                    points: ui_state
+                        .livedata
+                        .get(&key)
+                        .map(|window| sparkline_points(&window.data))
+                        .unwrap_or_default(),
+                    last_value: ui_state
+                        .latest_value(sensor.sensor_id, metric_id)
+                        .map(|(_, value)| value),
+                    alert: ui_state.alerts.contains(&key),
+                }
+            })
+            .collect();
+        OverviewRow {
+            index: tab.index,
+            sensor_id: sensor.sensor_id,
+            name: sensor.name.clone(),
+            disabled: tab.disabled,
+            muted: tab.muted,
+            selected: ui_state
+                .current_sensor
+                .is_some_and(|(_, sensor_id)| sensor_id == sensor.sensor_id),
+            sparklines,
+        }
+    }
+
+    fn metric_table(sensor: &Sensor<Metric>, cards: &[MetricCard], ui_state: &UIState) -> MetricTable {
+        let mut rows: Vec<MetricRow> = cards
+            .iter()
+            .filter_map(|card| {
+                let (kind, annotation) = match sensor.metrics.get(&card.metric_id)? {
+                    Metric::Predefined { value_unit, .. } => (format!("{value_unit:?}"), String::new()),
+                    Metric::Custom {
+                        value_type,
+                        value_annotation,
+                        ..
+                    } => (format!("{value_type:?}"), value_annotation.clone()),
+                };
+                let latest = ui_state.latest_value(sensor.sensor_id, card.metric_id);
+                Some(MetricRow {
+                    index: card.index,
+                    metric_id: card.metric_id,
+                    name: card.name.clone(),
+                    kind,
+                    annotation,
+                    updated_at: latest.as_ref().map(|(timestamp, _)| *timestamp),
+                    last_value: latest.map(|(_, value)| value),
+                    selected: card.selected,
+                    alert: card.alert,
+                })
+            })
+            .collect();
+        let sort = ui_state.table_sort;
+        rows.sort_by(|a, b| compare_rows(sort, a, b).then(a.index.cmp(&b.index)));
+        MetricTable { sort, rows }
+    }
+}
+
+fn sparkline_points(data: &[(f64, f64)]) -> Vec<u64> {
+    let (min, max) = data
+        .iter()
+        .fold((f64::INFINITY, f64::NEG_INFINITY), |(min, max), (_, value)| {
+            (min.min(*value), max.max(*value))
+        });
+    data.iter()
+        .map(|(_, value)| {
+            let scaled = if max > min { (value - min) / (max - min) } else { 0.0 };
+            1 + (scaled * (SPARKLINE_MAX - 1) as f64).round() as u64
+        })
+        .collect()
+}
+
+/// Rows without livedata go last whatever the column
+fn compare_rows(column: TableColumn, a: &MetricRow, b: &MetricRow) -> Ordering {
+    fn present_first<T>(a: &Option<T>, b: &Option<T>, compare: impl FnOnce(&T, &T) -> Ordering) -> Ordering {
+        match (a, b) {
+            (Some(a), Some(b)) => compare(a, b),
+            (Some(_), None) => Ordering::Less,
+            (None, Some(_)) => Ordering::Greater,
+            (None, None) => Ordering::Equal,
+        }
+    }
+
+    match column {
+        TableColumn::Name => a.name.to_lowercase().cmp(&b.name.to_lowercase()),
+        TableColumn::Type => a.kind.cmp(&b.kind),
+        TableColumn::Annotation => a.annotation.cmp(&b.annotation),
+        TableColumn::LastValue => present_first(&a.last_value, &b.last_value, |a, b| {
+            match (a.parse::<f64>(), b.parse::<f64>()) {
+                (Ok(a), Ok(b)) => a.total_cmp(&b),
+                _ => a.cmp(b),
+            }
+        }),
+        TableColumn::LastUpdate => present_first(&a.updated_at, &b.updated_at, |a, b| b.cmp(a)),
+    }
+}
@@ -1,8 +1,88 @@
 use eyre::Result;
 
+use serde::{Deserialize, Serialize};
+
 use taffy::prelude::*;
 
-use ratatui::layout::Rect;
+use ratatui::layout::{Constraint, Rect};
+
+pub const LOG_PANE_MAX_HEIGHT: u16 = 10;
+
+const OVERVIEW_COLUMN_MIN_WIDTH: u16 = 12;
+const OVERVIEW_COLUMN_MAX_WIDTH: u16 = 48;
+/// Columns of the overview grow and shrink by this many cells at once
+const OVERVIEW_COLUMN_STEP: u16 = 4;
+
+/// User-adjustable split of the sensor view between the metric grid and the log pane, and
+/// the width of the sparkline columns of the overview. Kept per profile across runs.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(default)]
+pub struct RegionSizes {
+    log_pane_height: u16,
+    overview_column_width: u16,
+}
+
+impl Default for RegionSizes {
+    fn default() -> Self {
+        Self {
+            log_pane_height: 3,
+            overview_column_width: 24,
+        }
+    }
+}
+
+impl RegionSizes {
+    pub fn resize_log_pane(&mut self, delta: i16) {
+        self.log_pane_height = self
+            .log_pane_height
+            .saturating_add_signed(delta)
+            .clamp(1, LOG_PANE_MAX_HEIGHT);
+    }
+
+    pub fn log_pane_height(&self) -> u16 {
+        self.log_pane_height
+    }
+
+    /// By `delta` steps, fewer and wider columns fit the overview as they grow
+    pub fn resize_overview_columns(&mut self, delta: i16) {
+        self.overview_column_width = self
+            .overview_column_width
+            .saturating_add_signed(delta.saturating_mul(OVERVIEW_COLUMN_STEP as i16))
+            .clamp(OVERVIEW_COLUMN_MIN_WIDTH, OVERVIEW_COLUMN_MAX_WIDTH);
+    }
+
+    /// Sparkline of a metric along with the column of spacing after it
+    pub fn overview_column_width(&self) -> u16 {
+        self.overview_column_width
+    }
+
+    /// Sizes read back from a file, pulled into the ranges the keys resize them within
+    pub fn clamped(self) -> Self {
+        Self {
+            log_pane_height: self.log_pane_height.clamp(1, LOG_PANE_MAX_HEIGHT),
+            overview_column_width: self
+                .overview_column_width
+                .clamp(OVERVIEW_COLUMN_MIN_WIDTH, OVERVIEW_COLUMN_MAX_WIDTH),
+        }
+    }
+
+    /// The metric grid takes whatever the log pane leaves, the pane's title takes a line
+    pub fn sensor_view_constraints(&self, log_lines: usize) -> [Constraint; 2] {
+        let log_height = match (log_lines as u16).min(self.log_pane_height) {
+            0 => 0,
+            lines => lines + 1,
+        };
+        [Constraint::Fill(1), Constraint::Length(log_height)]
+    }
+}
+
+/// Number of metric cards of at least the given size fitting `area` at once
+pub fn metric_page_size(area: Rect, min_width: u16, min_height: u16) -> usize {
+    let columns = (area.width / min_width).max(1) as usize;
+    // Cards are separated by a single line gap
+    let rows = ((area.height + 1) / (min_height + 1)).max(1) as usize;
+    columns * rows
+}
 
 pub fn metric_dyn_layout(
     metric_count: usize,
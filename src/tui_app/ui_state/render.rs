@@ -1,153 +1,660 @@
-use actix::{AsyncContext, Handler, Message, WrapFuture};
+use actix::{Handler, Message, ResponseFuture};
 
-use ratatui::layout::{Constraint, Direction, Layout, Rect};
+use ratatui::layout::{Constraint, Direction, Layout, Margin, Rect};
 use ratatui::style::{Style, Stylize};
 use ratatui::symbols;
 use ratatui::symbols::border;
 use ratatui::text::{Line, Span, Text};
 use ratatui::widgets::{
-    Axis, Block, BorderType, Borders, Chart, Dataset, GraphType, List, ListItem, Paragraph, Tabs,
-    Wrap,
+    Axis, Block, BorderType, Borders, Cell, Chart, Dataset, GraphType, List, ListItem, Paragraph,
+    Row, Sparkline, Table, TableState, Tabs,
 };
 use ratatui::Frame;
 
+use strum::IntoEnumIterator;
+
+use std::ops::Range;
+use std::sync::Arc;
+use std::time::Duration;
+
 use crate::client::state::Sensors;
-use crate::model::sensor::{Metric, Sensor, ValueType};
+use crate::i18n::Msg;
+use crate::logging::{self, LogEntry};
+use crate::model::format::ValueFormat;
+use crate::model::protocol::MetricValue;
+use crate::model::sensor::{Metric, Sensor, SensorState, ValueType};
 use crate::model::SensorId;
 use crate::tui_app::dialog::render::Renderable;
 use crate::tui_app::dialog::*;
-use crate::tui_app::ui_state::layout::metric_dyn_layout;
-use crate::tui_app::ui_state::{MetricLivedataWindow, UIState};
+use crate::tui_app::keymap;
+use crate::tui_app::ui_state::frame_model::{
+    Body, FrameModel, MetricCard, MetricTable, OverviewRow, PingBadge, SensorView, SPARKLINE_MAX,
+};
+use crate::tui_app::ui_state::layout::{metric_dyn_layout, metric_page_size};
+use crate::tui_app::ui_state::{
+    resample_envelope, Comparison, LivedataStats, MetricLivedataWindow, Screensaver, StringLivedataWindow, TableColumn,
+    TimeAxis, UIState, ACTIVITY_TRAIL_LENGTH,
+};
 
 use crate::tui_app::theme::*;
-use crate::tui_app::tui::SharedTui;
+use crate::tui_app::tui::Tui;
 use crate::tui_app::utils;
 
 use crate::tui_app::theme::Emojified;
 use UIElement::*;
 
 #[derive(Message)]
-#[rtype(result = "()")]
+#[rtype(result = "FrameSnapshot")]
 pub struct Render {
-    pub tui: SharedTui,
     pub sensors: Sensors,
+    /// State version the sensors snapshot was taken at
+    pub version: u64,
+    pub reload_pending: bool,
+}
+
+/// Immutable data a frame is drawn from, handed over to the renderer
+pub struct FrameSnapshot {
+    sensors: Sensors,
+    ui_state: UIState,
+    dialog: Option<Box<dyn Renderable + Send>>,
+    reload_pending: bool,
+    /// Set only if the title changed since the previous frame
+    window_title: Option<String>,
+}
+
+impl FrameSnapshot {
+    /// A superseded frame is never drawn, so its title change is carried over
+    pub fn supersede(&mut self, older: FrameSnapshot) {
+        if self.window_title.is_none() {
+            self.window_title = older.window_title;
+        }
+    }
+
+    /// Returns the number of metric cards fitting a page, if a sensor is shown
+    pub fn draw(self, tui: &mut Tui) -> Option<usize> {
+        let FrameSnapshot {
+            sensors,
+            ui_state,
+            dialog,
+            reload_pending,
+            window_title,
+        } = self;
+        if let Some(window_title) = window_title
+            && let Err(err) = tui.set_title(&window_title)
+        {
+            log::debug!("Failed to set the terminal title: {err}");
+        }
+        let mut metric_page_size = None;
+        let _ = tui.terminal.draw(|frame| {
+            if let Some(screensaver) = &ui_state.screensaver {
+                render_screensaver(frame, &sensors, &ui_state, screensaver);
+                return;
+            }
+            let model = FrameModel::build(&sensors, &ui_state, reload_pending);
+            metric_page_size = render_state(frame, &model, &sensors, &ui_state);
+            if let Some(dialog) = dialog {
+                dialog.render(frame);
+            }
+        });
+        metric_page_size
+    }
 }
 
 impl Handler<Render> for UIState {
-    type Result = ();
+    type Result = ResponseFuture<FrameSnapshot>;
 
-    fn handle(&mut self, Render { tui, sensors }: Render, ctx: &mut Self::Context) -> Self::Result {
+    fn handle(
+        &mut self,
+        Render {
+            sensors,
+            version,
+            reload_pending,
+        }: Render,
+        _: &mut Self::Context,
+    ) -> Self::Result {
+        if self.render_cache.version != Some(version) {
+            self.render_cache = Arc::new(RenderCache::new(version, &sensors));
+        }
+        let window_title = self.window_title();
+        let window_title =
+            (window_title != self.terminal_title).then(|| {
+                self.terminal_title = window_title.clone();
+                window_title
+            });
         let ui_state = self.clone();
 
-        ctx.spawn(
-            async move {
-                let dialog_to_render: Option<Box<dyn Renderable>> = match &ui_state.modal_dialog {
-                    Some(ModalDialog::Confirmation(dialog)) => {
-                        if let Ok(dialog_state) = dialog
-                            .send(StateSnapshot::<ConfirmationDialogState>::default())
-                            .await
-                        {
-                            Some(Box::new(dialog_state))
-                        } else {
-                            None
-                        }
+        // Dialogs are asked for their state outside of the actor, which goes on handling input meanwhile
+        Box::pin(async move {
+            let dialog_to_render: Option<Box<dyn Renderable + Send>> = match &ui_state.modal_dialog {
+                Some(ModalDialog::Confirmation(dialog)) => {
+                    if let Ok(dialog_state) = dialog
+                        .send(StateSnapshot::<ConfirmationDialogState>::default())
+                        .await
+                    {
+                        Some(Box::new(dialog_state))
+                    } else {
+                        None
                     }
-                    Some(ModalDialog::Input(dialog)) => {
-                        if let Ok(dialog_state) = dialog
-                            .send(StateSnapshot::<InputDialogState>::default())
-                            .await
-                        {
-                            Some(Box::new(dialog_state))
-                        } else {
-                            None
-                        }
+                }
+                Some(ModalDialog::Input(dialog)) => {
+                    if let Ok(dialog_state) = dialog
+                        .send(StateSnapshot::<InputDialogState>::default())
+                        .await
+                    {
+                        Some(Box::new(dialog_state))
+                    } else {
+                        None
                     }
-                    Some(ModalDialog::Metric(dialog)) => {
-                        if let Ok(dialog_state) = dialog
-                            .send(StateSnapshot::<MetricDialogState>::default())
-                            .await
-                        {
-                            Some(Box::new(dialog_state))
-                        } else {
-                            None
-                        }
+                }
+                Some(ModalDialog::Metric(dialog)) => {
+                    if let Ok(dialog_state) = dialog
+                        .send(StateSnapshot::<MetricDialogState>::default())
+                        .await
+                    {
+                        Some(Box::new(dialog_state))
+                    } else {
+                        None
                     }
-                    None => None,
-                };
-
-                let _ = tui.lock().await.terminal.draw(move |frame| {
-                    render_state(frame, &sensors, &ui_state);
-                    if let Some(dialog) = dialog_to_render {
-                        dialog.render(frame);
+                }
+                Some(ModalDialog::LogFilter(dialog)) => {
+                    if let Ok(dialog_state) = dialog
+                        .send(StateSnapshot::<LogFilterDialogState>::default())
+                        .await
+                    {
+                        Some(Box::new(dialog_state))
+                    } else {
+                        None
+                    }
+                }
+                Some(ModalDialog::JsonViewer(dialog)) => {
+                    if let Ok(dialog_state) = dialog
+                        .send(StateSnapshot::<JsonViewerDialogState>::default())
+                        .await
+                    {
+                        Some(Box::new(dialog_state))
+                    } else {
+                        None
+                    }
+                }
+                Some(ModalDialog::Path(dialog)) => {
+                    if let Ok(dialog_state) = dialog
+                        .send(StateSnapshot::<PathDialogState>::default())
+                        .await
+                    {
+                        Some(Box::new(dialog_state))
+                    } else {
+                        None
+                    }
+                }
+                Some(ModalDialog::Help(dialog)) => {
+                    if let Ok(dialog_state) = dialog
+                        .send(StateSnapshot::<HelpDialogState>::default())
+                        .await
+                    {
+                        Some(Box::new(dialog_state))
+                    } else {
+                        None
                     }
-                });
+                }
+                Some(ModalDialog::Message(dialog)) => {
+                    if let Ok(dialog_state) = dialog
+                        .send(StateSnapshot::<MessageDialogState>::default())
+                        .await
+                    {
+                        Some(Box::new(dialog_state))
+                    } else {
+                        None
+                    }
+                }
+                Some(ModalDialog::Alert(dialog)) => {
+                    if let Ok(dialog_state) = dialog
+                        .send(StateSnapshot::<AlertDialogState>::default())
+                        .await
+                    {
+                        Some(Box::new(dialog_state))
+                    } else {
+                        None
+                    }
+                }
+                Some(ModalDialog::Errors(dialog)) => {
+                    if let Ok(dialog_state) = dialog
+                        .send(StateSnapshot::<ErrorsDialogState>::default())
+                        .await
+                    {
+                        Some(Box::new(dialog_state))
+                    } else {
+                        None
+                    }
+                }
+                Some(ModalDialog::AlertHistory(dialog)) => {
+                    if let Ok(dialog_state) = dialog
+                        .send(StateSnapshot::<AlertHistoryDialogState>::default())
+                        .await
+                    {
+                        Some(Box::new(dialog_state))
+                    } else {
+                        None
+                    }
+                }
+                None => None,
+            };
+
+            FrameSnapshot {
+                sensors,
+                ui_state,
+                dialog: dialog_to_render,
+                reload_pending,
+                window_title,
             }
-            .into_actor(self),
-        );
+        })
     }
 }
 
-fn render_state(frame: &mut Frame, sensors: &Sensors, ui_state: &UIState) {
-    let app_area = frame.area();
-
-    // TODO Fetch name and version from Cargo.toml
-    let app_title = Line::from(format!("{} v{}", "SensorVision", "0.1.0").bold());
-    let instructions = Line::from(vec![
-        " <Sensor Action> ".themed(InstructionsText),
-        "<Key>".themed(InstructionsActionText).bold(),
-        " <Metric Action> ".themed(InstructionsText),
-        "<⇧ + Key> ".themed(InstructionsActionText).bold(),
-        "|".themed(InstructionsText),
-        " Next ".themed(InstructionsText),
-        "↹ ".themed(InstructionsActionText).bold(),
-        " New ".themed(InstructionsText),
-        "n".themed(InstructionsActionText).bold(),
-        " Edit ".themed(InstructionsText),
-        "e".themed(InstructionsActionText).bold(),
-        " Delete ".themed(InstructionsText),
-        "d".themed(InstructionsActionText).bold(),
-        " Push Value ".themed(InstructionsText),
-        "␣ ".themed(InstructionsActionText).bold(),
-        "|".themed(InstructionsText),
-        " Quit ".themed(InstructionsText),
-        "q ".themed(InstructionsActionText).bold(),
-    ]);
-    let app_pad = Block::bordered()
+const SCREENSAVER_TOP_METRICS: usize = 5;
+
+/// Small summary box drifting across an otherwise blank screen
+fn render_screensaver(
+    frame: &mut Frame,
+    sensors: &Sensors,
+    ui_state: &UIState,
+    screensaver: &Screensaver,
+) {
+    let mut lines = Vec::new();
+    if screensaver.tick.is_multiple_of(2) {
+        lines.push(Line::from(Msg::MostActiveMetrics.text()).bold());
+        let mut counts: Vec<_> = ui_state.livedata_counts.iter().collect();
+        counts.sort_by(|a, b| b.1.cmp(a.1));
+        for ((sensor_id, metric_id), count) in counts.into_iter().take(SCREENSAVER_TOP_METRICS) {
+            let Some(sensor) = sensors.get(sensor_id) else {
+                continue;
+            };
+            let Some(metric) = sensor.metrics.get(metric_id) else {
+                continue;
+            };
+            lines.push(Line::from(format!("{}/{}: {count}", sensor.name, metric.name())));
+        }
+        if lines.len() == 1 {
+            lines.push(Line::from(Msg::NoLivedataYet.text()));
+        }
+    } else {
+        let metrics_count: usize = sensors.values().map(|sensor| sensor.metrics.len()).sum();
+        let livedata_count: u64 = ui_state.livedata_counts.values().sum();
+        lines.push(Line::from(Msg::Overview.text()).bold());
+        lines.push(Line::from(Msg::Uptime.format(&[(
+            "uptime",
+            &utils::format_duration(screensaver.uptime),
+        )])));
+        lines.push(Line::from(Msg::SensorsCount.format(&[
+            ("sensors", &sensors.len()),
+            ("metrics", &metrics_count),
+        ])));
+        lines.push(Line::from(Msg::LivedataCount.format(&[("count", &livedata_count)])));
+        lines.push(Line::from(Msg::ErrorsCount.format(&[("count", &logging::errors_count())])));
+    }
+
+    let width = lines.iter().map(Line::width).max().unwrap_or_default() as u16 + 4;
+    let height = lines.len() as u16 + 2;
+    let frame_area = frame.area();
+    let (Some(free_width), Some(free_height)) = (
+        frame_area.width.checked_sub(width),
+        frame_area.height.checked_sub(height),
+    ) else {
+        return;
+    };
+    // Move the box around to avoid burning it in
+    let area = Rect::new(
+        (screensaver.tick as u16).wrapping_mul(7) % (free_width + 1),
+        (screensaver.tick as u16).wrapping_mul(3) % (free_height + 1),
+        width,
+        height,
+    );
+
+    let summary = Paragraph::new(Text::from(lines))
+        .centered()
+        .block(
+            Block::bordered()
+                .border_type(BorderType::Rounded)
+                .title_bottom(Line::from(format!(" {} ", Msg::PressAnyKey.text())).centered()),
+        )
+        .themed(ScreensaverSummary);
+    frame.render_widget(summary, area);
+}
+
+/// Returns the number of metric cards fitting a page, if a sensor is shown
+fn render_state(
+    frame: &mut Frame,
+    model: &FrameModel,
+    sensors: &Sensors,
+    ui_state: &UIState,
+) -> Option<usize> {
+    let mut app_area = frame.area();
+    if let Some(banner) = &model.banner {
+        let [banner_area, rest] =
+            Layout::vertical([Constraint::Length(1), Constraint::Fill(1)]).areas(app_area);
+        frame.render_widget(
+            Paragraph::new(banner.as_str()).centered().themed(HealthBanner),
+            banner_area,
+        );
+        app_area = rest;
+    }
+
+    let header = &model.header;
+    let mut app_title = Line::from(header.title.as_str().bold());
+    if let Some(profile) = &header.profile {
+        app_title.push_span(" ");
+        app_title.push_span(format!(" {profile} ").themed(ProfileBadge));
+    }
+    if header.following {
+        app_title.push_span(" [following]".themed(ActivityTrail));
+    }
+    if header.frozen {
+        app_title.push_span(" [frozen]".themed(InstructionsText));
+    }
+    if header.reloading {
+        app_title.push_span(format!(" [{}]", Msg::ReloadingBadge.text()).themed(InstructionsText));
+    }
+    if header.maintenance > 0 {
+        app_title.push_span(
+            format!(" [{}]", Msg::MaintenanceBadge.format(&[("count", &header.maintenance)])).themed(InstructionsText),
+        );
+    }
+    if let Some(until) = header.silenced_until {
+        let until = until.to_datetime().with_timezone(&chrono::Local).format("%H:%M");
+        app_title.push_span(
+            format!(" [{}]", Msg::SilencedUntilBadge.format(&[("until", &until)])).themed(InstructionsText),
+        );
+    }
+    if header.errors > 0 {
+        app_title.push_span(
+            format!(" [{}]", Msg::ErrorsBadge.format(&[("count", &header.errors)])).themed(ErrorLog),
+        );
+    }
+    if let Some(badge) = header.ping {
+        app_title.push_span(ping_badge(badge));
+    }
+    let instructions = keymap::footer_instructions();
+    let mut app_pad = Block::bordered()
         .title(app_title.centered())
         .title_bottom(instructions.centered())
         .style(Style::default().themed(AppPad))
         .border_set(border::THICK);
 
-    if sensors.is_empty() {
-        let no_sensors = Paragraph::new(Line::from("Current connector has no sensors"))
-            .themed(NoSensors)
-            .centered()
-            .block(app_pad);
-        frame.render_widget(no_sensors, app_area);
-        return;
+    if let Some(filter) = &model.filter {
+        let cursor = if filter.editing { "_" } else { "" };
+        app_pad = app_pad.title(
+            Line::from(vec![
+                " /".themed(InstructionsActionText).bold(),
+                format!("{}{cursor} ", filter.query).themed(FilterQuery),
+            ])
+            .left_aligned(),
+        );
     }
 
+    let (tabs, selected_sensor, sensor_view) = match &model.body {
+        Body::NoSensors => {
+            let no_sensors = Paragraph::new(Line::from(Msg::NoSensors.text()))
+                .themed(NoSensors)
+                .centered()
+                .block(app_pad);
+            frame.render_widget(no_sensors, app_area);
+            return None;
+        }
+        Body::NoMatch { query } => {
+            let no_match = Paragraph::new(Line::from(Msg::NoMatch.format(&[("query", query)])))
+                .themed(NoSensors)
+                .centered()
+                .block(app_pad);
+            frame.render_widget(no_match, app_area);
+            return None;
+        }
+        Body::Overview { rows } => {
+            let overview_area = app_pad.inner(app_area);
+            frame.render_widget(app_pad, app_area);
+            render_overview(frame, overview_area, rows, ui_state.region_sizes.overview_column_width());
+            return None;
+        }
+        Body::Sensors {
+            tabs,
+            selected,
+            sensor,
+        } => (tabs, *selected, sensor),
+    };
+
+    let render_cache = &ui_state.render_cache;
+    let shown_tab_widths: Vec<u16> = tabs
+        .iter()
+        .map(|tab| {
+            // Mutes aren't part of the inventory, so they aren't cached
+            let muted_badge_width = if tab.muted { Line::from(MUTED_TAB_BADGE).width() as u16 } else { 0 };
+            render_cache.tab_widths[tab.index] + muted_badge_width
+        })
+        .collect();
+
+    // Only the tabs around the selected one which fit the screen are built
+    let visible_tabs = visible_tabs(
+        &shown_tab_widths,
+        selected_sensor.unwrap_or_default(),
+        app_area.width.saturating_sub(2),
+    );
+    let app_pad = if visible_tabs.len() < tabs.len() {
+        app_pad.title(
+            Line::from(format!(
+                " {}-{} of {} ",
+                visible_tabs.start + 1,
+                visible_tabs.end,
+                tabs.len()
+            ))
+            .right_aligned(),
+        )
+    } else {
+        app_pad
+    };
+
     let sensor_tabs = Tabs::new(
-        sensors
+        tabs[visible_tabs.clone()]
             .iter()
-            .map(|(_, sensor)| sensor.name.clone())
+            .map(|tab| {
+                let mut line = Line::from(tab.title.as_str());
+                if tab.disabled {
+                    line.push_span(DISABLED_TAB_BADGE.themed(SensorDisabled));
+                }
+                if tab.muted {
+                    line.push_span(MUTED_TAB_BADGE.themed(SensorMuted));
+                }
+                if let Some(marker) = activity_marker(tab.activity) {
+                    line.push_span(marker);
+                }
+                line
+            })
             .collect::<Vec<_>>(),
     )
     .block(app_pad)
     .highlight_style(Style::default().themed(SelectedSensorTab))
     .divider(symbols::DOT)
-    .select(ui_state.current_sensor.map(|(i, _)| i));
+    .select(selected_sensor.map(|selected| selected - visible_tabs.start));
 
     frame.render_widget(sensor_tabs, app_area);
 
-    if let Some((current_sensor, _)) = ui_state.current_sensor {
-        let (_, current_sensor) = sensors.iter().nth(current_sensor).unwrap();
-        render_sensor(frame, current_sensor, ui_state);
+    let sensor_view = sensor_view.as_ref()?;
+    let current_sensor = sensors.get(&sensor_view.sensor_id)?;
+    Some(render_sensor(frame, app_area, sensor_view, current_sensor, ui_state))
+}
+
+const OVERVIEW_ROW_HEIGHT: u16 = 4;
+
+/// A sensor per row: its name, then the names and the last values of its metrics
+/// over their sparklines, `column_width` wide each. The rows scroll to keep the selected sensor in sight.
+fn render_overview(frame: &mut Frame, area: Rect, rows: &[OverviewRow], column_width: u16) {
+    let area = area.inner(Margin::new(1, 0));
+    let rows_fitting = usize::from(area.height / OVERVIEW_ROW_HEIGHT).max(1);
+    let selected = rows.iter().position(|row| row.selected).unwrap_or_default();
+    let first = (selected + 1).saturating_sub(rows_fitting);
+    let columns = usize::from(area.width / column_width).max(1);
+
+    let row_areas = Layout::vertical(vec![Constraint::Length(OVERVIEW_ROW_HEIGHT); rows_fitting])
+        .split(area);
+    for (row, row_area) in rows[first..].iter().zip(row_areas.iter()) {
+        let [title_area, names_area, sparklines_area] = Layout::vertical([
+            Constraint::Length(1),
+            Constraint::Length(1),
+            Constraint::Length(OVERVIEW_ROW_HEIGHT - 2),
+        ])
+        .areas(*row_area);
+
+        let mut title = Line::from(Span::styled(
+            row.name.clone(),
+            Style::default().themed(SensorName).bold(),
+        ));
+        if row.disabled {
+            title.push_span(DISABLED_TAB_BADGE.themed(SensorDisabled));
+        }
+        if row.muted {
+            title.push_span(MUTED_TAB_BADGE.themed(SensorMuted));
+        }
+        if row.sparklines.len() > columns {
+            title.push_span(
+                format!(" {}", Msg::HiddenMetrics.format(&[("count", &(row.sparklines.len() - columns))]))
+                    .themed(HiddenMetrics),
+            );
+        }
+        let title = if row.selected {
+            title.themed(SelectedSensorTab)
+        } else {
+            title
+        };
+        frame.render_widget(Paragraph::new(title), title_area);
+
+        if row.sparklines.is_empty() {
+            frame.render_widget(Paragraph::new(Msg::NoMetrics.text()).themed(NoMetrics), names_area);
+            continue;
+        }
+
+        let column_constraints = vec![Constraint::Length(column_width); columns];
+        let name_areas = Layout::horizontal(column_constraints.clone()).split(names_area);
+        let sparkline_areas = Layout::horizontal(column_constraints).split(sparklines_area);
+        for ((metric, name_area), sparkline_area) in row
+            .sparklines
+            .iter()
+            .zip(name_areas.iter())
+            .zip(sparkline_areas.iter())
+        {
+            // A column of spacing between the sparklines
+            let width = column_width - 1;
+            let name_area = Rect { width, ..*name_area };
+            let sparkline_area = Rect { width, ..*sparkline_area };
+            let style = if metric.alert {
+                Style::default().themed(MetricPropsBlockAlert)
+            } else {
+                Style::default().themed(LivedataLine)
+            };
+
+            let mut label = Line::from(Span::styled(
+                metric.name.clone(),
+                Style::default().themed(MetricName),
+            ));
+            match &metric.last_value {
+                Some(value) => label.push_span(Span::styled(format!(" {value}"), style)),
+                None => label.push_span(format!(" {}", Msg::NoData.text()).themed(MetricNoData)),
+            }
+            frame.render_widget(Paragraph::new(label), name_area);
+
+            let points = &metric.points[metric.points.len().saturating_sub(usize::from(width))..];
+            let sparkline = Sparkline::default()
+                .data(points)
+                .max(SPARKLINE_MAX)
+                .style(style);
+            frame.render_widget(sparkline, sparkline_area);
+        }
+    }
+}
+
+/// Latency of the last ping, or the number of pings failed since
+fn ping_badge(badge: PingBadge) -> Span<'static> {
+    match badge {
+        PingBadge::Failing(failures) => {
+            format!(" [{}]", Msg::PingFailingBadge.format(&[("count", &failures)])).themed(PingFailing)
+        }
+        PingBadge::Latency(latency) => {
+            format!(" [{}]", Msg::PingLatencyBadge.format(&[("millis", &latency.as_millis())]))
+                .themed(PingHealthy)
+        }
+    }
+}
+
+// Tabs are padded with a space on each side and separated by a single character
+const TAB_PADDING: u16 = 2;
+const TAB_DIVIDER_WIDTH: u16 = 1;
+
+/// Range of tabs fitting into `width` with the `selected` one among them
+fn visible_tabs(tab_widths: &[u16], selected: usize, width: u16) -> Range<usize> {
+    let mut visible = selected..selected + 1;
+    let mut used_width = tab_widths[selected] + TAB_PADDING;
+    let tab_cost = |i: usize| tab_widths[i] + TAB_PADDING + TAB_DIVIDER_WIDTH;
+
+    loop {
+        let mut grown = false;
+        if visible.end < tab_widths.len() && used_width + tab_cost(visible.end) <= width {
+            used_width += tab_cost(visible.end);
+            visible.end += 1;
+            grown = true;
+        }
+        if visible.start > 0 && used_width + tab_cost(visible.start - 1) <= width {
+            used_width += tab_cost(visible.start - 1);
+            visible.start -= 1;
+            grown = true;
+        }
+        if !grown {
+            return visible;
+        }
+    }
+}
+
+const DISABLED_TAB_BADGE: &str = " ⏸";
+const MUTED_TAB_BADGE: &str = " 🔇";
+
+fn state_badge(state: SensorState) -> Span<'static> {
+    match state {
+        SensorState::Enabled => Span::styled("● enabled", Style::default().themed(SensorEnabled)),
+        SensorState::Disabled => Span::styled("⏸ disabled", Style::default().themed(SensorDisabled)),
+    }
+}
+
+/// Per-inventory data the frames are built from, rebuilt only when the sensors state changes
+#[derive(Debug, Default)]
+pub struct RenderCache {
+    version: Option<u64>,
+    // Including the room for an activity marker and the disabled badge
+    tab_widths: Vec<u16>,
+}
+
+impl RenderCache {
+    fn new(version: u64, sensors: &Sensors) -> Self {
+        Self {
+            version: Some(version),
+            tab_widths: sensors
+                .values()
+                .map(|sensor| {
+                    let badge_width = if sensor.state == SensorState::Disabled {
+                        Line::from(DISABLED_TAB_BADGE).width() as u16
+                    } else {
+                        0
+                    };
+                    Line::from(sensor.name.as_str()).width() as u16 + badge_width + 2
+                })
+                .collect(),
+        }
     }
 }
 
-fn render_sensor(frame: &mut Frame, sensor: &Sensor<Metric>, ui_state: &UIState) {
-    let metrics_count = sensor.metrics.len();
+/// Returns the number of metric cards fitting a page
+fn render_sensor(
+    frame: &mut Frame,
+    app_area: Rect,
+    view: &SensorView,
+    sensor: &Sensor<Metric>,
+    ui_state: &UIState,
+) -> usize {
+    let metrics_count = view.cards.len();
 
     // Cut boundaries and Tabs
     let area = {
@@ -158,7 +665,7 @@ fn render_sensor(frame: &mut Frame, sensor: &Sensor<Metric>, ui_state: &UIState)
                 Constraint::Fill(1),
                 Constraint::Length(1),
             ])
-            .split(frame.area());
+            .split(app_area);
         Layout::default()
             .direction(Direction::Horizontal)
             .constraints([
@@ -169,20 +676,25 @@ fn render_sensor(frame: &mut Frame, sensor: &Sensor<Metric>, ui_state: &UIState)
             .split(vbox[1])[1]
     };
 
+    let log_entries = if ui_state.log_pane.hidden {
+        Vec::new()
+    } else {
+        logging::recent_entries(
+            ui_state.region_sizes.log_pane_height() as usize,
+            ui_state.log_pane.scroll,
+        )
+    };
     let vbox = Layout::default()
         .direction(Direction::Vertical)
-        .constraints([
-            Constraint::Fill(1),
-            Constraint::Length(ui_state.errors.len() as u16),
-        ])
+        .constraints(
+            ui_state
+                .region_sizes
+                .sensor_view_constraints(log_entries.len()),
+        )
         .split(area);
 
-    if !ui_state.errors.is_empty() {
-        let error_log = ui_state.errors.iter().fold(String::default(), |a, b| a + "\n" + b).trim().to_string();
-        let errors_log = Paragraph::new(Text::from(error_log))
-            .themed(ErrorLog)
-            .wrap(Wrap { trim: true });
-        frame.render_widget(errors_log, vbox[1]);
+    if !log_entries.is_empty() {
+        frame.render_widget(log_pane(&log_entries, ui_state.log_pane.scroll), vbox[1]);
     }
 
     let sensor_area = Layout::default()
@@ -200,49 +712,152 @@ fn render_sensor(frame: &mut Frame, sensor: &Sensor<Metric>, ui_state: &UIState)
         .split(sensor_area);
 
     if metrics_count == 0 {
-        let no_metrics = Paragraph::new(Line::from("Current sensor has no metrics"))
+        let no_metrics = Paragraph::new(Line::from(Msg::NoSensorMetrics.text()))
             .themed(NoMetrics)
             .centered();
         frame.render_widget(no_metrics, vbox_layout[0]);
-        return;
+        return metric_page_size(vbox_layout[1], METRIC_CARD_WIDTH, METRIC_CARD_HEIGHT);
     }
 
-    let title = Paragraph::new(
-        Line::from(vec![
-            Span::styled(
-                format!(
-                    "{} {}",
-                    emojis::get_by_shortcode("signal_strength").unwrap(),
-                    sensor.name
-                ),
-                Style::default().themed(SensorName).bold(),
+    if let Some(table) = &view.table {
+        let mut title = sensor_title(view);
+        title.push_span(Span::styled(
+            format!(" | {}", Msg::SortedBy.format(&[("column", &table.sort.title().to_lowercase())])),
+            Style::default().themed(InstructionsText),
+        ));
+        frame.render_widget(Paragraph::new(title.centered()), vbox_layout[0]);
+        return render_metric_table(frame, vbox_layout[1], table);
+    }
+
+    // Only the page of cards holding the selected metric is laid out
+    let grid_area = vbox_layout[1];
+    let page_size = metric_page_size(grid_area, METRIC_CARD_WIDTH, METRIC_CARD_HEIGHT);
+    let pages_count = metrics_count.div_ceil(page_size);
+    let page = ui_state
+        .current_metric
+        .and_then(|(i, _)| view.cards.iter().position(|card| card.index == i))
+        .map_or(0, |position| position / page_size)
+        .min(pages_count - 1);
+    let visible_metrics = page * page_size..((page + 1) * page_size).min(metrics_count);
+
+    let mut title = sensor_title(view);
+    if pages_count > 1 {
+        let hidden_before = visible_metrics.start;
+        let hidden_after = metrics_count - visible_metrics.end;
+        title.push_span(Span::styled(
+            format!(" | {} ", Msg::PageOfPages.format(&[("page", &(page + 1)), ("pages", &pages_count)])),
+            Style::default().themed(InstructionsText),
+        ));
+        title.push_span(Span::styled(
+            format!("▲ {hidden_before} ▼ {hidden_after}"),
+            Style::default().themed(HiddenMetrics),
+        ));
+    }
+    frame.render_widget(Paragraph::new(title.centered()), vbox_layout[0]);
+
+    if let Ok(metric_areas) = metric_dyn_layout(
+        visible_metrics.len(),
+        grid_area,
+        METRIC_CARD_WIDTH,
+        METRIC_CARD_HEIGHT,
+    ) {
+        for (card, metric_area) in view.cards[visible_metrics].iter().zip(metric_areas) {
+            if let Some(metric) = sensor.metrics.get(&card.metric_id) {
+                render_metric(frame, metric_area, ui_state, card, metric, sensor.sensor_id);
+            }
+        }
+    }
+    page_size
+}
+
+/// Name, id and state of the sensor above its metrics
+fn sensor_title(view: &SensorView) -> Line<'static> {
+    Line::from(vec![
+        Span::styled(
+            format!(
+                "{} {}",
+                emojis::get_by_shortcode("signal_strength").unwrap(),
+                view.name
             ),
-            Span::styled(" | ", Style::default().themed(InstructionsText)),
-            Span::styled(
-                format!(
-                    "{}️ {}",
-                    emojis::get_by_shortcode("id").unwrap(),
-                    sensor.sensor_id
-                ),
-                Style::default().themed(SensorId),
+            Style::default().themed(SensorName).bold(),
+        ),
+        Span::styled(" | ", Style::default().themed(InstructionsText)),
+        Span::styled(
+            format!(
+                "{}️ {}",
+                emojis::get_by_shortcode("id").unwrap(),
+                view.sensor_id
             ),
-        ])
-        .centered(),
-    );
-    frame.render_widget(title, vbox_layout[0]);
+            Style::default().themed(SensorId),
+        ),
+        Span::styled(" | ", Style::default().themed(InstructionsText)),
+        state_badge(view.state),
+    ])
+}
+
+/// Returns the number of rows fitting the area, which makes a page of metrics
+fn render_metric_table(frame: &mut Frame, area: Rect, table: &MetricTable) -> usize {
+    let header = Row::new(TableColumn::iter().map(|column| {
+        let marker = if column == table.sort { " ▾" } else { "" };
+        Cell::from(format!("{}{marker}", column.title()))
+    }))
+    .themed(MetricTableHeader);
 
-    if let Ok(metric_areas) = metric_dyn_layout(metrics_count, vbox_layout[1], 50, 20) {
-        for i in 0..metrics_count {
-            let metric = &sensor.metrics[i];
-            render_metric(frame, metric_areas[i], ui_state, metric, sensor.sensor_id);
+    let rows = table.rows.iter().map(|row| {
+        let updated_at = row.updated_at.map_or_else(
+            || String::from("–"),
+            |timestamp| timestamp.to_datetime().format("%H:%M:%S").to_string(),
+        );
+        let last_value = match &row.last_value {
+            Some(value) => Cell::from(value.clone()),
+            None => Cell::from(Msg::NoData.text()).themed(MetricNoData),
+        };
+        let table_row = Row::new([
+            Cell::from(row.name.clone()),
+            Cell::from(row.kind.clone()),
+            Cell::from(row.annotation.clone()),
+            last_value,
+            Cell::from(updated_at),
+        ]);
+        if row.alert {
+            table_row.themed(MetricPropsBlockAlert)
+        } else {
+            table_row
         }
-    }
+    });
+
+    let widths = [
+        Constraint::Fill(2),
+        Constraint::Length(12),
+        Constraint::Fill(1),
+        Constraint::Fill(2),
+        Constraint::Length(8),
+    ];
+    let metric_table = Table::new(rows, widths)
+        .header(header)
+        .column_spacing(2)
+        .row_highlight_style(Style::default().themed(MetricTableSelected))
+        .block(
+            Block::bordered()
+                .border_type(BorderType::Rounded)
+                .themed(MetricPropsBlock),
+        );
+    let mut state =
+        TableState::default().with_selected(table.rows.iter().position(|row| row.selected));
+    frame.render_stateful_widget(metric_table, area, &mut state);
+
+    // Borders and the header
+    usize::from(area.height.saturating_sub(3)).max(1)
 }
 
+const METRIC_CARD_WIDTH: u16 = 50;
+const METRIC_CARD_HEIGHT: u16 = 20;
+
 fn render_metric(
     frame: &mut Frame,
     area: Rect,
     ui_state: &UIState,
+    card: &MetricCard,
     metric: &Metric,
     sensor_id: SensorId,
 ) {
@@ -305,27 +920,75 @@ fn render_metric(
 
     let livedata_key = (sensor_id, *metric.metric_id());
 
+    let mut metric_title = Line::from(Span::styled(name, Style::default().themed(MetricName)));
+    if let Some(marker) = activity_marker(card.activity) {
+        metric_title.push_span(marker);
+    }
+
     let mut metric_props_block = Block::default()
         .borders(Borders::ALL)
         .themed(MetricPropsBlock)
-        .title(Line::from(Span::styled(name, Style::default().themed(MetricName))).centered())
+        .title(metric_title.centered())
         .border_type(BorderType::Rounded);
-    if ui_state
-        .current_metric
-        .is_some_and(|(_, metric_id)| *metric.metric_id() == metric_id)
-    {
+    if card.selected {
         metric_props_block =
             metric_props_block.border_style(Style::default().themed(MetricPropsBlockSelected));
     }
+    if card.alert {
+        metric_props_block =
+            metric_props_block.border_style(Style::default().themed(MetricPropsBlockAlert));
+    }
     frame.render_widget(metric_props_block, area);
 
-    if let Some(livedata) = ui_state.livedata.get(&livedata_key) {
+    let no_data_msg = if ui_state.muted.contains(&sensor_id) { Msg::Muted } else { Msg::NoData };
+    if let Metric::Custom {
+        value_type: ValueType::String,
+        ..
+    } = metric
+    {
+        if let Some(livedata) = ui_state.string_livedata.get(&livedata_key) {
+            frame.render_widget(
+                string_livedata_list(livedata, vbox_layout[1].height),
+                vbox_layout[1],
+            );
+        } else {
+            let no_data = Line::from(no_data_msg.text()).themed(MetricNoData).bold().centered();
+            frame.render_widget(no_data, vbox_layout[1]);
+        }
+    } else if let Some(livedata) = ui_state
+        .livedata
+        .get(&livedata_key)
+        .and_then(|livedata| livedata.view(ui_state.time_axis, ui_state.frozen_at))
+    {
+        let format = ValueFormat::of(metric);
+        let chart_area = if ui_state.show_stats {
+            let [chart_area, stats_area] =
+                Layout::vertical([Constraint::Fill(1), Constraint::Length(2)]).areas(vbox_layout[1]);
+            frame.render_widget(stats_strip(&livedata.stats, &format), stats_area);
+            chart_area
+        } else {
+            vbox_layout[1]
+        };
+        let gap_marks = livedata.gap_marks(&ui_state.sleep_gaps);
+        // Braille packs two dots per cell, the plot is what the borders and the labels leave
+        let label_width = value_labels(&livedata, &format)
+            .iter()
+            .map(|label| label.chars().count())
+            .max()
+            .unwrap_or_default() as u16;
+        let columns = usize::from(chart_area.width.saturating_sub(label_width + 3)) * 2;
+        let plotted = resample_envelope(&livedata.data, columns);
+        let ghost = ui_state
+            .ghosts
+            .get(&livedata_key)
+            .map(|ghost| (ui_state.comparison, resample_envelope(ghost, columns)));
+        let ghost = ghost.as_ref().map(|(comparison, ghost)| (*comparison, ghost.as_ref()));
         match metric {
             Metric::Predefined { value_unit, .. } => {
                 let annotation = format!("{:?}", value_unit);
                 frame.render_widget(
-                    numeric_livedata_chart(&livedata, &annotation),
-                    vbox_layout[1],
+                    numeric_livedata_chart(&livedata, &plotted, &annotation, &format, &gap_marks, ghost, ui_state),
+                    chart_area,
                 );
             }
             Metric::Custom {
@@ -337,37 +1000,184 @@ fn render_metric(
                     ValueType::Double | ValueType::Integer | ValueType::Boolean => {
                         let annotation = format!("{:?}", value_annotation);
                         frame.render_widget(
-                            numeric_livedata_chart(&livedata, &annotation),
-                            vbox_layout[1],
+                            numeric_livedata_chart(&livedata, &plotted, &annotation, &format, &gap_marks, ghost, ui_state),
+                            chart_area,
                         );
                     }
-                    // TODO Render string chart
-                    _ => {}
+                    // Handled above
+                    ValueType::String => {}
                 };
             }
         };
     } else {
-        let no_data = Line::from("NO DATA").themed(MetricNoData).bold().centered();
+        let no_data = Line::from(no_data_msg.text()).themed(MetricNoData).bold().centered();
         frame.render_widget(no_data, vbox_layout[1]);
     }
 }
 
+/// Two lines of numbers under a chart
+fn stats_strip(stats: &LivedataStats, format: &ValueFormat) -> Paragraph<'static> {
+    // Averages of whole numbers aren't whole
+    let derived = format.fractional();
+    let rate = stats
+        .rate
+        .map(|rate| {
+            let sign = if rate < 0.0 { "" } else { "+" };
+            format!("{sign}{}/s", derived.format(rate))
+        })
+        .unwrap_or_else(|| String::from("–"));
+    let mut first_line = format!(
+        "n {}  avg {}  σ {}",
+        stats.count,
+        derived.format(stats.mean),
+        derived.format(stats.stddev)
+    );
+    if let (Some(latency), Some(max_latency)) = (stats.latency, stats.max_latency) {
+        first_line += &format!(
+            "  ⏱ {} (max {})",
+            format_latency(latency),
+            format_latency(max_latency)
+        );
+    }
+    Paragraph::new(vec![
+        Line::from(first_line),
+        Line::from(format!(
+            "min {}  max {}  Δ {rate}",
+            format.format(stats.min),
+            format.format(stats.max)
+        )),
+    ])
+    .centered()
+    .themed(InstructionsText)
+}
+
+/// `85 ms`, `1.2 s` past a second
+fn format_latency(latency: Duration) -> String {
+    if latency < Duration::from_secs(1) {
+        format!("{} ms", latency.as_millis())
+    } else {
+        format!("{:.1} s", latency.as_secs_f64())
+    }
+}
+
+/// Log records colored by their levels, the latest at the bottom
+fn log_pane(entries: &[LogEntry], scroll: usize) -> Paragraph<'static> {
+    let lines: Vec<Line> = entries
+        .iter()
+        .map(|entry| {
+            let element = match entry.level {
+                log::Level::Error => ErrorLog,
+                log::Level::Warn => WarnLog,
+                log::Level::Info => InfoLog,
+                log::Level::Debug | log::Level::Trace => DebugLog,
+            };
+            Line::from(vec![
+                format!("{} ", entry.time.format("%H:%M:%S")).themed(InstructionsText),
+                format!("{:<5} {}", entry.level, entry.message).themed(element),
+            ])
+        })
+        .collect();
+
+    let mut title = format!(" {} ", Msg::Log.text());
+    if scroll > 0 {
+        title += &format!("{} ", Msg::NewerLogRecords.format(&[("count", &scroll)]));
+    }
+    Paragraph::new(lines).block(
+        Block::default()
+            .borders(Borders::TOP)
+            .title(title.themed(InstructionsText)),
+    )
+}
+
+/// Fading dot showing how recently livedata arrived
+fn activity_marker(activity: Option<usize>) -> Option<Span<'static>> {
+    const MARKERS: [&str; ACTIVITY_TRAIL_LENGTH] = [" ◉", " ●", " •", " ·"];
+    activity.map(|rank| MARKERS[rank].themed(ActivityTrail))
+}
+
+/// The latest values fitting `height`, the newest at the bottom
+fn string_livedata_list(livedata_window: &StringLivedataWindow, height: u16) -> List<'_> {
+    let visible = (height as usize).saturating_sub(2);
+    let skip = livedata_window.values.len().saturating_sub(visible);
+    let list_items: Vec<ListItem> = livedata_window
+        .values
+        .iter()
+        .skip(skip)
+        .map(|(timestamp, value)| {
+            ListItem::new(Line::from(vec![
+                Span::styled(
+                    format!("{} ", timestamp.to_datetime().format("%H:%M:%S")),
+                    Style::default().themed(InstructionsText),
+                ),
+                Span::styled(value.as_str(), Style::default().themed(LivedataText)),
+            ]))
+        })
+        .collect();
+
+    let list_block = Block::default()
+        .borders(Borders::ALL)
+        .title(
+            Line::from(Span::styled(
+                Msg::Livedata.text(),
+                Style::default().themed(InstructionsText),
+            ))
+            .centered(),
+        )
+        .border_type(BorderType::Thick);
+
+    List::new(list_items).block(list_block).themed(LivedataChart)
+}
+
+/// Labels of the bounds of the value axis
+fn value_labels(livedata: &MetricLivedataWindow, format: &ValueFormat) -> [String; 2] {
+    match livedata.integer_bounds {
+        Some((min, max)) => [min, max].map(|bound| format.format_value(&MetricValue::Integer(bound))),
+        None => [livedata.min_value, livedata.max_value].map(|bound| format.format(bound)),
+    }
+}
+
+/// `plotted` is the data of the window resampled to the width of the chart
 fn numeric_livedata_chart<'a>(
     livedata_window: &'a MetricLivedataWindow,
+    plotted: &'a [(f64, f64)],
     annotation: &'a str,
+    format: &ValueFormat,
+    gap_marks: &'a [[(f64, f64); 2]],
+    ghost: Option<(Comparison, &'a [(f64, f64)])>,
+    ui_state: &UIState,
 ) -> Chart<'a> {
-    let datasets = vec![
+    // Marks go first, so that the livedata is drawn over them
+    let mut datasets: Vec<Dataset> = gap_marks
+        .iter()
+        .map(|mark| {
+            Dataset::default()
+                .marker(symbols::Marker::Braille)
+                .graph_type(GraphType::Line)
+                .themed(LivedataGap)
+                .data(mark)
+        })
+        .collect();
+    if let Some((_, ghost)) = ghost {
+        datasets.push(
+            Dataset::default()
+                .marker(symbols::Marker::Braille)
+                .graph_type(GraphType::Line)
+                .themed(LivedataGhost)
+                .data(ghost),
+        );
+    }
+    datasets.extend([
         Dataset::default()
             .marker(symbols::Marker::Braille)
             .graph_type(GraphType::Line)
             .themed(LivedataLine)
-            .data(&livedata_window.data),
+            .data(plotted),
         Dataset::default()
             .marker(symbols::Marker::Dot)
             .graph_type(GraphType::Scatter)
             .themed(LivedataScatter)
-            .data(&livedata_window.data),
-    ];
+            .data(plotted),
+    ]);
 
     let x_axis = Axis::default()
         .themed(InstructionsText)
@@ -377,25 +1187,49 @@ fn numeric_livedata_chart<'a>(
             livedata_window.max_timestamp_str.clone(),
         ]);
 
+    // The ghost may stick out of the live values, the bounds grow to fit it then
+    let (mut min_value, mut max_value) = (livedata_window.min_value, livedata_window.max_value);
+    let mut labels = value_labels(livedata_window, format);
+    for (_, value) in ghost.iter().flat_map(|(_, ghost)| ghost.iter()) {
+        if *value < min_value {
+            min_value = *value;
+            labels[0] = format.format(min_value);
+        }
+        if *value > max_value {
+            max_value = *value;
+            labels[1] = format.format(max_value);
+        }
+    }
+
     let y_axis = Axis::default()
         .title(annotation.themed(InstructionsText))
         .themed(InstructionsText)
-        .bounds([livedata_window.min_value, livedata_window.max_value])
-        .labels([
-            livedata_window.min_value_str.clone(),
-            livedata_window.max_value_str.clone(),
-        ]);
+        .bounds([min_value, max_value])
+        .labels(labels);
 
-    let chart_block = Block::default()
+    let mut title = match ghost {
+        Some((comparison, _)) => Msg::LivedataVs.format(&[("comparison", &comparison.name())]),
+        None => Msg::Livedata.text().to_owned(),
+    };
+    if ui_state.time_axis != TimeAxis::Auto {
+        title += &format!(", {}", ui_state.time_axis.name());
+    }
+    if ui_state.frozen_at.is_some() {
+        title += " ⏸";
+    }
+    let mut chart_block = Block::default()
         .borders(Borders::ALL)
         .title(
-            Line::from(Span::styled(
-                "Livedata",
-                Style::default().themed(InstructionsText),
-            ))
-            .centered(),
+            Line::from(Span::styled(title, Style::default().themed(InstructionsText))).centered(),
         )
         .border_type(BorderType::Thick);
+    // End-to-end latency of the latest value, to tell a lagging pipeline from a quiet device
+    if let Some(latency) = livedata_window.stats.latency {
+        chart_block = chart_block.title_bottom(
+            Line::from(format!(" ⏱ {} ", format_latency(latency)).themed(LatencyWatermark))
+                .right_aligned(),
+        );
+    }
 
     Chart::new(datasets)
         .block(chart_block)
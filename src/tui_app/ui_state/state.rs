@@ -1,12 +1,27 @@
 use actix::{Actor, Context};
 
-use chrono::{DateTime, Utc};
+use std::borrow::Cow;
+use std::collections::{BTreeMap, HashMap, HashSet, VecDeque};
+use std::sync::Arc;
+use std::time::Duration;
 
-use std::collections::{BTreeMap, HashMap, VecDeque};
-use std::time::{Duration, UNIX_EPOCH};
+use chrono::NaiveDate;
 
-use crate::model::{MetricId, SensorId};
+use serde::Serialize;
+
+use strum::{EnumIter, EnumProperty};
+
+use crate::client::state::{MqttScheme, Sensors, Silencing};
+use crate::correlation::CorrelationId;
+use crate::i18n::Msg;
+use crate::logging;
+use crate::model::protocol::MetricValue;
+use crate::model::sensor::{Metric, Sensor};
+use crate::model::{ConnectorId, MetricId, SensorId, Timestamp};
 use crate::tui_app::dialog::ModalDialog;
+use crate::tui_app::ui_state::RegionSizes;
+use crate::tui_app::ui_state::render::RenderCache;
+use crate::utils::fuzzy_match;
 
 #[derive(Debug, Clone, Default)]
 pub struct UIState {
@@ -15,9 +30,261 @@ pub struct UIState {
 
     pub modal_dialog: Option<ModalDialog>,
 
-    pub errors: VecDeque<String>,
+    pub log_pane: LogPane,
+
+    pub region_sizes: RegionSizes,
 
     pub livedata: HashMap<(SensorId, MetricId), MetricLivedataWindow>,
+
+    /// Livedata of string metrics, which can't be charted
+    pub string_livedata: HashMap<(SensorId, MetricId), StringLivedataWindow>,
+
+    /// Selection jumps to the metric which received livedata most recently
+    pub focus_follow: bool,
+
+    /// Metrics which received livedata most recently, the latest first
+    pub activity_trail: VecDeque<(SensorId, MetricId)>,
+
+    /// Metrics whose livedata crossed an alert threshold
+    pub alerts: HashSet<(SensorId, MetricId)>,
+
+    /// Maintenance windows in progress and the silence of all the alerts, if any
+    pub silencing: Silencing,
+
+    /// Livedata values received per metric since the start
+    pub livedata_counts: HashMap<(SensorId, MetricId), u64>,
+
+    /// Sensors whose livedata is only counted, neither kept nor drawn
+    pub muted: HashSet<SensorId>,
+
+    /// Errors the agent replied with, the latest last, until they are dismissed
+    pub errors: VecDeque<ErrorEntry>,
+    pub(super) next_error_id: u64,
+
+    pub connector_id: Option<ConnectorId>,
+
+    /// Config profile the connector was picked by
+    pub profile: Option<String>,
+
+    /// The broker connection is lost and being retried
+    pub offline: bool,
+
+    pub ping: PingHealth,
+
+    /// Last title set to the terminal window
+    pub(super) terminal_title: String,
+
+    /// Number of metric cards which fit the last rendered frame
+    pub metric_page_size: usize,
+
+    /// Narrows down the sensor tabs and metric cards
+    pub filter: FilterState,
+
+    /// Periods the system was asleep, marked on the charts
+    pub sleep_gaps: VecDeque<(Timestamp, Timestamp)>,
+
+    /// Shown instead of everything else once the user has been idle for a while
+    pub screensaver: Option<Screensaver>,
+
+    /// Livedata of the numeric metrics bucketed by day. Too big to go into snapshots,
+    /// which get the ghosts taken from it instead.
+    pub(super) history: HashMap<(SensorId, MetricId), MetricHistory>,
+
+    /// Period the charts overlay the livedata with
+    pub comparison: Comparison,
+
+    /// Livedata of one comparison period ago, shifted onto the time axes of the windows
+    pub ghosts: HashMap<(SensorId, MetricId), Ghost>,
+
+    pub time_axis: TimeAxis,
+
+    /// Statistics of the livedata are shown under the charts
+    pub show_stats: bool,
+
+    pub metric_layout: MetricLayout,
+
+    /// Column the metric table is sorted by
+    pub table_sort: TableColumn,
+
+    /// Every sensor the filter shows gets a row of sparklines instead of the selected
+    /// sensor's metrics
+    pub overview: bool,
+
+    /// The charts stop moving at this moment for inspection, while the livedata keeps coming
+    pub frozen_at: Option<Timestamp>,
+
+    pub(super) render_cache: Arc<RenderCache>,
+}
+
+#[derive(Debug, Clone)]
+pub struct ErrorEntry {
+    pub id: u64,
+    pub scheme: MqttScheme,
+    pub code: i32,
+    pub message: String,
+    pub timestamp: Timestamp,
+    pub correlation_id: Option<CorrelationId>,
+    /// Payload of the failed request if it's worth retrying
+    pub request: Option<String>,
+}
+
+impl ErrorEntry {
+    pub fn is_retriable(&self) -> bool {
+        self.request.is_some()
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct FilterState {
+    pub query: String,
+    /// Keys go to the query rather than to the actions
+    pub editing: bool,
+}
+
+impl FilterState {
+    pub fn is_active(&self) -> bool {
+        !self.query.is_empty()
+    }
+
+    /// Sensors are shown if either their names or names of any of their metrics match
+    pub fn shows_sensor(&self, sensor: &Sensor<Metric>) -> bool {
+        !self.is_active()
+            || fuzzy_match(&self.query, &sensor.name)
+            || sensor
+                .metrics
+                .iter()
+                .any(|metric| fuzzy_match(&self.query, metric.name()))
+    }
+
+    /// All metrics of a matching sensor are shown
+    pub fn shows_metric(&self, sensor: &Sensor<Metric>, metric: &Metric) -> bool {
+        !self.is_active()
+            || fuzzy_match(&self.query, &sensor.name)
+            || fuzzy_match(&self.query, metric.name())
+    }
+}
+
+/// Bottom pane showing the latest log records
+#[derive(Debug, Clone, Default)]
+pub struct LogPane {
+    pub hidden: bool,
+    /// Number of the latest records scrolled past, 0 sticks to the bottom
+    pub scroll: usize,
+}
+
+/// Outcome of the periodic pings of the agent
+#[derive(Debug, Clone, Copy, Default)]
+pub struct PingHealth {
+    /// Round trip of the last answered ping
+    pub latency: Option<Duration>,
+    /// Pings in a row the agent didn't answer
+    pub failures: u32,
+}
+
+/// Unanswered pings in a row which raise the warning banner
+pub const PING_FAILURES_WARNING: u32 = 3;
+
+impl PingHealth {
+    pub fn is_failing(&self) -> bool {
+        self.failures >= PING_FAILURES_WARNING
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Screensaver {
+    /// Increments every time the summary page rotates
+    pub tick: usize,
+    pub uptime: Duration,
+}
+
+pub const ACTIVITY_TRAIL_LENGTH: usize = 4;
+
+pub const SLEEP_GAPS_LIMIT: usize = 16;
+
+pub const ERRORS_LIMIT: usize = 50;
+
+impl UIState {
+    /// Connector along with whatever needs attention, visible even when the terminal
+    /// is in the background
+    pub(super) fn window_title(&self) -> String {
+        let mut title = String::from("SensorVision");
+        if let Some(connector_id) = &self.connector_id {
+            title += &format!(" {connector_id}");
+        }
+        if let Some(profile) = &self.profile {
+            title += &format!(" ({profile})");
+        }
+        if self.offline {
+            title += &format!(" [{}]", Msg::OfflineBadge.text());
+        } else if self.ping.is_failing() {
+            title += &format!(" [{}]", Msg::NotRespondingBadge.text());
+        }
+        if !self.alerts.is_empty() {
+            title += &format!(" [{}]", Msg::AlertsBadge.format(&[("count", &self.alerts.len())]));
+        }
+        if self.silencing.is_active() {
+            title += &format!(" [{}]", Msg::SilencedBadge.text());
+        }
+        let errors_count = logging::errors_count();
+        if errors_count > 0 {
+            title += &format!(" [{}]", Msg::ErrorsBadge.format(&[("count", &errors_count)]));
+        }
+        title
+    }
+
+    pub(super) fn record_activity(&mut self, sensor_id: SensorId, metric_id: MetricId) {
+        *self.livedata_counts.entry((sensor_id, metric_id)).or_default() += 1;
+
+        self.activity_trail.retain(|key| *key != (sensor_id, metric_id));
+        self.activity_trail.push_front((sensor_id, metric_id));
+        self.activity_trail.truncate(ACTIVITY_TRAIL_LENGTH);
+    }
+
+    /// Latest value of the metric as it's shown, along with its timestamp
+    pub fn latest_value(&self, sensor_id: SensorId, metric_id: MetricId) -> Option<(Timestamp, String)> {
+        let key = (sensor_id, metric_id);
+        if let Some((timestamp, value)) = self
+            .string_livedata
+            .get(&key)
+            .and_then(|window| window.values.back())
+        {
+            return Some((*timestamp, value.clone()));
+        }
+        self.livedata
+            .get(&key)
+            .and_then(MetricLivedataWindow::last_entry)
+            .map(|(timestamp, value)| (timestamp, value.to_string()))
+    }
+
+    /// How recently the metric (or any metric of the sensor if `metric_id` is `None`)
+    /// received livedata, 0 being the latest
+    pub fn activity_rank(&self, sensor_id: SensorId, metric_id: Option<MetricId>) -> Option<usize> {
+        self.activity_trail.iter().position(|(sens_id, metr_id)| {
+            *sens_id == sensor_id && metric_id.is_none_or(|metric_id| *metr_id == metric_id)
+        })
+    }
+
+    /// Re-derives selection indices from the selected ids, as the indices
+    /// might've been computed against an older sensors snapshot.
+    pub fn anchor_to(&mut self, sensors: &Sensors) {
+        self.current_sensor = self.current_sensor.and_then(|(_, sensor_id)| {
+            sensors
+                .keys()
+                .position(|id| *id == sensor_id)
+                .map(|index| (index, sensor_id))
+        });
+
+        let metrics = self
+            .current_sensor
+            .and_then(|(_, sensor_id)| sensors.get(&sensor_id))
+            .map(|sensor| &sensor.metrics);
+        self.current_metric = match (self.current_metric, metrics) {
+            (Some((_, metric_id)), Some(metrics)) => metrics
+                .position(&metric_id)
+                .map(|index| (index, metric_id)),
+            _ => None,
+        };
+    }
 }
 
 const LIVEDATA_WINDOW_LIMIT: usize = 50;
@@ -27,33 +294,88 @@ pub struct MetricLivedataWindow {
     pub data: Vec<(f64, f64)>,
     pub min_value: f64,
     pub max_value: f64,
-    pub min_value_str: String,
-    pub max_value_str: String,
+    /// Exact bounds of a window holding integers only, which the f64 ones may round
+    pub integer_bounds: Option<(i64, i64)>,
 
     pub min_timestamp: f64,
     pub max_timestamp: f64,
     pub min_timestamp_str: String,
     pub max_timestamp_str: String,
 
-    data_sorted: BTreeMap<u64, f64>,
+    pub stats: LivedataStats,
+
+    // Values are kept as they came, so integers above 2^53 aren't rounded
+    data_sorted: BTreeMap<Timestamp, MetricValue>,
+    /// How long after their timestamps the values were received, by the same keys
+    latencies: BTreeMap<Timestamp, Duration>,
 }
 
 impl MetricLivedataWindow {
-    pub(super) fn push_data(&mut self, timestamp: u64, value: f64) {
+    /// Vertical lines at the edges of the gaps falling into the window
+    pub fn gap_marks(&self, gaps: &VecDeque<(Timestamp, Timestamp)>) -> Vec<[(f64, f64); 2]> {
+        gaps.iter()
+            .flat_map(|(from, to)| [from, to])
+            .map(|edge| edge.as_millis() as f64)
+            .filter(|edge| (self.min_timestamp..=self.max_timestamp).contains(edge))
+            .map(|edge| [(edge, self.min_value), (edge, self.max_value)])
+            .collect()
+    }
+
+    pub(super) fn push_data(&mut self, timestamp: Timestamp, value: MetricValue, latency: Duration) {
         if self.data_sorted.len() == LIVEDATA_WINDOW_LIMIT {
-            self.data_sorted
-                .remove(&self.data_sorted.keys().next().unwrap().clone());
+            let oldest = *self.data_sorted.keys().next().unwrap();
+            self.data_sorted.remove(&oldest);
+            self.latencies.remove(&oldest);
         }
         self.data_sorted.insert(timestamp, value);
+        self.latencies.insert(timestamp, latency);
+        let min_timestamp = *self.data_sorted.first_key_value().unwrap().0;
+        let max_timestamp = *self.data_sorted.last_key_value().unwrap().0;
+        self.update(min_timestamp, max_timestamp);
+    }
+
+    /// The window as the time axis shows it, `None` if no value falls into the axis.
+    /// Frozen axes end at the freezing time, sliding ones span the last seconds before the end.
+    pub fn view(&self, axis: TimeAxis, frozen_at: Option<Timestamp>) -> Option<Cow<'_, Self>> {
+        let span = axis.span();
+        if span.is_none() && frozen_at.is_none() {
+            return (!self.data_sorted.is_empty()).then_some(Cow::Borrowed(self));
+        }
+        let end = frozen_at.unwrap_or_else(Timestamp::now);
+        let start = span.map_or(Timestamp::default(), |span| {
+            Timestamp::from_millis(end.as_millis().saturating_sub(span.as_millis() as u64))
+        });
+        let mut view = Self {
+            data_sorted: self
+                .data_sorted
+                .range(start..=end)
+                .map(|(timestamp, value)| (*timestamp, value.clone()))
+                .collect(),
+            latencies: self
+                .latencies
+                .range(start..=end)
+                .map(|(timestamp, latency)| (*timestamp, *latency))
+                .collect(),
+            ..Self::default()
+        };
+        let (first, last) = (
+            *view.data_sorted.first_key_value()?.0,
+            *view.data_sorted.last_key_value()?.0,
+        );
+        // Sliding axes keep their span however few values there are
+        match span {
+            Some(_) => view.update(start, end),
+            None => view.update(first, last),
+        }
+        Some(Cow::Owned(view))
+    }
 
-        let min_timestamp = self.data_sorted.first_key_value().unwrap().0;
-        let max_timestamp = self.data_sorted.last_key_value().unwrap().0;
-        self.min_timestamp = *min_timestamp as f64;
-        self.max_timestamp = *max_timestamp as f64;
-        let min_datetime =
-            DateTime::<Utc>::from(UNIX_EPOCH + Duration::from_millis(*min_timestamp));
-        let max_datetime =
-            DateTime::<Utc>::from(UNIX_EPOCH + Duration::from_millis(*max_timestamp));
+    /// Derives the chart data from the values, `data_sorted` mustn't be empty
+    fn update(&mut self, min_timestamp: Timestamp, max_timestamp: Timestamp) {
+        self.min_timestamp = min_timestamp.as_millis() as f64;
+        self.max_timestamp = max_timestamp.as_millis() as f64;
+        let min_datetime = min_timestamp.to_datetime();
+        let max_datetime = max_timestamp.to_datetime();
         let ts_format = if min_datetime.date_naive() == max_datetime.date_naive() {
             "%H:%M:%S"
         } else {
@@ -64,7 +386,7 @@ impl MetricLivedataWindow {
         self.data = self
             .data_sorted
             .iter()
-            .map(|(ts, val)| (*ts as f64, *val))
+            .filter_map(|(ts, val)| Some((ts.as_millis() as f64, val.as_f64()?)))
             .collect();
         self.min_value = self
             .data
@@ -72,6 +394,9 @@ impl MetricLivedataWindow {
             .map(|(_, val)| *val)
             .reduce(f64::min)
             .unwrap();
+        self.stats = LivedataStats::of(&self.data);
+        self.stats.latency = self.latencies.values().last().copied();
+        self.stats.max_latency = self.latencies.values().max().copied();
         if self.min_value > 0.0 {
             // Nullify the min value to make it look more natural on the chart.
             self.min_value = 0.0;
@@ -82,8 +407,276 @@ impl MetricLivedataWindow {
             .map(|(_, val)| *val)
             .reduce(f64::max)
             .unwrap();
-        self.min_value_str = format!("{:.2}", self.min_value);
-        self.max_value_str = format!("{:.2}", self.max_value);
+
+        let integers = self
+            .data_sorted
+            .values()
+            .filter_map(|val| match val {
+                MetricValue::Integer(val) => Some(*val),
+                MetricValue::Boolean(val) => Some(*val as i64),
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+        self.integer_bounds = (integers.len() == self.data_sorted.len()).then(|| {
+            let min_value = integers.iter().min().copied().unwrap().min(0);
+            let max_value = integers.iter().max().copied().unwrap();
+            (min_value, max_value)
+        });
+    }
+
+    pub fn last_value(&self) -> Option<&MetricValue> {
+        self.data_sorted.last_key_value().map(|(_, val)| val)
+    }
+
+    pub fn last_entry(&self) -> Option<(Timestamp, &MetricValue)> {
+        self.data_sorted.last_key_value().map(|(ts, val)| (*ts, val))
+    }
+}
+
+/// Thins `data` out to at most two points per column of `columns` spanning its time range,
+/// the lowest and the highest of the column in their order, so that spikes stay visible.
+/// Left as it is if it fits already.
+pub fn resample_envelope(data: &[(f64, f64)], columns: usize) -> Cow<'_, [(f64, f64)]> {
+    let (Some(first), Some(last)) = (data.first(), data.last()) else {
+        return Cow::Borrowed(data);
+    };
+    if columns == 0 || data.len() <= columns * 2 || last.0 <= first.0 {
+        return Cow::Borrowed(data);
+    }
+    let column_span = (last.0 - first.0) / columns as f64;
+    let column_of = |timestamp: f64| (((timestamp - first.0) / column_span) as usize).min(columns - 1);
+
+    let mut resampled = Vec::with_capacity(columns * 2);
+    let mut points = data.iter().copied().peekable();
+    while let Some(point) = points.next() {
+        let column = column_of(point.0);
+        let (mut low, mut high, mut latest) = (point, point, point);
+        while let Some(next) = points.next_if(|next| column_of(next.0) == column) {
+            if next.1 < low.1 {
+                low = next;
+            }
+            if next.1 > high.1 {
+                high = next;
+            }
+            latest = next;
+        }
+        // Flat columns span their time all the same
+        if low == high {
+            high = latest;
+        }
+        if low == high {
+            resampled.push(low);
+        } else if low.0 <= high.0 {
+            resampled.extend([low, high]);
+        } else {
+            resampled.extend([high, low]);
+        }
+    }
+    Cow::Owned(resampled)
+}
+
+/// Numbers of the values a window holds
+#[derive(Debug, Clone, Default)]
+pub struct LivedataStats {
+    pub count: usize,
+    pub mean: f64,
+    /// Population standard deviation
+    pub stddev: f64,
+    pub min: f64,
+    pub max: f64,
+    /// Change per second from the oldest value to the latest one, if they are apart in time
+    pub rate: Option<f64>,
+    /// How long after its timestamp the latest value was received, which is the lag of
+    /// the agent, the broker and the client rather than the one of the device
+    pub latency: Option<Duration>,
+    pub max_latency: Option<Duration>,
+}
+
+impl LivedataStats {
+    fn of(data: &[(f64, f64)]) -> Self {
+        let (Some(first), Some(last)) = (data.first(), data.last()) else {
+            return Self::default();
+        };
+        let count = data.len();
+        let mean = data.iter().map(|(_, value)| value).sum::<f64>() / count as f64;
+        let variance = data
+            .iter()
+            .map(|(_, value)| (value - mean).powi(2))
+            .sum::<f64>()
+            / count as f64;
+        // Timestamps are in milliseconds
+        let elapsed_secs = (last.0 - first.0) / 1000.0;
+        Self {
+            count,
+            mean,
+            stddev: variance.sqrt(),
+            min: data.iter().map(|(_, value)| *value).fold(f64::INFINITY, f64::min),
+            max: data.iter().map(|(_, value)| *value).fold(f64::NEG_INFINITY, f64::max),
+            rate: (elapsed_secs > 0.0).then(|| (last.1 - first.1) / elapsed_secs),
+            ..Self::default()
+        }
+    }
+}
+
+/// What the time axes of the charts span
+#[derive(Debug, Default, Clone, Copy, PartialEq, EnumIter, EnumProperty)]
+pub enum TimeAxis {
+    /// From the oldest value kept to the latest one
+    #[default]
+    #[strum(props(name = "auto"))]
+    Auto,
+    #[strum(props(name = "last minute", secs = "60"))]
+    LastMinute,
+    #[strum(props(name = "last 10 minutes", secs = "600"))]
+    LastTenMinutes,
+}
+
+impl TimeAxis {
+    pub fn name(&self) -> &'static str {
+        self.get_str("name").unwrap_or_default()
+    }
+
+    /// Sliding axes span this much time before now
+    pub fn span(&self) -> Option<Duration> {
+        self.get_str("secs")
+            .and_then(|secs| secs.parse().ok())
+            .map(Duration::from_secs)
+    }
+}
+
+/// How the metrics of the selected sensor are shown
+#[derive(Debug, Default, Clone, Copy, PartialEq, Serialize)]
+pub enum MetricLayout {
+    /// A card with a chart per metric
+    #[default]
+    Cards,
+    /// A row per metric, for sensors with too many metrics to page through
+    Table,
+}
+
+/// Columns of the metric table, in the order they're shown
+#[derive(Debug, Default, Clone, Copy, PartialEq, EnumIter, EnumProperty, Serialize)]
+pub enum TableColumn {
+    #[default]
+    #[strum(props(title = "Name"))]
+    Name,
+    #[strum(props(title = "Type/Unit"))]
+    Type,
+    #[strum(props(title = "Annotation"))]
+    Annotation,
+    /// Numerically if both values are numbers
+    #[strum(props(title = "Last Value"))]
+    LastValue,
+    /// The latest first
+    #[strum(props(title = "Updated"))]
+    LastUpdate,
+}
+
+impl TableColumn {
+    pub fn title(&self) -> &'static str {
+        self.get_str("title").unwrap_or_default()
+    }
+}
+
+/// Earlier livedata shown under the live line as a ghost, shifted by the period
+#[derive(Debug, Default, Clone, Copy, PartialEq, EnumIter, EnumProperty)]
+pub enum Comparison {
+    #[default]
+    #[strum(props(name = "off"))]
+    Off,
+    #[strum(props(name = "last hour", secs = "3600"))]
+    LastHour,
+    #[strum(props(name = "yesterday", secs = "86400"))]
+    Yesterday,
+}
+
+impl Comparison {
+    pub fn name(&self) -> &'static str {
+        self.get_str("name").unwrap_or_default()
+    }
+
+    pub fn period(&self) -> Option<Duration> {
+        self.get_str("secs")
+            .and_then(|secs| secs.parse().ok())
+            .map(Duration::from_secs)
+    }
+}
+
+/// Days of history kept per metric, today included
+const HISTORY_DAYS: usize = 2;
+
+/// Once a day bucket fills up, every other value of it is dropped, so the whole day stays
+/// covered at a lower resolution
+const HISTORY_DAY_LIMIT: usize = 20_000;
+
+/// Chart points of a ghost, shared by the snapshots taken until the history changes
+pub type Ghost = Arc<[(f64, f64)]>;
+
+#[derive(Debug, Clone, Default)]
+pub struct MetricHistory {
+    days: BTreeMap<NaiveDate, Vec<(Timestamp, f64)>>,
+    /// Last ghost taken, with the time range it covers. Dropped whenever a value comes in,
+    /// so snapshots taken in between share it.
+    ghost: Option<((f64, f64), Ghost)>,
+}
+
+impl MetricHistory {
+    pub(super) fn push_data(&mut self, timestamp: Timestamp, value: f64) {
+        self.ghost = None;
+        let day = self
+            .days
+            .entry(timestamp.to_datetime().date_naive())
+            .or_default();
+        if day.len() == HISTORY_DAY_LIMIT {
+            let mut index = 0;
+            day.retain(|_| {
+                index += 1;
+                index % 2 == 0
+            });
+        }
+        // Livedata mostly comes in order, late values are rare
+        let position = day.partition_point(|(ts, _)| *ts <= timestamp);
+        day.insert(position, (timestamp, value));
+
+        while self.days.len() > HISTORY_DAYS {
+            self.days.pop_first();
+        }
+    }
+
+    /// Values from one period before the window, moved onto the window's time axis
+    pub fn ghost(&mut self, window: &MetricLivedataWindow, period: Duration) -> Ghost {
+        let period = period.as_millis() as f64;
+        let (from, to) = (window.min_timestamp - period, window.max_timestamp - period);
+        if let Some((range, ghost)) = &self.ghost
+            && *range == (from, to)
+        {
+            return ghost.clone();
+        }
+        let ghost: Ghost = self
+            .days
+            .values()
+            .flatten()
+            .map(|(ts, value)| (ts.as_millis() as f64, *value))
+            .filter(|(ts, _)| (from..=to).contains(ts))
+            .map(|(ts, value)| (ts + period, value))
+            .collect();
+        self.ghost = Some(((from, to), ghost.clone()));
+        ghost
+    }
+}
+
+/// Latest values of a string metric in the order they arrived
+#[derive(Debug, Clone, Default)]
+pub struct StringLivedataWindow {
+    pub values: VecDeque<(Timestamp, String)>,
+}
+
+impl StringLivedataWindow {
+    pub(super) fn push_data(&mut self, timestamp: Timestamp, value: String) {
+        if self.values.len() == LIVEDATA_WINDOW_LIMIT {
+            self.values.pop_front();
+        }
+        self.values.push_back((timestamp, value));
     }
 }
 
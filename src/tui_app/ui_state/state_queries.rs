@@ -1,11 +1,21 @@
 use actix::{AsyncContext, Handler, Message, MessageResult, WrapFuture};
 
-use crossterm::event::KeyEvent;
+use crossterm::event::{KeyCode, KeyEvent};
 
+use std::collections::HashSet;
+use std::time::Duration;
+
+use crate::client::state::{MqttScheme, Silencing};
+use crate::correlation::CorrelationId;
+use crate::logging;
 use crate::model::protocol::MetricValue;
-use crate::model::{MetricId, SensorId};
+use crate::model::{ConnectorId, MetricId, SensorId, Timestamp};
 use crate::tui_app::dialog::ModalDialog;
-use crate::tui_app::ui_state::UIState;
+use crate::tui_app::ui_state::{
+    Comparison, ErrorEntry, FilterState, MetricLayout, PingHealth, RegionSizes, Screensaver,
+    TableColumn, TimeAxis, UIState, ERRORS_LIMIT, SLEEP_GAPS_LIMIT,
+};
+use crate::utils::CircularEnum;
 
 #[derive(Message)]
 #[rtype(result = "UIState")]
@@ -19,13 +29,16 @@ pub struct SelectSensor(pub Option<(usize, SensorId)>);
 #[rtype(result = "()")]
 pub struct SelectMetric(pub Option<(usize, MetricId)>);
 
+/// Returns whether the selection should follow the livedata,
+/// `None` if the sensor is muted and there's nothing new to draw
 #[derive(Message)]
-#[rtype(result = "()")]
+#[rtype(result = "Option<bool>")]
 pub struct AcceptLivedata {
     pub sensor_id: SensorId,
     pub metric_id: MetricId,
     pub value: MetricValue,
-    pub timestamp: u64,
+    pub timestamp: Timestamp,
+    pub received_at: Timestamp,
 }
 
 #[derive(Message)]
@@ -44,18 +57,168 @@ pub struct DropSensor(pub SensorId);
 #[rtype(result = "()")]
 pub struct DropMetric(pub SensorId, pub MetricId);
 
+/// Grows (positive) or shrinks (negative) the sparkline columns while the overview is shown,
+/// the log pane by the given number of lines otherwise. Returns the sizes to keep.
+#[derive(Message)]
+#[rtype(result = "RegionSizes")]
+pub struct ResizeRegion(pub i16);
+
+/// Restores the sizes kept from an earlier run
 #[derive(Message)]
 #[rtype(result = "()")]
-pub struct AppendError{
-    pub message: String,
+pub struct SetRegionSizes(pub RegionSizes);
+
+#[derive(Message)]
+#[rtype(result = "()")]
+pub struct SetScreensaver(pub Option<Screensaver>);
+
+/// Returns whether focus-follow mode got enabled
+#[derive(Message)]
+#[rtype(result = "bool")]
+pub struct ToggleFocusFollow;
+
+/// Switches the charts to the next comparison period and returns it
+#[derive(Message)]
+#[rtype(result = "Comparison")]
+pub struct CycleComparison;
+
+/// Switches the time axes of the charts to the next mode and returns it
+#[derive(Message)]
+#[rtype(result = "TimeAxis")]
+pub struct CycleTimeAxis;
+
+/// Returns whether the statistics got shown
+#[derive(Message)]
+#[rtype(result = "bool")]
+pub struct ToggleStats;
+
+/// Switches between the metric cards and the metric table and returns the new layout
+#[derive(Message)]
+#[rtype(result = "MetricLayout")]
+pub struct ToggleMetricLayout;
+
+/// Sorts the metric table by the next column and returns it
+#[derive(Message)]
+#[rtype(result = "TableColumn")]
+pub struct CycleTableSort;
+
+/// Switches between the selected sensor and the overview of all sensors, returns whether
+/// the overview is shown
+#[derive(Message)]
+#[rtype(result = "bool")]
+pub struct ToggleOverview;
+
+/// Returns whether the charts got frozen
+#[derive(Message)]
+#[rtype(result = "bool")]
+pub struct ToggleFreeze;
+
+/// Mutes or unmutes the selected sensor, returns it and the sensors muted afterwards,
+/// `None` if no sensor is selected
+#[derive(Message)]
+#[rtype(result = "Option<(SensorId, HashSet<SensorId>)>")]
+pub struct ToggleMute;
+
+#[derive(Message)]
+#[rtype(result = "()")]
+pub struct SetMuted(pub HashSet<SensorId>);
+
+/// Marks the metric as being in alert or clears the mark
+#[derive(Message)]
+#[rtype(result = "()")]
+pub struct SetAlert {
+    pub sensor_id: SensorId,
+    pub metric_id: MetricId,
+    pub raised: bool,
+}
+
+#[derive(Message)]
+#[rtype(result = "()")]
+pub struct SetSilencing(pub Silencing);
+
+/// Files an error the agent replied with, the oldest one is dropped beyond the limit
+#[derive(Message)]
+#[rtype(result = "()")]
+pub struct AddError {
+    pub scheme: MqttScheme,
     pub code: i32,
+    pub message: String,
+    pub timestamp: Timestamp,
+    pub correlation_id: Option<CorrelationId>,
+    pub request: Option<String>,
 }
 
+/// Removes the errors by their ids
+#[derive(Message)]
+#[rtype(result = "()")]
+pub struct DismissErrors(pub Vec<u64>);
+
+#[derive(Message)]
+#[rtype(result = "()")]
+pub struct SetConnector(pub ConnectorId);
+
+#[derive(Message)]
+#[rtype(result = "()")]
+pub struct SetProfile(pub String);
+
+#[derive(Message)]
+#[rtype(result = "()")]
+pub struct SetOffline(pub bool);
+
+/// Round trip of a ping, `None` if the agent didn't answer it. Returns the health before it.
+#[derive(Message)]
+#[rtype(result = "PingHealth")]
+pub struct RecordPing(pub Option<Duration>);
+
+/// Starts typing the filter query
+#[derive(Message)]
+#[rtype(result = "()")]
+pub struct EditFilter;
+
+/// Returns whether the key went to the filter query being typed
+#[derive(Message)]
+#[rtype(result = "bool")]
+pub struct HandleFilterKey(pub KeyEvent);
+
+/// Wall clock period the system was asleep for
+#[derive(Message)]
+#[rtype(result = "()")]
+pub struct MarkSleepGap {
+    pub from: Timestamp,
+    pub to: Timestamp,
+}
+
+/// Returns whether the log pane got shown
+#[derive(Message)]
+#[rtype(result = "bool")]
+pub struct ToggleLogPane;
+
+/// Scrolls the log pane back (positive) or forward (negative) by the given number of records
+#[derive(Message)]
+#[rtype(result = "()")]
+pub struct ScrollLogPane(pub isize);
+
+/// Number of metric cards which fit the last drawn frame
+#[derive(Message)]
+#[rtype(result = "()")]
+pub struct SetMetricPageSize(pub usize);
+
 impl Handler<GetUIStateSnapshot> for UIState {
     type Result = MessageResult<GetUIStateSnapshot>;
 
     fn handle(&mut self, _: GetUIStateSnapshot, _: &mut Self::Context) -> Self::Result {
-        MessageResult(self.clone())
+        let mut history = std::mem::take(&mut self.history);
+        let mut snapshot = self.clone();
+        if let Some(period) = self.comparison.period() {
+            snapshot.ghosts = self
+                .livedata
+                .iter()
+                .filter_map(|(key, window)| Some((*key, history.get_mut(key)?.ghost(window, period))))
+                .filter(|(_, ghost)| !ghost.is_empty())
+                .collect();
+        }
+        self.history = history;
+        MessageResult(snapshot)
     }
 }
 
@@ -84,7 +247,7 @@ impl Handler<SelectMetric> for UIState {
 }
 
 impl Handler<AcceptLivedata> for UIState {
-    type Result = ();
+    type Result = Option<bool>;
 
     fn handle(
         &mut self,
@@ -93,21 +256,31 @@ impl Handler<AcceptLivedata> for UIState {
             metric_id,
             value,
             timestamp,
+            received_at,
         }: AcceptLivedata,
         _: &mut Self::Context,
     ) -> Self::Result {
         let key = (sensor_id, metric_id);
-        let value = match value {
-            MetricValue::Double(value) => value,
-            MetricValue::Integer(value) => value as f64,
-            MetricValue::Boolean(value) => value as u8 as f64,
-            MetricValue::String(_) => {
-                return;
-            }
-        };
+        if self.muted.contains(&sensor_id) {
+            *self.livedata_counts.entry(key).or_default() += 1;
+            return None;
+        }
+        self.record_activity(sensor_id, metric_id);
 
+        if let MetricValue::String(value) = value {
+            let string_livedata_window = self.string_livedata.entry(key).or_default();
+            string_livedata_window.push_data(timestamp, value);
+            return Some(self.focus_follow);
+        }
+
+        if let Some(value) = value.as_f64() {
+            self.history.entry(key).or_default().push_data(timestamp, value);
+        }
         let metric_livedata_window = self.livedata.entry(key).or_default();
-        metric_livedata_window.push_data(timestamp, value);
+        // Device clocks running ahead make for no latency rather than a negative one
+        let latency = Duration::from_millis(received_at.as_millis().saturating_sub(timestamp.as_millis()));
+        metric_livedata_window.push_data(timestamp, value, latency);
+        Some(self.focus_follow)
     }
 }
 
@@ -117,6 +290,16 @@ impl Handler<DropSensor> for UIState {
     fn handle(&mut self, DropSensor(sensor_id): DropSensor, _: &mut Self::Context) -> Self::Result {
         self.livedata
             .retain(|(sens_id, _), _| sens_id != &sensor_id);
+        self.string_livedata
+            .retain(|(sens_id, _), _| sens_id != &sensor_id);
+        self.history
+            .retain(|(sens_id, _), _| sens_id != &sensor_id);
+        self.alerts
+            .retain(|(sens_id, _)| sens_id != &sensor_id);
+        self.activity_trail
+            .retain(|(sens_id, _)| sens_id != &sensor_id);
+        self.livedata_counts
+            .retain(|(sens_id, _), _| sens_id != &sensor_id);
         if self
             .current_sensor
             .is_some_and(|(_, sens_id)| sens_id == sensor_id)
@@ -137,6 +320,12 @@ impl Handler<DropMetric> for UIState {
     ) -> Self::Result {
         self.livedata
             .retain(|(sens_id, metr_id), _| sensor_id.ne(sens_id) && metric_id.ne(metr_id));
+        self.string_livedata.remove(&(sensor_id, metric_id));
+        self.history.remove(&(sensor_id, metric_id));
+        self.alerts.remove(&(sensor_id, metric_id));
+        self.activity_trail
+            .retain(|key| *key != (sensor_id, metric_id));
+        self.livedata_counts.remove(&(sensor_id, metric_id));
         if self
             .current_sensor
             .is_some_and(|(_, sens_id)| sens_id == sensor_id)
@@ -149,6 +338,191 @@ impl Handler<DropMetric> for UIState {
     }
 }
 
+impl Handler<SetScreensaver> for UIState {
+    type Result = ();
+
+    fn handle(
+        &mut self,
+        SetScreensaver(screensaver): SetScreensaver,
+        _: &mut Self::Context,
+    ) -> Self::Result {
+        self.screensaver = screensaver;
+    }
+}
+
+impl Handler<ToggleFocusFollow> for UIState {
+    type Result = bool;
+
+    fn handle(&mut self, _: ToggleFocusFollow, _: &mut Self::Context) -> Self::Result {
+        self.focus_follow = !self.focus_follow;
+        self.focus_follow
+    }
+}
+
+impl Handler<CycleComparison> for UIState {
+    type Result = MessageResult<CycleComparison>;
+
+    fn handle(&mut self, _: CycleComparison, _: &mut Self::Context) -> Self::Result {
+        self.comparison = self.comparison.next();
+        MessageResult(self.comparison)
+    }
+}
+
+impl Handler<CycleTimeAxis> for UIState {
+    type Result = MessageResult<CycleTimeAxis>;
+
+    fn handle(&mut self, _: CycleTimeAxis, _: &mut Self::Context) -> Self::Result {
+        self.time_axis = self.time_axis.next();
+        MessageResult(self.time_axis)
+    }
+}
+
+impl Handler<ToggleStats> for UIState {
+    type Result = bool;
+
+    fn handle(&mut self, _: ToggleStats, _: &mut Self::Context) -> Self::Result {
+        self.show_stats = !self.show_stats;
+        self.show_stats
+    }
+}
+
+impl Handler<ToggleMetricLayout> for UIState {
+    type Result = MessageResult<ToggleMetricLayout>;
+
+    fn handle(&mut self, _: ToggleMetricLayout, _: &mut Self::Context) -> Self::Result {
+        self.metric_layout = match self.metric_layout {
+            MetricLayout::Cards => MetricLayout::Table,
+            MetricLayout::Table => MetricLayout::Cards,
+        };
+        MessageResult(self.metric_layout)
+    }
+}
+
+impl Handler<CycleTableSort> for UIState {
+    type Result = MessageResult<CycleTableSort>;
+
+    fn handle(&mut self, _: CycleTableSort, _: &mut Self::Context) -> Self::Result {
+        self.table_sort = self.table_sort.next();
+        MessageResult(self.table_sort)
+    }
+}
+
+impl Handler<ToggleOverview> for UIState {
+    type Result = bool;
+
+    fn handle(&mut self, _: ToggleOverview, _: &mut Self::Context) -> Self::Result {
+        self.overview = !self.overview;
+        self.overview
+    }
+}
+
+impl Handler<ToggleMute> for UIState {
+    type Result = MessageResult<ToggleMute>;
+
+    fn handle(&mut self, _: ToggleMute, _: &mut Self::Context) -> Self::Result {
+        let Some((_, sensor_id)) = self.current_sensor else {
+            return MessageResult(None);
+        };
+        if !self.muted.remove(&sensor_id) {
+            self.muted.insert(sensor_id);
+            // What was received before muting isn't drawn either
+            self.livedata.retain(|(sens_id, _), _| *sens_id != sensor_id);
+            self.string_livedata.retain(|(sens_id, _), _| *sens_id != sensor_id);
+            self.history.retain(|(sens_id, _), _| *sens_id != sensor_id);
+            self.ghosts.retain(|(sens_id, _), _| *sens_id != sensor_id);
+            self.activity_trail.retain(|(sens_id, _)| *sens_id != sensor_id);
+        }
+        MessageResult(Some((sensor_id, self.muted.clone())))
+    }
+}
+
+impl Handler<SetMuted> for UIState {
+    type Result = ();
+
+    fn handle(&mut self, SetMuted(muted): SetMuted, _: &mut Self::Context) -> Self::Result {
+        self.muted = muted;
+    }
+}
+
+impl Handler<ToggleFreeze> for UIState {
+    type Result = bool;
+
+    fn handle(&mut self, _: ToggleFreeze, _: &mut Self::Context) -> Self::Result {
+        self.frozen_at = match self.frozen_at {
+            Some(_) => None,
+            None => Some(Timestamp::now()),
+        };
+        self.frozen_at.is_some()
+    }
+}
+
+impl Handler<SetAlert> for UIState {
+    type Result = ();
+
+    fn handle(
+        &mut self,
+        SetAlert {
+            sensor_id,
+            metric_id,
+            raised,
+        }: SetAlert,
+        _: &mut Self::Context,
+    ) -> Self::Result {
+        if raised {
+            self.alerts.insert((sensor_id, metric_id));
+        } else {
+            self.alerts.remove(&(sensor_id, metric_id));
+        }
+    }
+}
+
+impl Handler<SetSilencing> for UIState {
+    type Result = ();
+
+    fn handle(&mut self, SetSilencing(silencing): SetSilencing, _: &mut Self::Context) -> Self::Result {
+        self.silencing = silencing;
+    }
+}
+
+impl Handler<AddError> for UIState {
+    type Result = ();
+
+    fn handle(
+        &mut self,
+        AddError {
+            scheme,
+            code,
+            message,
+            timestamp,
+            correlation_id,
+            request,
+        }: AddError,
+        _: &mut Self::Context,
+    ) -> Self::Result {
+        if self.errors.len() == ERRORS_LIMIT {
+            self.errors.pop_front();
+        }
+        self.errors.push_back(ErrorEntry {
+            id: self.next_error_id,
+            scheme,
+            code,
+            message,
+            timestamp,
+            correlation_id,
+            request,
+        });
+        self.next_error_id += 1;
+    }
+}
+
+impl Handler<DismissErrors> for UIState {
+    type Result = ();
+
+    fn handle(&mut self, DismissErrors(ids): DismissErrors, _: &mut Self::Context) -> Self::Result {
+        self.errors.retain(|error| !ids.contains(&error.id));
+    }
+}
+
 impl Handler<SetModalDialog> for UIState {
     type Result = ();
 
@@ -190,6 +564,54 @@ impl Handler<HandleKeyEvent> for UIState {
                         let _ = dialog_actor.send(key_event_message).await;
                     }.into_actor(self));
                 },
+                LogFilter(dialog_actor) => {
+                    let dialog_actor = dialog_actor.clone();
+                    ctx.spawn(async move {
+                        let _ = dialog_actor.send(key_event_message).await;
+                    }.into_actor(self));
+                },
+                JsonViewer(dialog_actor) => {
+                    let dialog_actor = dialog_actor.clone();
+                    ctx.spawn(async move {
+                        let _ = dialog_actor.send(key_event_message).await;
+                    }.into_actor(self));
+                },
+                Path(dialog_actor) => {
+                    let dialog_actor = dialog_actor.clone();
+                    ctx.spawn(async move {
+                        let _ = dialog_actor.send(key_event_message).await;
+                    }.into_actor(self));
+                },
+                Help(dialog_actor) => {
+                    let dialog_actor = dialog_actor.clone();
+                    ctx.spawn(async move {
+                        let _ = dialog_actor.send(key_event_message).await;
+                    }.into_actor(self));
+                },
+                ModalDialog::Message(dialog_actor) => {
+                    let dialog_actor = dialog_actor.clone();
+                    ctx.spawn(async move {
+                        let _ = dialog_actor.send(key_event_message).await;
+                    }.into_actor(self));
+                },
+                Alert(dialog_actor) => {
+                    let dialog_actor = dialog_actor.clone();
+                    ctx.spawn(async move {
+                        let _ = dialog_actor.send(key_event_message).await;
+                    }.into_actor(self));
+                },
+                Errors(dialog_actor) => {
+                    let dialog_actor = dialog_actor.clone();
+                    ctx.spawn(async move {
+                        let _ = dialog_actor.send(key_event_message).await;
+                    }.into_actor(self));
+                },
+                AlertHistory(dialog_actor) => {
+                    let dialog_actor = dialog_actor.clone();
+                    ctx.spawn(async move {
+                        let _ = dialog_actor.send(key_event_message).await;
+                    }.into_actor(self));
+                },
             }
             true
         } else {
@@ -198,17 +620,145 @@ impl Handler<HandleKeyEvent> for UIState {
     }
 }
 
-impl Handler<AppendError> for UIState {
+impl Handler<SetConnector> for UIState {
+    type Result = ();
+
+    fn handle(&mut self, SetConnector(connector_id): SetConnector, _: &mut Self::Context) {
+        self.connector_id = Some(connector_id);
+    }
+}
+
+impl Handler<SetProfile> for UIState {
+    type Result = ();
+
+    fn handle(&mut self, SetProfile(profile): SetProfile, _: &mut Self::Context) {
+        self.profile = Some(profile);
+    }
+}
+
+impl Handler<SetOffline> for UIState {
+    type Result = ();
+
+    fn handle(&mut self, SetOffline(offline): SetOffline, _: &mut Self::Context) {
+        self.offline = offline;
+    }
+}
+
+impl Handler<RecordPing> for UIState {
+    type Result = MessageResult<RecordPing>;
+
+    fn handle(&mut self, RecordPing(latency): RecordPing, _: &mut Self::Context) -> Self::Result {
+        let previous = self.ping;
+        match latency {
+            Some(latency) => {
+                self.ping.latency = Some(latency);
+                self.ping.failures = 0;
+            }
+            None => self.ping.failures += 1,
+        }
+        MessageResult(previous)
+    }
+}
+
+impl Handler<EditFilter> for UIState {
+    type Result = ();
+
+    fn handle(&mut self, _: EditFilter, _: &mut Self::Context) -> Self::Result {
+        self.filter.editing = true;
+    }
+}
+
+impl Handler<HandleFilterKey> for UIState {
+    type Result = bool;
+
+    fn handle(&mut self, HandleFilterKey(key_event): HandleFilterKey, _: &mut Self::Context) -> bool {
+        if !self.filter.editing {
+            return false;
+        }
+        match key_event.code {
+            KeyCode::Char(char) => self.filter.query.push(char),
+            KeyCode::Backspace => {
+                self.filter.query.pop();
+            }
+            KeyCode::Enter => self.filter.editing = false,
+            KeyCode::Esc => self.filter = FilterState::default(),
+            _ => {}
+        }
+        true
+    }
+}
+
+impl Handler<MarkSleepGap> for UIState {
+    type Result = ();
+
+    fn handle(&mut self, MarkSleepGap { from, to }: MarkSleepGap, _: &mut Self::Context) {
+        if self.sleep_gaps.len() == SLEEP_GAPS_LIMIT {
+            self.sleep_gaps.pop_front();
+        }
+        self.sleep_gaps.push_back((from, to));
+    }
+}
+
+impl Handler<ToggleLogPane> for UIState {
+    type Result = bool;
+
+    fn handle(&mut self, _: ToggleLogPane, _: &mut Self::Context) -> Self::Result {
+        self.log_pane.hidden = !self.log_pane.hidden;
+        !self.log_pane.hidden
+    }
+}
+
+impl Handler<ScrollLogPane> for UIState {
+    type Result = ();
+
+    fn handle(&mut self, ScrollLogPane(delta): ScrollLogPane, _: &mut Self::Context) -> Self::Result {
+        // The oldest record stays in view
+        let max_scroll = logging::entries_count().saturating_sub(1);
+        self.log_pane.scroll = self
+            .log_pane
+            .scroll
+            .saturating_add_signed(delta)
+            .min(max_scroll);
+    }
+}
+
+impl Handler<SetMetricPageSize> for UIState {
     type Result = ();
 
     fn handle(
         &mut self,
-        AppendError{message, code}: AppendError,
+        SetMetricPageSize(metric_page_size): SetMetricPageSize,
         _: &mut Self::Context,
     ) -> Self::Result {
-        if self.errors.len() == 3 {
-            self.errors.pop_front();
+        self.metric_page_size = metric_page_size;
+    }
+}
+
+impl Handler<ResizeRegion> for UIState {
+    type Result = MessageResult<ResizeRegion>;
+
+    fn handle(
+        &mut self,
+        ResizeRegion(delta): ResizeRegion,
+        _: &mut Self::Context,
+    ) -> Self::Result {
+        if self.overview {
+            self.region_sizes.resize_overview_columns(delta);
+        } else {
+            self.region_sizes.resize_log_pane(delta);
         }
-        self.errors.push_back(format!("Error #{}: {}", code, message));
+        MessageResult(self.region_sizes)
+    }
+}
+
+impl Handler<SetRegionSizes> for UIState {
+    type Result = ();
+
+    fn handle(
+        &mut self,
+        SetRegionSizes(region_sizes): SetRegionSizes,
+        _: &mut Self::Context,
+    ) -> Self::Result {
+        self.region_sizes = region_sizes;
     }
 }
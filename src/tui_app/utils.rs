@@ -1,5 +1,9 @@
 use ratatui::layout::{Constraint, Direction, Layout, Rect};
 
+use std::io::Write;
+use std::path::Path;
+use std::time::Duration;
+
 pub fn centered_rect_abs(width: u16, height: u16, r: Rect) -> Rect {
     let popup_layout = Layout::default()
         .direction(Direction::Vertical)
@@ -20,3 +24,84 @@ pub fn centered_rect_abs(width: u16, height: u16, r: Rect) -> Rect {
         ])
         .split(popup_layout[1])[1] // Return the middle chunk
 }
+
+/// Entries of the directory `input` points into whose names start with its last component,
+/// along with that directory as typed. Directories are flagged and listed first.
+pub fn path_candidates(input: &str) -> (&str, Vec<(String, bool)>) {
+    let (dir, prefix) = match input.rfind('/') {
+        Some(index) => (&input[..=index], &input[index + 1..]),
+        None => ("", input),
+    };
+    let dir_path = if dir.is_empty() { Path::new(".") } else { Path::new(dir) };
+
+    let Ok(entries) = std::fs::read_dir(dir_path) else {
+        return (dir, Vec::new());
+    };
+    let mut candidates: Vec<(String, bool)> = entries
+        .filter_map(|entry| entry.ok())
+        .filter_map(|entry| {
+            let name = entry.file_name().into_string().ok()?;
+            let is_dir = entry.file_type().is_ok_and(|file_type| file_type.is_dir());
+            name.starts_with(prefix).then_some((name, is_dir))
+        })
+        .collect();
+    candidates.sort_by(|(a, a_is_dir), (b, b_is_dir)| b_is_dir.cmp(a_is_dir).then(a.cmp(b)));
+    (dir, candidates)
+}
+
+/// Completes the last component of `input` as far as the matching directory entries agree,
+/// a directory matched unambiguously gets a trailing separator
+pub fn complete_path(input: &str) -> Option<String> {
+    let (dir, candidates) = path_candidates(input);
+
+    match &candidates[..] {
+        [] => None,
+        [(name, is_dir)] => Some(format!("{dir}{name}{}", if *is_dir { "/" } else { "" })),
+        [(first, _), rest @ ..] => {
+            let common_len = rest.iter().fold(first.len(), |len, (name, _)| {
+                first
+                    .char_indices()
+                    .zip(name.chars())
+                    .take_while(|((index, a), b)| *index < len && a == b)
+                    .map(|((index, a), _)| index + a.len_utf8())
+                    .last()
+                    .unwrap_or(0)
+            });
+            Some(format!("{dir}{}", &first[..common_len]))
+        }
+    }
+}
+
+/// `1h 02m 03s`
+pub fn format_duration(duration: Duration) -> String {
+    let secs = duration.as_secs();
+    format!("{}h {:02}m {:02}s", secs / 3600, secs / 60 % 60, secs % 60)
+}
+
+/// Puts `text` on the clipboard through the OSC 52 escape sequence, which works over SSH too,
+/// provided the terminal supports it
+pub fn copy_to_clipboard(text: &str) -> std::io::Result<()> {
+    let mut stdout = std::io::stdout();
+    write!(stdout, "\x1b]52;c;{}\x07", base64(text.as_bytes()))?;
+    stdout.flush()
+}
+
+fn base64(bytes: &[u8]) -> String {
+    const ALPHABET: &[u8; 64] =
+        b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+    let mut encoded = String::with_capacity(bytes.len().div_ceil(3) * 4);
+    for chunk in bytes.chunks(3) {
+        let triple = chunk
+            .iter()
+            .enumerate()
+            .fold(0u32, |triple, (i, byte)| triple | (*byte as u32) << (16 - 8 * i));
+        for i in 0..4 {
+            if i <= chunk.len() {
+                encoded.push(ALPHABET[(triple >> (18 - 6 * i) & 0x3f) as usize] as char);
+            } else {
+                encoded.push('=');
+            }
+        }
+    }
+    encoded
+}
@@ -1,5 +1,9 @@
+use eyre::{eyre, Result};
+
 use strum::IntoEnumIterator;
 
+use std::sync::{Mutex, MutexGuard};
+
 pub trait CircularEnum: IntoEnumIterator + Sized + PartialEq {
     fn next(&self) -> Self {
         let current_index = Self::iter()
@@ -22,3 +26,86 @@ pub trait CircularEnum: IntoEnumIterator + Sized + PartialEq {
 }
 
 impl<T> CircularEnum for T where T: IntoEnumIterator + Sized + PartialEq {}
+
+/// Shell-like matching where `*` stands for any run of characters and `?` for a single one
+pub fn glob_match(pattern: &str, text: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let text: Vec<char> = text.chars().collect();
+
+    let (mut p, mut t) = (0, 0);
+    // Position of the last `*` and the text position it was tried at
+    let mut backtrack: Option<(usize, usize)> = None;
+
+    while t < text.len() {
+        match pattern.get(p) {
+            Some('*') => {
+                backtrack = Some((p, t));
+                p += 1;
+            }
+            Some(&c) if c == '?' || c == text[t] => {
+                p += 1;
+                t += 1;
+            }
+            _ => match backtrack {
+                // Let the last `*` swallow one more character
+                Some((star_p, star_t)) => {
+                    backtrack = Some((star_p, star_t + 1));
+                    p = star_p + 1;
+                    t = star_t + 1;
+                }
+                None => return false,
+            },
+        }
+    }
+
+    pattern[p..].iter().all(|&c| c == '*')
+}
+
+/// Whether all characters of `query` appear in `text` in the same order, ignoring case
+pub fn fuzzy_match(query: &str, text: &str) -> bool {
+    let mut text = text.chars().flat_map(char::to_lowercase);
+    query
+        .chars()
+        .flat_map(char::to_lowercase)
+        .filter(|c| !c.is_whitespace())
+        .all(|c| text.any(|t| t == c))
+}
+
+/// Locks `mutex` even if a thread panicked while holding it. The shared state here is
+/// only ever updated in single steps, so a panic can't leave it half-written.
+pub fn lock_recovering<T>(mutex: &Mutex<T>) -> MutexGuard<'_, T> {
+    mutex.lock().unwrap_or_else(|poisoned| {
+        mutex.clear_poison();
+        poisoned.into_inner()
+    })
+}
+
+/// Seconds of a Prometheus-style duration such as `90s`, `5m` or `1h30m`
+pub fn duration_secs(duration: &str) -> Result<u64> {
+    let invalid = || eyre!("Invalid duration '{duration}', expected e.g. 90s, 5m or 1h30m");
+    let mut rest = duration.trim();
+    if rest.is_empty() {
+        return Err(invalid());
+    }
+    let mut secs = 0u64;
+    while !rest.is_empty() {
+        let (number, after) = rest.split_at(rest.find(|c: char| !c.is_ascii_digit()).unwrap_or(rest.len()));
+        let (unit, after) = after.split_at(after.find(|c: char| c.is_ascii_digit()).unwrap_or(after.len()));
+        let scale = match unit {
+            "s" => 1,
+            "m" => 60,
+            "h" => 60 * 60,
+            "d" => 24 * 60 * 60,
+            "w" => 7 * 24 * 60 * 60,
+            _ => return Err(invalid()),
+        };
+        secs = number
+            .parse::<u64>()
+            .ok()
+            .and_then(|number| number.checked_mul(scale))
+            .and_then(|part| secs.checked_add(part))
+            .ok_or_else(invalid)?;
+        rest = after;
+    }
+    Ok(secs)
+}
@@ -0,0 +1,132 @@
+use actix::Actor;
+
+use eyre::{eyre, Result};
+
+use std::time::Duration;
+
+use sensor_vision::client::rules_watcher::AlertRulesWatcher;
+use sensor_vision::client::state::{AlertRule, GetAlertRules, SetAlertRules};
+use sensor_vision::config::{read_alert_rules, write_alert_rules};
+use sensor_vision::testing::{connect, connector_id, FakeBroker, TempFile};
+
+#[test]
+fn edited_rules_are_read_back() {
+    let file = TempFile::new("edited-alerts.yaml");
+    let path = file.path();
+    let rules = vec![
+        AlertRule::with_condition(String::from("Boiler Room"), String::from("Temperature"), "10..90").unwrap(),
+        AlertRule {
+            critical: true,
+            sinks: vec![String::from("bell")],
+            ..AlertRule::with_condition(String::from("*"), String::from("Status"), "~(?i)fault").unwrap()
+        },
+    ];
+    write_alert_rules(path, &rules).unwrap();
+    assert_eq!(read_alert_rules(path).unwrap(), rules);
+
+    write_alert_rules(path, &rules[1..]).unwrap();
+    assert_eq!(read_alert_rules(path).unwrap(), rules[1..]);
+}
+
+fn rule(sensor: &str, metric: &str, condition: &str) -> AlertRule {
+    AlertRule::with_condition(String::from(sensor), String::from(metric), condition).unwrap()
+}
+
+#[test]
+fn expressions_select_the_metric_and_the_threshold() {
+    let rule_of = |expr: &str| AlertRule::with_expression(expr).unwrap();
+    assert_eq!(rule_of(r#"Temperature{sensor="Boiler*"} > 90"#), rule("Boiler*", "Temperature", "..90"));
+    assert_eq!(rule_of("Temperature < -5.5"), rule("*", "Temperature", "-5.5.."));
+    assert_eq!(
+        rule_of(r#"{sensor="Room \"A\"", metric="Flow Rate"} <= 2"#),
+        rule("Room \"A\"", "Flow Rate", &format!("{}..", 2f64.next_up())),
+    );
+    assert_eq!(
+        rule_of(r#"{metric="Status"} =~ "(?i)fault\d""#),
+        rule("*", "Status", r"~(?i)fault\d"),
+    );
+
+    for (expr, error) in [
+        ("> 90", "selects no metric"),
+        ("Temperature == 90", "Expected one of"),
+        (r#"Temperature{room="A"} > 90"#, "Unknown label"),
+        (r#"{metric="A", metric="B"} > 90"#, "selected twice"),
+        ("Temperature > hot", "Invalid threshold"),
+        (r#"Status =~ "(""#, "Invalid pattern"),
+    ] {
+        let err = AlertRule::with_expression(expr).unwrap_err();
+        assert!(format!("{err:#}").contains(error), "{expr}: {err:#}");
+    }
+}
+
+#[test]
+fn prometheus_style_rules_are_read() {
+    let file = TempFile::with_contents(
+        "prometheus-alerts.yaml",
+        r#"
+groups:
+  - name: boiler
+    rules:
+      - alert: BoilerHot
+        expr: 'Temperature{sensor="Boiler*"} > 90'
+        for: 1h30m
+        labels:
+          severity: critical
+        annotations:
+          summary: The boiler overheats
+  - name: status
+    rules:
+      - sensor: "*"
+        metric: Status
+        matches: (?i)fault
+"#,
+    );
+    let rules = read_alert_rules(file.path()).unwrap();
+    assert_eq!(
+        rules,
+        [
+            AlertRule {
+                for_secs: Some(5400),
+                critical: true,
+                ..rule("Boiler*", "Temperature", "..90")
+            },
+            rule("*", "Status", "~(?i)fault"),
+        ]
+    );
+
+    std::fs::write(file.path(), "- alert: Cold\n  expr: Temperature < 5\n  for: 5 minutes\n").unwrap();
+    let err = read_alert_rules(file.path()).unwrap_err();
+    assert!(format!("{err:#}").contains("#1 (Cold)"), "{err:#}");
+    assert!(format!("{err:#}").contains("Invalid duration"), "{err:#}");
+}
+
+#[actix::test]
+async fn edited_rules_files_are_reloaded() -> Result<()> {
+    let file = TempFile::with_contents("reloaded-alerts.yaml", "- expr: Temperature > 90\n");
+    let broker = FakeBroker::start(connector_id()).await?;
+    let client = connect(&broker).await?;
+    client.send(SetAlertRules(read_alert_rules(file.path())?)).await??;
+    let _watcher = AlertRulesWatcher::new(client.clone(), file.path().to_owned()).start();
+
+    let wait_for_rules = async |expected: &[AlertRule]| -> Result<()> {
+        for _ in 0..50 {
+            if client.send(GetAlertRules).await?? == expected {
+                return Ok(());
+            }
+            tokio::time::sleep(Duration::from_millis(100)).await;
+        }
+        Err(eyre!("The rules weren't reloaded"))
+    };
+
+    // Apart enough for the modification times to differ
+    tokio::time::sleep(Duration::from_millis(50)).await;
+    std::fs::write(file.path(), "- expr: Temperature > 80\n")?;
+    wait_for_rules(&[rule("*", "Temperature", "..80")]).await?;
+
+    tokio::time::sleep(Duration::from_millis(50)).await;
+    std::fs::write(file.path(), "- expr: Temperature >\n")?;
+    // Checked at least once more, keeping the rules in force
+    tokio::time::sleep(Duration::from_millis(1500)).await;
+    assert_eq!(client.send(GetAlertRules).await??, [rule("*", "Temperature", "..80")]);
+    Ok(())
+}
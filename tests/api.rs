@@ -0,0 +1,105 @@
+#![cfg(feature = "api")]
+
+use actix::Actor;
+
+use eyre::Result;
+
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpStream;
+
+use std::net::SocketAddr;
+use std::time::Duration;
+
+use sensor_vision::client::api::ApiActor;
+use sensor_vision::client::client::load_inventory;
+use sensor_vision::client::state::queries::GetStateSnapshot;
+use sensor_vision::config::ApiConfig;
+use sensor_vision::model::sensor::SensorState;
+use sensor_vision::testing::{connect, connector_id, FakeBroker};
+
+/// Status code of the response to a bodiless request
+async fn status(address: SocketAddr, method: &str, path: &str, token: Option<&str>) -> Result<u16> {
+    let authorization = token
+        .map(|token| format!("Authorization: Bearer {token}\r\n"))
+        .unwrap_or_default();
+    send(address, format!("{method} {path} HTTP/1.1\r\nHost: localhost\r\n{authorization}Connection: close\r\n\r\n")).await
+}
+
+/// Status code of the response to a request with a JSON body, on loopback without a token
+async fn post_json(address: SocketAddr, path: &str, body: &str) -> Result<u16> {
+    send(
+        address,
+        format!(
+            "POST {path} HTTP/1.1\r\nHost: localhost\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{body}",
+            body.len()
+        ),
+    )
+    .await
+}
+
+async fn send(address: SocketAddr, request: String) -> Result<u16> {
+    let mut stream = TcpStream::connect(address).await?;
+    stream.write_all(request.as_bytes()).await?;
+    let mut response = String::new();
+    stream.read_to_string(&mut response).await?;
+    Ok(response.split_whitespace().nth(1).unwrap_or_default().parse()?)
+}
+
+#[actix::test]
+async fn other_than_loopback_takes_a_token() -> Result<()> {
+    let broker = FakeBroker::start(connector_id()).await?;
+    let client = connect(&broker).await?;
+    let config = ApiConfig {
+        listen: Some(String::from("0.0.0.0:0")),
+        token: None,
+    };
+    assert!(ApiActor::bind(client, &config).is_err());
+    Ok(())
+}
+
+#[actix::test]
+async fn requests_carry_the_token() -> Result<()> {
+    let broker = FakeBroker::start(connector_id()).await?;
+    let client = connect(&broker).await?;
+    let config = ApiConfig {
+        listen: Some(String::from("127.0.0.1:0")),
+        token: Some(String::from("secret")),
+    };
+    let api = ApiActor::bind(client, &config)?;
+    let address = api.local_addr().unwrap();
+    let _api = api.start();
+
+    assert_eq!(status(address, "GET", "/subscriptions", None).await?, 401);
+    assert_eq!(status(address, "GET", "/subscriptions", Some("wrong")).await?, 401);
+    assert_eq!(status(address, "GET", "/subscriptions", Some("secret")).await?, 200);
+    assert_eq!(status(address, "GET", "/nothing", Some("secret")).await?, 404);
+    assert_eq!(status(address, "DELETE", "/sensors/malformed", Some("secret")).await?, 400);
+    Ok(())
+}
+
+#[actix::test]
+async fn created_sensors_take_the_given_state() -> Result<()> {
+    let broker = FakeBroker::start(connector_id()).await?;
+    let client = connect(&broker).await?;
+    load_inventory(&client).await?;
+    let config = ApiConfig {
+        listen: Some(String::from("127.0.0.1:0")),
+        token: None,
+    };
+    let api = ApiActor::bind(client.clone(), &config)?;
+    let address = api.local_addr().unwrap();
+    let _api = api.start();
+
+    assert_eq!(post_json(address, "/sensors", r#"{"name": "Furnace", "state": 0}"#).await?, 202);
+    assert_eq!(post_json(address, "/sensors", r#"{"name": "", "state": 1}"#).await?, 400);
+    for _ in 0..50 {
+        let sensors = client.send(GetStateSnapshot).await??;
+        if let Some(furnace) = sensors.values().find(|sensor| sensor.name == "Furnace")
+            && furnace.state == SensorState::Disabled
+        {
+            return Ok(());
+        }
+        tokio::time::sleep(Duration::from_millis(100)).await;
+    }
+    panic!("Furnace wasn't disabled");
+}
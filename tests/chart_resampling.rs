@@ -0,0 +1,38 @@
+use sensor_vision::tui_app::ui_state::resample_envelope;
+
+use std::borrow::Cow;
+
+/// A value per second, flat but for a spike and a dip
+fn spiky(count: usize) -> Vec<(f64, f64)> {
+    (0..count)
+        .map(|second| {
+            let value = match second {
+                13 => 100.0,
+                31 => -100.0,
+                _ => 1.0,
+            };
+            (second as f64 * 1000.0, value)
+        })
+        .collect()
+}
+
+#[test]
+fn fitting_data_is_left_alone() {
+    let data = spiky(50);
+    assert!(matches!(resample_envelope(&data, 25), Cow::Borrowed(_)));
+    assert!(matches!(resample_envelope(&data, 0), Cow::Borrowed(_)));
+    assert!(matches!(resample_envelope(&[], 10), Cow::Borrowed(_)));
+}
+
+#[test]
+fn narrow_chart_keeps_the_envelope() {
+    let data = spiky(50);
+    let resampled = resample_envelope(&data, 10);
+    assert!(resampled.len() <= 20);
+    assert!(resampled.contains(&(13_000.0, 100.0)));
+    assert!(resampled.contains(&(31_000.0, -100.0)));
+    // Still in time order, from the first value to the last one
+    assert!(resampled.windows(2).all(|pair| pair[0].0 < pair[1].0));
+    assert_eq!(resampled.first(), data.first());
+    assert_eq!(resampled.last().map(|(timestamp, _)| *timestamp), Some(49_000.0));
+}
@@ -0,0 +1,81 @@
+use eyre::eyre;
+
+use sensor_vision::config::Config;
+use sensor_vision::migrations::{self, Migration, CONFIG_VERSION};
+use sensor_vision::testing::TempFile;
+
+use std::fs;
+
+use toml::{Table, Value};
+
+/// Version 1 called the broker host `server`
+const RENAME_SERVER: Migration = Migration {
+    description: "Rename broker.server to broker.host",
+    apply: |config| {
+        if let Some(Value::Table(broker)) = config.get_mut("broker")
+            && let Some(server) = broker.remove("server")
+        {
+            broker.insert(String::from("host"), server);
+        }
+        Ok(())
+    },
+};
+
+const STAMP: Migration = Migration {
+    description: "Record the version",
+    apply: |_| Ok(()),
+};
+
+#[test]
+fn only_missing_migrations_are_applied() {
+    let mut config: Table = toml::from_str("version = 1\n[broker]\nserver = \"a\"\n").unwrap();
+    let applied = migrations::upgrade(&mut config, &[STAMP, RENAME_SERVER]).unwrap();
+    assert_eq!(applied, [RENAME_SERVER.description]);
+    assert_eq!(config["version"], Value::Integer(2));
+    assert_eq!(config["broker"]["host"], Value::String(String::from("a")));
+
+    // Up to date already
+    assert!(migrations::upgrade(&mut config, &[STAMP, RENAME_SERVER]).unwrap().is_empty());
+}
+
+#[test]
+fn newer_and_failing_versions_are_rejected() {
+    let mut config: Table = toml::from_str("version = 3").unwrap();
+    let err = migrations::upgrade(&mut config, &[STAMP]).unwrap_err();
+    assert!(err.to_string().contains("newer"), "{err}");
+
+    let failing = Migration {
+        description: "Fail",
+        apply: |_| Err(eyre!("no way")),
+    };
+    let mut config = Table::new();
+    let err = migrations::upgrade(&mut config, &[failing]).unwrap_err();
+    assert_eq!(err.to_string(), "Failed to upgrade the config from version 0: Fail");
+}
+
+#[test]
+fn changed_files_are_rewritten_with_a_backup() {
+    let original = "# Staging\n[broker]\nserver = \"broker.example.com\"\n";
+    let file = TempFile::with_contents("upgraded.toml", original);
+    let path = file.path();
+    let mut config: Table = toml::from_str(original).unwrap();
+    let upgrade = migrations::upgrade_file(path, &mut config, &[RENAME_SERVER])
+        .unwrap()
+        .expect("an upgrade");
+    assert_eq!(upgrade.from_version, 0);
+    assert_eq!(fs::read_to_string(&upgrade.backup).unwrap(), original);
+    let rewritten: Table = toml::from_str(&fs::read_to_string(path).unwrap()).unwrap();
+    assert_eq!(rewritten, config);
+}
+
+#[test]
+fn unversioned_configs_load_untouched() {
+    let original = "# Local broker\n[broker]\nhost = \"localhost\"\n";
+    let file = TempFile::with_contents("unversioned.toml", original);
+    let path = file.path();
+    let config = Config::load(Some(path), None).unwrap();
+    assert_eq!(config.version, CONFIG_VERSION);
+    assert_eq!(config.upgrade, None);
+    // Only the version would've changed, the comments are kept
+    assert_eq!(fs::read_to_string(path).unwrap(), original);
+}
@@ -0,0 +1,81 @@
+use sensor_vision::config::Config;
+use sensor_vision::testing::TempFile;
+
+const CONFIG: &str = r#"
+profile = "dev"
+
+[broker]
+host = "broker.example.com"
+cert_port = 18883
+
+[tls]
+trust_store = "ca.crt"
+
+[profiles.dev]
+broker = "mqtt://localhost:1883"
+client_cert = "dev/clientCert.crt"
+private_key = "dev/privkey.pem"
+
+[profiles.prod]
+broker = "mqtts://prod.example.com:18884"
+cert_port = 28883
+client_cert = "prod/clientCert.crt"
+private_key = "prod/privkey.pem"
+connector_id = "00000000-0000-0000-0000-0000000000AB"
+"#;
+
+#[test]
+fn default_profile_of_the_config() {
+    let file = TempFile::with_contents("default.toml", CONFIG);
+    let config = Config::load(Some(file.path()), None).unwrap();
+    assert_eq!(config.active_profile.as_deref(), Some("dev"));
+    assert_eq!(config.broker.uri(), "mqtt://localhost:1883");
+    assert_eq!(config.broker.cert_port, 18883);
+    // Unset by the profile
+    assert_eq!(config.tls.trust_store.as_deref(), Some("ca.crt"));
+    assert_eq!(config.credentials().client_cert, "dev/clientCert.crt");
+    assert_eq!(config.expected_connector_id(), None);
+}
+
+#[test]
+fn profile_given() {
+    let file = TempFile::with_contents("given.toml", CONFIG);
+    let config = Config::load(Some(file.path()), Some("prod")).unwrap();
+    assert_eq!(config.active_profile.as_deref(), Some("prod"));
+    assert_eq!(config.broker.uri(), "mqtts://prod.example.com:18884");
+    assert_eq!(config.broker.cert_uri(), "mqtts://prod.example.com:28883");
+    assert_eq!(config.credentials().private_key, "prod/privkey.pem");
+    assert_eq!(config.expected_connector_id(), Some("00000000-0000-0000-0000-0000000000AB"));
+}
+
+#[test]
+fn unknown_profile() {
+    let file = TempFile::with_contents("unknown.toml", CONFIG);
+    let err = Config::load(Some(file.path()), Some("staging")).unwrap_err();
+    assert!(err.to_string().contains("dev, prod"), "{err}");
+}
+
+#[test]
+fn malformed_broker() {
+    let file = TempFile::with_contents("malformed.toml", "[profiles.dev]\nbroker = \"localhost:1883\"\n");
+    assert!(Config::load(Some(file.path()), Some("dev")).is_err());
+}
+
+#[test]
+fn polling_intervals_out_of_range() {
+    for (endpoint, error) in [
+        ("interval_secs = 0.0", "Interval"),
+        ("interval_secs = -1.0", "Interval"),
+        ("interval_secs = 1e300", "Interval"),
+        ("max_backoff_secs = inf", "Max backoff"),
+        ("max_backoff_secs = nan", "Max backoff"),
+        ("max_backoff_secs = -5.0", "Max backoff"),
+    ] {
+        let file = TempFile::with_contents(
+            "polling.toml",
+            &format!("[[http]]\nurl = \"http://localhost:8080\"\n{endpoint}\n"),
+        );
+        let err = Config::load(Some(file.path()), None).unwrap_err();
+        assert!(err.to_string().contains(error), "{endpoint}: {err}");
+    }
+}
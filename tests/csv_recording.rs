@@ -0,0 +1,30 @@
+use sensor_vision::client::replay::{read_csv, CsvRecording};
+use sensor_vision::testing::TempFile;
+
+#[test]
+fn the_header_tells_the_delimiter() {
+    let file = TempFile::with_contents("semicolons.csv", "time;Boiler/Temperature\n1700000000;21,5\n\n1700000001;22\n");
+    let recording = CsvRecording::read(file.path()).unwrap();
+    assert_eq!(recording.columns, ["Boiler/Temperature"]);
+    let values: Vec<&str> = recording.rows.iter().map(|row| row.values[0].as_str()).collect();
+    assert_eq!(values, ["21,5", "22"]);
+}
+
+#[test]
+fn quoted_cells_keep_delimiters() {
+    let file = TempFile::with_contents("quoted.csv", "\"Boiler, Room/Temperature\",Status\n21.5,\"says \"\"ok\"\"\"\n");
+    let rows = read_csv(file.path()).unwrap();
+    assert_eq!(rows[0].cells, ["Boiler, Room/Temperature", "Status"]);
+    assert_eq!(rows[1].cells, ["21.5", "says \"ok\""]);
+}
+
+#[test]
+fn malformed_rows_are_rejected_with_their_line() {
+    let file = TempFile::with_contents("ragged.csv", "time,a,b\n1700000000,1,2\n\n1700000001,3\n");
+    let err = CsvRecording::read(file.path()).unwrap_err().to_string();
+    assert!(err.contains("Line 4"), "{err}");
+
+    let file = TempFile::with_contents("unterminated.csv", "a,b\n1,\"2\n");
+    let err = read_csv(file.path()).unwrap_err().to_string();
+    assert!(err.contains("line 2"), "{err}");
+}
@@ -0,0 +1,76 @@
+use sensor_vision::client::describe_scheduler::DescribeScheduler;
+use sensor_vision::model::{MetricId, SensorId};
+
+use std::time::{Duration, Instant};
+
+const BOILER: &str = "00000000000000000000000000000001";
+
+fn metric_ids(count: u32) -> Vec<MetricId> {
+    (1..=count)
+        .map(|index| MetricId::from(format!("{index:032x}").as_str()))
+        .collect()
+}
+
+#[test]
+fn batches_in_queued_order() {
+    let mut scheduler = DescribeScheduler::new(2, Duration::from_millis(100));
+    let boiler = SensorId::from(BOILER);
+    let now = Instant::now();
+    assert_eq!(scheduler.enqueue(boiler, metric_ids(5), None, now), 5);
+
+    let batches: Vec<Vec<MetricId>> = (0..3)
+        .map(|_| {
+            scheduler
+                .next_batch(now)
+                .into_iter()
+                .map(|(_, metric_id, _)| metric_id)
+                .collect()
+        })
+        .collect();
+    let ids = metric_ids(5);
+    assert_eq!(batches, [ids[..2].to_vec(), ids[2..4].to_vec(), ids[4..].to_vec()]);
+    assert!(scheduler.is_idle());
+}
+
+#[test]
+fn queued_and_in_flight_describes_are_dropped() {
+    let mut scheduler = DescribeScheduler::new(2, Duration::from_millis(100));
+    let boiler = SensorId::from(BOILER);
+    let ids = metric_ids(3);
+    let now = Instant::now();
+    assert_eq!(scheduler.enqueue(boiler, ids.clone(), None, now), 3);
+    // E.g. SensorMetricsUpdated right after the sensor list
+    assert_eq!(scheduler.enqueue(boiler, ids.clone(), None, now), 0);
+
+    assert_eq!(scheduler.next_batch(now).len(), 2);
+    assert_eq!(scheduler.enqueue(boiler, ids.clone(), None, now), 0);
+
+    // Replied to, so described anew
+    scheduler.described(boiler, ids[0]);
+    assert_eq!(scheduler.enqueue(boiler, ids.clone(), None, now), 1);
+}
+
+#[test]
+fn unanswered_describes_are_sent_again() {
+    let mut scheduler = DescribeScheduler::new(0, Duration::ZERO);
+    let boiler = SensorId::from(BOILER);
+    let now = Instant::now();
+    scheduler.enqueue(boiler, metric_ids(3), None, now);
+    // No batching
+    assert_eq!(scheduler.next_batch(now).len(), 3);
+
+    assert_eq!(scheduler.enqueue(boiler, metric_ids(3), None, now + Duration::from_secs(1)), 0);
+    assert_eq!(scheduler.enqueue(boiler, metric_ids(3), None, now + Duration::from_secs(60)), 3);
+}
+
+#[test]
+fn deleted_sensor_is_forgotten() {
+    let mut scheduler = DescribeScheduler::new(1, Duration::from_millis(100));
+    let boiler = SensorId::from(BOILER);
+    let now = Instant::now();
+    scheduler.enqueue(boiler, metric_ids(3), None, now);
+    scheduler.next_batch(now);
+    scheduler.forget_sensor(boiler);
+    assert!(scheduler.is_idle());
+    assert_eq!(scheduler.enqueue(boiler, metric_ids(3), None, now), 3);
+}
@@ -0,0 +1,64 @@
+use std::time::Duration;
+
+use tokio::sync::oneshot;
+
+use sensor_vision::tui_app::dialog::{DialogResult, DialogTasks};
+
+/// Lets the spawned tasks run up to their next await
+async fn settle() {
+    actix::clock::sleep(Duration::from_millis(10)).await;
+}
+
+#[actix::test]
+async fn finished_tasks_drop_out() {
+    let tasks = DialogTasks::default();
+    let (tx, rx) = oneshot::channel::<DialogResult<()>>();
+    tasks.spawn(async move {
+        let _ = rx.await;
+    });
+    settle().await;
+    assert_eq!(tasks.len(), 1);
+
+    tx.send(DialogResult::Cancel).unwrap();
+    settle().await;
+    assert!(tasks.is_empty());
+}
+
+#[actix::test]
+async fn dialogs_closed_without_a_result_end_their_tasks() {
+    let tasks = DialogTasks::default();
+    let (tx, rx) = oneshot::channel::<DialogResult<String>>();
+    tasks.spawn(async move {
+        let Ok(_) = rx.await else {
+            return;
+        };
+        panic!("No result expected");
+    });
+    settle().await;
+
+    // The way a replaced dialog actor drops its sender
+    drop(tx);
+    settle().await;
+    assert!(tasks.is_empty());
+}
+
+#[actix::test]
+async fn no_task_outlives_the_app() {
+    let tasks = DialogTasks::default();
+    let (finished_tx, mut finished_rx) = tokio::sync::mpsc::channel::<()>(4);
+    for _ in 0..3 {
+        let finished_tx = finished_tx.clone();
+        tasks.spawn(async move {
+            std::future::pending::<()>().await;
+            let _ = finished_tx.send(()).await;
+        });
+    }
+    drop(finished_tx);
+    settle().await;
+    assert_eq!(tasks.len(), 3);
+
+    tasks.abort_all();
+    assert!(tasks.is_empty());
+    // Aborted tasks drop their senders without ever getting past the await
+    assert_eq!(finished_rx.recv().await, None);
+}
@@ -0,0 +1,42 @@
+use sensor_vision::client::exchanges;
+use sensor_vision::model::SensorId;
+
+const BOILER: &str = "00000000000000000000000000000001";
+
+const REPLY: &str = "/v1.0/connector/sensor/boiler/update/info/inbox";
+const ERROR: &str = "/v1.0/connector/sensor/boiler/update/error/inbox";
+
+#[test]
+fn same_topic_requests_get_their_own_replies() {
+    let sensor_id = Some(SensorId::from(BOILER));
+    exchanges::request_sent(1, sensor_id, "update", r#"{"name": "first"}"#, [REPLY, ERROR]);
+    exchanges::request_sent(2, sensor_id, "update", r#"{"name": "second"}"#, [REPLY, ERROR]);
+
+    let first = exchanges::reply_received(ERROR, "{}", true).unwrap();
+    assert_eq!(first.request, r#"{"name": "first"}"#);
+    assert!(first.is_error);
+    let second = exchanges::reply_received(REPLY, "{}", false).unwrap();
+    assert_eq!(second.request, r#"{"name": "second"}"#);
+    assert!(!second.is_error);
+
+    // Both were answered
+    assert!(exchanges::reply_received(REPLY, "{}", false).is_none());
+    assert_eq!(exchanges::of_sensor(sensor_id).len(), 2);
+}
+
+#[test]
+fn late_replies_skip_expired_requests() {
+    const SENSOR: &str = "00000000000000000000000000000002";
+    const REPLY: &str = "/v1.0/connector/sensor/pump/delete/info/inbox";
+    const ERROR: &str = "/v1.0/connector/sensor/pump/delete/error/inbox";
+
+    let sensor_id = Some(SensorId::from(SENSOR));
+    exchanges::request_sent(10, sensor_id, "delete", "timed out", [REPLY, ERROR]);
+    exchanges::request_sent(11, sensor_id, "delete", "answered", [REPLY, ERROR]);
+    exchanges::request_expired(10);
+
+    let answered = exchanges::reply_received(REPLY, "{}", false).unwrap();
+    assert_eq!(answered.request, "answered");
+    // The expired one is still listed, without a reply
+    assert!(exchanges::of_sensor(sensor_id).iter().any(|exchange| exchange.request == "timed out" && exchange.reply.is_none()));
+}
@@ -0,0 +1,223 @@
+use actix::Addr;
+
+use eyre::{eyre, Result};
+
+use std::time::Duration;
+
+use sensor_vision::client::client::{load_inventory, SensorVisionClient};
+use sensor_vision::client::client_queries::{
+    CreateMetrics, CreateSensor, PingTest, PushValue, PushValueByName, UpdateSensor,
+};
+use sensor_vision::client::mqtt::topic_matches;
+use sensor_vision::client::state::queries::{GetStateSnapshot, ResolveMetric};
+use sensor_vision::client::state::Sensors;
+use sensor_vision::config::PushConfig;
+use sensor_vision::model::protocol::MetricValue;
+use sensor_vision::model::sensor::{Metric, ValueType, ValueUnit};
+use sensor_vision::testing::{connect, connect_with_push, connector_id, FakeBroker};
+
+/// Polls the state until `done` holds, the agent replies are processed asynchronously
+async fn wait_for(
+    client: &Addr<SensorVisionClient>,
+    done: impl Fn(&Sensors) -> bool,
+) -> Result<Sensors> {
+    for _ in 0..50 {
+        let sensors = client.send(GetStateSnapshot).await??;
+        if done(&sensors) {
+            return Ok(sensors);
+        }
+        tokio::time::sleep(Duration::from_millis(100)).await;
+    }
+    Err(eyre!("State didn't settle"))
+}
+
+#[test]
+fn topic_filters() {
+    assert!(topic_matches("/v1.0/+/#", "/v1.0/abc/sensor/list"));
+    assert!(topic_matches("a/+/c", "a/b/c"));
+    assert!(!topic_matches("a/+/c", "a/b/c/d"));
+    assert!(!topic_matches("a/b", "a/c"));
+}
+
+#[actix::test]
+async fn ping() -> Result<()> {
+    let broker = FakeBroker::start(connector_id()).await?;
+    let client = connect(&broker).await?;
+
+    client.send(PingTest).await??;
+    Ok(())
+}
+
+#[actix::test]
+async fn loads_the_inventory() -> Result<()> {
+    let broker = FakeBroker::start(connector_id()).await?;
+    broker.add_sensor(
+        "Boiler",
+        vec![
+            Metric::predefined("Temperature".into(), ValueUnit::Celsius),
+            Metric::custom("Mode".into(), ValueType::String, "mode".into()),
+        ],
+    );
+    let client = connect(&broker).await?;
+
+    load_inventory(&client).await?;
+
+    let sensors = client.send(GetStateSnapshot).await??;
+    let boiler = sensors.values().next().ok_or_else(|| eyre!("No sensors"))?;
+    assert_eq!(boiler.name, "Boiler");
+    let names: Vec<_> = boiler.metrics.iter().map(|metric| metric.name().as_str()).collect();
+    assert_eq!(names, ["Temperature", "Mode"]);
+    Ok(())
+}
+
+#[actix::test]
+async fn creates_sensors_and_metrics() -> Result<()> {
+    let broker = FakeBroker::start(connector_id()).await?;
+    let client = connect(&broker).await?;
+    load_inventory(&client).await?;
+
+    client.send(CreateSensor { name: "Pump".into() }).await??;
+    let sensors = wait_for(&client, |sensors| !sensors.is_empty()).await?;
+    let sensor_id = *sensors.keys().next().unwrap();
+
+    client
+        .send(CreateMetrics {
+            sensor_id,
+            metrics: vec![Metric::custom("Pressure".into(), ValueType::Double, "bar".into())],
+        })
+        .await??;
+    wait_for(&client, |sensors| {
+        sensors
+            .get(&sensor_id)
+            .is_some_and(|sensor| sensor.metrics.len() == 1)
+    })
+    .await?;
+
+    let (_, name, metrics) = broker.sensors().pop().unwrap();
+    assert_eq!(name, "Pump");
+    assert_eq!(metrics[0].name(), "Pressure");
+    Ok(())
+}
+
+#[actix::test]
+async fn reloads_describe_the_metrics_never_described() -> Result<()> {
+    let broker = FakeBroker::start(connector_id()).await?;
+    let boiler_id = broker.add_sensor("Boiler", vec![Metric::predefined("Temperature".into(), ValueUnit::Celsius)]);
+    let pump_id = broker.add_sensor("Pump", vec![]);
+    let client = connect(&broker).await?;
+    load_inventory(&client).await?;
+    wait_for(&client, |sensors| sensors.get(&boiler_id).is_some_and(|sensor| sensor.metrics.len() == 1)).await?;
+
+    // Its creation went unnoticed, so only the reload after another sensor changed lists it
+    broker.add_metric(boiler_id, Metric::custom("Mode".into(), ValueType::String, "mode".into()));
+    client
+        .send(UpdateSensor {
+            sensor_id: pump_id,
+            name: "Pump 2".into(),
+            state: None,
+        })
+        .await??;
+    wait_for(&client, |sensors| sensors.get(&boiler_id).is_some_and(|sensor| sensor.metrics.len() == 2)).await?;
+    Ok(())
+}
+
+#[actix::test]
+async fn pushes_values() -> Result<()> {
+    let broker = FakeBroker::start(connector_id()).await?;
+    broker.add_sensor(
+        "Boiler",
+        vec![Metric::predefined("Temperature".into(), ValueUnit::Celsius)],
+    );
+    let client = connect(&broker).await?;
+    load_inventory(&client).await?;
+
+    let (sensor_id, metric) = client
+        .send(ResolveMetric {
+            sensor_name: "Boiler".into(),
+            metric_name: "Temperature".into(),
+        })
+        .await??;
+    client
+        .send(PushValue {
+            sensor_id,
+            metric_id: *metric.metric_id(),
+            value: MetricValue::Double(21.5),
+            timestamp: None,
+        })
+        .await??;
+    client.send(PingTest).await??;
+
+    let pushed = broker
+        .published()
+        .into_iter()
+        .find(|(topic, _)| topic.ends_with("/metric/pushValues"))
+        .ok_or_else(|| eyre!("Nothing pushed"))?;
+    assert!(pushed.1.contains("21.5"));
+    Ok(())
+}
+
+fn push_by_name(sensor_name: &str, metric_name: &str, value: &str) -> PushValueByName {
+    PushValueByName {
+        sensor_name: sensor_name.into(),
+        metric_name: metric_name.into(),
+        value: value.into(),
+        timestamp: None,
+    }
+}
+
+#[actix::test]
+async fn creates_what_a_push_by_name_lacks() -> Result<()> {
+    let broker = FakeBroker::start(connector_id()).await?;
+    broker.add_sensor(
+        "Boiler",
+        vec![Metric::predefined("Temperature".into(), ValueUnit::Celsius)],
+    );
+    let push = PushConfig {
+        create_missing: true,
+        value_unit: Some(ValueUnit::Percent),
+        ..PushConfig::default()
+    };
+    let client = connect_with_push(&broker, push).await?;
+    load_inventory(&client).await?;
+
+    client.send(push_by_name("Garage", "Humidity", "55.5")).await??;
+    client.send(push_by_name("Boiler", "Pressure", "1.5")).await??;
+    client.send(PingTest).await??;
+
+    let mut created: Vec<_> = broker
+        .sensors()
+        .into_iter()
+        .map(|(_, name, metrics)| {
+            let metrics: Vec<_> = metrics.iter().map(|metric| metric.name().clone()).collect();
+            (name, metrics)
+        })
+        .collect();
+    created.sort();
+    assert_eq!(
+        created,
+        [
+            ("Boiler".to_owned(), vec!["Temperature".to_owned(), "Pressure".to_owned()]),
+            ("Garage".to_owned(), vec!["Humidity".to_owned()]),
+        ]
+    );
+    let pushed: Vec<_> = broker
+        .published()
+        .into_iter()
+        .filter(|(topic, _)| topic.ends_with("/metric/pushValues"))
+        .collect();
+    assert_eq!(pushed.len(), 2);
+    assert!(pushed[0].1.contains("55.5"));
+    Ok(())
+}
+
+#[actix::test]
+async fn push_by_name_fails_for_missing_metrics_by_default() -> Result<()> {
+    let broker = FakeBroker::start(connector_id()).await?;
+    let client = connect(&broker).await?;
+    load_inventory(&client).await?;
+
+    let error = client.send(push_by_name("Garage", "Humidity", "55.5")).await?.unwrap_err();
+    assert!(error.to_string().contains("Garage"), "{error}");
+    assert!(broker.sensors().is_empty());
+    Ok(())
+}
@@ -0,0 +1,196 @@
+use std::time::Duration;
+
+use sensor_vision::client::state::Sensors;
+use sensor_vision::model::{MetricId, SensorId, Timestamp};
+use sensor_vision::testing::{metric, sensor};
+use sensor_vision::tui_app::ui_state::frame_model::{Body, FrameModel, PingBadge};
+use sensor_vision::tui_app::ui_state::{
+    MetricLayout, MetricLivedataWindow, StringLivedataWindow, TableColumn, UIState,
+};
+
+/// Boiler with Temperature and Pressure, Garage with Humidity
+fn sensors() -> Sensors {
+    [
+        sensor(
+            "00000000000000000000000000000001",
+            "Boiler",
+            vec![
+                metric("00000000000000000000000000000011", "Temperature"),
+                metric("00000000000000000000000000000012", "Pressure"),
+            ],
+        ),
+        sensor(
+            "00000000000000000000000000000002",
+            "Garage",
+            vec![metric("00000000000000000000000000000021", "Humidity")],
+        ),
+    ]
+    .into_iter()
+    .map(|sensor| (sensor.sensor_id, sensor))
+    .collect()
+}
+
+#[test]
+fn no_sensors() {
+    let model = FrameModel::build(&Sensors::new(), &UIState::default(), false);
+    assert_eq!(model.body, Body::NoSensors);
+    assert_eq!(model.banner, None);
+    assert_eq!(model.filter, None);
+}
+
+#[test]
+fn selected_sensor_and_metric() {
+    let sensors = sensors();
+    let garage_id = SensorId::from("00000000000000000000000000000002");
+    let humidity_id = MetricId::from("00000000000000000000000000000021");
+    let mut ui_state = UIState::default();
+    ui_state.current_sensor = Some((1, garage_id));
+    ui_state.current_metric = Some((0, humidity_id));
+    ui_state.alerts.insert((garage_id, humidity_id));
+    ui_state.activity_trail.push_front((garage_id, humidity_id));
+    ui_state.muted.insert(SensorId::from("00000000000000000000000000000001"));
+
+    let Body::Sensors {
+        tabs,
+        selected,
+        sensor,
+    } = FrameModel::build(&sensors, &ui_state, false).body
+    else {
+        panic!("Sensors expected");
+    };
+    let titles: Vec<&str> = tabs.iter().map(|tab| tab.title.as_str()).collect();
+    assert_eq!(titles, ["Boiler", "Garage"]);
+    assert_eq!(selected, Some(1));
+    assert_eq!(tabs[0].activity, None);
+    assert_eq!(tabs[1].activity, Some(0));
+    assert!(tabs[0].muted);
+    assert!(!tabs[1].muted);
+
+    let sensor = sensor.expect("the selected sensor");
+    assert_eq!(sensor.name, "Garage");
+    assert_eq!(sensor.cards.len(), 1);
+    let card = &sensor.cards[0];
+    assert_eq!(card.name, "Humidity");
+    assert!(card.selected);
+    assert!(card.alert);
+    assert_eq!(card.activity, Some(0));
+}
+
+#[test]
+fn filtered_out_sensors() {
+    let sensors = sensors();
+    let mut ui_state = UIState::default();
+    ui_state.filter.query = String::from("press");
+
+    let model = FrameModel::build(&sensors, &ui_state, false);
+    let filter = model.filter.expect("the filter line");
+    assert_eq!(filter.query, "press");
+    assert!(!filter.editing);
+    // Nothing is selected yet, the tabs keep their positions among all the sensors
+    let Body::Sensors { tabs, selected, sensor } = model.body else {
+        panic!("Sensors expected");
+    };
+    assert_eq!(tabs.len(), 1);
+    assert_eq!(tabs[0].title, "Boiler");
+    assert_eq!(tabs[0].index, 0);
+    assert_eq!(selected, None);
+    assert_eq!(sensor, None);
+
+    ui_state.filter.query = String::from("attic");
+    let model = FrameModel::build(&sensors, &ui_state, false);
+    assert_eq!(
+        model.body,
+        Body::NoMatch {
+            query: String::from("attic")
+        }
+    );
+}
+
+#[test]
+fn header_and_banner() {
+    let mut ui_state = UIState::default();
+    ui_state.ping.latency = Some(Duration::from_millis(12));
+    let model = FrameModel::build(&sensors(), &ui_state, true);
+    assert!(model.header.reloading);
+    assert_eq!(model.header.ping, Some(PingBadge::Latency(Duration::from_millis(12))));
+    assert_eq!(model.banner, None);
+
+    ui_state.ping.failures = 3;
+    let model = FrameModel::build(&sensors(), &ui_state, false);
+    assert_eq!(model.header.ping, Some(PingBadge::Failing(3)));
+    assert!(model.banner.is_some_and(|banner| banner.contains("last 3 pings")));
+}
+
+#[test]
+fn metric_table_layout() {
+    let sensors = sensors();
+    let boiler_id = SensorId::from("00000000000000000000000000000001");
+    let pressure_id = MetricId::from("00000000000000000000000000000012");
+    let mut ui_state = UIState::default();
+    ui_state.current_sensor = Some((0, boiler_id));
+    ui_state.current_metric = Some((1, pressure_id));
+
+    let sensor_view = |ui_state: &UIState| match FrameModel::build(&sensors, ui_state, false).body {
+        Body::Sensors { sensor, .. } => sensor.expect("the selected sensor"),
+        body => panic!("Sensors expected, got {body:?}"),
+    };
+    // Cards by default
+    assert_eq!(sensor_view(&ui_state).table, None);
+
+    ui_state.metric_layout = MetricLayout::Table;
+    let table = sensor_view(&ui_state).table.expect("the metric table");
+    assert_eq!(table.sort, TableColumn::Name);
+    let names: Vec<&str> = table.rows.iter().map(|row| row.name.as_str()).collect();
+    assert_eq!(names, ["Pressure", "Temperature"]);
+    assert!(table.rows[0].selected);
+    assert_eq!(table.rows[0].kind, "Double");
+    assert_eq!(table.rows[0].annotation, "unit");
+    assert_eq!(table.rows[0].last_value, None);
+
+    // Metrics with livedata come first, the latest first
+    let mut window = StringLivedataWindow::default();
+    window.values.push_back((Timestamp::from_millis(1_000), String::from("12.5")));
+    ui_state
+        .string_livedata
+        .insert((boiler_id, MetricId::from("00000000000000000000000000000011")), window);
+    ui_state.table_sort = TableColumn::LastUpdate;
+    let table = sensor_view(&ui_state).table.expect("the metric table");
+    let rows: Vec<(&str, Option<&str>)> = table
+        .rows
+        .iter()
+        .map(|row| (row.name.as_str(), row.last_value.as_deref()))
+        .collect();
+    assert_eq!(rows, [("Temperature", Some("12.5")), ("Pressure", None)]);
+    assert_eq!(table.rows[0].updated_at, Some(Timestamp::from_millis(1_000)));
+}
+
+#[test]
+fn overview_of_all_sensors() {
+    let sensors = sensors();
+    let boiler_id = SensorId::from("00000000000000000000000000000001");
+    let temperature_id = MetricId::from("00000000000000000000000000000011");
+    let mut ui_state = UIState::default();
+    ui_state.overview = true;
+    ui_state.current_sensor = Some((0, boiler_id));
+    let mut window = MetricLivedataWindow::default();
+    window.data = vec![(1.0, 20.0), (2.0, 25.0), (3.0, 30.0)];
+    ui_state.livedata.insert((boiler_id, temperature_id), window);
+
+    let Body::Overview { rows } = FrameModel::build(&sensors, &ui_state, false).body else {
+        panic!("Overview expected");
+    };
+    let names: Vec<&str> = rows.iter().map(|row| row.name.as_str()).collect();
+    assert_eq!(names, ["Boiler", "Garage"]);
+    assert!(rows[0].selected && !rows[1].selected);
+    // Scaled from the lowest to the highest value
+    assert_eq!(rows[0].sparklines[0].points, [1, 51, 100]);
+    assert!(rows[0].sparklines[1].points.is_empty());
+
+    // Filtered like the tabs
+    ui_state.filter.query = String::from("humid");
+    let Body::Overview { rows } = FrameModel::build(&sensors, &ui_state, false).body else {
+        panic!("Overview expected");
+    };
+    assert_eq!(rows.len(), 1);
+    assert_eq!(rows[0].sparklines[0].name, "Humidity");
+}
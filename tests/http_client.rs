@@ -0,0 +1,57 @@
+use sensor_vision::http::{self, RESPONSE_SIZE_LIMIT};
+
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpListener;
+
+/// Serves one connection with the response, handing back the request head
+async fn serve_once(response: Vec<u8>) -> (String, tokio::task::JoinHandle<String>) {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let address = listener.local_addr().unwrap();
+    let served = tokio::spawn(async move {
+        let (mut stream, _) = listener.accept().await.unwrap();
+        let mut request = vec![0; 4096];
+        let read = stream.read(&mut request).await.unwrap();
+        // The client may be gone already once it's got enough
+        let _ = stream.write_all(&response).await;
+        String::from_utf8_lossy(&request[..read]).into_owned()
+    });
+    (format!("http://{address}"), served)
+}
+
+#[test]
+fn urls_are_parsed_with_their_query() {
+    let url = http::parse_url("http://sensors.local?station=1").unwrap();
+    assert_eq!(url.host_str(), Some("sensors.local"));
+    assert_eq!(url.port_or_known_default(), Some(80));
+    assert_eq!(url.path(), "/");
+    assert_eq!(url.query(), Some("station=1"));
+
+    assert!(http::parse_url("https://sensors.local/data").is_err());
+    assert!(http::parse_url("sensors.local:8080/data").is_err());
+}
+
+#[actix::test]
+async fn chunked_bodies_are_decoded() {
+    let response = b"HTTP/1.1 200 OK\r\nTransfer-Encoding: chunked\r\n\r\n4\r\n{\"a\"\r\n3\r\n:1}\r\n0\r\n\r\n";
+    let (url, served) = serve_once(response.to_vec()).await;
+    let url = http::parse_url(&format!("{url}?station=1")).unwrap();
+    let body = http::get(&url, &[("X-Token", "secret")]).await.unwrap();
+    assert_eq!(body, b"{\"a\":1}");
+
+    let request = served.await.unwrap();
+    assert!(request.starts_with("GET /?station=1 HTTP/1.1\r\n"), "{request}");
+    assert!(request.contains("\r\nX-Token: secret\r\n"), "{request}");
+}
+
+#[actix::test]
+async fn failed_and_oversized_responses_are_rejected() {
+    let (url, _) = serve_once(b"HTTP/1.1 503 Service Unavailable\r\n\r\n".to_vec()).await;
+    let err = http::post_json(&http::parse_url(&url).unwrap(), b"{}").await.unwrap_err();
+    assert_eq!(err.to_string(), "HTTP/1.1 503 Service Unavailable");
+
+    let mut response = b"HTTP/1.1 200 OK\r\n\r\n".to_vec();
+    response.resize(RESPONSE_SIZE_LIMIT + 1, b' ');
+    let (url, _) = serve_once(response).await;
+    let err = http::get(&http::parse_url(&url).unwrap(), &[]).await.unwrap_err();
+    assert!(err.to_string().contains("larger than"), "{err}");
+}
@@ -0,0 +1,52 @@
+use regex::Regex;
+
+use sensor_vision::config::Config;
+use sensor_vision::i18n::{Locale, Msg};
+
+use strum::{EnumProperty, IntoEnumIterator};
+
+fn placeholders(text: &str) -> Vec<String> {
+    let mut names: Vec<String> = Regex::new(r"\{(\w+)\}")
+        .unwrap()
+        .captures_iter(text)
+        .map(|captures| captures[1].to_owned())
+        .collect();
+    names.sort();
+    names
+}
+
+#[test]
+fn every_message_is_translated_with_the_same_placeholders() {
+    for msg in Msg::iter() {
+        let english = msg.text_in(Locale::En);
+        assert!(!english.is_empty(), "{msg:?} has no English text");
+        for locale in Locale::iter() {
+            let text = msg
+                .get_str(locale.code())
+                .unwrap_or_else(|| panic!("{msg:?} isn't translated to {locale:?}"));
+            assert_eq!(placeholders(text), placeholders(english), "{msg:?} in {locale:?}");
+        }
+    }
+}
+
+#[test]
+fn placeholders_are_filled_in() {
+    let args: &[(&str, &dyn std::fmt::Display)] = &[("sensor", &"Boiler"), ("count", &3)];
+    assert_eq!(
+        Msg::CloneSensorText.format_in(Locale::En, args),
+        "Clone Sensor Boiler with its 3 metric(s)?"
+    );
+    assert_eq!(
+        Msg::CloneSensorText.format_in(Locale::Ru, args),
+        "Скопировать датчик Boiler вместе с метриками (3)?"
+    );
+}
+
+#[test]
+fn locale_is_read_from_the_config() {
+    let config: Config = toml::from_str("[ui]\nlocale = \"ru\"\n").unwrap();
+    assert_eq!(config.ui.locale, Locale::Ru);
+    assert_eq!(Config::default().ui.locale, Locale::En);
+    assert_eq!("ru".parse::<Locale>().unwrap(), Locale::Ru);
+    assert!(toml::from_str::<Config>("[ui]\nlocale = \"xx\"\n").is_err());
+}
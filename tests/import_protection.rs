@@ -0,0 +1,155 @@
+use sensor_vision::client::state::{
+    parse_inventory, plan_import, protect_recent_livedata, summarize_import, unwatched_livedata, ImportStep,
+    LastLivedata, Sensors, SqliteStateStore, StateStore,
+};
+use sensor_vision::model::{MetricId, SensorId, Timestamp};
+use sensor_vision::testing::{metric, sensor, TempFile};
+
+use std::time::Duration;
+
+const GARAGE: &str = "00000000000000000000000000000001";
+const HUMIDITY: &str = "00000000000000000000000000000011";
+const BOILER: &str = "00000000000000000000000000000002";
+const TEMPERATURE: &str = "00000000000000000000000000000021";
+const PRESSURE: &str = "00000000000000000000000000000022";
+
+/// Garage with Humidity, Boiler Room with Temperature and Pressure
+fn sensors() -> Sensors {
+    [
+        sensor(GARAGE, "Garage", vec![metric(HUMIDITY, "Humidity")]),
+        sensor(
+            BOILER,
+            "Boiler Room",
+            vec![metric(TEMPERATURE, "Temperature"), metric(PRESSURE, "Pressure")],
+        ),
+    ]
+    .into_iter()
+    .map(|sensor| (sensor.sensor_id, sensor))
+    .collect()
+}
+
+fn livedata(entries: &[(&str, &str, u64)]) -> LastLivedata {
+    entries
+        .iter()
+        .map(|(sensor_id, metric_id, millis)| {
+            ((SensorId::from(*sensor_id), MetricId::from(*metric_id)), Timestamp::from_millis(*millis))
+        })
+        .collect()
+}
+
+/// Only the Boiler Room, with Temperature in Celsius, which takes recreating it
+fn pruning_plan() -> Vec<ImportStep> {
+    let desired = parse_inventory(
+        r#"[{"name": "Boiler Room", "metrics": [{"name": "Temperature", "valueUnit": "SI.Temperature.CELSIUS"}]}]"#,
+    )
+    .unwrap();
+    plan_import(&sensors(), &desired, true)
+}
+
+fn is_deletion(step: &ImportStep) -> bool {
+    matches!(step, ImportStep::DeleteSensor { .. } | ImportStep::DeleteMetric { .. })
+}
+
+#[test]
+fn stale_livedata_does_not_protect() {
+    let steps = pruning_plan();
+    let last_livedata = livedata(&[(GARAGE, HUMIDITY, 1_000), (BOILER, PRESSURE, 1_000)]);
+    let protected = protect_recent_livedata(steps.clone(), &last_livedata, Timestamp::from_millis(2_000), Duration::ZERO);
+    assert_eq!(protected, steps);
+}
+
+#[test]
+fn recent_livedata_keeps_sensors_and_metrics() {
+    let last_livedata = livedata(&[(GARAGE, HUMIDITY, 5_000), (BOILER, PRESSURE, 3_000)]);
+    let protected = protect_recent_livedata(pruning_plan(), &last_livedata, Timestamp::from_millis(2_000), Duration::ZERO);
+
+    // Temperature is recreated for its unit and received no livedata
+    let deletions: Vec<String> = protected.iter().filter(|step| is_deletion(step)).map(ToString::to_string).collect();
+    assert_eq!(deletions, ["- Boiler Room/Temperature"]);
+    let kept: Vec<(String, Timestamp)> = protected
+        .iter()
+        .filter_map(|step| match step {
+            ImportStep::Protected {
+                deletion,
+                last_livedata,
+            } => Some((deletion.to_string(), *last_livedata)),
+            _ => None,
+        })
+        .collect();
+    assert_eq!(kept.len(), 2);
+    assert!(kept.contains(&(String::from("- Garage"), Timestamp::from_millis(5_000))));
+    assert!(kept.contains(&(String::from("- Boiler Room/Pressure"), Timestamp::from_millis(3_000))));
+    assert!(summarize_import(&protected).ends_with("2 kept for their livedata"));
+}
+
+#[test]
+fn kept_metric_is_not_recreated() {
+    let last_livedata = livedata(&[(BOILER, TEMPERATURE, 5_000)]);
+    let protected = protect_recent_livedata(pruning_plan(), &last_livedata, Timestamp::from_millis(2_000), Duration::ZERO);
+
+    assert!(!protected.iter().any(|step| matches!(step, ImportStep::CreateMetrics { .. })));
+    assert!(protected.iter().any(|step| {
+        matches!(step, ImportStep::Protected { deletion, .. } if matches!(**deletion, ImportStep::DeleteMetric { metric_id, .. } if metric_id == MetricId::from(TEMPERATURE)))
+    }));
+    // The rest of the plan goes on
+    assert!(protected.iter().any(|step| matches!(step, ImportStep::DeleteSensor { .. })));
+}
+
+#[test]
+fn unwatched_window_keeps_everything() {
+    let last_livedata = livedata(&[(BOILER, PRESSURE, 3_000)]);
+    let unwatched = Duration::from_secs(600);
+    let protected = protect_recent_livedata(pruning_plan(), &last_livedata, Timestamp::from_millis(2_000), unwatched);
+
+    assert!(!protected.iter().any(is_deletion));
+    assert!(!protected.iter().any(|step| matches!(step, ImportStep::CreateMetrics { .. })));
+    let kept: Vec<String> = protected
+        .iter()
+        .filter(|step| matches!(step, ImportStep::Unwatched { .. }))
+        .map(ToString::to_string)
+        .collect();
+    assert_eq!(
+        kept,
+        ["! Boiler Room/Temperature kept, livedata unwatched for 600s", "! Garage kept, livedata unwatched for 600s"]
+    );
+    assert!(summarize_import(&protected).ends_with("3 kept for their livedata"));
+}
+
+#[test]
+fn livedata_history_outlives_the_store() {
+    let file = TempFile::new("outlives-history.sqlite");
+    let path = file.path();
+    {
+        let mut store = SqliteStateStore::with_history(path).unwrap();
+        store.record_livedata(&SensorId::from(BOILER), &MetricId::from(PRESSURE), Timestamp::from_millis(3_000)).unwrap();
+    }
+
+    let store = SqliteStateStore::with_history(path).unwrap();
+    assert_eq!(store.last_livedata().unwrap(), livedata(&[(BOILER, PRESSURE, 3_000)]));
+}
+
+#[test]
+fn the_whole_window_is_unwatched_without_history() {
+    let now = Timestamp::now();
+    let since = Timestamp::from_millis(now.as_millis() - 60_000);
+    let unwatched = unwatched_livedata(TempFile::new("missing-history.sqlite").path(), since, now).unwrap();
+    assert_eq!(unwatched, Duration::from_secs(60));
+}
+
+#[test]
+fn watches_cover_the_window() {
+    let file = TempFile::new("watches-history.sqlite");
+    let path = file.path();
+    // Taken before the store starts its watch, which leaves all of the window before it unwatched
+    let started = Timestamp::now();
+    let mut store = SqliteStateStore::with_history(path).unwrap();
+    let since = Timestamp::from_millis(started.as_millis() - 60_000);
+
+    let now = Timestamp::from_millis(started.as_millis() + 20_000);
+    store.record_watch(now).unwrap();
+    // Only the part of the window before the store started is left
+    let unwatched = unwatched_livedata(path, since, now).unwrap();
+    assert!(unwatched >= Duration::from_secs(60) && unwatched < Duration::from_secs(61), "{unwatched:?}");
+    // The store tells the same from its own watches
+    assert_eq!(store.unwatched_livedata(since, now).unwrap(), unwatched);
+}
@@ -0,0 +1,54 @@
+use sensor_vision::client::state::{search_inventory, Sensors};
+use sensor_vision::testing::{metric, sensor};
+
+/// Garage with Humidity, Boiler Room with Temperature and Pressure
+fn sensors() -> Sensors {
+    [
+        sensor(
+            "00000000000000000000000000000001",
+            "Garage",
+            vec![metric("00000000000000000000000000000011", "Humidity")],
+        ),
+        sensor(
+            "00000000000000000000000000000002",
+            "Boiler Room",
+            vec![
+                metric("00000000000000000000000000000021", "Temperature"),
+                metric("00000000000000000000000000000022", "Pressure"),
+            ],
+        ),
+    ]
+    .into_iter()
+    .map(|sensor| (sensor.sensor_id, sensor))
+    .collect()
+}
+
+#[test]
+fn substring_ignoring_case() {
+    let matched = search_inventory(&sensors(), "RE");
+    // Neither Garage nor Humidity contain "re"
+    let names: Vec<&str> = matched.iter().map(|sensor| sensor.sensor_name.as_str()).collect();
+    assert_eq!(names, ["Boiler Room"]);
+    assert!(!matched[0].sensor_matched);
+    let metrics: Vec<&str> = matched[0].metrics.iter().map(|metric| metric.name().as_str()).collect();
+    assert_eq!(metrics, ["Temperature", "Pressure"]);
+}
+
+#[test]
+fn sensor_name_match() {
+    let matched = search_inventory(&sensors(), "gar");
+    assert_eq!(matched.len(), 1);
+    assert_eq!(matched[0].sensor_name, "Garage");
+    assert!(matched[0].sensor_matched);
+    assert!(matched[0].metrics.is_empty());
+}
+
+#[test]
+fn wildcards_match_whole_names() {
+    let matched = search_inventory(&sensors(), "*ure");
+    let metrics: Vec<&str> = matched[0].metrics.iter().map(|metric| metric.name().as_str()).collect();
+    assert_eq!(metrics, ["Temperature", "Pressure"]);
+
+    assert!(search_inventory(&sensors(), "Boiler*").iter().all(|sensor| sensor.metrics.is_empty()));
+    assert!(search_inventory(&sensors(), "oil*").is_empty());
+}
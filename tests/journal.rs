@@ -0,0 +1,52 @@
+use actix::Actor;
+
+use eyre::Result;
+
+use sensor_vision::client::journal;
+use sensor_vision::client::state::queries::GetStateSnapshot;
+use sensor_vision::client::state::{MqttScheme, SensorsStateActor};
+use sensor_vision::model::sensor::{Metric, ValueUnit};
+use sensor_vision::testing::{connector_id, SensorStateHarness, TempFile};
+
+#[actix::test]
+async fn replay_reproduces_the_recorded_state() -> Result<()> {
+    let file = TempFile::new("replay.journal");
+    let path = file.path();
+    journal::open(path)?;
+
+    let mut harness = SensorStateHarness::start(connector_id()).await?;
+    let temperature = Metric::predefined(String::from("Temperature"), ValueUnit::Celsius);
+    let sensor_id = harness.agent().add_sensor("Boiler", vec![temperature]);
+    let metric_id = *harness.agent().sensors()[0].2[0].metric_id();
+
+    // Recorded the way the listener records what it receives
+    let mut replies = harness.request(MqttScheme::SensorList, "");
+    replies.extend(harness.request(MqttScheme::MetricDescribe(sensor_id, metric_id), ""));
+    for reply in replies {
+        journal::record(&reply);
+        harness.inject(reply).await?;
+    }
+
+    let entries = journal::read(path)?;
+    assert!(entries.iter().all(|entry| entry.topic.starts_with(&format!("/v1.0/{}/", connector_id()))));
+
+    let state = SensorsStateActor::new()?.start();
+    assert_eq!(journal::replay(&state, entries.clone()).await?, entries.len());
+
+    let recorded = serde_json::to_value(harness.snapshot().await?)?;
+    let replayed = serde_json::to_value(state.send(GetStateSnapshot).await??)?;
+    assert_eq!(replayed, recorded);
+
+    Ok(())
+}
+
+#[test]
+fn malformed_lines_are_pointed_at() -> Result<()> {
+    let file = TempFile::with_contents(
+        "malformed.journal",
+        "{\"received_at\":1,\"topic\":\"a\",\"payload\":\"{}\"}\n\n{\"topic\":\"b\"}\n",
+    );
+    let err = journal::read(file.path()).unwrap_err();
+    assert!(err.to_string().ends_with(":3: not a journal entry"), "{err}");
+    Ok(())
+}
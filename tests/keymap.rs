@@ -0,0 +1,85 @@
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+
+use std::collections::{BTreeMap, HashSet};
+
+use strum::IntoEnumIterator;
+
+use sensor_vision::config::{keymap_to_yaml, read_keymap};
+use sensor_vision::testing::TempFile;
+use sensor_vision::tui_app::keymap::{keymap, Action, Key, KeyBinding, KeymapPreset};
+
+fn keys(entries: &[(&str, &str)]) -> BTreeMap<String, String> {
+    entries
+        .iter()
+        .map(|(action, key)| (action.to_string(), key.to_string()))
+        .collect()
+}
+
+fn key_of(bindings: &[KeyBinding], action: Action) -> String {
+    bindings
+        .iter()
+        .find(|binding| binding.action == action)
+        .map(|binding| binding.key.to_string())
+        .unwrap()
+}
+
+#[test]
+fn presets_bind_every_action_to_a_key_of_its_own() {
+    for preset in KeymapPreset::iter() {
+        let bindings = preset.bindings();
+        let actions: HashSet<_> = bindings.iter().map(|binding| binding.action).collect();
+        assert_eq!(actions.len(), Action::iter().count(), "{preset:?}");
+        let keys: HashSet<_> = bindings.iter().map(|binding| binding.key).collect();
+        assert_eq!(keys.len(), bindings.len(), "{preset:?}");
+    }
+
+    let vim = KeymapPreset::Vim.bindings();
+    assert_eq!(key_of(&vim, Action::NextSensor), "j");
+    assert_eq!(key_of(&vim, Action::PrevMetric), "h");
+    let emacs = KeymapPreset::Emacs.bindings();
+    assert_eq!(key_of(&emacs, Action::NextSensor), "ctrl+n");
+    assert_eq!(key_of(&emacs, Action::PrevMetricPage), "alt+v");
+}
+
+#[test]
+fn keys_are_written_and_read_back() {
+    for text in ["ctrl+n", "alt+v", "N", "space", "backtab", "pagedown", "f5", "?"] {
+        assert_eq!(text.parse::<Key>().unwrap().to_string(), text);
+    }
+    assert_eq!("PageDown".parse::<Key>().unwrap(), Key::plain(KeyCode::PageDown));
+    assert_eq!("+".parse::<Key>().unwrap(), Key::plain(KeyCode::Char('+')));
+    assert!("hyper+x".parse::<Key>().is_err());
+    assert!("f13".parse::<Key>().is_err());
+
+    let ctrl_n = KeyEvent::new(KeyCode::Char('n'), KeyModifiers::CONTROL);
+    assert_eq!(Key::of(&ctrl_n), Key::ctrl('n'));
+    let shift_n = KeyEvent::new(KeyCode::Char('N'), KeyModifiers::SHIFT);
+    assert_eq!(Key::of(&shift_n), Key::plain(KeyCode::Char('N')));
+    assert_eq!(Key::ctrl('n').label(), "^n");
+}
+
+#[test]
+fn keymap_files_replace_the_keys_of_the_preset() {
+    let bindings = keymap(KeymapPreset::Vim, &keys(&[("quit", "ctrl+q"), ("help", "f1")])).unwrap();
+    assert_eq!(key_of(&bindings, Action::Quit), "ctrl+q");
+    assert_eq!(key_of(&bindings, Action::Help), "f1");
+    assert_eq!(key_of(&bindings, Action::NextSensor), "j");
+
+    let err = keymap(KeymapPreset::Default, &keys(&[("quit", "n")])).unwrap_err();
+    assert_eq!(err.to_string(), "n is bound to both create_sensor and quit");
+    let err = keymap(KeymapPreset::Default, &keys(&[("launch", "x")])).unwrap_err();
+    assert_eq!(err.to_string(), "Unknown action 'launch'");
+}
+
+#[test]
+fn exported_keymaps_are_read_back() {
+    let bindings = keymap(KeymapPreset::Emacs, &keys(&[("quit", "ctrl+q")])).unwrap();
+    let file = TempFile::with_contents("keymap.yaml", &keymap_to_yaml(&bindings).unwrap());
+
+    let exported = read_keymap(file.path()).unwrap();
+    assert_eq!(exported.len(), Action::iter().count());
+    let read_back = keymap(KeymapPreset::Default, &exported).unwrap();
+    for (binding, read_back) in bindings.iter().zip(&read_back) {
+        assert_eq!((binding.action, binding.key), (read_back.action, read_back.key));
+    }
+}
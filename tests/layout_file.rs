@@ -0,0 +1,30 @@
+use sensor_vision::config::{read_region_sizes, write_region_sizes};
+use sensor_vision::testing::TempFile;
+use sensor_vision::tui_app::ui_state::RegionSizes;
+
+#[test]
+fn region_sizes_are_kept_per_profile() {
+    let file = TempFile::new("profiles-layout.yaml");
+    let path = file.path();
+    assert_eq!(read_region_sizes(path, Some("dev")).unwrap(), RegionSizes::default());
+
+    let mut resized = RegionSizes::default();
+    resized.resize_log_pane(4);
+    resized.resize_overview_columns(-2);
+    write_region_sizes(path, Some("dev"), &resized).unwrap();
+    write_region_sizes(path, None, &RegionSizes::default()).unwrap();
+
+    let restored = read_region_sizes(path, Some("dev")).unwrap();
+    assert_eq!(restored, resized);
+    assert_eq!(restored.log_pane_height(), 7);
+    assert_eq!(restored.overview_column_width(), 16);
+    assert_eq!(read_region_sizes(path, None).unwrap(), RegionSizes::default());
+}
+
+#[test]
+fn edited_sizes_are_pulled_into_range() {
+    let file = TempFile::with_contents("edited-layout.yaml", "dev:\n  log_pane_height: 100\n");
+    let restored = read_region_sizes(file.path(), Some("dev")).unwrap();
+    assert_eq!(restored.log_pane_height(), 10);
+    assert_eq!(restored.overview_column_width(), RegionSizes::default().overview_column_width());
+}
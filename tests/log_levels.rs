@@ -0,0 +1,47 @@
+use sensor_vision::config::{write_log_levels, Config};
+use sensor_vision::logging::Subsystem;
+use sensor_vision::testing::TempFile;
+
+use log::LevelFilter;
+
+use std::collections::BTreeMap;
+
+fn levels(levels: &[(&str, &str)]) -> BTreeMap<String, String> {
+    levels.iter().map(|(name, level)| (name.to_string(), level.to_string())).collect()
+}
+
+#[test]
+fn levels_are_written_next_to_the_rest_of_the_config() {
+    let file = TempFile::with_contents(
+        "levels-config.toml",
+        "# Kept as written\n[log]\nlevel = \"info\"\n\n[log.levels]\nui = \"trace\"\n",
+    );
+    let path = file.path();
+    write_log_levels(path, &levels(&[("mqtt", "warn"), ("state", "debug")])).unwrap();
+
+    let contents = std::fs::read_to_string(path).unwrap();
+    assert!(contents.starts_with("# Kept as written\n"), "{contents}");
+    assert!(!contents.contains("ui ="), "{contents}");
+
+    let config = Config::load(Some(path), None).unwrap();
+    assert_eq!(config.log.level.as_deref(), Some("info"));
+    assert_eq!(
+        config.log.levels().unwrap(),
+        vec![(Subsystem::Mqtt, LevelFilter::Warn), (Subsystem::State, LevelFilter::Debug)]
+    );
+}
+
+#[test]
+fn levels_are_written_to_a_new_config() {
+    let file = TempFile::new("new-config.toml");
+    write_log_levels(file.path(), &levels(&[("other", "error")])).unwrap();
+    let config = Config::load(Some(file.path()), None).unwrap();
+    assert_eq!(config.log.levels().unwrap(), vec![(Subsystem::Other, LevelFilter::Error)]);
+}
+
+#[test]
+fn unknown_subsystems_are_rejected() {
+    let file = TempFile::with_contents("unknown-levels.toml", "[log.levels]\nbroker = \"info\"\n");
+    let config = Config::load(Some(file.path()), None).unwrap();
+    assert!(config.log.levels().is_err());
+}
@@ -0,0 +1,88 @@
+use chrono::{NaiveDate, NaiveDateTime};
+
+use sensor_vision::client::state::MaintenanceWindow;
+
+fn window(schedule: &str, duration: &str) -> MaintenanceWindow {
+    MaintenanceWindow {
+        schedule: String::from(schedule),
+        duration: String::from(duration),
+        sensor: String::from("*"),
+        metric: String::from("*"),
+    }
+}
+
+/// 2024-06-02 is a Sunday
+fn at(day: u32, hour: u32, minute: u32) -> NaiveDateTime {
+    NaiveDate::from_ymd_opt(2024, 6, day).unwrap().and_hms_opt(hour, minute, 0).unwrap()
+}
+
+#[test]
+fn windows_last_their_duration_from_every_start() {
+    let window = window("0 2 * * SUN", "2h");
+    assert!(!window.is_active(at(2, 1, 59)).unwrap());
+    assert!(window.is_active(at(2, 2, 0)).unwrap());
+    assert!(window.is_active(at(2, 3, 59)).unwrap());
+    assert!(!window.is_active(at(2, 4, 0)).unwrap());
+    // Monday
+    assert!(!window.is_active(at(3, 2, 30)).unwrap());
+    assert!(window.is_active(at(9, 2, 30)).unwrap());
+}
+
+#[test]
+fn windows_may_span_midnight() {
+    let window = window("30 23 * * 6", "1h");
+    assert!(window.is_active(at(1, 23, 45)).unwrap());
+    assert!(window.is_active(at(2, 0, 15)).unwrap());
+    assert!(!window.is_active(at(2, 0, 30)).unwrap());
+}
+
+#[test]
+fn both_0_and_7_stand_for_sunday() {
+    for schedule in ["0 2 * * 0", "0 2 * * 7", "0 2 * * sun"] {
+        assert!(window(schedule, "1h").is_active(at(2, 2, 0)).unwrap(), "{schedule}");
+    }
+}
+
+#[test]
+fn lists_ranges_and_steps_are_understood() {
+    let window = window("*/15 8-17 * JAN,JUN MON-FRI", "1m");
+    assert!(window.is_active(at(3, 8, 45)).unwrap());
+    assert!(!window.is_active(at(3, 8, 46)).unwrap());
+    assert!(!window.is_active(at(3, 18, 0)).unwrap());
+    assert!(!window.is_active(at(2, 8, 45)).unwrap());
+}
+
+#[test]
+fn either_day_or_weekday_will_do_once_both_are_restricted() {
+    // The 1st of the month or any Sunday, as in cron
+    let window = window("0 0 1 * SUN", "1m");
+    assert!(window.is_active(at(1, 0, 0)).unwrap());
+    assert!(window.is_active(at(2, 0, 0)).unwrap());
+    assert!(!window.is_active(at(3, 0, 0)).unwrap());
+}
+
+#[test]
+fn malformed_windows_are_rejected() {
+    for (schedule, duration) in [
+        ("0 2 * *", "1h"),
+        ("60 2 * * *", "1h"),
+        ("0 2 * * FUNDAY", "1h"),
+        ("0 5-2 * * *", "1h"),
+        ("*/0 2 * * *", "1h"),
+        ("0 2 * * *", "0m"),
+        ("0 2 * * *", "8d"),
+        ("0 2 * * *", "soon"),
+    ] {
+        assert!(window(schedule, duration).validate().is_err(), "{schedule} for {duration}");
+    }
+}
+
+#[test]
+fn windows_apply_to_matching_metrics() {
+    let window = MaintenanceWindow {
+        sensor: String::from("Boiler*"),
+        ..window("0 2 * * *", "1h")
+    };
+    assert!(window.applies_to("Boiler Room", "Temperature"));
+    assert!(!window.applies_to("Garage", "Temperature"));
+}
@@ -0,0 +1,62 @@
+use sensor_vision::model::sensor::{Metric, Sensor, SensorState, ValueType, ValueUnit};
+use sensor_vision::model::{MetricId, SensorId};
+
+fn metric(metric_id: &str, name: &str) -> Metric {
+    Metric::Custom {
+        name: String::from(name),
+        metric_id: MetricId::from(metric_id),
+        value_annotation: String::from("unit"),
+        value_type: ValueType::Double,
+    }
+}
+
+/// Boiler with Temperature and Temperature (2)
+fn boiler() -> Sensor<Metric> {
+    Sensor {
+        name: String::from("Boiler"),
+        sensor_id: SensorId::from("00000000000000000000000000000001"),
+        metrics: [
+            metric("00000000000000000000000000000011", "Temperature"),
+            metric("00000000000000000000000000000012", "Temperature (2)"),
+        ]
+        .into_iter()
+        .collect(),
+        state: SensorState::Enabled,
+        connector_id: Default::default(),
+    }
+}
+
+fn names(metrics: &[Metric]) -> Vec<&str> {
+    metrics.iter().map(|metric| metric.name().as_str()).collect()
+}
+
+#[test]
+fn no_collisions() {
+    let mut new_metrics = vec![
+        Metric::predefined(String::from("Pressure"), ValueUnit::Pascal),
+        Metric::custom(String::from("Flow"), ValueType::Integer, String::from("l/min")),
+    ];
+    assert!(boiler().colliding_names(&new_metrics).is_empty());
+    assert!(boiler().suffix_colliding(&mut new_metrics).is_empty());
+    assert_eq!(names(&new_metrics), ["Pressure", "Flow"]);
+}
+
+#[test]
+fn collisions_with_existing_and_new_metrics() {
+    let mut new_metrics = vec![
+        Metric::predefined(String::from("Temperature"), ValueUnit::Celsius),
+        Metric::predefined(String::from("Pressure"), ValueUnit::Pascal),
+        Metric::predefined(String::from("Pressure"), ValueUnit::Pascal),
+        Metric::predefined(String::from("Pressure"), ValueUnit::Pascal),
+    ];
+    assert_eq!(boiler().colliding_names(&new_metrics), ["Temperature", "Pressure"]);
+
+    let renamed = boiler().suffix_colliding(&mut new_metrics);
+    // Taken suffixes are skipped
+    assert_eq!(
+        names(&new_metrics),
+        ["Temperature (3)", "Pressure", "Pressure (2)", "Pressure (3)"]
+    );
+    assert_eq!(renamed.len(), 3);
+    assert_eq!(renamed[0], (String::from("Temperature"), String::from("Temperature (3)")));
+}
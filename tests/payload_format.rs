@@ -0,0 +1,53 @@
+use serde_json::{json, Value};
+
+use sensor_vision::config::LogConfig;
+use sensor_vision::logging::PayloadFormat;
+
+const LIVEDATA: &str = r#"{"metrics":[{"metricId":"c0ffee","value":"Jane Doe, 42 Elm St"},{"metricId":"cafe","value":21.5}],"timestamp":1}"#;
+
+fn format(configure: impl FnOnce(&mut LogConfig)) -> PayloadFormat {
+    let mut config = LogConfig::default();
+    configure(&mut config);
+    PayloadFormat::from_config(&config)
+}
+
+#[test]
+fn payloads_are_left_alone_by_default() {
+    let format = format(|_| {});
+    assert_eq!(format.apply(LIVEDATA), LIVEDATA);
+    assert_eq!(format.apply("not json"), "not json");
+}
+
+#[test]
+fn string_values_of_redacted_fields_are_replaced() {
+    let format = format(|config| config.redact = vec![String::from("value")]);
+    let redacted: Value = serde_json::from_str(&format.apply(LIVEDATA)).unwrap();
+    assert_eq!(
+        redacted["metrics"],
+        json!([
+            {"metricId": "c0ffee", "value": "<redacted>"},
+            // Numbers carry nothing personal
+            {"metricId": "cafe", "value": 21.5},
+        ])
+    );
+}
+
+#[test]
+fn long_strings_are_cut() {
+    let format = format(|config| config.max_value_len = 4);
+    let cut: Value = serde_json::from_str(&format.apply(LIVEDATA)).unwrap();
+    assert_eq!(cut["metrics"][0]["value"], "Jane…(+15)");
+    assert_eq!(cut["metrics"][1]["metricId"], "cafe");
+    assert_eq!(format.apply("plain text"), "plai…(+6)");
+}
+
+#[test]
+fn pretty_payloads_span_lines() {
+    let format = format(|config| config.pretty_payloads = true);
+    let pretty = format.apply(LIVEDATA);
+    assert!(pretty.lines().count() > 1);
+    assert_eq!(
+        serde_json::from_str::<Value>(&pretty).unwrap(),
+        serde_json::from_str::<Value>(LIVEDATA).unwrap()
+    );
+}
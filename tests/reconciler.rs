@@ -0,0 +1,31 @@
+use sensor_vision::client::reconciler::Reconciler;
+use sensor_vision::model::SensorId;
+
+const BOILER: &str = "00000000000000000000000000000001";
+const PUMP: &str = "00000000000000000000000000000002";
+
+#[test]
+fn only_changed_sensors_are_described() {
+    let mut reconciler = Reconciler::default();
+    let (boiler, pump) = (SensorId::from(BOILER), SensorId::from(PUMP));
+    // A burst of updates of the same sensor
+    reconciler.mark_dirty(boiler);
+    reconciler.mark_dirty(boiler);
+    assert_eq!(reconciler.dirty_count(), 1);
+
+    assert!(!reconciler.take_dirty(pump));
+    assert!(reconciler.take_dirty(boiler));
+    // Described once per list
+    assert!(!reconciler.take_dirty(boiler));
+}
+
+#[test]
+fn full_reload_and_deletion() {
+    let mut reconciler = Reconciler::default();
+    let (boiler, pump) = (SensorId::from(BOILER), SensorId::from(PUMP));
+    reconciler.mark_all_dirty([boiler, pump]);
+    reconciler.forget(pump);
+    assert_eq!(reconciler.dirty_count(), 1);
+    assert!(reconciler.take_dirty(boiler));
+    assert_eq!(reconciler.dirty_count(), 0);
+}
@@ -0,0 +1,52 @@
+use serde_json::{json, Value};
+
+use sensor_vision::schema::SchemaKind;
+
+use strum::IntoEnumIterator;
+
+fn schema(name: &str) -> Value {
+    let kind: SchemaKind = name.parse().unwrap();
+    serde_json::to_value(kind.schema()).unwrap()
+}
+
+#[test]
+fn sensor_keeps_the_wire_names() {
+    let sensor = schema("sensor");
+    let properties = sensor["properties"].as_object().unwrap();
+    let mut names: Vec<&str> = properties.keys().map(String::as_str).collect();
+    names.sort();
+    assert_eq!(names, ["metrics", "name", "sensorId", "state"]);
+    assert_eq!(sensor["required"], json!(["name", "sensorId"]));
+    assert_eq!(properties["name"]["minLength"], 2);
+    assert_eq!(sensor["definitions"]["SensorState"]["enum"], json!([1, 0]));
+}
+
+#[test]
+fn metric_is_either_kind() {
+    let metric = schema("metric");
+    assert_eq!(metric["anyOf"].as_array().unwrap().len(), 2);
+    let units = metric["definitions"]["ValueUnit"]["enum"].as_array().unwrap();
+    assert!(units.contains(&json!("SI.Temperature.CELSIUS")));
+}
+
+#[test]
+fn config_and_alert_rules_reject_unknown_fields() {
+    let config = schema("config");
+    assert_eq!(config["additionalProperties"], false);
+    assert_eq!(config["definitions"]["BrokerConfig"]["properties"]["port"]["default"], 18884);
+
+    let alerts = schema("alerts");
+    assert_eq!(alerts["type"], "array");
+    let rule = &alerts["definitions"]["AlertRule"];
+    assert_eq!(rule["additionalProperties"], false);
+    assert_eq!(rule["required"], json!(["metric", "sensor"]));
+}
+
+#[test]
+fn every_kind_is_named() {
+    for kind in SchemaKind::iter() {
+        assert_eq!(kind.name().parse::<SchemaKind>().unwrap(), kind);
+    }
+    let err = "sensors".parse::<SchemaKind>().unwrap_err();
+    assert!(err.to_string().contains("sensor, metric, inventory, alerts, config"), "{err}");
+}
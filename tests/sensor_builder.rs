@@ -0,0 +1,47 @@
+use sensor_vision::model::sensor::{Metric, Sensor, SensorState, ValueType, ValueUnit};
+
+#[test]
+fn builds_a_disabled_sensor_with_metrics() {
+    let sensor = Sensor::builder()
+        .name("Boiler Room")
+        .state(SensorState::Disabled)
+        .predefined("Temperature", ValueUnit::Celsius)
+        .custom("Status", ValueType::Integer, "code")
+        .build()
+        .unwrap();
+    assert_eq!(sensor.name, "Boiler Room");
+    assert_eq!(sensor.state, SensorState::Disabled);
+    let names: Vec<&String> = sensor.metrics.iter().map(Metric::name).collect();
+    assert_eq!(names, ["Temperature", "Status"]);
+}
+
+#[test]
+fn invalid_names_and_annotations_are_rejected() {
+    let err = Sensor::builder().name("B").build().unwrap_err();
+    assert!(err.to_string().starts_with("Invalid sensor 'B'"), "{err}");
+
+    let err = Sensor::builder()
+        .name("Boiler Room")
+        .custom("Status", ValueType::Integer, "code!")
+        .build()
+        .unwrap_err();
+    assert!(err.to_string().starts_with("Invalid metric 'Status'"), "{err}");
+}
+
+#[test]
+fn duplicate_and_attached_metrics_are_rejected() {
+    let err = Sensor::builder()
+        .name("Boiler Room")
+        .predefined("Temperature", ValueUnit::Celsius)
+        .predefined("Temperature", ValueUnit::Percent)
+        .build()
+        .unwrap_err();
+    assert_eq!(err.to_string(), "Metric Temperature is added twice");
+
+    let attached: Metric = serde_json::from_str(
+        r#"{"name": "Temperature", "metricId": "6d69c58223fb44a7b76ae61a18faf37c", "valueUnit": "SI.Temperature.CELSIUS"}"#,
+    )
+    .unwrap();
+    let err = Sensor::builder().name("Boiler Room").metric(attached).build().unwrap_err();
+    assert!(err.to_string().starts_with("Metric Temperature already has id"), "{err}");
+}
@@ -0,0 +1,37 @@
+use sensor_vision::config::{read_muted_sensors, write_muted_sensors};
+use sensor_vision::model::SensorId;
+use sensor_vision::testing::TempFile;
+
+use std::collections::BTreeSet;
+
+const BOILER: &str = "00000000000000000000000000000001";
+const PUMP: &str = "00000000000000000000000000000002";
+
+fn sensors(ids: &[&str]) -> BTreeSet<SensorId> {
+    ids.iter().map(|id| SensorId::from(*id)).collect()
+}
+
+#[test]
+fn nothing_is_muted_without_the_file() {
+    let file = TempFile::new("missing-mutes.yaml");
+    let path = file.path();
+    assert!(read_muted_sensors(path, None).unwrap().is_empty());
+    assert!(read_muted_sensors(path, Some("dev")).unwrap().is_empty());
+}
+
+#[test]
+fn mutes_are_kept_per_profile() {
+    let file = TempFile::new("profiles-mutes.yaml");
+    let path = file.path();
+    write_muted_sensors(path, Some("dev"), &sensors(&[BOILER, PUMP])).unwrap();
+    write_muted_sensors(path, None, &sensors(&[PUMP])).unwrap();
+
+    assert_eq!(read_muted_sensors(path, Some("dev")).unwrap(), sensors(&[BOILER, PUMP]));
+    assert_eq!(read_muted_sensors(path, None).unwrap(), sensors(&[PUMP]));
+    assert!(read_muted_sensors(path, Some("prod")).unwrap().is_empty());
+
+    write_muted_sensors(path, Some("dev"), &BTreeSet::new()).unwrap();
+    assert!(read_muted_sensors(path, Some("dev")).unwrap().is_empty());
+    assert_eq!(read_muted_sensors(path, None).unwrap(), sensors(&[PUMP]));
+    assert!(!std::fs::read_to_string(path).unwrap().contains("dev"));
+}
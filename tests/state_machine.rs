@@ -0,0 +1,541 @@
+use eyre::Result;
+
+use serde_json::json;
+
+use sensor_vision::client::state::{AlertRule, MqttScheme, SensorStateEvent};
+use sensor_vision::model::protocol::MetricValue;
+use sensor_vision::model::sensor::{Metric, SensorState, ValueType, ValueUnit};
+use sensor_vision::model::{MetricId, SensorId, Timestamp};
+use sensor_vision::testing::{connector_id, SensorStateHarness, TempFile};
+
+fn temperature() -> Metric {
+    Metric::predefined(String::from("Temperature"), ValueUnit::Celsius)
+}
+
+fn status() -> Metric {
+    Metric::Custom {
+        name: String::from("Status"),
+        metric_id: MetricId::default(),
+        value_annotation: String::from("code"),
+        value_type: ValueType::Integer,
+    }
+}
+
+/// Lists the sensors and describes their metrics, the way the client loads the inventory
+async fn load(harness: &mut SensorStateHarness) -> Result<()> {
+    harness.exchange(MqttScheme::SensorList, "").await?;
+    for (sensor_id, _, metrics) in harness.agent().sensors() {
+        for metric in metrics {
+            let describe = MqttScheme::MetricDescribe(sensor_id, *metric.metric_id());
+            harness.exchange(describe, "").await?;
+        }
+    }
+    Ok(())
+}
+
+fn metric_id(harness: &mut SensorStateHarness, sensor_id: SensorId, name: &str) -> MetricId {
+    harness
+        .agent()
+        .sensors()
+        .into_iter()
+        .find(|(id, ..)| *id == sensor_id)
+        .and_then(|(_, _, metrics)| metrics.into_iter().find(|metric| metric.name() == name))
+        .map(|metric| *metric.metric_id())
+        .expect("metric of the agent")
+}
+
+#[actix::test]
+async fn loading_the_inventory() -> Result<()> {
+    let mut harness = SensorStateHarness::start(connector_id()).await?;
+    let sensor_id = harness.agent().add_sensor("Boiler", vec![temperature(), status()]);
+
+    load(&mut harness).await?;
+
+    let events = harness.take_events().await?;
+    assert!(matches!(&events[0], SensorStateEvent::NewLinkedSensorLoaded(sensor) if sensor.sensor_id == sensor_id));
+    let loaded: Vec<&str> = events[1..]
+        .iter()
+        .map(|event| match event {
+            SensorStateEvent::NewMetricLoaded { metric, .. } => metric.name().as_str(),
+            event => panic!("Unexpected {event:?}"),
+        })
+        .collect();
+    assert_eq!(loaded, ["Temperature", "Status"]);
+
+    let sensors = harness.snapshot().await?;
+    let names: Vec<&String> = sensors[&sensor_id].metrics.iter().map(Metric::name).collect();
+    assert_eq!(names, ["Temperature", "Status"]);
+
+    // Reloading an unchanged inventory changes nothing
+    harness.exchange(MqttScheme::SensorList, "").await?;
+    let events = harness.take_events().await?;
+    assert_eq!(events.len(), 1);
+    assert!(matches!(&events[0], SensorStateEvent::ExistingLinkedSensorLoaded(_)));
+    assert_eq!(harness.snapshot().await?, sensors);
+    Ok(())
+}
+
+#[actix::test]
+async fn created_sensor() -> Result<()> {
+    let mut harness = SensorStateHarness::start(connector_id()).await?;
+
+    let payload = json!({"name": "Boiler"}).to_string();
+    harness.exchange(MqttScheme::SensorCreate, &payload).await?;
+
+    let events = harness.take_events().await?;
+    let [SensorStateEvent::NewSensorCreated(sensor)] = events.as_slice() else {
+        panic!("Unexpected {events:?}");
+    };
+    assert_eq!(sensor.name, "Boiler");
+    assert!(harness.snapshot().await?.contains_key(&sensor.sensor_id));
+    Ok(())
+}
+
+#[actix::test]
+async fn renamed_and_disabled_sensor() -> Result<()> {
+    let mut harness = SensorStateHarness::start(connector_id()).await?;
+    let sensor_id = harness.agent().add_sensor("Boiler", Vec::new());
+    load(&mut harness).await?;
+    harness.take_events().await?;
+
+    let payload = json!({"name": "Furnace", "state": 0}).to_string();
+    harness.exchange(MqttScheme::SensorUpdate(sensor_id), &payload).await?;
+    // The reply tells nothing about the change, it's only known once the sensors are listed
+    let events = harness.take_events().await?;
+    assert!(matches!(events.as_slice(), [SensorStateEvent::SensorUpdated { .. }]));
+    assert_eq!(harness.snapshot().await?[&sensor_id].name, "Boiler");
+
+    harness.exchange(MqttScheme::SensorList, "").await?;
+    let events = harness.take_events().await?;
+    assert!(matches!(
+        events.as_slice(),
+        [
+            SensorStateEvent::SensorNameChanged { name, .. },
+            SensorStateEvent::SensorStateChanged {
+                state: SensorState::Disabled,
+                ..
+            },
+            SensorStateEvent::ExistingLinkedSensorLoaded(_),
+        ] if name == "Furnace"
+    ));
+    let sensor = &harness.snapshot().await?[&sensor_id];
+    assert_eq!((sensor.name.as_str(), sensor.state), ("Furnace", SensorState::Disabled));
+    Ok(())
+}
+
+#[actix::test]
+async fn deleted_sensor_and_metric() -> Result<()> {
+    let mut harness = SensorStateHarness::start(connector_id()).await?;
+    let boiler = harness.agent().add_sensor("Boiler", vec![temperature(), status()]);
+    let pump = harness.agent().add_sensor("Pump", Vec::new());
+    load(&mut harness).await?;
+    harness.take_events().await?;
+
+    let status_id = metric_id(&mut harness, boiler, "Status");
+    let payload = json!({"metrics": [{"metricId": status_id}]}).to_string();
+    harness.exchange(MqttScheme::MetricDelete(boiler), &payload).await?;
+    harness.exchange(MqttScheme::SensorDelete(pump), "").await?;
+    harness.exchange(MqttScheme::SensorList, "").await?;
+
+    let events = harness.take_events().await?;
+    assert!(events.iter().any(|event| matches!(
+        event,
+        SensorStateEvent::SensorDeleted { sensor_id } if *sensor_id == pump
+    )));
+    assert!(events.iter().any(|event| matches!(
+        event,
+        SensorStateEvent::MetricDeleted { metric_id, .. } if *metric_id == status_id
+    )));
+
+    let sensors = harness.snapshot().await?;
+    assert!(!sensors.contains_key(&pump));
+    let names: Vec<&String> = sensors[&boiler].metrics.iter().map(Metric::name).collect();
+    assert_eq!(names, ["Temperature"]);
+    Ok(())
+}
+
+#[actix::test]
+async fn livedata_keeps_its_order() -> Result<()> {
+    let mut harness = SensorStateHarness::start(connector_id()).await?;
+    let sensor_id = harness.agent().add_sensor("Boiler", vec![temperature(), status()]);
+    load(&mut harness).await?;
+    harness.take_events().await?;
+
+    let temperature_id = metric_id(&mut harness, sensor_id, "Temperature");
+    let status_id = metric_id(&mut harness, sensor_id, "Status");
+    let timestamp = Timestamp::now();
+    harness
+        .livedata(
+            sensor_id,
+            &[(status_id, MetricValue::Integer(3)), (temperature_id, MetricValue::Double(21.5))],
+            timestamp,
+        )
+        .await?;
+    harness
+        .livedata(sensor_id, &[(temperature_id, MetricValue::Double(22.0))], timestamp)
+        .await?;
+
+    let values: Vec<(MetricId, MetricValue)> = harness
+        .take_events()
+        .await?
+        .into_iter()
+        .map(|event| match event {
+            SensorStateEvent::Livedata {
+                metric_id, value, ..
+            } => (metric_id, value),
+            event => panic!("Unexpected {event:?}"),
+        })
+        .collect();
+    assert_eq!(
+        values,
+        [
+            (status_id, MetricValue::Integer(3)),
+            (temperature_id, MetricValue::Double(21.5)),
+            (temperature_id, MetricValue::Double(22.0)),
+        ]
+    );
+    Ok(())
+}
+
+#[actix::test]
+async fn describe_reply_overtaking_the_sensor_list() -> Result<()> {
+    let mut harness = SensorStateHarness::start(connector_id()).await?;
+    let sensor_id = harness.agent().add_sensor("Boiler", vec![temperature()]);
+    let temperature_id = metric_id(&mut harness, sensor_id, "Temperature");
+
+    let list = harness.request(MqttScheme::SensorList, "");
+    let describe = harness.request(MqttScheme::MetricDescribe(sensor_id, temperature_id), "");
+    for reply in describe.iter().chain(&list) {
+        harness.inject(reply.clone()).await?;
+    }
+
+    // The metric of a sensor not known yet is held back until the sensor is listed
+    let events = harness.take_events().await?;
+    assert!(matches!(
+        events.as_slice(),
+        [
+            SensorStateEvent::NewLinkedSensorLoaded(_),
+            SensorStateEvent::NewMetricLoaded { metric, .. },
+        ] if *metric.metric_id() == temperature_id
+    ));
+    assert_eq!(harness.snapshot().await?[&sensor_id].metrics.len(), 1);
+
+    // and the next describe changes nothing
+    for reply in describe {
+        harness.inject(reply).await?;
+    }
+    assert!(harness.take_events().await?.is_empty());
+    Ok(())
+}
+
+#[actix::test]
+async fn livedata_records_when_it_was_received() -> Result<()> {
+    let mut harness = SensorStateHarness::start(connector_id()).await?;
+    let sensor_id = harness.agent().add_sensor("Boiler", vec![temperature()]);
+    load(&mut harness).await?;
+    harness.take_events().await?;
+
+    let temperature_id = metric_id(&mut harness, sensor_id, "Temperature");
+    // Sent by a device lagging 5 seconds behind
+    let timestamp = Timestamp::from_millis(Timestamp::now().as_millis() - 5000);
+    harness
+        .livedata(sensor_id, &[(temperature_id, MetricValue::Double(21.5))], timestamp)
+        .await?;
+    let events = harness.take_events().await?;
+    let [SensorStateEvent::Livedata {
+        timestamp: sent_at,
+        received_at,
+        ..
+    }] = events.as_slice()
+    else {
+        panic!("Unexpected {events:?}");
+    };
+    assert_eq!(*sent_at, timestamp);
+    assert!(received_at.as_millis() - sent_at.as_millis() >= 5000);
+    Ok(())
+}
+
+#[actix::test]
+async fn livedata_overtaking_the_sensor_list() -> Result<()> {
+    let mut harness = SensorStateHarness::start(connector_id()).await?;
+    let sensor_id = harness.agent().add_sensor("Boiler", vec![temperature()]);
+    let temperature_id = metric_id(&mut harness, sensor_id, "Temperature");
+
+    harness
+        .livedata(sensor_id, &[(temperature_id, MetricValue::Double(21.5))], Timestamp::now())
+        .await?;
+    assert!(harness.take_events().await?.is_empty());
+
+    harness.exchange(MqttScheme::SensorList, "").await?;
+    let events = harness.take_events().await?;
+    assert!(matches!(
+        events.as_slice(),
+        [
+            SensorStateEvent::NewLinkedSensorLoaded(_),
+            SensorStateEvent::Livedata { value: MetricValue::Double(value), .. },
+        ] if *value == 21.5
+    ));
+    Ok(())
+}
+
+#[actix::test]
+async fn update_replies_out_of_order() -> Result<()> {
+    let mut harness = SensorStateHarness::start(connector_id()).await?;
+    let sensor_id = harness.agent().add_sensor("Boiler", vec![status()]);
+    load(&mut harness).await?;
+    harness.take_events().await?;
+
+    let status_id = metric_id(&mut harness, sensor_id, "Status");
+    let rename = |name: &str| json!({"metrics": [{"metricId": status_id, "name": name}]}).to_string();
+    let first = harness.request(MqttScheme::MetricUpdate(sensor_id), &rename("State"));
+    let second = harness.request(MqttScheme::MetricUpdate(sensor_id), &rename("Mode"));
+    let describe = harness.request(MqttScheme::MetricDescribe(sensor_id, status_id), "");
+    for reply in second.into_iter().chain(first) {
+        harness.inject(reply).await?;
+    }
+
+    // Nothing is taken from the replies themselves, the metric is as the agent describes it
+    let events = harness.take_events().await?;
+    assert_eq!(events.len(), 2);
+    assert!(events
+        .iter()
+        .all(|event| matches!(event, SensorStateEvent::SensorMetricsUpdated { .. })));
+    for reply in describe {
+        harness.inject(reply).await?;
+    }
+    let events = harness.take_events().await?;
+    assert!(matches!(
+        events.as_slice(),
+        [SensorStateEvent::MetricNameChanged { name, .. }] if name == "Mode"
+    ));
+    Ok(())
+}
+
+#[actix::test]
+async fn reply_after_the_sensor_was_deleted() -> Result<()> {
+    let mut harness = SensorStateHarness::start(connector_id()).await?;
+    let sensor_id = harness.agent().add_sensor("Boiler", vec![temperature()]);
+    harness.exchange(MqttScheme::SensorList, "").await?;
+    let temperature_id = metric_id(&mut harness, sensor_id, "Temperature");
+
+    let describe = harness.request(MqttScheme::MetricDescribe(sensor_id, temperature_id), "");
+    harness.exchange(MqttScheme::SensorDelete(sensor_id), "").await?;
+    for reply in describe {
+        harness.inject(reply).await?;
+    }
+
+    // The late reply doesn't bring the sensor back
+    assert!(harness.snapshot().await?.is_empty());
+    Ok(())
+}
+
+#[actix::test]
+async fn error_replies() -> Result<()> {
+    let mut harness = SensorStateHarness::start(connector_id()).await?;
+    let sensor_id: SensorId = "0123456789abcdef0123456789abcdef".into();
+
+    harness
+        .exchange(MqttScheme::SensorDelete(sensor_id), "")
+        .await?;
+
+    let events = harness.take_events().await?;
+    let [SensorStateEvent::Error { code, scheme, .. }] = events.as_slice() else {
+        panic!("Unexpected {events:?}");
+    };
+    assert_eq!(*code, 404);
+    assert_eq!(scheme.sensor_id(), Some(sensor_id));
+    assert!(harness.snapshot().await?.is_empty());
+    Ok(())
+}
+
+#[actix::test]
+async fn every_matching_alert_rule_is_checked() -> Result<()> {
+    let mut harness = SensorStateHarness::start(connector_id()).await?;
+    let sensor_id = harness.agent().add_sensor("Boiler", vec![temperature()]);
+    load(&mut harness).await?;
+    let temperature_id = metric_id(&mut harness, sensor_id, "Temperature");
+    let rule = |sensor: &str, condition: &str| {
+        AlertRule::with_condition(sensor.to_owned(), String::from("Temperature"), condition).unwrap()
+    };
+    harness
+        .set_alert_rules(vec![rule("Boiler", "..90"), rule("*", "..80"), rule("Pump", "..0")])
+        .await?;
+    harness.take_events().await?;
+
+    let mut alerts = Vec::new();
+    for value in [85.0, 95.0, 85.0, 20.0] {
+        harness
+            .livedata(sensor_id, &[(temperature_id, MetricValue::Double(value))], Timestamp::now())
+            .await?;
+        for event in harness.take_events().await? {
+            match event {
+                SensorStateEvent::AlertRaised { message, .. } => alerts.push(message),
+                SensorStateEvent::AlertCleared { .. } => alerts.push(String::from("cleared")),
+                _ => {}
+            }
+        }
+    }
+    assert_eq!(
+        alerts,
+        [
+            "Boiler/Temperature = 85 is above 80",
+            "Boiler/Temperature = 95 is above 90",
+            "cleared",
+        ]
+    );
+    Ok(())
+}
+
+/// Alerts raised, by message, and the ones cleared
+async fn alert_events(harness: &SensorStateHarness) -> Result<Vec<String>> {
+    let mut alerts = Vec::new();
+    for event in harness.take_events().await? {
+        match event {
+            SensorStateEvent::AlertRaised { message, .. } => alerts.push(message),
+            SensorStateEvent::AlertCleared { .. } => alerts.push(String::from("cleared")),
+            _ => {}
+        }
+    }
+    Ok(alerts)
+}
+
+fn boiler_above_90() -> Vec<AlertRule> {
+    vec![AlertRule::with_condition(String::from("Boiler"), String::from("Temperature"), "..90").unwrap()]
+}
+
+#[actix::test]
+async fn raised_alerts_are_recorded_until_resolved() -> Result<()> {
+    let mut harness = SensorStateHarness::start(connector_id()).await?;
+    let sensor_id = harness.agent().add_sensor("Boiler", vec![temperature()]);
+    load(&mut harness).await?;
+    let temperature_id = metric_id(&mut harness, sensor_id, "Temperature");
+    harness.set_alert_rules(boiler_above_90()).await?;
+
+    for (value, millis) in [(95.0, 1_000), (96.0, 2_000)] {
+        harness
+            .livedata(sensor_id, &[(temperature_id, MetricValue::Double(value))], Timestamp::from_millis(millis))
+            .await?;
+    }
+    let history = harness.alert_history(Timestamp::now()).await?;
+    assert_eq!(history.len(), 1);
+    assert_eq!(history[0].message, "Boiler/Temperature = 95 is above 90");
+    assert_eq!(history[0].rule, "Boiler/Temperature ..90");
+    assert_eq!(history[0].fired_at, Timestamp::from_millis(1_000));
+    assert_eq!(history[0].resolved_at, None);
+
+    harness
+        .livedata(sensor_id, &[(temperature_id, MetricValue::Double(20.0))], Timestamp::from_millis(3_000))
+        .await?;
+    // Resolved long ago
+    assert!(harness.alert_history(Timestamp::now()).await?.is_empty());
+    let history = harness.alert_history(Timestamp::from_millis(0)).await?;
+    assert_eq!(history.len(), 1);
+    assert_eq!(history[0].resolved_at, Some(Timestamp::from_millis(3_000)));
+    Ok(())
+}
+
+#[actix::test]
+async fn alerts_in_force_are_resumed_after_a_restart() -> Result<()> {
+    let history_file = TempFile::new("resumed-alerts.sqlite");
+    let mut harness = SensorStateHarness::start_with_history(connector_id(), history_file.path()).await?;
+    let sensor_id = harness.agent().add_sensor("Boiler", vec![temperature()]);
+    let temperature_id = metric_id(&mut harness, sensor_id, "Temperature");
+    load(&mut harness).await?;
+    harness.set_alert_rules(boiler_above_90()).await?;
+    harness
+        .livedata(sensor_id, &[(temperature_id, MetricValue::Double(95.0))], Timestamp::from_millis(1_000))
+        .await?;
+
+    harness.restart().await?;
+    load(&mut harness).await?;
+    harness.set_alert_rules(boiler_above_90()).await?;
+    harness.take_events().await?;
+
+    // Still violating, neither raised again nor recorded twice
+    harness
+        .livedata(sensor_id, &[(temperature_id, MetricValue::Double(96.0))], Timestamp::from_millis(2_000))
+        .await?;
+    assert!(alert_events(&harness).await?.is_empty());
+    let history = harness.alert_history(Timestamp::now()).await?;
+    assert_eq!(history.len(), 1);
+    assert_eq!(history[0].fired_at, Timestamp::from_millis(1_000));
+
+    harness
+        .livedata(sensor_id, &[(temperature_id, MetricValue::Double(20.0))], Timestamp::from_millis(3_000))
+        .await?;
+    assert_eq!(alert_events(&harness).await?, ["cleared"]);
+    assert!(harness.alert_history(Timestamp::now()).await?.is_empty());
+    Ok(())
+}
+
+#[actix::test]
+async fn alerts_in_force_before_a_restart_are_resolved_once_the_value_is_back() -> Result<()> {
+    let history_file = TempFile::new("resolved-alerts.sqlite");
+    let mut harness = SensorStateHarness::start_with_history(connector_id(), history_file.path()).await?;
+    let sensor_id = harness.agent().add_sensor("Boiler", vec![temperature()]);
+    let temperature_id = metric_id(&mut harness, sensor_id, "Temperature");
+    load(&mut harness).await?;
+    harness.set_alert_rules(boiler_above_90()).await?;
+    harness
+        .livedata(sensor_id, &[(temperature_id, MetricValue::Double(95.0))], Timestamp::from_millis(1_000))
+        .await?;
+
+    harness.restart().await?;
+    load(&mut harness).await?;
+    harness.set_alert_rules(boiler_above_90()).await?;
+    harness.take_events().await?;
+    harness
+        .livedata(sensor_id, &[(temperature_id, MetricValue::Double(20.0))], Timestamp::from_millis(2_000))
+        .await?;
+
+    assert_eq!(alert_events(&harness).await?, ["cleared"]);
+    let history = harness.alert_history(Timestamp::from_millis(0)).await?;
+    assert_eq!(history.len(), 1);
+    assert_eq!(history[0].resolved_at, Some(Timestamp::from_millis(2_000)));
+    Ok(())
+}
+
+#[actix::test]
+async fn silenced_alerts_are_recorded_but_flagged() -> Result<()> {
+    let mut harness = SensorStateHarness::start(connector_id()).await?;
+    let sensor_id = harness.agent().add_sensor("Boiler", vec![temperature()]);
+    load(&mut harness).await?;
+    let temperature_id = metric_id(&mut harness, sensor_id, "Temperature");
+    harness.set_alert_rules(boiler_above_90()).await?;
+    let in_an_hour = Timestamp::from(std::time::SystemTime::now() + std::time::Duration::from_secs(3600));
+    harness.silence_alerts(Some(in_an_hour)).await?;
+    let silencing = harness.take_events().await?.into_iter().find_map(|event| match event {
+        SensorStateEvent::SilencingChanged(silencing) => Some(silencing),
+        _ => None,
+    });
+    assert_eq!(silencing.and_then(|silencing| silencing.until), Some(in_an_hour));
+
+    harness
+        .livedata(sensor_id, &[(temperature_id, MetricValue::Double(95.0))], Timestamp::from_millis(1_000))
+        .await?;
+    let silenced: Vec<bool> = harness
+        .take_events()
+        .await?
+        .into_iter()
+        .filter_map(|event| match event {
+            SensorStateEvent::AlertRaised { silenced, .. } => Some(silenced),
+            _ => None,
+        })
+        .collect();
+    assert_eq!(silenced, [true]);
+    let history = harness.alert_history(Timestamp::now()).await?;
+    assert_eq!(history.len(), 1);
+    assert!(history[0].silenced);
+
+    harness.silence_alerts(None).await?;
+    harness
+        .livedata(sensor_id, &[(temperature_id, MetricValue::Double(20.0))], Timestamp::from_millis(2_000))
+        .await?;
+    harness
+        .livedata(sensor_id, &[(temperature_id, MetricValue::Double(97.0))], Timestamp::from_millis(3_000))
+        .await?;
+    let history = harness.alert_history(Timestamp::now()).await?;
+    assert_eq!(history.len(), 1);
+    assert!(!history[0].silenced);
+    Ok(())
+}
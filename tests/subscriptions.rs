@@ -0,0 +1,39 @@
+use regex::Regex;
+
+use sensor_vision::client::mqtt::subscriptions;
+
+#[test]
+fn messages_are_counted_per_matching_filter() {
+    subscriptions::subscribed("counting_event", "/v1.0/+/sensor/+/livedata", 1);
+    subscriptions::subscribed("counting_event", "/v1.0/#", 1);
+    subscriptions::message_received("counting_event", "/v1.0/c0ffee/sensor/cafe/livedata");
+    subscriptions::message_received("counting_event", "/v1.0/c0ffee/sensor/list/info");
+    // Another client subscribed to the same filter doesn't get it
+    subscriptions::message_received("counting_other", "/v1.0/c0ffee/sensor/list/info");
+
+    let pattern = Regex::new("^counting_").unwrap();
+    let counts: Vec<(String, u64)> = subscriptions::list(Some(&pattern))
+        .into_iter()
+        .map(|sub| (sub.filter, sub.messages))
+        .collect();
+    assert_eq!(
+        counts,
+        [
+            (String::from("/v1.0/#"), 2),
+            (String::from("/v1.0/+/sensor/+/livedata"), 1)
+        ]
+    );
+}
+
+#[test]
+fn unsubscribed_filters_are_gone() {
+    subscriptions::subscribed("leaving_client", "/v1.0/c0ffee/sensor/list/info", 1);
+    subscriptions::subscribed("leaving_client", "/v1.0/c0ffee/sensor/list/error", 1);
+    subscriptions::unsubscribed("leaving_client", "/v1.0/c0ffee/sensor/list/info");
+
+    let pattern = Regex::new("leaving").unwrap();
+    let listed = subscriptions::list(Some(&pattern));
+    assert_eq!(listed.len(), 1);
+    assert_eq!(listed[0].filter, "/v1.0/c0ffee/sensor/list/error");
+    assert_eq!(listed[0].last_activity, None);
+}
@@ -0,0 +1,31 @@
+use sensor_vision::config::ThrottleConfig;
+use sensor_vision::model::sensor::split_target;
+
+use std::collections::BTreeMap;
+
+fn parts(sensor: &str, metric: &str) -> Option<(String, String)> {
+    Some((sensor.to_owned(), metric.to_owned()))
+}
+
+#[test]
+fn targets_split_at_the_first_unescaped_slash() {
+    assert_eq!(split_target("Boiler Room/Temperature"), parts("Boiler Room", "Temperature"));
+    assert_eq!(split_target("Boiler/Inlet/Outlet"), parts("Boiler", "Inlet/Outlet"));
+    assert_eq!(split_target(r"Room 1\/2/Temperature"), parts("Room 1/2", "Temperature"));
+    assert_eq!(split_target(r"Share\\/Usage"), parts(r"Share\", "Usage"));
+    assert_eq!(split_target(r"C:\Temp/Free*"), parts(r"C:\Temp", "Free*"));
+    assert_eq!(split_target(r"Room 1\/2"), None);
+    assert_eq!(split_target("Boiler"), None);
+}
+
+#[test]
+fn settings_are_found_however_the_target_is_escaped() {
+    let throttle = ThrottleConfig {
+        deadband: Some(0.5),
+        ..ThrottleConfig::default()
+    };
+    let throttles = BTreeMap::from([(String::from(r"Room 1\/2/Inlet/Outlet"), throttle)]);
+    let collector = ThrottleConfig::default();
+    assert_eq!(ThrottleConfig::of_target(&throttles, r"Room 1\/2/Inlet\/Outlet", collector), throttle);
+    assert_eq!(ThrottleConfig::of_target(&throttles, "Room 1/2/Inlet/Outlet", collector), collector);
+}
@@ -0,0 +1,63 @@
+use sensor_vision::model::format::ValueFormat;
+use sensor_vision::model::protocol::MetricValue;
+use sensor_vision::model::sensor::{Metric, ValueType, ValueUnit};
+
+fn predefined(value_unit: ValueUnit) -> ValueFormat {
+    ValueFormat::of(&Metric::predefined(String::from("Metric"), value_unit))
+}
+
+fn custom(value_type: ValueType, annotation: &str) -> ValueFormat {
+    ValueFormat::of(&Metric::custom(String::from("Metric"), value_type, annotation.to_owned()))
+}
+
+#[test]
+fn units_set_precision_prefixes_and_suffixes() {
+    assert_eq!(predefined(ValueUnit::Pascal).format(2500.0), "2.50 kPa");
+    assert_eq!(predefined(ValueUnit::Ampere).format(0.0123), "12.30 mA");
+    assert_eq!(predefined(ValueUnit::Volt).format(0.0), "0.00 V");
+    assert_eq!(predefined(ValueUnit::Celsius).format(21.54), "21.5 °C");
+    assert_eq!(predefined(ValueUnit::Celsius).format(-1500.0), "-1500.0 °C");
+    assert_eq!(predefined(ValueUnit::Latitude).format(48.137433), "48.13743°");
+    assert_eq!(predefined(ValueUnit::Percent).format(0.5), "0.5 %");
+}
+
+#[test]
+fn custom_metrics_are_suffixed_with_the_annotation() {
+    assert_eq!(custom(ValueType::Double, "rpm").format(1500.0), "1500.00 rpm");
+    assert_eq!(custom(ValueType::Integer, "W").format(42.0), "42 W");
+    assert_eq!(custom(ValueType::Integer, "W").format(1500.0), "1.50 kW");
+    assert_eq!(custom(ValueType::Integer, "W").fractional().format(42.5), "42.50 W");
+    assert_eq!(
+        custom(ValueType::Integer, "code").format_value(&MetricValue::Integer(i64::MAX)),
+        "9223372036854775807 code"
+    );
+}
+
+#[test]
+fn editable_values_read_back() {
+    let pascal = Metric::predefined(String::from("Pressure"), ValueUnit::Pascal);
+    let editable = ValueFormat::of(&pascal).editable(&MetricValue::Double(101325.4));
+    assert_eq!(editable, "101325");
+    assert_eq!(pascal.parse_value(&editable).unwrap(), MetricValue::Double(101325.0));
+
+    assert_eq!(predefined(ValueUnit::Celsius).editable(&MetricValue::Double(21.549)), "21.5");
+    assert_eq!(predefined(ValueUnit::Ampere).editable(&MetricValue::Double(0.0123)), "0.01230");
+    assert_eq!(custom(ValueType::Integer, "W").editable(&MetricValue::Integer(1500)), "1500");
+    assert_eq!(custom(ValueType::Boolean, "on").editable(&MetricValue::Boolean(true)), "true");
+}
+
+#[test]
+fn commas_are_decimal_or_grouping() {
+    let double = |value: &str| ValueType::Double.to_value(value).unwrap();
+    assert_eq!(double("2,75"), MetricValue::Double(2.75));
+    assert_eq!(double("1 234,5"), MetricValue::Double(1234.5));
+    assert_eq!(double("1,234.5"), MetricValue::Double(1234.5));
+    // A comma followed by exactly 3 digits groups the thousands
+    assert_eq!(double("1,234"), MetricValue::Double(1234.0));
+    assert_eq!(double("-12,500"), MetricValue::Double(-12500.0));
+    assert_eq!(ValueType::Integer.to_value("1,234").unwrap(), MetricValue::Integer(1234));
+    // Unless the integer part can't be a group
+    assert_eq!(double("0,125"), MetricValue::Double(0.125));
+    assert_eq!(double("1234,567"), MetricValue::Double(1234.567));
+    assert_eq!(double("1.234"), MetricValue::Double(1.234));
+}